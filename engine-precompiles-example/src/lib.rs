@@ -0,0 +1,70 @@
+//! Example of a precompile built outside of the engine.
+//!
+//! It only depends on the extension interface of `aurora-engine-precompiles`. The engine
+//! compiles it in with the `precompile-example` feature.
+
+#![no_std]
+
+extern crate alloc;
+
+use aurora_engine_precompiles::extension::{
+    ExtensionContext, ExtensionError, ExtensionOutput, ExtensionPrecompile,
+};
+use aurora_engine_precompiles::make_address;
+use aurora_engine_types::types::EthGas;
+use aurora_engine_types::Address;
+
+/// Returns its input, prefixed with the address of the caller.
+pub struct Echo;
+
+impl Echo {
+    /// Address: `0x5df8fcffbdc5e4ff1d468f004910ec1ea0ce327b`
+    /// This address is computed as: `&keccak("echoPrecompile")[12..]`
+    pub const ADDRESS: Address = make_address(0x5df8fcff, 0xbdc5e4ff1d468f004910ec1ea0ce327b);
+
+    const BASE_GAS: EthGas = EthGas::new(15);
+    const PER_WORD_GAS: EthGas = EthGas::new(3);
+}
+
+impl ExtensionPrecompile for Echo {
+    fn address(&self) -> Address {
+        Self::ADDRESS
+    }
+
+    fn required_gas(&self, input: &[u8]) -> Result<EthGas, ExtensionError> {
+        let words = (input.len() as u64 + 31) / 32;
+        Ok(words * Self::PER_WORD_GAS + Self::BASE_GAS)
+    }
+
+    fn execute(
+        &self,
+        input: &[u8],
+        context: &ExtensionContext,
+    ) -> Result<ExtensionOutput, ExtensionError> {
+        let output = [context.caller.as_bytes(), input].concat();
+        Ok(ExtensionOutput::new(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aurora_engine_types::{H256, U256};
+
+    #[test]
+    fn test_echo() {
+        let current_account_id = "aurora".parse().unwrap();
+        let context = ExtensionContext {
+            address: Echo::ADDRESS,
+            caller: Address::from_low_u64_be(7),
+            value: U256::zero(),
+            is_static: false,
+            current_account_id: &current_account_id,
+            random_seed: H256::zero(),
+        };
+        let output = Echo.execute(b"hello", &context).unwrap();
+        assert_eq!(&output.output[..20], context.caller.as_bytes());
+        assert_eq!(&output.output[20..], b"hello");
+        assert_eq!(Echo.required_gas(b"hello").unwrap(), EthGas::new(18));
+    }
+}
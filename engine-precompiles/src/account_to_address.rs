@@ -0,0 +1,100 @@
+use crate::prelude::types::EthGas;
+use crate::prelude::{sdk, vec, AccountId, Address, Cow, TryFrom};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::ExitError;
+
+mod costs {
+    use crate::prelude::types::EthGas;
+
+    /// The derivation is one keccak of the account id, so it costs as much as the
+    /// `KECCAK256` opcode: a base cost...
+    pub(super) const ACCOUNT_TO_ADDRESS_BASE: EthGas = EthGas::new(30);
+
+    /// ...and a cost per word of the account id.
+    pub(super) const ACCOUNT_TO_ADDRESS_PER_WORD: EthGas = EthGas::new(6);
+}
+
+mod consts {
+    pub(super) const WORD_LEN: u64 = 32;
+}
+
+/// Computes the address the engine uses for a NEAR account, e.g. as `msg.sender` of the
+/// calls made with the `call` method. The input is the account id; the output is the
+/// address as an ABI-encoded word.
+pub struct NearAccountToAddress;
+
+impl NearAccountToAddress {
+    /// NEAR account id to address precompile address
+    ///
+    /// Address: `0x511e84b1290f0d9f7cd3882a67c6b914f40796b7`
+    /// This address is computed as: `&keccak("nearAccountToAddress")[12..]`
+    pub const ADDRESS: Address =
+        super::make_address(0x511e84b1, 0x290f0d9f7cd3882a67c6b914f40796b7);
+}
+
+impl Precompile for NearAccountToAddress {
+    fn required_gas(input: &[u8]) -> Result<EthGas, ExitError> {
+        let words = (input.len() as u64 + consts::WORD_LEN - 1) / consts::WORD_LEN;
+        Ok(words * costs::ACCOUNT_TO_ADDRESS_PER_WORD + costs::ACCOUNT_TO_ADDRESS_BASE)
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        _context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        let account_id = AccountId::try_from(input)
+            .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_ACCOUNT_ID")))?;
+        let address = sdk::types::near_account_to_evm_address(account_id.as_bytes());
+        let mut output = vec![0u8; 32];
+        output[12..].copy_from_slice(address.as_bytes());
+
+        Ok(PrecompileOutput::without_logs(cost, output).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::sdk::types::near_account_to_evm_address;
+    use crate::utils::new_context;
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(
+            NearAccountToAddress::ADDRESS,
+            near_account_to_evm_address("nearAccountToAddress".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_account_to_address() {
+        let output = NearAccountToAddress
+            .run(b"relay.aurora", Some(EthGas::new(36)), &new_context())
+            .unwrap()
+            .output;
+        assert_eq!(&output[..12], &[0u8; 12]);
+        assert_eq!(
+            &output[12..],
+            near_account_to_evm_address(b"relay.aurora").as_bytes()
+        );
+
+        let error = NearAccountToAddress
+            .run(b"Not an account", None, &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_ACCOUNT_ID")));
+
+        let error = NearAccountToAddress
+            .run(b"relay.aurora", Some(EthGas::new(35)), &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::OutOfGas);
+    }
+}
@@ -1,8 +1,8 @@
-use evm::{Context, ExitError};
+use evm::ExitError;
 
 use crate::prelude::types::EthGas;
 use crate::prelude::{mem, Address, Borrowed, TryInto};
-use crate::{EvmPrecompileResult, Precompile, PrecompileOutput};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
 
 /// Blake2 costs.
 mod costs {
@@ -20,7 +20,7 @@ mod consts {
 pub(super) struct Blake2F;
 
 impl Blake2F {
-    pub(super) const ADDRESS: Address = crate::make_address(0, 9);
+    pub const ADDRESS: Address = crate::make_address(0, 9);
 }
 
 impl Precompile for Blake2F {
@@ -47,8 +47,7 @@ impl Precompile for Blake2F {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         if input.len() != consts::INPUT_LENGTH {
             return Err(ExitError::Other(Borrowed("ERR_BLAKE2F_INVALID_LEN")));
@@ -126,12 +125,12 @@ mod tests {
 
     fn test_blake2f_out_of_gas() -> EvmPrecompileResult {
         let input = hex::decode(INPUT).unwrap();
-        Blake2F.run(&input, Some(EthGas::new(11)), &new_context(), false)
+        Blake2F.run(&input, Some(EthGas::new(11)), &new_context())
     }
 
     fn test_blake2f_empty() -> EvmPrecompileResult {
         let input = [0u8; 0];
-        Blake2F.run(&input, Some(EthGas::new(0)), &new_context(), false)
+        Blake2F.run(&input, Some(EthGas::new(0)), &new_context())
     }
 
     fn test_blake2f_invalid_len_1() -> EvmPrecompileResult {
@@ -149,7 +148,7 @@ mod tests {
             01",
         )
         .unwrap();
-        Blake2F.run(&input, Some(EthGas::new(12)), &new_context(), false)
+        Blake2F.run(&input, Some(EthGas::new(12)), &new_context())
     }
 
     fn test_blake2f_invalid_len_2() -> EvmPrecompileResult {
@@ -167,7 +166,7 @@ mod tests {
             01",
         )
         .unwrap();
-        Blake2F.run(&input, Some(EthGas::new(12)), &new_context(), false)
+        Blake2F.run(&input, Some(EthGas::new(12)), &new_context())
     }
 
     fn test_blake2f_invalid_flag() -> EvmPrecompileResult {
@@ -185,7 +184,7 @@ mod tests {
             02",
         )
         .unwrap();
-        Blake2F.run(&input, Some(EthGas::new(12)), &new_context(), false)
+        Blake2F.run(&input, Some(EthGas::new(12)), &new_context())
     }
 
     fn test_blake2f_r_0() -> Vec<u8> {
@@ -204,7 +203,7 @@ mod tests {
         )
         .unwrap();
         Blake2F
-            .run(&input, Some(EthGas::new(12)), &new_context(), false)
+            .run(&input, Some(EthGas::new(12)), &new_context())
             .unwrap()
             .output
     }
@@ -212,7 +211,7 @@ mod tests {
     fn test_blake2f_r_12() -> Vec<u8> {
         let input = hex::decode(INPUT).unwrap();
         Blake2F
-            .run(&input, Some(EthGas::new(12)), &new_context(), false)
+            .run(&input, Some(EthGas::new(12)), &new_context())
             .unwrap()
             .output
     }
@@ -233,7 +232,7 @@ mod tests {
         )
         .unwrap();
         Blake2F
-            .run(&input, Some(EthGas::new(12)), &new_context(), false)
+            .run(&input, Some(EthGas::new(12)), &new_context())
             .unwrap()
             .output
     }
@@ -1,7 +1,10 @@
 use crate::prelude::types::EthGas;
 use crate::prelude::{Address, Borrowed, PhantomData, Vec};
-use crate::{Byzantium, EvmPrecompileResult, HardFork, Istanbul, Precompile, PrecompileOutput};
-use evm::{Context, ExitError};
+use crate::{
+    Byzantium, EvmPrecompileResult, HardFork, Istanbul, Precompile, PrecompileContext,
+    PrecompileOutput,
+};
+use evm::ExitError;
 
 /// bn128 costs.
 mod costs {
@@ -70,7 +73,7 @@ fn read_point(input: &[u8], pos: usize) -> Result<bn::G1, ExitError> {
 pub(super) struct Bn128Add<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> Bn128Add<HF> {
-    pub(super) const ADDRESS: Address = super::make_address(0, 6);
+    pub const ADDRESS: Address = super::make_address(0, 6);
 
     pub fn new() -> Self {
         Self(Default::default())
@@ -78,7 +81,7 @@ impl<HF: HardFork> Bn128Add<HF> {
 }
 
 impl<HF: HardFork> Bn128Add<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> Result<Vec<u8>, ExitError> {
+    fn run_inner(input: &[u8], _context: &PrecompileContext) -> Result<Vec<u8>, ExitError> {
         use bn::AffineG1;
 
         let mut input = input.to_vec();
@@ -113,8 +116,7 @@ impl Precompile for Bn128Add<Byzantium> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -142,8 +144,7 @@ impl Precompile for Bn128Add<Istanbul> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -159,7 +160,7 @@ impl Precompile for Bn128Add<Istanbul> {
 pub(super) struct Bn128Mul<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> Bn128Mul<HF> {
-    pub(super) const ADDRESS: Address = super::make_address(0, 7);
+    pub const ADDRESS: Address = super::make_address(0, 7);
 
     pub fn new() -> Self {
         Self(Default::default())
@@ -167,7 +168,7 @@ impl<HF: HardFork> Bn128Mul<HF> {
 }
 
 impl<HF: HardFork> Bn128Mul<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> Result<Vec<u8>, ExitError> {
+    fn run_inner(input: &[u8], _context: &PrecompileContext) -> Result<Vec<u8>, ExitError> {
         use bn::AffineG1;
 
         let mut input = input.to_vec();
@@ -204,8 +205,7 @@ impl Precompile for Bn128Mul<Byzantium> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -232,8 +232,7 @@ impl Precompile for Bn128Mul<Istanbul> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -250,7 +249,7 @@ impl Precompile for Bn128Mul<Istanbul> {
 pub(super) struct Bn128Pair<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> Bn128Pair<HF> {
-    pub(super) const ADDRESS: Address = super::make_address(0, 8);
+    pub const ADDRESS: Address = super::make_address(0, 8);
 
     pub fn new() -> Self {
         Self(Default::default())
@@ -258,7 +257,7 @@ impl<HF: HardFork> Bn128Pair<HF> {
 }
 
 impl<HF: HardFork> Bn128Pair<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> Result<Vec<u8>, ExitError> {
+    fn run_inner(input: &[u8], _context: &PrecompileContext) -> Result<Vec<u8>, ExitError> {
         use bn::{arith::U256, AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
 
         if input.len() % consts::PAIR_ELEMENT_LEN != 0 {
@@ -367,8 +366,7 @@ impl Precompile for Bn128Pair<Byzantium> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -398,8 +396,7 @@ impl Precompile for Bn128Pair<Istanbul> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        _is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -437,7 +434,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Add::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(500)), &new_context(), false)
+            .run(&input, Some(EthGas::new(500)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -459,7 +456,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Add::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(500)), &new_context(), false)
+            .run(&input, Some(EthGas::new(500)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -473,8 +470,7 @@ mod tests {
             0000000000000000000000000000000000000000000000000000000000000000",
         )
         .unwrap();
-        let res =
-            Bn128Add::<Byzantium>::new().run(&input, Some(EthGas::new(499)), &new_context(), false);
+        let res = Bn128Add::<Byzantium>::new().run(&input, Some(EthGas::new(499)), &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
 
         // no input test
@@ -487,7 +483,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Add::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(500)), &new_context(), false)
+            .run(&input, Some(EthGas::new(500)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -502,8 +498,7 @@ mod tests {
         )
         .unwrap();
 
-        let res =
-            Bn128Add::<Byzantium>::new().run(&input, Some(EthGas::new(500)), &new_context(), false);
+        let res = Bn128Add::<Byzantium>::new().run(&input, Some(EthGas::new(500)), &new_context());
         assert!(matches!(
             res,
             Err(ExitError::Other(Borrowed("ERR_BN128_INVALID_POINT")))
@@ -527,7 +522,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Mul::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(40_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(40_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -540,12 +535,8 @@ mod tests {
             0200000000000000000000000000000000000000000000000000000000000000",
         )
         .unwrap();
-        let res = Bn128Mul::<Byzantium>::new().run(
-            &input,
-            Some(EthGas::new(39_999)),
-            &new_context(),
-            false,
-        );
+        let res =
+            Bn128Mul::<Byzantium>::new().run(&input, Some(EthGas::new(39_999)), &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
 
         // zero multiplication test
@@ -564,7 +555,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Mul::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(40_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(40_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -579,7 +570,7 @@ mod tests {
         .unwrap();
 
         let res = Bn128Mul::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(40_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(40_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -593,12 +584,8 @@ mod tests {
         )
         .unwrap();
 
-        let res = Bn128Mul::<Byzantium>::new().run(
-            &input,
-            Some(EthGas::new(40_000)),
-            &new_context(),
-            false,
-        );
+        let res =
+            Bn128Mul::<Byzantium>::new().run(&input, Some(EthGas::new(40_000)), &new_context());
         assert!(matches!(
             res,
             Err(ExitError::Other(Borrowed("ERR_BN128_INVALID_POINT")))
@@ -628,7 +615,7 @@ mod tests {
                 .unwrap();
 
         let res = Bn128Pair::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(260_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(260_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -650,12 +637,8 @@ mod tests {
             12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
         )
         .unwrap();
-        let res = Bn128Pair::<Byzantium>::new().run(
-            &input,
-            Some(EthGas::new(259_999)),
-            &new_context(),
-            false,
-        );
+        let res =
+            Bn128Pair::<Byzantium>::new().run(&input, Some(EthGas::new(259_999)), &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
 
         // no input test
@@ -665,7 +648,7 @@ mod tests {
                 .unwrap();
 
         let res = Bn128Pair::<Byzantium>::new()
-            .run(&input, Some(EthGas::new(260_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(260_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -682,12 +665,8 @@ mod tests {
         )
         .unwrap();
 
-        let res = Bn128Pair::<Byzantium>::new().run(
-            &input,
-            Some(EthGas::new(260_000)),
-            &new_context(),
-            false,
-        );
+        let res =
+            Bn128Pair::<Byzantium>::new().run(&input, Some(EthGas::new(260_000)), &new_context());
         assert!(matches!(
             res,
             Err(ExitError::Other(Borrowed("ERR_BN128_INVALID_A")))
@@ -703,12 +682,8 @@ mod tests {
         )
         .unwrap();
 
-        let res = Bn128Pair::<Byzantium>::new().run(
-            &input,
-            Some(EthGas::new(260_000)),
-            &new_context(),
-            false,
-        );
+        let res =
+            Bn128Pair::<Byzantium>::new().run(&input, Some(EthGas::new(260_000)), &new_context());
         assert!(matches!(
             res,
             Err(ExitError::Other(Borrowed("ERR_BN128_INVALID_LEN",)))
@@ -0,0 +1,66 @@
+//! Runs the standard precompiles on the test vectors of `res/ethereum_vectors.json`, in the
+//! format of the go-ethereum precompile tests, through `Precompiles` like the executor does.
+
+use crate::prelude::{Rc, String, Vec};
+use crate::utils::new_context;
+use crate::{BlockContext, PrecompileConstructorContext, Precompiles};
+use evm::executor::{PrecompileFailure, PrecompileSet};
+use evm::ExitError;
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Vector {
+    name: String,
+    address: u8,
+    input: String,
+    expected: String,
+    gas: u64,
+}
+
+fn vectors() -> Vec<Vector> {
+    serde_json::from_str(include_str!("../res/ethereum_vectors.json")).unwrap()
+}
+
+fn precompiles() -> Precompiles<'static> {
+    Precompiles::new_berlin(PrecompileConstructorContext {
+        current_account_id: "aurora".parse().unwrap(),
+        block: BlockContext::default(),
+        predecessor_account_id: "aurora".parse().unwrap(),
+        promise_results: Vec::new(),
+        state: Rc::new(()),
+    })
+}
+
+#[test]
+fn test_ethereum_vectors() {
+    let precompiles = precompiles();
+    for vector in vectors() {
+        let address = crate::make_address(0, vector.address as u128);
+        let input = hex::decode(&vector.input).unwrap();
+
+        let output = precompiles
+            .execute(address, &input, Some(vector.gas), &new_context(), false)
+            .unwrap_or_else(|| panic!("{}: no precompile", vector.name))
+            .unwrap_or_else(|_| panic!("{}: failed", vector.name));
+        assert_eq!(
+            hex::encode(output.output),
+            vector.expected,
+            "{}",
+            vector.name
+        );
+        assert_eq!(output.cost, vector.gas, "{}", vector.name);
+
+        let result =
+            precompiles.execute(address, &input, Some(vector.gas - 1), &new_context(), false);
+        assert!(
+            matches!(
+                result,
+                Some(Err(PrecompileFailure::Error {
+                    exit_status: ExitError::OutOfGas
+                }))
+            ),
+            "{}: not out of gas",
+            vector.name
+        );
+    }
+}
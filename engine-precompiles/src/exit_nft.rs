@@ -0,0 +1,270 @@
+//! Exit of bridged NEP-171 tokens back to NEAR.
+//!
+//! A NEP-171 collection is bridged to an ERC-721 contract deployed by the engine (see
+//! `deploy_erc721_token`): the NEAR token with id `t` is minted on Aurora as the ERC-721
+//! token `uint256(keccak256(t))`, and the contract keeps `t` to send the token back. Its
+//! `withdrawToNear` burns the token and calls the `ExitNftToNear` precompile, which
+//! schedules `nft_transfer` of `t` from the engine to the NEAR recipient.
+//!
+//! The input of the precompile is the length of the NEAR token id (4 bytes, big-endian),
+//! the token id and the account id of the recipient. Only the ERC-721 of a bridged
+//! collection can call it.
+
+use crate::prelude::types::EthGas;
+use crate::prelude::{
+    format,
+    parameters::{PromiseArgs, PromiseCreateArgs},
+    storage::{bytes_to_key, KeyPrefix},
+    str, vec, AccountId, Address, BorshSerialize, Cow, Rc, String, ToString, TryFrom, Vec,
+};
+use crate::state::PrecompileState;
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::backend::Log;
+use evm::ExitError;
+
+mod costs {
+    use crate::prelude::types::EthGas;
+
+    // TODO(#51): Determine the correct amount of gas
+    pub(super) const EXIT_NFT_TO_NEAR_GAS: EthGas = EthGas::new(0);
+
+    // TODO(#332): Determine the correct amount of gas
+    pub(super) const NFT_TRANSFER_GAS: EthGas = EthGas::new(100_000_000_000_000);
+}
+
+const NEP171_ERC721_KEY: &[u8; 13] = b"NEP171_ERC721";
+const ERC721_NEP171_KEY: &[u8; 13] = b"ERC721_NEP171";
+
+/// Longest NEAR token id which can be bridged.
+pub const MAX_TOKEN_ID_LEN: usize = 256;
+
+/// Storage key of the ERC-721 bridged from a NEP-171 collection.
+pub fn erc721_key(nep171: &AccountId) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[NEP171_ERC721_KEY.as_slice(), nep171.as_bytes()].concat(),
+    )
+}
+
+/// Storage key of the NEP-171 collection bridged to an ERC-721.
+pub fn nep171_key(erc721: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[ERC721_NEP171_KEY.as_slice(), erc721.as_bytes()].concat(),
+    )
+}
+
+/// Whether a NEAR token id can be bridged. Token ids are written as is in the JSON arguments
+/// of `nft_transfer`, so the characters which would need escaping are refused.
+pub fn is_valid_token_id(token_id: &str) -> bool {
+    !token_id.is_empty()
+        && token_id.len() <= MAX_TOKEN_ID_LEN
+        && !token_id
+            .chars()
+            .any(|c| c == '"' || c == '\\' || c.is_control())
+}
+
+pub struct ExitNftToNear<'a> {
+    state: Rc<dyn PrecompileState + 'a>,
+}
+
+impl<'a> ExitNftToNear<'a> {
+    /// Exit NFT to NEAR precompile address
+    ///
+    /// Address: `0x4ef4bcbe1bc5c00c19ec753e362ac0756c01f43b`
+    /// This address is computed as: `&keccak("exitNftToNear")[12..]`
+    pub const ADDRESS: Address =
+        super::make_address(0x4ef4bcbe, 0x1bc5c00c19ec753e362ac0756c01f43b);
+
+    pub fn new(state: Rc<dyn PrecompileState + 'a>) -> Self {
+        Self { state }
+    }
+
+    /// Splits the input into the NEAR token id and the recipient.
+    fn parse_input(input: &[u8]) -> Result<(&str, AccountId), ExitError> {
+        if input.len() < 4 {
+            return Err(ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+        }
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&input[..4]);
+        let len = u32::from_be_bytes(len) as usize;
+        let input = &input[4..];
+        if input.len() < len {
+            return Err(ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+        }
+        let (token_id, recipient) = input.split_at(len);
+        let token_id = str::from_utf8(token_id)
+            .ok()
+            .filter(|token_id| is_valid_token_id(token_id))
+            .ok_or_else(|| ExitError::Other(Cow::from("ERR_INVALID_TOKEN_ID")))?;
+        let recipient = AccountId::try_from(recipient)
+            .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_RECEIVER_ACCOUNT_ID")))?;
+        Ok((token_id, recipient))
+    }
+}
+
+impl<'a> Precompile for ExitNftToNear<'a> {
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Ok(costs::EXIT_NFT_TO_NEAR_GAS)
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        if let Some(target_gas) = target_gas {
+            if Self::required_gas(input)? > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        // It's not allowed to call exit precompiles in static mode
+        if context.is_static {
+            return Err(ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
+        }
+
+        let nep171 = self
+            .state
+            .nep171_from_erc721(&context.caller)
+            .ok_or_else(|| ExitError::Other(Cow::from("ERR_NFT_COLLECTION_NOT_FOUND")))?;
+        let (token_id, recipient) = Self::parse_input(input)?;
+
+        let promise = PromiseArgs::Create(PromiseCreateArgs {
+            target_account_id: nep171,
+            method: "nft_transfer".to_string(),
+            // The token id was checked not to need escaping, and the account id cannot.
+            args: format!(
+                r#"{{"receiver_id": "{}", "token_id": "{}", "memo": null}}"#,
+                recipient, token_id
+            )
+            .into_bytes(),
+            // NEP-171 requires exactly one yoctoNEAR.
+            attached_balance: 1,
+            attached_gas: costs::NFT_TRANSFER_GAS.into_u64(),
+        });
+        let promise_log = Log {
+            address: Self::ADDRESS,
+            topics: Vec::new(),
+            data: promise.try_to_vec().unwrap(),
+        };
+
+        Ok(PrecompileOutput {
+            logs: vec![promise_log],
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::near_state::MirroredValue;
+    use crate::prelude::sdk::types::near_account_to_evm_address;
+    use crate::prelude::BorshDeserialize;
+    use crate::utils::{new_context, new_static_context};
+
+    struct Collection;
+
+    impl PrecompileState for Collection {
+        fn nep141_from_erc20(&self, _erc20_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn is_allowed_exit_swap_dex(&self, _dex: &AccountId) -> bool {
+            false
+        }
+
+        fn gas_cost_percent(&self, _address: &Address) -> Option<u16> {
+            None
+        }
+
+        fn near_state(&self, _source: &AccountId, _key: &[u8]) -> Option<MirroredValue> {
+            None
+        }
+
+        fn nep171_from_erc721(&self, erc721_address: &Address) -> Option<AccountId> {
+            if erc721_address == &new_context().caller {
+                Some("nft.near".parse().unwrap())
+            } else {
+                None
+            }
+        }
+
+        fn paused_flags(&self) -> u8 {
+            0
+        }
+    }
+
+    fn input(token_id: &str, recipient: &str) -> Vec<u8> {
+        [
+            &(token_id.len() as u32).to_be_bytes()[..],
+            token_id.as_bytes(),
+            recipient.as_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(
+            ExitNftToNear::ADDRESS,
+            near_account_to_evm_address("exitNftToNear".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_exit_nft_to_near() {
+        let precompile = ExitNftToNear::new(Rc::new(Collection));
+        let output = precompile
+            .run(&input("token-1", "alice.near"), None, &new_context())
+            .unwrap();
+        assert_eq!(output.logs.len(), 1);
+        let promise = match PromiseArgs::try_from_slice(&output.logs[0].data).unwrap() {
+            PromiseArgs::Create(promise) => promise,
+            other => panic!("Unexpected promise {:?}", other),
+        };
+        assert_eq!(promise.target_account_id.as_ref(), "nft.near");
+        assert_eq!(promise.method, "nft_transfer");
+        assert_eq!(promise.attached_balance, 1);
+        assert_eq!(
+            String::from_utf8(promise.args).unwrap(),
+            r#"{"receiver_id": "alice.near", "token_id": "token-1", "memo": null}"#
+        );
+
+        let error = precompile
+            .run(&input("token-1", "alice.near"), None, &new_static_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
+        let error = precompile
+            .run(&input("\"a\"", "alice.near"), None, &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_TOKEN_ID")));
+        let error = precompile
+            .run(&[0, 0, 0, 9, b'a'], None, &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+
+        let mut context = new_context();
+        context.caller = Address::from_low_u64_be(1);
+        let error = precompile
+            .run(&input("token-1", "alice.near"), None, &context)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ExitError::Other(Cow::from("ERR_NFT_COLLECTION_NOT_FOUND"))
+        );
+    }
+
+    #[test]
+    fn test_token_ids() {
+        assert!(is_valid_token_id("1"));
+        assert!(is_valid_token_id("🦀:42"));
+        assert!(!is_valid_token_id(""));
+        assert!(!is_valid_token_id("a\\b"));
+        assert!(!is_valid_token_id("a\nb"));
+        assert!(!is_valid_token_id(&"a".repeat(MAX_TOKEN_ID_LEN + 1)));
+    }
+}
@@ -0,0 +1,170 @@
+//! Exits to NEAR which swap the withdrawn tokens on a NEAR DEX.
+//!
+//! Instead of an account id, the recipient of an exit to NEAR can be a swap request: the
+//! [`EXIT_SWAP_MARKER`] byte (which cannot start an account id) followed by the
+//! borsh-encoded [`ExitSwapArgs`]. The tokens are then sent with `ft_transfer_call` to the
+//! DEX, with a ref-finance style instant swap message which sends the output tokens to the
+//! final recipient. The engine builds the message itself, so only a swap from the exited
+//! token through a single pool can be requested, and only on a DEX allowed by the owner.
+//!
+//! The DEX gives back the tokens it does not use (e.g. if the price moved past the minimum
+//! output); with the `error_refund` feature they are refunded on Aurora like a failed exit.
+
+use crate::prelude::{
+    format, storage::bytes_to_key, storage::KeyPrefix, AccountId, BorshDeserialize, BorshSerialize,
+    Cow, String, Vec, U256,
+};
+use evm::ExitError;
+
+/// First byte of the recipient of an exit to NEAR which requests a swap.
+pub const EXIT_SWAP_MARKER: u8 = 0;
+
+const EXIT_SWAP_DEX_KEY: &[u8; 13] = b"EXIT_SWAP_DEX";
+
+/// Storage key which is set for the DEXes allowed by the owner.
+pub fn dex_key(dex: &AccountId) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[EXIT_SWAP_DEX_KEY.as_slice(), dex.as_bytes()].concat(),
+    )
+}
+
+/// Swap requested by an exit to NEAR.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExitSwapArgs {
+    /// DEX account receiving the exited tokens.
+    pub dex: AccountId,
+    /// Pool of the DEX swapping the exited tokens for `token_out`.
+    pub pool_id: u64,
+    pub token_out: AccountId,
+    /// Minimum amount of `token_out` to receive; the swap fails below it.
+    pub min_amount_out: u128,
+    /// NEAR account receiving the `token_out` tokens.
+    pub recipient: AccountId,
+}
+
+impl ExitSwapArgs {
+    /// Parses the recipient of an exit to NEAR. Returns `None` if it is not a swap request.
+    pub fn parse(recipient: &[u8]) -> Option<Result<Self, ExitError>> {
+        match recipient.split_first() {
+            Some((&EXIT_SWAP_MARKER, args)) => Some(
+                Self::try_from_slice(args)
+                    .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_EXIT_SWAP_ARGS")))
+                    .and_then(|args| {
+                        if args.min_amount_out == 0 {
+                            Err(ExitError::Other(Cow::from("ERR_EXIT_SWAP_NO_MIN_AMOUNT")))
+                        } else {
+                            Ok(args)
+                        }
+                    }),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Message of the `ft_transfer_call` to the DEX swapping `token_in`.
+    pub fn message(&self, token_in: &AccountId) -> String {
+        // There is no way to inject json: account ids cannot contain quotes.
+        format!(
+            r#"{{"actions":[{{"pool_id":{},"token_in":"{}","token_out":"{}","min_amount_out":"{}"}}],"swap_out_recipient":"{}"}}"#,
+            self.pool_id, token_in, self.token_out, self.min_amount_out, self.recipient
+        )
+    }
+
+    /// Arguments of the `ft_transfer_call` of `amount` of `token_in` to the DEX.
+    pub fn ft_transfer_call_args(&self, token_in: &AccountId, amount: u128) -> String {
+        format!(
+            r#"{{"receiver_id": "{}", "amount": "{}", "memo": null, "msg": "{}"}}"#,
+            self.dex,
+            amount,
+            self.message(token_in).replace('"', "\\\"")
+        )
+    }
+}
+
+/// Amount to refund after an exit transfer which succeeded with the given output. A plain
+/// `ft_transfer` returns nothing; a `ft_transfer_call` returns the amount which was used.
+pub fn unused_amount(amount: U256, output: &[u8]) -> U256 {
+    let used = crate::prelude::str::from_utf8(output)
+        .ok()
+        .and_then(|output| output.strip_prefix('"'))
+        .and_then(|output| output.strip_suffix('"'))
+        .and_then(|used| U256::from_dec_str(used).ok());
+    match used {
+        Some(used) => amount.saturating_sub(used),
+        // Refunding an amount which may have been used would mint tokens out of thin air.
+        None => U256::zero(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::vec;
+
+    fn args() -> ExitSwapArgs {
+        ExitSwapArgs {
+            dex: AccountId::new("v2.ref-finance.near").unwrap(),
+            pool_id: 3,
+            token_out: AccountId::new("wrap.near").unwrap(),
+            min_amount_out: 100,
+            recipient: AccountId::new("alice.near").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_exit_swap() {
+        assert!(ExitSwapArgs::parse(b"alice.near").is_none());
+
+        let mut input = vec![EXIT_SWAP_MARKER];
+        input.extend(args().try_to_vec().unwrap());
+        assert_eq!(ExitSwapArgs::parse(&input).unwrap().unwrap(), args());
+
+        assert_eq!(
+            ExitSwapArgs::parse(&input[..input.len() - 1]).unwrap(),
+            Err(ExitError::Other(Cow::from("ERR_INVALID_EXIT_SWAP_ARGS")))
+        );
+
+        let mut no_min_amount = args();
+        no_min_amount.min_amount_out = 0;
+        let mut input = vec![EXIT_SWAP_MARKER];
+        input.extend(no_min_amount.try_to_vec().unwrap());
+        assert_eq!(
+            ExitSwapArgs::parse(&input).unwrap(),
+            Err(ExitError::Other(Cow::from("ERR_EXIT_SWAP_NO_MIN_AMOUNT")))
+        );
+    }
+
+    #[test]
+    fn test_exit_swap_ft_transfer_call_args() {
+        let token_in = AccountId::new("aurora").unwrap();
+        let args: serde_json::Value =
+            serde_json::from_str(&args().ft_transfer_call_args(&token_in, 42)).unwrap();
+        assert_eq!(args["receiver_id"], "v2.ref-finance.near");
+        assert_eq!(args["amount"], "42");
+
+        let msg: serde_json::Value = serde_json::from_str(args["msg"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            msg,
+            serde_json::json!({
+                "actions": [{
+                    "pool_id": 3,
+                    "token_in": "aurora",
+                    "token_out": "wrap.near",
+                    "min_amount_out": "100",
+                }],
+                "swap_out_recipient": "alice.near",
+            })
+        );
+    }
+
+    #[test]
+    fn test_unused_amount() {
+        let amount = U256::from(100);
+        assert_eq!(unused_amount(amount, b""), U256::zero());
+        assert_eq!(unused_amount(amount, b"\"100\""), U256::zero());
+        assert_eq!(unused_amount(amount, b"\"0\""), amount);
+        assert_eq!(unused_amount(amount, b"\"60\""), U256::from(40));
+        assert_eq!(unused_amount(amount, b"60"), U256::zero());
+    }
+}
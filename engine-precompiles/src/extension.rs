@@ -0,0 +1,232 @@
+//! Stable interface for precompiles developed outside of this crate.
+//!
+//! Extensions implement `ExtensionPrecompile`, which only uses the types defined here and in
+//! `aurora-engine-types`, so they keep compiling when the executor is upgraded. The engine
+//! adds them to its precompile set with `Precompiles::register_extension`, usually behind a
+//! feature flag; see the `aurora-engine-precompile-example` crate.
+
+use crate::prelude::types::EthGas;
+use crate::prelude::{Address, Cow, Vec, H256, U256};
+use crate::{
+    EvmPrecompileResult, Precompile, PrecompileConstructorContext, PrecompileContext,
+    PrecompileOutput,
+};
+use aurora_engine_types::account_id::AccountId;
+use evm::backend::Log;
+use evm::ExitError;
+
+/// Information about the call to an extension precompile.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtensionContext<'a> {
+    /// Address of the precompile.
+    pub address: Address,
+    pub caller: Address,
+    /// Value sent with the call, in wei.
+    pub value: U256,
+    /// Set in `STATICCALL` frames, where the precompile must not have side effects.
+    pub is_static: bool,
+    /// NEAR account of the engine.
+    pub current_account_id: &'a AccountId,
+    pub random_seed: H256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionLog {
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionOutput {
+    pub output: Vec<u8>,
+    /// Logs emitted by the precompile, from its own address.
+    pub logs: Vec<ExtensionLog>,
+}
+
+impl ExtensionOutput {
+    pub fn new(output: Vec<u8>) -> Self {
+        Self {
+            output,
+            logs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionError {
+    OutOfGas,
+    /// Any other failure, identified by an `ERR_*` code.
+    Other(&'static str),
+}
+
+impl AsRef<[u8]> for ExtensionError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::OutOfGas => b"ERR_OUT_OF_GAS",
+            Self::Other(code) => code.as_bytes(),
+        }
+    }
+}
+
+impl From<ExtensionError> for ExitError {
+    fn from(e: ExtensionError) -> Self {
+        match e {
+            ExtensionError::OutOfGas => ExitError::OutOfGas,
+            ExtensionError::Other(code) => ExitError::Other(Cow::Borrowed(code)),
+        }
+    }
+}
+
+/// A precompile which can be built outside of this crate.
+pub trait ExtensionPrecompile {
+    /// Address the precompile is called at.
+    fn address(&self) -> Address;
+
+    /// Gas charged for running the precompile with the given input.
+    fn required_gas(&self, input: &[u8]) -> Result<EthGas, ExtensionError>;
+
+    /// Runs the precompile. The gas is charged by the engine beforehand.
+    fn execute(
+        &self,
+        input: &[u8],
+        context: &ExtensionContext,
+    ) -> Result<ExtensionOutput, ExtensionError>;
+}
+
+/// Adapter running an `ExtensionPrecompile` as a `Precompile`.
+pub struct Extension<P> {
+    precompile: P,
+    current_account_id: AccountId,
+    random_seed: H256,
+}
+
+impl<P: ExtensionPrecompile> Extension<P> {
+    pub fn new(precompile: P, ctx: &PrecompileConstructorContext) -> Self {
+        Self {
+            precompile,
+            current_account_id: ctx.current_account_id.clone(),
+            random_seed: ctx.block.random_seed,
+        }
+    }
+}
+
+impl<P: ExtensionPrecompile> Precompile for Extension<P> {
+    /// The cost of an extension depends on the instance, see
+    /// `ExtensionPrecompile::required_gas`.
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Err(ExitError::Other(Cow::Borrowed(
+            "ERR_EXTENSION_GAS_REQUIRES_INSTANCE",
+        )))
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let cost = self.precompile.required_gas(input)?;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        let address = self.precompile.address();
+        let extension_context = ExtensionContext {
+            address,
+            caller: context.caller,
+            value: context.apparent_value,
+            is_static: context.is_static,
+            current_account_id: &self.current_account_id,
+            random_seed: self.random_seed,
+        };
+        let result = self.precompile.execute(input, &extension_context)?;
+        let logs = result
+            .logs
+            .into_iter()
+            .map(|log| Log {
+                address,
+                topics: log.topics,
+                data: log.data,
+            })
+            .collect();
+
+        Ok(PrecompileOutput {
+            cost,
+            output: result.output,
+            logs,
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{new_context, new_static_context};
+
+    struct Caller;
+
+    impl ExtensionPrecompile for Caller {
+        fn address(&self) -> Address {
+            crate::make_address(0xe0, 1)
+        }
+
+        fn required_gas(&self, _input: &[u8]) -> Result<EthGas, ExtensionError> {
+            Ok(EthGas::new(10))
+        }
+
+        fn execute(
+            &self,
+            _input: &[u8],
+            context: &ExtensionContext,
+        ) -> Result<ExtensionOutput, ExtensionError> {
+            if context.is_static {
+                return Err(ExtensionError::Other("ERR_STATIC"));
+            }
+            Ok(ExtensionOutput {
+                output: context.caller.as_bytes().to_vec(),
+                logs: crate::prelude::vec![ExtensionLog {
+                    topics: Vec::new(),
+                    data: context.current_account_id.as_bytes().to_vec(),
+                }],
+            })
+        }
+    }
+
+    fn extension() -> Extension<Caller> {
+        Extension::new(
+            Caller,
+            &PrecompileConstructorContext {
+                current_account_id: "aurora".parse().unwrap(),
+                block: crate::BlockContext::default(),
+                predecessor_account_id: "aurora".parse().unwrap(),
+                promise_results: Vec::new(),
+                state: crate::prelude::Rc::new(()),
+            },
+        )
+    }
+
+    #[test]
+    fn test_extension_adapter() {
+        let context = new_context();
+        let output = extension()
+            .run(&[], Some(EthGas::new(10)), &context)
+            .unwrap();
+        assert_eq!(output.cost, 10);
+        assert_eq!(output.output, context.caller.as_bytes());
+        assert_eq!(output.logs.len(), 1);
+        assert_eq!(output.logs[0].address, Caller.address());
+        assert_eq!(output.logs[0].data, b"aurora");
+
+        assert!(matches!(
+            extension().run(&[], Some(EthGas::new(9)), &context),
+            Err(ExitError::OutOfGas)
+        ));
+        assert!(matches!(
+            extension().run(&[], None, &new_static_context()),
+            Err(ExitError::Other(_))
+        ));
+    }
+}
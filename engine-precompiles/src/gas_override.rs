@@ -0,0 +1,234 @@
+//! Gas cost overrides of the custom precompiles.
+//!
+//! The costs of the precompiles specific to Aurora (the exits, the random seed, the account
+//! conversion and the extensions) are constants of this crate, so a mispriced one used to
+//! need a code upgrade. The owner can now scale the cost of each of them by a percentage
+//! between `MIN_PERCENT` and `MAX_PERCENT`, stored under `gas_override_key`. The standard
+//! Ethereum precompiles always keep the costs of their EIPs.
+//!
+//! `Precompiles` wraps every custom precompile in a `GasOverride`, which reads the
+//! percentage (see `PrecompileState::gas_cost_percent`) only when the precompile is called.
+
+use crate::blake2::Blake2F;
+use crate::prelude::types::EthGas;
+use crate::prelude::{
+    sdk::io::{StorageIntermediate, IO},
+    storage::{bytes_to_key, KeyPrefix},
+    Address, Box, Cow, Rc, TryFrom, Vec,
+};
+use crate::secp256k1::ECRecover;
+use crate::state::PrecompileState;
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext};
+use evm::ExitError;
+
+const PRECOMPILE_GAS_KEY: &[u8; 14] = b"PRECOMPILE_GAS";
+
+/// Percentage applied to the cost of a precompile without override.
+pub const DEFAULT_PERCENT: u16 = 100;
+/// Lowest percentage the owner can set.
+pub const MIN_PERCENT: u16 = 25;
+/// Highest percentage the owner can set.
+pub const MAX_PERCENT: u16 = 400;
+
+/// Storage key of the percentage of the precompile at the given address.
+pub fn gas_override_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[PRECOMPILE_GAS_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+/// Whether the address is one of the standard Ethereum precompiles, whose cost cannot be
+/// overridden.
+pub fn is_standard(address: &Address) -> bool {
+    ECRecover::ADDRESS <= *address && *address <= Blake2F::ADDRESS
+}
+
+/// Percentage stored for the precompile, `None` if there is none. A stored value outside
+/// of the bounds is ignored.
+pub fn read_percent<I: IO>(io: &I, address: &Address) -> Option<u16> {
+    let bytes = io.read_storage(&gas_override_key(address))?.to_vec();
+    let percent = u16::from_le_bytes(<[u8; 2]>::try_from(bytes.as_slice()).ok()?);
+    if (MIN_PERCENT..=MAX_PERCENT).contains(&percent) {
+        Some(percent)
+    } else {
+        None
+    }
+}
+
+/// Precompile whose cost is scaled by the percentage set by the owner.
+pub struct GasOverride<'a> {
+    address: Address,
+    precompile: Box<dyn Precompile + 'a>,
+    state: Rc<dyn PrecompileState + 'a>,
+}
+
+impl<'a> GasOverride<'a> {
+    pub fn new(
+        address: Address,
+        precompile: Box<dyn Precompile + 'a>,
+        state: Rc<dyn PrecompileState + 'a>,
+    ) -> Self {
+        Self {
+            address,
+            precompile,
+            state,
+        }
+    }
+}
+
+impl<'a> Precompile for GasOverride<'a> {
+    /// The cost depends on the stored override, see `PrecompileState::gas_cost_percent`.
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Err(ExitError::Other(Cow::Borrowed(
+            "ERR_GAS_OVERRIDE_REQUIRES_INSTANCE",
+        )))
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let percent = match self.state.gas_cost_percent(&self.address) {
+            Some(percent) if percent != DEFAULT_PERCENT => u64::from(percent),
+            _ => return self.precompile.run(input, target_gas, context),
+        };
+        // Gas available to the unscaled precompile.
+        let inner_target_gas = target_gas
+            .map(|target_gas| EthGas::new(target_gas.into_u64().saturating_mul(100) / percent));
+        let mut output = self.precompile.run(input, inner_target_gas, context)?;
+        let cost = u128::from(output.cost) * u128::from(percent) / 100;
+        let cost = u64::try_from(cost).map_err(|_| ExitError::OutOfGas)?;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas.into_u64() {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+        output.cost = cost;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account_to_address::NearAccountToAddress;
+    use crate::identity::Identity;
+    use crate::utils::new_context;
+    use crate::{
+        make_address, BlockContext, PrecompileConstructorContext, PrecompileOutput, Precompiles,
+    };
+    use aurora_engine_types::account_id::AccountId;
+
+    const COST: u64 = 1_000;
+
+    struct FixedCost;
+
+    impl Precompile for FixedCost {
+        fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+            Ok(EthGas::new(COST))
+        }
+
+        fn run(
+            &self,
+            input: &[u8],
+            target_gas: Option<EthGas>,
+            _context: &PrecompileContext,
+        ) -> EvmPrecompileResult {
+            let cost = Self::required_gas(input)?;
+            if let Some(target_gas) = target_gas {
+                if cost > target_gas {
+                    return Err(ExitError::OutOfGas);
+                }
+            }
+            Ok(PrecompileOutput::without_logs(cost, Vec::new()).into())
+        }
+    }
+
+    struct Override(Option<u16>);
+
+    impl PrecompileState for Override {
+        fn nep141_from_erc20(&self, _erc20_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn is_allowed_exit_swap_dex(&self, _dex: &AccountId) -> bool {
+            false
+        }
+
+        fn gas_cost_percent(&self, _address: &Address) -> Option<u16> {
+            self.0
+        }
+
+        fn near_state(
+            &self,
+            _source: &AccountId,
+            _key: &[u8],
+        ) -> Option<crate::near_state::MirroredValue> {
+            None
+        }
+
+        fn nep171_from_erc721(&self, _erc721_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn paused_flags(&self) -> u8 {
+            0
+        }
+    }
+
+    fn run(percent: Option<u16>, target_gas: Option<u64>) -> Result<u64, ExitError> {
+        let precompile = GasOverride::new(
+            make_address(1, 1),
+            Box::new(FixedCost),
+            Rc::new(Override(percent)),
+        );
+        precompile
+            .run(&[], target_gas.map(EthGas::new), &new_context())
+            .map(|output| output.cost)
+    }
+
+    #[test]
+    fn test_cost_is_scaled() {
+        assert_eq!(run(None, None), Ok(COST));
+        assert_eq!(run(Some(50), None), Ok(COST / 2));
+        assert_eq!(run(Some(250), None), Ok(COST * 5 / 2));
+    }
+
+    #[test]
+    fn test_scaled_cost_is_limited_by_target_gas() {
+        // Halving the cost lets the precompile run with half of its normal gas.
+        assert_eq!(run(Some(50), Some(COST / 2)), Ok(COST / 2));
+        assert_eq!(run(None, Some(COST / 2)), Err(ExitError::OutOfGas));
+        // Doubling it requires twice as much.
+        assert_eq!(run(Some(200), Some(COST)), Err(ExitError::OutOfGas));
+        assert_eq!(run(Some(200), Some(2 * COST)), Ok(2 * COST));
+    }
+
+    #[test]
+    fn test_only_custom_precompiles_are_overridden() {
+        assert!(is_standard(&ECRecover::ADDRESS));
+        assert!(is_standard(&Blake2F::ADDRESS));
+        assert!(!is_standard(&NearAccountToAddress::ADDRESS));
+        assert!(!is_standard(&Address::zero()));
+
+        let precompiles = Precompiles::new_london(PrecompileConstructorContext {
+            current_account_id: "aurora".parse().unwrap(),
+            block: BlockContext::default(),
+            predecessor_account_id: "aurora".parse().unwrap(),
+            promise_results: Vec::new(),
+            state: Rc::new(Override(Some(200))),
+        });
+        let cost = |address: Address, input: &[u8]| {
+            precompiles.all_precompiles[&address]
+                .run(input, None, &new_context())
+                .unwrap()
+                .cost
+        };
+        // One word of input.
+        assert_eq!(cost(NearAccountToAddress::ADDRESS, b"aurora"), 2 * (30 + 6));
+        assert_eq!(cost(Identity::ADDRESS, b"aurora"), 15 + 3);
+    }
+}
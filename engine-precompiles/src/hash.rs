@@ -2,8 +2,8 @@
 use crate::prelude::sdk;
 use crate::prelude::types::EthGas;
 use crate::prelude::{vec, Address};
-use crate::{EvmPrecompileResult, Precompile, PrecompileOutput};
-use evm::{Context, ExitError};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::ExitError;
 
 mod costs {
     use crate::prelude::types::EthGas;
@@ -27,7 +27,7 @@ mod consts {
 pub struct SHA256;
 
 impl SHA256 {
-    pub(super) const ADDRESS: Address = super::make_address(0, 2);
+    pub const ADDRESS: Address = super::make_address(0, 2);
 }
 
 impl Precompile for SHA256 {
@@ -47,8 +47,7 @@ impl Precompile for SHA256 {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         use sha2::Digest;
 
@@ -71,8 +70,7 @@ impl Precompile for SHA256 {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -90,7 +88,7 @@ impl Precompile for SHA256 {
 pub struct RIPEMD160;
 
 impl RIPEMD160 {
-    pub(super) const ADDRESS: Address = super::make_address(0, 3);
+    pub const ADDRESS: Address = super::make_address(0, 3);
 
     #[cfg(not(feature = "contract"))]
     fn internal_impl(input: &[u8]) -> [u8; 20] {
@@ -118,8 +116,7 @@ impl Precompile for RIPEMD160 {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -154,7 +151,7 @@ mod tests {
                 .unwrap();
 
         let res = SHA256
-            .run(input, Some(EthGas::new(60)), &new_context(), false)
+            .run(input, Some(EthGas::new(60)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -168,7 +165,7 @@ mod tests {
                 .unwrap();
 
         let res = RIPEMD160
-            .run(input, Some(EthGas::new(600)), &new_context(), false)
+            .run(input, Some(EthGas::new(600)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -1,7 +1,7 @@
 use crate::prelude::types::EthGas;
 use crate::prelude::Address;
-use crate::{EvmPrecompileResult, Precompile, PrecompileOutput};
-use evm::{Context, ExitError};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::ExitError;
 
 /// Identity precompile costs.
 mod costs {
@@ -22,7 +22,7 @@ mod consts {
 pub struct Identity;
 
 impl Identity {
-    pub(super) const ADDRESS: Address = super::make_address(0, 4);
+    pub const ADDRESS: Address = super::make_address(0, 4);
 }
 
 impl Precompile for Identity {
@@ -42,8 +42,7 @@ impl Precompile for Identity {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -70,20 +69,20 @@ mod tests {
 
         let expected = input[0..2].to_vec();
         let res = Identity
-            .run(&input[0..2], Some(EthGas::new(18)), &new_context(), false)
+            .run(&input[0..2], Some(EthGas::new(18)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
 
         let expected = input.to_vec();
         let res = Identity
-            .run(&input, Some(EthGas::new(18)), &new_context(), false)
+            .run(&input, Some(EthGas::new(18)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
 
         // gas fail
-        let res = Identity.run(&input[0..2], Some(EthGas::new(17)), &new_context(), false);
+        let res = Identity.run(&input[0..2], Some(EthGas::new(17)), &new_context());
 
         assert!(matches!(res, Err(ExitError::OutOfGas)));
 
@@ -93,7 +92,7 @@ mod tests {
             24, 25, 26, 27, 28, 29, 30, 31, 32,
         ];
         let res = Identity
-            .run(&input, Some(EthGas::new(21)), &new_context(), false)
+            .run(&input, Some(EthGas::new(21)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, input.to_vec());
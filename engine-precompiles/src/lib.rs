@@ -4,32 +4,53 @@
 #![cfg_attr(not(feature = "std"), feature(alloc_error_handler))]
 #![cfg_attr(feature = "log", feature(panic_info_message))]
 
+pub mod account_to_address;
 pub mod blake2;
 pub mod bn128;
+#[cfg(test)]
+mod ethereum_vectors;
+pub mod exit_nft;
+pub mod exit_swap;
+pub mod extension;
+pub mod gas_override;
 pub mod hash;
 pub mod identity;
 pub mod modexp;
 pub mod native;
+pub mod near_state;
+pub mod pausable;
 mod prelude;
+pub mod promise_result;
 pub mod random;
 pub mod secp256k1;
+pub mod state;
 #[cfg(test)]
 mod utils;
+pub mod xcc;
 
+use crate::account_to_address::NearAccountToAddress;
 use crate::blake2::Blake2F;
 use crate::bn128::{Bn128Add, Bn128Mul, Bn128Pair};
+use crate::exit_nft::ExitNftToNear;
+use crate::gas_override::GasOverride;
 use crate::hash::{RIPEMD160, SHA256};
 use crate::identity::Identity;
 use crate::modexp::ModExp;
 use crate::native::{ExitToEthereum, ExitToNear};
-use crate::prelude::types::EthGas;
-use crate::prelude::{Vec, H256};
+use crate::near_state::NearStateRead;
+use crate::pausable::Pausable;
+use crate::prelude::types::{EthGas, PromiseResult};
+use crate::prelude::{Rc, Vec, H256, U256};
+use crate::promise_result::PromiseResults;
 use crate::random::RandomSeed;
 use crate::secp256k1::ECRecover;
+use crate::state::PrecompileState;
+use crate::xcc::CrossContractCall;
+use aurora_engine_types::error::RevertError;
 use aurora_engine_types::{account_id::AccountId, vec, Address, BTreeMap, Box};
 use evm::backend::Log;
 use evm::executor;
-use evm::{Context, ExitError, ExitSucceed};
+use evm::{Context, ExitError, ExitRevert, ExitSucceed};
 
 #[derive(Debug, Default)]
 pub struct PrecompileOutput {
@@ -61,6 +82,40 @@ impl From<PrecompileOutput> for evm::executor::PrecompileOutput {
 
 type EvmPrecompileResult = Result<evm::executor::PrecompileOutput, ExitError>;
 
+/// Failure which reverts the call to a precompile with the Solidity custom error
+/// corresponding to `error`, so that callers can decode it. `cost` is the gas charged.
+pub fn revert<E: RevertError>(error: &E, cost: EthGas) -> executor::PrecompileFailure {
+    executor::PrecompileFailure::Revert {
+        exit_status: ExitRevert::Reverted,
+        output: error.revert_data(),
+        cost: cost.into_u64(),
+    }
+}
+
+/// The NEAR block in which a transaction is executed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockContext {
+    pub height: u64,
+    /// Nanoseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub random_seed: H256,
+}
+
+/// Information about a call to a precompile.
+#[derive(Debug, Clone, Copy)]
+pub struct PrecompileContext<'a> {
+    /// Address the precompile is executed at, the caller's own address for a `DELEGATECALL`.
+    pub address: Address,
+    pub caller: Address,
+    /// Value sent with the call, in wei.
+    pub apparent_value: U256,
+    /// Set in `STATICCALL` frames, where the precompile must not have side effects.
+    pub is_static: bool,
+    pub block: BlockContext,
+    /// NEAR account which called the engine method executing the transaction.
+    pub predecessor_account_id: &'a AccountId,
+}
+
 /// A precompiled function for use in the EVM.
 pub trait Precompile {
     /// The required gas in order to run the precompile function.
@@ -73,8 +128,7 @@ pub trait Precompile {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult;
 }
 
@@ -101,9 +155,13 @@ impl HardFork for Istanbul {}
 
 impl HardFork for Berlin {}
 
-pub struct Precompiles(pub prelude::BTreeMap<Address, Box<dyn Precompile>>);
+pub struct Precompiles<'a> {
+    pub all_precompiles: prelude::BTreeMap<Address, Box<dyn Precompile + 'a>>,
+    block: BlockContext,
+    predecessor_account_id: AccountId,
+}
 
-impl executor::PrecompileSet for Precompiles {
+impl<'a> executor::PrecompileSet for Precompiles<'a> {
     fn execute(
         &self,
         address: prelude::Address,
@@ -112,25 +170,40 @@ impl executor::PrecompileSet for Precompiles {
         context: &Context,
         is_static: bool,
     ) -> Option<Result<executor::PrecompileOutput, executor::PrecompileFailure>> {
-        self.0.get(&address).map(|p| {
-            p.run(input, gas_limit.map(EthGas::new), context, is_static)
+        self.all_precompiles.get(&address).map(|p| {
+            let context = PrecompileContext {
+                address: context.address,
+                caller: context.caller,
+                apparent_value: context.apparent_value,
+                is_static,
+                block: self.block,
+                predecessor_account_id: &self.predecessor_account_id,
+            };
+            p.run(input, gas_limit.map(EthGas::new), &context)
                 .map_err(|exit_status| executor::PrecompileFailure::Error { exit_status })
         })
     }
 
     fn is_precompile(&self, address: prelude::Address) -> bool {
-        self.0.contains_key(&address)
+        self.all_precompiles.contains_key(&address)
     }
 }
 
-pub struct PrecompileConstructorContext {
+#[derive(Clone)]
+pub struct PrecompileConstructorContext<'a> {
     pub current_account_id: AccountId,
-    pub random_seed: H256,
+    pub block: BlockContext,
+    /// NEAR account which called the engine method executing the transaction.
+    pub predecessor_account_id: AccountId,
+    /// Results of the promises the running method is the callback of, see `promise_result`.
+    pub promise_results: Vec<PromiseResult>,
+    /// Engine state read by the precompiles, see `state`.
+    pub state: Rc<dyn PrecompileState + 'a>,
 }
 
-impl Precompiles {
+impl<'a> Precompiles<'a> {
     #[allow(dead_code)]
-    pub fn new_homestead(ctx: PrecompileConstructorContext) -> Self {
+    pub fn new_homestead(ctx: PrecompileConstructorContext<'a>) -> Self {
         let addresses = vec![
             ECRecover::ADDRESS,
             SHA256::ADDRESS,
@@ -138,22 +211,36 @@ impl Precompiles {
             ExitToNear::ADDRESS,
             ExitToEthereum::ADDRESS,
             RandomSeed::ADDRESS,
+            NearAccountToAddress::ADDRESS,
+            NearStateRead::ADDRESS,
+            ExitNftToNear::ADDRESS,
+            CrossContractCall::ADDRESS,
+            PromiseResults::ADDRESS,
         ];
-        let fun: prelude::Vec<Box<dyn Precompile>> = vec![
+        let fun: prelude::Vec<Box<dyn Precompile + 'a>> = vec![
             Box::new(ECRecover),
             Box::new(SHA256),
             Box::new(RIPEMD160),
-            Box::new(ExitToNear::new(ctx.current_account_id.clone())),
+            Box::new(ExitToNear::new(
+                ctx.current_account_id.clone(),
+                ctx.state.clone(),
+            )),
             Box::new(ExitToEthereum::new(ctx.current_account_id)),
-            Box::new(RandomSeed::new(ctx.random_seed)),
+            Box::new(RandomSeed::new(ctx.block.random_seed)),
+            Box::new(NearAccountToAddress),
+            Box::new(NearStateRead::new(ctx.state.clone())),
+            Box::new(ExitNftToNear::new(ctx.state.clone())),
+            Box::new(CrossContractCall),
+            Box::new(PromiseResults::new(ctx.promise_results)),
         ];
-        let map: BTreeMap<Address, Box<dyn Precompile>> = addresses.into_iter().zip(fun).collect();
+        let map: BTreeMap<Address, Box<dyn Precompile + 'a>> =
+            addresses.into_iter().zip(fun).collect();
 
-        Precompiles(map)
+        Self::with_gas_overrides(map, ctx.state, ctx.block, ctx.predecessor_account_id)
     }
 
     #[allow(dead_code)]
-    pub fn new_byzantium(ctx: PrecompileConstructorContext) -> Self {
+    pub fn new_byzantium(ctx: PrecompileConstructorContext<'a>) -> Self {
         let addresses = vec![
             ECRecover::ADDRESS,
             SHA256::ADDRESS,
@@ -166,8 +253,13 @@ impl Precompiles {
             ExitToNear::ADDRESS,
             ExitToEthereum::ADDRESS,
             RandomSeed::ADDRESS,
+            NearAccountToAddress::ADDRESS,
+            NearStateRead::ADDRESS,
+            ExitNftToNear::ADDRESS,
+            CrossContractCall::ADDRESS,
+            PromiseResults::ADDRESS,
         ];
-        let fun: prelude::Vec<Box<dyn Precompile>> = vec![
+        let fun: prelude::Vec<Box<dyn Precompile + 'a>> = vec![
             Box::new(ECRecover),
             Box::new(SHA256),
             Box::new(RIPEMD160),
@@ -176,16 +268,25 @@ impl Precompiles {
             Box::new(Bn128Add::<Byzantium>::new()),
             Box::new(Bn128Mul::<Byzantium>::new()),
             Box::new(Bn128Pair::<Byzantium>::new()),
-            Box::new(ExitToNear::new(ctx.current_account_id.clone())),
+            Box::new(ExitToNear::new(
+                ctx.current_account_id.clone(),
+                ctx.state.clone(),
+            )),
             Box::new(ExitToEthereum::new(ctx.current_account_id)),
-            Box::new(RandomSeed::new(ctx.random_seed)),
+            Box::new(RandomSeed::new(ctx.block.random_seed)),
+            Box::new(NearAccountToAddress),
+            Box::new(NearStateRead::new(ctx.state.clone())),
+            Box::new(ExitNftToNear::new(ctx.state.clone())),
+            Box::new(CrossContractCall),
+            Box::new(PromiseResults::new(ctx.promise_results)),
         ];
-        let map: BTreeMap<Address, Box<dyn Precompile>> = addresses.into_iter().zip(fun).collect();
+        let map: BTreeMap<Address, Box<dyn Precompile + 'a>> =
+            addresses.into_iter().zip(fun).collect();
 
-        Precompiles(map)
+        Self::with_gas_overrides(map, ctx.state, ctx.block, ctx.predecessor_account_id)
     }
 
-    pub fn new_istanbul(ctx: PrecompileConstructorContext) -> Self {
+    pub fn new_istanbul(ctx: PrecompileConstructorContext<'a>) -> Self {
         let addresses = vec![
             ECRecover::ADDRESS,
             SHA256::ADDRESS,
@@ -199,8 +300,13 @@ impl Precompiles {
             ExitToNear::ADDRESS,
             ExitToEthereum::ADDRESS,
             RandomSeed::ADDRESS,
+            NearAccountToAddress::ADDRESS,
+            NearStateRead::ADDRESS,
+            ExitNftToNear::ADDRESS,
+            CrossContractCall::ADDRESS,
+            PromiseResults::ADDRESS,
         ];
-        let fun: prelude::Vec<Box<dyn Precompile>> = vec![
+        let fun: prelude::Vec<Box<dyn Precompile + 'a>> = vec![
             Box::new(ECRecover),
             Box::new(SHA256),
             Box::new(RIPEMD160),
@@ -210,16 +316,25 @@ impl Precompiles {
             Box::new(Bn128Mul::<Istanbul>::new()),
             Box::new(Bn128Pair::<Istanbul>::new()),
             Box::new(Blake2F),
-            Box::new(ExitToNear::new(ctx.current_account_id.clone())),
+            Box::new(ExitToNear::new(
+                ctx.current_account_id.clone(),
+                ctx.state.clone(),
+            )),
             Box::new(ExitToEthereum::new(ctx.current_account_id)),
-            Box::new(RandomSeed::new(ctx.random_seed)),
+            Box::new(RandomSeed::new(ctx.block.random_seed)),
+            Box::new(NearAccountToAddress),
+            Box::new(NearStateRead::new(ctx.state.clone())),
+            Box::new(ExitNftToNear::new(ctx.state.clone())),
+            Box::new(CrossContractCall),
+            Box::new(PromiseResults::new(ctx.promise_results)),
         ];
-        let map: BTreeMap<Address, Box<dyn Precompile>> = addresses.into_iter().zip(fun).collect();
+        let map: BTreeMap<Address, Box<dyn Precompile + 'a>> =
+            addresses.into_iter().zip(fun).collect();
 
-        Precompiles(map)
+        Self::with_gas_overrides(map, ctx.state, ctx.block, ctx.predecessor_account_id)
     }
 
-    pub fn new_berlin(ctx: PrecompileConstructorContext) -> Self {
+    pub fn new_berlin(ctx: PrecompileConstructorContext<'a>) -> Self {
         let addresses = vec![
             ECRecover::ADDRESS,
             SHA256::ADDRESS,
@@ -233,8 +348,13 @@ impl Precompiles {
             ExitToNear::ADDRESS,
             ExitToEthereum::ADDRESS,
             RandomSeed::ADDRESS,
+            NearAccountToAddress::ADDRESS,
+            NearStateRead::ADDRESS,
+            ExitNftToNear::ADDRESS,
+            CrossContractCall::ADDRESS,
+            PromiseResults::ADDRESS,
         ];
-        let fun: prelude::Vec<Box<dyn Precompile>> = vec![
+        let fun: prelude::Vec<Box<dyn Precompile + 'a>> = vec![
             Box::new(ECRecover),
             Box::new(SHA256),
             Box::new(RIPEMD160),
@@ -244,19 +364,76 @@ impl Precompiles {
             Box::new(Bn128Mul::<Istanbul>::new()),
             Box::new(Bn128Pair::<Istanbul>::new()),
             Box::new(Blake2F),
-            Box::new(ExitToNear::new(ctx.current_account_id.clone())),
+            Box::new(ExitToNear::new(
+                ctx.current_account_id.clone(),
+                ctx.state.clone(),
+            )),
             Box::new(ExitToEthereum::new(ctx.current_account_id)),
-            Box::new(RandomSeed::new(ctx.random_seed)),
+            Box::new(RandomSeed::new(ctx.block.random_seed)),
+            Box::new(NearAccountToAddress),
+            Box::new(NearStateRead::new(ctx.state.clone())),
+            Box::new(ExitNftToNear::new(ctx.state.clone())),
+            Box::new(CrossContractCall),
+            Box::new(PromiseResults::new(ctx.promise_results)),
         ];
-        let map: BTreeMap<Address, Box<dyn Precompile>> = addresses.into_iter().zip(fun).collect();
+        let map: BTreeMap<Address, Box<dyn Precompile + 'a>> =
+            addresses.into_iter().zip(fun).collect();
 
-        Precompiles(map)
+        Self::with_gas_overrides(map, ctx.state, ctx.block, ctx.predecessor_account_id)
     }
 
-    pub fn new_london(ctx: PrecompileConstructorContext) -> Self {
+    pub fn new_london(ctx: PrecompileConstructorContext<'a>) -> Self {
         // no precompile changes in London HF
         Self::new_berlin(ctx)
     }
+
+    /// Adds an extension precompile to the set. Returns `false`, leaving the set unchanged,
+    /// if its address is already taken: existing precompiles cannot be replaced.
+    pub fn register_extension<P: extension::ExtensionPrecompile + 'static>(
+        &mut self,
+        precompile: P,
+        ctx: &PrecompileConstructorContext<'a>,
+    ) -> bool {
+        let address = precompile.address();
+        if self.all_precompiles.contains_key(&address) {
+            return false;
+        }
+        let extension = Box::new(extension::Extension::new(precompile, ctx));
+        self.all_precompiles.insert(
+            address,
+            Box::new(GasOverride::new(address, extension, ctx.state.clone())),
+        );
+        true
+    }
+
+    /// Wraps the custom precompiles of the map so that the owner can override their cost,
+    /// and pause the exits.
+    fn with_gas_overrides(
+        map: BTreeMap<Address, Box<dyn Precompile + 'a>>,
+        state: Rc<dyn PrecompileState + 'a>,
+        block: BlockContext,
+        predecessor_account_id: AccountId,
+    ) -> Self {
+        let map = map
+            .into_iter()
+            .map(|(address, precompile)| {
+                if gas_override::is_standard(&address) {
+                    return (address, precompile);
+                }
+                let mut precompile: Box<dyn Precompile + 'a> =
+                    Box::new(GasOverride::new(address, precompile, state.clone()));
+                if let Some(flag) = pausable::flag(&address) {
+                    precompile = Box::new(Pausable::new(flag, precompile, state.clone()));
+                }
+                (address, precompile)
+            })
+            .collect();
+        Precompiles {
+            all_precompiles: map,
+            block,
+            predecessor_account_id,
+        }
+    }
 }
 
 /// const fn for making an address by concatenating the bytes from two given numbers,
@@ -360,6 +537,60 @@ mod tests {
         }
     }
 
+    /// Outputs the block height, the static flag and the predecessor it is called with.
+    struct ContextEcho;
+
+    impl super::Precompile for ContextEcho {
+        fn required_gas(_input: &[u8]) -> Result<super::EthGas, super::ExitError> {
+            Ok(super::EthGas::new(0))
+        }
+
+        fn run(
+            &self,
+            _input: &[u8],
+            _target_gas: Option<super::EthGas>,
+            context: &super::PrecompileContext,
+        ) -> super::EvmPrecompileResult {
+            let mut output = context.block.height.to_be_bytes().to_vec();
+            output.push(context.is_static as u8);
+            output.extend_from_slice(context.predecessor_account_id.as_bytes());
+            Ok(super::PrecompileOutput::without_logs(super::EthGas::new(0), output).into())
+        }
+    }
+
+    #[test]
+    fn test_precompile_context() {
+        use evm::executor::PrecompileSet;
+
+        let mut precompiles = super::Precompiles::new_london(super::PrecompileConstructorContext {
+            current_account_id: "aurora".parse().unwrap(),
+            block: super::BlockContext {
+                height: 7,
+                ..Default::default()
+            },
+            predecessor_account_id: "relay.near".parse().unwrap(),
+            promise_results: prelude::Vec::new(),
+            state: prelude::Rc::new(()),
+        });
+        let address = super::make_address(0xe0, 7);
+        precompiles
+            .all_precompiles
+            .insert(address, prelude::Box::new(ContextEcho));
+        let context = evm::Context {
+            address,
+            caller: prelude::Address::zero(),
+            apparent_value: prelude::U256::zero(),
+        };
+
+        let output = match precompiles.execute(address, &[], None, &context, true) {
+            Some(Ok(output)) => output.output,
+            _ => panic!("precompile should succeed"),
+        };
+        assert_eq!(&output[..8], &7u64.to_be_bytes());
+        assert_eq!(output[8], 1);
+        assert_eq!(&output[9..], b"relay.near");
+    }
+
     fn u8_to_address(x: u8) -> prelude::Address {
         let mut bytes = [0u8; 20];
         bytes[19] = x;
@@ -1,14 +1,17 @@
 use crate::prelude::{Address, PhantomData, Vec, U256};
-use crate::{Berlin, Byzantium, EvmPrecompileResult, HardFork, Precompile, PrecompileOutput};
+use crate::{
+    Berlin, Byzantium, EvmPrecompileResult, HardFork, Precompile, PrecompileContext,
+    PrecompileOutput,
+};
 
 use crate::prelude::types::EthGas;
-use evm::{Context, ExitError};
+use evm::ExitError;
 use num::{BigUint, Integer};
 
 pub(super) struct ModExp<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> ModExp<HF> {
-    pub(super) const ADDRESS: Address = super::make_address(0, 5);
+    pub const ADDRESS: Address = super::make_address(0, 5);
 
     pub fn new() -> Self {
         Self(Default::default())
@@ -112,8 +115,7 @@ impl Precompile for ModExp<Byzantium> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -152,8 +154,7 @@ impl Precompile for ModExp<Berlin> {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -418,7 +419,7 @@ mod tests {
             let input = hex::decode(&test.input).unwrap();
 
             let res = ModExp::<Byzantium>::new()
-                .run(&input, Some(*test_gas), &new_context(), false)
+                .run(&input, Some(*test_gas), &new_context())
                 .unwrap()
                 .output;
             let expected = hex::decode(&test.expected).unwrap();
@@ -487,7 +488,7 @@ mod tests {
     #[test]
     fn test_berlin_modexp_empty_input() {
         let res = ModExp::<Berlin>::new()
-            .run(&[], Some(EthGas::new(100_000)), &new_context(), false)
+            .run(&[], Some(EthGas::new(100_000)), &new_context())
             .unwrap();
         let expected: Vec<u8> = Vec::new();
         assert_eq!(res.output, expected)
@@ -1,10 +1,8 @@
-use super::{EvmPrecompileResult, Precompile};
+use super::{EvmPrecompileResult, Precompile, PrecompileContext};
 #[cfg(feature = "contract")]
 use crate::prelude::{
     format,
     parameters::{PromiseArgs, PromiseCreateArgs, WithdrawCallArgs},
-    sdk,
-    storage::{bytes_to_key, KeyPrefix},
     vec, BorshSerialize, Cow, String, ToString, TryFrom, TryInto, Vec, H160, U256,
 };
 #[cfg(all(feature = "error_refund", feature = "contract"))]
@@ -13,13 +11,16 @@ use crate::prelude::{
     types,
 };
 
+#[cfg(feature = "contract")]
+use crate::exit_swap::ExitSwapArgs;
 use crate::prelude::types::EthGas;
-use crate::prelude::Address;
+use crate::prelude::{Address, Rc};
+use crate::state::PrecompileState;
 use crate::PrecompileOutput;
 use aurora_engine_types::account_id::AccountId;
 #[cfg(feature = "contract")]
 use evm::backend::Log;
-use evm::{Context, ExitError};
+use evm::ExitError;
 
 const ERR_TARGET_TOKEN_NOT_FOUND: &str = "Target token not found";
 
@@ -35,6 +36,9 @@ mod costs {
     // TODO(#332): Determine the correct amount of gas
     pub(super) const FT_TRANSFER_GAS: EthGas = EthGas::new(100_000_000_000_000);
 
+    // TODO(#332): Determine the correct amount of gas
+    pub(super) const EXIT_SWAP_GAS: EthGas = EthGas::new(150_000_000_000_000);
+
     // TODO(#332): Determine the correct amount of gas
     #[cfg(feature = "error_refund")]
     pub(super) const REFUND_ON_ERROR_GAS: EthGas = EthGas::new(60_000_000_000_000);
@@ -191,11 +195,12 @@ pub mod events {
 }
 
 //TransferEthToNear
-pub struct ExitToNear {
+pub struct ExitToNear<'a> {
     current_account_id: AccountId,
+    state: Rc<dyn PrecompileState + 'a>,
 }
 
-impl ExitToNear {
+impl<'a> ExitToNear<'a> {
     /// Exit to NEAR precompile address
     ///
     /// Address: `0xe9217bc70b7ed1f598ddd3199e80b093fa71124f`
@@ -203,24 +208,15 @@ impl ExitToNear {
     pub const ADDRESS: Address =
         super::make_address(0xe9217bc7, 0x0b7ed1f598ddd3199e80b093fa71124f);
 
-    pub fn new(current_account_id: AccountId) -> Self {
-        Self { current_account_id }
+    pub fn new(current_account_id: AccountId, state: Rc<dyn PrecompileState + 'a>) -> Self {
+        Self {
+            current_account_id,
+            state,
+        }
     }
 }
 
-#[cfg(feature = "contract")]
-fn get_nep141_from_erc20(erc20_token: &[u8]) -> AccountId {
-    use sdk::io::{StorageIntermediate, IO};
-    AccountId::try_from(
-        sdk::near_runtime::Runtime
-            .read_storage(bytes_to_key(KeyPrefix::Erc20Nep141Map, erc20_token).as_slice())
-            .map(|s| s.to_vec())
-            .expect(ERR_TARGET_TOKEN_NOT_FOUND),
-    )
-    .unwrap()
-}
-
-impl Precompile for ExitToNear {
+impl<'a> Precompile for ExitToNear<'a> {
     fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
         Ok(costs::EXIT_TO_NEAR_GAS)
     }
@@ -230,8 +226,7 @@ impl Precompile for ExitToNear {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         if let Some(target_gas) = target_gas {
             if Self::required_gas(input)? > target_gas {
@@ -247,8 +242,7 @@ impl Precompile for ExitToNear {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         #[cfg(feature = "error_refund")]
         fn parse_input(input: &[u8]) -> (Address, &[u8]) {
@@ -267,7 +261,7 @@ impl Precompile for ExitToNear {
         }
 
         // It's not allowed to call exit precompiles in static mode
-        if is_static {
+        if context.is_static {
             return Err(ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
         }
 
@@ -283,35 +277,19 @@ impl Precompile for ExitToNear {
         #[cfg(feature = "error_refund")]
         let refund_on_error_target = current_account_id.clone();
 
-        let (nep141_address, args, exit_event) = match flag {
+        let (nep141_address, sender, erc20_address, amount) = match flag {
             0x0 => {
                 // ETH transfer
                 //
                 // Input slice format:
                 //      recipient_account_id (bytes) - the NEAR recipient account which will receive NEP-141 ETH tokens
-
-                if let Ok(dest_account) = AccountId::try_from(input) {
-                    (
-                        current_account_id,
-                        // There is no way to inject json, given the encoding of both arguments
-                        // as decimal and valid account id respectively.
-                        format!(
-                            r#"{{"receiver_id": "{}", "amount": "{}", "memo": null}}"#,
-                            dest_account,
-                            context.apparent_value.as_u128()
-                        ),
-                        events::ExitToNear {
-                            sender: context.caller,
-                            erc20_address: events::ETH_ADDRESS,
-                            dest: dest_account.to_string(),
-                            amount: context.apparent_value,
-                        },
-                    )
-                } else {
-                    return Err(ExitError::Other(Cow::from(
-                        "ERR_INVALID_RECEIVER_ACCOUNT_ID",
-                    )));
-                }
+                //          or an exit swap request (see `exit_swap`)
+                (
+                    current_account_id,
+                    context.caller,
+                    events::ETH_ADDRESS,
+                    context.apparent_value,
+                )
             }
             0x1 => {
                 // ERC20 transfer
@@ -321,6 +299,7 @@ impl Precompile for ExitToNear {
                 // Input slice format:
                 //      amount (U256 big-endian bytes) - the amount that was burned
                 //      recipient_account_id (bytes) - the NEAR recipient account which will receive NEP-141 tokens
+                //          or an exit swap request (see `exit_swap`)
 
                 if context.apparent_value != U256::from(0) {
                     return Err(ExitError::Other(Cow::from(
@@ -329,46 +308,65 @@ impl Precompile for ExitToNear {
                 }
 
                 let erc20_address = context.caller;
-                let nep141_address = get_nep141_from_erc20(erc20_address.as_bytes());
+                let nep141_address = self
+                    .state
+                    .nep141_from_erc20(&erc20_address)
+                    .expect(ERR_TARGET_TOKEN_NOT_FOUND);
 
                 let amount = U256::from_big_endian(&input[..32]);
                 input = &input[32..];
 
-                if let Ok(receiver_account_id) = AccountId::try_from(input) {
-                    (
-                        nep141_address,
-                        // There is no way to inject json, given the encoding of both arguments
-                        // as decimal and valid account id respectively.
-                        format!(
-                            r#"{{"receiver_id": "{}", "amount": "{}", "memo": null}}"#,
-                            receiver_account_id,
-                            amount.as_u128()
-                        ),
-                        events::ExitToNear {
-                            sender: erc20_address,
-                            erc20_address,
-                            dest: receiver_account_id.to_string(),
-                            amount,
-                        },
-                    )
-                } else {
-                    return Err(ExitError::Other(Cow::from(
-                        "ERR_INVALID_RECEIVER_ACCOUNT_ID",
-                    )));
-                }
+                (nep141_address, erc20_address, erc20_address, amount)
             }
             _ => return Err(ExitError::Other(Cow::from("ERR_INVALID_FLAG"))),
         };
 
+        let (method, args, dest, attached_gas) = match ExitSwapArgs::parse(input) {
+            Some(swap) => {
+                let swap = swap?;
+                if !self.state.is_allowed_exit_swap_dex(&swap.dex) {
+                    return Err(ExitError::Other(Cow::from("ERR_EXIT_SWAP_DEX_NOT_ALLOWED")));
+                }
+                (
+                    "ft_transfer_call",
+                    swap.ft_transfer_call_args(&nep141_address, amount.as_u128()),
+                    swap.recipient.to_string(),
+                    costs::EXIT_SWAP_GAS,
+                )
+            }
+            None => {
+                let receiver_account_id = AccountId::try_from(input)
+                    .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_RECEIVER_ACCOUNT_ID")))?;
+                (
+                    "ft_transfer",
+                    // There is no way to inject json, given the encoding of both arguments
+                    // as decimal and valid account id respectively.
+                    format!(
+                        r#"{{"receiver_id": "{}", "amount": "{}", "memo": null}}"#,
+                        receiver_account_id,
+                        amount.as_u128()
+                    ),
+                    receiver_account_id.to_string(),
+                    costs::FT_TRANSFER_GAS,
+                )
+            }
+        };
+        let exit_event = events::ExitToNear {
+            sender,
+            erc20_address,
+            dest,
+            amount,
+        };
+
         #[cfg(feature = "error_refund")]
         let erc20_address = if flag == 0 {
             None
         } else {
-            Some(exit_event.erc20_address.0)
+            Some(exit_event.erc20_address.into())
         };
         #[cfg(feature = "error_refund")]
         let refund_args = RefundCallArgs {
-            recipient_address: refund_address.0,
+            recipient_address: refund_address.into(),
             erc20_address,
             amount: types::u256_to_arr(&exit_event.amount),
         };
@@ -382,10 +380,10 @@ impl Precompile for ExitToNear {
         };
         let transfer_promise = PromiseCreateArgs {
             target_account_id: nep141_address,
-            method: "ft_transfer".to_string(),
+            method: method.to_string(),
             args: args.as_bytes().to_vec(),
             attached_balance: 1,
-            attached_gas: costs::FT_TRANSFER_GAS.into_u64(),
+            attached_gas: attached_gas.into_u64(),
         };
 
         #[cfg(feature = "error_refund")]
@@ -443,8 +441,7 @@ impl Precompile for ExitToEthereum {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         if let Some(target_gas) = target_gas {
             if Self::required_gas(input)? > target_gas {
@@ -460,8 +457,7 @@ impl Precompile for ExitToEthereum {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        context: &Context,
-        is_static: bool,
+        context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         if let Some(target_gas) = target_gas {
             if Self::required_gas(input)? > target_gas {
@@ -470,7 +466,7 @@ impl Precompile for ExitToEthereum {
         }
 
         // It's not allowed to call exit precompiles in static mode
-        if is_static {
+        if context.is_static {
             return Err(ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
         }
 
@@ -487,7 +483,7 @@ impl Precompile for ExitToEthereum {
                 //
                 // Input slice format:
                 //      eth_recipient (20 bytes) - the address of recipient which will receive ETH on Ethereum
-                let recipient_address = input
+                let recipient_address: [u8; 20] = input
                     .try_into()
                     .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_RECIPIENT_ADDRESS")))?;
                 (
@@ -495,7 +491,7 @@ impl Precompile for ExitToEthereum {
                     // There is no way to inject json, given the encoding of both arguments
                     // as decimal and hexadecimal respectively.
                     WithdrawCallArgs {
-                        recipient_address,
+                        recipient_address: recipient_address.into(),
                         amount: context.apparent_value.as_u128(),
                     }
                     .try_to_vec()
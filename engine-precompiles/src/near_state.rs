@@ -0,0 +1,258 @@
+//! Synchronous reads of NEAR contract state mirrored by the engine.
+//!
+//! NEAR contracts cannot read each other's state synchronously, so an EVM contract can
+//! only learn a value held on NEAR (e.g. the price published by an oracle) through a
+//! promise, which the EVM cannot wait for. Instead, the NEAR contracts allowed by the
+//! owner (see `source_key`) publish values to the engine with `mirror_near_state`, on
+//! their own schedule, and the `NearStateRead` precompile returns the last published value
+//! together with the height of the block it was published at, so that the reader can
+//! reject stale values.
+//!
+//! The input of the precompile is the length of the account id of the source (one byte),
+//! the account id and the key. The output is `abi.encode(uint64 publishedAt, bytes value)`.
+
+use crate::prelude::types::EthGas;
+use crate::prelude::{
+    storage::{bytes_to_key, KeyPrefix},
+    vec, AccountId, Address, BorshDeserialize, BorshSerialize, Cow, Rc, TryFrom, Vec, U256,
+};
+use crate::state::PrecompileState;
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::ExitError;
+
+mod costs {
+    use crate::prelude::types::EthGas;
+
+    /// A read of the engine storage, priced like a cold `SLOAD`...
+    pub(super) const NEAR_STATE_READ_BASE: EthGas = EthGas::new(2_100);
+
+    /// ...plus a cost per word of the value, like a memory copy.
+    pub(super) const NEAR_STATE_READ_PER_WORD: EthGas = EthGas::new(3);
+}
+
+const WORD_LEN: usize = 32;
+
+const NEAR_STATE_SOURCE_KEY: &[u8; 17] = b"NEAR_STATE_SOURCE";
+const NEAR_STATE_KEY: &[u8; 10] = b"NEAR_STATE";
+
+/// Longest key a source can publish a value under.
+pub const MAX_KEY_LEN: usize = 256;
+/// Longest value a source can publish.
+pub const MAX_VALUE_LEN: usize = 4_096;
+
+/// Storage key which is set for the NEAR contracts allowed by the owner to publish values.
+pub fn source_key(source: &AccountId) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[NEAR_STATE_SOURCE_KEY.as_slice(), source.as_bytes()].concat(),
+    )
+}
+
+/// Storage key of the value published by `source` under `key`. The account id is prefixed
+/// with its length so that the keys of different sources cannot collide.
+pub fn value_key(source: &AccountId, key: &[u8]) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[
+            NEAR_STATE_KEY.as_slice(),
+            &[source.as_bytes().len() as u8],
+            source.as_bytes(),
+            key,
+        ]
+        .concat(),
+    )
+}
+
+/// Value published by a NEAR contract.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MirroredValue {
+    pub value: Vec<u8>,
+    /// Height of the block the value was published at.
+    pub published_at: u64,
+}
+
+pub struct NearStateRead<'a> {
+    state: Rc<dyn PrecompileState + 'a>,
+}
+
+impl<'a> NearStateRead<'a> {
+    /// NEAR state read precompile address
+    ///
+    /// Address: `0x0afbc729d2e8d60eac78932afae2dab83bc9d6b7`
+    /// This address is computed as: `&keccak("nearStateRead")[12..]`
+    pub const ADDRESS: Address =
+        super::make_address(0x0afbc729, 0xd2e8d60eac78932afae2dab83bc9d6b7);
+
+    pub fn new(state: Rc<dyn PrecompileState + 'a>) -> Self {
+        Self { state }
+    }
+
+    /// Splits the input into the account id of the source and the key.
+    fn parse_input(input: &[u8]) -> Result<(AccountId, &[u8]), ExitError> {
+        let (len, rest) = input
+            .split_first()
+            .ok_or_else(|| ExitError::Other(Cow::from("ERR_INVALID_INPUT")))?;
+        let len = usize::from(*len);
+        if rest.len() < len {
+            return Err(ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+        }
+        let (source, key) = rest.split_at(len);
+        let source = AccountId::try_from(source)
+            .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_ACCOUNT_ID")))?;
+        Ok((source, key))
+    }
+
+    fn encode_output(value: &MirroredValue) -> Vec<u8> {
+        let words = (value.value.len() + WORD_LEN - 1) / WORD_LEN;
+        let mut output = vec![0u8; (3 + words) * WORD_LEN];
+        U256::from(value.published_at).to_big_endian(&mut output[..WORD_LEN]);
+        U256::from(2 * WORD_LEN).to_big_endian(&mut output[WORD_LEN..2 * WORD_LEN]);
+        U256::from(value.value.len()).to_big_endian(&mut output[2 * WORD_LEN..3 * WORD_LEN]);
+        output[3 * WORD_LEN..3 * WORD_LEN + value.value.len()].copy_from_slice(&value.value);
+        output
+    }
+}
+
+impl<'a> Precompile for NearStateRead<'a> {
+    /// The cost depends on the length of the value, see `run`.
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Ok(costs::NEAR_STATE_READ_BASE)
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        _context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let base_cost = Self::required_gas(input)?;
+        if let Some(target_gas) = target_gas {
+            if base_cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        let (source, key) = Self::parse_input(input)?;
+        let value = self
+            .state
+            .near_state(&source, key)
+            .ok_or_else(|| ExitError::Other(Cow::from("ERR_NEAR_STATE_NOT_FOUND")))?;
+        let words = ((value.value.len() + WORD_LEN - 1) / WORD_LEN) as u64;
+        let cost = base_cost + words * costs::NEAR_STATE_READ_PER_WORD;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        Ok(PrecompileOutput::without_logs(cost, Self::encode_output(&value)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::sdk::types::near_account_to_evm_address;
+    use crate::utils::new_context;
+
+    struct Mirror(MirroredValue);
+
+    impl PrecompileState for Mirror {
+        fn nep141_from_erc20(&self, _erc20_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn is_allowed_exit_swap_dex(&self, _dex: &AccountId) -> bool {
+            false
+        }
+
+        fn gas_cost_percent(&self, _address: &Address) -> Option<u16> {
+            None
+        }
+
+        fn near_state(&self, source: &AccountId, key: &[u8]) -> Option<MirroredValue> {
+            if source.as_ref() == "oracle.near" && key == b"price" {
+                Some(self.0.clone())
+            } else {
+                None
+            }
+        }
+
+        fn nep171_from_erc721(&self, _erc721_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn paused_flags(&self) -> u8 {
+            0
+        }
+    }
+
+    fn input(source: &str, key: &[u8]) -> Vec<u8> {
+        [&[source.len() as u8], source.as_bytes(), key].concat()
+    }
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(
+            NearStateRead::ADDRESS,
+            near_account_to_evm_address("nearStateRead".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_near_state_read() {
+        let value = MirroredValue {
+            value: vec![7u8; 33],
+            published_at: 1_234,
+        };
+        let precompile = NearStateRead::new(Rc::new(Mirror(value.clone())));
+        let output = precompile
+            .run(&input("oracle.near", b"price"), None, &new_context())
+            .unwrap();
+        // Two words of value.
+        assert_eq!(output.cost, 2_100 + 2 * 3);
+        let decoded = ethabi::decode(
+            &[ethabi::ParamType::Uint(64), ethabi::ParamType::Bytes],
+            &output.output,
+        )
+        .unwrap();
+        assert_eq!(
+            decoded,
+            vec![
+                ethabi::Token::Uint(U256::from(1_234)),
+                ethabi::Token::Bytes(value.value)
+            ]
+        );
+
+        let error = precompile
+            .run(&input("oracle.near", b"volume"), None, &new_context())
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ExitError::Other(Cow::from("ERR_NEAR_STATE_NOT_FOUND"))
+        );
+        let error = precompile
+            .run(&[12, b'o'], None, &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+        let error = precompile
+            .run(&input("Oracle", b"price"), None, &new_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_ACCOUNT_ID")));
+        let error = precompile
+            .run(
+                &input("oracle.near", b"price"),
+                Some(EthGas::new(2_105)),
+                &new_context(),
+            )
+            .unwrap_err();
+        assert_eq!(error, ExitError::OutOfGas);
+    }
+
+    #[test]
+    fn test_value_keys_do_not_collide() {
+        let a: AccountId = "ab".parse().unwrap();
+        let b: AccountId = "abc".parse().unwrap();
+        assert_ne!(value_key(&a, b"ckey"), value_key(&b, b"key"));
+    }
+}
@@ -0,0 +1,182 @@
+//! Pausing of the exit precompiles.
+//!
+//! The exits are paused with the flags of the eth-connector (see `set_paused_flags` in the
+//! engine), next to the flags pausing deposits and withdrawals, so that the owner can halt
+//! the bridge during an incident without a code upgrade. A paused exit fails with
+//! `ERR_PAUSED`, reverting the call to the precompile.
+//!
+//! `Precompiles` wraps every exit in a `Pausable`, which reads the flags (see
+//! `PrecompileState::paused_flags`) only when the precompile is called.
+
+use crate::exit_nft::ExitNftToNear;
+use crate::native::{ExitToEthereum, ExitToNear};
+use crate::prelude::types::EthGas;
+use crate::prelude::{
+    sdk::io::{StorageIntermediate, IO},
+    storage::{bytes_to_key, EthConnectorStorageId, KeyPrefix},
+    Address, Box, Cow, Rc,
+};
+use crate::state::PrecompileState;
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext};
+use evm::ExitError;
+
+/// Flag pausing the `ExitToNear` precompile.
+pub const PAUSE_EXIT_TO_NEAR: u8 = 1 << 2;
+/// Flag pausing the `ExitToEthereum` precompile.
+pub const PAUSE_EXIT_TO_ETHEREUM: u8 = 1 << 3;
+/// Flag pausing the `ExitNftToNear` precompile.
+pub const PAUSE_EXIT_NFT_TO_NEAR: u8 = 1 << 4;
+
+/// Flag pausing the precompile at the given address, `None` if it cannot be paused.
+pub fn flag(address: &Address) -> Option<u8> {
+    match *address {
+        ExitToNear::ADDRESS => Some(PAUSE_EXIT_TO_NEAR),
+        ExitToEthereum::ADDRESS => Some(PAUSE_EXIT_TO_ETHEREUM),
+        ExitNftToNear::ADDRESS => Some(PAUSE_EXIT_NFT_TO_NEAR),
+        _ => None,
+    }
+}
+
+/// Paused flags stored by the eth-connector, none if they were never set.
+pub fn read_paused_flags<I: IO>(io: &I) -> u8 {
+    io.read_storage(&bytes_to_key(
+        KeyPrefix::EthConnector,
+        &[EthConnectorStorageId::PausedMask as u8],
+    ))
+    .and_then(|value| value.to_vec().first().copied())
+    .unwrap_or(0)
+}
+
+/// Precompile which fails while its flag is set.
+pub struct Pausable<'a> {
+    flag: u8,
+    precompile: Box<dyn Precompile + 'a>,
+    state: Rc<dyn PrecompileState + 'a>,
+}
+
+impl<'a> Pausable<'a> {
+    pub fn new(
+        flag: u8,
+        precompile: Box<dyn Precompile + 'a>,
+        state: Rc<dyn PrecompileState + 'a>,
+    ) -> Self {
+        Self {
+            flag,
+            precompile,
+            state,
+        }
+    }
+}
+
+impl<'a> Precompile for Pausable<'a> {
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Err(ExitError::Other(Cow::Borrowed(
+            "ERR_PAUSABLE_REQUIRES_INSTANCE",
+        )))
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        if self.state.paused_flags() & self.flag != 0 {
+            return Err(ExitError::Other(Cow::Borrowed("ERR_PAUSED")));
+        }
+        self.precompile.run(input, target_gas, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Identity;
+    use crate::utils::new_context;
+    use crate::{BlockContext, PrecompileConstructorContext, Precompiles};
+    use aurora_engine_types::account_id::AccountId;
+
+    struct Paused(u8);
+
+    impl PrecompileState for Paused {
+        fn nep141_from_erc20(&self, _erc20_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn is_allowed_exit_swap_dex(&self, _dex: &AccountId) -> bool {
+            false
+        }
+
+        fn gas_cost_percent(&self, _address: &Address) -> Option<u16> {
+            None
+        }
+
+        fn near_state(
+            &self,
+            _source: &AccountId,
+            _key: &[u8],
+        ) -> Option<crate::near_state::MirroredValue> {
+            None
+        }
+
+        fn nep171_from_erc721(&self, _erc721_address: &Address) -> Option<AccountId> {
+            None
+        }
+
+        fn paused_flags(&self) -> u8 {
+            self.0
+        }
+    }
+
+    fn paused() -> Result<(), ExitError> {
+        Err(ExitError::Other(Cow::Borrowed("ERR_PAUSED")))
+    }
+
+    #[test]
+    fn test_paused_exits_fail() {
+        let precompiles = Precompiles::new_london(PrecompileConstructorContext {
+            current_account_id: "aurora".parse().unwrap(),
+            block: BlockContext::default(),
+            predecessor_account_id: "aurora".parse().unwrap(),
+            promise_results: crate::prelude::Vec::new(),
+            state: Rc::new(Paused(
+                PAUSE_EXIT_TO_NEAR | PAUSE_EXIT_TO_ETHEREUM | PAUSE_EXIT_NFT_TO_NEAR,
+            )),
+        });
+        for address in [
+            ExitToNear::ADDRESS,
+            ExitToEthereum::ADDRESS,
+            ExitNftToNear::ADDRESS,
+        ] {
+            // The exits would panic on an empty input, if they ran.
+            let result = precompiles.all_precompiles[&address]
+                .run(&[], None, &new_context())
+                .map(|_| ());
+            assert_eq!(result, paused());
+        }
+    }
+
+    #[test]
+    fn test_only_its_flag_pauses_precompile() {
+        let run = |paused_flags: u8| {
+            Pausable::new(
+                PAUSE_EXIT_TO_NEAR,
+                Box::new(Identity),
+                Rc::new(Paused(paused_flags)),
+            )
+            .run(b"aurora", None, &new_context())
+            .map(|_| ())
+        };
+        assert_eq!(run(PAUSE_EXIT_TO_NEAR), paused());
+        assert_eq!(run(PAUSE_EXIT_TO_NEAR | PAUSE_EXIT_TO_ETHEREUM), paused());
+        // The deposit and withdrawal flags of the connector.
+        assert_eq!(
+            run(0b11 | PAUSE_EXIT_TO_ETHEREUM | PAUSE_EXIT_NFT_TO_NEAR),
+            Ok(())
+        );
+        assert_eq!(run(0), Ok(()));
+
+        assert_eq!(flag(&ExitToNear::ADDRESS), Some(PAUSE_EXIT_TO_NEAR));
+        assert_eq!(flag(&Identity::ADDRESS), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Results of the promises an engine method is the callback of.
+//!
+//! When the engine runs EVM code as the callback of promises (e.g. `xcc_callback`, once the
+//! NEAR call of a cross-contract call is resolved), the `PromiseResults` precompile lets the
+//! code read the results of these promises, so that a contract can handle the failure of a
+//! NEAR call itself. Outside of a callback there are no results.
+//!
+//! With an empty input, the output of the precompile is `abi.encode(uint256 count)`, the
+//! number of results. With the index of a result as input (a `uint256`), the output is
+//! `abi.encode(uint8 status, bytes data)`, where the status is `0` if the promise is not
+//! ready, `1` if it succeeded, with its return value as data, and `2` if it failed.
+
+use crate::prelude::types::{EthGas, PromiseResult};
+use crate::prelude::{vec, Address, Cow, Vec, U256};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::ExitError;
+
+mod costs {
+    use crate::prelude::types::EthGas;
+
+    /// A read of the results held by the runtime, priced like a warm `SLOAD`...
+    pub(super) const PROMISE_RESULT_BASE: EthGas = EthGas::new(100);
+
+    /// ...plus a cost per word of the result, like a memory copy.
+    pub(super) const PROMISE_RESULT_PER_WORD: EthGas = EthGas::new(3);
+}
+
+const WORD_LEN: usize = 32;
+
+/// Status of a promise which is not ready.
+pub const NOT_READY: u8 = 0;
+/// Status of a promise which succeeded.
+pub const SUCCESSFUL: u8 = 1;
+/// Status of a promise which failed.
+pub const FAILED: u8 = 2;
+
+pub struct PromiseResults {
+    results: Vec<PromiseResult>,
+}
+
+impl PromiseResults {
+    /// Promise results precompile address
+    ///
+    /// Address: `0xdb8c359bfe90d4cde46fe1b67c810fc9734f5188`
+    /// This address is computed as: `&keccak("nearPromiseResult")[12..]`
+    pub const ADDRESS: Address =
+        super::make_address(0xdb8c359b, 0xfe90d4cde46fe1b67c810fc9734f5188);
+
+    pub fn new(results: Vec<PromiseResult>) -> Self {
+        Self { results }
+    }
+
+    fn encode_result(result: &PromiseResult) -> Vec<u8> {
+        let (status, data): (u8, &[u8]) = match result {
+            PromiseResult::NotReady => (NOT_READY, &[]),
+            PromiseResult::Successful(data) => (SUCCESSFUL, data),
+            PromiseResult::Failed => (FAILED, &[]),
+        };
+        let words = (data.len() + WORD_LEN - 1) / WORD_LEN;
+        let mut output = vec![0u8; (3 + words) * WORD_LEN];
+        output[WORD_LEN - 1] = status;
+        U256::from(2 * WORD_LEN).to_big_endian(&mut output[WORD_LEN..2 * WORD_LEN]);
+        U256::from(data.len()).to_big_endian(&mut output[2 * WORD_LEN..3 * WORD_LEN]);
+        output[3 * WORD_LEN..3 * WORD_LEN + data.len()].copy_from_slice(data);
+        output
+    }
+}
+
+impl Precompile for PromiseResults {
+    /// The cost depends on the length of the result, see `run`.
+    fn required_gas(_input: &[u8]) -> Result<EthGas, ExitError> {
+        Ok(costs::PROMISE_RESULT_BASE)
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        _context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let base_cost = Self::required_gas(input)?;
+        if let Some(target_gas) = target_gas {
+            if base_cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        let output = match input.len() {
+            0 => {
+                let mut output = vec![0u8; WORD_LEN];
+                U256::from(self.results.len()).to_big_endian(&mut output);
+                output
+            }
+            WORD_LEN => {
+                let index = U256::from_big_endian(input);
+                let result = if index < U256::from(self.results.len()) {
+                    &self.results[index.as_usize()]
+                } else {
+                    return Err(ExitError::Other(Cow::from("ERR_PROMISE_RESULT_INDEX")));
+                };
+                Self::encode_result(result)
+            }
+            _ => return Err(ExitError::Other(Cow::from("ERR_INVALID_INPUT"))),
+        };
+        let words = (output.len() / WORD_LEN) as u64;
+        let cost = base_cost + words * costs::PROMISE_RESULT_PER_WORD;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        Ok(PrecompileOutput::without_logs(cost, output).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::sdk::types::near_account_to_evm_address;
+    use crate::utils::new_static_context;
+
+    fn index(i: u64) -> Vec<u8> {
+        let mut input = vec![0u8; WORD_LEN];
+        U256::from(i).to_big_endian(&mut input);
+        input
+    }
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(
+            PromiseResults::ADDRESS,
+            near_account_to_evm_address("nearPromiseResult".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_promise_results() {
+        let precompile = PromiseResults::new(vec![
+            PromiseResult::Successful(vec![7; 33]),
+            PromiseResult::Failed,
+        ]);
+        let context = new_static_context();
+
+        let output = precompile.run(&[], None, &context).unwrap();
+        assert_eq!(U256::from_big_endian(&output.output), U256::from(2));
+
+        let output = precompile.run(&index(0), None, &context).unwrap();
+        assert_eq!(output.output.len(), 5 * WORD_LEN);
+        assert_eq!(output.output[WORD_LEN - 1], SUCCESSFUL);
+        assert_eq!(
+            U256::from_big_endian(&output.output[2 * WORD_LEN..3 * WORD_LEN]),
+            U256::from(33)
+        );
+        assert_eq!(
+            &output.output[3 * WORD_LEN..3 * WORD_LEN + 33],
+            &[7; 33][..]
+        );
+        assert_eq!(output.cost, 100 + 5 * 3);
+
+        let output = precompile.run(&index(1), None, &context).unwrap();
+        assert_eq!(output.output.len(), 3 * WORD_LEN);
+        assert_eq!(output.output[WORD_LEN - 1], FAILED);
+
+        let error = precompile.run(&index(2), None, &context).unwrap_err();
+        assert_eq!(
+            error,
+            ExitError::Other(Cow::from("ERR_PROMISE_RESULT_INDEX"))
+        );
+        let error = precompile.run(&[1], None, &context).unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_INPUT")));
+    }
+}
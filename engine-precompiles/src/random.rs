@@ -1,8 +1,8 @@
-use super::{EvmPrecompileResult, Precompile};
+use super::{EvmPrecompileResult, Precompile, PrecompileContext};
 use crate::prelude::types::EthGas;
 use crate::prelude::{Address, H256};
 use crate::PrecompileOutput;
-use evm::{Context, ExitError};
+use evm::ExitError;
 
 mod costs {
     use crate::prelude::types::EthGas;
@@ -18,7 +18,8 @@ pub struct RandomSeed {
 impl RandomSeed {
     /// Random bytes precompile address
     /// This is a per-block entropy source which could then be used to create a random sequence.
-    /// It will return the same seed if called multiple time in the same block.
+    /// It will return the same seed if called multiple time in the same block. The `DIFFICULTY`
+    /// opcode gives a value derived from this seed which differs for each transaction.
     ///
     /// Address: `0xc104f4840573bed437190daf5d2898c2bdf928ac`
     /// This address is computed as: `&keccak("randomSeed")[12..]`
@@ -39,8 +40,7 @@ impl Precompile for RandomSeed {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -1,8 +1,8 @@
 use crate::prelude::types::EthGas;
 use crate::prelude::{sdk, vec, Borrowed, H256};
-use crate::{EvmPrecompileResult, Precompile, PrecompileOutput};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
 use ethabi::Address;
-use evm::{Context, ExitError};
+use evm::ExitError;
 
 mod costs {
     use crate::prelude::types::EthGas;
@@ -55,7 +55,7 @@ fn internal_impl(hash: H256, signature: &[u8]) -> Result<Address, ExitError> {
 pub(super) struct ECRecover;
 
 impl ECRecover {
-    pub(super) const ADDRESS: Address = super::make_address(0, 1);
+    pub const ADDRESS: Address = super::make_address(0, 1);
 }
 
 impl Precompile for ECRecover {
@@ -67,8 +67,7 @@ impl Precompile for ECRecover {
         &self,
         input: &[u8],
         target_gas: Option<EthGas>,
-        _context: &Context,
-        _is_static: bool,
+        _context: &PrecompileContext,
     ) -> EvmPrecompileResult {
         let cost = Self::required_gas(input)?;
         if let Some(target_gas) = target_gas {
@@ -145,7 +144,7 @@ mod tests {
                 .unwrap();
 
         let res = ECRecover
-            .run(&input, Some(EthGas::new(3_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(3_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -153,7 +152,7 @@ mod tests {
         // out of gas
         let input = hex::decode("47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000000000000000000000000000000000000000000000000000001b650acf9d3f5f0a2c799776a1254355d5f4061762a237396a99a0e0e3fc2bcd6729514a0dacb2e623ac4abd157cb18163ff942280db4d5caad66ddf941ba12e03").unwrap();
 
-        let res = ECRecover.run(&input, Some(EthGas::new(2_999)), &new_context(), false);
+        let res = ECRecover.run(&input, Some(EthGas::new(2_999)), &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
 
         // bad inputs
@@ -163,7 +162,7 @@ mod tests {
                 .unwrap();
 
         let res = ECRecover
-            .run(&input, Some(EthGas::new(3_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(3_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -174,7 +173,7 @@ mod tests {
                 .unwrap();
 
         let res = ECRecover
-            .run(&input, Some(EthGas::new(3_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(3_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -185,7 +184,7 @@ mod tests {
                 .unwrap();
 
         let res = ECRecover
-            .run(&input, Some(EthGas::new(3_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(3_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
@@ -196,7 +195,7 @@ mod tests {
                 .unwrap();
 
         let res = ECRecover
-            .run(&input, Some(EthGas::new(3_000)), &new_context(), false)
+            .run(&input, Some(EthGas::new(3_000)), &new_context())
             .unwrap()
             .output;
         assert_eq!(res, expected);
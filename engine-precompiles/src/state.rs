@@ -0,0 +1,99 @@
+//! Engine state read by the precompiles.
+//!
+//! Precompiles do not access the engine storage directly: the few reads they need go
+//! through the `PrecompileState` of their `PrecompileConstructorContext`. The engine passes
+//! a `StorageState` over its storage, while other users of this crate (e.g. a reference EVM
+//! running the standard precompiles) can pass `()`, the state without any bridged token.
+
+use crate::near_state::MirroredValue;
+use crate::prelude::{
+    sdk::io::{StorageIntermediate, IO},
+    storage::{bytes_to_key, KeyPrefix},
+    AccountId, Address, BorshDeserialize, TryFrom,
+};
+
+pub trait PrecompileState {
+    /// NEP-141 token bridged to the given ERC-20 token, `None` if there is none.
+    fn nep141_from_erc20(&self, erc20_address: &Address) -> Option<AccountId>;
+
+    /// Whether the owner allowed exit swaps through the given DEX (see `exit_swap`).
+    fn is_allowed_exit_swap_dex(&self, dex: &AccountId) -> bool;
+
+    /// Percentage applied by the owner to the cost of the given custom precompile, `None`
+    /// if it keeps its own cost (see `gas_override`).
+    fn gas_cost_percent(&self, address: &Address) -> Option<u16>;
+
+    /// Last value published by the NEAR contract `source` under `key` (see `near_state`).
+    fn near_state(&self, source: &AccountId, key: &[u8]) -> Option<MirroredValue>;
+
+    /// NEP-171 collection bridged to the given ERC-721, `None` if there is none (see
+    /// `exit_nft`).
+    fn nep171_from_erc721(&self, erc721_address: &Address) -> Option<AccountId>;
+
+    /// Paused flags of the eth-connector, some of which pause the exits (see `pausable`).
+    fn paused_flags(&self) -> u8;
+}
+
+impl PrecompileState for () {
+    fn nep141_from_erc20(&self, _erc20_address: &Address) -> Option<AccountId> {
+        None
+    }
+
+    fn is_allowed_exit_swap_dex(&self, _dex: &AccountId) -> bool {
+        false
+    }
+
+    fn gas_cost_percent(&self, _address: &Address) -> Option<u16> {
+        None
+    }
+
+    fn near_state(&self, _source: &AccountId, _key: &[u8]) -> Option<MirroredValue> {
+        None
+    }
+
+    fn nep171_from_erc721(&self, _erc721_address: &Address) -> Option<AccountId> {
+        None
+    }
+
+    fn paused_flags(&self) -> u8 {
+        0
+    }
+}
+
+/// `PrecompileState` read from the engine storage.
+pub struct StorageState<I>(pub I);
+
+impl<I: IO> PrecompileState for StorageState<I> {
+    fn nep141_from_erc20(&self, erc20_address: &Address) -> Option<AccountId> {
+        self.0
+            .read_storage(&bytes_to_key(
+                KeyPrefix::Erc20Nep141Map,
+                erc20_address.as_bytes(),
+            ))
+            .and_then(|nep141| AccountId::try_from(nep141.to_vec()).ok())
+    }
+
+    fn is_allowed_exit_swap_dex(&self, dex: &AccountId) -> bool {
+        self.0.storage_has_key(&crate::exit_swap::dex_key(dex))
+    }
+
+    fn gas_cost_percent(&self, address: &Address) -> Option<u16> {
+        crate::gas_override::read_percent(&self.0, address)
+    }
+
+    fn near_state(&self, source: &AccountId, key: &[u8]) -> Option<MirroredValue> {
+        self.0
+            .read_storage(&crate::near_state::value_key(source, key))
+            .and_then(|value| MirroredValue::try_from_slice(&value.to_vec()).ok())
+    }
+
+    fn nep171_from_erc721(&self, erc721_address: &Address) -> Option<AccountId> {
+        self.0
+            .read_storage(&crate::exit_nft::nep171_key(erc721_address))
+            .and_then(|nep171| AccountId::try_from(nep171.to_vec()).ok())
+    }
+
+    fn paused_flags(&self) -> u8 {
+        crate::pausable::read_paused_flags(&self.0)
+    }
+}
@@ -1,9 +1,21 @@
-use evm::Context;
+use crate::prelude::{AccountId, Box};
+use crate::PrecompileContext;
 
-pub fn new_context() -> Context {
-    Context {
+pub fn new_context() -> PrecompileContext<'static> {
+    let predecessor_account_id: AccountId = "predecessor.near".parse().unwrap();
+    PrecompileContext {
         address: Default::default(),
         caller: Default::default(),
         apparent_value: Default::default(),
+        is_static: false,
+        block: Default::default(),
+        predecessor_account_id: Box::leak(Box::new(predecessor_account_id)),
+    }
+}
+
+pub fn new_static_context() -> PrecompileContext<'static> {
+    PrecompileContext {
+        is_static: true,
+        ..new_context()
     }
 }
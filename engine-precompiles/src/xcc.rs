@@ -0,0 +1,179 @@
+//! Cross-contract calls from the EVM to arbitrary NEAR contracts.
+//!
+//! The input of the `CrossContractCall` precompile is a Borsh-encoded
+//! `CrossContractCallArgs`: the NEAR call to make (target account, method, arguments,
+//! attached NEAR and gas) and, optionally, the input of an EVM call back to the caller.
+//!
+//! The call is not made by the engine itself, which would let any EVM address act as the
+//! engine on NEAR, but by the router of the calling address: the sub-account
+//! `{hex address}.{engine account}` (see `router_account_id`), which the engine creates on
+//! the first call of the address. The NEAR contracts called therefore see one predecessor per
+//! EVM address. Once the call is resolved, the engine calls the caller back from the address
+//! of the precompile, so that a contract can store the result of its NEAR call.
+//!
+//! The precompile only validates and logs the call; the engine schedules the promises, which
+//! depend on its state (see `xcc` in the engine).
+
+use crate::prelude::types::{EthGas, RawAddress};
+use crate::prelude::{
+    format, parameters::CrossContractCallArgs, vec, AccountId, Address, BorshDeserialize,
+    BorshSerialize, Cow, TryFrom, Vec,
+};
+use crate::{EvmPrecompileResult, Precompile, PrecompileContext, PrecompileOutput};
+use evm::backend::Log;
+use evm::ExitError;
+
+mod costs {
+    use crate::prelude::types::EthGas;
+
+    pub(super) const CROSS_CONTRACT_CALL_BASE: EthGas = EthGas::new(343_650);
+
+    pub(super) const CROSS_CONTRACT_CALL_BYTE: EthGas = EthGas::new(4);
+}
+
+/// Cross-contract call logged by the precompile, for the engine to schedule.
+#[derive(Debug, BorshSerialize, BorshDeserialize, Clone)]
+pub struct ScheduledCall {
+    pub sender: RawAddress,
+    pub args: CrossContractCallArgs,
+}
+
+/// Account making the NEAR calls of the EVM address `address`.
+pub fn router_account_id(address: &Address, engine_account_id: &AccountId) -> AccountId {
+    // An address in hex followed by a valid account id is a valid account id, as long as the
+    // engine account id is at most 23 characters long.
+    AccountId::try_from(format!(
+        "{}.{}",
+        hex::encode(address.as_bytes()),
+        engine_account_id
+    ))
+    .unwrap()
+}
+
+pub struct CrossContractCall;
+
+impl CrossContractCall {
+    /// Cross-contract call precompile address
+    ///
+    /// Address: `0x516cded1d16af10cad47d6d49128e2eb7d27b372`
+    /// This address is computed as: `&keccak("nearCrossContractCall")[12..]`
+    pub const ADDRESS: Address =
+        super::make_address(0x516cded1, 0xd16af10cad47d6d49128e2eb7d27b372);
+}
+
+impl Precompile for CrossContractCall {
+    fn required_gas(input: &[u8]) -> Result<EthGas, ExitError> {
+        Ok(costs::CROSS_CONTRACT_CALL_BASE + costs::CROSS_CONTRACT_CALL_BYTE * input.len())
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        target_gas: Option<EthGas>,
+        context: &PrecompileContext,
+    ) -> EvmPrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if let Some(target_gas) = target_gas {
+            if cost > target_gas {
+                return Err(ExitError::OutOfGas);
+            }
+        }
+
+        // The call changes the state of NEAR contracts.
+        if context.is_static {
+            return Err(ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
+        }
+        // ETH cannot be sent to NEAR, NEAR is attached in the arguments.
+        if !context.apparent_value.is_zero() {
+            return Err(ExitError::Other(Cow::from("ERR_ETH_ATTACHED_FOR_XCC")));
+        }
+
+        let args = CrossContractCallArgs::try_from_slice(input)
+            .map_err(|_| ExitError::Other(Cow::from("ERR_INVALID_XCC_ARGS")))?;
+        let call = ScheduledCall {
+            sender: context.caller.0,
+            args,
+        };
+        let log = Log {
+            address: Self::ADDRESS,
+            topics: Vec::new(),
+            data: call.try_to_vec().unwrap(),
+        };
+
+        Ok(PrecompileOutput {
+            cost,
+            logs: vec![log],
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::parameters::PromiseCreateArgs;
+    use crate::prelude::sdk::types::near_account_to_evm_address;
+    use crate::prelude::U256;
+    use crate::utils::{new_context, new_static_context};
+
+    fn input() -> Vec<u8> {
+        CrossContractCallArgs {
+            promise: PromiseCreateArgs {
+                target_account_id: "wrap.near".parse().unwrap(),
+                method: "near_deposit".into(),
+                args: b"{}".to_vec(),
+                attached_balance: 1,
+                attached_gas: 5_000_000_000_000,
+            },
+            callback: Some(vec![1, 2, 3]),
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(
+            CrossContractCall::ADDRESS,
+            near_account_to_evm_address("nearCrossContractCall".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_cross_contract_call() {
+        let context = new_context();
+        let output = CrossContractCall.run(&input(), None, &context).unwrap();
+        assert_eq!(output.logs.len(), 1);
+        assert_eq!(output.logs[0].address, CrossContractCall::ADDRESS);
+        let call = ScheduledCall::try_from_slice(&output.logs[0].data).unwrap();
+        assert_eq!(call.sender, context.caller.0);
+        assert_eq!(call.args.promise.method, "near_deposit");
+        assert_eq!(call.args.callback, Some(vec![1, 2, 3]));
+
+        let error = CrossContractCall
+            .run(&input(), None, &new_static_context())
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_IN_STATIC")));
+        let error = CrossContractCall
+            .run(&input()[1..], None, &context)
+            .unwrap_err();
+        assert_eq!(error, ExitError::Other(Cow::from("ERR_INVALID_XCC_ARGS")));
+        let mut context = new_context();
+        context.apparent_value = U256::one();
+        let error = CrossContractCall.run(&input(), None, &context).unwrap_err();
+        assert_eq!(
+            error,
+            ExitError::Other(Cow::from("ERR_ETH_ATTACHED_FOR_XCC"))
+        );
+    }
+
+    #[test]
+    fn test_router_account_id() {
+        let address = Address::from_low_u64_be(0x42);
+        assert_eq!(
+            router_account_id(&address, &"aurora".parse().unwrap()).as_ref(),
+            "0000000000000000000000000000000000000042.aurora"
+        );
+    }
+}
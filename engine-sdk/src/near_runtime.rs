@@ -352,6 +352,9 @@ impl crate::promise::PromiseHandler for Runtime {
                         *gas,
                     )
                 },
+                PromiseAction::CreateAccount => unsafe {
+                    exports::promise_batch_action_create_account(id);
+                },
             }
         }
 
@@ -448,7 +451,7 @@ pub(crate) mod exports {
         // #######################
         // # Promise API actions #
         // #######################
-        fn promise_batch_action_create_account(promise_index: u64);
+        pub(crate) fn promise_batch_action_create_account(promise_index: u64);
         pub(crate) fn promise_batch_action_deploy_contract(
             promise_index: u64,
             code_len: u64,
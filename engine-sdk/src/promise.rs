@@ -16,6 +16,8 @@ impl PromiseId {
     }
 }
 
+/// Promises are scheduled in the order the methods are called, and their ids are
+/// assigned in that order too.
 pub trait PromiseHandler {
     fn promise_results_count(&self) -> u64;
     fn promise_result(&self, index: u64) -> Option<PromiseResult>;
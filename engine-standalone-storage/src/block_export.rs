@@ -0,0 +1,221 @@
+//! Per-block export files for block explorers and indexers.
+//!
+//! Block explorers otherwise reconstruct Aurora blocks from the NEAR chain themselves. The
+//! replayer can instead write one JSON file per block (see `Storage::set_block_export_dir`),
+//! named after the zero padded block height, with the schema of `BlockExport`:
+//!
+//! - the block context (height, hash, timestamp, random seed);
+//! - each transaction of the block which succeeded on NEAR, in position order, with the
+//!   engine method and input, its receipt (status, EVM gas used, logs), the engine state it
+//!   modified and the ETH balances it changed.
+//!
+//! Bytes are base64 encoded and hashes and addresses hex encoded, like in the transaction
+//! bundles. Execution traces are not part of the files: they are produced on demand by
+//! `engine-standalone-tracing`. `SCHEMA_VERSION` is incremented on breaking changes.
+
+use aurora_engine::parameters::{SubmitResult, TransactionStatus};
+use aurora_engine_types::H256;
+use rocksdb::{Direction, IteratorMode};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::bundle::{BundleBalanceChange, BundleStateEntry};
+use crate::{construct_storage_key, Storage, StoragePrefix, TransactionIncluded};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Storage(crate::Error),
+    Io(std::io::Error),
+}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        Self::Storage(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockExport {
+    pub schema_version: u32,
+    pub block_height: u64,
+    /// Hex encoded hash of the NEAR block.
+    pub block_hash: String,
+    /// Block timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub random_seed: String,
+    pub transactions: Vec<ExportedTransaction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedTransaction {
+    /// Hex encoded hash under which the transaction is stored: the hash of the signed
+    /// Ethereum transaction for `submit`, the NEAR transaction hash otherwise.
+    pub tx_hash: String,
+    pub near_tx_hash: String,
+    /// Position in the block. Positions can have gaps, since the transactions which
+    /// failed on NEAR are not exported.
+    pub position: u16,
+    pub signer: String,
+    pub caller: String,
+    pub method: String,
+    /// Base64 encoded input of the engine method.
+    pub input: String,
+    /// Result of the EVM execution, only present for `submit`, `call` and `deploy_code`.
+    pub receipt: Option<ExportedReceipt>,
+    /// Values of the modified keys after the transaction executed.
+    pub state_diff: Vec<BundleStateEntry>,
+    /// Net change of the ETH balances modified by the transaction, in ascending
+    /// address order.
+    pub balance_changes: Vec<BundleBalanceChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedReceipt {
+    /// `Succeed`, `Revert` or the name of the EVM error.
+    pub status: String,
+    /// Base64 encoded return (or revert) data.
+    pub output: Option<String>,
+    pub gas_used: u64,
+    pub logs: Vec<ExportedLog>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedLog {
+    pub address: String,
+    pub topics: Vec<String>,
+    /// Base64 encoded data.
+    pub data: String,
+}
+
+impl BlockExport {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Name of the file of the block, which sorts in height order.
+    pub fn file_name(&self) -> String {
+        format!("{:012}.json", self.block_height)
+    }
+
+    /// Writes the block to its file in the given directory, returning the path of the file.
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<PathBuf> {
+        let path = dir.as_ref().join(self.file_name());
+        let json = self.to_json().map_err(std::io::Error::from)?;
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+impl From<&SubmitResult> for ExportedReceipt {
+    fn from(result: &SubmitResult) -> Self {
+        let (status, output) = match &result.status {
+            TransactionStatus::Succeed(output) => ("Succeed".to_string(), Some(output)),
+            TransactionStatus::Revert(output) => ("Revert".to_string(), Some(output)),
+            TransactionStatus::ReturnDataTooLarge { reverted, .. } => {
+                let status = if *reverted { "Revert" } else { "Succeed" };
+                (status.to_string(), None)
+            }
+            error => (format!("{:?}", error), None),
+        };
+        let logs = result
+            .logs
+            .iter()
+            .map(|log| ExportedLog {
+                address: log.address.encode(),
+                topics: log.topics.iter().map(hex::encode).collect(),
+                data: base64::encode(&log.data),
+            })
+            .collect();
+        Self {
+            status,
+            output: output.map(base64::encode),
+            gas_used: result.gas_used,
+            logs,
+        }
+    }
+}
+
+impl Storage {
+    /// Collects the exported data of the block with the given hash. Only the transactions
+    /// consumed after the record keeping was introduced can be exported.
+    pub fn export_block(&self, block_hash: H256) -> Result<BlockExport, crate::Error> {
+        let block_height = self.get_block_height_by_hash(block_hash)?;
+        let block_metadata = self.get_block_metadata(block_hash)?;
+
+        // The transactions of the block are stored under its hash followed by their position.
+        let db_prefix = construct_storage_key(StoragePrefix::TransactionHash, block_hash.as_ref());
+        let mut transactions = Vec::new();
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&db_prefix, Direction::Forward))
+            .take_while(|(key, _)| key.starts_with(&db_prefix));
+        for (key, value) in iter {
+            let mut position = [0u8; 2];
+            position.copy_from_slice(&key[db_prefix.len()..]);
+            let tx_included = TransactionIncluded {
+                block_hash,
+                position: u16::from_be_bytes(position),
+            };
+            let tx_hash = H256::from_slice(value.as_ref());
+            transactions.push(self.export_transaction(tx_hash, tx_included, block_height)?);
+        }
+
+        Ok(BlockExport {
+            schema_version: SCHEMA_VERSION,
+            block_height,
+            block_hash: hex::encode(block_hash),
+            timestamp: block_metadata.timestamp.nanos(),
+            random_seed: hex::encode(block_metadata.random_seed),
+            transactions,
+        })
+    }
+
+    fn export_transaction(
+        &self,
+        tx_hash: H256,
+        tx_included: TransactionIncluded,
+        block_height: u64,
+    ) -> Result<ExportedTransaction, crate::Error> {
+        let record = self.get_transaction_record(tx_hash)?;
+        let diff = self.get_transaction_diff(tx_included)?;
+        let receipt = self
+            .get_transaction_outcome(tx_hash)?
+            .as_ref()
+            .map(ExportedReceipt::from);
+
+        let state_diff = diff
+            .iter()
+            .map(|(key, value)| BundleStateEntry {
+                key: base64::encode(key),
+                value: value.value().map(base64::encode),
+            })
+            .collect();
+
+        let balance_changes = self.balance_changes(&diff, block_height, tx_included.position);
+
+        Ok(ExportedTransaction {
+            tx_hash: hex::encode(tx_hash),
+            near_tx_hash: hex::encode(record.near_tx_hash),
+            position: tx_included.position,
+            signer: record.signer.to_string(),
+            caller: record.caller.to_string(),
+            method: record.method,
+            input: base64::encode(record.input),
+            receipt,
+            state_diff,
+            balance_changes,
+        })
+    }
+}
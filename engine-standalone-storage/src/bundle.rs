@@ -0,0 +1,215 @@
+//! Reproducible transaction bundles.
+//!
+//! A bundle contains everything needed to re-execute a single transaction outside of
+//! the replica: the block context, the raw input of the engine method and the subset
+//! of the engine state the transaction touched, both before and after its execution.
+//! Bundles are serialized as JSON (with base64 encoded bytes, like the JSON snapshots)
+//! so they can be attached to bug reports.
+
+use aurora_engine::balance_journal::{self, BalanceJournal};
+use aurora_engine_types::account_id::AccountId;
+use aurora_engine_types::types::Wei;
+use aurora_engine_types::H256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::sync::types::{TransactionKind, TransactionMessage};
+use crate::Storage;
+
+/// Data about a transaction which is not part of its diff, kept by the storage
+/// so that a bundle can be exported later.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TransactionRecord {
+    pub near_tx_hash: H256Bytes,
+    pub signer: AccountId,
+    pub caller: AccountId,
+    pub attached_near: u128,
+    /// Name of the engine method which was called.
+    pub method: String,
+    /// Input of the engine method, exactly as it was given on NEAR.
+    pub input: Vec<u8>,
+    /// Engine keys read or written while executing the transaction.
+    pub accessed_keys: Vec<Vec<u8>>,
+}
+
+/// `H256` does not implement the borsh traits.
+pub type H256Bytes = [u8; 32];
+
+impl TransactionRecord {
+    pub fn new(message: &TransactionMessage, accessed_keys: Vec<Vec<u8>>) -> Self {
+        let (method, input) = method_and_input(&message.transaction);
+        Self {
+            near_tx_hash: message.near_tx_hash.0,
+            signer: message.signer.clone(),
+            caller: message.caller.clone(),
+            attached_near: message.attached_near,
+            method: method.to_string(),
+            input,
+            accessed_keys,
+        }
+    }
+
+    pub fn try_to_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        self.try_to_vec()
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, std::io::Error> {
+        Self::try_from_slice(bytes)
+    }
+}
+
+fn method_and_input(transaction: &TransactionKind) -> (&'static str, Vec<u8>) {
+    match transaction {
+        TransactionKind::Submit(tx) => ("submit", tx.clone().into()),
+        TransactionKind::Call(args) => ("call", args.try_to_vec().unwrap()),
+        TransactionKind::Deploy(input) => ("deploy_code", input.clone()),
+        TransactionKind::DeployErc20(args) => ("deploy_erc20_token", args.try_to_vec().unwrap()),
+        TransactionKind::FtOnTransfer(args) => {
+            ("ft_on_transfer", String::from(args.clone()).into_bytes())
+        }
+        TransactionKind::Deposit(raw_proof) => ("deposit", raw_proof.clone()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBundle {
+    /// Hex encoded hash under which the transaction is stored.
+    pub tx_hash: String,
+    pub near_tx_hash: String,
+    pub block_hash: String,
+    pub block_height: u64,
+    pub position: u16,
+    /// Block timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub random_seed: String,
+    pub signer: String,
+    pub caller: String,
+    /// Decimal string, since the amount does not fit in a JSON number.
+    pub attached_near: String,
+    pub method: String,
+    /// Base64 encoded input of the engine method.
+    pub input: String,
+    /// Values of the accessed keys before the transaction executed.
+    pub pre_state: Vec<BundleStateEntry>,
+    /// Values of the modified keys after the transaction executed.
+    pub post_state: Vec<BundleStateEntry>,
+    /// Net change of the ETH balances modified by the transaction, in ascending
+    /// address order.
+    #[serde(default)]
+    pub balance_changes: Vec<BundleBalanceChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BundleStateEntry {
+    /// Base64 encoded engine key.
+    pub key: String,
+    /// Base64 encoded value; `None` means the key is not present.
+    pub value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BundleBalanceChange {
+    /// Hex encoded address.
+    pub address: String,
+    /// Balance in wei before the transaction, as a decimal string.
+    pub before: String,
+    /// Balance in wei after the transaction, as a decimal string.
+    pub after: String,
+}
+
+impl TransactionBundle {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl Storage {
+    /// Collects everything needed to reproduce the execution of the transaction with
+    /// the given hash. Only transactions consumed after the record keeping was
+    /// introduced can be exported.
+    pub fn export_transaction_bundle(
+        &self,
+        tx_hash: H256,
+    ) -> Result<TransactionBundle, crate::Error> {
+        let tx_included = self.get_transaction_by_hash(tx_hash)?;
+        let record = self.get_transaction_record(tx_hash)?;
+        let diff = self.get_transaction_diff(tx_included)?;
+        let block_height = self.get_block_height_by_hash(tx_included.block_hash)?;
+        let block_metadata = self.get_block_metadata(tx_included.block_hash)?;
+
+        let pre_state = record
+            .accessed_keys
+            .iter()
+            .map(|key| BundleStateEntry {
+                key: base64::encode(key),
+                value: self
+                    .read_engine_key_at_position(key, block_height, tx_included.position)
+                    .map(base64::encode),
+            })
+            .collect();
+        let post_state = diff
+            .iter()
+            .map(|(key, value)| BundleStateEntry {
+                key: base64::encode(key),
+                value: value.value().map(base64::encode),
+            })
+            .collect();
+
+        let balance_changes = self.balance_changes(&diff, block_height, tx_included.position);
+
+        Ok(TransactionBundle {
+            tx_hash: hex::encode(tx_hash),
+            near_tx_hash: hex::encode(record.near_tx_hash),
+            block_hash: hex::encode(tx_included.block_hash),
+            block_height,
+            position: tx_included.position,
+            timestamp: block_metadata.timestamp.nanos(),
+            random_seed: hex::encode(block_metadata.random_seed),
+            signer: record.signer.to_string(),
+            caller: record.caller.to_string(),
+            attached_near: record.attached_near.to_string(),
+            method: record.method,
+            input: base64::encode(record.input),
+            pre_state,
+            post_state,
+            balance_changes,
+        })
+    }
+
+    /// Net change of the ETH balances modified by the diff of the transaction at the given
+    /// position, in ascending address order.
+    pub(crate) fn balance_changes(
+        &self,
+        diff: &crate::Diff,
+        block_height: u64,
+        transaction_position: u16,
+    ) -> Vec<BundleBalanceChange> {
+        let mut journal = BalanceJournal::new();
+        for (key, value) in diff.iter() {
+            if let Some(address) = balance_journal::balance_key_address(key) {
+                let before = self
+                    .read_engine_key_at_position(key, block_height, transaction_position)
+                    .map(|bytes| balance_journal::balance_from_bytes(&bytes))
+                    .unwrap_or_else(Wei::zero);
+                let after = value
+                    .value()
+                    .map(balance_journal::balance_from_bytes)
+                    .unwrap_or_else(Wei::zero);
+                journal.record(address, before, after);
+            }
+        }
+        journal
+            .net_changes()
+            .into_iter()
+            .map(|(address, change)| BundleBalanceChange {
+                address: hex::encode(address),
+                before: change.before.to_decimal_string(),
+                after: change.after.to_decimal_string(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,54 @@
+//! Migration job for the deduplicated contract code.
+//!
+//! Contracts deployed before the code deduplication keep their code under their own key.
+//! Their addresses are found here, from the engine state kept by the replica, and handed
+//! to the `migrate_code_dedup` method of the engine in batches.
+
+use aurora_engine_types::storage::{address_to_key, KeyPrefix};
+use aurora_engine_types::types::RawAddress;
+use aurora_engine_types::Address;
+use std::collections::BTreeSet;
+
+use crate::{construct_storage_key, Storage, StoragePrefix, ENGINE_KEY_SUFFIX_LEN};
+
+impl Storage {
+    /// Addresses of the accounts whose code still has to be moved to the deduplicated
+    /// storage, as of the latest state, in batches of at most `batch_size` addresses.
+    pub fn code_dedup_batches(&self, batch_size: usize) -> Vec<Vec<RawAddress>> {
+        let any_key = address_to_key(KeyPrefix::Code, &Address::zero());
+        let key_prefix = &any_key[..2];
+        let db_prefix = construct_storage_key(StoragePrefix::Engine, key_prefix);
+        let addresses: BTreeSet<RawAddress> = self
+            .db
+            .iterator(rocksdb::IteratorMode::From(
+                &db_prefix,
+                rocksdb::Direction::Forward,
+            ))
+            .take_while(|(db_key, _)| db_key.starts_with(&db_prefix))
+            .filter_map(|(db_key, _)| {
+                // Strip the storage version and prefix, and the height and position.
+                let engine_key = db_key.get(2..db_key.len().checked_sub(ENGINE_KEY_SUFFIX_LEN)?)?;
+                if engine_key.len() != any_key.len() {
+                    return None;
+                }
+                let mut address = [0u8; 20];
+                address.copy_from_slice(&engine_key[2..]);
+                Some(address)
+            })
+            .collect();
+
+        // Accounts removed (or already migrated) since have no code under their own key.
+        let addresses: Vec<RawAddress> = addresses
+            .into_iter()
+            .filter(|address| {
+                let key = address_to_key(KeyPrefix::Code, &Address(*address));
+                self.read_engine_key_at_position(&key, u64::MAX, u16::MAX)
+                    .is_some()
+            })
+            .collect();
+        addresses
+            .chunks(batch_size.max(1))
+            .map(|batch| batch.to_vec())
+            .collect()
+    }
+}
@@ -1,6 +1,7 @@
 use aurora_engine_sdk::io::{StorageIntermediate, IO};
 use rocksdb::DB;
 use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
 
 use crate::diff::{Diff, DiffValue};
 use crate::StoragePrefix;
@@ -40,6 +41,7 @@ pub struct EngineStateAccess<'db, 'input, 'output> {
     bound_block_height: u64,
     bound_tx_position: u16,
     transaction_diff: &'output RefCell<Diff>,
+    accessed_keys: &'output RefCell<BTreeSet<Vec<u8>>>,
     output: &'output Cell<Vec<u8>>,
     db: &'db DB,
 }
@@ -50,6 +52,7 @@ impl<'db, 'input, 'output> EngineStateAccess<'db, 'input, 'output> {
         bound_block_height: u64,
         bound_tx_position: u16,
         transaction_diff: &'output RefCell<Diff>,
+        accessed_keys: &'output RefCell<BTreeSet<Vec<u8>>>,
         output: &'output Cell<Vec<u8>>,
         db: &'db DB,
     ) -> Self {
@@ -58,6 +61,7 @@ impl<'db, 'input, 'output> EngineStateAccess<'db, 'input, 'output> {
             bound_block_height,
             bound_tx_position,
             transaction_diff,
+            accessed_keys,
             output,
             db,
         }
@@ -67,6 +71,12 @@ impl<'db, 'input, 'output> EngineStateAccess<'db, 'input, 'output> {
         self.transaction_diff.borrow().clone()
     }
 
+    /// All keys read or written through this object, i.e. the part of the
+    /// pre-state the transaction depends on.
+    pub fn get_accessed_keys(&self) -> Vec<Vec<u8>> {
+        self.accessed_keys.borrow().iter().cloned().collect()
+    }
+
     fn construct_engine_read(&self, key: &[u8]) -> rocksdb::ReadOptions {
         let upper_bound =
             super::construct_engine_key(key, self.bound_block_height, self.bound_tx_position);
@@ -90,6 +100,10 @@ impl<'db, 'input: 'db, 'output: 'db> IO for EngineStateAccess<'db, 'input, 'outp
     }
 
     fn read_storage(&self, key: &[u8]) -> Option<Self::StorageValue> {
+        if !self.accessed_keys.borrow().contains(key) {
+            self.accessed_keys.borrow_mut().insert(key.to_vec());
+        }
+
         if let Some(diff) = self.transaction_diff.borrow().get(key) {
             return diff
                 .value()
@@ -11,6 +11,8 @@ pub fn initialize_engine_state(
     // When we access engine storage, we are accessing the pre-state. The snapshot is giving
     // us a post-state, which of course is the pre-state of the following block.
     let block_height = snapshot.result.block_height + 1;
+    let _span = tracing::info_span!("initialize_engine_state", block_height).entered();
+    let num_entries = snapshot.result.values.len();
 
     let mut batch = rocksdb::WriteBatch::default();
     for entry in snapshot.result.values {
@@ -21,6 +23,7 @@ pub fn initialize_engine_state(
         batch.put(storage_key, storage_value.try_to_bytes()?);
     }
     storage.db.write(batch)?;
+    tracing::info!(num_entries, "engine state initialized from snapshot");
 
     Ok(())
 }
@@ -1,19 +1,30 @@
+use aurora_engine::parameters::SubmitResult;
+use aurora_engine::upgrade_history::{self, UpgradeHistoryError, UpgradeRecord};
 use aurora_engine_sdk::env::Timestamp;
 use aurora_engine_types::H256;
+use borsh::{BorshDeserialize, BorshSerialize};
 use rocksdb::DB;
 use std::cell::{Cell, RefCell};
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
 const VERSION: u8 = 0;
 
+pub mod block_export;
+pub mod bundle;
+pub mod code_dedup;
 pub mod diff;
 pub mod engine_state;
 pub mod error;
 pub mod json_snapshot;
+pub mod metrics;
+mod profile;
 mod promise;
 pub mod relayer_db;
+pub mod storage_sharding;
 /// Functions for receiving new blocks and transactions to keep the storage up to date.
 pub mod sync;
+pub mod view;
 
 pub use diff::Diff;
 pub use error::Error;
@@ -27,26 +38,80 @@ pub enum StoragePrefix {
     Diff = 0x04,
     Engine = 0x05,
     BlockMetadata = 0x06,
+    TransactionRecord = 0x07,
+    TransactionOutcome = 0x08,
 }
 
 pub struct Storage {
     db: DB,
     engine_transaction: RefCell<Diff>,
+    engine_accessed_keys: RefCell<BTreeSet<Vec<u8>>>,
     engine_output: Cell<Vec<u8>>,
+    supported_engine_code: BTreeSet<H256>,
+    block_export_dir: Option<PathBuf>,
+    last_block_hash: Option<H256>,
+    view_cache: Option<view::ViewCache>,
 }
 
 impl Storage {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rocksdb::Error> {
         let db = DB::open_default(path)?;
         let engine_transaction = RefCell::new(Diff::default());
+        let engine_accessed_keys = RefCell::new(BTreeSet::new());
         let engine_output = Cell::new(Vec::new());
         Ok(Self {
             db,
             engine_transaction,
+            engine_accessed_keys,
             engine_output,
+            supported_engine_code: BTreeSet::new(),
+            block_export_dir: None,
+            last_block_hash: None,
+            view_cache: None,
         })
     }
 
+    /// Restricts replays to the given engine code hashes: transactions which were executed
+    /// on chain by any other code (according to the upgrade registry of the engine) are
+    /// refused instead of being replayed with the wrong semantics. An empty set accepts
+    /// any code.
+    pub fn set_supported_engine_code<I: IntoIterator<Item = H256>>(&mut self, code_hashes: I) {
+        self.supported_engine_code = code_hashes.into_iter().collect();
+    }
+
+    pub fn is_engine_code_supported(&self, code_hash: &H256) -> bool {
+        self.supported_engine_code.is_empty() || self.supported_engine_code.contains(code_hash)
+    }
+
+    /// Makes `sync::consume_message` write the export file of each block (see
+    /// `block_export`) to the given directory once the next block is received. The
+    /// last block consumed is written by `flush_block_export`.
+    pub fn set_block_export_dir(&mut self, dir: Option<PathBuf>) {
+        self.block_export_dir = dir;
+    }
+
+    /// Writes the export file of the last block consumed, if block exports are enabled.
+    pub fn flush_block_export(&mut self) -> Result<Option<PathBuf>, block_export::Error> {
+        let (dir, block_hash) = match (&self.block_export_dir, self.last_block_hash.take()) {
+            (Some(dir), Some(block_hash)) => (dir, block_hash),
+            _ => return Ok(None),
+        };
+        let path = self.export_block(block_hash)?.write_to_dir(dir)?;
+        Ok(Some(path))
+    }
+
+    /// Records the block being consumed, writing the export file of the previous one.
+    pub(crate) fn start_block_export(
+        &mut self,
+        block_hash: H256,
+    ) -> Result<Option<PathBuf>, block_export::Error> {
+        let path = self.flush_block_export()?;
+        if self.block_export_dir.is_some() {
+            self.last_block_hash = Some(block_hash);
+        }
+        Ok(path)
+    }
+
     pub fn get_block_hash_by_height(&self, block_height: u64) -> Result<H256, error::Error> {
         let storage_key =
             construct_storage_key(StoragePrefix::BlockHash, &block_height.to_be_bytes());
@@ -99,6 +164,7 @@ impl Storage {
         let storage_key = construct_storage_key(StoragePrefix::BlockMetadata, block_hash.as_ref());
         batch.put(storage_key, block_metadata.to_bytes());
 
+        self.invalidate_view_cache(block_height);
         self.db.write(batch)
     }
 
@@ -167,9 +233,115 @@ impl Storage {
             batch.put(storage_key, value.try_to_bytes().unwrap());
         }
 
+        self.invalidate_view_cache(block_height);
         self.db.write(batch).map_err(Into::into)
     }
 
+    /// Estimated size of the database files on disk, in bytes.
+    pub fn estimated_size(&self) -> Option<u64> {
+        self.db
+            .property_int_value("rocksdb.total-sst-files-size")
+            .ok()
+            .flatten()
+    }
+
+    pub fn get_transaction_record(
+        &self,
+        tx_hash: H256,
+    ) -> Result<bundle::TransactionRecord, error::Error> {
+        let storage_key = construct_storage_key(StoragePrefix::TransactionRecord, tx_hash.as_ref());
+        self.db
+            .get_pinned(storage_key)?
+            .map(|slice| bundle::TransactionRecord::try_from_bytes(slice.as_ref()).unwrap())
+            .ok_or(error::Error::TransactionHashNotFound(tx_hash))
+    }
+
+    /// Keep the information needed to export a reproducible bundle of the transaction.
+    pub fn set_transaction_record(
+        &mut self,
+        tx_hash: H256,
+        record: &bundle::TransactionRecord,
+    ) -> Result<(), error::Error> {
+        let storage_key = construct_storage_key(StoragePrefix::TransactionRecord, tx_hash.as_ref());
+        self.db
+            .put(storage_key, record.try_to_bytes().unwrap())
+            .map_err(Into::into)
+    }
+
+    /// Result of the EVM execution of the transaction, kept for `submit`, `call` and
+    /// `deploy_code` transactions only.
+    pub fn get_transaction_outcome(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<SubmitResult>, error::Error> {
+        let storage_key =
+            construct_storage_key(StoragePrefix::TransactionOutcome, tx_hash.as_ref());
+        Ok(self
+            .db
+            .get_pinned(storage_key)?
+            .map(|slice| SubmitResult::try_from_slice(slice.as_ref()).unwrap()))
+    }
+
+    pub fn set_transaction_outcome(
+        &mut self,
+        tx_hash: H256,
+        outcome: &SubmitResult,
+    ) -> Result<(), error::Error> {
+        let storage_key =
+            construct_storage_key(StoragePrefix::TransactionOutcome, tx_hash.as_ref());
+        self.db
+            .put(storage_key, outcome.try_to_vec().unwrap())
+            .map_err(Into::into)
+    }
+
+    /// Read the value of an engine key as it was right before the transaction at the
+    /// given position was executed.
+    pub fn read_engine_key_at_position(
+        &self,
+        key: &[u8],
+        block_height: u64,
+        transaction_position: u16,
+    ) -> Option<Vec<u8>> {
+        use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+        let transaction_diff = RefCell::new(Diff::default());
+        let accessed_keys = RefCell::new(BTreeSet::new());
+        let output = Cell::new(Vec::new());
+        let io = engine_state::EngineStateAccess::new(
+            &[],
+            block_height,
+            transaction_position,
+            &transaction_diff,
+            &accessed_keys,
+            &output,
+            &self.db,
+        );
+        io.read_storage(key).map(|value| value.to_vec())
+    }
+
+    /// The engine upgrade in effect for the transaction at the given position, according
+    /// to the registry kept by the engine. `None` if no upgrade was recorded before it.
+    pub fn get_active_upgrade(
+        &self,
+        block_height: u64,
+        transaction_position: u16,
+    ) -> Result<Option<UpgradeRecord>, UpgradeHistoryError> {
+        let transaction_diff = RefCell::new(Diff::default());
+        let accessed_keys = RefCell::new(BTreeSet::new());
+        let output = Cell::new(Vec::new());
+        let io = engine_state::EngineStateAccess::new(
+            &[],
+            block_height,
+            transaction_position,
+            &transaction_diff,
+            &accessed_keys,
+            &output,
+            &self.db,
+        );
+        let history = upgrade_history::get_history(&io)?;
+        Ok(upgrade_history::active_at(&history, block_height).copied())
+    }
+
     /// Get an object which represents the state of the engine at the given block hash,
     /// after transactions up to (not including) the given transaction index.
     /// The `input` is the bytes that would be present in the NEAR runtime (normally
@@ -181,6 +353,7 @@ impl Storage {
         input: &'input [u8],
     ) -> engine_state::EngineStateAccess<'db, 'db, 'db> {
         self.engine_transaction.borrow_mut().clear();
+        self.engine_accessed_keys.borrow_mut().clear();
         self.engine_output.set(Vec::new());
 
         engine_state::EngineStateAccess::new(
@@ -188,6 +361,7 @@ impl Storage {
             block_height,
             transaction_position,
             &self.engine_transaction,
+            &self.engine_accessed_keys,
             &self.engine_output,
             &self.db,
         )
@@ -262,6 +436,9 @@ fn construct_storage_key(prefix: StoragePrefix, key: &[u8]) -> Vec<u8> {
     [&[VERSION], &[prefix as u8], key].concat()
 }
 
+/// Length of the block height and transaction position suffix of the engine keys.
+const ENGINE_KEY_SUFFIX_LEN: usize = 10;
+
 fn construct_engine_key(key: &[u8], block_height: u64, transaction_position: u16) -> Vec<u8> {
     construct_storage_key(
         StoragePrefix::Engine,
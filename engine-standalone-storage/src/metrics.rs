@@ -0,0 +1,169 @@
+//! Prometheus metrics for standalone engine replicas.
+//!
+//! `Metrics` collects replay throughput (via `SyncProgress`), cache hit rates and
+//! per-method execution latencies. `serve` exposes them over HTTP in the Prometheus
+//! text format; it is never started implicitly.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::sync::status::SyncProgress;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheCounters {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheCounters {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub total_seconds: f64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Metrics {
+    pub sync: SyncProgress,
+    /// Estimated size of the storage on disk, in bytes.
+    pub storage_size_bytes: Option<u64>,
+    caches: BTreeMap<String, CacheCounters>,
+    method_latencies: BTreeMap<String, LatencySummary>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_cache_hit(&mut self, cache: &str) {
+        self.caches.entry(cache.to_string()).or_default().hits += 1;
+    }
+
+    pub fn record_cache_miss(&mut self, cache: &str) {
+        self.caches.entry(cache.to_string()).or_default().misses += 1;
+    }
+
+    pub fn cache(&self, cache: &str) -> CacheCounters {
+        self.caches.get(cache).copied().unwrap_or_default()
+    }
+
+    /// Records how long executing an engine method (e.g. `submit`, `call`) took.
+    pub fn record_method_latency(&mut self, method: &str, latency: Duration) {
+        let summary = self.method_latencies.entry(method.to_string()).or_default();
+        summary.count += 1;
+        summary.total_seconds += latency.as_secs_f64();
+    }
+
+    pub fn method_latency(&self, method: &str) -> LatencySummary {
+        self.method_latencies
+            .get(method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn update_storage_size(&mut self, storage: &crate::Storage) {
+        self.storage_size_bytes = storage.estimated_size();
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = self.sync.to_prometheus();
+
+        if let Some(size) = self.storage_size_bytes {
+            let _ = writeln!(
+                out,
+                "# HELP aurora_standalone_storage_size_bytes Estimated size of the storage on disk."
+            );
+            let _ = writeln!(out, "# TYPE aurora_standalone_storage_size_bytes gauge");
+            let _ = writeln!(out, "aurora_standalone_storage_size_bytes {}", size);
+        }
+
+        if !self.caches.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP aurora_standalone_cache_hit_rate Fraction of lookups served from the cache."
+            );
+            let _ = writeln!(out, "# TYPE aurora_standalone_cache_hit_rate gauge");
+            for (cache, counters) in self.caches.iter() {
+                let _ = writeln!(
+                    out,
+                    "aurora_standalone_cache_hit_rate{{cache=\"{}\"}} {}",
+                    cache,
+                    counters.hit_rate()
+                );
+            }
+        }
+
+        if !self.method_latencies.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP aurora_standalone_method_latency_seconds Execution time of engine methods."
+            );
+            let _ = writeln!(
+                out,
+                "# TYPE aurora_standalone_method_latency_seconds summary"
+            );
+            for (method, summary) in self.method_latencies.iter() {
+                let _ = writeln!(
+                    out,
+                    "aurora_standalone_method_latency_seconds_sum{{method=\"{}\"}} {}",
+                    method, summary.total_seconds
+                );
+                let _ = writeln!(
+                    out,
+                    "aurora_standalone_method_latency_seconds_count{{method=\"{}\"}} {}",
+                    method, summary.count
+                );
+            }
+        }
+
+        out
+    }
+}
+
+/// Serves the metrics over HTTP on a background thread. Every request, regardless
+/// of path, receives the current metrics.
+pub fn serve<A: ToSocketAddrs>(
+    addr: A,
+    metrics: Arc<Mutex<Metrics>>,
+) -> std::io::Result<thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    let handle = thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // The request itself is irrelevant; read (part of) it so the client is not reset.
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let body = match metrics.lock() {
+                Ok(metrics) => metrics.to_prometheus(),
+                Err(_) => continue,
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    Ok(handle)
+}
@@ -0,0 +1,62 @@
+//! Profiling of the host functions called by a transaction, for developers optimizing
+//! contracts for their NEAR gas cost and not only their EVM gas.
+//!
+//! The transaction is executed like `submit` on the replayed state at the end of a block,
+//! through a `ProfilingIO` counting its storage accesses, and its changes are discarded.
+
+use aurora_engine::engine;
+use aurora_engine::execution_profile::ProfilingIO;
+use aurora_engine::parameters::{ExecutionProfile, SubmitResult};
+use aurora_engine_sdk::env::{self, DEFAULT_PREPAID_GAS};
+use aurora_engine_types::account_id::AccountId;
+use aurora_engine_types::H256;
+use std::cell::Cell;
+
+use crate::view::Error;
+use crate::Storage;
+
+impl Storage {
+    /// Executes the signed transaction like `submit` on the state of the engine at the end of
+    /// the block with the given hash, without storing its changes, and returns its result
+    /// with the host functions it called.
+    pub fn profile_submit(
+        &mut self,
+        block_hash: H256,
+        transaction_bytes: &[u8],
+    ) -> Result<(SubmitResult, ExecutionProfile), Error> {
+        let block_height = self.get_block_height_by_hash(block_hash)?;
+        let block_metadata = self.get_block_metadata(block_hash)?;
+        let current_account_id: AccountId = crate::sync::AURORA_ACCOUNT_ID.parse().unwrap();
+        let relayer_address =
+            aurora_engine_sdk::types::near_account_to_evm_address(current_account_id.as_bytes());
+        let env = env::Fixed {
+            signer_account_id: current_account_id.clone(),
+            current_account_id: current_account_id.clone(),
+            predecessor_account_id: current_account_id.clone(),
+            block_height,
+            block_timestamp: block_metadata.timestamp,
+            attached_deposit: 0,
+            random_seed: block_metadata.random_seed,
+            prepaid_gas: DEFAULT_PREPAID_GAS,
+        };
+
+        let profile = Cell::new(ExecutionProfile::default());
+        // After all the transactions of the block.
+        let io = self.access_engine_storage_at_position(block_height, u16::MAX, &[]);
+        let state = engine::get_state(&io)?;
+        let result = engine::submit(
+            ProfilingIO::new(io, &profile),
+            &env,
+            transaction_bytes,
+            state,
+            current_account_id,
+            relayer_address,
+            &mut crate::promise::Noop,
+        )
+        .map_err(|e| e.kind)?;
+
+        let mut profile = profile.get();
+        profile.promises = result.promises.count;
+        Ok((result, profile))
+    }
+}
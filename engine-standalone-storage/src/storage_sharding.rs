@@ -0,0 +1,84 @@
+//! Migration job for the sharded storage of hot contracts.
+//!
+//! Contracts cannot list their own storage, so after the owner switches a contract to
+//! sharded keys its existing slots are found here, from the engine state kept by the
+//! replica, and handed to the `migrate_storage_sharding` method of the engine in batches.
+
+use aurora_engine::parameters::MigrateStorageShardingArgs;
+use aurora_engine::storage_sharding;
+use aurora_engine_types::storage::storage_to_key;
+use aurora_engine_types::{Address, H256};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+
+use crate::diff::Diff;
+use crate::{construct_storage_key, engine_state, Storage, StoragePrefix, ENGINE_KEY_SUFFIX_LEN};
+
+impl Storage {
+    /// Slots of the contract which still have to be moved to the sharded layout, as of the
+    /// latest state, in batches of at most `batch_size` slots. Empty if the contract is not
+    /// being migrated.
+    pub fn storage_sharding_batches(
+        &self,
+        address: Address,
+        batch_size: usize,
+    ) -> Vec<MigrateStorageShardingArgs> {
+        let transaction_diff = RefCell::new(Diff::default());
+        let accessed_keys = RefCell::new(BTreeSet::new());
+        let output = Cell::new(Vec::new());
+        let io = engine_state::EngineStateAccess::new(
+            &[],
+            u64::MAX,
+            u16::MAX,
+            &transaction_diff,
+            &accessed_keys,
+            &output,
+            &self.db,
+        );
+        let legacy_generation = match storage_sharding::legacy_generation(&io, &address) {
+            Some(generation) => generation,
+            None => return Vec::new(),
+        };
+
+        let any_key = storage_to_key(&address, &H256::zero(), legacy_generation);
+        let key_len = any_key.as_ref().len();
+        let key_prefix = &any_key.as_ref()[..key_len - 32];
+        let db_prefix = construct_storage_key(StoragePrefix::Engine, key_prefix);
+        let slots: BTreeSet<[u8; 32]> = self
+            .db
+            .iterator(rocksdb::IteratorMode::From(
+                &db_prefix,
+                rocksdb::Direction::Forward,
+            ))
+            .take_while(|(db_key, _)| db_key.starts_with(&db_prefix))
+            .filter_map(|(db_key, _)| {
+                // Strip the storage version and prefix, and the height and position.
+                let engine_key = db_key.get(2..db_key.len().checked_sub(ENGINE_KEY_SUFFIX_LEN)?)?;
+                if engine_key.len() != key_len {
+                    // Keys of a later generation sharing the prefix.
+                    return None;
+                }
+                let mut slot = [0u8; 32];
+                slot.copy_from_slice(&engine_key[key_len - 32..]);
+                Some(slot)
+            })
+            .collect();
+
+        // Slots removed (or already migrated) since are not stored anymore.
+        let slots: Vec<[u8; 32]> = slots
+            .into_iter()
+            .filter(|slot| {
+                let key = storage_to_key(&address, &H256(*slot), legacy_generation);
+                self.read_engine_key_at_position(key.as_ref(), u64::MAX, u16::MAX)
+                    .is_some()
+            })
+            .collect();
+        slots
+            .chunks(batch_size.max(1))
+            .map(|keys| MigrateStorageShardingArgs {
+                address: address.into(),
+                keys: keys.to_vec(),
+            })
+            .collect()
+    }
+}
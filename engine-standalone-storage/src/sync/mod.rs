@@ -1,13 +1,46 @@
-use aurora_engine::{connector, engine, parameters};
+use aurora_engine::{connector, deploy_gate, engine, parameters};
 use aurora_engine_sdk::env::{self, Env, DEFAULT_PREPAID_GAS};
-use aurora_engine_types::TryFrom;
+use aurora_engine_types::{TryFrom, H256};
 use borsh::BorshDeserialize;
 
+pub mod status;
 pub mod types;
 
 use types::{Message, TransactionKind};
 
-const AURORA_ACCOUNT_ID: &str = "aurora";
+pub(crate) const AURORA_ACCOUNT_ID: &str = "aurora";
+
+/// How a transaction is replayed, chosen from the engine code which executed it on chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionSemantics {
+    /// The transaction is replayed by the engine linked into this binary.
+    Current,
+    /// The transaction was executed by engine code this replica was not built for.
+    Unsupported { code_hash: H256, activated_at: u64 },
+}
+
+/// Selects the semantics for the transaction at the given position from the upgrade
+/// registry of the engine. Transactions preceding the first recorded upgrade are assumed
+/// to match the linked engine.
+pub fn execution_semantics(
+    storage: &crate::Storage,
+    block_height: u64,
+    transaction_position: u16,
+) -> Result<ExecutionSemantics, error::Error> {
+    let upgrade = match storage.get_active_upgrade(block_height, transaction_position)? {
+        Some(upgrade) => upgrade,
+        None => return Ok(ExecutionSemantics::Current),
+    };
+    let code_hash = H256(upgrade.code_hash);
+    if storage.is_engine_code_supported(&code_hash) {
+        Ok(ExecutionSemantics::Current)
+    } else {
+        Ok(ExecutionSemantics::Unsupported {
+            code_hash,
+            activated_at: upgrade.activated_at,
+        })
+    }
+}
 
 pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result<(), error::Error> {
     match message {
@@ -15,18 +48,35 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
             let block_hash = block_message.hash;
             let block_height = block_message.height;
             let block_metadata = block_message.metadata;
+            let _span =
+                tracing::info_span!("block", height = block_height, hash = ?block_hash).entered();
+            if let Some(path) = storage.start_block_export(block_hash)? {
+                tracing::debug!(?path, "previous block exported");
+            }
             storage
                 .set_block_data(block_hash, block_height, block_metadata)
                 .map_err(crate::Error::Rocksdb)?;
+            tracing::debug!("block stored");
             Ok(())
         }
 
         Message::Transaction(transaction_message) => {
+            let _span = tracing::info_span!(
+                "transaction",
+                block_hash = ?transaction_message.block_hash,
+                position = transaction_message.position,
+                near_tx_hash = ?transaction_message.near_tx_hash
+            )
+            .entered();
+
             // Failed transactions have no impact on the state of our database.
             if !transaction_message.succeeded {
+                tracing::debug!("skipping failed transaction");
                 return Ok(());
             }
 
+            let mut record =
+                crate::bundle::TransactionRecord::new(&transaction_message, Vec::new());
             let signer_account_id = transaction_message.signer;
             let predecessor_account_id = transaction_message.caller;
             let relayer_address = aurora_engine_sdk::types::near_account_to_evm_address(
@@ -37,6 +87,11 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
             let block_hash = transaction_message.block_hash;
             let block_height = storage.get_block_height_by_hash(block_hash)?;
             let block_metadata = storage.get_block_metadata(block_hash)?;
+            if let ExecutionSemantics::Unsupported { code_hash, .. } =
+                execution_semantics(storage, block_height, transaction_position)?
+            {
+                return Err(error::Error::UnsupportedEngineCode(code_hash));
+            }
             let current_account_id = AURORA_ACCOUNT_ID.parse().unwrap();
             let env = env::Fixed {
                 signer_account_id,
@@ -51,7 +106,8 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
             let io =
                 storage.access_engine_storage_at_position(block_height, transaction_position, &[]);
 
-            let tx_hash = match transaction_message.transaction {
+            let execute_span = tracing::debug_span!("execute").entered();
+            let (tx_hash, outcome) = match transaction_message.transaction {
                 TransactionKind::Submit(tx) => {
                     // Only promises possible from `submit` are exit precompiles and we cannot act on those promises
                     let mut handler = crate::promise::Noop;
@@ -59,7 +115,7 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                     let transaction_bytes: Vec<u8> = tx.into();
                     let tx_hash = aurora_engine_sdk::keccak(&transaction_bytes);
 
-                    let _result = engine::submit(
+                    let result = engine::submit(
                         io,
                         &env,
                         &transaction_bytes,
@@ -69,7 +125,7 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                         &mut handler,
                     )?;
 
-                    tx_hash
+                    (tx_hash, Some(result))
                 }
 
                 TransactionKind::Call(args) => {
@@ -78,27 +134,30 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                     let mut engine =
                         engine::Engine::new(relayer_address, env.current_account_id(), io, &env)?;
 
-                    let _result = engine.call_with_args(args, &mut handler)?;
+                    let result = engine.call_with_args(args, &mut handler)?;
 
-                    near_tx_hash
+                    (near_tx_hash, Some(result))
                 }
 
                 TransactionKind::Deploy(input) => {
                     // Only promises possible from `deploy` are exit precompiles and we cannot act on those promises
                     let mut handler = crate::promise::Noop;
+                    deploy_gate::check(&io, &relayer_address).map_err(|e| {
+                        engine::EngineError::from(engine::EngineErrorKind::DeployGate(e))
+                    })?;
                     let mut engine =
                         engine::Engine::new(relayer_address, env.current_account_id(), io, &env)?;
 
-                    let _result = engine.deploy_code_with_input(input, &mut handler)?;
+                    let result = engine.deploy_code_with_input(input, &mut handler)?;
 
-                    near_tx_hash
+                    (near_tx_hash, Some(result))
                 }
 
                 TransactionKind::DeployErc20(args) => {
                     // No promises can be created by `deploy_erc20_token`
                     let mut handler = crate::promise::Noop;
                     let _result = engine::deploy_erc20_token(args, io, &env, &mut handler)?;
-                    near_tx_hash
+                    (near_tx_hash, None)
                 }
 
                 TransactionKind::FtOnTransfer(args) => {
@@ -109,7 +168,7 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
 
                     if env.predecessor_account_id == env.current_account_id {
                         connector::EthConnectorContract::init_instance(io)
-                            .ft_on_transfer(&engine, &args)?;
+                            .ft_on_transfer(&engine, &args);
                     } else {
                         engine.receive_erc20_tokens(
                             &env.predecessor_account_id,
@@ -120,16 +179,18 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                         );
                     }
 
-                    near_tx_hash
+                    (near_tx_hash, None)
                 }
 
                 TransactionKind::Deposit(raw_proof) => {
                     let mut connector_contract = connector::EthConnectorContract::init_instance(io);
-                    let promise_args = connector_contract.deposit(
-                        raw_proof,
-                        env.current_account_id(),
-                        env.predecessor_account_id(),
-                    )?;
+                    let promise_args = connector_contract
+                        .deposit(
+                            raw_proof,
+                            env.current_account_id(),
+                            env.predecessor_account_id(),
+                        )?
+                        .into_promise();
 
                     // Assume the relayer will mark `transaction.succeeded = false` if the
                     // proof failed to verify. This means the proof must be valid if we made
@@ -147,7 +208,7 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                         env.prepaid_gas,
                     )?;
 
-                    if let Some(promise_args) = maybe_promise_args {
+                    if let Some(promise_args) = maybe_promise_args.map(|i| i.into_promise()) {
                         let on_transfer_args =
                             aurora_engine::json::parse_json(&promise_args.base.args)
                                 .and_then(|json| {
@@ -160,22 +221,32 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
                             io,
                             &env,
                         )?;
-                        connector_contract.ft_on_transfer(&engine, &on_transfer_args)?;
-                        // `ft_on_transfer` always returns an unused amount of 0 if it executes
-                        // successfully, meaning that `ft_resolve_transfer` will do nothing,
-                        // so we skip the promise_args callback.
+                        connector_contract.ft_on_transfer(&engine, &on_transfer_args);
+                        // The message was validated by `deposit`, so `ft_on_transfer` only
+                        // refunds tokens if minting them overflows, which a verified deposit
+                        // cannot do. `ft_resolve_transfer` will then do nothing, so we skip
+                        // the promise_args callback.
                     }
 
-                    near_tx_hash
+                    (near_tx_hash, None)
                 }
             };
 
+            drop(execute_span);
+
+            let _commit_span = tracing::debug_span!("commit", tx_hash = ?tx_hash).entered();
             let diff = io.get_transaction_diff();
             let tx_included = crate::TransactionIncluded {
                 block_hash,
                 position: transaction_position,
             };
+            record.accessed_keys = io.get_accessed_keys();
             storage.set_transaction_included(tx_hash, &tx_included, &diff)?;
+            storage.set_transaction_record(tx_hash, &record)?;
+            if let Some(outcome) = outcome {
+                storage.set_transaction_outcome(tx_hash, &outcome)?;
+            }
+            tracing::debug!(modified_keys = diff.iter().count(), "transaction committed");
 
             Ok(())
         }
@@ -183,17 +254,23 @@ pub fn consume_message(storage: &mut crate::Storage, message: Message) -> Result
 }
 
 pub mod error {
-    use aurora_engine::{connector, engine};
+    use aurora_engine::{connector, engine, upgrade_history};
+    use aurora_engine_types::H256;
 
     #[derive(Debug)]
     pub enum Error {
         Storage(crate::Error),
+        BlockExport(crate::block_export::Error),
         EngineState(engine::EngineStateError),
         Engine(engine::EngineError),
         DeployErc20(engine::DeployErc20Error),
         FtOnTransfer(connector::error::FtTransferCallError),
         Deposit(connector::error::DepositError),
         FinishDeposit(connector::error::FinishDepositError),
+        UpgradeHistory(upgrade_history::UpgradeHistoryError),
+        /// The transaction was executed on chain by engine code with this hash, which the
+        /// replica does not support (see `Storage::set_supported_engine_code`).
+        UnsupportedEngineCode(H256),
     }
 
     impl From<crate::Error> for Error {
@@ -201,6 +278,11 @@ pub mod error {
             Self::Storage(e)
         }
     }
+    impl From<crate::block_export::Error> for Error {
+        fn from(e: crate::block_export::Error) -> Self {
+            Self::BlockExport(e)
+        }
+    }
     impl From<engine::EngineStateError> for Error {
         fn from(e: engine::EngineStateError) -> Self {
             Self::EngineState(e)
@@ -231,4 +313,9 @@ pub mod error {
             Self::FinishDeposit(e)
         }
     }
+    impl From<upgrade_history::UpgradeHistoryError> for Error {
+        fn from(e: upgrade_history::UpgradeHistoryError) -> Self {
+            Self::UpgradeHistory(e)
+        }
+    }
 }
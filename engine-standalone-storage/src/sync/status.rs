@@ -0,0 +1,161 @@
+use std::fmt::Write;
+use std::time::Instant;
+
+use super::types::Message;
+
+/// Tracks how far the replica is in applying messages from the blockchain.
+/// Call `record` for every message successfully passed to `consume_message`,
+/// and `set_target_height` whenever the head of the chain is observed.
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgress {
+    starting_height: Option<u64>,
+    latest_applied_height: Option<u64>,
+    target_height: Option<u64>,
+    transactions_applied: u64,
+    started_at: Option<Instant>,
+}
+
+/// Mirrors the result of Ethereum's `eth_syncing` RPC method.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    /// Nothing was applied yet, or the target height is unknown.
+    NotStarted,
+    /// The replica has applied all blocks up to the target height.
+    Synced {
+        height: u64,
+    },
+    Syncing(SyncingInfo),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncingInfo {
+    /// NEAR height of the first block applied by this process.
+    pub starting_height: u64,
+    /// NEAR height of the last block applied.
+    pub current_height: u64,
+    /// NEAR height of the chain head.
+    pub highest_height: u64,
+    /// Number of blocks the replica is behind the head.
+    pub lag: u64,
+    /// Average number of transactions applied per second since the start.
+    pub transactions_per_second: f64,
+}
+
+impl SyncProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_target_height(&mut self, height: u64) {
+        self.target_height = Some(height);
+    }
+
+    pub fn record(&mut self, message: &Message) {
+        if self.started_at.is_none() {
+            self.started_at = Some(Instant::now());
+        }
+        match message {
+            Message::Block(block) => {
+                if self.starting_height.is_none() {
+                    self.starting_height = Some(block.height);
+                }
+                self.latest_applied_height = Some(block.height);
+                // The head is never behind the blocks we have seen.
+                if self.target_height.map(|h| h < block.height).unwrap_or(true) {
+                    self.target_height = Some(block.height);
+                }
+            }
+            Message::Transaction(_) => {
+                self.transactions_applied += 1;
+            }
+        }
+    }
+
+    pub fn latest_applied_height(&self) -> Option<u64> {
+        self.latest_applied_height
+    }
+
+    pub fn transactions_applied(&self) -> u64 {
+        self.transactions_applied
+    }
+
+    pub fn transactions_per_second(&self) -> f64 {
+        let elapsed = match self.started_at {
+            Some(started_at) => started_at.elapsed().as_secs_f64(),
+            None => return 0.0,
+        };
+        if elapsed > 0.0 {
+            self.transactions_applied as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        let (starting_height, current_height, highest_height) = match (
+            self.starting_height,
+            self.latest_applied_height,
+            self.target_height,
+        ) {
+            (Some(start), Some(current), Some(highest)) => (start, current, highest),
+            _ => return SyncStatus::NotStarted,
+        };
+        let lag = highest_height.saturating_sub(current_height);
+        if lag == 0 {
+            return SyncStatus::Synced {
+                height: current_height,
+            };
+        }
+
+        SyncStatus::Syncing(SyncingInfo {
+            starting_height,
+            current_height,
+            highest_height,
+            lag,
+            transactions_per_second: self.transactions_per_second(),
+        })
+    }
+
+    /// Renders the progress in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: f64| {
+            // Writing to a String cannot fail.
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        if let Some(height) = self.latest_applied_height {
+            gauge(
+                "aurora_standalone_sync_latest_applied_height",
+                "NEAR height of the last block applied by the replica.",
+                height as f64,
+            );
+        }
+        if let Some(height) = self.target_height {
+            gauge(
+                "aurora_standalone_sync_target_height",
+                "NEAR height of the chain head known to the replica.",
+                height as f64,
+            );
+        }
+        if let (Some(current), Some(target)) = (self.latest_applied_height, self.target_height) {
+            gauge(
+                "aurora_standalone_sync_lag_blocks",
+                "Number of blocks the replica is behind the chain head.",
+                target.saturating_sub(current) as f64,
+            );
+        }
+        gauge(
+            "aurora_standalone_sync_transactions_applied",
+            "Number of transactions applied since the replica started.",
+            self.transactions_applied as f64,
+        );
+        gauge(
+            "aurora_standalone_sync_transactions_per_second",
+            "Average number of transactions applied per second.",
+            self.transactions_per_second(),
+        );
+        out
+    }
+}
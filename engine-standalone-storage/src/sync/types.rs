@@ -48,7 +48,7 @@ pub enum TransactionKind {
     /// Input here represents the EVM code used to create the new contract
     Deploy(Vec<u8>),
     /// New bridged token
-    DeployErc20(parameters::DeployErc20TokenArgs),
+    DeployErc20(parameters::DeployErc20TokenWithTemplateArgs),
     /// This type of transaction can impact the aurora state because of the bridge
     FtOnTransfer(parameters::NEP141FtOnTransferArgs),
     /// Bytes here will be parsed into `aurora_engine::proof::Proof`
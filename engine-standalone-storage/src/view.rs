@@ -0,0 +1,227 @@
+//! Read-only calls (`view`) against the replayed engine state, for RPC facades serving
+//! `eth_call`.
+//!
+//! The same expensive calls (e.g. the quote functions of a DEX) are often made by many users
+//! at the same block. When a cache is enabled (see `Storage::set_view_cache_capacity`), the
+//! status of a call is memoized under the block height it was executed at and the hash of
+//! its arguments. Each height keeps its most recently used results, and only the most
+//! recently queried heights are kept. Storing a block or a transaction forgets the results
+//! at its height and above, so a result is never served for a state it was not computed on.
+
+use aurora_engine::engine::{self, EngineErrorKind, EngineStateError};
+use aurora_engine::parameters::{TransactionStatus, ViewCallArgs};
+use aurora_engine_sdk::env::{self, DEFAULT_PREPAID_GAS};
+use aurora_engine_types::account_id::AccountId;
+use aurora_engine_types::{Address, H256};
+use borsh::BorshSerialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::metrics::CacheCounters;
+use crate::Storage;
+
+/// Number of block heights whose results are kept, the least recently queried one being
+/// dropped to make room for a new one.
+pub const MAX_CACHED_HEIGHTS: usize = 16;
+
+#[derive(Debug)]
+pub enum Error {
+    Storage(crate::Error),
+    EngineState(EngineStateError),
+    Engine(EngineErrorKind),
+    /// The arguments of the call cannot be serialized.
+    Serialize(std::io::Error),
+}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        Self::Storage(e)
+    }
+}
+
+impl From<EngineStateError> for Error {
+    fn from(e: EngineStateError) -> Self {
+        Self::EngineState(e)
+    }
+}
+
+impl From<EngineErrorKind> for Error {
+    fn from(e: EngineErrorKind) -> Self {
+        Self::Engine(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
+
+/// Results at one block height, by hash of the Borsh-encoded call arguments, from the least
+/// to the most recently used.
+#[derive(Debug, Default)]
+struct HeightResults {
+    results: HashMap<H256, TransactionStatus>,
+    order: VecDeque<H256>,
+}
+
+impl HeightResults {
+    fn touch(&mut self, key: &H256) {
+        if let Some(index) = self.order.iter().position(|k| k == key) {
+            self.order.remove(index);
+        }
+        self.order.push_back(*key);
+    }
+}
+
+/// Statuses of view calls, at most `capacity` of them for each of the last
+/// `MAX_CACHED_HEIGHTS` block heights queried. The least recently used result of a height is
+/// dropped to make room for a new one.
+#[derive(Debug)]
+pub struct ViewCache {
+    capacity: usize,
+    heights: HashMap<u64, HeightResults>,
+    /// Cached heights, from the least to the most recently queried.
+    height_order: VecDeque<u64>,
+    counters: CacheCounters,
+}
+
+impl ViewCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heights: HashMap::new(),
+            height_order: VecDeque::new(),
+            counters: CacheCounters::default(),
+        }
+    }
+
+    pub fn counters(&self) -> CacheCounters {
+        self.counters
+    }
+
+    /// Number of results cached, over all heights.
+    pub fn len(&self) -> usize {
+        self.heights
+            .values()
+            .map(|height| height.results.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn touch_height(&mut self, block_height: u64) {
+        if let Some(index) = self.height_order.iter().position(|h| *h == block_height) {
+            self.height_order.remove(index);
+        }
+        self.height_order.push_back(block_height);
+    }
+
+    fn get(&mut self, block_height: u64, key: &H256) -> Option<TransactionStatus> {
+        let status = self.heights.get_mut(&block_height).and_then(|height| {
+            let status = height.results.get(key).cloned();
+            if status.is_some() {
+                height.touch(key);
+            }
+            status
+        });
+        match status {
+            Some(_) => {
+                self.counters.hits += 1;
+                self.touch_height(block_height);
+            }
+            None => self.counters.misses += 1,
+        }
+        status
+    }
+
+    fn insert(&mut self, block_height: u64, key: H256, status: &TransactionStatus) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.heights.contains_key(&block_height) && self.heights.len() >= MAX_CACHED_HEIGHTS {
+            if let Some(oldest) = self.height_order.pop_front() {
+                self.heights.remove(&oldest);
+            }
+        }
+        self.touch_height(block_height);
+        let capacity = self.capacity;
+        let height = self.heights.entry(block_height).or_default();
+        if !height.results.contains_key(&key) && height.results.len() >= capacity {
+            if let Some(oldest) = height.order.pop_front() {
+                height.results.remove(&oldest);
+            }
+        }
+        height.results.insert(key, status.clone());
+        height.touch(&key);
+    }
+
+    /// Forgets the results at the given height and above.
+    fn invalidate_from(&mut self, block_height: u64) {
+        self.heights.retain(|height, _| *height < block_height);
+        self.height_order.retain(|height| *height < block_height);
+    }
+}
+
+impl Storage {
+    /// Enables memoizing the results of `view` with room for `capacity` results at each block
+    /// height, or disables it with `None`.
+    pub fn set_view_cache_capacity(&mut self, capacity: Option<usize>) {
+        self.view_cache = capacity.map(ViewCache::new);
+    }
+
+    pub fn view_cache(&self) -> Option<&ViewCache> {
+        self.view_cache.as_ref()
+    }
+
+    /// Runs a view call on the state of the engine at the end of the block with the given hash.
+    pub fn view(
+        &mut self,
+        block_hash: H256,
+        args: ViewCallArgs,
+    ) -> Result<TransactionStatus, Error> {
+        let block_height = self.get_block_height_by_hash(block_hash)?;
+        let key = aurora_engine_sdk::keccak(&args.try_to_vec()?);
+        if let Some(status) = self
+            .view_cache
+            .as_mut()
+            .and_then(|cache| cache.get(block_height, &key))
+        {
+            return Ok(status);
+        }
+
+        let block_metadata = self.get_block_metadata(block_hash)?;
+        let current_account_id: AccountId = crate::sync::AURORA_ACCOUNT_ID.parse().unwrap();
+        let env = env::Fixed {
+            signer_account_id: current_account_id.clone(),
+            current_account_id: current_account_id.clone(),
+            predecessor_account_id: current_account_id.clone(),
+            block_height,
+            block_timestamp: block_metadata.timestamp,
+            attached_deposit: 0,
+            random_seed: block_metadata.random_seed,
+            prepaid_gas: DEFAULT_PREPAID_GAS,
+        };
+        let status = {
+            // After all the transactions of the block.
+            let io = self.access_engine_storage_at_position(block_height, u16::MAX, &[]);
+            let engine =
+                engine::Engine::new(Address::from(args.sender), current_account_id, io, &env)?;
+            engine.view_with_args(args)?
+        };
+
+        if let Some(cache) = self.view_cache.as_mut() {
+            cache.insert(block_height, key, &status);
+        }
+        Ok(status)
+    }
+
+    /// Forgets the memoized view results at the given height and above, whose state may have
+    /// just changed.
+    pub(crate) fn invalidate_view_cache(&mut self, block_height: u64) {
+        if let Some(cache) = self.view_cache.as_mut() {
+            cache.invalidate_from(block_height);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Serialization of traces in the format of the geth struct logger, the default tracer of
+//! `debug_traceTransaction`, so that a relayer can serve the traces of the standalone engine
+//! as is:
+//!
+//! ```json
+//! {
+//!   "gas": 25523,
+//!   "failed": false,
+//!   "returnValue": "",
+//!   "structLogs": [
+//!     {
+//!       "pc": 0,
+//!       "op": "PUSH1",
+//!       "gas": 67960,
+//!       "gasCost": 3,
+//!       "depth": 1,
+//!       "stack": [],
+//!       "memory": [],
+//!       "storage": {}
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! As in geth, stack items are `0x`-prefixed hex numbers, while words of memory and storage
+//! are 32 bytes in hex without prefix.
+//!
+//! Call frames are serialized in the format of the geth `callTracer`, with numbers and bytes
+//! in `0x`-prefixed hex:
+//!
+//! ```json
+//! {
+//!   "type": "CALL",
+//!   "from": "0x...",
+//!   "to": "0x...",
+//!   "value": "0x0",
+//!   "gas": "0x15f90",
+//!   "gasUsed": "0x5d0b",
+//!   "input": "0x",
+//!   "output": "0x...",
+//!   "calls": [...]
+//! }
+//! ```
+
+use crate::types::{CallFrame, TraceLog, TransactionTrace};
+use evm_core::Opcode;
+use serde_json::{json, Map, Value};
+
+pub fn transaction_trace(trace: &TransactionTrace) -> Value {
+    json!({
+        "gas": trace.gas().into_u64(),
+        "failed": trace.failed(),
+        "returnValue": hex::encode(trace.result()),
+        "structLogs": trace.logs().0.iter().map(struct_log).collect::<Vec<_>>(),
+    })
+}
+
+pub fn struct_log(log: &TraceLog) -> Value {
+    let stack: Vec<Value> = log
+        .stack
+        .clone()
+        .into_raw()
+        .iter()
+        .map(|word| Value::String(compact_hex(word)))
+        .collect();
+    let memory: Vec<Value> = log
+        .memory
+        .clone()
+        .into_raw()
+        .iter()
+        .map(|word| Value::String(hex::encode(word)))
+        .collect();
+    let storage: Map<String, Value> = log
+        .storage
+        .clone()
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                hex::encode(key.into_raw()),
+                Value::String(hex::encode(value.into_raw())),
+            )
+        })
+        .collect();
+
+    let mut result = Map::new();
+    result.insert("pc".into(), log.program_counter.into_u32().into());
+    result.insert("op".into(), opcode_name(log.opcode).into());
+    result.insert("gas".into(), log.gas.into_u64().into());
+    result.insert("gasCost".into(), log.gas_cost.into_u64().into());
+    result.insert("depth".into(), log.depth.into_u32().into());
+    if let Some(error) = &log.error {
+        result.insert("error".into(), error.clone().into());
+    }
+    result.insert("stack".into(), Value::Array(stack));
+    result.insert("memory".into(), Value::Array(memory));
+    result.insert("storage".into(), Value::Object(storage));
+    Value::Object(result)
+}
+
+pub fn call_frame(frame: &CallFrame) -> Value {
+    let mut result = Map::new();
+    result.insert("type".into(), frame.call_type.as_str().into());
+    result.insert("from".into(), prefixed_hex(frame.from.as_bytes()).into());
+    result.insert("to".into(), prefixed_hex(frame.to.as_bytes()).into());
+    if let Some(value) = frame.value {
+        result.insert("value".into(), format!("{:#x}", value).into());
+    }
+    result.insert("gas".into(), format!("{:#x}", frame.gas.into_u64()).into());
+    result.insert(
+        "gasUsed".into(),
+        format!("{:#x}", frame.gas_used.into_u64()).into(),
+    );
+    result.insert("input".into(), prefixed_hex(&frame.input).into());
+    if !frame.output.is_empty() {
+        result.insert("output".into(), prefixed_hex(&frame.output).into());
+    }
+    if let Some(error) = &frame.error {
+        result.insert("error".into(), error.clone().into());
+    }
+    if !frame.calls.is_empty() {
+        let calls = frame.calls.iter().map(call_frame).collect();
+        result.insert("calls".into(), Value::Array(calls));
+    }
+    Value::Object(result)
+}
+
+fn prefixed_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// The word as a `0x`-prefixed hex number, without leading zeros.
+fn compact_hex(word: &[u8; 32]) -> String {
+    let digits = hex::encode(word);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        "0x0".into()
+    } else {
+        format!("0x{}", digits)
+    }
+}
+
+/// Name of the opcode, as printed by geth. Undefined opcodes are named after their value
+/// (e.g. `opcode 0xc (not defined)`).
+pub fn opcode_name(opcode: Opcode) -> String {
+    let name = match opcode.as_u8() {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "SHA3",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "DIFFICULTY",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        op @ 0x60..=0x7f => return format!("PUSH{}", op - 0x5f),
+        op @ 0x80..=0x8f => return format!("DUP{}", op - 0x7f),
+        op @ 0x90..=0x9f => return format!("SWAP{}", op - 0x8f),
+        op @ 0xa0..=0xa4 => return format!("LOG{}", op - 0xa0),
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        op => return format!("opcode {:#x} (not defined)", op),
+    };
+    name.into()
+}
@@ -1,7 +1,8 @@
+pub mod geth;
 pub mod sputnik;
 pub mod types;
 
 pub use types::{
-    Depth, LogMemory, LogStack, LogStorage, LogStorageKey, LogStorageValue, Logs, ProgramCounter,
-    StepTransactionTrace, TraceLog, TransactionTrace,
+    CallFrame, CallType, Depth, LogMemory, LogStack, LogStorage, LogStorageKey, LogStorageValue,
+    Logs, ProgramCounter, StepTransactionTrace, TraceLog, TransactionTrace,
 };
@@ -1,11 +1,14 @@
+use aurora_engine::parameters::SubmitResult;
 use aurora_engine_types::types::EthGas;
-use evm::{Capture, Opcode};
+use aurora_engine_types::{H160, U256};
+use evm::{Capture, CreateScheme, ExitError, ExitReason, Opcode};
 use std::cell::RefCell;
 use std::ptr::NonNull;
 use std::rc::Rc;
 
 use crate::types::{
-    LogStorageKey, LogStorageValue, Logs, ProgramCounter, TraceLog, TransactionTrace,
+    CallFrame, CallType, LogStorageKey, LogStorageValue, Logs, ProgramCounter, TraceLog,
+    TransactionTrace,
 };
 
 /// Capture all events from SputnikVM emitted from within the given closure using the given listener.
@@ -44,18 +47,27 @@ impl TransactionTraceBuilder {
     }
 }
 
+/// Gas left in the current call frame when the snapshot was taken, that is before the cost of
+/// the step is recorded.
+fn remaining_gas(snapshot: &evm_gasometer::tracing::Snapshot) -> EthGas {
+    EthGas::new(snapshot.gas_limit - snapshot.used_gas - snapshot.memory_gas)
+}
+
 impl evm_gasometer::tracing::EventListener for TransactionTraceBuilder {
     fn event(&mut self, event: evm_gasometer::tracing::Event) {
         use evm_gasometer::tracing::Event;
         match event {
-            Event::RecordCost { cost, snapshot: _ } => {
+            Event::RecordCost { cost, snapshot } => {
                 self.current.gas_cost = EthGas::new(cost);
+                if let Some(snapshot) = snapshot {
+                    self.current.gas = remaining_gas(&snapshot);
+                }
             }
             Event::RecordDynamicCost {
                 gas_cost,
                 memory_gas,
                 gas_refund: _,
-                snapshot: _,
+                snapshot,
             } => {
                 // In SputnikVM memory gas is cumulative (ie this event always shows the total) gas
                 // spent on memory up to this point. But geth traces simply show how much gas each step
@@ -68,6 +80,9 @@ impl evm_gasometer::tracing::EventListener for TransactionTraceBuilder {
                 };
                 self.current_memory_gas = memory_gas;
                 self.current.gas_cost = EthGas::new(gas_cost + memory_cost_diff);
+                if let Some(snapshot) = snapshot {
+                    self.current.gas = remaining_gas(&snapshot);
+                }
             }
             Event::RecordRefund {
                 refund: _,
@@ -198,6 +213,269 @@ impl evm::tracing::EventListener for TransactionTraceBuilder {
     }
 }
 
+/// Gas added to the gas of a call which transfers value.
+const CALL_STIPEND: u64 = 2300;
+
+/// Builds the tree of the call frames of a transaction, like the `callTracer` of geth.
+///
+/// A frame starts with the `Call` (or `Create`) event of SputnikVM, and its gas is the gas its
+/// caller records right after. A frame running code ends with the `Exit` of its last step, and
+/// the gas it left is then given back to its caller (unless it failed, in which case all its gas
+/// is used). A frame which runs no code (a precompile, or a call failing before its first step)
+/// ends when the gas it left is given back, or when its caller takes a new step. The output of
+/// precompiles is not part of the events, so their frames have an empty output.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    /// Frames being executed, the outermost first.
+    stack: Vec<OpenFrame>,
+    /// Frame which exited, until the gas it left is given back to its caller.
+    exited: Option<CallFrame>,
+    /// Frame of the transaction, once it exited.
+    root: Option<CallFrame>,
+    /// Gas limit of the transaction.
+    gas_limit: u64,
+}
+
+#[derive(Debug)]
+struct OpenFrame {
+    frame: CallFrame,
+    /// The gas of the frame has been recorded by its caller.
+    gas_known: bool,
+    /// The frame executed its first step.
+    started: bool,
+}
+
+impl CallTracer {
+    /// The frame of the transaction, with the gas limit and gas used of the transaction.
+    pub fn finish(mut self, result: &SubmitResult) -> Option<CallFrame> {
+        self.settle();
+        while !self.stack.is_empty() {
+            self.exit(None, Vec::new());
+            self.settle();
+        }
+        let gas_limit = self.gas_limit;
+        self.root.map(|mut root| {
+            root.gas = EthGas::new(gas_limit);
+            root.gas_used = EthGas::new(result.gas_used);
+            root
+        })
+    }
+
+    fn enter(&mut self, frame: CallFrame) {
+        self.settle();
+        self.stack.push(OpenFrame {
+            frame,
+            gas_known: false,
+            started: false,
+        });
+    }
+
+    fn exit(&mut self, error: Option<String>, output: Vec<u8>) {
+        if let Some(OpenFrame { mut frame, .. }) = self.stack.pop() {
+            frame.error = error;
+            frame.output = output;
+            self.exited = Some(frame);
+        }
+    }
+
+    /// Attaches the frame which exited to its caller, with the given gas left.
+    fn give_back(&mut self, gas_left: u64) {
+        if let Some(mut frame) = self.exited.take() {
+            frame.gas_used = EthGas::new(frame.gas.into_u64().saturating_sub(gas_left));
+            match self.stack.last_mut() {
+                Some(caller) => caller.frame.calls.push(frame),
+                None => self.root = Some(frame),
+            }
+        }
+    }
+
+    /// Attaches the frame which exited to its caller, with all its gas used.
+    fn settle(&mut self) {
+        self.give_back(0);
+    }
+}
+
+fn call_frame(call_type: CallType, from: H160, to: H160, value: Option<U256>) -> CallFrame {
+    CallFrame {
+        call_type,
+        from,
+        to,
+        value,
+        gas: EthGas::default(),
+        gas_used: EthGas::default(),
+        input: Vec::new(),
+        output: Vec::new(),
+        error: None,
+        calls: Vec::new(),
+    }
+}
+
+/// The error of an exit, as reported by geth.
+fn exit_error(reason: &ExitReason) -> Option<String> {
+    let error = match reason {
+        ExitReason::Succeed(_) => return None,
+        ExitReason::Revert(_) => "execution reverted".to_string(),
+        ExitReason::Error(ExitError::OutOfGas) => "out of gas".to_string(),
+        ExitReason::Error(ExitError::OutOfFund) => "insufficient balance for transfer".to_string(),
+        ExitReason::Error(ExitError::CallTooDeep) => "max call depth exceeded".to_string(),
+        ExitReason::Error(ExitError::StackUnderflow) => "stack underflow".to_string(),
+        ExitReason::Error(ExitError::StackOverflow) => "stack overflow".to_string(),
+        ExitReason::Error(ExitError::InvalidJump) => "invalid jump destination".to_string(),
+        ExitReason::Error(ExitError::InvalidRange) => "return data out of bounds".to_string(),
+        ExitReason::Error(ExitError::DesignatedInvalid) => "invalid opcode: INVALID".to_string(),
+        ExitReason::Error(ExitError::CreateCollision) => "contract address collision".to_string(),
+        ExitReason::Error(ExitError::CreateContractLimit) => "max code size exceeded".to_string(),
+        ExitReason::Error(ExitError::Other(message)) => message.to_string(),
+        ExitReason::Error(e) => format!("{:?}", e),
+        ExitReason::Fatal(e) => format!("{:?}", e),
+    };
+    Some(error)
+}
+
+impl evm_gasometer::tracing::EventListener for CallTracer {
+    fn event(&mut self, event: evm_gasometer::tracing::Event) {
+        use evm_gasometer::tracing::Event;
+        match event {
+            Event::RecordCost { cost, .. } => {
+                // The caller records the gas of a frame before entering it.
+                let depth = self.stack.len();
+                if let Some(open) = self.stack.last_mut() {
+                    if !open.gas_known && !open.started {
+                        let stipend = match open.frame.call_type {
+                            CallType::Call | CallType::CallCode
+                                if depth > 1
+                                    && open.frame.value.unwrap_or_default() != U256::zero() =>
+                            {
+                                CALL_STIPEND
+                            }
+                            _ => 0,
+                        };
+                        open.frame.gas = EthGas::new(cost + stipend);
+                        open.gas_known = true;
+                    }
+                }
+            }
+            Event::RecordStipend { stipend, .. } => {
+                if self.exited.is_some() {
+                    self.give_back(stipend);
+                } else if matches!(self.stack.last(), Some(open) if open.gas_known && !open.started)
+                {
+                    // A frame which ran no code.
+                    self.exit(None, Vec::new());
+                    self.give_back(stipend);
+                }
+            }
+            Event::RecordDynamicCost { .. } => (),
+            Event::RecordRefund { .. } => (),
+            Event::RecordTransaction { .. } => (),
+        }
+    }
+}
+
+impl evm_runtime::tracing::EventListener for CallTracer {
+    fn event(&mut self, event: evm_runtime::tracing::Event) {
+        use evm_runtime::tracing::Event;
+        match event {
+            Event::Step { position, .. } => {
+                self.settle();
+                if matches!(self.stack.last(), Some(open) if !open.started) {
+                    if let Ok(0) = position {
+                        if let Some(open) = self.stack.last_mut() {
+                            open.started = true;
+                        }
+                    } else {
+                        // The caller took a new step: the frame ran no code and failed.
+                        self.exit(None, Vec::new());
+                        self.settle();
+                    }
+                }
+            }
+            Event::StepResult {
+                result: Err(Capture::Exit(reason)),
+                return_value,
+            } => {
+                self.settle();
+                self.exit(exit_error(reason), return_value.to_vec());
+            }
+            Event::StepResult { .. } => (),
+            Event::SLoad { .. } => (),
+            Event::SStore { .. } => (),
+        }
+    }
+}
+
+impl evm::tracing::EventListener for CallTracer {
+    fn event(&mut self, event: evm::tracing::Event) {
+        use evm::tracing::Event;
+        match event {
+            Event::Call {
+                code_address,
+                transfer,
+                input,
+                is_static,
+                context,
+                ..
+            } => {
+                let (call_type, from, value) = if is_static {
+                    (CallType::StaticCall, context.caller, None)
+                } else if context.address == code_address {
+                    let value = transfer.as_ref().map(|t| t.value).unwrap_or_default();
+                    (CallType::Call, context.caller, Some(value))
+                } else if let Some(transfer) = transfer {
+                    (CallType::CallCode, context.address, Some(transfer.value))
+                } else {
+                    (CallType::DelegateCall, context.address, None)
+                };
+                let mut frame = call_frame(call_type, from, code_address, value);
+                frame.input = input.to_vec();
+                self.enter(frame);
+            }
+            Event::Create {
+                caller,
+                address,
+                scheme,
+                value,
+                init_code,
+                ..
+            } => {
+                let call_type = match scheme {
+                    CreateScheme::Create2 { .. } => CallType::Create2,
+                    _ => CallType::Create,
+                };
+                let mut frame = call_frame(call_type, caller, address, Some(value));
+                frame.input = init_code.to_vec();
+                self.enter(frame);
+            }
+            Event::Suicide {
+                address,
+                target,
+                balance,
+            } => {
+                self.settle();
+                let frame = call_frame(CallType::SelfDestruct, address, target, Some(balance));
+                if let Some(open) = self.stack.last_mut() {
+                    open.frame.calls.push(frame);
+                }
+            }
+            Event::Exit {
+                reason,
+                return_value,
+            } => {
+                // The transaction ended: frames which are still open failed before their
+                // first step.
+                self.settle();
+                while !self.stack.is_empty() {
+                    self.exit(exit_error(reason), return_value.to_vec());
+                    self.settle();
+                }
+            }
+            Event::TransactCall { gas_limit, .. } => self.gas_limit = gas_limit,
+            Event::TransactCreate { gas_limit, .. } => self.gas_limit = gas_limit,
+            Event::TransactCreate2 { gas_limit, .. } => self.gas_limit = gas_limit,
+        }
+    }
+}
+
 /// This structure is intentionally private to this module as it is memory unsafe (contains a raw pointer).
 /// Its purpose here is to allow a single event handling object to be used as the listener for
 /// all SputnikVM events. It is needed because the listener must be passed as an object with a `'static`
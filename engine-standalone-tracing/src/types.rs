@@ -1,5 +1,5 @@
 use aurora_engine_types::types::EthGas;
-use aurora_engine_types::{BTreeMap, H256};
+use aurora_engine_types::{Address, BTreeMap, H256, U256};
 use evm_core::Opcode;
 use std::ops::Index;
 
@@ -151,7 +151,7 @@ pub struct TraceLog {
     pub depth: Depth,
     /// Any errors that may have occurred during execution.
     pub error: Option<String>,
-    /// Gas used to execute the transaction.
+    /// Gas remaining before the step.
     pub gas: EthGas,
     /// Gas cost for the transaction.
     pub gas_cost: EthGas,
@@ -256,6 +256,11 @@ impl TransactionTrace {
         self.return_value.as_slice()
     }
 
+    /// Returns true if the transaction exited with an error.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
     /// Returns a reference to the logs.
     pub fn logs(&self) -> &Logs {
         &self.struct_logs
@@ -297,3 +302,54 @@ impl StepTransactionTrace {
         }
     }
 }
+
+/// The kind of a call frame, as named by geth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallType {
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Create2,
+    SelfDestruct,
+}
+
+impl CallType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Call => "CALL",
+            Self::StaticCall => "STATICCALL",
+            Self::DelegateCall => "DELEGATECALL",
+            Self::CallCode => "CALLCODE",
+            Self::Create => "CREATE",
+            Self::Create2 => "CREATE2",
+            Self::SelfDestruct => "SELFDESTRUCT",
+        }
+    }
+}
+
+/// A call frame of the execution of a transaction on the EVM, with the frames of the calls
+/// it made (the internal transactions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallFrame {
+    pub call_type: CallType,
+    /// The caller, or the contract which destructed itself.
+    pub from: Address,
+    /// The callee, the created contract, or the beneficiary of a self-destruct.
+    pub to: Address,
+    /// The value transferred. `DELEGATECALL` and `STATICCALL` do not transfer any value.
+    pub value: Option<U256>,
+    /// Gas available to the frame.
+    pub gas: EthGas,
+    /// Gas used by the frame, including the gas used by the frames it called.
+    pub gas_used: EthGas,
+    /// Call data, or init code of a created contract.
+    pub input: Vec<u8>,
+    /// Data returned, or code of a created contract.
+    pub output: Vec<u8>,
+    /// Any errors that ended the frame.
+    pub error: Option<String>,
+    /// The frames of the calls made, in order.
+    pub calls: Vec<CallFrame>,
+}
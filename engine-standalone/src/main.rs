@@ -1,5 +1,14 @@
 mod ffi;
 
 fn main() {
+    init_tracing();
     println!("Hello, World!");
 }
+
+/// Logs are filtered with the `RUST_LOG` environment variable, e.g.
+/// `RUST_LOG=engine_standalone_storage::sync=debug`. Defaults to `info`.
+fn init_tracing() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
@@ -0,0 +1,635 @@
+//! Differential testing of the engine against another EVM implementation.
+//!
+//! A `Case` (pre-state, block environment and one transaction) is executed by the engine,
+//! through the standalone runner, and by a `ReferenceEvm`. The status, gas used, logs and
+//! post-state of both executions are compared and every mismatch is reported as a
+//! `Divergence`. `random_case` generates cases from a seed, so a divergence found by a
+//! randomized run can be replayed from its seed alone.
+//!
+//! `SputnikReference` executes cases with the plain SputnikVM executor over an in-memory
+//! backend, which checks everything the engine adds around the interpreter (state storage,
+//! nonces, balances, gas accounting, logs). Other implementations, e.g. revm or geth through
+//! FFI, plug in by implementing `ReferenceEvm`.
+//!
+//! Generated programs stay within what both sides define identically: the gas price is zero
+//! (the engine pays fees to the relayer, not the coinbase), accounts called or inspected are
+//! the accounts of the case (never precompiles) and `BLOCKHASH` is not used (the engine
+//! derives block hashes from the NEAR block height and account id).
+
+use crate::prelude::transaction::legacy::TransactionLegacy;
+use crate::prelude::{Address, Wei, H256, U256};
+use crate::test_utils::{self, standalone::StandaloneRunner};
+use aurora_engine::parameters::TransactionStatus;
+use aurora_engine_precompiles::blake2::Blake2F;
+use aurora_engine_precompiles::{BlockContext, PrecompileConstructorContext, Precompiles};
+use evm::backend::{ApplyBackend, MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, CreateScheme, ExitReason};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use secp256k1::SecretKey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+/// Storage slots (and memory offsets) used by generated programs are below this bound, so
+/// comparing these slots of every account compares all the storage a case can write.
+const SMALL_LIMIT: u64 = 64;
+const CONTRACTS_PER_CASE: usize = 3;
+const OPS_PER_PROGRAM: usize = 24;
+const CASE_GAS_LIMIT: u64 = 1_000_000;
+/// Coinbase reported by the engine.
+const ENGINE_COINBASE: [u8; 20] = [
+    0x44, 0x44, 0x58, 0x84, 0x43, 0xC3, 0xa9, 0x12, 0x88, 0xc5, 0x00, 0x24, 0x83, 0x44, 0x9A, 0xba,
+    0x10, 0x54, 0x19, 0x2b,
+];
+
+#[derive(Debug, Clone)]
+pub struct CaseAccount {
+    pub address: Address,
+    pub balance: Wei,
+    pub nonce: U256,
+    pub code: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockEnv {
+    pub chain_id: u64,
+    pub number: u64,
+    /// Seconds.
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub seed: u64,
+    pub accounts: Vec<CaseAccount>,
+    /// Empty account which programs may send value to or self-destruct to.
+    pub empty_account: Address,
+    pub block: BlockEnv,
+    pub sender: SecretKey,
+    pub transaction: TransactionLegacy,
+}
+
+impl Case {
+    pub fn sender_address(&self) -> Address {
+        test_utils::address_from_secret_key(&self.sender)
+    }
+}
+
+/// How a transaction ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Succeed(Vec<u8>),
+    Revert(Vec<u8>),
+    /// Exceptional halt of the EVM, by name of the error (e.g. `OutOfGas`).
+    Halt(String),
+    /// The transaction was not executed (or its result was not committed).
+    Rejected(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: U256,
+    pub nonce: U256,
+    pub code: Vec<u8>,
+    /// Non-zero storage slots.
+    pub storage: BTreeMap<H256, H256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub outcome: Outcome,
+    pub gas_used: u64,
+    pub logs: Vec<ExecutionLog>,
+    /// Post-state of the non-empty accounts. The engine side is only asked for the accounts
+    /// known to either execution, see `compare`.
+    pub state: BTreeMap<Address, AccountState>,
+}
+
+/// An EVM implementation the engine is compared with.
+pub trait ReferenceEvm {
+    fn name(&self) -> &str;
+
+    fn execute(&mut self, case: &Case) -> Execution;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    Outcome {
+        engine: Outcome,
+        reference: Outcome,
+    },
+    GasUsed {
+        engine: u64,
+        reference: u64,
+    },
+    Logs {
+        engine: Vec<ExecutionLog>,
+        reference: Vec<ExecutionLog>,
+    },
+    Account {
+        address: Address,
+        engine: AccountState,
+        reference: AccountState,
+    },
+}
+
+/// Result of a case where the engine and the reference diverged.
+#[derive(Debug)]
+pub struct Report {
+    pub reference: String,
+    pub case: Case,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Runs the cases generated from the given seeds and returns those which diverged.
+pub fn run<R: ReferenceEvm>(
+    reference: &mut R,
+    seeds: impl IntoIterator<Item = u64>,
+) -> Vec<Report> {
+    seeds
+        .into_iter()
+        .filter_map(|seed| {
+            let case = random_case(seed);
+            let divergences = check_case(reference, &case);
+            if divergences.is_empty() {
+                None
+            } else {
+                Some(Report {
+                    reference: reference.name().to_string(),
+                    case,
+                    divergences,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Executes a case with the engine and the reference and compares the results.
+pub fn check_case<R: ReferenceEvm>(reference: &mut R, case: &Case) -> Vec<Divergence> {
+    let expected = reference.execute(case);
+    let mut runner = engine_runner(case);
+    let outcome = execute_in_engine(&mut runner, case);
+    let divergences = compare(&mut runner, case, outcome, expected);
+    runner.close();
+    divergences
+}
+
+/// The standalone engine with the pre-state of the case.
+fn engine_runner(case: &Case) -> StandaloneRunner {
+    let mut runner = StandaloneRunner::default();
+    runner.init_evm_with_chain_id(case.block.chain_id);
+    for account in case.accounts.iter() {
+        let code = if account.code.is_empty() {
+            None
+        } else {
+            Some(account.code.clone())
+        };
+        runner.mint_account(account.address, account.balance, account.nonce, code);
+    }
+    runner
+}
+
+fn execute_in_engine(
+    runner: &mut StandaloneRunner,
+    case: &Case,
+) -> (Outcome, u64, Vec<ExecutionLog>) {
+    // The block height is incremented before the transaction is executed.
+    assert!(runner.env.block_height < case.block.number);
+    runner.env.block_height = case.block.number - 1;
+    runner.env.block_timestamp =
+        aurora_engine_sdk::env::Timestamp::new(case.block.timestamp * 1_000_000_000);
+
+    match runner.submit_transaction(&case.sender, case.transaction.clone()) {
+        Ok(result) => {
+            let outcome = match result.status {
+                TransactionStatus::Succeed(output) => Outcome::Succeed(output),
+                TransactionStatus::Revert(output) => Outcome::Revert(output),
+                other => Outcome::Halt(format!("{:?}", other)),
+            };
+            let logs = result
+                .logs
+                .into_iter()
+                .map(|log| ExecutionLog {
+                    address: Address(log.address),
+                    topics: log.topics.into_iter().map(H256).collect(),
+                    data: log.data,
+                })
+                .collect();
+            (outcome, result.gas_used, logs)
+        }
+        Err(e) => (
+            Outcome::Rejected(format!("{:?}", e.kind)),
+            e.gas_used,
+            Vec::new(),
+        ),
+    }
+}
+
+/// Compares the engine execution with the reference one. The engine state is read for the
+/// accounts of the case and the accounts present in the reference post-state; storage is
+/// compared on the slots set in the reference post-state and on the slots generated programs
+/// can write.
+fn compare(
+    runner: &mut StandaloneRunner,
+    case: &Case,
+    (outcome, gas_used, logs): (Outcome, u64, Vec<ExecutionLog>),
+    expected: Execution,
+) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    if outcome != expected.outcome {
+        divergences.push(Divergence::Outcome {
+            engine: outcome,
+            reference: expected.outcome,
+        });
+    }
+    if gas_used != expected.gas_used {
+        divergences.push(Divergence::GasUsed {
+            engine: gas_used,
+            reference: expected.gas_used,
+        });
+    }
+    if logs != expected.logs {
+        divergences.push(Divergence::Logs {
+            engine: logs,
+            reference: expected.logs,
+        });
+    }
+
+    let addresses: BTreeSet<Address> = case
+        .accounts
+        .iter()
+        .map(|account| account.address)
+        .chain(std::iter::once(case.empty_account))
+        .chain(expected.state.keys().copied())
+        .collect();
+    for address in addresses {
+        let reference = expected.state.get(&address).cloned().unwrap_or_default();
+        let slots: BTreeSet<H256> = (0..SMALL_LIMIT)
+            .map(|slot| H256::from_low_u64_be(slot))
+            .chain(reference.storage.keys().copied())
+            .collect();
+        let storage = slots
+            .into_iter()
+            .filter_map(|slot| {
+                let value = runner.get_storage(&address, &slot);
+                if value.is_zero() {
+                    None
+                } else {
+                    Some((slot, value))
+                }
+            })
+            .collect();
+        let engine = AccountState {
+            balance: runner.get_balance(&address).raw(),
+            nonce: runner.get_nonce(&address),
+            code: runner.get_code(&address),
+            storage,
+        };
+        if engine != reference {
+            divergences.push(Divergence::Account {
+                address,
+                engine,
+                reference,
+            });
+        }
+    }
+    divergences
+}
+
+/// SputnikVM executor (London rules) over an in-memory backend, with the standard Ethereum
+/// precompiles of `aurora-engine-precompiles`.
+#[derive(Default)]
+pub struct SputnikReference;
+
+/// The precompiles of the engine, without the Aurora-specific ones (exits, random seed...).
+fn ethereum_precompiles() -> Precompiles<'static> {
+    let mut precompiles = Precompiles::new_london(PrecompileConstructorContext {
+        current_account_id: "aurora".parse().unwrap(),
+        block: BlockContext::default(),
+        predecessor_account_id: "aurora".parse().unwrap(),
+        promise_results: Vec::new(),
+        state: Rc::new(()),
+    });
+    precompiles
+        .all_precompiles
+        .retain(|address, _| *address <= Blake2F::ADDRESS);
+    precompiles
+}
+
+/// `DIFFICULTY` in the engine at the given height. The standalone runner executes with a
+/// zero random seed.
+fn block_difficulty(block_number: u64, sender: &Address, nonce: U256) -> U256 {
+    if !aurora_engine::era::at(block_number).has_block_randomness() {
+        return U256::zero();
+    }
+    U256::from_big_endian(
+        aurora_engine::engine::compute_block_randomness(H256::zero(), sender, nonce).as_bytes(),
+    )
+}
+
+impl ReferenceEvm for SputnikReference {
+    fn name(&self) -> &str {
+        "sputnikvm"
+    }
+
+    fn execute(&mut self, case: &Case) -> Execution {
+        let sender = case.sender_address();
+        let tx = &case.transaction;
+        let vicinity = MemoryVicinity {
+            gas_price: tx.gas_price,
+            origin: sender,
+            chain_id: U256::from(case.block.chain_id),
+            block_hashes: Vec::new(),
+            block_number: U256::from(case.block.number),
+            block_coinbase: Address(ENGINE_COINBASE),
+            block_timestamp: U256::from(case.block.timestamp),
+            block_difficulty: block_difficulty(case.block.number, &sender, tx.nonce),
+            block_gas_limit: U256::max_value(),
+            block_base_fee_per_gas: U256::zero(),
+        };
+        let state = case
+            .accounts
+            .iter()
+            .map(|account| {
+                (
+                    account.address,
+                    MemoryAccount {
+                        nonce: account.nonce,
+                        balance: account.balance.raw(),
+                        storage: BTreeMap::new(),
+                        code: account.code.clone(),
+                    },
+                )
+            })
+            .collect();
+        let mut backend = MemoryBackend::new(&vicinity, state);
+
+        let config = Config::london();
+        let gas_limit = tx.gas_limit.as_u64();
+        let metadata = StackSubstateMetadata::new(gas_limit, &config);
+        let executor_state = MemoryStackState::new(metadata, &backend);
+        let precompiles = ethereum_precompiles();
+        let mut executor =
+            StackExecutor::new_with_precompiles(executor_state, &config, &precompiles);
+        let (exit_reason, output) = match tx.to {
+            Some(to) => executor.transact_call(
+                sender,
+                to,
+                tx.value.raw(),
+                tx.data.clone(),
+                gas_limit,
+                Vec::new(),
+            ),
+            None => {
+                // The engine returns the address of the contract, whatever the status.
+                let address = executor.create_address(CreateScheme::Legacy { caller: sender });
+                let exit_reason = executor.transact_create(
+                    sender,
+                    tx.value.raw(),
+                    tx.data.clone(),
+                    gas_limit,
+                    Vec::new(),
+                );
+                (exit_reason, address.0.to_vec())
+            }
+        };
+        let gas_used = executor.used_gas();
+        let outcome = match exit_reason {
+            ExitReason::Succeed(_) => Outcome::Succeed(output),
+            ExitReason::Revert(_) => Outcome::Revert(output),
+            ExitReason::Error(e) => Outcome::Halt(format!("{:?}", e)),
+            ExitReason::Fatal(e) => Outcome::Rejected(format!("{:?}", e)),
+        };
+        let (values, logs) = executor.into_state().deconstruct();
+        let logs: Vec<ExecutionLog> = logs
+            .into_iter()
+            .map(|log| ExecutionLog {
+                address: log.address,
+                topics: log.topics,
+                data: log.data,
+            })
+            .collect();
+        backend.apply(values, Vec::new(), true);
+
+        let state = backend
+            .state()
+            .iter()
+            .map(|(address, account)| {
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(key, value)| (*key, *value))
+                    .collect();
+                (
+                    *address,
+                    AccountState {
+                        balance: account.balance,
+                        nonce: account.nonce,
+                        code: account.code.clone(),
+                        storage,
+                    },
+                )
+            })
+            .filter(|(_, account)| *account != AccountState::default())
+            .collect();
+
+        Execution {
+            outcome,
+            gas_used,
+            logs,
+            state,
+        }
+    }
+}
+
+/// Kinds of the stack arguments of generated instructions.
+#[derive(Clone, Copy)]
+enum Arg {
+    /// Offsets, sizes, storage slots and values sent by calls.
+    Small,
+    Word,
+    /// An account of the case.
+    Account,
+    Gas,
+}
+
+use Arg::{Account, Gas, Small, Word};
+
+/// Opcodes of generated programs with their arguments, top of the stack first.
+const INSTRUCTIONS: &[(u8, &[Arg])] = &[
+    (0x01, &[Word, Word]),                                      // ADD
+    (0x02, &[Word, Word]),                                      // MUL
+    (0x03, &[Word, Word]),                                      // SUB
+    (0x04, &[Word, Word]),                                      // DIV
+    (0x05, &[Word, Word]),                                      // SDIV
+    (0x06, &[Word, Word]),                                      // MOD
+    (0x07, &[Word, Word]),                                      // SMOD
+    (0x08, &[Word, Word, Word]),                                // ADDMOD
+    (0x09, &[Word, Word, Word]),                                // MULMOD
+    (0x0a, &[Word, Small]),                                     // EXP
+    (0x0b, &[Small, Word]),                                     // SIGNEXTEND
+    (0x10, &[Word, Word]),                                      // LT
+    (0x11, &[Word, Word]),                                      // GT
+    (0x12, &[Word, Word]),                                      // SLT
+    (0x13, &[Word, Word]),                                      // SGT
+    (0x14, &[Word, Word]),                                      // EQ
+    (0x15, &[Word]),                                            // ISZERO
+    (0x16, &[Word, Word]),                                      // AND
+    (0x17, &[Word, Word]),                                      // OR
+    (0x18, &[Word, Word]),                                      // XOR
+    (0x19, &[Word]),                                            // NOT
+    (0x1a, &[Small, Word]),                                     // BYTE
+    (0x1b, &[Small, Word]),                                     // SHL
+    (0x1c, &[Small, Word]),                                     // SHR
+    (0x1d, &[Small, Word]),                                     // SAR
+    (0x20, &[Small, Small]),                                    // SHA3
+    (0x30, &[]),                                                // ADDRESS
+    (0x31, &[Account]),                                         // BALANCE
+    (0x32, &[]),                                                // ORIGIN
+    (0x33, &[]),                                                // CALLER
+    (0x34, &[]),                                                // CALLVALUE
+    (0x35, &[Small]),                                           // CALLDATALOAD
+    (0x36, &[]),                                                // CALLDATASIZE
+    (0x37, &[Small, Small, Small]),                             // CALLDATACOPY
+    (0x38, &[]),                                                // CODESIZE
+    (0x39, &[Small, Small, Small]),                             // CODECOPY
+    (0x3a, &[]),                                                // GASPRICE
+    (0x3b, &[Account]),                                         // EXTCODESIZE
+    (0x3c, &[Account, Small, Small, Small]),                    // EXTCODECOPY
+    (0x3d, &[]),                                                // RETURNDATASIZE
+    (0x3e, &[Small, Small, Small]),                             // RETURNDATACOPY
+    (0x3f, &[Account]),                                         // EXTCODEHASH
+    (0x41, &[]),                                                // COINBASE
+    (0x42, &[]),                                                // TIMESTAMP
+    (0x43, &[]),                                                // NUMBER
+    (0x44, &[]),                                                // DIFFICULTY
+    (0x45, &[]),                                                // GASLIMIT
+    (0x46, &[]),                                                // CHAINID
+    (0x47, &[]),                                                // SELFBALANCE
+    (0x48, &[]),                                                // BASEFEE
+    (0x50, &[Word]),                                            // POP
+    (0x51, &[Small]),                                           // MLOAD
+    (0x52, &[Small, Word]),                                     // MSTORE
+    (0x53, &[Small, Word]),                                     // MSTORE8
+    (0x54, &[Small]),                                           // SLOAD
+    (0x55, &[Small, Word]),                                     // SSTORE
+    (0x55, &[Small, Small]),       // SSTORE (of small values, to clear slots)
+    (0x58, &[]),                   // PC
+    (0x59, &[]),                   // MSIZE
+    (0x5a, &[]),                   // GAS
+    (0xa0, &[Small, Small]),       // LOG0
+    (0xa1, &[Small, Small, Word]), // LOG1
+    (0xa2, &[Small, Small, Word, Word]), // LOG2
+    (0xf0, &[Small, Small, Small]), // CREATE
+    (0xf1, &[Gas, Account, Small, Small, Small, Small, Small]), // CALL
+    (0xf3, &[Small, Small]),       // RETURN
+    (0xf4, &[Gas, Account, Small, Small, Small, Small]), // DELEGATECALL
+    (0xf5, &[Small, Small, Small, Word]), // CREATE2
+    (0xfa, &[Gas, Account, Small, Small, Small, Small]), // STATICCALL
+    (0xfd, &[Small, Small]),       // REVERT
+    (0xfe, &[]),                   // INVALID
+    (0xff, &[Account]),            // SELFDESTRUCT
+];
+
+fn random_program(rng: &mut StdRng, accounts: &[Address]) -> Vec<u8> {
+    let mut code = Vec::new();
+    for _ in 0..OPS_PER_PROGRAM {
+        let (opcode, args) = INSTRUCTIONS[rng.gen_range(0, INSTRUCTIONS.len())];
+        // Arguments are pushed in reverse so that the first one ends up on top.
+        for arg in args.iter().rev() {
+            match arg {
+                Small => push(&mut code, &[rng.gen_range(0, SMALL_LIMIT) as u8]),
+                Word => {
+                    let word: [u8; 32] = rng.gen();
+                    push(&mut code, &word)
+                }
+                Account => {
+                    let address = accounts[rng.gen_range(0, accounts.len())];
+                    push(&mut code, address.as_bytes())
+                }
+                Gas => push(&mut code, &rng.gen_range(0u32, 200_000).to_be_bytes()),
+            }
+        }
+        code.push(opcode);
+    }
+    // Most programs end with their storage and logs committed.
+    code.push(0x00);
+    code
+}
+
+fn push(code: &mut Vec<u8>, bytes: &[u8]) {
+    // PUSH1 is 0x60, PUSH32 is 0x7f.
+    code.push(0x5f + bytes.len() as u8);
+    code.extend_from_slice(bytes);
+}
+
+/// The case generated from the given seed.
+pub fn random_case(seed: u64) -> Case {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sender = SecretKey::random(&mut rng);
+    let sender_address = test_utils::address_from_secret_key(&sender);
+    let contracts: Vec<Address> = (0..CONTRACTS_PER_CASE)
+        .map(|_| Address(rng.gen()))
+        .collect();
+    let empty_account = Address(rng.gen());
+    let targets: Vec<Address> = contracts
+        .iter()
+        .copied()
+        .chain([sender_address, empty_account].iter().copied())
+        .collect();
+
+    let sender_nonce = U256::from(rng.gen_range(0u64, 8));
+    let mut accounts = vec![CaseAccount {
+        address: sender_address,
+        balance: Wei::new_u64(1_000_000_000_000_000_000),
+        nonce: sender_nonce,
+        code: Vec::new(),
+    }];
+    for address in contracts.iter() {
+        let code = random_program(&mut rng, &targets);
+        accounts.push(CaseAccount {
+            address: *address,
+            balance: Wei::new_u64(rng.gen_range(0, 1_000)),
+            nonce: U256::from(rng.gen_range(0u64, 2)),
+            code,
+        });
+    }
+
+    let (to, data) = if rng.gen_ratio(1, 5) {
+        (None, random_program(&mut rng, &targets))
+    } else {
+        let to = contracts[rng.gen_range(0, contracts.len())];
+        let data_len = rng.gen_range(0, SMALL_LIMIT as usize);
+        (Some(to), (0..data_len).map(|_| rng.gen()).collect())
+    };
+    let transaction = TransactionLegacy {
+        nonce: sender_nonce,
+        gas_price: U256::zero(),
+        gas_limit: U256::from(CASE_GAS_LIMIT),
+        to,
+        value: Wei::new_u64(rng.gen_range(0, 1_000)),
+        data,
+    };
+
+    Case {
+        seed,
+        accounts,
+        empty_account,
+        block: BlockEnv {
+            chain_id: test_utils::AuroraRunner::default().chain_id,
+            number: rng.gen_range(1_000, 1_000_000),
+            timestamp: rng.gen_range(1, 2_000_000_000),
+        },
+        sender,
+        transaction,
+    }
+}
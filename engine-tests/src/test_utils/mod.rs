@@ -13,26 +13,29 @@ use secp256k1::{self, Message, PublicKey, SecretKey};
 
 use crate::prelude::fungible_token::{FungibleToken, FungibleTokenMetadata};
 use crate::prelude::parameters::{
-    InitCallArgs, NewCallArgs, SubmitResult, TransactionStatus, ViewCallArgs,
+    GasEstimate, InitCallArgs, NewCallArgs, SubmitResult, TransactionStatus, ViewCallArgs,
+    ViewTransactionArgs,
 };
 use crate::prelude::transaction::{
     eip_1559::{self, SignedTransaction1559, Transaction1559},
-    eip_2930::{self, SignedTransaction2930, Transaction2930},
+    eip_2930::{self, AccessTuple, SignedTransaction2930, Transaction2930},
     legacy::{LegacyEthSignedTransaction, TransactionLegacy},
+    EthTransactionKind, TransactionType,
 };
 use crate::prelude::{sdk, Address, Wei, H256, U256};
 use crate::test_utils::solidity::{ContractConstructor, DeployedContract};
 
 // TODO(Copied from #84): Make sure that there is only one Signer after both PR are merged.
 
-pub fn origin() -> String {
-    "aurora".to_string()
+pub fn origin() -> AccountId {
+    str_to_account_id("aurora")
 }
 
 pub(crate) const SUBMIT: &str = "submit";
 pub(crate) const CALL: &str = "call";
 pub(crate) const DEPLOY_ERC20: &str = "deploy_erc20_token";
 
+pub(crate) mod differential;
 pub(crate) mod erc20;
 pub(crate) mod exit_precompile;
 pub(crate) mod one_inch;
@@ -344,11 +347,28 @@ impl AuroraRunner {
         account: &SecretKey,
         transaction: TransactionLegacy,
     ) -> Result<(SubmitResult, ExecutionProfile), VMError> {
-        let calling_account_id = "some-account.near";
         let signed_tx = sign_transaction(transaction, Some(self.chain_id), account);
+        self.submit_signed_profiled(EthTransactionKind::Legacy(signed_tx))
+    }
 
-        let (output, maybe_err) =
-            self.call(SUBMIT, calling_account_id, rlp::encode(&signed_tx).to_vec());
+    /// Signs the transaction built by `builder` with the next nonce of `signer` and the chain
+    /// id of the runner, then submits it.
+    pub fn submit_typed_transaction(
+        &mut self,
+        signer: &mut Signer,
+        builder: TransactionBuilder,
+    ) -> Result<SubmitResult, VMError> {
+        let signed_tx = builder.sign(signer, Some(self.chain_id));
+        self.submit_signed_profiled(signed_tx)
+            .map(|(result, _)| result)
+    }
+
+    fn submit_signed_profiled(
+        &mut self,
+        signed_tx: EthTransactionKind,
+    ) -> Result<(SubmitResult, ExecutionProfile), VMError> {
+        let calling_account_id = "some-account.near";
+        let (output, maybe_err) = self.call(SUBMIT, calling_account_id, signed_tx.into());
 
         if let Some(err) = maybe_err {
             Err(err)
@@ -392,6 +412,24 @@ impl AuroraRunner {
         )
     }
 
+    pub fn view_call_with_gas_used(&self, args: ViewCallArgs) -> Result<SubmitResult, VMError> {
+        let input = args.try_to_vec().unwrap();
+        let (outcome, maybe_error) = self.one_shot().call("view_with_gas_used", "viewer", input);
+        Ok(SubmitResult::try_from_slice(&Self::bytes_from_outcome(outcome, maybe_error)?).unwrap())
+    }
+
+    pub fn view_transaction(&self, args: ViewTransactionArgs) -> Result<SubmitResult, VMError> {
+        let input = args.try_to_vec().unwrap();
+        let (outcome, maybe_error) = self.one_shot().call("view_transaction", "viewer", input);
+        Ok(SubmitResult::try_from_slice(&Self::bytes_from_outcome(outcome, maybe_error)?).unwrap())
+    }
+
+    pub fn estimate_gas(&self, args: ViewCallArgs) -> Result<GasEstimate, VMError> {
+        let input = args.try_to_vec().unwrap();
+        let (outcome, maybe_error) = self.one_shot().call("estimate_gas", "viewer", input);
+        Ok(GasEstimate::try_from_slice(&Self::bytes_from_outcome(outcome, maybe_error)?).unwrap())
+    }
+
     pub fn profiled_view_call(
         &self,
         args: ViewCallArgs,
@@ -413,13 +451,22 @@ impl AuroraRunner {
         self.u256_getter_method_call("get_nonce", address)
     }
 
+    pub fn get_stranded_eth(&self) -> Wei {
+        let (outcome, maybe_error) = self
+            .one_shot()
+            .call("get_stranded_eth", "getter", Vec::new());
+        assert!(maybe_error.is_none());
+        let bytes = outcome.unwrap().return_data.as_value().unwrap();
+        Wei::new(U256::from_big_endian(&bytes))
+    }
+
     pub fn get_code(&self, address: Address) -> Vec<u8> {
         self.getter_method_call("get_code", address)
     }
 
     pub fn get_storage(&self, address: Address, key: H256) -> H256 {
         let input = aurora_engine::parameters::GetStorageAtArgs {
-            address: address.0,
+            address: address.into(),
             key: key.0,
         };
         let (outcome, maybe_error) =
@@ -653,8 +700,8 @@ pub(crate) fn create_eth_transaction(
 
 pub(crate) fn as_view_call(tx: TransactionLegacy, sender: Address) -> ViewCallArgs {
     ViewCallArgs {
-        sender: sender.0,
-        address: tx.to.unwrap().0,
+        sender: sender.into(),
+        address: tx.to.unwrap().into(),
         amount: tx.value.to_bytes(),
         input: tx.data,
     }
@@ -729,6 +776,121 @@ pub(crate) fn sign_eip_1559_transaction(
     }
 }
 
+/// Builds transactions of any type, signed from a `Signer`.
+///
+/// The fields which do not apply to the type are ignored: the gas price is the max fee per gas
+/// of an EIP-1559 transaction, and legacy transactions have no access list.
+#[derive(Debug, Clone)]
+pub(crate) struct TransactionBuilder {
+    pub tx_type: TransactionType,
+    pub to: Option<Address>,
+    pub value: Wei,
+    pub data: Vec<u8>,
+    pub gas_limit: U256,
+    pub gas_price: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub access_list: Vec<AccessTuple>,
+}
+
+impl TransactionBuilder {
+    /// Transaction deploying a contract, which creation code is set with `data`.
+    pub fn new(tx_type: TransactionType) -> Self {
+        Self {
+            tx_type,
+            to: None,
+            value: Wei::zero(),
+            data: Vec::new(),
+            gas_limit: u64::MAX.into(),
+            gas_price: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            access_list: Vec::new(),
+        }
+    }
+
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    pub fn value(mut self, value: Wei) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: U256) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    pub fn max_priority_fee_per_gas(mut self, max_priority_fee_per_gas: U256) -> Self {
+        self.max_priority_fee_per_gas = max_priority_fee_per_gas;
+        self
+    }
+
+    pub fn access_list(mut self, access_list: Vec<AccessTuple>) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    /// Signs the transaction with the next nonce of `signer`.
+    ///
+    /// A legacy transaction without a chain id is signed without the replay protection of
+    /// EIP-155. Typed transactions always commit to a chain id, so one must be given.
+    pub fn sign(self, signer: &mut Signer, chain_id: Option<u64>) -> EthTransactionKind {
+        let nonce = signer.use_nonce().into();
+        match self.tx_type {
+            TransactionType::Legacy => {
+                let tx = TransactionLegacy {
+                    nonce,
+                    gas_price: self.gas_price,
+                    gas_limit: self.gas_limit,
+                    to: self.to,
+                    value: self.value,
+                    data: self.data,
+                };
+                EthTransactionKind::Legacy(sign_transaction(tx, chain_id, &signer.secret_key))
+            }
+            TransactionType::Eip2930 => {
+                let tx = Transaction2930 {
+                    chain_id: chain_id.expect("Typed transactions require a chain id"),
+                    nonce,
+                    gas_price: self.gas_price,
+                    gas_limit: self.gas_limit,
+                    to: self.to,
+                    value: self.value,
+                    data: self.data,
+                    access_list: self.access_list,
+                };
+                EthTransactionKind::Eip2930(sign_access_list_transaction(tx, &signer.secret_key))
+            }
+            TransactionType::Eip1559 => {
+                let tx = Transaction1559 {
+                    chain_id: chain_id.expect("Typed transactions require a chain id"),
+                    nonce,
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+                    max_fee_per_gas: self.gas_price,
+                    gas_limit: self.gas_limit,
+                    to: self.to,
+                    value: self.value,
+                    data: self.data,
+                    access_list: self.access_list,
+                };
+                EthTransactionKind::Eip1559(sign_eip_1559_transaction(tx, &signer.secret_key))
+            }
+        }
+    }
+}
+
 pub(crate) fn address_from_secret_key(sk: &SecretKey) -> Address {
     let pk = PublicKey::from_secret_key(sk);
     let hash = sdk::keccak(&pk.serialize()[1..]);
@@ -59,6 +59,7 @@ pub fn init_evm<I: IO + Copy, E: Env>(mut io: I, env: &E, chain_id: u64) {
     };
 
     engine::set_state(&mut io, new_args.into());
+    aurora_engine::system_contracts::install_genesis(&mut io).unwrap();
 
     let connector_args = InitCallArgs {
         prover_account: test_utils::str_to_account_id("prover.near"),
@@ -83,21 +84,14 @@ pub fn mint_evm_account<I: IO + Copy, E: Env>(
     mut io: I,
     env: &E,
 ) {
-    use evm::backend::ApplyBackend;
-
     let aurora_account_id = env.current_account_id();
-    let mut engine = engine::Engine::new(address, aurora_account_id.clone(), io, env).unwrap();
-    let state_change = evm::backend::Apply::Modify {
-        address,
-        basic: evm::backend::Basic {
-            balance: balance.raw(),
-            nonce,
-        },
-        code,
-        storage: std::iter::empty(),
-        reset_storage: false,
-    };
-    engine.apply(std::iter::once(state_change), std::iter::empty(), false);
+    // Written directly rather than through `Engine::apply`, like the accounts created in the
+    // trie of the wasm runner, which are not EVM state changes (see `state_commitment`).
+    engine::set_nonce(&mut io, &address, &nonce);
+    engine::set_balance(&mut io, &address, &balance);
+    if let Some(code) = code {
+        engine::set_code(&mut io, &address, &code);
+    }
 
     let deposit_args = FinishDepositCallArgs {
         new_owner_id: aurora_account_id.clone(),
@@ -2,7 +2,7 @@ use aurora_engine_sdk::promise::PromiseHandler;
 use aurora_engine_sdk::promise::PromiseId;
 use aurora_engine_types::parameters::{PromiseBatchAction, PromiseCreateArgs};
 use aurora_engine_types::types::PromiseResult;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub enum PromiseArgs {
     Create(PromiseCreateArgs),
@@ -14,12 +14,13 @@ pub enum PromiseArgs {
     Batch(PromiseBatchAction),
 }
 
-/// Doesn't actually schedule any promises, only tracks what promises should be scheduled
+/// Doesn't actually schedule any promises, only tracks what promises should be scheduled.
+/// `scheduled_promises` iterates in the order the promises were scheduled.
 #[derive(Default)]
 pub struct PromiseTracker {
     internal_index: u64,
     pub promise_results: Vec<PromiseResult>,
-    pub scheduled_promises: HashMap<u64, PromiseArgs>,
+    pub scheduled_promises: BTreeMap<u64, PromiseArgs>,
     pub returned_promise: Option<PromiseId>,
 }
 
@@ -26,6 +26,13 @@ pub struct Storage {
     kv_store: HashMap<Vec<u8>, Vec<u8>>,
 }
 
+impl Storage {
+    /// Output of the last method which returned one.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct StoragePointer<'a>(pub &'a RwLock<Storage>);
 
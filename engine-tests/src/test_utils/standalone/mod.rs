@@ -1,5 +1,7 @@
 use aurora_engine::engine;
-use aurora_engine::parameters::{CallArgs, DeployErc20TokenArgs, SubmitResult, TransactionStatus};
+use aurora_engine::parameters::{
+    CallArgs, DeployErc20TokenWithTemplateArgs, SubmitResult, TransactionStatus,
+};
 use aurora_engine::transaction::legacy::{LegacyEthSignedTransaction, TransactionLegacy};
 use aurora_engine_sdk::env::{self, Env};
 use aurora_engine_types::types::NearGas;
@@ -175,16 +177,16 @@ impl StandaloneRunner {
             io.finish().commit(storage, &mut self.cumulative_diff);
             Ok(result)
         } else if method_name == test_utils::DEPLOY_ERC20 {
-            let deploy_args = DeployErc20TokenArgs::try_from_slice(&ctx.input).unwrap();
+            let deploy_args = DeployErc20TokenWithTemplateArgs::deserialize(&ctx.input).unwrap();
             let mut handler = mocks::promise::PromiseTracker::default();
             let transaction_hash = aurora_engine_sdk::keccak(&ctx.input);
             let io = Self::get_engine_io(storage, &env, 0, transaction_hash);
-            let address = engine::deploy_erc20_token(deploy_args, io.engine_io, &env, &mut handler)
+            let result = engine::deploy_erc20_token(deploy_args, io.engine_io, &env, &mut handler)
                 .map_err(mocks::unsafe_to_string)
                 .unwrap();
             io.finish().commit(storage, &mut self.cumulative_diff);
             Ok(SubmitResult::new(
-                TransactionStatus::Succeed(address.as_ref().to_vec()),
+                TransactionStatus::Succeed(result.address.as_bytes().to_vec()),
                 0,
                 Vec::new(),
             ))
@@ -218,6 +220,14 @@ impl StandaloneRunner {
         engine::get_code(&io, address)
     }
 
+    pub fn get_storage(&mut self, address: &Address, key: &H256) -> H256 {
+        let io = self
+            .storage
+            .access_engine_storage_at_position(self.env.block_height + 1, 0, &[]);
+        let generation = engine::get_generation(&io, address);
+        engine::get_storage(&io, address, key, generation)
+    }
+
     pub fn close(self) {
         drop(self.storage);
         self.storage_dir.close().unwrap();
@@ -1,4 +1,6 @@
-use crate::prelude::{parameters::SubmitResult, vec, Address, Wei, H256, U256};
+use crate::prelude::{
+    parameters::SubmitResult, transaction::legacy::TransactionLegacy, vec, Address, Wei, H256, U256,
+};
 use crate::test_utils::{origin, AuroraRunner, Signer};
 
 use crate::test_utils;
@@ -6,7 +8,7 @@ use crate::test_utils::exit_precompile::{Tester, TesterConstructor, DEST_ACCOUNT
 
 fn setup_test() -> (AuroraRunner, Signer, [u8; 20], Tester) {
     let mut runner = AuroraRunner::new();
-    let token = runner.deploy_erc20_token(&"tt.testnet".to_string());
+    let token = runner.deploy_erc20_token(&test_utils::str_to_account_id("tt.testnet"));
     let mut signer = test_utils::Signer::random();
     runner.create_address(
         test_utils::address_from_secret_key(&signer.secret_key),
@@ -114,6 +116,35 @@ fn withdraw() {
     }
 }
 
+#[test]
+fn estimate_gas_of_withdraw() {
+    let (runner, signer, _token, tester) = setup_test();
+
+    for is_to_near in [true, false] {
+        let input = tester
+            .contract
+            .abi
+            .function("withdraw")
+            .unwrap()
+            .encode_input(&[ethabi::Token::Bool(is_to_near)])
+            .unwrap();
+        let estimate = runner
+            .estimate_gas(aurora_engine::parameters::ViewCallArgs {
+                sender: test_utils::address_from_secret_key(&signer.secret_key).into(),
+                address: tester.contract.address.into(),
+                amount: [0u8; 32],
+                input,
+            })
+            .unwrap();
+
+        assert!(estimate.result.status.is_ok());
+        assert!(estimate.result.promises.count > 0);
+        // The fees of the receipts come on top of the gas attached to the promises.
+        assert!(estimate.promises_near_gas > estimate.result.promises.attached_gas);
+        assert!(estimate.gas_limit >= estimate.result.gas_used);
+    }
+}
+
 #[test]
 fn withdraw_and_fail() {
     let (mut runner, mut signer, _token, tester) = setup_test();
@@ -228,6 +259,51 @@ fn withdraw_eth() {
     assert_eq!(&expected_event, &exit_events[0].params);
 }
 
+#[test]
+fn withdraw_reports_and_respects_promise_budget() {
+    let (mut runner, mut signer, _token, tester) = setup_test();
+
+    let result = tester.withdraw(&mut runner, &mut signer, true).unwrap();
+    // `ft_transfer` (and the refund callback, if enabled) with 1 yoctoNEAR attached
+    assert!(result.promises.count >= 1);
+    assert_eq!(result.promises.attached_balance, 1);
+
+    let budget = aurora_engine::promise_budget::PromiseBudget {
+        max_promises: 10,
+        max_attached_balance: 0,
+    };
+    let owner = runner.aurora_account_id.clone();
+    let (_, maybe_error) = runner.call(
+        "set_promise_budget",
+        &owner,
+        borsh::BorshSerialize::try_to_vec(&budget).unwrap(),
+    );
+    assert!(maybe_error.is_none());
+
+    let data = tester
+        .contract
+        .abi
+        .function("withdraw")
+        .unwrap()
+        .encode_input(&[ethabi::Token::Bool(true)])
+        .unwrap();
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| TransactionLegacy {
+            nonce,
+            gas_price: U256::zero(),
+            gas_limit: U256::from(1_000_000_000u64),
+            to: Some(tester.contract.address),
+            value: Wei::zero(),
+            data,
+        })
+        .unwrap_err();
+    let error_message = format!("{:?}", err);
+    assert!(error_message.contains("ERR_PROMISE_BUDGET_ATTACHED_BALANCE"));
+    assert!(!runner
+        .previous_logs
+        .contains(&"call_contract tt.testnet.ft_transfer".to_string()));
+}
+
 fn parse_exit_events(result: SubmitResult, schema: &ethabi::Event) -> Vec<ethabi::Log> {
     let signature = schema.signature();
     result
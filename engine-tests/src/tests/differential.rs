@@ -0,0 +1,46 @@
+use crate::test_utils::differential::{
+    self, Case, Divergence, Execution, ReferenceEvm, SputnikReference,
+};
+
+const SEEDS: u64 = 32;
+
+#[test]
+fn test_engine_matches_sputnik_on_random_cases() {
+    let reports = differential::run(&mut SputnikReference, 0..SEEDS);
+    assert!(reports.is_empty(), "{:#?}", reports);
+}
+
+#[test]
+fn test_random_case_is_reproducible() {
+    let case = differential::random_case(7);
+    let again = differential::random_case(7);
+    assert_eq!(case.transaction, again.transaction);
+    assert_eq!(case.sender, again.sender);
+    assert_eq!(case.block.number, again.block.number);
+}
+
+/// Reference which reports one more unit of gas than it uses.
+struct OffByOne(SputnikReference);
+
+impl ReferenceEvm for OffByOne {
+    fn name(&self) -> &str {
+        "off-by-one"
+    }
+
+    fn execute(&mut self, case: &Case) -> Execution {
+        let mut execution = self.0.execute(case);
+        execution.gas_used += 1;
+        execution
+    }
+}
+
+#[test]
+fn test_divergence_is_reported() {
+    let case = differential::random_case(0);
+    let divergences = differential::check_case(&mut OffByOne(SputnikReference), &case);
+    assert_eq!(divergences.len(), 1);
+    match &divergences[0] {
+        Divergence::GasUsed { engine, reference } => assert_eq!(*engine + 1, *reference),
+        other => panic!("Unexpected divergence {:?}", other),
+    }
+}
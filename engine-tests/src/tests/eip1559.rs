@@ -1,6 +1,7 @@
 use crate::prelude::Wei;
 use crate::prelude::{H256, U256};
 use crate::test_utils;
+use crate::tests::vectors;
 use aurora_engine::parameters::SubmitResult;
 use aurora_engine::transaction::eip_1559::{self, SignedTransaction1559, Transaction1559};
 use aurora_engine::transaction::eip_2930::AccessTuple;
@@ -11,14 +12,12 @@ use std::iter;
 
 const SECRET_KEY: &str = "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
 const INITIAL_NONCE: u64 = 1;
-const INITIAL_BALANCE: Wei = Wei::new_u64(0x0de0b6b3a7640000);
+const INITIAL_BALANCE: Wei = Wei::ONE_ETH;
 
 const CONTRACT_ADDRESS: &str = "0xcccccccccccccccccccccccccccccccccccccccc";
 const CONTRACT_NONCE: u64 = 1;
 const CONTRACT_CODE: &str = "3a6000554860015500";
-const CONTRACT_BALANCE: Wei = Wei::new_u64(0x0de0b6b3a7640000);
-
-const EXAMPLE_TX_HEX: &str = "02f8c101010a8207d0833d090094cccccccccccccccccccccccccccccccccccccccc8000f85bf85994ccccccccccccccccccccccccccccccccccccccccf842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000180a0d671815898b8dd34321adbba4cb6a57baa7017323c26946f3719b00e70c755c2a03528b9efe3be57ea65a933d1e6bbf3b7d0c78830138883c1201e0c641fee6464";
+const CONTRACT_BALANCE: Wei = Wei::ONE_ETH;
 
 // Test taken from https://github.com/ethereum/tests/blob/develop/GeneralStateTests/stExample/eip1559.json
 // TODO(#170): generally support Ethereum tests
@@ -29,7 +28,7 @@ fn test_eip_1559_tx_encoding_decoding() {
 
     let signed_tx = test_utils::sign_eip_1559_transaction(transaction, &secret_key);
     let bytes = encode_tx(&signed_tx);
-    let expected_bytes = hex::decode(EXAMPLE_TX_HEX).unwrap();
+    let expected_bytes = vectors::raw_transaction("eip1559_example");
 
     assert_eq!(bytes, expected_bytes);
 
@@ -1,13 +1,19 @@
 use crate::prelude::{Address, Balance, RawAddress, TryInto, Wei, WeiU256, U256};
 use crate::test_utils;
-use crate::test_utils::{create_eth_transaction, origin, AuroraRunner};
-use aurora_engine::parameters::{CallArgs, FunctionCallArgsV2, SubmitResult};
+use crate::test_utils::{create_eth_transaction, origin, str_to_account_id, AuroraRunner};
+use aurora_engine::deposit_event::FtTransferMessageData;
+use aurora_engine::parameters::{
+    CallArgs, DeployErc20Result, DeployErc20TokenWithTemplateArgs, Erc20Template,
+    Erc20TemplateInfo, FunctionCallArgsV2, RegisterErc20TemplateArgs, SetComplianceHookArgs,
+    SubmitResult,
+};
 use aurora_engine::transaction::legacy::LegacyEthSignedTransaction;
+use aurora_engine_types::account_id::AccountId;
 use borsh::{BorshDeserialize, BorshSerialize};
 use ethabi::Token;
 use near_vm_logic::VMOutcome;
 use near_vm_runner::VMError;
-use secp256k1::SecretKey;
+use secp256k1::{Message, SecretKey};
 use serde_json::json;
 use sha3::Digest;
 
@@ -72,31 +78,40 @@ impl test_utils::AuroraRunner {
     pub fn make_call(
         &mut self,
         method_name: &str,
-        caller_account_id: String,
+        caller_account_id: AccountId,
         input: Vec<u8>,
     ) -> CallResult {
-        let (outcome, error) = self.call(method_name, &caller_account_id, input);
+        let (outcome, error) = self.call(method_name, caller_account_id.as_ref(), input);
         CallResult { outcome, error }
     }
 
     pub fn make_call_with_signer(
         &mut self,
         method_name: &str,
-        caller_account_id: String,
-        signer_account_id: String,
+        caller_account_id: AccountId,
+        signer_account_id: AccountId,
         input: Vec<u8>,
     ) -> CallResult {
-        let (outcome, error) =
-            self.call_with_signer(method_name, &caller_account_id, &signer_account_id, input);
+        let (outcome, error) = self.call_with_signer(
+            method_name,
+            caller_account_id.as_ref(),
+            signer_account_id.as_ref(),
+            input,
+        );
         CallResult { outcome, error }
     }
 
-    pub fn evm_call(&mut self, contract: RawAddress, input: Vec<u8>, origin: String) -> CallResult {
+    pub fn evm_call(
+        &mut self,
+        contract: RawAddress,
+        input: Vec<u8>,
+        origin: AccountId,
+    ) -> CallResult {
         self.make_call(
             "call",
             origin,
             CallArgs::V2(FunctionCallArgsV2 {
-                contract,
+                contract: contract.into(),
                 value: WeiU256::default(),
                 input,
             })
@@ -105,11 +120,15 @@ impl test_utils::AuroraRunner {
         )
     }
 
-    pub fn evm_submit(&mut self, input: LegacyEthSignedTransaction, origin: String) -> CallResult {
+    pub fn evm_submit(
+        &mut self,
+        input: LegacyEthSignedTransaction,
+        origin: AccountId,
+    ) -> CallResult {
         self.make_call("submit", origin, rlp::encode(&input).to_vec())
     }
 
-    pub fn deploy_erc20_token(&mut self, nep141: &String) -> RawAddress {
+    pub fn deploy_erc20_token(&mut self, nep141: &AccountId) -> RawAddress {
         let result = self.make_call("deploy_erc20_token", origin(), nep141.try_to_vec().unwrap());
 
         result.check_ok();
@@ -120,6 +139,25 @@ impl test_utils::AuroraRunner {
             .unwrap()
     }
 
+    pub fn deploy_erc20_token_with_template(
+        &mut self,
+        nep141: &AccountId,
+        template: Erc20Template,
+    ) -> RawAddress {
+        let args = DeployErc20TokenWithTemplateArgs {
+            nep141: nep141.clone(),
+            template,
+        };
+        let result = self.make_call("deploy_erc20_token", origin(), args.try_to_vec().unwrap());
+
+        result.check_ok();
+
+        Vec::<u8>::try_from_slice(result.value().as_slice())
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
     pub fn create_account(&mut self) -> EthereumAddress {
         let mut rng = rand::thread_rng();
         let source_account = SecretKey::random(&mut rng);
@@ -131,7 +169,7 @@ impl test_utils::AuroraRunner {
         }
     }
 
-    pub fn balance_of(&mut self, token: RawAddress, target: RawAddress, origin: String) -> U256 {
+    pub fn balance_of(&mut self, token: RawAddress, target: RawAddress, origin: AccountId) -> U256 {
         let input = build_input("balanceOf(address)", &[Token::Address(target.into())]);
         let result = self.evm_call(token, input, origin);
         result.check_ok();
@@ -144,7 +182,7 @@ impl test_utils::AuroraRunner {
         token: RawAddress,
         target: RawAddress,
         amount: u64,
-        origin: String,
+        origin: AccountId,
     ) -> CallResult {
         let input = build_input(
             "mint(address,uint256)",
@@ -159,7 +197,7 @@ impl test_utils::AuroraRunner {
     }
 
     #[allow(dead_code)]
-    pub fn admin(&mut self, token: RawAddress, origin: String) -> CallResult {
+    pub fn admin(&mut self, token: RawAddress, origin: AccountId) -> CallResult {
         let input = build_input("admin()", &[]);
         let result = self.evm_call(token, input, origin);
         result.check_ok();
@@ -172,7 +210,7 @@ impl test_utils::AuroraRunner {
         sender: SecretKey,
         receiver: RawAddress,
         amount: u64,
-        origin: String,
+        origin: AccountId,
     ) -> CallResult {
         // transfer(address recipient, uint256 amount)
         let input = build_input(
@@ -192,9 +230,9 @@ impl test_utils::AuroraRunner {
 
     pub fn ft_on_transfer(
         &mut self,
-        nep141: String,
-        sender_id: String,
-        relayer_id: String,
+        nep141: AccountId,
+        sender_id: AccountId,
+        relayer_id: AccountId,
         amount: Balance,
         msg: String,
     ) -> String {
@@ -203,7 +241,7 @@ impl test_utils::AuroraRunner {
             nep141,
             relayer_id,
             json!({
-                "sender_id": sender_id,
+                "sender_id": sender_id.to_string(),
                 "amount": amount.to_string(),
                 "msg": msg
             })
@@ -216,7 +254,7 @@ impl test_utils::AuroraRunner {
 
     pub fn register_relayer(
         &mut self,
-        relayer_account_id: String,
+        relayer_account_id: AccountId,
         relayer_address: Address,
     ) -> CallResult {
         self.make_call(
@@ -230,13 +268,96 @@ impl test_utils::AuroraRunner {
 #[test]
 fn test_deploy_erc20_token() {
     let mut runner = AuroraRunner::new();
-    runner.deploy_erc20_token(&"tt.testnet".to_string());
+    runner.deploy_erc20_token(&str_to_account_id("tt.testnet"));
+}
+
+#[test]
+fn test_deploy_erc20_token_v2() {
+    let mut runner = AuroraRunner::new();
+    let nep141 = str_to_account_id("tt.testnet");
+    let result = runner.make_call(
+        "deploy_erc20_token_v2",
+        origin(),
+        nep141.try_to_vec().unwrap(),
+    );
+    result.check_ok();
+
+    let deployed = DeployErc20Result::try_from_slice(&result.value()).unwrap();
+    assert_eq!(deployed.version(), 1);
+    assert_eq!(deployed.nep141, nep141);
+    assert_eq!(deployed.decimals, 0);
+    assert_eq!(
+        deployed.template_version,
+        aurora_engine::engine::ERC20_TEMPLATE_VERSION
+    );
+
+    let event = result
+        .outcome
+        .as_ref()
+        .unwrap()
+        .logs
+        .iter()
+        .find_map(|log| log.strip_prefix(aurora_engine::events::EVENT_JSON_PREFIX))
+        .unwrap();
+    let event: serde_json::Value = serde_json::from_str(event).unwrap();
+    assert_eq!(event["event"], "erc20_deployed");
+    assert_eq!(event["data"][0]["address"], hex::encode(deployed.address));
+}
+
+#[test]
+fn test_convert_bridged_amounts() {
+    use aurora_engine::parameters::{ConvertErc20AmountArgs, ConvertNep141AmountArgs};
+
+    let mut runner = AuroraRunner::new();
+    let nep141 = str_to_account_id("tt.testnet");
+    let token = runner.deploy_erc20_token(&nep141);
+    let to_erc20 = |runner: &mut AuroraRunner, nep141: &AccountId, amount: Balance| {
+        let args = ConvertNep141AmountArgs {
+            nep141: nep141.clone(),
+            amount,
+        };
+        runner.make_call(
+            "convert_nep141_amount_to_erc20",
+            origin(),
+            args.try_to_vec().unwrap(),
+        )
+    };
+    let to_nep141 = |runner: &mut AuroraRunner, erc20: RawAddress, amount: U256| {
+        let args = ConvertErc20AmountArgs {
+            erc20: erc20.into(),
+            amount: aurora_engine_types::types::u256_to_arr(&amount),
+        };
+        runner.make_call(
+            "convert_erc20_amount_to_nep141",
+            origin(),
+            args.try_to_vec().unwrap(),
+        )
+    };
+
+    // Amounts are bridged as is.
+    let result = to_erc20(&mut runner, &nep141, Balance::MAX);
+    result.check_ok();
+    let amount = <[u8; 32]>::try_from_slice(&result.value()).unwrap();
+    assert_eq!(U256::from_big_endian(&amount), U256::from(Balance::MAX));
+    let result = to_nep141(&mut runner, token, U256::from(Balance::MAX));
+    result.check_ok();
+    assert_eq!(
+        Balance::try_from_slice(&result.value()).unwrap(),
+        Balance::MAX
+    );
+
+    let result = to_nep141(&mut runner, token, U256::from(Balance::MAX) + 1);
+    assert!(format!("{:?}", result.error.unwrap()).contains("ERR_AMOUNT_OVERFLOW"));
+    let result = to_erc20(&mut runner, &str_to_account_id("other.testnet"), 1);
+    assert!(format!("{:?}", result.error.unwrap()).contains("ERR_TOKEN_NOT_FOUND"));
+    let result = to_nep141(&mut runner, [1; 20], U256::one());
+    assert!(format!("{:?}", result.error.unwrap()).contains("ERR_TOKEN_NOT_FOUND"));
 }
 
 #[test]
 fn test_mint() {
     let mut runner = AuroraRunner::new();
-    let token = runner.deploy_erc20_token(&"tt.testnet".to_string());
+    let token = runner.deploy_erc20_token(&str_to_account_id("tt.testnet"));
     let address = runner.create_account().address;
     let balance = runner.balance_of(token, address, origin());
     assert_eq!(balance, U256::from(0));
@@ -249,12 +370,12 @@ fn test_mint() {
 #[test]
 fn test_mint_not_admin() {
     let mut runner = AuroraRunner::new();
-    let token = runner.deploy_erc20_token(&"tt.testnet".to_string());
+    let token = runner.deploy_erc20_token(&str_to_account_id("tt.testnet"));
     let address = runner.create_account().address;
     let balance = runner.balance_of(token, address, origin());
     assert_eq!(balance, U256::from(0));
     let amount = 10;
-    runner.mint(token, address, amount, "not_admin".to_string());
+    runner.mint(token, address, amount, str_to_account_id("not_admin"));
     let balance = runner.balance_of(token, address, origin());
     assert_eq!(balance, U256::from(0));
 }
@@ -264,8 +385,8 @@ fn test_ft_on_transfer() {
     let mut runner = AuroraRunner::new();
     // Standalone runner presently does not support ft_on_transfer
     runner.standalone_runner = None;
-    let nep141 = "tt.testnet".to_string();
-    let alice = "alice".to_string();
+    let nep141 = str_to_account_id("tt.testnet");
+    let alice = str_to_account_id("alice");
     let token = runner.deploy_erc20_token(&nep141);
     let amount = 10;
     let recipient = runner.create_account().address;
@@ -284,8 +405,8 @@ fn test_ft_on_transfer() {
 #[test]
 fn test_ft_on_transfer_fail() {
     let mut runner = AuroraRunner::new();
-    let nep141 = "tt.testnet".to_string();
-    let alice = "alice".to_string();
+    let nep141 = str_to_account_id("tt.testnet");
+    let alice = str_to_account_id("alice");
     let amount = 10;
 
     let recipient = runner.create_account().address;
@@ -296,6 +417,126 @@ fn test_ft_on_transfer_fail() {
     assert_eq!(res, format!("\"{}\"", amount.to_string()));
 }
 
+#[test]
+fn test_ft_on_transfer_refunds_malformed_messages() {
+    let mut runner = AuroraRunner::new();
+    // Standalone runner presently does not support ft_on_transfer
+    runner.standalone_runner = None;
+    let nep141 = str_to_account_id("tt.testnet");
+    let alice = str_to_account_id("alice");
+    let token = runner.deploy_erc20_token(&nep141);
+    let amount = 10;
+    let recipient = runner.create_account().address;
+    let recipient_hex = hex::encode(recipient);
+    let fee_hex = |fee: U256| {
+        let mut fee_encoded = [0u8; 32];
+        fee.to_big_endian(&mut fee_encoded);
+        hex::encode(fee_encoded)
+    };
+
+    let malformed = [
+        // Too short for an address
+        recipient_hex[..38].to_string(),
+        // Invalid hex
+        format!("zz{}", &recipient_hex[2..]),
+        // Truncated fee
+        format!("{}{}", recipient_hex, &fee_hex(U256::one())[2..]),
+        // Fee which does not fit the transferred amount of wei
+        format!("{}{}", recipient_hex, fee_hex(U256::from(u64::MAX) + 1)),
+    ];
+    for msg in malformed.iter() {
+        let res = runner.ft_on_transfer(
+            nep141.clone(),
+            alice.clone(),
+            alice.clone(),
+            amount,
+            msg.clone(),
+        );
+        assert_eq!(res, format!("\"{}\"", amount), "{}", msg);
+    }
+    let balance = runner.balance_of(token, recipient, origin());
+    assert_eq!(balance, U256::zero());
+
+    // The eth connector refunds malformed messages too, instead of panicking.
+    let aurora = str_to_account_id(runner.aurora_account_id.as_str());
+    for msg in ["relayer.near", "relayer.near:zz", "Relayer:00"].iter() {
+        let res = runner.ft_on_transfer(
+            aurora.clone(),
+            alice.clone(),
+            alice.clone(),
+            amount,
+            msg.to_string(),
+        );
+        assert_eq!(res, format!("\"{}\"", amount), "{}", msg);
+    }
+    let msg = FtTransferMessageData {
+        relayer: alice.clone(),
+        recipient: recipient.into(),
+        fee: 0.into(),
+    }
+    .encode();
+    let res = runner.ft_on_transfer(aurora, alice.clone(), alice, amount, msg);
+    assert_eq!(res, "\"0\"");
+    assert_eq!(
+        runner.get_balance(recipient.into()),
+        INITIAL_BALANCE + Wei::new_u64(amount as u64)
+    );
+}
+
+#[test]
+fn test_ft_on_transfer_compliance_hook() {
+    let mut runner = AuroraRunner::new();
+    // Standalone runner presently does not support ft_on_transfer
+    runner.standalone_runner = None;
+    let nep141 = str_to_account_id("tt.testnet");
+    let alice = str_to_account_id("alice");
+    let token = runner.deploy_erc20_token(&nep141);
+    let amount = 10;
+    let blocked = runner.create_account().address;
+    let allowed = runner.create_account().address;
+
+    // isBlacklisted(address) returning whether the argument is `blocked`:
+    // CALLDATALOAD(4) PUSH20 blocked EQ MSTORE(0) RETURN(0, 32)
+    let code = [
+        hex::decode("600435").unwrap(),
+        [0x73].to_vec(),
+        blocked.to_vec(),
+        hex::decode("1460005260206000f3").unwrap(),
+    ]
+    .concat();
+    let hook = create_ethereum_address();
+    runner.create_address_with_code(hook, Wei::zero(), U256::zero(), code);
+    let owner = str_to_account_id(runner.aurora_account_id.as_str());
+    let result = runner.make_call(
+        "set_compliance_hook",
+        owner,
+        SetComplianceHookArgs {
+            token: token.into(),
+            hook: Some(hook.into()),
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    result.check_ok();
+
+    // Minting to a blacklisted account fails so everything is refunded
+    let res = runner.ft_on_transfer(
+        nep141.clone(),
+        alice.clone(),
+        alice.clone(),
+        amount,
+        hex::encode(blocked),
+    );
+    assert_eq!(res, format!("\"{}\"", amount));
+    let balance = runner.balance_of(token, blocked, origin());
+    assert_eq!(balance, U256::from(0));
+
+    let res = runner.ft_on_transfer(nep141, alice.clone(), alice, amount, hex::encode(allowed));
+    assert_eq!(res, "\"0\"");
+    let balance = runner.balance_of(token, allowed, origin());
+    assert_eq!(balance, U256::from(amount));
+}
+
 #[test]
 fn test_relayer_charge_fee() {
     let mut runner = AuroraRunner::new();
@@ -303,8 +544,8 @@ fn test_relayer_charge_fee() {
     runner.standalone_runner = None;
     let amount = 10;
     let fee = 51;
-    let nep141 = "tt.testnet".to_string();
-    let alice = "alice".to_string();
+    let nep141 = str_to_account_id("tt.testnet");
+    let alice = str_to_account_id("alice");
     let token = runner.deploy_erc20_token(&nep141);
     let recipient = runner.create_account().address;
 
@@ -345,7 +586,7 @@ fn test_relayer_charge_fee() {
 #[test]
 fn test_transfer_erc20_token() {
     let mut runner = AuroraRunner::new();
-    let token = runner.deploy_erc20_token(&"tt.testnet".to_string());
+    let token = runner.deploy_erc20_token(&str_to_account_id("tt.testnet"));
     let peer0 = runner.create_account();
     let peer1 = runner.create_account();
 
@@ -386,6 +627,318 @@ fn test_transfer_erc20_token() {
     );
 }
 
+/// EIP-712 domain of the permits of a bridged token, until its metadata is set.
+fn permit_domain_separator(token: RawAddress, chain_id: u64) -> Vec<u8> {
+    keccak256(&ethabi::encode(&[
+        Token::FixedBytes(keccak256(
+            b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+        )),
+        Token::FixedBytes(keccak256(b"Empty")),
+        Token::FixedBytes(keccak256(b"1")),
+        Token::Uint(chain_id.into()),
+        Token::Address(token.into()),
+    ]))
+}
+
+/// EIP-2612 signature of `owner` approving `value` tokens to `spender`.
+fn sign_permit(
+    token: RawAddress,
+    owner: &EthereumAddress,
+    spender: RawAddress,
+    value: U256,
+    nonce: U256,
+    chain_id: u64,
+) -> Vec<Token> {
+    let deadline = U256::MAX;
+    let domain_separator = permit_domain_separator(token, chain_id);
+    let struct_hash = keccak256(&ethabi::encode(&[
+        Token::FixedBytes(keccak256(
+            b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+        )),
+        Token::Address(owner.address.into()),
+        Token::Address(spender.into()),
+        Token::Uint(value),
+        Token::Uint(nonce),
+        Token::Uint(deadline),
+    ]));
+
+    let mut args = vec![
+        Token::Address(owner.address.into()),
+        Token::Address(spender.into()),
+        Token::Uint(value),
+        Token::Uint(deadline),
+    ];
+    args.extend(sign_typed_data(
+        &domain_separator,
+        &struct_hash,
+        &owner.secret_key,
+    ));
+    args
+}
+
+/// EIP-712 signature of the struct hash, as the `v`, `r` and `s` arguments of a call.
+fn sign_typed_data(
+    domain_separator: &[u8],
+    struct_hash: &[u8],
+    secret_key: &SecretKey,
+) -> Vec<Token> {
+    let digest = keccak256(&[&[0x19, 0x01][..], domain_separator, struct_hash].concat());
+    let (signature, recovery_id) =
+        secp256k1::sign(&Message::parse_slice(&digest).unwrap(), secret_key);
+
+    vec![
+        Token::Uint((recovery_id.serialize() + 27).into()),
+        Token::FixedBytes(signature.r.b32().to_vec()),
+        Token::FixedBytes(signature.s.b32().to_vec()),
+    ]
+}
+
+#[test]
+fn test_permit_erc20_token() {
+    let mut runner = AuroraRunner::new();
+    let token = runner.deploy_erc20_token(&str_to_account_id("tt.testnet"));
+    let owner = runner.create_account();
+    let spender = runner.create_account();
+    let recipient = create_ethereum_address();
+
+    let to_mint = 51;
+    let to_permit = 43;
+    runner.mint(token, owner.address, to_mint, origin());
+
+    let domain_separator = runner.evm_call(token, build_input("DOMAIN_SEPARATOR()", &[]), origin());
+    domain_separator.check_ok();
+    assert_eq!(
+        test_utils::unwrap_success(domain_separator.submit_result()),
+        permit_domain_separator(token, runner.chain_id)
+    );
+
+    // Anybody can submit the permit signed by the owner, who needs no ETH for gas.
+    let permit = sign_permit(
+        token,
+        &owner,
+        spender.address,
+        U256::from(to_permit),
+        U256::zero(),
+        runner.chain_id,
+    );
+    let input = build_input(
+        "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+        &permit,
+    );
+    let result = runner.evm_call(token, input.clone(), origin());
+    result.check_ok();
+    test_utils::unwrap_success(result.submit_result());
+
+    let nonce = runner.evm_call(
+        token,
+        build_input("nonces(address)", &[Token::Address(owner.address.into())]),
+        origin(),
+    );
+    nonce.check_ok();
+    assert_eq!(
+        U256::from_big_endian(&test_utils::unwrap_success(nonce.submit_result())),
+        U256::one()
+    );
+
+    // The signature cannot be used twice
+    let result = runner.evm_call(token, input, origin());
+    result.check_ok();
+    assert!(result.submit_result().status.is_revert());
+
+    // The spender uses the allowance
+    let input = build_input(
+        "transferFrom(address,address,uint256)",
+        &[
+            Token::Address(owner.address.into()),
+            Token::Address(recipient),
+            Token::Uint(U256::from(to_permit)),
+        ],
+    );
+    let input = create_eth_transaction(
+        Some(token.into()),
+        Wei::zero(),
+        input,
+        None,
+        &spender.secret_key,
+    );
+    let result = runner.evm_submit(input, origin());
+    result.check_ok();
+    test_utils::unwrap_success(result.submit_result());
+
+    assert_eq!(
+        runner.balance_of(token, owner.address, origin()),
+        U256::from(to_mint - to_permit)
+    );
+    assert_eq!(
+        runner.balance_of(token, recipient.into(), origin()),
+        U256::from(to_permit)
+    );
+}
+
+#[test]
+fn test_transfer_with_authorization_erc20_token() {
+    let mut runner = AuroraRunner::new();
+    let nep141 = str_to_account_id("tt.testnet");
+    let token = runner.deploy_erc20_token_with_template(&nep141, Erc20Template::Stablecoin);
+    let owner = runner.create_account();
+    let recipient = create_ethereum_address();
+
+    let to_mint = 51;
+    let to_transfer = 43;
+    runner.mint(token, owner.address, to_mint, origin());
+
+    // Authorizations have random nonces
+    let nonce = [7u8; 32];
+    let struct_hash = keccak256(&ethabi::encode(&[
+        Token::FixedBytes(keccak256(
+            b"TransferWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 validBefore,bytes32 nonce)",
+        )),
+        Token::Address(owner.address.into()),
+        Token::Address(recipient),
+        Token::Uint(U256::from(to_transfer)),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::MAX),
+        Token::FixedBytes(nonce.to_vec()),
+    ]));
+    let mut args = vec![
+        Token::Address(owner.address.into()),
+        Token::Address(recipient),
+        Token::Uint(U256::from(to_transfer)),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::MAX),
+        Token::FixedBytes(nonce.to_vec()),
+    ];
+    args.extend(sign_typed_data(
+        &permit_domain_separator(token, runner.chain_id),
+        &struct_hash,
+        &owner.secret_key,
+    ));
+    let input = build_input(
+        "transferWithAuthorization(address,address,uint256,uint256,uint256,bytes32,uint8,bytes32,bytes32)",
+        &args,
+    );
+
+    // The standard template does not support authorizations
+    let standard_token = runner.deploy_erc20_token(&str_to_account_id("standard.testnet"));
+    let result = runner.evm_call(standard_token, input.clone(), origin());
+    result.check_ok();
+    assert!(result.submit_result().status.is_revert());
+
+    // Anybody can submit the authorization signed by the owner
+    let result = runner.evm_call(token, input.clone(), origin());
+    result.check_ok();
+    test_utils::unwrap_success(result.submit_result());
+
+    assert_eq!(
+        runner.balance_of(token, owner.address, origin()),
+        U256::from(to_mint - to_transfer)
+    );
+    assert_eq!(
+        runner.balance_of(token, recipient.into(), origin()),
+        U256::from(to_transfer)
+    );
+
+    let state = runner.evm_call(
+        token,
+        build_input(
+            "authorizationState(address,bytes32)",
+            &[
+                Token::Address(owner.address.into()),
+                Token::FixedBytes(nonce.to_vec()),
+            ],
+        ),
+        origin(),
+    );
+    state.check_ok();
+    assert_eq!(
+        U256::from_big_endian(&test_utils::unwrap_success(state.submit_result())),
+        U256::one()
+    );
+
+    // The authorization cannot be used twice
+    let result = runner.evm_call(token, input, origin());
+    result.check_ok();
+    assert!(result.submit_result().status.is_revert());
+}
+
+#[test]
+fn test_deploy_erc20_token_from_registered_template() {
+    let mut runner = AuroraRunner::new();
+    let code = std::fs::read("../etc/eth-contracts/res/EvmErc20.bin").unwrap();
+    let code_hash = aurora_engine_sdk::keccak(&code).0;
+    let args = RegisterErc20TemplateArgs {
+        name: "audited".to_string(),
+        version: 1,
+        code,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let result = runner.make_call(
+        "register_erc20_template",
+        str_to_account_id("not_the_owner.near"),
+        args.clone(),
+    );
+    assert!(result.error.is_some());
+    let result = runner.make_call("register_erc20_template", origin(), args.clone());
+    result.check_ok();
+    let info = Erc20TemplateInfo {
+        name: "audited".to_string(),
+        version: 1,
+        code_hash,
+    };
+    assert_eq!(
+        Erc20TemplateInfo::try_from_slice(&result.value()).unwrap(),
+        info
+    );
+    // The code of a registered template cannot be replaced
+    let result = runner.make_call("register_erc20_template", origin(), args);
+    assert!(format!("{:?}", result.error).contains("ERR_TEMPLATE_ALREADY_REGISTERED"));
+
+    let templates = runner.make_call("get_erc20_templates", origin(), Vec::new());
+    templates.check_ok();
+    let templates = Vec::<Erc20TemplateInfo>::try_from_slice(&templates.value()).unwrap();
+    let names: Vec<_> = templates.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["standard", "stablecoin", "audited"]);
+    assert_eq!(templates[2], info);
+
+    // The deployment fails unless the code has the pinned hash
+    let nep141 = str_to_account_id("tt.testnet");
+    let template = |code_hash| Erc20Template::Registered {
+        name: "audited".to_string(),
+        version: 1,
+        code_hash,
+    };
+    let args = DeployErc20TokenWithTemplateArgs {
+        nep141: nep141.clone(),
+        template: template([0u8; 32]),
+    };
+    let result = runner.make_call("deploy_erc20_token", origin(), args.try_to_vec().unwrap());
+    assert!(format!("{:?}", result.error).contains("ERR_TEMPLATE_CODE_HASH_MISMATCH"));
+
+    let token = runner.deploy_erc20_token_with_template(&nep141, template(code_hash));
+    let recipient = create_ethereum_address();
+    runner.mint(token, recipient.into(), 10, origin());
+    assert_eq!(
+        runner.balance_of(token, recipient.into(), origin()),
+        U256::from(10)
+    );
+
+    // The template of each token is recorded
+    let get_template = |runner: &mut AuroraRunner, token: RawAddress| {
+        let result = runner.make_call("get_erc20_template", origin(), token.to_vec());
+        result.check_ok();
+        Option::<Erc20TemplateInfo>::try_from_slice(&result.value()).unwrap()
+    };
+    assert_eq!(get_template(&mut runner, token), Some(info));
+    let standard_token = runner.deploy_erc20_token(&str_to_account_id("standard.testnet"));
+    assert_eq!(
+        get_template(&mut runner, standard_token).unwrap().code_hash,
+        templates[0].code_hash
+    );
+    assert_eq!(get_template(&mut runner, [0u8; 20]), None);
+}
+
 // Simulation tests for exit to NEAR precompile.
 // Note: `AuroraRunner` is not suitable for these tests because
 // it does not execute promises; but `near-sdk-sim` does.
@@ -595,6 +1148,72 @@ mod sim_tests {
         );
     }
 
+    #[test]
+    fn test_exit_to_near_eth_paused() {
+        // Same as above, with the exit precompile paused
+        use aurora_engine::connector::PAUSE_EXIT_TO_NEAR;
+        use aurora_engine::parameters::PauseEthConnectorCallArgs;
+
+        let TestExitToNearEthContext {
+            signer,
+            signer_address,
+            chain_id,
+            tester_address,
+            aurora,
+        } = test_exit_to_near_eth_common();
+        let exit_account_id = "any.near".to_owned();
+
+        let args = PauseEthConnectorCallArgs {
+            paused_mask: PAUSE_EXIT_TO_NEAR,
+        };
+        aurora
+            .contract
+            .call(
+                aurora.contract.account_id(),
+                "set_paused_flags",
+                &args.try_to_vec().unwrap(),
+                near_sdk_sim::DEFAULT_GAS,
+                0,
+            )
+            .assert_success();
+
+        // call exit to near
+        let input = super::build_input(
+            "withdrawEthToNear(bytes)",
+            &[ethabi::Token::Bytes(exit_account_id.as_bytes().to_vec())],
+        );
+        let tx = test_utils::create_eth_transaction(
+            Some(tester_address),
+            Wei::new_u64(ETH_EXIT_AMOUNT),
+            input,
+            Some(chain_id),
+            &signer.secret_key,
+        );
+        aurora.call("submit", &rlp::encode(&tx)).assert_success();
+
+        // The tester ignores the failure of the precompile, so it keeps the ETH on Aurora
+        assert_eq!(
+            nep_141_balance_of(
+                aurora.contract.account_id.as_str(),
+                &aurora.contract,
+                &aurora
+            ),
+            INITIAL_ETH_BALANCE.into()
+        );
+        assert_eq!(
+            nep_141_balance_of(exit_account_id.as_str(), &aurora.contract, &aurora),
+            0
+        );
+        assert_eq!(
+            eth_balance_of(signer_address, &aurora),
+            Wei::new_u64(INITIAL_ETH_BALANCE - ETH_EXIT_AMOUNT)
+        );
+        assert_eq!(
+            eth_balance_of(tester_address, &aurora),
+            Wei::new_u64(ETH_EXIT_AMOUNT)
+        );
+    }
+
     fn test_exit_to_near_eth_common() -> TestExitToNearEthContext {
         let aurora = deploy_evm();
         let chain_id = test_utils::AuroraRunner::default().chain_id;
@@ -718,7 +1337,7 @@ mod sim_tests {
             ],
         );
         let call_args = CallArgs::V2(FunctionCallArgsV2 {
-            contract: erc20.0.address.0,
+            contract: erc20.0.address.into(),
             value: WeiU256::default(),
             input,
         });
@@ -758,7 +1377,7 @@ mod sim_tests {
 
         let mint_tx = erc20.mint(dest, amount.into(), 0.into());
         let call_args = CallArgs::V2(FunctionCallArgsV2 {
-            contract: erc20.0.address.0,
+            contract: erc20.0.address.into(),
             value: WeiU256::default(),
             input: mint_tx.data,
         });
@@ -789,7 +1408,7 @@ mod sim_tests {
     fn erc20_balance(erc20: &ERC20, address: Address, aurora: &AuroraAccount) -> U256 {
         let balance_tx = erc20.balance_of(address, 0.into());
         let call_args = CallArgs::V2(FunctionCallArgsV2 {
-            contract: erc20.0.address.0,
+            contract: erc20.0.address.into(),
             value: WeiU256::default(),
             input: balance_tx.data,
         });
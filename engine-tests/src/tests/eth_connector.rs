@@ -1,14 +1,23 @@
 use crate::prelude::EthAddress;
 use crate::prelude::WithdrawCallArgs;
 use crate::test_utils::str_to_account_id;
+use crate::tests::vectors;
 use aurora_engine::admin_controlled::{PausedMask, ERR_PAUSED};
+use aurora_engine::admin_simulation::{AdminAction, AdminSimulation};
+use aurora_engine::bridge_stats::BridgeTokenStats;
 use aurora_engine::connector::{
     ERR_NOT_ENOUGH_BALANCE_FOR_FEE, PAUSE_DEPOSIT, PAUSE_WITHDRAW, UNPAUSE_ALL,
 };
+use aurora_engine::fee_schedule::{FeeRate, FeeSchedule};
 use aurora_engine::fungible_token::FungibleTokenMetadata;
 use aurora_engine::parameters::{
-    InitCallArgs, NewCallArgs, RegisterRelayerCallArgs, WithdrawResult,
+    CustodianChangeArgs, CustodianSupply, FinishWithdrawWithProofArgs, ForgetPendingWithdrawalArgs,
+    GetFeeScheduleArgs, GetPendingWithdrawalsArgs, InitCallArgs, NewCallArgs,
+    PauseEthConnectorCallArgs, PendingCustodianChange, PendingWithdrawal, PendingWithdrawalsPage,
+    RegisterRelayerCallArgs, SetContractDataCallArgs, SetCustodianCapArgs, SetFeeCollectorArgs,
+    SetFeeScheduleArgs, WithdrawResult,
 };
+use aurora_engine::protocol_fee::ProtocolFeeConfig;
 use aurora_engine_types::types::Fee;
 use borsh::{BorshDeserialize, BorshSerialize};
 use byte_slice_cast::AsByteSlice;
@@ -19,8 +28,6 @@ use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS, STORAGE_
 use serde_json::json;
 
 const CONTRACT_ACC: &'static str = "eth_connector.root";
-const PROOF_DATA_NEAR: &'static str = r#"{"log_index":0,"log_entry_data":[248,251,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,160,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,12,54,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,18,101,116,104,95,114,101,99,105,112,105,101,110,116,46,114,111,111,116,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"receipt_index":0,"receipt_data":[249,2,6,1,130,107,17,185,1,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,248,253,248,251,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,160,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,12,54,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,18,101,116,104,95,114,101,99,105,112,105,101,110,116,46,114,111,111,116,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"header_data":[249,2,10,160,177,33,112,26,26,176,12,12,163,2,249,133,245,12,51,201,55,50,148,156,122,67,27,26,101,178,36,153,54,100,53,137,160,29,204,77,232,222,199,93,122,171,133,181,103,182,204,212,26,211,18,69,27,148,138,116,19,240,161,66,253,64,212,147,71,148,124,28,230,160,8,239,64,193,62,78,177,68,166,204,116,240,224,174,172,126,160,197,65,5,202,188,134,5,164,246,19,133,35,57,28,114,241,186,81,123,163,166,161,24,32,157,168,170,13,108,58,61,46,160,6,199,163,13,91,119,225,39,168,255,213,10,107,252,143,246,138,241,108,139,59,35,187,185,162,223,53,108,222,73,181,109,160,27,154,49,63,26,170,15,177,97,255,6,204,84,221,234,197,159,172,114,47,148,126,32,199,241,127,101,120,182,51,52,100,185,1,0,0,0,8,0,0,0,0,0,0,0,32,0,0,0,0,0,2,0,8,0,0,0,0,0,0,0,0,0,0,2,0,0,0,0,0,0,0,0,8,32,0,32,0,0,128,0,2,0,0,0,1,0,32,0,0,0,2,0,0,0,0,32,0,0,0,0,0,4,0,0,0,0,0,0,0,8,0,0,0,0,0,0,0,128,64,0,0,0,0,1,32,0,0,0,0,0,0,96,32,0,64,0,0,0,128,1,0,0,0,0,1,0,0,0,8,0,0,0,18,32,0,0,64,145,1,8,0,4,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32,33,16,0,128,0,0,0,0,0,0,128,0,2,0,0,0,0,0,0,0,0,0,0,2,0,80,0,0,0,0,0,0,0,0,1,128,0,8,0,0,0,0,4,0,0,0,128,2,0,32,0,128,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,16,0,8,0,0,0,0,0,0,0,0,0,0,128,0,64,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,132,25,1,227,23,131,157,85,14,131,122,18,0,131,75,91,132,132,96,174,58,224,140,115,112,105,100,101,114,49,48,1,2,8,230,160,188,212,199,183,154,22,223,85,103,215,24,122,240,235,79,129,44,93,184,88,161,218,79,5,44,226,106,100,50,40,163,97,136,155,158,202,3,149,91,200,78],"proof":[[248,113,160,46,156,31,85,241,226,241,13,5,56,73,146,176,67,195,109,6,189,172,104,44,103,44,88,32,15,181,152,136,29,121,252,160,191,48,87,174,71,151,208,114,164,150,51,200,171,90,90,106,46,200,79,77,222,145,95,89,141,137,138,149,67,73,8,87,128,128,128,128,128,128,160,175,9,219,77,174,13,247,133,55,172,92,185,202,7,160,10,204,112,44,133,36,96,30,234,235,134,30,209,205,166,212,255,128,128,128,128,128,128,128,128],[249,2,13,48,185,2,9,249,2,6,1,130,107,17,185,1,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,248,253,248,251,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,160,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,12,54,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,144,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,18,101,116,104,95,114,101,99,105,112,105,101,110,116,46,114,111,111,116,0,0,0,0,0,0,0,0,0,0,0,0,0,0]]}"#;
-const PROOF_DATA_ETH: &'static str = r#"{"log_index":0,"log_entry_data":[249,1,27,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,192,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,39,216,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,200,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,59,101,116,104,95,99,111,110,110,101,99,116,111,114,46,114,111,111,116,58,56,57,49,66,50,55,52,57,50,51,56,66,50,55,102,70,53,56,101,57,53,49,48,56,56,101,53,53,98,48,52,100,101,55,49,68,99,51,55,52,0,0,0,0,0],"receipt_index":0,"receipt_data":[249,2,40,1,130,121,129,185,1,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,249,1,30,249,1,27,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,192,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,39,216,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,200,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,59,101,116,104,95,99,111,110,110,101,99,116,111,114,46,114,111,111,116,58,56,57,49,66,50,55,52,57,50,51,56,66,50,55,102,70,53,56,101,57,53,49,48,56,56,101,53,53,98,48,52,100,101,55,49,68,99,51,55,52,0,0,0,0,0],"header_data":[249,2,23,160,227,118,223,171,207,47,75,187,79,185,74,198,88,140,54,97,161,196,35,70,121,178,154,141,172,91,193,252,86,64,228,227,160,29,204,77,232,222,199,93,122,171,133,181,103,182,204,212,26,211,18,69,27,148,138,116,19,240,161,66,253,64,212,147,71,148,109,150,79,199,61,172,73,162,195,49,105,169,235,252,47,207,92,249,136,136,160,232,74,213,122,210,55,65,43,78,225,85,247,174,212,229,211,176,186,250,113,21,129,16,181,52,172,217,167,148,242,153,45,160,15,198,229,127,6,235,198,161,226,121,173,106,62,0,90,25,158,11,242,44,178,3,137,22,245,126,227,91,74,156,24,115,160,65,253,74,43,97,155,196,93,59,43,202,12,155,49,115,95,124,247,230,15,1,171,150,10,56,115,247,86,81,8,39,11,185,1,0,128,32,9,2,0,0,0,0,0,0,32,16,128,32,0,0,128,2,0,0,64,51,0,0,0,129,0,32,66,32,0,14,0,144,0,0,0,2,13,34,0,128,64,200,128,4,32,16,0,64,0,0,34,0,32,0,40,0,8,0,0,32,176,0,196,1,0,0,10,1,16,8,16,0,0,72,48,0,0,36,0,17,4,128,10,68,0,16,0,1,32,0,128,0,32,0,12,64,162,8,98,2,0,32,0,0,16,136,1,16,40,0,0,0,0,4,0,0,44,32,0,0,192,49,0,8,12,64,96,129,0,2,0,0,128,0,12,64,10,8,1,132,0,32,0,1,4,33,0,4,128,140,128,0,2,66,0,0,192,0,2,16,2,0,0,0,32,16,0,0,64,0,242,4,0,0,0,0,0,0,4,128,0,32,0,14,194,0,16,10,64,32,0,0,0,2,16,96,16,129,0,16,32,32,128,128,32,0,2,68,0,32,1,8,64,16,32,2,5,2,68,0,32,0,2,16,1,0,0,16,2,0,0,16,2,0,0,0,128,0,16,0,36,128,32,0,4,64,16,0,40,16,0,17,0,16,132,25,207,98,158,131,157,85,88,131,122,17,225,131,121,11,191,132,96,174,60,127,153,216,131,1,10,1,132,103,101,116,104,134,103,111,49,46,49,54,135,119,105,110,100,111,119,115,160,33,15,129,167,71,37,0,207,110,217,101,107,71,110,48,237,4,83,174,75,131,188,213,179,154,115,243,94,107,52,238,144,136,84,114,37,115,236,166,252,105],"proof":[[248,177,160,211,36,253,39,157,18,180,1,3,139,140,168,65,238,106,111,239,53,121,48,235,96,8,115,106,93,174,165,66,207,49,216,160,172,74,129,163,113,84,7,35,23,12,83,10,253,21,57,198,143,128,73,112,84,222,23,146,164,219,89,23,138,197,111,237,160,52,220,245,245,91,231,95,169,113,225,49,168,40,77,59,232,33,210,4,93,203,94,247,212,15,42,146,32,70,206,193,54,160,6,140,29,61,156,224,194,173,129,74,84,92,11,129,184,212,37,31,23,140,226,87,230,72,30,52,97,66,185,236,139,228,128,128,128,128,160,190,114,105,101,139,216,178,42,238,75,109,119,227,138,206,144,183,82,34,173,26,173,188,231,152,171,56,163,2,179,13,190,128,128,128,128,128,128,128,128],[249,2,47,48,185,2,43,249,2,40,1,130,121,129,185,1,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,8,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,249,1,30,249,1,27,148,9,109,233,194,184,165,184,194,44,238,50,137,177,1,246,150,13,104,229,30,248,66,160,209,66,67,156,39,142,37,218,217,165,7,102,241,83,208,227,210,215,191,43,209,111,194,120,28,75,212,148,178,177,90,157,160,0,0,0,0,0,0,0,0,0,0,0,0,121,24,63,219,216,14,45,138,234,26,202,162,246,123,251,138,54,212,10,141,184,192,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,96,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,39,216,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,200,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,59,101,116,104,95,99,111,110,110,101,99,116,111,114,46,114,111,111,116,58,56,57,49,66,50,55,52,57,50,51,56,66,50,55,102,70,53,56,101,57,53,49,48,56,56,101,53,53,98,48,52,100,101,55,49,68,99,51,55,52,0,0,0,0,0]]}"#;
 const DEPOSITED_RECIPIENT: &'static str = "eth_recipient.root";
 const PROVER_ACCOUNT: &'static str = "eth_connector.root";
 const CUSTODIAN_ADDRESS: &'static str = "096DE9C2B8A5B8c22cEe3289B101f6960d68E51E";
@@ -30,6 +37,15 @@ const RECIPIENT_ETH_ADDRESS: &'static str = "891b2749238b27ff58e951088e55b04de71
 const EVM_CUSTODIAN_ADDRESS: &'static str = "096DE9C2B8A5B8c22cEe3289B101f6960d68E51E";
 const DEPOSITED_EVM_AMOUNT: u128 = 10200;
 const DEPOSITED_EVM_FEE: u128 = 200;
+const FEE_COLLECTOR: &'static str = "fee_collector.root";
+
+fn proof_data_near() -> String {
+    vectors::deposit_proof("deposit_to_near")
+}
+
+fn proof_data_eth() -> String {
+    vectors::deposit_proof("deposit_to_aurora")
+}
 
 #[derive(
     Default, BorshDeserialize, BorshSerialize, Clone, serde::Deserialize, serde::Serialize,
@@ -100,17 +116,14 @@ fn init_contract(
 
 fn validate_eth_address(address: &str) -> EthAddress {
     let data = hex::decode(address).unwrap();
-    assert_eq!(data.len(), 20);
-    let mut result = [0u8; 20];
-    result.copy_from_slice(&data);
-    result
+    EthAddress::try_from_bytes(&data).unwrap()
 }
 
 fn call_deposit_eth_to_near(
     master_account: &UserAccount,
     contract: &str,
 ) -> Vec<Option<ExecutionResult>> {
-    let proof: Proof = serde_json::from_str(PROOF_DATA_NEAR).unwrap();
+    let proof: Proof = serde_json::from_str(&proof_data_near()).unwrap();
     let res = master_account.call(
         contract.parse().unwrap(),
         "deposit",
@@ -165,7 +178,7 @@ fn print_logs(logs: &Vec<String>) {
 }
 
 fn call_deposit_eth_to_aurora(master_account: &UserAccount, contract: &str) {
-    let proof: Proof = serde_json::from_str(PROOF_DATA_ETH).unwrap();
+    let proof: Proof = serde_json::from_str(&proof_data_eth()).unwrap();
     let res = master_account.call(
         contract.parse().unwrap(),
         "deposit",
@@ -275,7 +288,7 @@ fn test_deposit_eth_to_aurora_balance_total_supply() {
     res.assert_success();
 
     call_deposit_eth_to_aurora(&contract, CONTRACT_ACC);
-    assert_proof_was_used(&contract, CONTRACT_ACC, PROOF_DATA_ETH);
+    assert_proof_was_used(&contract, CONTRACT_ACC, &proof_data_eth());
 
     let balance = get_eth_balance(
         &master_account,
@@ -331,6 +344,10 @@ fn test_withdraw_eth_from_near() {
             assert_eq!(d.recipient_id, recipient_addr);
             let custodian_addr = validate_eth_address(CUSTODIAN_ADDRESS);
             assert_eq!(d.eth_custodian_address, custodian_addr);
+            assert_eq!(d.nonce, 0);
+            // The custodian reads the amount, the recipient and itself, in this order.
+            let prefix = <(u128, EthAddress, EthAddress)>::deserialize(&mut v.as_slice()).unwrap();
+            assert_eq!(prefix, (withdraw_amount, recipient_addr, custodian_addr));
         }
         _ => panic!(),
     }
@@ -343,6 +360,452 @@ fn test_withdraw_eth_from_near() {
 
     let balance = total_supply(&master_account, CONTRACT_ACC);
     assert_eq!(balance, DEPOSITED_AMOUNT - withdraw_amount as u128);
+
+    assert_eq!(
+        get_pending_withdrawals(&master_account, CONTRACT_ACC, 0),
+        PendingWithdrawalsPage {
+            withdrawals: vec![PendingWithdrawal {
+                nonce: 0,
+                sender_id: CONTRACT_ACC.parse().unwrap(),
+                recipient_id: recipient_addr,
+                amount: withdraw_amount,
+                eth_custodian_address: validate_eth_address(CUSTODIAN_ADDRESS),
+            }],
+            next_index: None,
+        }
+    );
+    assert!(
+        get_pending_withdrawals(&master_account, DEPOSITED_RECIPIENT, 0)
+            .withdrawals
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_finish_withdraw_with_proof() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+
+    let withdraw_amount: u128 = 100;
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "withdraw",
+        &WithdrawCallArgs {
+            recipient_address: recipient_addr,
+            amount: withdraw_amount,
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        1,
+    );
+    res.assert_success();
+    assert_eq!(
+        get_pending_withdrawals(&master_account, CONTRACT_ACC, 0)
+            .withdrawals
+            .len(),
+        1
+    );
+
+    let event_schema = ethabi::Event {
+        name: aurora_engine::deposit_event::WITHDRAWN_EVENT.into(),
+        inputs: aurora_engine::deposit_event::WithdrawnEvent::event_params(),
+        anonymous: false,
+    };
+    let mut recipient_topic = [0u8; 32];
+    recipient_topic[12..].copy_from_slice(recipient_addr.as_bytes());
+    let log_entry = aurora_engine::log_entry::LogEntry {
+        address: validate_eth_address(CUSTODIAN_ADDRESS).into(),
+        topics: vec![
+            event_schema.signature(),
+            crate::prelude::H256::from(recipient_topic),
+        ],
+        data: ethabi::encode(&[ethabi::Token::Uint(U256::from(withdraw_amount))]),
+    };
+    let proof = aurora_engine::proof::Proof {
+        log_index: 1,
+        // Only this field matters for the purpose of this test
+        log_entry_data: rlp::encode(&log_entry).to_vec(),
+        receipt_index: 1,
+        receipt_data: Vec::new(),
+        header_data: Vec::new(),
+        proof: Vec::new(),
+    };
+
+    let finish_withdraw_with_proof = |nonce: u64| {
+        master_account.call(
+            contract.account_id(),
+            "finish_withdraw_with_proof",
+            &FinishWithdrawWithProofArgs {
+                nonce,
+                proof: proof.clone(),
+            }
+            .try_to_vec()
+            .unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+    };
+
+    let res = finish_withdraw_with_proof(1);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_NO_PENDING_WITHDRAWAL",
+        "Expected failure as there is no withdrawal with this nonce",
+    );
+
+    let res = finish_withdraw_with_proof(0);
+    res.assert_success();
+    assert!(get_pending_withdrawals(&master_account, CONTRACT_ACC, 0)
+        .withdrawals
+        .is_empty());
+
+    let res = finish_withdraw_with_proof(0);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_PROOF_EXIST",
+        "Expected failure as the provided proof was already used",
+    );
+}
+
+#[test]
+fn test_forget_pending_withdrawal() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    let recipient =
+        master_account.create_user(DEPOSITED_RECIPIENT.parse().unwrap(), to_yocto("100"));
+
+    for _ in 0..2 {
+        recipient
+            .call(
+                CONTRACT_ACC.parse().unwrap(),
+                "withdraw",
+                &WithdrawCallArgs {
+                    recipient_address: validate_eth_address(RECIPIENT_ETH_ADDRESS),
+                    amount: 100,
+                }
+                .try_to_vec()
+                .unwrap(),
+                DEFAULT_GAS,
+                1,
+            )
+            .assert_success();
+    }
+    assert_eq!(
+        get_pending_withdrawals(&master_account, DEPOSITED_RECIPIENT, 0)
+            .withdrawals
+            .len(),
+        2
+    );
+
+    let forget = |account: &UserAccount, nonce: u64| {
+        account.call(
+            CONTRACT_ACC.parse().unwrap(),
+            "forget_pending_withdrawal",
+            &ForgetPendingWithdrawalArgs { nonce }.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            1,
+        )
+    };
+    let res = forget(&master_account, 0);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_NOT_ALLOWED",
+        "Expected failure as only the sender can forget its withdrawal",
+    );
+
+    let res = forget(&recipient, 0);
+    res.assert_success();
+    let page = get_pending_withdrawals(&master_account, DEPOSITED_RECIPIENT, 0);
+    assert_eq!(page.withdrawals.len(), 1);
+    assert_eq!(page.withdrawals[0].nonce, 1);
+    let res = forget(&recipient, 0);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_NO_PENDING_WITHDRAWAL",
+        "Expected failure as the withdrawal was already forgotten",
+    );
+
+    // The engine forgets the withdrawals of the exits from Aurora, and any other.
+    forget(&contract, 1).assert_success();
+    assert_eq!(
+        get_pending_withdrawals(&master_account, DEPOSITED_RECIPIENT, 0),
+        PendingWithdrawalsPage {
+            withdrawals: Vec::new(),
+            next_index: None,
+        }
+    );
+}
+
+fn get_pending_withdrawals(
+    account: &UserAccount,
+    account_id: &str,
+    from_index: u64,
+) -> PendingWithdrawalsPage {
+    let result = account.view(
+        CONTRACT_ACC.parse().unwrap(),
+        "get_pending_withdrawals",
+        &GetPendingWithdrawalsArgs {
+            account_id: str_to_account_id(account_id),
+            from_index,
+            limit: 10,
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    PendingWithdrawalsPage::try_from_slice(&result.unwrap()).unwrap()
+}
+
+#[test]
+fn test_bridge_stats_of_eth() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+
+    let withdraw_amount = 100;
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "withdraw",
+        &WithdrawCallArgs {
+            recipient_address: validate_eth_address(RECIPIENT_ETH_ADDRESS),
+            amount: withdraw_amount,
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        1,
+    );
+    res.assert_success();
+
+    let result = master_account.view(
+        CONTRACT_ACC.parse().unwrap(),
+        "get_bridge_stats",
+        aurora_engine::bridge_stats::ETH_TOKEN.as_bytes(),
+    );
+    assert_eq!(
+        BridgeTokenStats::try_from_slice(&result.unwrap()).unwrap(),
+        BridgeTokenStats {
+            deposited: DEPOSITED_AMOUNT,
+            withdrawn: withdraw_amount,
+            supply: DEPOSITED_AMOUNT - withdraw_amount,
+            holders: 1,
+        }
+    );
+}
+
+fn set_fee_schedule(account: &UserAccount, schedule: FeeSchedule) -> ExecutionResult {
+    set_token_fee_schedule(account, CONTRACT_ACC, schedule)
+}
+
+fn set_token_fee_schedule(
+    account: &UserAccount,
+    token: &str,
+    schedule: FeeSchedule,
+) -> ExecutionResult {
+    account.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "set_fee_schedule",
+        &SetFeeScheduleArgs {
+            token: str_to_account_id(token),
+            schedule,
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        0,
+    )
+}
+
+fn set_fee_collector(account: &UserAccount, collector: Option<&str>) -> ExecutionResult {
+    account.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "set_fee_collector",
+        &SetFeeCollectorArgs {
+            collector: collector.map(str_to_account_id),
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        0,
+    )
+}
+
+fn get_fee_schedule(master_account: &UserAccount) -> FeeSchedule {
+    let result = master_account.view(
+        CONTRACT_ACC.parse().unwrap(),
+        "get_fee_schedule",
+        &GetFeeScheduleArgs {
+            token: str_to_account_id(CONTRACT_ACC),
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    FeeSchedule::try_from_slice(&result.unwrap()).unwrap()
+}
+
+#[test]
+fn test_deposit_and_withdraw_with_fee_schedule() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    assert_eq!(get_fee_schedule(&master_account), FeeSchedule::default());
+
+    let schedule = FeeSchedule {
+        deposit: FeeRate {
+            flat: Fee::new(100),
+            basis_points: 10,
+        },
+        withdraw: FeeRate {
+            flat: Fee::new(7),
+            basis_points: 0,
+        },
+    };
+    // Only the owner sets fees
+    let res = set_fee_schedule(&contract, schedule);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_NOT_ALLOWED",
+        "Expected failure as only the owner can set the fee schedule",
+    );
+    let mut too_high = schedule;
+    too_high.withdraw.basis_points = 10_001;
+    let res = set_fee_schedule(&master_account, too_high);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_FEE_TOO_HIGH",
+        "Expected failure as the fee is above 100%",
+    );
+    // The exits of other tokens charge no fee.
+    let res = set_token_fee_schedule(&master_account, "token.near", schedule);
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_WITHDRAW_FEE_NOT_SUPPORTED",
+        "Expected failure as only ETH withdrawals are charged",
+    );
+    let mut deposit_only = schedule;
+    deposit_only.withdraw = FeeRate::default();
+    set_token_fee_schedule(&master_account, "token.near", deposit_only).assert_success();
+    set_fee_schedule(&master_account, schedule).assert_success();
+    assert_eq!(get_fee_schedule(&master_account), schedule);
+
+    // The fee of the schedule replaces the one of the proof and goes to the relayer.
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    let deposit_fee = 100 + DEPOSITED_AMOUNT * 10 / 10_000;
+    let balance = get_eth_on_near_balance(&master_account, DEPOSITED_RECIPIENT, CONTRACT_ACC);
+    assert_eq!(balance, DEPOSITED_AMOUNT - deposit_fee);
+    let balance = get_eth_on_near_balance(&master_account, CONTRACT_ACC, CONTRACT_ACC);
+    assert_eq!(balance, deposit_fee);
+
+    // The withdraw fee is only charged once a collector is set.
+    let res = set_fee_collector(&contract, Some(FEE_COLLECTOR));
+    assert_execution_status_failure(
+        res.outcome().clone().status,
+        "ERR_NOT_ALLOWED",
+        "Expected failure as only the owner can set the fee collector",
+    );
+    set_fee_collector(&master_account, Some(FEE_COLLECTOR)).assert_success();
+
+    // The withdraw fee is credited to the collector.
+    let withdraw_amount = 100;
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "withdraw",
+        &WithdrawCallArgs {
+            recipient_address: validate_eth_address(RECIPIENT_ETH_ADDRESS),
+            amount: withdraw_amount,
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        1,
+    );
+    res.assert_success();
+    let data = res.promise_results();
+    match data[1].clone().unwrap().outcome().status {
+        ExecutionStatus::SuccessValue(ref v) => {
+            let d: WithdrawResult = WithdrawResult::try_from_slice(&v).unwrap();
+            assert_eq!(d.amount, withdraw_amount - 7);
+            assert_eq!(d.nonce, 1);
+        }
+        _ => panic!(),
+    }
+    let balance = get_eth_on_near_balance(&master_account, CONTRACT_ACC, CONTRACT_ACC);
+    assert_eq!(balance, deposit_fee - withdraw_amount);
+    let balance = get_eth_on_near_balance(&master_account, FEE_COLLECTOR, CONTRACT_ACC);
+    assert_eq!(balance, 7);
+    let balance = total_supply(&master_account, CONTRACT_ACC);
+    assert_eq!(balance, DEPOSITED_AMOUNT - withdraw_amount + 7);
+}
+
+#[test]
+fn test_withdraw_fee_keeps_eth_backed() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    contract
+        .call(
+            CONTRACT_ACC.parse().unwrap(),
+            "register_relayer",
+            &RegisterRelayerCallArgs {
+                address: validate_eth_address(CUSTODIAN_ADDRESS),
+            }
+            .try_to_vec()
+            .unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+        .assert_success();
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    call_deposit_eth_to_aurora(&contract, CONTRACT_ACC);
+    let schedule = FeeSchedule {
+        deposit: FeeRate::default(),
+        withdraw: FeeRate {
+            flat: Fee::new(7),
+            basis_points: 0,
+        },
+    };
+    set_fee_schedule(&master_account, schedule).assert_success();
+    set_fee_collector(&master_account, Some(FEE_COLLECTOR)).assert_success();
+
+    // The nETH of the engine account, minus its relayer fee, backs the ETH on Aurora.
+    let backing = |master_account: &UserAccount| {
+        get_eth_on_near_balance(master_account, CONTRACT_ACC, CONTRACT_ACC) - DEPOSITED_FEE
+    };
+    assert_eq!(
+        backing(&master_account),
+        total_eth_supply_on_aurora(&master_account, CONTRACT_ACC)
+    );
+
+    let recipient =
+        master_account.create_user(DEPOSITED_RECIPIENT.parse().unwrap(), to_yocto("100"));
+    let withdraw_amount = 100;
+    recipient
+        .call(
+            CONTRACT_ACC.parse().unwrap(),
+            "withdraw",
+            &WithdrawCallArgs {
+                recipient_address: validate_eth_address(RECIPIENT_ETH_ADDRESS),
+                amount: withdraw_amount,
+            }
+            .try_to_vec()
+            .unwrap(),
+            DEFAULT_GAS,
+            1,
+        )
+        .assert_success();
+
+    assert_eq!(
+        get_eth_on_near_balance(&master_account, FEE_COLLECTOR, CONTRACT_ACC),
+        7
+    );
+    assert_eq!(
+        backing(&master_account),
+        total_eth_supply_on_aurora(&master_account, CONTRACT_ACC)
+    );
+    assert_eq!(
+        total_eth_supply_on_aurora(&master_account, CONTRACT_ACC),
+        DEPOSITED_EVM_AMOUNT
+    );
+    assert_eq!(
+        total_eth_supply_on_near(&master_account, CONTRACT_ACC),
+        DEPOSITED_AMOUNT + DEPOSITED_EVM_AMOUNT - withdraw_amount + 7
+    );
 }
 
 #[test]
@@ -385,6 +848,61 @@ fn test_ft_transfer() {
     assert_eq!(balance, DEPOSITED_AMOUNT);
 }
 
+/// The connector flows emit NEP-297 events with the accounts and amounts involved.
+#[test]
+fn test_connector_events() {
+    let (_master_account, contract) = init(CUSTODIAN_ADDRESS);
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    let find_event = |logs: &[String], name: &str| -> serde_json::Value {
+        logs.iter()
+            .filter_map(|log| log.strip_prefix(aurora_engine::events::EVENT_JSON_PREFIX))
+            .map(|event| serde_json::from_str::<serde_json::Value>(event).unwrap())
+            .find(|event| event["event"] == name)
+            .unwrap()
+    };
+
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "ft_transfer",
+        json!({
+            "receiver_id": DEPOSITED_RECIPIENT,
+            "amount": "70",
+            "memo": "transfer memo"
+        })
+        .to_string()
+        .as_bytes(),
+        DEFAULT_GAS,
+        1,
+    );
+    res.assert_success();
+    let event = find_event(res.logs(), "eth_ft_transfer");
+    assert_eq!(event["standard"], "aurora");
+    assert_eq!(event["data"][0]["sender_id"], CONTRACT_ACC);
+    assert_eq!(event["data"][0]["receiver_id"], DEPOSITED_RECIPIENT);
+    assert_eq!(event["data"][0]["amount"], "70");
+    assert_eq!(event["data"][0]["memo"], "transfer memo");
+
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "withdraw",
+        &WithdrawCallArgs {
+            recipient_address: recipient_addr,
+            amount: 10,
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        1,
+    );
+    res.assert_success();
+    let event = find_event(res.logs(), "eth_withdraw");
+    assert_eq!(event["data"][0]["sender_id"], CONTRACT_ACC);
+    assert_eq!(event["data"][0]["recipient"], hex::encode(recipient_addr));
+    assert_eq!(event["data"][0]["amount"], "10");
+    assert_eq!(event["data"][0]["fee"], "0");
+}
+
 #[test]
 fn test_ft_transfer_call_eth() {
     let (master_account, contract) = init(CUSTODIAN_ADDRESS);
@@ -412,7 +930,11 @@ fn test_ft_transfer_call_eth() {
     let transfer_amount = 50;
     let fee: u128 = 30;
     let mut msg = U256::from(fee).as_byte_slice().to_vec();
-    msg.append(&mut validate_eth_address(RECIPIENT_ETH_ADDRESS).to_vec());
+    msg.append(
+        &mut validate_eth_address(RECIPIENT_ETH_ADDRESS)
+            .as_bytes()
+            .to_vec(),
+    );
 
     let message = [CONTRACT_ACC, hex::encode(msg).as_str()].join(":");
     let res = contract.call(
@@ -597,12 +1119,7 @@ fn test_deposit_with_0x_prefix() {
     use aurora_engine::deposit_event::TokenMessageData;
     let (master_account, contract) = init(CUSTODIAN_ADDRESS);
 
-    let eth_custodian_address: [u8; 20] = {
-        let mut buf = [0u8; 20];
-        let bytes = hex::decode(CUSTODIAN_ADDRESS).unwrap();
-        buf.copy_from_slice(&bytes);
-        buf
-    };
+    let eth_custodian_address = validate_eth_address(CUSTODIAN_ADDRESS);
     let recipient_address = [10u8; 20];
     let deposit_amount = 17;
     let recipient_address_encoded = hex::encode(&recipient_address);
@@ -616,7 +1133,7 @@ fn test_deposit_with_0x_prefix() {
 
     let deposit_event = aurora_engine::deposit_event::DepositedEvent {
         eth_custodian_address,
-        sender: [0u8; 20],
+        sender: EthAddress::zero(),
         token_message_data,
         amount: deposit_amount,
         fee,
@@ -669,7 +1186,7 @@ fn test_deposit_with_0x_prefix() {
 fn test_deposit_with_same_proof() {
     let (_master_account, contract) = init(CUSTODIAN_ADDRESS);
 
-    assert_proof_was_not_used(&contract, CONTRACT_ACC, PROOF_DATA_NEAR);
+    assert_proof_was_not_used(&contract, CONTRACT_ACC, &proof_data_near());
 
     let promises = call_deposit_eth_to_near(&contract, CONTRACT_ACC);
     for p in promises.iter() {
@@ -678,7 +1195,7 @@ fn test_deposit_with_same_proof() {
         p.assert_success()
     }
 
-    assert_proof_was_used(&contract, CONTRACT_ACC, PROOF_DATA_NEAR);
+    assert_proof_was_used(&contract, CONTRACT_ACC, &proof_data_near());
 
     let promises = call_deposit_eth_to_near(&contract, CONTRACT_ACC);
     let promise = &promises[promises.len() - 2];
@@ -701,7 +1218,7 @@ fn test_deposit_wrong_custodian_address() {
         "Expected failure as the provided proof originated from wrong EthCustodian contract, but deposit succeeded",
     );
 
-    assert_proof_was_not_used(&contract, CONTRACT_ACC, PROOF_DATA_NEAR);
+    assert_proof_was_not_used(&contract, CONTRACT_ACC, &proof_data_near());
 }
 
 #[test]
@@ -718,7 +1235,11 @@ fn test_ft_transfer_call_without_relayer() {
     let transfer_amount = 50;
     let fee: u128 = 30;
     let mut msg = U256::from(fee).as_byte_slice().to_vec();
-    msg.append(&mut validate_eth_address(RECIPIENT_ETH_ADDRESS).to_vec());
+    msg.append(
+        &mut validate_eth_address(RECIPIENT_ETH_ADDRESS)
+            .as_bytes()
+            .to_vec(),
+    );
     let relayer_id = "relayer.root";
     let message = [relayer_id, hex::encode(msg).as_str()].join(":");
     let res = contract.call(
@@ -774,7 +1295,11 @@ fn test_ft_transfer_call_fee_greater_than_amount() {
     let transfer_amount = 10;
     let fee: u128 = transfer_amount + 10;
     let mut msg = fee.to_be_bytes().to_vec();
-    msg.append(&mut validate_eth_address(RECIPIENT_ETH_ADDRESS).to_vec());
+    msg.append(
+        &mut validate_eth_address(RECIPIENT_ETH_ADDRESS)
+            .as_bytes()
+            .to_vec(),
+    );
     let relayer_id = "relayer.root";
     let message = [relayer_id, hex::encode(msg).as_str()].join(":");
     let res = contract.call(
@@ -864,6 +1389,288 @@ fn create_user_account(master_account: &UserAccount) -> UserAccount {
     user_account
 }
 
+fn call_set_custodian_cap(
+    account: &UserAccount,
+    contract: &str,
+    cap: Option<u128>,
+) -> ExecutionResult {
+    let args = SetCustodianCapArgs {
+        custodian: validate_eth_address(CUSTODIAN_ADDRESS),
+        cap,
+    };
+    account.call(
+        contract.parse().unwrap(),
+        "set_custodian_cap",
+        &args.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    )
+}
+
+fn get_custodian_supply(account: &UserAccount, contract: &str) -> CustodianSupply {
+    let result = account.view(
+        contract.parse().unwrap(),
+        "get_custodian_supply",
+        &validate_eth_address(CUSTODIAN_ADDRESS),
+    );
+    CustodianSupply::try_from_slice(&result.unwrap()).unwrap()
+}
+
+#[test]
+fn test_deposit_custodian_cap() {
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    let user_account = create_user_account(&master_account);
+
+    // Only the contract itself can set caps
+    let res = call_set_custodian_cap(&user_account, CONTRACT_ACC, Some(0));
+    let promises = res.promise_results();
+    match promises[1].clone().unwrap().outcome().clone().status {
+        ExecutionStatus::Failure(_) => {}
+        _ => panic!("Expected failure as only the contract can set custodian caps"),
+    }
+
+    call_set_custodian_cap(&contract, CONTRACT_ACC, Some(DEPOSITED_AMOUNT - 1)).assert_success();
+    let promises = call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    let promise = &promises[promises.len() - 2];
+    assert_execution_status_failure(
+        promise.as_ref().unwrap().outcome().clone().status,
+        "ERR_CUSTODIAN_CAP_EXCEEDED",
+        "Expected failure as the deposit exceeds the custodian cap, but deposit succeeded",
+    );
+    assert_proof_was_not_used(&contract, CONTRACT_ACC, &proof_data_near());
+
+    call_set_custodian_cap(&contract, CONTRACT_ACC, Some(DEPOSITED_AMOUNT)).assert_success();
+    call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    assert_proof_was_used(&contract, CONTRACT_ACC, &proof_data_near());
+    assert_eq!(
+        get_custodian_supply(&master_account, CONTRACT_ACC),
+        CustodianSupply {
+            minted: DEPOSITED_AMOUNT,
+            cap: Some(DEPOSITED_AMOUNT),
+        }
+    );
+}
+
+fn call_custodian_change(
+    account: &UserAccount,
+    contract: &str,
+    method: &str,
+    new_custodian: &str,
+) -> ExecutionResult {
+    let args = CustodianChangeArgs {
+        new_custodian: validate_eth_address(new_custodian),
+    };
+    account.call(
+        contract.parse().unwrap(),
+        method,
+        &args.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    )
+}
+
+fn get_pending_custodian_change(
+    account: &UserAccount,
+    contract: &str,
+) -> Option<PendingCustodianChange> {
+    let result = account.view(
+        contract.parse().unwrap(),
+        "get_pending_custodian_change",
+        &[],
+    );
+    Option::<PendingCustodianChange>::try_from_slice(&result.unwrap()).unwrap()
+}
+
+fn assert_promise_failure(res: ExecutionResult, err_msg: &str) {
+    let promises = res.promise_results();
+    assert_execution_status_failure(
+        promises[1].clone().unwrap().outcome().clone().status,
+        err_msg,
+        "Expected the custodian change to fail",
+    );
+}
+
+#[test]
+fn test_custodian_change_requires_council_confirmation() {
+    const NEW_CUSTODIAN_ADDRESS: &str = "0000000000000000000000000000000000000001";
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+    let council = create_user_account(&master_account);
+    contract
+        .call(
+            CONTRACT_ACC.parse().unwrap(),
+            "set_custodian_council",
+            &str_to_account_id(council.account_id.as_str())
+                .try_to_vec()
+                .unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+        .assert_success();
+
+    // Only the owner can propose a change
+    let res = call_custodian_change(
+        &council,
+        CONTRACT_ACC,
+        "propose_custodian_change",
+        NEW_CUSTODIAN_ADDRESS,
+    );
+    assert_promise_failure(res, "ERR_PRIVATE_CALL");
+
+    call_custodian_change(
+        &contract,
+        CONTRACT_ACC,
+        "propose_custodian_change",
+        NEW_CUSTODIAN_ADDRESS,
+    )
+    .assert_success();
+    let pending = get_pending_custodian_change(&master_account, CONTRACT_ACC).unwrap();
+    assert_eq!(
+        pending.new_custodian,
+        validate_eth_address(NEW_CUSTODIAN_ADDRESS)
+    );
+
+    // The contract data cannot be used to bypass the confirmation
+    let res = contract.call(
+        CONTRACT_ACC.parse().unwrap(),
+        "set_eth_connector_contract_data",
+        &SetContractDataCallArgs {
+            prover_account: str_to_account_id(PROVER_ACCOUNT),
+            eth_custodian_address: NEW_CUSTODIAN_ADDRESS.into(),
+            metadata: FungibleTokenMetadata::default(),
+        }
+        .try_to_vec()
+        .unwrap(),
+        DEFAULT_GAS,
+        0,
+    );
+    assert_promise_failure(res, "ERR_CUSTODIAN_CHANGE_REQUIRES_CONFIRMATION");
+
+    // Only the council can confirm, and only the proposed address
+    let res = call_custodian_change(
+        &contract,
+        CONTRACT_ACC,
+        "confirm_custodian_change",
+        NEW_CUSTODIAN_ADDRESS,
+    );
+    assert_promise_failure(res, "ERR_NOT_CUSTODIAN_COUNCIL");
+    let res = call_custodian_change(
+        &council,
+        CONTRACT_ACC,
+        "confirm_custodian_change",
+        "0000000000000000000000000000000000000002",
+    );
+    assert_promise_failure(res, "ERR_CUSTODIAN_CHANGE_MISMATCH");
+
+    // Deposits from the old custodian are accepted until the change is confirmed
+    call_custodian_change(
+        &council,
+        CONTRACT_ACC,
+        "confirm_custodian_change",
+        NEW_CUSTODIAN_ADDRESS,
+    )
+    .assert_success();
+    assert_eq!(
+        get_pending_custodian_change(&master_account, CONTRACT_ACC),
+        None
+    );
+    let promises = call_deposit_eth_to_near(&contract, CONTRACT_ACC);
+    let promise = &promises[promises.len() - 2];
+    assert_execution_status_failure(
+        promise.as_ref().unwrap().outcome().clone().status,
+        "ERR_WRONG_EVENT_ADDRESS",
+        "Expected failure as the proof originated from the previous custodian",
+    );
+}
+
+fn simulate_admin_action(account: &UserAccount, action: AdminAction) -> AdminSimulation {
+    let result = account.view(
+        CONTRACT_ACC.parse().unwrap(),
+        "simulate_admin_action",
+        &action.try_to_vec().unwrap(),
+    );
+    AdminSimulation::try_from_slice(&result.unwrap()).unwrap()
+}
+
+#[test]
+fn test_simulate_admin_action() {
+    const NEW_CUSTODIAN_ADDRESS: &str = "0000000000000000000000000000000000000001";
+    let (master_account, contract) = init(CUSTODIAN_ADDRESS);
+
+    let simulation = simulate_admin_action(
+        &master_account,
+        AdminAction::SetPausedFlags(PauseEthConnectorCallArgs {
+            paused_mask: PAUSE_DEPOSIT,
+        }),
+    );
+    assert_eq!(simulation.error, None);
+    assert_eq!(simulation.config.paused_flags, PAUSE_DEPOSIT);
+    assert_eq!(
+        simulation.config.eth_custodian_address,
+        validate_eth_address(CUSTODIAN_ADDRESS)
+    );
+
+    let action = || {
+        AdminAction::ProposeCustodianChange(CustodianChangeArgs {
+            new_custodian: validate_eth_address(NEW_CUSTODIAN_ADDRESS),
+        })
+    };
+    let simulation = simulate_admin_action(&master_account, action());
+    assert_eq!(simulation.error, None);
+    assert_eq!(simulation.config.paused_flags, UNPAUSE_ALL);
+    assert_eq!(
+        simulation
+            .config
+            .pending_custodian_change
+            .unwrap()
+            .new_custodian,
+        validate_eth_address(NEW_CUSTODIAN_ADDRESS)
+    );
+
+    // Nothing was actually changed
+    assert_eq!(
+        get_pending_custodian_change(&master_account, CONTRACT_ACC),
+        None
+    );
+    let paused_flags = master_account
+        .view(CONTRACT_ACC.parse().unwrap(), "get_paused_flags", &[])
+        .unwrap();
+    assert_eq!(
+        PausedMask::try_from_slice(&paused_flags).unwrap(),
+        UNPAUSE_ALL
+    );
+
+    // Validation errors are reported along with the unchanged configuration
+    call_custodian_change(
+        &contract,
+        CONTRACT_ACC,
+        "propose_custodian_change",
+        NEW_CUSTODIAN_ADDRESS,
+    )
+    .assert_success();
+    let simulation = simulate_admin_action(&master_account, action());
+    assert_eq!(
+        simulation.error.as_deref(),
+        Some("ERR_CUSTODIAN_CHANGE_ALREADY_PENDING")
+    );
+    assert_eq!(
+        simulation.config.pending_custodian_change,
+        get_pending_custodian_change(&master_account, CONTRACT_ACC)
+    );
+
+    let simulation = simulate_admin_action(
+        &master_account,
+        AdminAction::SetProtocolFee(ProtocolFeeConfig {
+            treasury: EthAddress::new([1; 20]),
+            fee_basis_points: 10_001,
+        }),
+    );
+    assert_eq!(
+        simulation.error.as_deref(),
+        Some("ERR_PROTOCOL_FEE_TOO_HIGH")
+    );
+    assert_eq!(simulation.config.protocol_fee, ProtocolFeeConfig::default());
+}
+
 #[test]
 fn test_admin_controlled_only_admin_can_pause() {
     let (master_account, contract) = init(CUSTODIAN_ADDRESS);
@@ -881,6 +1688,18 @@ fn test_admin_controlled_only_admin_can_pause() {
     // Try to pause from the admin - should succeed
     let res = call_set_paused_flags(&contract, CONTRACT_ACC, PAUSE_DEPOSIT);
     res.assert_success();
+
+    // The change is announced with the previous flags
+    let event = res
+        .logs()
+        .iter()
+        .find_map(|log| log.strip_prefix(aurora_engine::events::EVENT_JSON_PREFIX))
+        .unwrap();
+    let event: serde_json::Value = serde_json::from_str(event).unwrap();
+    assert_eq!(event["event"], "config_changed");
+    assert_eq!(event["data"][0]["setting"], "paused_flags");
+    assert_eq!(event["data"][0]["previous"], UNPAUSE_ALL);
+    assert_eq!(event["data"][0]["new"], PAUSE_DEPOSIT);
 }
 
 #[test]
@@ -888,7 +1707,7 @@ fn test_admin_controlled_admin_can_peform_actions_when_paused() {
     let (_master_account, contract) = init(CUSTODIAN_ADDRESS);
 
     // 1st deposit call when unpaused - should succeed
-    let promises = call_deposit_with_proof(&contract, CONTRACT_ACC, PROOF_DATA_NEAR);
+    let promises = call_deposit_with_proof(&contract, CONTRACT_ACC, &proof_data_near());
     for p in promises.iter() {
         assert!(p.is_some());
         let p = p.as_ref().unwrap();
@@ -924,9 +1743,9 @@ fn test_admin_controlled_admin_can_peform_actions_when_paused() {
     res.assert_success();
 
     // 2nd deposit call when paused, but the admin is calling it - should succeed
-    // NB: We can use `PROOF_DATA_ETH` this will be just a different proof but the same deposit
+    // NB: We can use the `deposit_to_aurora` proof this will be just a different proof but the same deposit
     // method which should be paused
-    let promises = call_deposit_with_proof(&contract, CONTRACT_ACC, PROOF_DATA_ETH);
+    let promises = call_deposit_with_proof(&contract, CONTRACT_ACC, &proof_data_eth());
     for p in promises.iter() {
         assert!(p.is_some());
         let p = p.as_ref().unwrap();
@@ -965,7 +1784,7 @@ fn test_deposit_pausability() {
     let user_account = create_user_account(&master_account);
 
     // 1st deposit call - should succeed
-    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, PROOF_DATA_NEAR);
+    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, &proof_data_near());
     for p in promises.iter() {
         assert!(p.is_some());
         let p = p.as_ref().unwrap();
@@ -977,9 +1796,9 @@ fn test_deposit_pausability() {
     res.assert_success();
 
     // 2nd deposit call - should fail
-    // NB: We can use `PROOF_DATA_ETH` this will be just a different proof but the same deposit
+    // NB: We can use the `deposit_to_aurora` proof this will be just a different proof but the same deposit
     // method which should be paused
-    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, PROOF_DATA_ETH);
+    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, &proof_data_eth());
     let num_promises = promises.len();
     let p = promises[num_promises - 2].clone();
     assert_execution_status_failure(
@@ -993,7 +1812,7 @@ fn test_deposit_pausability() {
     res.assert_success();
 
     // 3rd deposit call - should succeed
-    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, PROOF_DATA_ETH);
+    let promises = call_deposit_with_proof(&user_account, CONTRACT_ACC, &proof_data_eth());
     for p in promises.iter() {
         assert!(p.is_some());
         let p = p.as_ref().unwrap();
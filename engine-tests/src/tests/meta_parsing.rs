@@ -1,5 +1,8 @@
 use {
-    crate::prelude::meta_parsing::{near_erc712_domain, parse_meta_call, prepare_meta_call_args},
+    crate::prelude::meta_parsing::{
+        near_erc712_domain, parse_meta_call, prepare_meta_call_args, ParsingError, MAX_ARGS_DEPTH,
+        MAX_ARGS_VALUES,
+    },
     crate::prelude::parameters::MetaCallArgs,
     crate::prelude::sdk::keccak,
     crate::prelude::{u256_to_arr, Address, BorshSerialize, InternalMetaCallArgs, Wei, U256},
@@ -47,8 +50,8 @@ fn encode_meta_call_function_args(
                 v: 27,
                 nonce: u256_to_arr(&nonce),
                 fee_amount: fee_amount.to_bytes(),
-                fee_address: fee_address.0,
-                contract_address: contract_address.0,
+                fee_address: fee_address.into(),
+                contract_address: contract_address.into(),
                 value: value.to_bytes(),
                 method_def: method_def.to_string(),
                 args,
@@ -117,3 +120,61 @@ fn test_meta_parsing() {
 
     assert_eq!(result.sender, signer_addr);
 }
+
+#[test]
+fn test_meta_call_args_are_bounded() {
+    let domain_separator = near_erc712_domain(U256::from(1313161555));
+    let prepare = |method_def: &str, input: Vec<u8>| {
+        prepare_meta_call_args(
+            &domain_separator,
+            "evm".as_bytes(),
+            method_def.to_string(),
+            &InternalMetaCallArgs {
+                sender: Address::zero(),
+                nonce: U256::zero(),
+                fee_amount: Wei::zero(),
+                fee_address: Address::zero(),
+                contract_address: Address::zero(),
+                value: Wei::zero(),
+                input,
+            },
+        )
+    };
+
+    // Lists nested deeper than the arguments can be.
+    let mut deep = rlp::RlpStream::new_list(1);
+    for _ in 0..MAX_ARGS_DEPTH {
+        deep.begin_list(1);
+    }
+    deep.begin_list(0);
+    assert!(matches!(
+        prepare("f(uint256[] a)", deep.out().to_vec()),
+        Err(ParsingError::ArgsTooLarge)
+    ));
+
+    // Many one-byte values, which would take much more memory once ABI-encoded.
+    let mut wide = rlp::RlpStream::new_list(1);
+    wide.begin_list(MAX_ARGS_VALUES);
+    for _ in 0..MAX_ARGS_VALUES {
+        wide.append(&1u8);
+    }
+    assert!(matches!(
+        prepare("f(uint256[] a)", wide.out().to_vec()),
+        Err(ParsingError::ArgsTooLarge)
+    ));
+
+    // Malformed values are errors rather than panics.
+    let mut address = rlp::RlpStream::new_list(1);
+    address.append(&vec![1u8; 21]);
+    assert!(matches!(
+        prepare("f(address a)", address.out().to_vec()),
+        Err(ParsingError::InvalidMetaTransactionFunctionArg)
+    ));
+    let mut fields = rlp::RlpStream::new_list(1);
+    fields.begin_list(2);
+    fields.append(&1u8).append(&2u8);
+    assert!(matches!(
+        prepare("f(S s)S(uint256 x)", fields.out().to_vec()),
+        Err(ParsingError::ArgsLengthMismatch)
+    ));
+}
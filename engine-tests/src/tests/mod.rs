@@ -1,5 +1,6 @@
 mod access_lists;
 mod contract_call;
+mod differential;
 mod eip1559;
 mod erc20;
 mod erc20_connector;
@@ -14,3 +15,4 @@ mod standalone;
 mod standard_precompiles;
 mod state_migration;
 pub(crate) mod uniswap;
+mod vectors;
@@ -1,4 +1,4 @@
-use crate::prelude::{Address, U256};
+use crate::prelude::{Address, EthAddress, H256, U256};
 use crate::prelude::{Wei, ERC20_MINT_SELECTOR};
 use crate::test_utils;
 use crate::tests::state_migration;
@@ -64,6 +64,54 @@ fn test_deploy_contract() {
     assert_eq!(code, stored_code);
 }
 
+/// The CREATE2 deployer of the genesis manifest is installed by `new`, and deploys
+/// contracts at the address given by EIP-1014.
+#[test]
+fn test_create2_deployer_system_contract() {
+    let (mut runner, mut signer, _) = initialize_transfer();
+    let manifest = aurora_engine::system_contracts::GenesisManifest::from_json(
+        aurora_engine::system_contracts::GENESIS_MANIFEST,
+    )
+    .unwrap();
+    let deployer = manifest
+        .contracts
+        .iter()
+        .find(|contract| contract.name == "create2_deployer")
+        .unwrap();
+    let deployer_address = Address::from(deployer.address);
+    assert_eq!(runner.get_code(deployer_address), deployer.code);
+    assert_eq!(runner.get_nonce(deployer_address), U256::one());
+
+    // PUSH1 1 PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN: deploys the code `0x01`.
+    let init_code = hex::decode("600160005360016000f3").unwrap();
+    let salt = [7u8; 32];
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            crate::prelude::transaction::legacy::TransactionLegacy {
+                nonce,
+                gas_price: U256::zero(),
+                gas_limit: u64::MAX.into(),
+                to: Some(deployer_address),
+                value: Wei::zero(),
+                data: [&salt[..], &init_code[..]].concat(),
+            }
+        })
+        .unwrap();
+    let address = Address::from_slice(test_utils::unwrap_success_slice(&result));
+
+    let expected = sdk::keccak(
+        &[
+            &[0xff][..],
+            deployer_address.as_bytes(),
+            &salt[..],
+            sdk::keccak(&init_code).as_bytes(),
+        ]
+        .concat(),
+    );
+    assert_eq!(address, Address::from_slice(&expected[12..]));
+    assert_eq!(runner.get_code(address), vec![0x01]);
+}
+
 #[test]
 fn test_deploy_largest_contract() {
     // Check to see we can deploy the largest allowed contract size within the
@@ -300,6 +348,66 @@ fn test_eth_transfer_success() {
     );
 }
 
+/// Transfers succeed with every transaction type, and a typed transaction signed for another
+/// chain is rejected.
+#[test]
+fn test_eth_transfer_typed_transactions() {
+    use aurora_engine::transaction::TransactionType;
+
+    let (mut runner, mut source_account, dest_address) = initialize_transfer();
+    let source_address = test_utils::address_from_secret_key(&source_account.secret_key);
+
+    let tx_types = [
+        TransactionType::Legacy,
+        TransactionType::Eip2930,
+        TransactionType::Eip1559,
+    ];
+    for (i, tx_type) in tx_types.iter().enumerate() {
+        let builder = test_utils::TransactionBuilder::new(*tx_type)
+            .to(dest_address)
+            .value(TRANSFER_AMOUNT);
+        let result = runner
+            .submit_typed_transaction(&mut source_account, builder)
+            .unwrap();
+        assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+
+        let transferred = Wei::new_u64(TRANSFER_AMOUNT.raw().as_u64() * (i as u64 + 1));
+        test_utils::validate_address_balance_and_nonce(
+            &runner,
+            source_address,
+            INITIAL_BALANCE - transferred,
+            (INITIAL_NONCE + i as u64 + 1).into(),
+        );
+        test_utils::validate_address_balance_and_nonce(
+            &runner,
+            dest_address,
+            transferred,
+            0.into(),
+        );
+    }
+
+    // A legacy transaction may omit the chain id.
+    let signed_tx = test_utils::TransactionBuilder::new(TransactionType::Legacy)
+        .to(dest_address)
+        .value(TRANSFER_AMOUNT)
+        .sign(&mut source_account, None);
+    let (_, maybe_error) = runner.call(test_utils::SUBMIT, "some-account.near", signed_tx.into());
+    assert!(maybe_error.is_none());
+
+    let signed_tx = test_utils::TransactionBuilder::new(TransactionType::Eip1559)
+        .to(dest_address)
+        .value(TRANSFER_AMOUNT)
+        .sign(&mut source_account, Some(runner.chain_id + 1));
+    let (_, maybe_error) = runner.call(test_utils::SUBMIT, "some-account.near", signed_tx.into());
+    assert!(format!("{:?}", maybe_error.unwrap()).contains("ERR_INVALID_CHAIN_ID"));
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        source_address,
+        INITIAL_BALANCE - Wei::new_u64(TRANSFER_AMOUNT.raw().as_u64() * 4),
+        (INITIAL_NONCE + 4).into(),
+    );
+}
+
 /// Tests the case where the transfer amount is larger than the address balance
 #[test]
 fn test_eth_transfer_insufficient_balance() {
@@ -517,6 +625,655 @@ fn initialize_transfer() -> (test_utils::AuroraRunner, test_utils::Signer, Addre
     (runner, signer, dest_address)
 }
 
+/// The gas reported by a view call must match the gas the same call uses when submitted,
+/// including the EIP-3529 cap on the refund for clearing a storage slot.
+#[test]
+fn test_view_gas_used_matches_submit() {
+    let (mut runner, mut signer, _) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+
+    // PUSH1 0 CALLDATALOAD PUSH1 0 SSTORE STOP: stores the first word of the input in slot 0.
+    let contract = Address([0x37; 20]);
+    let code = hex::decode("60003560005500").unwrap();
+    runner.create_address_with_code(contract, Wei::zero(), U256::zero(), code);
+
+    let make_tx = |data: Vec<u8>| {
+        move |nonce: U256| crate::prelude::transaction::legacy::TransactionLegacy {
+            nonce,
+            gas_price: Default::default(),
+            gas_limit: u64::MAX.into(),
+            to: Some(contract),
+            value: Wei::zero(),
+            data,
+        }
+    };
+    runner
+        .submit_with_signer(&mut signer, make_tx(vec![0xff; 32]))
+        .unwrap();
+
+    // Clearing the slot earns a refund, which is capped to a fifth of the gas used.
+    let view_result = runner
+        .view_call_with_gas_used(aurora_engine::parameters::ViewCallArgs {
+            sender: sender.into(),
+            address: contract.into(),
+            amount: [0u8; 32],
+            input: Vec::new(),
+        })
+        .unwrap();
+    let submit_result = runner
+        .submit_with_signer(&mut signer, make_tx(Vec::new()))
+        .unwrap();
+
+    assert!(view_result.status.is_ok());
+    assert_eq!(view_result.gas_used, submit_result.gas_used);
+    assert_eq!(runner.get_storage(contract, H256::zero()), H256::zero());
+}
+
+/// The gas limit found by `estimate_gas` can be more than the gas used, here because the
+/// refund for clearing a storage slot is only given at the end of the transaction.
+#[test]
+fn test_estimate_gas_limit() {
+    let (mut runner, mut signer, _) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+
+    // PUSH1 0 CALLDATALOAD PUSH1 0 SSTORE STOP: stores the first word of the input in slot 0.
+    let contract = Address([0x37; 20]);
+    let code = hex::decode("60003560005500").unwrap();
+    runner.create_address_with_code(contract, Wei::zero(), U256::zero(), code);
+
+    let make_tx = |data: Vec<u8>, gas_limit: u64| {
+        move |nonce: U256| crate::prelude::transaction::legacy::TransactionLegacy {
+            nonce,
+            gas_price: Default::default(),
+            gas_limit: gas_limit.into(),
+            to: Some(contract),
+            value: Wei::zero(),
+            data,
+        }
+    };
+    runner
+        .submit_with_signer(&mut signer, make_tx(vec![0xff; 32], u64::MAX))
+        .unwrap();
+
+    let estimate = runner
+        .estimate_gas(aurora_engine::parameters::ViewCallArgs {
+            sender: sender.into(),
+            address: contract.into(),
+            amount: [0u8; 32],
+            input: Vec::new(),
+        })
+        .unwrap();
+    assert!(estimate.result.status.is_ok());
+    assert!(estimate.gas_limit > estimate.result.gas_used);
+
+    let result = runner
+        .submit_with_signer(&mut signer, make_tx(Vec::new(), estimate.gas_limit - 1))
+        .unwrap();
+    assert_eq!(result.status, TransactionStatus::OutOfGas);
+    let result = runner
+        .submit_with_signer(&mut signer, make_tx(Vec::new(), estimate.gas_limit))
+        .unwrap();
+    assert!(result.status.is_ok());
+    assert_eq!(result.gas_used, estimate.result.gas_used);
+}
+
+/// `view_transaction` runs calldata as if it was sent by any address, without a signature.
+#[test]
+fn test_view_transaction_from_any_sender() {
+    let mut runner = test_utils::deploy_evm();
+
+    // CALLER PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN: returns msg.sender.
+    let contract = Address([0x38; 20]);
+    let code = hex::decode("3360005260206000f3").unwrap();
+    runner.create_address_with_code(contract, Wei::zero(), U256::zero(), code);
+
+    let from = Address([0x55; 20]);
+    let args = |to: Option<Address>, gas_limit: Option<u64>| {
+        aurora_engine::parameters::ViewTransactionArgs {
+            from: from.into(),
+            to: to.map(Into::into),
+            value: [0u8; 32],
+            gas_limit,
+            data: Vec::new(),
+        }
+    };
+    let result = runner.view_transaction(args(Some(contract), None)).unwrap();
+    let mut sender = [0u8; 32];
+    sender[12..].copy_from_slice(from.as_bytes());
+    assert_eq!(result.status, TransactionStatus::Succeed(sender.to_vec()));
+
+    // The intrinsic gas of the transaction leaves nothing to execute the code.
+    let result = runner
+        .view_transaction(args(Some(contract), Some(21_000)))
+        .unwrap();
+    assert_eq!(result.status, TransactionStatus::OutOfGas);
+
+    // Without recipient the data is init code, and the output the address of the contract.
+    let result = runner.view_transaction(args(None, None)).unwrap();
+    let address = match result.status {
+        TransactionStatus::Succeed(address) => Address::from_slice(&address),
+        other => panic!("Unexpected status {:?}", other),
+    };
+    assert_ne!(address, Address::zero());
+    assert_eq!(runner.get_nonce(from), U256::zero());
+}
+
+/// Exceptional halts of the interpreter are reported in the transaction status instead of
+/// failing the NEAR call, and the transaction pays for all the gas it was given.
+#[test]
+fn test_exceptional_halt_consumes_gas() {
+    const GAS_LIMIT: u64 = 50_000;
+    let (mut runner, mut signer, _) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+
+    let cases = [
+        // ADD with an empty stack
+        ("01", TransactionStatus::StackUnderflow),
+        // JUMPDEST PC PUSH1 0 JUMP: pushes one item per iteration until the stack is full
+        ("5b58600056", TransactionStatus::StackOverflow),
+        // PUSH1 0 JUMP: there is no JUMPDEST at 0
+        ("600056", TransactionStatus::InvalidJump),
+        ("fe", TransactionStatus::DesignatedInvalid),
+    ];
+    let mut expected_balance = INITIAL_BALANCE;
+    for (i, (code, expected_status)) in cases.iter().enumerate() {
+        let contract = Address([0x40 + i as u8; 20]);
+        let code = hex::decode(code).unwrap();
+        runner.create_address_with_code(contract, Wei::zero(), U256::zero(), code);
+
+        let result = runner
+            .submit_with_signer(&mut signer, |nonce| {
+                crate::prelude::transaction::legacy::TransactionLegacy {
+                    nonce,
+                    gas_price: U256::one(),
+                    gas_limit: GAS_LIMIT.into(),
+                    to: Some(contract),
+                    value: Wei::zero(),
+                    data: Vec::new(),
+                }
+            })
+            .unwrap();
+        assert_eq!(&result.status, expected_status);
+        assert!(result.status.is_fail());
+        assert_eq!(result.gas_used, GAS_LIMIT);
+
+        expected_balance = expected_balance - Wei::new_u64(GAS_LIMIT);
+        test_utils::validate_address_balance_and_nonce(
+            &runner,
+            sender,
+            expected_balance,
+            (INITIAL_NONCE + i as u64 + 1).into(),
+        );
+    }
+}
+
+/// EIP-3607: transactions signed for an address with code are rejected, unless the owner
+/// switched the check off.
+#[test]
+fn test_reject_sender_with_code() {
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let owner = runner.aurora_account_id.clone();
+    runner.create_address_with_code(
+        sender,
+        INITIAL_BALANCE,
+        INITIAL_NONCE.into(),
+        hex::decode("00").unwrap(),
+    );
+
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap_err();
+    let error_message = format!("{:?}", err);
+    assert!(error_message.contains("ERR_SENDER_HAS_CODE"));
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        sender,
+        INITIAL_BALANCE,
+        INITIAL_NONCE.into(),
+    );
+
+    let args = aurora_engine::parameters::SetEip3607EnabledArgs { enabled: false };
+    let (_, maybe_error) = runner.call(
+        "set_eip3607_enabled",
+        "not_the_owner.near",
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_some());
+    let (_, maybe_error) = runner.call("set_eip3607_enabled", &owner, args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+
+    signer.nonce = INITIAL_NONCE;
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        dest_address,
+        TRANSFER_AMOUNT,
+        0.into(),
+    );
+}
+
+/// The deployment gate limits contract creations to the allowlist, or stops them.
+#[test]
+fn test_deploy_gate() {
+    use aurora_engine::deploy_gate::DeployMode;
+    use aurora_engine::parameters::SetDeployAllowedArgs;
+
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let owner = runner.aurora_account_id.clone();
+    let code = vec![0x00];
+
+    let args = DeployMode::AllowlistOnly.try_to_vec().unwrap();
+    let (_, maybe_error) = runner.call("set_deploy_mode", "not_the_owner.near", args.clone());
+    assert!(maybe_error.is_some());
+    let (_, maybe_error) = runner.call("set_deploy_mode", &owner, args);
+    assert!(maybe_error.is_none());
+
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::create_deploy_transaction(code.clone(), nonce)
+        })
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("ERR_DEPLOYER_NOT_ALLOWED"));
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        sender,
+        INITIAL_BALANCE,
+        INITIAL_NONCE.into(),
+    );
+
+    // Other transactions are not gated.
+    signer.nonce = INITIAL_NONCE;
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+
+    let args = SetDeployAllowedArgs {
+        address: sender.into(),
+        allowed: true,
+    };
+    let (_, maybe_error) = runner.call("set_deploy_allowed", &owner, args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::create_deploy_transaction(code.clone(), nonce)
+        })
+        .unwrap();
+    assert!(result.status.is_ok());
+
+    let (_, maybe_error) = runner.call(
+        "set_deploy_mode",
+        &owner,
+        DeployMode::Closed.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::create_deploy_transaction(code.clone(), nonce)
+        })
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("ERR_DEPLOY_CLOSED"));
+    let (_, maybe_error) = runner.call(
+        "deploy_code",
+        "alice.near",
+        test_utils::create_deploy_transaction(code, 0.into()).data,
+    );
+    assert!(format!("{:?}", maybe_error.unwrap()).contains("ERR_DEPLOY_CLOSED"));
+}
+
+/// Transactions paying less than the minimum gas price are rejected, unless they call a
+/// contract of the free tier which has quota left.
+#[test]
+fn test_min_gas_price_and_free_tier() {
+    use aurora_engine::min_gas_price::FreeTierStatus;
+    use aurora_engine::parameters::{SetFreeTierArgs, SetMinGasPriceArgs};
+
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let owner = runner.aurora_account_id.clone();
+    let args = SetMinGasPriceArgs {
+        min_gas_price: aurora_engine_types::types::u256_to_arr(&U256::from(GAS_PRICE)),
+    }
+    .try_to_vec()
+    .unwrap();
+    let (_, maybe_error) = runner.call("set_min_gas_price", "not_the_owner.near", args.clone());
+    assert!(maybe_error.is_some());
+    let (_, maybe_error) = runner.call("set_min_gas_price", &owner, args);
+    assert!(maybe_error.is_none());
+
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("ERR_GAS_PRICE_TOO_LOW"));
+
+    let args = SetFreeTierArgs {
+        address: dest_address.into(),
+        monthly_quota: 1,
+    };
+    let (_, maybe_error) = runner.call("set_free_tier", &owner, args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+
+    signer.nonce = INITIAL_NONCE;
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("ERR_FREE_TIER_QUOTA_EXCEEDED"));
+
+    let (maybe_outcome, maybe_error) =
+        runner.call("get_free_tier", &owner, dest_address.as_bytes().to_vec());
+    assert!(maybe_error.is_none());
+    let status = maybe_outcome.unwrap().return_data.as_value().unwrap();
+    assert_eq!(
+        Option::<FreeTierStatus>::try_from_slice(&status).unwrap(),
+        Some(FreeTierStatus {
+            monthly_quota: 1,
+            used: 1,
+        })
+    );
+}
+
+/// The config views return borsh-encoded values.
+#[test]
+fn test_engine_config_views() {
+    use aurora_engine::parameters::EngineConfig;
+    use aurora_engine_types::account_id::AccountId;
+
+    let (mut runner, _, _) = initialize_transfer();
+    let owner = runner.aurora_account_id.clone();
+    let chain_id = runner.chain_id;
+    let mut view = |method: &str| {
+        let (maybe_outcome, maybe_error) = runner.call(method, &owner, Vec::new());
+        assert!(maybe_error.is_none(), "{}", method);
+        maybe_outcome.unwrap().return_data.as_value().unwrap()
+    };
+    let eth_custodian_address =
+        EthAddress::decode("d045f7e19B2488924B97F9c145b5E51D0D895A65").unwrap();
+
+    let config = EngineConfig::try_from_slice(&view("get_engine_config")).unwrap();
+    assert_eq!(
+        config,
+        EngineConfig {
+            chain_id: aurora_engine_types::types::u256_to_arr(&U256::from(chain_id)),
+            owner_id: owner.parse().unwrap(),
+            bridge_prover_id: "bridge_prover.near".parse().unwrap(),
+            upgrade_delay_blocks: 1,
+            prover_account: "prover.near".parse().unwrap(),
+            eth_custodian_address,
+            custodian_council: "prover.near".parse().unwrap(),
+        }
+    );
+    assert_eq!(
+        u64::try_from_slice(&view("get_upgrade_delay_blocks")).unwrap(),
+        1
+    );
+    assert_eq!(
+        EthAddress::try_from_slice(&view("get_eth_custodian_address")).unwrap(),
+        eth_custodian_address
+    );
+    assert_eq!(
+        AccountId::try_from_slice(&view("get_connector_prover_account")).unwrap(),
+        config.prover_account
+    );
+}
+
+/// Once switched on by the owner, `submit` emits a NEP-141 `ft_transfer` event for the ETH
+/// moved by the transaction.
+#[test]
+fn test_eth_transfer_events() {
+    use aurora_engine::parameters::SetEthTransferEventsEnabledArgs;
+
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let source_address = test_utils::address_from_secret_key(&signer.secret_key);
+    let owner = runner.aurora_account_id.clone();
+    let ft_transfer_events = |logs: &[String]| -> Vec<serde_json::Value> {
+        logs.iter()
+            .filter_map(|log| log.strip_prefix(aurora_engine::events::EVENT_JSON_PREFIX))
+            .map(|event| serde_json::from_str::<serde_json::Value>(event).unwrap())
+            .filter(|event| event["standard"] == "nep141" && event["event"] == "ft_transfer")
+            .collect()
+    };
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    assert!(ft_transfer_events(&runner.previous_logs).is_empty());
+
+    let args = SetEthTransferEventsEnabledArgs { enabled: true };
+    let (_, maybe_error) = runner.call(
+        "set_eth_transfer_events_enabled",
+        "not_the_owner.near",
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_some());
+    let (_, maybe_error) = runner.call(
+        "set_eth_transfer_events_enabled",
+        &owner,
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    let events = ft_transfer_events(&runner.previous_logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0]["data"],
+        serde_json::json!([{
+            "old_owner_id": hex::encode(source_address),
+            "new_owner_id": hex::encode(dest_address),
+            "amount": TRANSFER_AMOUNT.raw().to_string(),
+        }])
+    );
+}
+
+/// The protocol share of the gas fee is credited to the treasury like the relayer reward,
+/// so it shows in the `ft_transfer` event of the transaction.
+#[test]
+fn test_protocol_fee_in_eth_transfer_events() {
+    use aurora_engine::parameters::SetEthTransferEventsEnabledArgs;
+    use aurora_engine::protocol_fee::ProtocolFeeConfig;
+
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let source_address = test_utils::address_from_secret_key(&signer.secret_key);
+    let owner = runner.aurora_account_id.clone();
+    let treasury = EthAddress::new([0x7e; 20]);
+    let config = ProtocolFeeConfig {
+        treasury,
+        fee_basis_points: 5_000,
+    };
+    let (_, maybe_error) = runner.call("set_protocol_fee", &owner, config.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+    let args = SetEthTransferEventsEnabledArgs { enabled: true };
+    let (_, maybe_error) = runner.call(
+        "set_eth_transfer_events_enabled",
+        &owner,
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            let mut tx = test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce);
+            tx.gas_limit = 30_000.into();
+            tx.gas_price = GAS_PRICE.into();
+            tx
+        })
+        .unwrap();
+    let spent_amount = Wei::new_u64(GAS_PRICE * result.gas_used);
+    let protocol_share = Wei::new_u64(GAS_PRICE * result.gas_used / 2);
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        Address::from(treasury),
+        protocol_share,
+        0.into(),
+    );
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        source_address,
+        INITIAL_BALANCE - TRANSFER_AMOUNT - spent_amount,
+        (INITIAL_NONCE + 1).into(),
+    );
+
+    let transfers: Vec<serde_json::Value> = runner
+        .previous_logs
+        .iter()
+        .filter_map(|log| log.strip_prefix(aurora_engine::events::EVENT_JSON_PREFIX))
+        .map(|event| serde_json::from_str::<serde_json::Value>(event).unwrap())
+        .filter(|event| event["standard"] == "nep141" && event["event"] == "ft_transfer")
+        .flat_map(|event| event["data"].as_array().unwrap().clone())
+        .collect();
+    assert!(transfers.contains(&serde_json::json!({
+        "old_owner_id": hex::encode(source_address),
+        "new_owner_id": hex::encode(treasury),
+        "amount": protocol_share.raw().to_string(),
+    })));
+}
+
+/// Identical code deployed at several addresses is stored once, including the code of
+/// accounts deployed before the deduplication once they are migrated.
+#[test]
+fn test_identical_code_is_stored_once() {
+    use crate::prelude::storage::{address_to_key, KeyPrefix};
+
+    let (mut runner, _, _) = initialize_transfer();
+    let owner = runner.aurora_account_id.clone();
+    let code = hex::decode("600160005500").unwrap();
+    let first = Address([0x61; 20]);
+    let second = Address([0x62; 20]);
+    let without_code = Address([0x63; 20]);
+    for address in [first, second] {
+        runner.create_address_with_code(address, Wei::zero(), U256::zero(), code.clone());
+    }
+    let count_shared_code = |runner: &test_utils::AuroraRunner| {
+        runner
+            .ext
+            .fake_trie
+            .keys()
+            .filter(|key| key.len() == 34 && key[1] == KeyPrefix::SharedCode as u8)
+            .count()
+    };
+    assert_eq!(count_shared_code(&runner), 0);
+
+    let addresses = vec![first.0, second.0, without_code.0];
+    let (maybe_outcome, maybe_error) = runner.call(
+        "migrate_code_dedup",
+        &owner,
+        addresses.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    let migrated = maybe_outcome.unwrap().return_data.as_value().unwrap();
+    assert_eq!(u32::try_from_slice(&migrated).unwrap(), 2);
+
+    assert_eq!(count_shared_code(&runner), 1);
+    for address in [first, second] {
+        assert_eq!(runner.get_code(address), code);
+        let legacy_key = address_to_key(KeyPrefix::Code, &address);
+        assert!(!runner.ext.fake_trie.contains_key(legacy_key.as_slice()));
+    }
+    assert!(runner.get_code(without_code).is_empty());
+}
+
+/// ETH sent to precompiles, which nobody can spend, is moved to a sink from which the owner
+/// can recover it. No ETH is created or lost on the way.
+#[test]
+fn test_eth_sent_to_precompiles_is_recoverable() {
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let owner = runner.aurora_account_id.clone();
+    let identity = Address::from_low_u64_be(4);
+    let sha256 = Address::from_low_u64_be(2);
+
+    // PUSH1 4 SELFDESTRUCT: sends the balance of the contract to the identity precompile
+    let contract = Address([0x50; 20]);
+    let contract_balance = Wei::new_u64(1000);
+    let code = hex::decode("6004ff").unwrap();
+    runner.create_address_with_code(contract, contract_balance, U256::zero(), code);
+    let total = INITIAL_BALANCE + contract_balance;
+    let total_of = |runner: &test_utils::AuroraRunner| {
+        [sender, contract, dest_address, identity, sha256]
+            .iter()
+            .fold(runner.get_stranded_eth(), |sum, address| {
+                sum + runner.get_balance(*address)
+            })
+    };
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(contract, Wei::zero(), nonce)
+        })
+        .unwrap();
+    assert_eq!(runner.get_balance(identity), Wei::zero());
+    assert_eq!(runner.get_stranded_eth(), contract_balance);
+    assert_eq!(total_of(&runner), total);
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(sha256, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    assert_eq!(runner.get_balance(sha256), Wei::zero());
+    assert_eq!(
+        runner.get_stranded_eth(),
+        contract_balance + TRANSFER_AMOUNT
+    );
+    assert_eq!(total_of(&runner), total);
+
+    // Only the owner can reconcile
+    let (_, maybe_error) = runner.call(
+        "reconcile_stranded_eth",
+        "not_the_owner.near",
+        dest_address.as_bytes().to_vec(),
+    );
+    assert!(maybe_error.is_some());
+    let (maybe_outcome, maybe_error) = runner.call(
+        "reconcile_stranded_eth",
+        &owner,
+        dest_address.as_bytes().to_vec(),
+    );
+    assert!(maybe_error.is_none());
+    let moved = maybe_outcome.unwrap().return_data.as_value().unwrap();
+    assert_eq!(
+        Wei::new(U256::from_big_endian(&moved)),
+        contract_balance + TRANSFER_AMOUNT
+    );
+    assert_eq!(runner.get_stranded_eth(), Wei::zero());
+    assert_eq!(
+        runner.get_balance(dest_address),
+        contract_balance + TRANSFER_AMOUNT
+    );
+    assert_eq!(total_of(&runner), total);
+
+    // The sink is empty now
+    let (_, maybe_error) = runner.call(
+        "reconcile_stranded_eth",
+        &owner,
+        dest_address.as_bytes().to_vec(),
+    );
+    assert!(maybe_error.is_some());
+}
+
 use sha3::Digest;
 
 #[test]
@@ -567,6 +1324,20 @@ fn test_block_hash_api() {
     );
 }
 
+#[test]
+fn test_block_height_api() {
+    let mut runner = test_utils::deploy_evm();
+
+    let (maybe_outcome, maybe_error) = runner.call("get_block_height", "any.near", Vec::new());
+    if let Some(error) = maybe_error {
+        panic!("Call failed: {:?}", error);
+    }
+    let outcome = maybe_outcome.unwrap();
+    let block_height = u64::try_from_slice(&outcome.return_data.as_value().unwrap()).unwrap();
+
+    assert_eq!(block_height, runner.context.block_index);
+}
+
 #[test]
 fn test_block_hash_contract() {
     let (mut runner, mut source_account, _) = initialize_transfer();
@@ -592,6 +1363,106 @@ fn test_block_hash_contract() {
     test_utils::panic_on_fail(result.status);
 }
 
+#[test]
+fn test_dev_fund() {
+    let mut runner = test_utils::deploy_evm();
+    let addresses = [Address([1u8; 20]), Address([2u8; 20])];
+    let args = aurora_engine::parameters::DevFundArgs {
+        addresses: addresses.iter().map(|a| (*a).into()).collect(),
+        amount: 1_000,
+    };
+    let input = args.try_to_vec().unwrap();
+
+    // only the owner can fund accounts
+    let (_, maybe_error) = runner.call("dev_fund", "not_the_owner.near", input.clone());
+    assert!(maybe_error.is_some());
+
+    let owner = runner.aurora_account_id.clone();
+    let (_, maybe_error) = runner.call("dev_fund", &owner, input);
+    assert!(maybe_error.is_none());
+    for address in addresses {
+        assert_eq!(runner.get_balance(address), Wei::new_u64(1_000));
+    }
+}
+
+#[test]
+fn test_evict_and_resurrect_inactive_account() {
+    let mut runner = test_utils::deploy_evm();
+    let owner = runner.aurora_account_id.clone();
+    let address = Address([7u8; 20]);
+    runner.create_address(address, INITIAL_BALANCE, U256::one());
+
+    let policy_args = aurora_engine::parameters::SetInactivityPolicyArgs {
+        inactivity_blocks: 100,
+    };
+    let (_, maybe_error) = runner.call(
+        "set_inactivity_policy",
+        &owner,
+        policy_args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+
+    let evict_args = aurora_engine::parameters::EvictAccountArgs {
+        address: address.into(),
+        storage: Vec::new(),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // the account was not inactive for long enough yet
+    let (_, maybe_error) = runner.call("evict_inactive_account", &owner, evict_args.clone());
+    assert!(maybe_error.is_some());
+
+    runner.context.block_index += 100;
+    let (_, maybe_error) = runner.call(
+        "evict_inactive_account",
+        "not_the_owner.near",
+        evict_args.clone(),
+    );
+    assert!(maybe_error.is_some());
+    let (maybe_outcome, maybe_error) = runner.call("evict_inactive_account", &owner, evict_args);
+    assert!(maybe_error.is_none());
+    let commitment = maybe_outcome.unwrap().return_data.as_value().unwrap();
+    assert_eq!(runner.get_balance(address), Wei::zero());
+
+    let account = aurora_engine::inactivity::EvictedAccount {
+        nonce: aurora_engine_types::types::u256_to_arr(&U256::one()),
+        balance: INITIAL_BALANCE.to_bytes(),
+        code: Vec::new(),
+        storage: Vec::new(),
+    };
+    assert_eq!(commitment, account.commitment().as_bytes().to_vec());
+
+    // a wrong preimage is rejected
+    let mut wrong_account = account.clone();
+    wrong_account.balance = Wei::new_u64(1).to_bytes();
+    let (_, maybe_error) = runner.call(
+        "resurrect_account",
+        "anyone.near",
+        aurora_engine::parameters::ResurrectAccountArgs {
+            address: address.into(),
+            account: wrong_account,
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    assert!(maybe_error.is_some());
+
+    let (_, maybe_error) = runner.call(
+        "resurrect_account",
+        "anyone.near",
+        aurora_engine::parameters::ResurrectAccountArgs {
+            address: address.into(),
+            account,
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    assert_eq!(runner.get_balance(address), INITIAL_BALANCE);
+    assert_eq!(runner.get_nonce(address), U256::one());
+}
+
 #[test]
 fn test_ft_metadata() {
     let mut runner = test_utils::deploy_evm();
@@ -675,6 +1546,251 @@ fn test_eth_transfer_charging_gas_not_enough_balance_sim() {
     );
 }
 
+#[test]
+fn test_near_account_to_address_precompile() {
+    use aurora_engine_precompiles::account_to_address::NearAccountToAddress;
+
+    let runner = test_utils::deploy_evm();
+    let result = runner
+        .view_call(aurora_engine::parameters::ViewCallArgs {
+            sender: EthAddress::zero(),
+            address: NearAccountToAddress::ADDRESS.into(),
+            amount: [0u8; 32],
+            input: b"relay.aurora".to_vec(),
+        })
+        .unwrap();
+    let expected = sdk::types::near_account_to_evm_address(b"relay.aurora");
+    match result {
+        TransactionStatus::Succeed(bytes) => {
+            assert_eq!(Address::from_slice(&bytes[12..]), expected)
+        }
+        other => panic!("Unexpected status: {:?}", other),
+    }
+}
+
+/// A call retried with the same idempotency key returns its original outcome without
+/// executing again, until the record expires.
+#[test]
+fn test_call_idempotency_key() {
+    use aurora_engine::parameters::{CallArgs, FunctionCallArgsV3};
+
+    let (mut runner, _, dest_address) = initialize_transfer();
+    let callers = ["alice.near", "bob.near"];
+    for caller in callers.iter() {
+        let address = sdk::types::near_account_to_evm_address(caller.as_bytes());
+        runner.create_address(address, INITIAL_BALANCE, INITIAL_NONCE.into());
+    }
+    let call = |runner: &mut test_utils::AuroraRunner, caller: &str, key: Option<[u8; 32]>| {
+        let args = CallArgs::V3(FunctionCallArgsV3 {
+            contract: dest_address.into(),
+            value: TRANSFER_AMOUNT.to_bytes(),
+            input: Vec::new(),
+            idempotency_key: key,
+        });
+        let (outcome, maybe_error) = runner.call("call", caller, args.try_to_vec().unwrap());
+        assert!(maybe_error.is_none());
+        SubmitResult::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap()).unwrap()
+    };
+
+    let result = call(&mut runner, callers[0], Some([1; 32]));
+    assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+    assert_eq!(runner.get_balance(dest_address), TRANSFER_AMOUNT);
+    // Retrying returns the same outcome and transfers nothing.
+    let retried = call(&mut runner, callers[0], Some([1; 32]));
+    assert_eq!(retried.status, result.status);
+    assert_eq!(retried.gas_used, result.gas_used);
+    assert_eq!(runner.get_balance(dest_address), TRANSFER_AMOUNT);
+
+    // Keys are scoped to the caller, and calls without a key are always executed.
+    call(&mut runner, callers[1], Some([1; 32]));
+    call(&mut runner, callers[0], Some([2; 32]));
+    call(&mut runner, callers[0], None);
+    call(&mut runner, callers[0], None);
+    assert_eq!(
+        runner.get_balance(dest_address),
+        Wei::new_u64(5 * TRANSFER_AMOUNT.raw().low_u64())
+    );
+
+    // Once the record expired, the key executes the call again.
+    runner.context.block_index += aurora_engine::idempotency::RETENTION_BLOCKS;
+    call(&mut runner, callers[0], Some([1; 32]));
+    assert_eq!(
+        runner.get_balance(dest_address),
+        Wei::new_u64(6 * TRANSFER_AMOUNT.raw().low_u64())
+    );
+}
+
+/// `decode_transaction` returns the fields of a signed transaction without executing it.
+#[test]
+fn test_decode_transaction() {
+    use aurora_engine::transaction::DecodedTransaction;
+
+    let (mut runner, signer, dest_address) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let signed_tx = test_utils::sign_transaction(
+        test_utils::transfer(dest_address, TRANSFER_AMOUNT, INITIAL_NONCE.into()),
+        Some(runner.chain_id),
+        &signer.secret_key,
+    );
+    let (outcome, maybe_error) = runner.call(
+        "decode_transaction",
+        "some-account.near",
+        rlp::encode(&signed_tx).to_vec(),
+    );
+    assert!(maybe_error.is_none());
+    let decoded =
+        DecodedTransaction::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap())
+            .unwrap();
+    assert_eq!(decoded.tx_type, 0);
+    assert_eq!(decoded.chain_id, Some(runner.chain_id));
+    assert_eq!(
+        decoded.nonce,
+        aurora_engine_types::types::u256_to_arr(&U256::from(INITIAL_NONCE))
+    );
+    assert_eq!(decoded.to, Some(dest_address.into()));
+    assert_eq!(decoded.value, TRANSFER_AMOUNT.to_bytes());
+    assert_eq!(decoded.data_len, 0);
+    assert_eq!(decoded.sender, Some(sender.into()));
+    assert_eq!(decoded.intrinsic_gas, Some(21_000));
+    // Nothing was executed.
+    assert_eq!(runner.get_nonce(sender), U256::from(INITIAL_NONCE));
+
+    let (_, maybe_error) = runner.call("decode_transaction", "some-account.near", vec![0x05]);
+    assert!(format!("{:?}", maybe_error.unwrap()).contains("ERR_UNKNOWN_TX_TYPE"));
+}
+
+/// The owner can scale the cost of a custom precompile, but not of a standard one.
+#[test]
+fn test_precompile_gas_override() {
+    use aurora_engine::parameters::SetPrecompileGasOverrideArgs;
+    use aurora_engine_precompiles::account_to_address::NearAccountToAddress;
+    use aurora_engine_precompiles::identity::Identity;
+
+    let mut runner = test_utils::deploy_evm();
+    let owner = runner.aurora_account_id.clone();
+    let gas_used = |runner: &test_utils::AuroraRunner| {
+        runner
+            .view_call_with_gas_used(aurora_engine::parameters::ViewCallArgs {
+                sender: EthAddress::zero(),
+                address: NearAccountToAddress::ADDRESS.into(),
+                amount: [0u8; 32],
+                input: b"relay.aurora".to_vec(),
+            })
+            .unwrap()
+            .gas_used
+    };
+    let set_override = |runner: &mut test_utils::AuroraRunner, address: Address, percent| {
+        let args = SetPrecompileGasOverrideArgs {
+            address: address.into(),
+            percent,
+        };
+        let (_, maybe_error) = runner.call(
+            "set_precompile_gas_override",
+            &owner,
+            args.try_to_vec().unwrap(),
+        );
+        maybe_error
+    };
+    let default_gas_used = gas_used(&runner);
+
+    assert!(set_override(&mut runner, NearAccountToAddress::ADDRESS, 400).is_none());
+    // The account id is one word: 30 + 6 gas, now charged 4 times.
+    assert_eq!(gas_used(&runner), default_gas_used + 3 * 36);
+    let (outcome, _) = runner.call(
+        "get_precompile_gas_override",
+        &owner,
+        NearAccountToAddress::ADDRESS.0.to_vec(),
+    );
+    let percent = u16::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap()).unwrap();
+    assert_eq!(percent, 400);
+
+    assert!(set_override(&mut runner, NearAccountToAddress::ADDRESS, 100).is_none());
+    assert_eq!(gas_used(&runner), default_gas_used);
+
+    let error = set_override(&mut runner, NearAccountToAddress::ADDRESS, 401).unwrap();
+    assert!(format!("{:?}", error).contains("ERR_GAS_PERCENT_OUT_OF_RANGE"));
+    let error = set_override(&mut runner, Identity::ADDRESS, 200).unwrap();
+    assert!(format!("{:?}", error).contains("ERR_NOT_CUSTOM_PRECOMPILE"));
+}
+
+#[test]
+fn test_near_state_read_precompile() {
+    use aurora_engine::parameters::{
+        GetMirroredNearStateArgs, MirrorNearStateArgs, SetNearStateSourceArgs,
+    };
+    use aurora_engine_precompiles::near_state::{MirroredValue, NearStateRead};
+
+    let mut runner = test_utils::deploy_evm();
+    let owner = runner.aurora_account_id.clone();
+    let oracle = "oracle.near";
+    let mirror = |runner: &mut test_utils::AuroraRunner, value: Option<Vec<u8>>| {
+        let args = MirrorNearStateArgs {
+            key: b"price".to_vec(),
+            value,
+        };
+        let (_, maybe_error) = runner.call("mirror_near_state", oracle, args.try_to_vec().unwrap());
+        maybe_error
+    };
+    let read = |runner: &test_utils::AuroraRunner| {
+        let input = [&[oracle.len() as u8], oracle.as_bytes(), b"price"].concat();
+        runner.view_call(aurora_engine::parameters::ViewCallArgs {
+            sender: EthAddress::zero(),
+            address: NearStateRead::ADDRESS.into(),
+            amount: [0u8; 32],
+            input,
+        })
+    };
+
+    // Only the contracts allowed by the owner can publish.
+    let error = mirror(&mut runner, Some(vec![1, 2, 3])).unwrap();
+    assert!(format!("{:?}", error).contains("ERR_NEAR_STATE_SOURCE_NOT_ALLOWED"));
+    let args = SetNearStateSourceArgs {
+        source: oracle.parse().unwrap(),
+        allowed: true,
+    };
+    let (_, maybe_error) = runner.call("set_near_state_source", &owner, args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+    assert!(mirror(&mut runner, Some(vec![1, 2, 3])).is_none());
+
+    let args = GetMirroredNearStateArgs {
+        source: oracle.parse().unwrap(),
+        key: b"price".to_vec(),
+    };
+    let (outcome, _) = runner.call(
+        "get_mirrored_near_state",
+        &owner,
+        args.try_to_vec().unwrap(),
+    );
+    let value =
+        Option::<MirroredValue>::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap())
+            .unwrap()
+            .unwrap();
+    assert_eq!(value.value, vec![1, 2, 3]);
+
+    // abi.encode(uint64 publishedAt, bytes value)
+    let output = match read(&runner).unwrap() {
+        TransactionStatus::Succeed(output) => output,
+        other => panic!("Unexpected status {:?}", other),
+    };
+    let decoded = ethabi::decode(
+        &[ethabi::ParamType::Uint(64), ethabi::ParamType::Bytes],
+        &output,
+    )
+    .unwrap();
+    assert_eq!(
+        decoded,
+        vec![
+            ethabi::Token::Uint(U256::from(value.published_at)),
+            ethabi::Token::Bytes(vec![1, 2, 3])
+        ]
+    );
+
+    // Removed values cannot be read any more.
+    assert!(mirror(&mut runner, None).is_none());
+    let error = read(&runner).unwrap_err();
+    assert!(format!("{:?}", error).contains("ERR_NEAR_STATE_NOT_FOUND"));
+}
+
 fn initialize_evm_sim() -> (state_migration::AuroraAccount, test_utils::Signer, Address) {
     let aurora = state_migration::deploy_evm();
     let signer = test_utils::Signer::random();
@@ -731,3 +1847,153 @@ fn get_compiled_artifact(runner: &test_utils::AuroraRunner) -> wasmer::Module {
 
     unsafe { wasmer::Module::deserialize(&store, &cache_record).unwrap() }
 }
+
+/// The self-test passes on a healthy engine and reports the checks which fail.
+#[test]
+fn test_post_upgrade_selftest() {
+    use aurora_engine::selftest::SelfTestReport;
+
+    let (mut runner, _, _) = initialize_transfer();
+    let owner = runner.aurora_account_id.clone();
+    let run_selftest = |runner: &mut test_utils::AuroraRunner| {
+        let (maybe_outcome, maybe_error) = runner.call("post_upgrade_selftest", &owner, Vec::new());
+        assert!(maybe_error.is_none());
+        let report = maybe_outcome.unwrap().return_data.as_value().unwrap();
+        SelfTestReport::try_from_slice(&report).unwrap()
+    };
+
+    let report = run_selftest(&mut runner);
+    assert!(report.passed(), "{:?}", report);
+    assert!(report
+        .checks
+        .iter()
+        .any(|check| check.name == "sample_execution"));
+
+    // Code at a precompile address would never run.
+    let identity = Address::from_low_u64_be(4);
+    runner.create_address_with_code(identity, Wei::zero(), U256::zero(), vec![0x00]);
+    let report = run_selftest(&mut runner);
+    assert!(!report.passed());
+    let failed: Vec<_> = report
+        .checks
+        .iter()
+        .filter(|check| check.error.is_some())
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].name, "precompiles");
+    assert_eq!(failed[0].error.as_deref(), Some("ERR_PRECOMPILE_HAS_CODE"));
+
+    // Only the owner can run it.
+    let (_, maybe_error) = runner.call("post_upgrade_selftest", "alice.near", Vec::new());
+    assert!(maybe_error.is_some());
+}
+
+/// The state commitment changes with each EVM state change and records its block height.
+#[test]
+fn test_state_commitment() {
+    use aurora_engine::state_commitment::StateCommitment;
+
+    let (mut runner, mut signer, dest_address) = initialize_transfer();
+    let get_commitment = |runner: &mut test_utils::AuroraRunner| {
+        let (maybe_outcome, maybe_error) = runner.call("get_state_commitment", "alice", Vec::new());
+        assert!(maybe_error.is_none());
+        let output = maybe_outcome.unwrap().return_data.as_value().unwrap();
+        StateCommitment::try_from_slice(&output).unwrap()
+    };
+
+    // Creating the accounts of the test is not an EVM state change.
+    assert_eq!(get_commitment(&mut runner), StateCommitment::default());
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    let first = get_commitment(&mut runner);
+    assert_ne!(first.commitment, [0u8; 32]);
+
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest_address, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    let second = get_commitment(&mut runner);
+    assert_ne!(second.commitment, first.commitment);
+    assert!(second.block_height > first.block_height);
+
+    // Views do not change the state.
+    assert_eq!(get_commitment(&mut runner), second);
+}
+
+#[test]
+fn test_validate_transaction() {
+    use aurora_engine::preflight::TransactionVerdict;
+
+    let (mut runner, signer, dest_address) = initialize_transfer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let mut validate = |transaction: crate::prelude::transaction::legacy::TransactionLegacy| {
+        let signed_tx =
+            test_utils::sign_transaction(transaction, Some(runner.chain_id), &signer.secret_key);
+        let (maybe_outcome, maybe_error) = runner.call(
+            "validate_transaction",
+            "relay.near",
+            rlp::encode(&signed_tx).to_vec(),
+        );
+        assert!(maybe_error.is_none());
+        let output = maybe_outcome.unwrap().return_data.as_value().unwrap();
+        TransactionVerdict::try_from_slice(&output).unwrap()
+    };
+    let transfer =
+        |amount: Wei, nonce: u64| crate::prelude::transaction::legacy::TransactionLegacy {
+            nonce: nonce.into(),
+            gas_price: GAS_PRICE.into(),
+            gas_limit: 21_000.into(),
+            to: Some(dest_address),
+            value: amount,
+            data: Vec::new(),
+        };
+    let max_cost = |amount: Wei| U256::from(21_000 * GAS_PRICE) + amount.raw();
+
+    assert_eq!(
+        validate(transfer(TRANSFER_AMOUNT, INITIAL_NONCE)),
+        TransactionVerdict::Valid {
+            sender: sender.into(),
+            intrinsic_gas: 21_000,
+            max_cost: aurora_engine_types::types::u256_to_arr(&max_cost(TRANSFER_AMOUNT)),
+        }
+    );
+
+    // A nonce gap
+    assert_eq!(
+        validate(transfer(TRANSFER_AMOUNT, INITIAL_NONCE + 2)),
+        TransactionVerdict::IncorrectNonce {
+            sender: sender.into(),
+            expected: aurora_engine_types::types::u256_to_arr(&INITIAL_NONCE.into()),
+            actual: aurora_engine_types::types::u256_to_arr(&(INITIAL_NONCE + 2).into()),
+        }
+    );
+
+    // The balance covers the value, but not the gas on top of it.
+    assert_eq!(
+        validate(transfer(INITIAL_BALANCE, INITIAL_NONCE)),
+        TransactionVerdict::InsufficientBalance {
+            sender: sender.into(),
+            balance: aurora_engine_types::types::u256_to_arr(&INITIAL_BALANCE.raw()),
+            max_cost: aurora_engine_types::types::u256_to_arr(&max_cost(INITIAL_BALANCE)),
+        }
+    );
+
+    let mut transaction = transfer(TRANSFER_AMOUNT, INITIAL_NONCE);
+    transaction.gas_limit = 20_000.into();
+    assert_eq!(
+        validate(transaction),
+        TransactionVerdict::Invalid {
+            sender: Some(sender.into()),
+            error: "ERR_INTRINSIC_GAS".to_string(),
+        }
+    );
+
+    // The transactions were only validated.
+    assert_eq!(runner.get_nonce(sender), INITIAL_NONCE.into());
+    assert_eq!(runner.get_balance(sender), INITIAL_BALANCE);
+}
@@ -0,0 +1,52 @@
+use engine_standalone_storage::metrics::{self, Metrics};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::test_utils::standalone::storage::create_db;
+
+#[test]
+fn test_metrics_rendering() {
+    let (temp_dir, storage) = create_db();
+    let mut metrics = Metrics::new();
+
+    metrics.record_cache_hit("view");
+    metrics.record_cache_hit("view");
+    metrics.record_cache_hit("view");
+    metrics.record_cache_miss("view");
+    metrics.record_method_latency("submit", Duration::from_millis(500));
+    metrics.record_method_latency("submit", Duration::from_millis(1500));
+    metrics.update_storage_size(&storage);
+
+    assert_eq!(metrics.cache("view").hit_rate(), 0.75);
+    assert_eq!(metrics.method_latency("submit").count, 2);
+
+    let rendered = metrics.to_prometheus();
+    assert!(rendered.contains("aurora_standalone_cache_hit_rate{cache=\"view\"} 0.75"));
+    assert!(rendered.contains("aurora_standalone_method_latency_seconds_sum{method=\"submit\"} 2"));
+    assert!(
+        rendered.contains("aurora_standalone_method_latency_seconds_count{method=\"submit\"} 2")
+    );
+
+    drop(storage);
+    temp_dir.close().unwrap();
+}
+
+#[test]
+fn test_metrics_endpoint() {
+    let metrics = Arc::new(Mutex::new(Metrics::new()));
+    metrics.lock().unwrap().record_cache_miss("code");
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    metrics::serve(addr, metrics).unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("aurora_standalone_cache_hit_rate{cache=\"code\"} 0"));
+}
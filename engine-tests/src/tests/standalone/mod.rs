@@ -1,4 +1,5 @@
 mod json_snapshot;
+mod metrics;
 mod sanity;
 mod storage;
 mod sync;
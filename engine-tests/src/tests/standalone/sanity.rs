@@ -1,8 +1,17 @@
-use crate::test_utils::standalone::mocks::{promise, storage};
+use crate::test_utils::standalone::mocks::{self, promise, storage};
+use aurora_engine::connector::EthConnectorContract;
 use aurora_engine::engine;
+use aurora_engine::parameters::{
+    BalanceOfCallArgs, FinishDepositCallArgs, ResolveTransferCallArgs, TransferCallArgs,
+    TransferCallCallArgs,
+};
 use aurora_engine_sdk::env::DEFAULT_PREPAID_GAS;
-use aurora_engine_types::types::Wei;
+use aurora_engine_types::parameters::WithdrawCallArgs;
+use aurora_engine_types::types::{EthAddress, NearGas, PromiseResult, Wei};
 use aurora_engine_types::{account_id::AccountId, Address, H256, U256};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 #[test]
@@ -63,6 +72,703 @@ fn test_deploy_code() {
     assert_eq!(engine::get_code(&io, &contract_address), code_to_deploy);
 }
 
+/// The receiver of an `ft_transfer_call` may withdraw the tokens from its `ft_on_transfer`
+/// and still report them all unused: the refund is capped to what it has left, so no nETH is
+/// created.
+#[test]
+fn test_connector_malicious_receiver_refund() {
+    let storage = RwLock::new(storage::Storage::default());
+    let io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let aurora = env.current_account_id.clone();
+    let alice: AccountId = "alice.near".parse().unwrap();
+    let receiver: AccountId = "malicious.near".parse().unwrap();
+    let gas = NearGas::new(near_sdk_sim::DEFAULT_GAS);
+
+    EthConnectorContract::init_instance(io)
+        .finish_deposit(
+            aurora.clone(),
+            aurora.clone(),
+            FinishDepositCallArgs {
+                new_owner_id: alice.clone(),
+                amount: 1000,
+                proof_key: "proof".to_string(),
+                relayer_id: aurora.clone(),
+                fee: 0.into(),
+                msg: None,
+            },
+            gas,
+        )
+        .map_err(mocks::unsafe_to_string)
+        .unwrap();
+
+    let promise = EthConnectorContract::init_instance(io)
+        .ft_transfer_call(
+            alice.clone(),
+            aurora.clone(),
+            TransferCallCallArgs {
+                receiver_id: receiver.clone(),
+                amount: 600,
+                memo: None,
+                msg: String::new(),
+            },
+            gas,
+        )
+        .map_err(mocks::unsafe_to_string)
+        .unwrap()
+        .into_promise();
+    assert_eq!(promise.base.target_account_id, receiver);
+
+    // `ft_on_transfer` of the receiver re-enters the connector to withdraw most of the tokens.
+    let result = EthConnectorContract::init_instance(io)
+        .withdraw_eth_from_near(
+            &aurora,
+            &receiver,
+            WithdrawCallArgs {
+                recipient_address: EthAddress::new([1; 20]),
+                amount: 500,
+            },
+        )
+        .map_err(mocks::unsafe_to_string)
+        .unwrap();
+    assert_eq!(result.amount, 500);
+
+    // It then claims the whole amount unused.
+    EthConnectorContract::init_instance(io).ft_resolve_transfer(
+        ResolveTransferCallArgs {
+            sender_id: alice.clone(),
+            amount: 600,
+            receiver_id: receiver.clone(),
+        },
+        PromiseResult::Successful(b"\"600\"".to_vec()),
+    );
+    assert_eq!(output_amount(&storage), 500);
+
+    assert_eq!(ft_balance_of(&storage, &receiver), 0);
+    assert_eq!(ft_balance_of(&storage, &alice), 500);
+    EthConnectorContract::init_instance(io).ft_total_eth_supply_on_near();
+    assert_eq!(output_amount(&storage), 500);
+}
+
+#[test]
+fn test_connector_deposit_withdraw_round_trip() {
+    for seed in 0..32 {
+        connector_round_trip(seed);
+    }
+}
+
+fn connector_round_trip(seed: u64) {
+    const OPS: usize = 64;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let storage = RwLock::new(storage::Storage::default());
+    let io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let aurora = env.current_account_id.clone();
+    let gas = NearGas::new(near_sdk_sim::DEFAULT_GAS);
+    let accounts: Vec<AccountId> = ["alice.near", "bob.near", "carol.near", "relayer.near"]
+        .iter()
+        .map(|account| account.parse().unwrap())
+        .collect();
+    // Expected balance of every account.
+    let mut balances: BTreeMap<AccountId, u128> = BTreeMap::new();
+    let mut deposited = 0u128;
+    let mut withdrawn = 0u128;
+    let withdraw_args = |amount| WithdrawCallArgs {
+        recipient_address: EthAddress::new([1; 20]),
+        amount,
+    };
+
+    for op in 0..OPS {
+        let mut connector = EthConnectorContract::init_instance(io);
+        let sender = accounts[rng.gen_range(0, accounts.len())].clone();
+        let receiver = accounts[rng.gen_range(0, accounts.len())].clone();
+        let balance = balances.get(&sender).copied().unwrap_or(0);
+        match rng.gen_range(0, 4) {
+            // Deposit, with a fee paid to the relayer.
+            0 => {
+                let amount = rng.gen_range(1, 1_000_000_000u128);
+                let fee = rng.gen_range(0, amount);
+                connector
+                    .finish_deposit(
+                        aurora.clone(),
+                        aurora.clone(),
+                        FinishDepositCallArgs {
+                            new_owner_id: receiver.clone(),
+                            amount,
+                            proof_key: format!("proof-{}", op),
+                            relayer_id: sender.clone(),
+                            fee: fee.into(),
+                            msg: None,
+                        },
+                        gas,
+                    )
+                    .map_err(mocks::unsafe_to_string)
+                    .unwrap();
+                *balances.entry(receiver).or_default() += amount - fee;
+                *balances.entry(sender).or_default() += fee;
+                deposited += amount;
+            }
+            1 if balance > 0 && sender != receiver => {
+                let amount = rng.gen_range(1, balance + 1);
+                connector
+                    .ft_transfer(
+                        &sender,
+                        TransferCallArgs {
+                            receiver_id: receiver.clone(),
+                            amount,
+                            memo: None,
+                        },
+                    )
+                    .map_err(mocks::unsafe_to_string)
+                    .unwrap();
+                *balances.get_mut(&sender).unwrap() -= amount;
+                *balances.entry(receiver).or_default() += amount;
+            }
+            // Transfer call, of which the receiver keeps a random part.
+            2 if balance > 0 && sender != receiver => {
+                let amount = rng.gen_range(1, balance + 1);
+                let _ = connector
+                    .ft_transfer_call(
+                        sender.clone(),
+                        aurora.clone(),
+                        TransferCallCallArgs {
+                            receiver_id: receiver.clone(),
+                            amount,
+                            memo: None,
+                            msg: String::new(),
+                        },
+                        gas,
+                    )
+                    .map_err(mocks::unsafe_to_string)
+                    .unwrap();
+                // `ft_on_transfer` returns the amount to refund, or fails to refund it all.
+                let unused = rng.gen_range(0, amount + 1);
+                let promise_result = if unused == amount && rng.gen() {
+                    PromiseResult::Failed
+                } else {
+                    PromiseResult::Successful(format!("\"{}\"", unused).into_bytes())
+                };
+                EthConnectorContract::init_instance(io).ft_resolve_transfer(
+                    ResolveTransferCallArgs {
+                        sender_id: sender.clone(),
+                        amount,
+                        receiver_id: receiver.clone(),
+                    },
+                    promise_result,
+                );
+                *balances.get_mut(&sender).unwrap() -= amount - unused;
+                *balances.entry(receiver).or_default() += amount - unused;
+            }
+            // Partial withdrawal.
+            3 if balance > 0 => {
+                let amount = rng.gen_range(1, balance + 1);
+                let result = connector
+                    .withdraw_eth_from_near(&aurora, &sender, withdraw_args(amount))
+                    .map_err(mocks::unsafe_to_string)
+                    .unwrap();
+                *balances.get_mut(&sender).unwrap() -= amount;
+                withdrawn += result.amount;
+            }
+            _ => (),
+        }
+    }
+
+    for (account, balance) in &balances {
+        assert_eq!(
+            ft_balance_of(&storage, account),
+            *balance,
+            "seed {}: balance of {}",
+            seed,
+            account
+        );
+        if *balance > 0 {
+            let result = EthConnectorContract::init_instance(io)
+                .withdraw_eth_from_near(&aurora, account, withdraw_args(*balance))
+                .map_err(mocks::unsafe_to_string)
+                .unwrap();
+            withdrawn += result.amount;
+        }
+    }
+
+    assert_eq!(withdrawn, deposited, "seed {}: withdrawn", seed);
+    let mut connector = EthConnectorContract::init_instance(io);
+    connector.ft_total_eth_supply_on_near();
+    assert_eq!(output_amount(&storage), 0, "seed {}: supply on NEAR", seed);
+    connector.ft_total_eth_supply_on_aurora();
+    assert_eq!(
+        output_amount(&storage),
+        0,
+        "seed {}: supply on Aurora",
+        seed
+    );
+    let custodian = connector.get_eth_custodian_address();
+    assert_eq!(
+        connector.get_custodian_supply(&custodian).minted,
+        0,
+        "seed {}: custodian supply",
+        seed
+    );
+}
+
+fn ft_balance_of(storage: &RwLock<storage::Storage>, account_id: &AccountId) -> u128 {
+    EthConnectorContract::init_instance(storage::StoragePointer(storage)).ft_balance_of(
+        BalanceOfCallArgs {
+            account_id: account_id.clone(),
+        },
+    );
+    output_amount(storage)
+}
+
+/// Amount returned by a connector method, as a JSON string.
+fn output_amount(storage: &RwLock<storage::Storage>) -> u128 {
+    let storage = storage.read().unwrap();
+    let output = std::str::from_utf8(storage.output()).unwrap();
+    output.trim_matches('"').parse().unwrap()
+}
+
+/// The free tier quota of a contract is renewed every month.
+#[test]
+fn test_free_tier_quota_period() {
+    use aurora_engine::min_gas_price::{self, MinGasPriceError, QUOTA_PERIOD_SECS};
+    use aurora_engine::transaction::NormalizedEthTransaction;
+    use aurora_engine_sdk::env::Timestamp;
+
+    let storage = RwLock::new(storage::Storage::default());
+    let mut io = storage::StoragePointer(&storage);
+    let dapp = Address([2; 20]);
+    let transaction = |gas_price: u64| NormalizedEthTransaction {
+        address: Some(Address([1; 20])),
+        chain_id: None,
+        nonce: U256::zero(),
+        gas_limit: U256::from(21_000),
+        max_priority_fee_per_gas: U256::from(gas_price),
+        max_fee_per_gas: U256::from(gas_price),
+        to: Some(dapp),
+        value: Wei::zero(),
+        data: Vec::new(),
+        access_list: Vec::new(),
+    };
+    let now = Timestamp::new(1_600_000_000 * 1_000_000_000);
+    let next_month = Timestamp::new(now.nanos() + QUOTA_PERIOD_SECS * 1_000_000_000);
+
+    // There is no minimum by default.
+    assert_eq!(min_gas_price::check(&mut io, &transaction(0), now), Ok(()));
+
+    min_gas_price::set_min_gas_price(&mut io, Wei::new_u64(10)).unwrap();
+    min_gas_price::set_free_tier(&mut io, &dapp, 2);
+    assert_eq!(min_gas_price::check(&mut io, &transaction(0), now), Ok(()));
+    assert_eq!(min_gas_price::check(&mut io, &transaction(0), now), Ok(()));
+    assert_eq!(
+        min_gas_price::check(&mut io, &transaction(0), now),
+        Err(MinGasPriceError::FreeTierQuotaExceeded)
+    );
+    // Paying the minimum does not use the quota.
+    assert_eq!(min_gas_price::check(&mut io, &transaction(10), now), Ok(()));
+
+    assert_eq!(
+        min_gas_price::check(&mut io, &transaction(0), next_month),
+        Ok(())
+    );
+    let status = min_gas_price::get_free_tier(&io, &dapp, next_month)
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.used, 1);
+
+    min_gas_price::set_free_tier(&mut io, &dapp, 0);
+    assert_eq!(
+        min_gas_price::get_free_tier(&io, &dapp, next_month),
+        Ok(None)
+    );
+    assert_eq!(
+        min_gas_price::check(&mut io, &transaction(0), next_month),
+        Err(MinGasPriceError::GasPriceTooLow)
+    );
+}
+
+/// A cross-contract call creates the router of its caller on the first call only, and is
+/// paid from the NEAR balance of the caller.
+#[test]
+fn test_cross_contract_call() {
+    use aurora_engine::parameters::{SubmitResult, XccCallbackArgs};
+    use aurora_engine::xcc;
+    use aurora_engine_precompiles::xcc::{router_account_id, CrossContractCall};
+    use aurora_engine_types::parameters::{
+        CrossContractCallArgs, PromiseAction, PromiseCreateArgs,
+    };
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    let storage = RwLock::new(storage::Storage::default());
+    let mut io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let aurora = env.current_account_id.clone();
+    let origin = Address::from_low_u64_be(0x42);
+    let near_call = PromiseCreateArgs {
+        target_account_id: "wrap.near".parse().unwrap(),
+        method: "near_deposit".into(),
+        args: b"{}".to_vec(),
+        attached_balance: 10,
+        attached_gas: 5_000_000_000_000,
+    };
+    let input = CrossContractCallArgs {
+        promise: near_call.clone(),
+        callback: Some(vec![1, 2, 3]),
+    }
+    .try_to_vec()
+    .unwrap();
+    let call = || {
+        let io = storage::StoragePointer(&storage);
+        let mut handler = promise::PromiseTracker::default();
+        let mut engine = engine::Engine::new(origin, aurora.clone(), io, &env).unwrap();
+        let result = engine.call(
+            origin,
+            CrossContractCall::ADDRESS,
+            Wei::zero(),
+            input.clone(),
+            u64::MAX,
+            Vec::new(),
+            &mut handler,
+        );
+        (result.map(|result: SubmitResult| result.promises), handler)
+    };
+
+    // No router code to create the router with.
+    let (result, handler) = call();
+    assert_eq!(
+        result.unwrap_err().kind.as_bytes(),
+        b"ERR_XCC_ROUTER_CODE_NOT_SET"
+    );
+    assert!(handler.scheduled_promises.is_empty());
+
+    // The balance must cover the router deposit and the NEAR attached.
+    xcc::set_router_code(&mut io, b"router");
+    xcc::fund(&mut io, &origin, xcc::ROUTER_DEPOSIT + 15);
+    let (result, handler) = call();
+    let stats = result.unwrap();
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.attached_balance, xcc::ROUTER_DEPOSIT + 10);
+    assert_eq!(xcc::get_balance(&io, &origin), 5);
+    assert!(xcc::has_router(&io, &origin));
+
+    let router = router_account_id(&origin, &aurora);
+    let promises: Vec<_> = handler.scheduled_promises.values().collect();
+    assert_eq!(promises.len(), 3);
+    match promises[0] {
+        promise::PromiseArgs::Batch(batch) => {
+            assert_eq!(batch.target_account_id, router);
+            assert!(matches!(batch.actions[0], PromiseAction::CreateAccount));
+            assert!(matches!(
+                &batch.actions[2],
+                PromiseAction::DeployConotract { code } if code == b"router"
+            ));
+        }
+        _ => panic!("Expected the creation of the router"),
+    }
+    match promises[1] {
+        promise::PromiseArgs::Callback { callback, .. } => {
+            assert_eq!(callback.target_account_id, router);
+            assert_eq!(callback.method, "execute");
+            assert_eq!(callback.attached_balance, 10);
+            let executed = PromiseCreateArgs::try_from_slice(&callback.args).unwrap();
+            assert_eq!(executed.target_account_id, near_call.target_account_id);
+            assert_eq!(executed.method, near_call.method);
+        }
+        _ => panic!("Expected the call of the router"),
+    }
+    match promises[2] {
+        promise::PromiseArgs::Callback { callback, .. } => {
+            assert_eq!(callback.target_account_id, aurora);
+            assert_eq!(callback.method, "xcc_callback");
+            assert_eq!(
+                XccCallbackArgs::try_from_slice(&callback.args).unwrap(),
+                XccCallbackArgs {
+                    address: origin.0,
+                    input: vec![1, 2, 3],
+                }
+            );
+        }
+        _ => panic!("Expected the callback of the caller"),
+    }
+
+    // The router exists: only the call and its callback are scheduled.
+    let (result, handler) = call();
+    assert_eq!(result.unwrap().count, 2);
+    assert_eq!(handler.scheduled_promises.len(), 2);
+    assert!(matches!(
+        handler.scheduled_promises.values().next(),
+        Some(promise::PromiseArgs::Create(_))
+    ));
+    assert_eq!(xcc::get_balance(&io, &origin), 0);
+
+    let (result, handler) = call();
+    assert_eq!(
+        result.unwrap_err().kind.as_bytes(),
+        b"ERR_XCC_INSUFFICIENT_BALANCE"
+    );
+    assert!(handler.scheduled_promises.is_empty());
+}
+
+/// EVM code run by a callback reads the results of the promises it is the callback of.
+#[test]
+fn test_promise_results_precompile() {
+    use aurora_engine::parameters::TransactionStatus;
+    use aurora_engine_precompiles::promise_result::{PromiseResults, FAILED, SUCCESSFUL};
+
+    let storage = RwLock::new(storage::Storage::default());
+    let io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let origin = Address::from_low_u64_be(0x42);
+    let call = |promise_results: Vec<PromiseResult>, input: Vec<u8>| {
+        let mut handler = promise::PromiseTracker {
+            promise_results,
+            ..Default::default()
+        };
+        let mut engine =
+            engine::Engine::new(origin, env.current_account_id.clone(), io, &env).unwrap();
+        let result = engine
+            .call(
+                origin,
+                PromiseResults::ADDRESS,
+                Wei::zero(),
+                input,
+                u64::MAX,
+                Vec::new(),
+                &mut handler,
+            )
+            .unwrap();
+        match result.status {
+            TransactionStatus::Succeed(output) => output,
+            other => panic!("Unexpected status: {:?}", other),
+        }
+    };
+    let index = |i: u64| {
+        let mut input = vec![0u8; 32];
+        U256::from(i).to_big_endian(&mut input);
+        input
+    };
+
+    // Outside of a callback there are no results.
+    assert_eq!(
+        U256::from_big_endian(&call(Vec::new(), Vec::new())),
+        U256::zero()
+    );
+
+    let results = vec![
+        PromiseResult::Failed,
+        PromiseResult::Successful(b"done".to_vec()),
+    ];
+    let count = call(results.clone(), Vec::new());
+    assert_eq!(U256::from_big_endian(&count), U256::from(2));
+    let failed = call(results.clone(), index(0));
+    assert_eq!(failed[31], FAILED);
+    let succeeded = call(results, index(1));
+    assert_eq!(succeeded[31], SUCCESSFUL);
+    assert_eq!(U256::from_big_endian(&succeeded[64..96]), U256::from(4));
+    assert_eq!(&succeeded[96..100], b"done");
+}
+
+/// Precompiles read the engine state of the standalone storage, as they do on chain.
+#[test]
+fn test_precompiles_read_standalone_state() {
+    use aurora_engine::near_state;
+    use aurora_engine::parameters::TransactionStatus;
+    use aurora_engine_precompiles::near_state::NearStateRead;
+
+    let storage = RwLock::new(storage::Storage::default());
+    let mut io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let oracle: AccountId = "oracle.near".parse().unwrap();
+    near_state::set_source(&mut io, &oracle, true);
+    near_state::publish(&mut io, &oracle, b"price", Some(vec![1, 2, 3]), 7).unwrap();
+
+    let origin = Address::from_low_u64_be(0x42);
+    let mut engine = engine::Engine::new(origin, env.current_account_id.clone(), io, &env).unwrap();
+    let input = [
+        &[oracle.as_bytes().len() as u8],
+        oracle.as_bytes(),
+        b"price",
+    ]
+    .concat();
+    let result = engine
+        .call(
+            origin,
+            NearStateRead::ADDRESS,
+            Wei::zero(),
+            input,
+            u64::MAX,
+            Vec::new(),
+            &mut promise::PromiseTracker::default(),
+        )
+        .unwrap();
+    let output = match result.status {
+        TransactionStatus::Succeed(output) => output,
+        other => panic!("Unexpected status: {:?}", other),
+    };
+    // abi.encode(uint64 publishedAt, bytes value)
+    assert_eq!(U256::from_big_endian(&output[..32]), U256::from(7));
+    assert_eq!(U256::from_big_endian(&output[64..96]), U256::from(3));
+    assert_eq!(&output[96..99], &[1, 2, 3]);
+}
+
+/// Blocks before `era::PARIS_HEIGHT` replay with the `DIFFICULTY` they were executed with.
+#[test]
+fn test_difficulty_replay_before_activation() {
+    use aurora_engine::era::PARIS_HEIGHT;
+    use aurora_engine::parameters::TransactionStatus;
+
+    let storage = RwLock::new(storage::Storage::default());
+    let io = storage::StoragePointer(&storage);
+    let origin = Address::from_low_u64_be(0x42);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    // DIFFICULTY PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+    let code = [0x44, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+    let mut engine = engine::Engine::new(origin, env.current_account_id.clone(), io, &env).unwrap();
+    let result = engine
+        .deploy_code_with_input(evm_deploy(&code), &mut promise::PromiseTracker::default())
+        .unwrap();
+    let contract = match result.status {
+        TransactionStatus::Succeed(bytes) => Address::from_slice(&bytes),
+        other => panic!("Unexpected status: {:?}", other),
+    };
+
+    let difficulty_at = |block_height: u64| {
+        let env = mocks::default_env(block_height);
+        let mut engine =
+            engine::Engine::new(origin, env.current_account_id.clone(), io, &env).unwrap();
+        let result = engine
+            .call(
+                origin,
+                contract,
+                Wei::zero(),
+                Vec::new(),
+                u64::MAX,
+                Vec::new(),
+                &mut promise::PromiseTracker::default(),
+            )
+            .unwrap();
+        match result.status {
+            TransactionStatus::Succeed(output) => U256::from_big_endian(&output),
+            other => panic!("Unexpected status: {:?}", other),
+        }
+    };
+
+    assert_eq!(difficulty_at(PARIS_HEIGHT - 1), U256::zero());
+    let expected =
+        engine::compute_block_randomness(H256::zero(), &origin, engine::get_nonce(&io, &origin));
+    assert_eq!(
+        difficulty_at(PARIS_HEIGHT),
+        U256::from_big_endian(expected.as_bytes())
+    );
+}
+
+/// An operator approved with a zero spend limit can make the allowed calls without value,
+/// until the owner revokes it.
+#[test]
+fn test_operator_zero_spend_limit_and_revocation() {
+    use aurora_engine::operator::{
+        self, OperatorApprovalMessage, OperatorError, OperatorRevocationMessage,
+    };
+    use aurora_engine::parameters::{
+        CallOnBehalfArgs, OperatorAllowedCall, OperatorApprovalArgs, OperatorRevocationArgs,
+    };
+
+    let storage = RwLock::new(storage::Storage::default());
+    let mut io = storage::StoragePointer(&storage);
+    let env = mocks::default_env(0);
+    mocks::init_evm(io, &env, 1313161554);
+    let chain_id = engine::get_state(&io).unwrap().chain_id;
+    let aurora = env.current_account_id.clone();
+    let operator: AccountId = "operator.near".parse().unwrap();
+    let secret_key = secp256k1::SecretKey::random(&mut rand::thread_rng());
+    let owner = EthAddress::from(crate::test_utils::address_from_secret_key(&secret_key));
+    let sign = |hash: H256| {
+        let message = secp256k1::Message::parse_slice(hash.as_bytes()).unwrap();
+        let (signature, recovery_id) = secp256k1::sign(&message, &secret_key);
+        let mut bytes = [0u8; 65];
+        bytes[..64].copy_from_slice(&signature.serialize());
+        bytes[64] = recovery_id.serialize() + 27;
+        bytes
+    };
+
+    let allowed_calls = vec![OperatorAllowedCall {
+        contract: EthAddress::new([1; 20]),
+        selector: None,
+    }];
+    let message = OperatorApprovalMessage {
+        chain_id,
+        engine_account_id: &aurora,
+        owner,
+        operator: &operator,
+        spend_limit: [0; 32],
+        allowed_calls: &allowed_calls,
+        expiry_height: 100,
+        nonce: 0,
+    };
+    let approval = OperatorApprovalArgs {
+        owner,
+        operator: operator.clone(),
+        spend_limit: [0; 32],
+        allowed_calls: allowed_calls.clone(),
+        expiry_height: 100,
+        signature: sign(message.signing_hash()),
+    };
+    operator::approve(&mut io, chain_id, &aurora, approval).unwrap();
+
+    let call = |value: u64| {
+        let mut raw_value = [0u8; 32];
+        U256::from(value).to_big_endian(&mut raw_value);
+        CallOnBehalfArgs {
+            owner,
+            contract: EthAddress::new([1; 20]),
+            value: raw_value,
+            input: Vec::new(),
+            gas_limit: 100_000,
+        }
+    };
+    assert_eq!(
+        operator::consume_allowance(&mut io, &operator, &call(0), 1),
+        Ok(())
+    );
+    assert_eq!(
+        operator::consume_allowance(&mut io, &operator, &call(1), 1),
+        Err(OperatorError::AllowanceExceeded)
+    );
+    // The approval stays after a call without value.
+    assert_eq!(
+        operator::consume_allowance(&mut io, &operator, &call(0), 1),
+        Ok(())
+    );
+
+    let message = OperatorRevocationMessage {
+        chain_id,
+        engine_account_id: &aurora,
+        owner,
+        operator: &operator,
+        nonce: 1,
+    };
+    let revocation = OperatorRevocationArgs {
+        owner,
+        operator: operator.clone(),
+        signature: sign(message.signing_hash()),
+    };
+    operator::revoke(&mut io, chain_id, &aurora, revocation.clone()).unwrap();
+    assert!(operator::get_approval(&io, &Address::from(owner), &operator).is_none());
+    assert_eq!(
+        operator::consume_allowance(&mut io, &operator, &call(0), 1),
+        Err(OperatorError::NotApproved)
+    );
+    // The revocation consumed its nonce.
+    assert_eq!(
+        operator::revoke(&mut io, chain_id, &aurora, revocation),
+        Err(OperatorError::InvalidSignature)
+    );
+}
+
 fn evm_deploy(code: &[u8]) -> Vec<u8> {
     let len = code.len();
     if len > u16::MAX as usize {
@@ -1,6 +1,8 @@
-use aurora_engine::engine;
+use aurora_engine::parameters::{TransactionStatus, ViewCallArgs};
+use aurora_engine::{engine, state_migration, storage_sharding, upgrade_history};
 use aurora_engine_sdk::env::Timestamp;
 use aurora_engine_types::{types::Wei, Address, H256, U256};
+use engine_standalone_storage::sync::{self, ExecutionSemantics};
 use engine_standalone_storage::BlockMetadata;
 
 use crate::test_utils::standalone::{mocks, storage::create_db};
@@ -292,3 +294,310 @@ fn test_transaction_index() {
     drop(storage);
     temp_dir.close().unwrap();
 }
+
+#[test]
+fn test_replay_semantics_follow_upgrade_history() {
+    let (temp_dir, mut storage) = create_db();
+    let code = b"upgraded engine code";
+    let code_hash = aurora_engine_sdk::keccak(code);
+
+    let block_height = 10;
+    let block_hash = mocks::compute_block_hash(block_height);
+    let block_metadata = BlockMetadata {
+        timestamp: Timestamp::new(0),
+        random_seed: H256::zero(),
+    };
+    storage
+        .set_block_data(block_hash, block_height, block_metadata)
+        .unwrap();
+    let diff = {
+        let mut io = storage.access_engine_storage_at_position(block_height, 0, &[]);
+        upgrade_history::set_pending(&mut io, code);
+        upgrade_history::activate_pending(&mut io, block_height).unwrap();
+        io.get_transaction_diff()
+    };
+    crate::test_utils::standalone::storage::commit(
+        &mut storage,
+        diff,
+        block_hash,
+        0,
+        H256([1u8; 32]),
+    );
+
+    // any code is accepted unless the replica is restricted to some code hashes
+    assert_eq!(
+        sync::execution_semantics(&storage, block_height + 1, 0).unwrap(),
+        ExecutionSemantics::Current
+    );
+    storage.set_supported_engine_code(vec![H256([2u8; 32])]);
+    // nothing was recorded before the upgrade
+    assert_eq!(
+        sync::execution_semantics(&storage, block_height - 1, 0).unwrap(),
+        ExecutionSemantics::Current
+    );
+    assert_eq!(
+        sync::execution_semantics(&storage, block_height + 1, 0).unwrap(),
+        ExecutionSemantics::Unsupported {
+            code_hash,
+            activated_at: block_height,
+        }
+    );
+    storage.set_supported_engine_code(vec![code_hash]);
+    assert_eq!(
+        sync::execution_semantics(&storage, block_height + 1, 0).unwrap(),
+        ExecutionSemantics::Current
+    );
+
+    drop(storage);
+    temp_dir.close().unwrap();
+}
+
+#[test]
+fn test_storage_sharding_migration() {
+    let (temp_dir, mut storage) = create_db();
+    let address = Address([0x42; 20]);
+    let slot = H256::from_low_u64_be;
+    let value = |i: u64| H256::from_low_u64_be(100 + i);
+
+    let block_height = 10;
+    let block_hash = mocks::compute_block_hash(block_height);
+    let block_metadata = BlockMetadata {
+        timestamp: Timestamp::new(0),
+        random_seed: H256::zero(),
+    };
+    storage
+        .set_block_data(block_hash, block_height, block_metadata)
+        .unwrap();
+    let mut position = 0;
+    let mut execute =
+        |storage: &mut engine_standalone_storage::Storage,
+         f: &dyn Fn(&mut engine_standalone_storage::engine_state::EngineStateAccess)| {
+            let diff = {
+                let mut io = storage.access_engine_storage_at_position(block_height, position, &[]);
+                f(&mut io);
+                io.get_transaction_diff()
+            };
+            crate::test_utils::standalone::storage::commit(
+                storage,
+                diff,
+                block_hash,
+                position,
+                H256::from_low_u64_be(position.into()),
+            );
+            position += 1;
+        };
+
+    execute(&mut storage, &|io| {
+        for i in 1..=3 {
+            engine::set_storage(io, &address, &slot(i), &value(i), 0);
+        }
+    });
+    execute(&mut storage, &|io| {
+        storage_sharding::enable(io, &address).unwrap();
+        let generation = engine::get_generation(io, &address);
+        // slots not migrated yet are still visible
+        assert_eq!(
+            engine::get_storage(io, &address, &slot(1), generation),
+            value(1)
+        );
+        engine::set_storage(io, &address, &slot(4), &value(4), generation);
+        engine::remove_storage(io, &address, &slot(3), generation);
+        assert_eq!(
+            engine::get_storage(io, &address, &slot(3), generation),
+            H256::zero()
+        );
+    });
+
+    let batches = storage.storage_sharding_batches(address, 1);
+    let keys: Vec<_> = batches
+        .iter()
+        .flat_map(|batch| batch.keys.clone())
+        .collect();
+    assert_eq!(keys, vec![slot(1).0, slot(2).0]);
+    assert_eq!(batches.len(), 2);
+
+    execute(&mut storage, &|io| {
+        for batch in &batches {
+            assert_eq!(
+                storage_sharding::migrate(io, &address, &batch.keys).unwrap(),
+                1
+            );
+        }
+        storage_sharding::finish(io, &address).unwrap();
+        let generation = engine::get_generation(io, &address);
+        for i in [1, 2, 4] {
+            assert_eq!(
+                engine::get_storage(io, &address, &slot(i), generation),
+                value(i)
+            );
+        }
+        assert_eq!(
+            engine::get_storage(io, &address, &slot(3), generation),
+            H256::zero()
+        );
+    });
+    assert!(storage.storage_sharding_batches(address, 1).is_empty());
+
+    drop(storage);
+    temp_dir.close().unwrap();
+}
+
+#[test]
+fn test_state_migrations_apply_once_in_order() {
+    use aurora_engine::state_migration::{StateMigration, StateMigrationError};
+    use aurora_engine_sdk::io::{StorageIntermediate, IO};
+    use engine_standalone_storage::engine_state::EngineStateAccess;
+
+    const KEY: &[u8] = b"migrated";
+
+    /// Appends its version to the value under `KEY`.
+    struct Append(u32);
+
+    impl<'db, 'input, 'output> StateMigration<EngineStateAccess<'db, 'input, 'output>> for Append {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn migrate(
+            &self,
+            io: &mut EngineStateAccess<'db, 'input, 'output>,
+            _block_height: u64,
+        ) -> Result<(), StateMigrationError> {
+            let mut value = io.read_storage(KEY).map(|v| v.to_vec()).unwrap_or_default();
+            value.push(self.0 as u8);
+            io.write_storage(KEY, &value);
+            Ok(())
+        }
+    }
+
+    let (temp_dir, mut storage) = create_db();
+    let block_height = 10;
+    let mut io = storage.access_engine_storage_at_position(block_height, 0, &[]);
+    assert_eq!(state_migration::get_version(&io), Ok(0));
+
+    let migrations: Vec<Box<dyn StateMigration<_>>> =
+        vec![Box::new(Append(1)), Box::new(Append(2))];
+    assert_eq!(
+        state_migration::apply(&mut io, &migrations, block_height),
+        Ok(2)
+    );
+    assert_eq!(io.read_storage(KEY).unwrap().to_vec(), vec![1, 2]);
+
+    // Only the migrations of the new code above the stored version run
+    let migrations: Vec<Box<dyn StateMigration<_>>> = vec![
+        Box::new(Append(1)),
+        Box::new(Append(2)),
+        Box::new(Append(5)),
+    ];
+    assert_eq!(
+        state_migration::apply(&mut io, &migrations, block_height),
+        Ok(5)
+    );
+    assert_eq!(io.read_storage(KEY).unwrap().to_vec(), vec![1, 2, 5]);
+    assert_eq!(
+        state_migration::apply(&mut io, &migrations, block_height),
+        Ok(5)
+    );
+    assert_eq!(state_migration::get_version(&io), Ok(5));
+
+    let migrations: Vec<Box<dyn StateMigration<_>>> =
+        vec![Box::new(Append(7)), Box::new(Append(6))];
+    assert_eq!(
+        state_migration::apply(&mut io, &migrations, block_height),
+        Err(StateMigrationError::UnorderedMigrations)
+    );
+    assert_eq!(io.read_storage(KEY).unwrap().to_vec(), vec![1, 2, 5]);
+
+    drop(io);
+    drop(storage);
+    temp_dir.close().unwrap();
+}
+
+#[test]
+fn test_view_cache() {
+    let mut runner = test_utils::standalone::StandaloneRunner::default();
+    runner.init_evm();
+    runner.storage.set_view_cache_capacity(Some(8));
+    // Returns `SELFBALANCE`.
+    let code = hex::decode("4760005260206000f3").unwrap();
+    let address = Address::from_low_u64_be(0xbeef);
+    runner.mint_account(address, Wei::new_u64(7), U256::zero(), Some(code.clone()));
+    let view_args = || ViewCallArgs {
+        sender: Address::zero().into(),
+        address: address.into(),
+        amount: [0u8; 32],
+        input: Vec::new(),
+    };
+    let balance_of = |status: TransactionStatus| match status {
+        TransactionStatus::Succeed(output) => U256::from_big_endian(&output),
+        other => panic!("Unexpected status {:?}", other),
+    };
+
+    let first_block = mocks::compute_block_hash(runner.env.block_height);
+    for _ in 0..2 {
+        let status = runner.storage.view(first_block, view_args()).unwrap();
+        assert_eq!(balance_of(status), U256::from(7));
+    }
+    let cache = runner.storage.view_cache().unwrap();
+    assert_eq!((cache.counters().hits, cache.counters().misses), (1, 1));
+    assert_eq!(cache.len(), 1);
+
+    // A new block keeps the results of earlier heights, and each height has its own results.
+    runner.mint_account(address, Wei::new_u64(9), U256::zero(), Some(code));
+    assert_eq!(runner.storage.view_cache().unwrap().len(), 1);
+    let second_block = mocks::compute_block_hash(runner.env.block_height);
+    let status = runner.storage.view(second_block, view_args()).unwrap();
+    assert_eq!(balance_of(status), U256::from(9));
+    let status = runner.storage.view(first_block, view_args()).unwrap();
+    assert_eq!(balance_of(status), U256::from(7));
+    let cache = runner.storage.view_cache().unwrap();
+    assert_eq!((cache.counters().hits, cache.counters().misses), (2, 2));
+    assert_eq!(cache.len(), 2);
+
+    // Storing a block forgets the results at its height and above.
+    let first_height = runner
+        .storage
+        .get_block_height_by_hash(first_block)
+        .unwrap();
+    let metadata = runner.storage.get_block_metadata(first_block).unwrap();
+    runner
+        .storage
+        .set_block_data(first_block, first_height, metadata)
+        .unwrap();
+    assert!(runner.storage.view_cache().unwrap().is_empty());
+
+    runner.close();
+}
+
+/// `profile_submit` counts the host functions called by a transaction without storing its
+/// changes.
+#[test]
+fn test_profile_submit() {
+    let mut runner = test_utils::standalone::StandaloneRunner::default();
+    runner.init_evm();
+    let mut signer = Signer::random();
+    let address = test_utils::address_from_secret_key(&signer.secret_key);
+    runner.mint_account(address, Wei::new_u64(1000), signer.nonce.into(), None);
+    let dest_address = Address::from_low_u64_be(0xbeef);
+    let signed_tx = test_utils::sign_transaction(
+        test_utils::transfer(dest_address, Wei::new_u64(10), signer.use_nonce().into()),
+        Some(runner.chain_id),
+        &signer.secret_key,
+    );
+    let transaction_bytes = rlp::encode(&signed_tx).to_vec();
+
+    let block_hash = mocks::compute_block_hash(runner.env.block_height);
+    // The changes are discarded, so the same transaction can be profiled again.
+    for _ in 0..2 {
+        let (result, profile) = runner
+            .storage
+            .profile_submit(block_hash, &transaction_bytes)
+            .unwrap();
+        assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+        assert!(profile.storage_reads > 0);
+        assert!(profile.storage_writes > 0);
+        assert_eq!(profile.promises, 0);
+    }
+
+    runner.close();
+}
@@ -1,6 +1,6 @@
 use aurora_engine::deposit_event::TokenMessageData;
 use aurora_engine_sdk::env::{Env, Timestamp};
-use aurora_engine_types::types::Fee;
+use aurora_engine_types::types::{EthAddress, Fee};
 use aurora_engine_types::{account_id::AccountId, types::Wei, Address, H256, U256};
 use borsh::BorshSerialize;
 use engine_standalone_storage::sync;
@@ -123,7 +123,8 @@ fn test_consume_deploy_erc20_message() {
 
     let args = aurora_engine::parameters::DeployErc20TokenArgs {
         nep141: token.clone(),
-    };
+    }
+    .into();
     let transaction_message = sync::types::TransactionMessage {
         block_hash: block_message.hash,
         near_tx_hash: H256([7u8; 32]),
@@ -331,6 +332,224 @@ fn test_consume_submit_message() {
     assert_eq!(runner.get_nonce(&signer_address), U256::one());
 }
 
+#[test]
+fn test_export_transaction_bundle() {
+    let (mut runner, _) = initialize();
+
+    let caller = "some_account.near";
+    let initial_balance = Wei::new_u64(800_000);
+    let transfer_amount = Wei::new_u64(115_321);
+    let caller_address = aurora_engine_sdk::types::near_account_to_evm_address(caller.as_bytes());
+    let recipient_address = Address([1u8; 20]);
+    runner.mint_account(caller_address, initial_balance, U256::zero(), None);
+
+    runner.env.block_height += 1;
+    runner.env.signer_account_id = caller.parse().unwrap();
+    runner.env.predecessor_account_id = caller.parse().unwrap();
+    test_utils::standalone::mocks::insert_block(&mut runner.storage, runner.env.block_height);
+    let block_hash = test_utils::standalone::mocks::compute_block_hash(runner.env.block_height);
+
+    let near_tx_hash = H256([7u8; 32]);
+    let args = simple_transfer_args(recipient_address, transfer_amount);
+    let transaction_message = sync::types::TransactionMessage {
+        block_hash,
+        near_tx_hash,
+        position: 0,
+        succeeded: true,
+        signer: runner.env.signer_account_id(),
+        caller: runner.env.predecessor_account_id(),
+        attached_near: 0,
+        transaction: sync::types::TransactionKind::Call(args.clone()),
+    };
+
+    sync::consume_message(
+        &mut runner.storage,
+        sync::types::Message::Transaction(Box::new(transaction_message)),
+    )
+    .unwrap();
+
+    // `call` transactions are stored under their NEAR hash
+    let bundle = runner
+        .storage
+        .export_transaction_bundle(near_tx_hash)
+        .unwrap();
+    assert_eq!(bundle.block_height, runner.env.block_height);
+    assert_eq!(bundle.position, 0);
+    assert_eq!(bundle.method, "call");
+    assert_eq!(bundle.caller, caller);
+    assert_eq!(
+        base64::decode(&bundle.input).unwrap(),
+        args.try_to_vec().unwrap()
+    );
+
+    // The caller balance was read before the transfer and modified by it.
+    let balance_key = base64::encode(aurora_engine_types::storage::address_to_key(
+        aurora_engine_types::storage::KeyPrefix::Balance,
+        &caller_address,
+    ));
+    let pre_balance = bundle
+        .pre_state
+        .iter()
+        .find(|entry| entry.key == balance_key)
+        .and_then(|entry| entry.value.as_ref())
+        .map(|value| base64::decode(value).unwrap())
+        .unwrap();
+    assert_eq!(pre_balance, initial_balance.to_bytes().to_vec());
+    assert!(bundle
+        .post_state
+        .iter()
+        .any(|entry| entry.key == balance_key));
+
+    // Only the transferred amount moved between the two accounts.
+    assert_eq!(bundle.balance_changes.len(), 2);
+    let change_of = |address: Address| {
+        bundle
+            .balance_changes
+            .iter()
+            .find(|change| change.address == hex::encode(address))
+            .map(|change| (change.before.clone(), change.after.clone()))
+            .unwrap()
+    };
+    assert_eq!(
+        change_of(caller_address),
+        (
+            initial_balance.raw().to_string(),
+            (initial_balance - transfer_amount).raw().to_string()
+        )
+    );
+    assert_eq!(
+        change_of(recipient_address),
+        ("0".to_string(), transfer_amount.raw().to_string())
+    );
+
+    let json = bundle.to_json().unwrap();
+    assert_eq!(
+        engine_standalone_storage::bundle::TransactionBundle::from_json(&json).unwrap(),
+        bundle
+    );
+
+    runner.close()
+}
+
+#[test]
+fn test_block_export() {
+    let (mut runner, _) = initialize();
+    let export_dir = tempfile::tempdir().unwrap();
+    runner
+        .storage
+        .set_block_export_dir(Some(export_dir.path().to_path_buf()));
+
+    let caller = "some_account.near";
+    let initial_balance = Wei::new_u64(800_000);
+    let transfer_amount = Wei::new_u64(115_321);
+    let caller_address = aurora_engine_sdk::types::near_account_to_evm_address(caller.as_bytes());
+    let recipient_address = Address([1u8; 20]);
+    runner.mint_account(caller_address, initial_balance, U256::zero(), None);
+
+    runner.env.block_height += 1;
+    runner.env.signer_account_id = caller.parse().unwrap();
+    runner.env.predecessor_account_id = caller.parse().unwrap();
+    let block_message = sync::types::BlockMessage {
+        height: runner.env.block_height,
+        hash: test_utils::standalone::mocks::compute_block_hash(runner.env.block_height),
+        metadata: engine_standalone_storage::BlockMetadata {
+            timestamp: Timestamp::new(1_000_002),
+            random_seed: H256([3u8; 32]),
+        },
+    };
+    sync::consume_message(
+        &mut runner.storage,
+        sync::types::Message::Block(block_message.clone()),
+    )
+    .unwrap();
+
+    let near_tx_hash = H256([7u8; 32]);
+    let transaction_message = sync::types::TransactionMessage {
+        block_hash: block_message.hash,
+        near_tx_hash,
+        position: 1,
+        succeeded: true,
+        signer: runner.env.signer_account_id(),
+        caller: runner.env.predecessor_account_id(),
+        attached_near: 0,
+        transaction: sync::types::TransactionKind::Call(simple_transfer_args(
+            recipient_address,
+            transfer_amount,
+        )),
+    };
+    sync::consume_message(
+        &mut runner.storage,
+        sync::types::Message::Transaction(Box::new(transaction_message)),
+    )
+    .unwrap();
+
+    let export = runner.storage.export_block(block_message.hash).unwrap();
+    assert_eq!(export.block_height, block_message.height);
+    assert_eq!(export.timestamp, 1_000_002);
+    assert_eq!(export.transactions.len(), 1);
+    let transaction = &export.transactions[0];
+    assert_eq!(transaction.tx_hash, hex::encode(near_tx_hash));
+    assert_eq!(transaction.position, 1);
+    assert_eq!(transaction.method, "call");
+    let receipt = transaction.receipt.as_ref().unwrap();
+    assert_eq!(receipt.status, "Succeed");
+    assert!(receipt.gas_used > 0);
+    assert!(!transaction.state_diff.is_empty());
+    assert_eq!(transaction.balance_changes.len(), 2);
+
+    // The file of the block is written once the next block is received.
+    let file = export_dir.path().join(export.file_name());
+    assert!(!file.exists());
+    let mut next_block = block_message.clone();
+    next_block.height += 1;
+    next_block.hash = test_utils::standalone::mocks::compute_block_hash(next_block.height);
+    sync::consume_message(&mut runner.storage, sync::types::Message::Block(next_block)).unwrap();
+    let json = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(
+        engine_standalone_storage::block_export::BlockExport::from_json(&json).unwrap(),
+        export
+    );
+
+    // The last block is written on demand.
+    let path = runner.storage.flush_block_export().unwrap().unwrap();
+    let json = std::fs::read_to_string(&path).unwrap();
+    let last = engine_standalone_storage::block_export::BlockExport::from_json(&json).unwrap();
+    assert_eq!(last.block_height, block_message.height + 1);
+    assert!(last.transactions.is_empty());
+
+    runner.close()
+}
+
+#[test]
+fn test_sync_progress() {
+    let mut progress = sync::status::SyncProgress::new();
+    assert_eq!(progress.status(), sync::status::SyncStatus::NotStarted);
+
+    let block_message = sample_block();
+    progress.set_target_height(block_message.height + 10);
+    progress.record(&sync::types::Message::Block(block_message.clone()));
+    match progress.status() {
+        sync::status::SyncStatus::Syncing(info) => {
+            assert_eq!(info.starting_height, block_message.height);
+            assert_eq!(info.current_height, block_message.height);
+            assert_eq!(info.highest_height, block_message.height + 10);
+            assert_eq!(info.lag, 10);
+        }
+        other => panic!("Unexpected status {:?}", other),
+    }
+
+    let mut head = block_message;
+    head.height += 10;
+    progress.record(&sync::types::Message::Block(head));
+    assert_eq!(
+        progress.status(),
+        sync::status::SyncStatus::Synced { height: 111 }
+    );
+    assert!(progress
+        .to_prometheus()
+        .contains("aurora_standalone_sync_lag_blocks 0"));
+}
+
 fn mock_proof(recipient_address: Address, deposit_amount: Wei) -> aurora_engine::proof::Proof {
     let eth_custodian_address = test_utils::standalone::mocks::ETH_CUSTODIAN_ADDRESS;
 
@@ -341,8 +560,8 @@ fn mock_proof(recipient_address: Address, deposit_amount: Wei) -> aurora_engine:
             .unwrap();
 
     let deposit_event = aurora_engine::deposit_event::DepositedEvent {
-        eth_custodian_address: eth_custodian_address.0,
-        sender: [0u8; 20],
+        eth_custodian_address: eth_custodian_address.into(),
+        sender: EthAddress::zero(),
         token_message_data,
         amount: deposit_amount.raw().as_u128(),
         fee,
@@ -382,7 +601,7 @@ fn simple_transfer_args(
     transfer_amount: Wei,
 ) -> aurora_engine::parameters::CallArgs {
     aurora_engine::parameters::CallArgs::V2(aurora_engine::parameters::FunctionCallArgsV2 {
-        contract: dest_address.0,
+        contract: dest_address.into(),
         value: transfer_amount.to_bytes(),
         input: Vec::new(),
     })
@@ -1,6 +1,9 @@
 use aurora_engine_sdk::env::Env;
 use aurora_engine_types::{types::Wei, Address, H256, U256};
-use engine_standalone_tracing::{sputnik, types::TransactionTrace};
+use engine_standalone_tracing::{
+    geth, sputnik,
+    types::{CallType, TransactionTrace},
+};
 use serde::Deserialize;
 use std::path::Path;
 
@@ -176,12 +179,160 @@ fn test_evm_tracing() {
 
     let op_codes: Vec<u8> = trace.logs().0.iter().map(|l| l.opcode.0).collect();
     assert_eq!(op_codes.as_slice(), &EXPECTED_OP_CODES);
+
+    // The intrinsic gas of the transaction is 22_040, the rest is available to the first step.
+    let gas: Vec<u64> = trace.logs().0.iter().map(|l| l.gas.into_u64()).collect();
+    assert_eq!(gas[0], 90_000 - 22_040);
+    for (i, log) in trace.logs().0.iter().enumerate().skip(1) {
+        assert_eq!(gas[i], gas[i - 1] - EXPECTED_COSTS[i - 1] as u64);
+        assert_eq!(log.depth.into_u32(), 1);
+    }
+
+    // Check the geth struct logger format
+    let json = geth::transaction_trace(&trace);
+    assert_eq!(json["gas"], trace.gas().into_u64());
+    assert_eq!(json["failed"], false);
+    assert_eq!(json["returnValue"], "");
+    let struct_logs = json["structLogs"].as_array().unwrap();
+    assert_eq!(struct_logs.len(), EXPECTED_POSITIONS.len());
+    assert_eq!(
+        struct_logs[0],
+        serde_json::json!({
+            "pc": 0,
+            "op": "PUSH1",
+            "gas": 90_000 - 22_040,
+            "gasCost": 3,
+            "depth": 1,
+            "stack": [],
+            "memory": [],
+            "storage": {},
+        })
+    );
+    // `PUSH1 0x60 PUSH1 0x40 MSTORE` stores the free memory pointer
+    assert_eq!(struct_logs[2]["op"], "MSTORE");
+    assert_eq!(struct_logs[2]["stack"], serde_json::json!(["0x60", "0x40"]));
+    assert_eq!(
+        struct_logs[3]["memory"],
+        serde_json::json!([
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+        ])
+    );
+    assert_eq!(struct_logs[26]["op"], "STOP");
+}
+
+#[test]
+fn test_call_tracer() {
+    let mut runner = standalone::StandaloneRunner::default();
+    let mut signer = test_utils::Signer::random();
+    let signer_address = test_utils::address_from_secret_key(&signer.secret_key);
+
+    // Initialize EVM
+    runner.init_evm();
+
+    let mut deploy = |runner: &mut standalone::StandaloneRunner, code: String| {
+        let deploy_tx = aurora_engine::transaction::legacy::TransactionLegacy {
+            nonce: signer.use_nonce().into(),
+            gas_price: U256::zero(),
+            gas_limit: u64::MAX.into(),
+            to: None,
+            value: Wei::zero(),
+            data: hex::decode(code).unwrap(),
+        };
+        let result = runner
+            .submit_transaction(&signer.secret_key, deploy_tx)
+            .unwrap();
+        Address::from_slice(test_utils::unwrap_success_slice(&result))
+    };
+
+    // Returns the word 42
+    let callee = deploy(
+        &mut runner,
+        format!("600a600c600039600a6000f3{}", CALLEE_CODE),
+    );
+    // Calls the callee, then the identity precompile on the word it returned
+    let caller_code = format!(
+        "6020600060006000600073{}5af150602060206020600060045afa5060406000f3",
+        hex::encode(callee.as_bytes())
+    );
+    let caller = deploy(
+        &mut runner,
+        format!("6034600c60003960346000f3{}", caller_code),
+    );
+
+    let tx = aurora_engine::transaction::legacy::TransactionLegacy {
+        nonce: signer.use_nonce().into(),
+        gas_price: U256::zero(),
+        gas_limit: 100_000.into(),
+        to: Some(caller),
+        value: Wei::zero(),
+        data: Vec::new(),
+    };
+    let mut listener = sputnik::CallTracer::default();
+    let result = sputnik::traced_call(&mut listener, || {
+        runner.submit_transaction(&signer.secret_key, tx).unwrap()
+    });
+    assert!(result.status.is_ok());
+
+    let mut word = [0u8; 32];
+    word[31] = 42;
+    let root = listener.finish(&result).unwrap();
+    assert_eq!(root.call_type, CallType::Call);
+    assert_eq!(root.from, signer_address);
+    assert_eq!(root.to, caller);
+    assert_eq!(root.value, Some(U256::zero()));
+    assert_eq!(root.gas.into_u64(), 100_000);
+    assert_eq!(root.gas_used.into_u64(), result.gas_used);
+    assert_eq!(root.output, [word, word].concat());
+    assert_eq!(root.error, None);
+    assert_eq!(root.calls.len(), 2);
+
+    let call = &root.calls[0];
+    assert_eq!(call.call_type, CallType::Call);
+    assert_eq!(call.from, caller);
+    assert_eq!(call.to, callee);
+    assert_eq!(call.value, Some(U256::zero()));
+    assert!(call.input.is_empty());
+    assert_eq!(call.output, word);
+    // PUSH1, PUSH1, MSTORE (with a word of memory), PUSH1, PUSH1, RETURN
+    assert_eq!(call.gas_used.into_u64(), 18);
+    assert!(call.gas.into_u64() > call.gas_used.into_u64());
+    assert!(call.calls.is_empty());
+
+    let static_call = &root.calls[1];
+    assert_eq!(static_call.call_type, CallType::StaticCall);
+    assert_eq!(static_call.from, caller);
+    assert_eq!(static_call.to, Address::from_low_u64_be(4));
+    assert_eq!(static_call.value, None);
+    assert_eq!(static_call.input, word);
+    // 15 plus 3 per word
+    assert_eq!(static_call.gas_used.into_u64(), 18);
+    assert_eq!(static_call.error, None);
+
+    // Check the geth callTracer format
+    let json = geth::call_frame(&root);
+    assert_eq!(json["type"], "CALL");
+    assert_eq!(
+        json["from"],
+        format!("0x{}", hex::encode(signer_address.as_bytes()))
+    );
+    assert_eq!(json["gas"], "0x186a0");
+    assert_eq!(json["input"], "0x");
+    assert_eq!(json["calls"][0]["gasUsed"], "0x12");
+    assert_eq!(
+        json["calls"][0]["output"],
+        format!("0x{}", hex::encode(word))
+    );
+    assert_eq!(json["calls"][1]["type"], "STATICCALL");
+    assert!(json["calls"][1].get("value").is_none());
 }
 
 const MAINNET_TX_79F7F8F9: &str = "02f87701028459682f00851fb8b1884182afee94c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2880c7d713b49da000084d0e30db0c080a0b1bf69eab31f6d5482f0f8a48f8fcda916db162e0b874d523293c29246e30ed4a03b79f1f9ccbc4fd6beb9809343eadfe1ddafbc0c7b8673aff2cad5bf3345c227";
 const MAINNET_TX_33DB52B0: &str = "02f877010e845d57122a85135bb40f4c826d2294c02aaa39b223fe8d0a0e5c4f27ead9083c756cc28805ebc9f935949db384d0e30db0c001a0956288989306881d6e400d6b40cf06d1210a87d71e8dc4179a3e1a37890ae318a06cbbffed3e749cf9c56de8f8db6ec3df62dbebe2e0b007d020de0b27c05db064";
 const CONTRACT_CODE: &str = "60606040525b60008054600160a060020a03191633600160a060020a0316179055346001555b5b61011e806100356000396000f3006060604052361560465763ffffffff7c010000000000000000000000000000000000000000000000000000000060003504166383197ef08114604a5780638da5cb5b14605c575b5b5b005b3415605457600080fd5b60466095565b005b3415606657600080fd5b606c60d6565b60405173ffffffffffffffffffffffffffffffffffffffff909116815260200160405180910390f35b6000543373ffffffffffffffffffffffffffffffffffffffff9081169116141560d35760005473ffffffffffffffffffffffffffffffffffffffff16ff5b5b565b60005473ffffffffffffffffffffffffffffffffffffffff16815600a165627a7a7230582080eeb07bf95bf0cca20d03576cbb3a25de3bd0d1275c173d370dcc90ce23158d0029";
 const CONTRACT_INPUT: &str = "2df07fbaabbe40e3244445af30759352e348ec8bebd4dd75467a9f29ec55d98d6cf6c418de0e922b1c55be39587364b88224451e7901d10a4a2ee2eeab3cccf51c";
+const CALLEE_CODE: &str = "602a60005260206000f3";
 const EXPECTED_POSITIONS: [u8; 27] = [
     0, 2, 4, 5, 6, 7, 9, 10, 15, 45, 47, 48, 49, 50, 55, 56, 57, 59, 60, 61, 66, 67, 69, 70, 71,
     72, 73,
@@ -214,6 +365,12 @@ fn check_transaction_trace<P: AsRef<Path>>(trace: TransactionTrace, expected_tra
             step.gas_cost,
             "gas costs should match"
         );
+        assert_eq!(log.gas.into_u64(), step.gas, "remaining gas should match");
+        assert_eq!(
+            geth::opcode_name(log.opcode),
+            step.opcode_name,
+            "opcode names should match"
+        );
     }
 }
 
@@ -0,0 +1,372 @@
+//! Engine test vectors.
+//!
+//! Raw transactions, deposit proofs and the results the engine is expected to produce for
+//! them are kept as data in versioned JSON files under `src/tests/res/vectors`. Each file is
+//! run by one generic test below, so covering a new case (e.g. a new transaction type) only
+//! takes a new entry in the file.
+
+use crate::prelude::parameters::{SubmitResult, TransactionStatus};
+use crate::prelude::transaction::{EthTransactionKind, NormalizedEthTransaction, TransactionType};
+use crate::prelude::{Address, Wei, H256, U256};
+use crate::test_utils;
+use aurora_engine::deposit_event::{DepositedEvent, TokenMessageData};
+use borsh::BorshDeserialize;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+const VECTORS_DIR: &str = "src/tests/res/vectors";
+/// Version of the layout of the vector files understood by the runners. Bumped, together
+/// with the files, whenever the layout changes.
+const VECTORS_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct VectorFile<T> {
+    version: u32,
+    vectors: Vec<T>,
+}
+
+fn load<T: DeserializeOwned>(file_name: &str) -> Vec<T> {
+    let path = std::path::Path::new(VECTORS_DIR).join(file_name);
+    let file = std::fs::File::open(&path).unwrap();
+    let file: VectorFile<T> = serde_json::from_reader(std::io::BufReader::new(file)).unwrap();
+    assert_eq!(
+        file.version,
+        VECTORS_VERSION,
+        "Unsupported version of {}",
+        path.display()
+    );
+    file.vectors
+}
+
+/// Raw transaction bytes of the vector with the given name in `transactions.json`.
+pub(crate) fn raw_transaction(name: &str) -> Vec<u8> {
+    let vector = load::<TransactionVector>("transactions.json")
+        .into_iter()
+        .find(|vector| vector.name == name)
+        .unwrap();
+    hex::decode(vector.raw).unwrap()
+}
+
+/// Proof of the vector with the given name in `deposit_proofs.json`, as JSON.
+pub(crate) fn deposit_proof(name: &str) -> String {
+    load::<DepositProofVector>("deposit_proofs.json")
+        .into_iter()
+        .find(|vector| vector.name == name)
+        .unwrap()
+        .proof
+        .to_string()
+}
+
+#[derive(Deserialize)]
+struct TransactionVector {
+    name: String,
+    raw: String,
+    #[serde(default)]
+    expected: Option<ExpectedTransaction>,
+    /// Error code if the transaction cannot be parsed.
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExpectedTransaction {
+    #[serde(rename = "type")]
+    tx_type: String,
+    sender: Option<String>,
+    chain_id: Option<u64>,
+    nonce: String,
+    gas_limit: String,
+    max_priority_fee_per_gas: String,
+    max_fee_per_gas: String,
+    to: Option<String>,
+    value: String,
+    data_len: usize,
+    access_list: Vec<ExpectedAccessTuple>,
+}
+
+#[derive(Deserialize)]
+struct ExpectedAccessTuple {
+    address: String,
+    storage_keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DepositProofVector {
+    name: String,
+    proof: serde_json::Value,
+    expected: ExpectedDeposit,
+}
+
+#[derive(Deserialize)]
+struct ExpectedDeposit {
+    eth_custodian_address: String,
+    sender: String,
+    recipient: String,
+    /// Recipient on Aurora, for deposits to Aurora.
+    eth_recipient: Option<String>,
+    amount: String,
+    fee: String,
+}
+
+#[derive(Deserialize)]
+struct SubmitVector {
+    name: String,
+    /// Accounts existing before the transaction is submitted.
+    accounts: Vec<AccountVector>,
+    raw: String,
+    #[serde(default)]
+    expected: Option<ExpectedSubmitResult>,
+    /// Error code if `submit` fails.
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AccountVector {
+    address: String,
+    balance: String,
+    nonce: String,
+    #[serde(default)]
+    code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExpectedSubmitResult {
+    /// Code of the status, e.g. `SUCCESS` or `ERR_REVERT`.
+    status: String,
+    /// Data returned or revert reason.
+    output: String,
+    gas_used: u64,
+    logs: usize,
+    /// Balances after the transaction.
+    #[serde(default)]
+    balances: BTreeMap<String, String>,
+}
+
+#[test]
+fn test_transaction_vectors() {
+    for vector in load::<TransactionVector>("transactions.json") {
+        let raw = hex::decode(&vector.raw).unwrap();
+        let parsed = EthTransactionKind::try_from(raw.as_slice());
+        let expected = match (vector.expected, vector.error) {
+            (Some(expected), None) => expected,
+            (None, Some(error)) => {
+                let error_code = parsed.map(|_| ()).unwrap_err();
+                assert_eq!(
+                    String::from_utf8_lossy(error_code.as_ref()),
+                    error,
+                    "{}",
+                    vector.name
+                );
+                continue;
+            }
+            _ => panic!("{}: expected either a transaction or an error", vector.name),
+        };
+
+        let kind = parsed.unwrap_or_else(|e| panic!("{}: {:?}", vector.name, e));
+        assert_eq!(
+            kind.tx_type(),
+            parse_tx_type(&expected.tx_type),
+            "{}",
+            vector.name
+        );
+        // Decoding and encoding again gives back the same bytes.
+        assert_eq!(Vec::<u8>::from(kind.clone()), raw, "{}", vector.name);
+
+        let tx = NormalizedEthTransaction::from(kind);
+        assert_eq!(
+            tx.address,
+            expected.sender.as_deref().map(parse_address),
+            "{}",
+            vector.name
+        );
+        assert_eq!(tx.chain_id, expected.chain_id, "{}", vector.name);
+        assert_eq!(tx.nonce, parse_u256(&expected.nonce), "{}", vector.name);
+        assert_eq!(
+            tx.gas_limit,
+            parse_u256(&expected.gas_limit),
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            tx.max_priority_fee_per_gas,
+            parse_u256(&expected.max_priority_fee_per_gas),
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            tx.max_fee_per_gas,
+            parse_u256(&expected.max_fee_per_gas),
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            tx.to,
+            expected.to.as_deref().map(parse_address),
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            tx.value,
+            Wei::new(parse_u256(&expected.value)),
+            "{}",
+            vector.name
+        );
+        assert_eq!(tx.data.len(), expected.data_len, "{}", vector.name);
+        assert_eq!(
+            tx.access_list.len(),
+            expected.access_list.len(),
+            "{}",
+            vector.name
+        );
+        for (tuple, expected) in tx.access_list.iter().zip(expected.access_list.iter()) {
+            assert_eq!(
+                tuple.address,
+                parse_address(&expected.address),
+                "{}",
+                vector.name
+            );
+            let storage_keys: Vec<H256> = expected
+                .storage_keys
+                .iter()
+                .map(|key| H256::from_slice(&hex::decode(key).unwrap()))
+                .collect();
+            assert_eq!(tuple.storage_keys, storage_keys, "{}", vector.name);
+        }
+    }
+}
+
+#[test]
+fn test_deposit_proof_vectors() {
+    for vector in load::<DepositProofVector>("deposit_proofs.json") {
+        let log_entry_data: Vec<u8> =
+            serde_json::from_value(vector.proof["log_entry_data"].clone()).unwrap();
+        let event = DepositedEvent::from_log_entry_data(&log_entry_data)
+            .unwrap_or_else(|e| panic!("{}: {:?}", vector.name, e));
+        let expected = vector.expected;
+
+        assert_eq!(
+            hex::encode(event.eth_custodian_address),
+            expected.eth_custodian_address,
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            hex::encode(event.sender),
+            expected.sender,
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            event.token_message_data.get_recipient().to_string(),
+            expected.recipient,
+            "{}",
+            vector.name
+        );
+        let eth_recipient = match &event.token_message_data {
+            TokenMessageData::Near(_) => None,
+            TokenMessageData::Eth { message, .. } => Some(hex::encode(message.recipient)),
+        };
+        assert_eq!(eth_recipient, expected.eth_recipient, "{}", vector.name);
+        assert_eq!(
+            event.amount,
+            expected.amount.parse::<u128>().unwrap(),
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            event.fee.into_u128(),
+            expected.fee.parse::<u128>().unwrap(),
+            "{}",
+            vector.name
+        );
+    }
+}
+
+#[test]
+fn test_submit_vectors() {
+    for vector in load::<SubmitVector>("submit.json") {
+        let mut runner = test_utils::deploy_evm();
+        for account in vector.accounts.iter() {
+            let address = parse_address(&account.address);
+            let balance = Wei::new(parse_u256(&account.balance));
+            let nonce = parse_u256(&account.nonce);
+            match &account.code {
+                Some(code) => runner.create_address_with_code(
+                    address,
+                    balance,
+                    nonce,
+                    hex::decode(code).unwrap(),
+                ),
+                None => runner.create_address(address, balance, nonce),
+            }
+        }
+
+        let (outcome, maybe_error) = runner.call(
+            test_utils::SUBMIT,
+            "relay.aurora",
+            hex::decode(&vector.raw).unwrap(),
+        );
+        let expected = match (vector.expected, vector.error) {
+            (Some(expected), None) => expected,
+            (None, Some(error)) => {
+                let error_message = format!("{:?}", maybe_error.unwrap());
+                assert!(
+                    error_message.contains(&error),
+                    "{}: {}",
+                    vector.name,
+                    error_message
+                );
+                continue;
+            }
+            _ => panic!("{}: expected either a result or an error", vector.name),
+        };
+
+        if let Some(error) = maybe_error {
+            panic!("{}: {:?}", vector.name, error);
+        }
+        let result =
+            SubmitResult::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap())
+                .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(result.status.as_ref()),
+            expected.status,
+            "{}",
+            vector.name
+        );
+        let output = match result.status {
+            TransactionStatus::Succeed(output) | TransactionStatus::Revert(output) => output,
+            _ => Vec::new(),
+        };
+        assert_eq!(hex::encode(output), expected.output, "{}", vector.name);
+        assert_eq!(result.gas_used, expected.gas_used, "{}", vector.name);
+        assert_eq!(result.logs.len(), expected.logs, "{}", vector.name);
+        for (address, balance) in expected.balances.iter() {
+            assert_eq!(
+                runner.get_balance(parse_address(address)),
+                Wei::new(parse_u256(balance)),
+                "{}",
+                vector.name
+            );
+        }
+    }
+}
+
+fn parse_tx_type(tx_type: &str) -> TransactionType {
+    match tx_type {
+        "legacy" => TransactionType::Legacy,
+        "eip2930" => TransactionType::Eip2930,
+        "eip1559" => TransactionType::Eip1559,
+        other => panic!("Unknown transaction type {}", other),
+    }
+}
+
+fn parse_address(address: &str) -> Address {
+    test_utils::address_from_hex(address)
+}
+
+fn parse_u256(value: &str) -> U256 {
+    U256::from_dec_str(value).unwrap()
+}
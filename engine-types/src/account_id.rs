@@ -11,9 +11,10 @@ pub const MAX_ACCOUNT_ID_LEN: usize = 64;
 /// Account identifier.
 ///
 /// This guarantees all properly constructed AccountId's are valid for the NEAR network.
-#[derive(
-    BorshSerialize, BorshDeserialize, Default, Eq, Ord, Hash, Clone, Debug, PartialEq, PartialOrd,
-)]
+/// Borsh encodes it like a `String`, but deserialization fails for invalid account ids.
+/// The empty (default) account id is accepted, since the engine state uses it to mean
+/// "no account".
+#[derive(BorshSerialize, Default, Eq, Ord, Hash, Clone, Debug, PartialEq, PartialOrd)]
 pub struct AccountId(Box<str>);
 
 impl AccountId {
@@ -81,6 +82,22 @@ impl AccountId {
     }
 }
 
+impl BorshDeserialize for AccountId {
+    fn deserialize(buf: &mut &[u8]) -> borsh::maybestd::io::Result<Self> {
+        let account_id = <Box<str>>::deserialize(buf)?;
+        if account_id.is_empty() {
+            return Ok(Self::default());
+        }
+        Self::validate(&account_id).map_err(|_| {
+            borsh::maybestd::io::Error::new(
+                borsh::maybestd::io::ErrorKind::InvalidData,
+                "ERR_INVALID_ACCOUNT_ID",
+            )
+        })?;
+        Ok(Self(account_id))
+    }
+}
+
 impl TryFrom<String> for AccountId {
     type Error = ParseAccountError;
 
@@ -463,4 +480,21 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_borsh_deserialize_validates() {
+        let valid = AccountId::new("aurora.near").unwrap();
+        let bytes = valid.try_to_vec().unwrap();
+        assert_eq!(bytes, String::from("aurora.near").try_to_vec().unwrap());
+        assert_eq!(AccountId::try_from_slice(&bytes).unwrap(), valid);
+
+        let empty = String::new().try_to_vec().unwrap();
+        assert_eq!(
+            AccountId::try_from_slice(&empty).unwrap(),
+            AccountId::default()
+        );
+
+        let invalid = String::from("Not.Valid").try_to_vec().unwrap();
+        assert!(AccountId::try_from_slice(&invalid).is_err());
+    }
 }
@@ -0,0 +1,221 @@
+//! Decoding of untrusted input.
+//!
+//! Every decoder takes the maximum size of the decoded output and checks it before
+//! allocating, so that input coming from users (transaction bytes, deposit proofs, transfer
+//! messages, view arguments) cannot make the engine allocate more than the caller expects.
+//! Decoders never panic.
+//!
+//! Borsh arguments need no such check: `borsh` only pre-allocates a bounded capacity, so a
+//! crafted length prefix fails on the missing bytes instead of allocating them.
+
+use crate::{TryFrom, Vec, H256};
+use rlp::{Decodable, Rlp};
+
+/// Maximum size of the input of a NEAR function call (`max_arguments_length`).
+pub const MAX_INPUT_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    TooLarge,
+    InvalidHex,
+    InvalidBase64,
+    InvalidRlp,
+    InvalidLength,
+}
+
+impl AsRef<[u8]> for DecodeError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::TooLarge => b"ERR_DECODE_TOO_LARGE",
+            Self::InvalidHex => b"ERR_DECODE_HEX",
+            Self::InvalidBase64 => b"ERR_DECODE_BASE64",
+            Self::InvalidRlp => b"ERR_DECODE_RLP",
+            Self::InvalidLength => b"ERR_DECODE_LENGTH",
+        }
+    }
+}
+
+/// Decodes a hex string. A `0x` prefix is not accepted, see `strip_hex_prefix`.
+pub fn hex_decode<T: AsRef<[u8]>>(input: T, max_len: usize) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    if input.len() / 2 > max_len {
+        return Err(DecodeError::TooLarge);
+    }
+    hex::decode(input).map_err(|_| DecodeError::InvalidHex)
+}
+
+/// Decodes a hex string of exactly `N` bytes.
+pub fn hex_decode_array<const N: usize, T: AsRef<[u8]>>(input: T) -> Result<[u8; N], DecodeError> {
+    let input = input.as_ref();
+    if input.len() != 2 * N {
+        return Err(DecodeError::InvalidLength);
+    }
+    let mut result = [0u8; N];
+    hex::decode_to_slice(input, &mut result).map_err(|_| DecodeError::InvalidHex)?;
+    Ok(result)
+}
+
+/// Decodes a 32-byte hex string.
+pub fn hex_decode_h256<T: AsRef<[u8]>>(input: T) -> Result<H256, DecodeError> {
+    hex_decode_array::<32, T>(input).map(H256)
+}
+
+/// Removes the `0x` prefix of a hex string, if present.
+pub fn strip_hex_prefix(input: &[u8]) -> &[u8] {
+    input.strip_prefix(b"0x").unwrap_or(input)
+}
+
+/// Decodes standard (padded) base64.
+pub fn base64_decode<T: AsRef<[u8]>>(input: T, max_len: usize) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    // Every 4 characters encode at most 3 bytes.
+    if input.len() / 4 * 3 > max_len.saturating_add(2) {
+        return Err(DecodeError::TooLarge);
+    }
+    let decoded = base64::decode(input).map_err(|_| DecodeError::InvalidBase64)?;
+    if decoded.len() > max_len {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(decoded)
+}
+
+/// Wraps RLP encoded bytes, checking their size first.
+pub fn rlp(input: &[u8], max_len: usize) -> Result<Rlp<'_>, DecodeError> {
+    if input.len() > max_len {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(Rlp::new(input))
+}
+
+/// Decodes an RLP encoded value which must span the whole input.
+pub fn rlp_decode<T: Decodable>(input: &[u8], max_len: usize) -> Result<T, DecodeError> {
+    if input.len() > max_len {
+        return Err(DecodeError::TooLarge);
+    }
+    rlp::decode(input).map_err(|_| DecodeError::InvalidRlp)
+}
+
+/// Checks that the `bytes` or `string` value whose offset is the ABI word at `head_offset`
+/// lies within `data`. To be called before decoding untrusted data with `ethabi`, which adds
+/// offsets and lengths without checking for overflows.
+pub fn abi_check_dynamic(data: &[u8], head_offset: usize) -> Result<(), DecodeError> {
+    let read_word = |offset: usize| -> Result<usize, DecodeError> {
+        let word = offset
+            .checked_add(32)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(DecodeError::InvalidLength)?;
+        if word[..24].iter().any(|b| *b != 0) {
+            return Err(DecodeError::TooLarge);
+        }
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&word[24..]);
+        usize::try_from(u64::from_be_bytes(bytes)).map_err(|_| DecodeError::TooLarge)
+    };
+    let offset = read_word(head_offset)?;
+    let len = read_word(offset)?;
+    match offset
+        .checked_add(32)
+        .and_then(|start| start.checked_add(len))
+    {
+        Some(end) if end <= data.len() => Ok(()),
+        _ => Err(DecodeError::TooLarge),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, RngCore};
+
+    #[test]
+    fn test_hex_decode() {
+        assert_eq!(hex_decode("0102", 2), Ok(vec![1, 2]));
+        assert_eq!(hex_decode("0x0102", 2), Err(DecodeError::InvalidHex));
+        assert_eq!(hex_decode(strip_hex_prefix(b"0x0102"), 2), Ok(vec![1, 2]));
+        assert_eq!(hex_decode("010203", 2), Err(DecodeError::TooLarge));
+        assert_eq!(hex_decode("01g2", 2), Err(DecodeError::InvalidHex));
+        assert_eq!(hex_decode("012", 2), Err(DecodeError::InvalidHex));
+        assert_eq!(hex_decode_array::<2, _>("0102"), Ok([1, 2]));
+        assert_eq!(
+            hex_decode_array::<2, _>("01"),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(base64_decode("AQID", 3), Ok(vec![1, 2, 3]));
+        assert_eq!(base64_decode("AQID", 2), Err(DecodeError::TooLarge));
+        assert_eq!(base64_decode("AQ==", 1), Ok(vec![1]));
+        assert_eq!(base64_decode("A?==", 1), Err(DecodeError::InvalidBase64));
+    }
+
+    #[test]
+    fn test_rlp_decode() {
+        let encoded = rlp::encode(&H256::repeat_byte(7)).to_vec();
+        assert_eq!(
+            rlp_decode::<H256>(&encoded, encoded.len()),
+            Ok(H256::repeat_byte(7))
+        );
+        assert_eq!(
+            rlp_decode::<H256>(&encoded, encoded.len() - 1),
+            Err(DecodeError::TooLarge)
+        );
+        assert_eq!(
+            rlp_decode::<H256>(&encoded[..10], encoded.len()),
+            Err(DecodeError::InvalidRlp)
+        );
+    }
+
+    #[test]
+    fn test_abi_check_dynamic() {
+        let word = |value: u64| {
+            let mut word = [0u8; 32];
+            word[24..].copy_from_slice(&value.to_be_bytes());
+            word.to_vec()
+        };
+        // head, length, then the 3 bytes padded to a word
+        let data = [word(32), word(3), vec![1, 2, 3], vec![0; 29]].concat();
+        assert_eq!(abi_check_dynamic(&data, 0), Ok(()));
+        assert_eq!(
+            abi_check_dynamic(&data, 64),
+            Err(DecodeError::InvalidLength)
+        );
+
+        let data = [word(32), word(u64::MAX - 16)].concat();
+        assert_eq!(abi_check_dynamic(&data, 0), Err(DecodeError::TooLarge));
+        let data = [word(32), word(33)].concat();
+        assert_eq!(abi_check_dynamic(&data, 0), Err(DecodeError::TooLarge));
+        let data = [word(u64::MAX), word(3)].concat();
+        assert_eq!(abi_check_dynamic(&data, 0), Err(DecodeError::InvalidLength));
+    }
+
+    /// Random inputs must be rejected (or decoded) without panicking, and never decode to
+    /// more than the cap.
+    #[test]
+    fn test_decoders_on_random_input() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..2_000 {
+            let len = rng.gen_range(0, 128);
+            let max_len = rng.gen_range(0, 96);
+            let mut input = vec![0u8; len];
+            rng.fill_bytes(&mut input);
+            // Mostly valid characters, so the decoders get past the first byte.
+            let alphabet = b"0123456789abcdefABCDEF+/=x";
+            let text: Vec<u8> = input
+                .iter()
+                .map(|b| alphabet[*b as usize % alphabet.len()])
+                .collect();
+
+            if let Ok(decoded) = hex_decode(&text, max_len) {
+                assert!(decoded.len() <= max_len);
+            }
+            let _ = hex_decode_array::<20, _>(&text);
+            if let Ok(decoded) = base64_decode(&text, max_len) {
+                assert!(decoded.len() <= max_len);
+            }
+            let _ = rlp_decode::<Vec<u8>>(&input, max_len);
+            let _ = rlp_decode::<H256>(&input, max_len);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Solidity-compatible revert payloads.
+//!
+//! A revert payload is the 4-byte selector of a Solidity custom error followed by its
+//! ABI-encoded arguments, e.g. `keccak256("InsufficientAllowance(uint256,uint256)")[..4]`
+//! followed by two 32-byte words. Callers in the EVM can decode it with `try`/`catch`,
+//! and off-chain tooling can decode it from the error ABI.
+
+use crate::{Address, String, Vec, U256};
+use sha3::{Digest, Keccak256};
+
+/// Signature of the generic error used for errors without a dedicated custom error.
+/// Its only argument is the `ERR_*` code of the error.
+pub const AURORA_ERROR_SIGNATURE: &str = "AuroraError(string)";
+
+/// Signature Solidity uses for `revert("reason")` and `require(cond, "reason")`.
+pub const ERROR_STRING_SIGNATURE: &str = "Error(string)";
+
+/// Errors which can be surfaced to EVM callers as a Solidity custom error. By default
+/// the error is encoded as `AuroraError(string)` carrying its `ERR_*` code; errors with
+/// useful arguments should override `revert_data` with a dedicated custom error.
+pub trait RevertError: AsRef<[u8]> {
+    fn revert_data(&self) -> Vec<u8> {
+        aurora_error(self.as_ref())
+    }
+}
+
+/// Payload of `AuroraError(string)` for the given error code.
+pub fn aurora_error(code: &[u8]) -> Vec<u8> {
+    RevertData::new(AURORA_ERROR_SIGNATURE)
+        .string(String::from_utf8_lossy(code))
+        .build()
+}
+
+/// Builder of revert payloads. The arguments must be added in the order (and with the
+/// types) given in the signature.
+#[derive(Debug, Clone)]
+pub struct RevertData {
+    signature: &'static str,
+    args: Vec<ethabi::Token>,
+}
+
+impl RevertData {
+    pub fn new(signature: &'static str) -> Self {
+        Self {
+            signature,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn uint(mut self, value: U256) -> Self {
+        self.args.push(ethabi::Token::Uint(value));
+        self
+    }
+
+    pub fn address(mut self, value: Address) -> Self {
+        self.args.push(ethabi::Token::Address(value));
+        self
+    }
+
+    pub fn bool(mut self, value: bool) -> Self {
+        self.args.push(ethabi::Token::Bool(value));
+        self
+    }
+
+    pub fn bytes(mut self, value: Vec<u8>) -> Self {
+        self.args.push(ethabi::Token::Bytes(value));
+        self
+    }
+
+    pub fn string<S: Into<String>>(mut self, value: S) -> Self {
+        self.args.push(ethabi::Token::String(value.into()));
+        self
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = Keccak256::digest(self.signature.as_bytes());
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&hash[..4]);
+        selector
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let selector = self.selector();
+        [selector.as_slice(), &ethabi::encode(&self.args)].concat()
+    }
+}
+
+/// Payload equivalent to Solidity's `revert(reason)`.
+pub fn revert_reason<S: Into<String>>(reason: S) -> Vec<u8> {
+    RevertData::new(ERROR_STRING_SIGNATURE)
+        .string(reason)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revert_reason_encoding() {
+        let data = revert_reason("abc");
+        assert_eq!(&data[0..4], &[0x08, 0xc3, 0x79, 0xa0]);
+        // offset of the string, its length and the padded content
+        assert_eq!(data.len(), 4 + 3 * 32);
+        assert_eq!(U256::from_big_endian(&data[4..36]), U256::from(32));
+        assert_eq!(U256::from_big_endian(&data[36..68]), U256::from(3));
+        assert_eq!(&data[68..71], b"abc");
+    }
+
+    #[test]
+    fn test_error_code_revert_data() {
+        let data = aurora_error(b"ERR_OUT_OF_FUND");
+        assert_eq!(
+            &data[0..4],
+            &RevertData::new(AURORA_ERROR_SIGNATURE).selector()
+        );
+        let decoded = ethabi::decode(&[ethabi::ParamType::String], &data[4..]).unwrap();
+        assert_eq!(decoded, [ethabi::Token::String("ERR_OUT_OF_FUND".into())]);
+    }
+}
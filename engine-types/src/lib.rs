@@ -4,6 +4,8 @@
 #![cfg_attr(feature = "log", feature(panic_info_message))]
 
 pub mod account_id;
+pub mod codec;
+pub mod error;
 pub mod parameters;
 pub mod storage;
 pub mod types;
@@ -21,7 +23,9 @@ mod v0 {
         boxed::Box,
         collections::BTreeMap as HashMap,
         collections::BTreeMap,
-        fmt, format, str,
+        fmt, format,
+        rc::Rc,
+        str,
         string::String,
         string::ToString,
         vec,
@@ -33,12 +37,16 @@ mod v0 {
         ops::Add, ops::Div, ops::Mul, ops::Sub,
     };
     pub use primitive_types::{H160, H256, U256};
+    // `HashMap` is an alias of `BTreeMap` in both builds: the iteration order of the std
+    // `HashMap` is randomized, which would make the standalone engine diverge from the
+    // contract wherever a map is iterated.
     #[cfg(feature = "std")]
     pub use std::{
         borrow::Cow, borrow::Cow::Borrowed, borrow::ToOwned, boxed::Box, cmp::Ordering,
-        collections::BTreeMap, collections::HashMap, convert::TryFrom, convert::TryInto,
-        error::Error, fmt, fmt::Display, format, marker::PhantomData, mem, ops::Add, ops::Div,
-        ops::Mul, ops::Sub, str, string::String, string::ToString, vec, vec::Vec,
+        collections::BTreeMap, collections::BTreeMap as HashMap, convert::TryFrom,
+        convert::TryInto, error::Error, fmt, fmt::Display, format, marker::PhantomData, mem,
+        ops::Add, ops::Div, ops::Mul, ops::Sub, rc::Rc, str, string::String, string::ToString, vec,
+        vec::Vec,
     };
 }
 
@@ -41,6 +41,7 @@ pub enum PromiseAction {
         attached_yocto: u128,
         gas: u64,
     },
+    CreateAccount,
 }
 
 #[must_use]
@@ -50,6 +51,16 @@ pub struct PromiseBatchAction {
     pub actions: Vec<PromiseAction>,
 }
 
+/// Borsh-encoded input of the cross-contract call precompile.
+#[derive(Debug, BorshSerialize, BorshDeserialize, Clone)]
+pub struct CrossContractCallArgs {
+    /// The NEAR call, made from the router account of the calling address.
+    pub promise: PromiseCreateArgs,
+    /// Input of the EVM call made back to the calling address once the NEAR call is
+    /// resolved, if any.
+    pub callback: Option<Vec<u8>>,
+}
+
 /// withdraw NEAR eth-connector call args
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct WithdrawCallArgs {
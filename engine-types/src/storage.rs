@@ -20,6 +20,12 @@ pub enum KeyPrefix {
     Generation = 0x7,
     Nep141Erc20Map = 0x8,
     Erc20Nep141Map = 0x9,
+    OperatorAllowance = 0xa,
+    AccountActivity = 0xb,
+    ShardedStorage = 0xc,
+    CodeHash = 0xd,
+    SharedCode = 0xe,
+    SharedCodeRefs = 0xf,
 }
 
 /// Enum used to differentiate different storage keys used by eth-connector
@@ -31,6 +37,12 @@ pub enum EthConnectorStorageId {
     PausedMask = 0x3,
     StatisticsAuroraAccountsCounter = 0x4,
     FungibleTokenMetadata = 0x5,
+    CustodianMinted = 0x6,
+    CustodianCap = 0x7,
+    CustodianCouncil = 0x8,
+    PendingCustodianChange = 0x9,
+    WithdrawalNonce = 0xb,
+    PendingWithdrawals = 0xc,
 }
 
 /// We can't use const generic over Enum, but we can do it over integral type
@@ -50,6 +62,12 @@ impl From<KeyPrefixU8> for KeyPrefix {
             0x7 => Self::Generation,
             0x8 => Self::Nep141Erc20Map,
             0x9 => Self::Erc20Nep141Map,
+            0xa => Self::OperatorAllowance,
+            0xb => Self::AccountActivity,
+            0xc => Self::ShardedStorage,
+            0xd => Self::CodeHash,
+            0xe => Self::SharedCode,
+            0xf => Self::SharedCodeRefs,
             _ => unreachable!(),
         }
     }
@@ -69,9 +87,18 @@ pub fn address_to_key(prefix: KeyPrefix, address: &Address) -> [u8; 22] {
     result
 }
 
+/// Storage generations with this bit set belong to contracts whose storage uses sharded
+/// keys (see `sharded_storage_key`).
+pub const SHARDED_GENERATION_FLAG: u32 = 1 << 31;
+
+pub fn is_sharded_generation(generation: u32) -> bool {
+    generation & SHARDED_GENERATION_FLAG != 0
+}
+
 pub enum StorageKeyKind {
     Normal([u8; 54]),
     Generation([u8; 58]),
+    Sharded([u8; 59]),
 }
 
 impl AsRef<[u8]> for StorageKeyKind {
@@ -80,12 +107,15 @@ impl AsRef<[u8]> for StorageKeyKind {
         match self {
             Normal(v) => v.as_slice(),
             Generation(v) => v.as_slice(),
+            Sharded(v) => v.as_slice(),
         }
     }
 }
 
 pub fn storage_to_key(address: &Address, key: &H256, generation: u32) -> StorageKeyKind {
-    if generation == 0 {
+    if is_sharded_generation(generation) {
+        StorageKeyKind::Sharded(sharded_storage_key(address, key, generation))
+    } else if generation == 0 {
         StorageKeyKind::Normal(normal_storage_key(address, key))
     } else {
         StorageKeyKind::Generation(generation_storage_key(address, key, generation))
@@ -112,3 +142,49 @@ fn generation_storage_key(address: &Address, key: &H256, generation: u32) -> [u8
     result[26..58].copy_from_slice(&key.0);
     result
 }
+
+/// Storage key of a contract with sharded storage. The shard byte comes before the address,
+/// so the slots of a single contract are spread over 256 subtrees of the NEAR trie instead
+/// of all sharing the path of the address.
+fn sharded_storage_key(address: &Address, key: &H256, generation: u32) -> [u8; 59] {
+    let mut result = [0u8; 59];
+    result[0] = VersionPrefix::V1 as u8;
+    result[1] = KeyPrefix::ShardedStorage as u8;
+    result[2] = storage_shard(key);
+    result[3..23].copy_from_slice(&address.0);
+    result[23..27].copy_from_slice(&generation.to_le_bytes());
+    result[27..59].copy_from_slice(&key.0);
+    result
+}
+
+/// Shard of a storage slot. Slots of mappings and dynamic arrays are hashes, and
+/// sequential slots differ in their last byte, so both are spread evenly.
+pub fn storage_shard(key: &H256) -> u8 {
+    key.0.iter().fold(0, |acc, b| acc ^ b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharded_storage_key() {
+        let address = Address([1; 20]);
+        let generation = SHARDED_GENERATION_FLAG | 2;
+        let key = storage_to_key(&address, &H256::from_low_u64_be(5), generation);
+        let key = key.as_ref();
+        assert_eq!(key.len(), 59);
+        assert_eq!(key[1], KeyPrefix::ShardedStorage as u8);
+        assert_eq!(key[2], 5);
+        assert_eq!(&key[3..23], &address.0);
+
+        let other = storage_to_key(&address, &H256::from_low_u64_be(6), generation);
+        assert_ne!(key[2], other.as_ref()[2]);
+        assert_eq!(
+            storage_to_key(&address, &H256::from_low_u64_be(5), 2)
+                .as_ref()
+                .len(),
+            58
+        );
+    }
+}
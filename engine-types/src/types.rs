@@ -1,5 +1,6 @@
-use crate::{str, vec, Add, Address, Display, Div, Mul, String, Sub, Vec, U256};
+use crate::{format, str, vec, Add, Address, Display, Div, Mul, String, Sub, Vec, U256};
 use borsh::{BorshDeserialize, BorshSerialize};
+use sha3::{Digest, Keccak256};
 
 use crate::fmt::Formatter;
 
@@ -10,8 +11,6 @@ pub type RawU256 = [u8; 32];
 // Big-endian large integer type.
 pub type RawH256 = [u8; 32]; // Unformatted binary data of fixed length.
 
-// TODO: introduce new type. Add encode/decode/validation methods
-pub type EthAddress = [u8; 20];
 pub type StorageUsage = u64;
 /// Wei compatible Borsh-encoded raw value to attach an ETH balance to the transaction
 pub type WeiU256 = [u8; 32];
@@ -138,8 +137,13 @@ impl Fee {
     pub fn into_u128(self) -> u128 {
         self.0
     }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
 }
 
+#[cfg(any(test, feature = "test-ops"))]
 impl Add<Fee> for Fee {
     type Output = Fee;
 
@@ -177,13 +181,172 @@ impl AsRef<[u8]> for AddressValidationError {
 
 /// Validate Ethereum address from string and return Result data EthAddress or Error data
 pub fn validate_eth_address(address: String) -> Result<EthAddress, AddressValidationError> {
-    let data = hex::decode(address).map_err(|_| AddressValidationError::FailedDecodeHex)?;
-    if data.len() != 20 {
-        return Err(AddressValidationError::IncorrectLength);
+    EthAddress::decode(&address)
+}
+
+/// A 20-byte Ethereum address, as found in the parameters of the engine methods.
+///
+/// Unlike a bare `[u8; 20]`, it cannot be mixed up with other 20-byte values. It is
+/// Borsh-encoded as its 20 bytes, so it can replace a `[u8; 20]` without changing the
+/// encoding of the structures containing it.
+#[derive(
+    Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize,
+)]
+pub struct EthAddress([u8; 20]);
+
+impl EthAddress {
+    /// Offset between the address of an Ethereum contract and its alias, the same as on
+    /// Optimism and Arbitrum: `0x1111000000000000000000000000000000001111`.
+    pub const L1_ALIAS_OFFSET: Self = Self([
+        0x11, 0x11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x11, 0x11,
+    ]);
+
+    pub const fn new(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    /// Address an Ethereum contract acts as when a message it sent is executed on Aurora:
+    /// the address plus `L1_ALIAS_OFFSET`, modulo 2^160. The contract at the same address on
+    /// Aurora is unrelated to the Ethereum one, so it must not be the sender of the message.
+    /// Messages of externally owned accounts, which have the same key on both chains, are not
+    /// aliased.
+    pub fn to_l1_alias(&self) -> Self {
+        Self::wrapping_from_u256(self.to_u256() + Self::L1_ALIAS_OFFSET.to_u256())
+    }
+
+    /// Inverse of `to_l1_alias`: the Ethereum contract which an alias stands for.
+    pub fn from_l1_alias(&self) -> Self {
+        // Adding 2^160 first keeps the difference positive.
+        Self::wrapping_from_u256(
+            self.to_u256() + (U256::one() << 160) - Self::L1_ALIAS_OFFSET.to_u256(),
+        )
+    }
+
+    fn to_u256(self) -> U256 {
+        U256::from_big_endian(&self.0)
+    }
+
+    /// The 160 low bits of `value`.
+    fn wrapping_from_u256(value: U256) -> Self {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        let mut result = [0u8; 20];
+        result.copy_from_slice(&bytes[12..]);
+        Self(result)
+    }
+
+    /// The address of the given bytes, if there are exactly 20 of them.
+    pub fn try_from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 20 {
+            return None;
+        }
+        let mut result = [0u8; 20];
+        result.copy_from_slice(bytes);
+        Some(Self(result))
+    }
+
+    /// Decodes the hex form of an address, without `0x` prefix. The case of the letters is
+    /// not checked, see `is_valid_checksum`.
+    pub fn decode(address: &str) -> Result<Self, AddressValidationError> {
+        let data = crate::codec::hex_decode(address, 20).map_err(|e| match e {
+            crate::codec::DecodeError::TooLarge => AddressValidationError::IncorrectLength,
+            _ => AddressValidationError::FailedDecodeHex,
+        })?;
+        Self::try_from_bytes(&data).ok_or(AddressValidationError::IncorrectLength)
+    }
+
+    /// Lowercase hex form of the address, without `0x` prefix.
+    pub fn encode(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Mixed-case checksum form of the address (EIP-55), with `0x` prefix.
+    pub fn to_checksum(&self) -> String {
+        let lowercase = self.encode();
+        let hash = Keccak256::digest(lowercase.as_bytes());
+        let mut result = String::with_capacity(42);
+        result.push_str("0x");
+        for (i, c) in lowercase.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                result.push(c.to_ascii_uppercase());
+            } else {
+                result.push(c);
+            }
+        }
+        result
+    }
+
+    /// Whether the given mixed-case (EIP-55) form of an address, with or without `0x`
+    /// prefix, is correct. All-lowercase and all-uppercase forms carry no checksum and are
+    /// accepted.
+    pub fn is_valid_checksum(address: &str) -> bool {
+        let digits = address.strip_prefix("0x").unwrap_or(address);
+        let decoded = match Self::decode(digits) {
+            Ok(decoded) => decoded,
+            Err(_) => return false,
+        };
+        if digits.chars().all(|c| !c.is_ascii_uppercase())
+            || digits.chars().all(|c| !c.is_ascii_lowercase())
+        {
+            return true;
+        }
+        decoded.to_checksum()[2..] == *digits
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub const fn to_fixed_bytes(self) -> [u8; 20] {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0u8; 20]
+    }
+}
+
+impl From<[u8; 20]> for EthAddress {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<EthAddress> for [u8; 20] {
+    fn from(address: EthAddress) -> Self {
+        address.0
+    }
+}
+
+impl From<Address> for EthAddress {
+    fn from(address: Address) -> Self {
+        Self(address.0)
+    }
+}
+
+impl From<EthAddress> for Address {
+    fn from(address: EthAddress) -> Self {
+        Address(address.0)
+    }
+}
+
+impl AsRef<[u8]> for EthAddress {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for EthAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::fmt::Result {
+        write!(f, "0x{}", self.encode())
+    }
+}
+
+impl crate::fmt::Debug for EthAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::fmt::Result {
+        write!(f, "EthAddress({})", self)
     }
-    let mut result = [0u8; 20];
-    result.copy_from_slice(&data);
-    Ok(result)
 }
 
 /// Newtype to distinguish balances (denominated in Wei) from other U256 types.
@@ -192,6 +355,11 @@ pub struct Wei(U256);
 
 impl Wei {
     const ETH_TO_WEI: U256 = U256([1_000_000_000_000_000_000, 0, 0, 0]);
+    const GWEI_TO_WEI: U256 = U256([1_000_000_000, 0, 0, 0]);
+
+    /// 1 gwei, the unit gas prices are usually expressed in.
+    pub const ONE_GWEI: Self = Self(Self::GWEI_TO_WEI);
+    pub const ONE_ETH: Self = Self(Self::ETH_TO_WEI);
 
     pub const fn zero() -> Self {
         Self(U256([0, 0, 0, 0]))
@@ -214,6 +382,15 @@ impl Wei {
         amount.checked_mul(Self::ETH_TO_WEI).map(Self)
     }
 
+    pub fn from_gwei(amount: U256) -> Option<Self> {
+        amount.checked_mul(Self::GWEI_TO_WEI).map(Self)
+    }
+
+    /// Amount in gwei, rounded down.
+    pub fn to_gwei_lossy(self) -> U256 {
+        self.0 / Self::GWEI_TO_WEI
+    }
+
     pub fn to_bytes(self) -> [u8; 32] {
         u256_to_arr(&self.0)
     }
@@ -234,6 +411,17 @@ impl Wei {
         self.0.checked_add(rhs.0).map(Self)
     }
 
+    /// Amount in wei as a decimal number, e.g. `1000000000000000000` for one ETH.
+    pub fn to_decimal_string(self) -> String {
+        u256_to_decimal_string(&self.0)
+    }
+
+    /// Amount in wei as a hexadecimal quantity of the Ethereum JSON-RPC API, e.g.
+    /// `0xde0b6b3a7640000` for one ETH.
+    pub fn to_hex_string(self) -> String {
+        u256_to_hex_string(&self.0)
+    }
+
     /// Try convert U256 to u128 with checking overflow.
     /// NOTICE: Error can contain only overflow
     pub fn try_into_u128(self) -> Result<u128, error::BalanceOverflowError> {
@@ -242,12 +430,21 @@ impl Wei {
     }
 }
 
+/// Decimal, like `to_decimal_string`.
 impl Display for Wei {
     fn fmt(&self, f: &mut Formatter<'_>) -> crate::fmt::Result {
-        self.0.fmt(f)
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// Hexadecimal without leading zeros; `{:#x}` adds the `0x` prefix of `to_hex_string`.
+impl crate::fmt::LowerHex for Wei {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::fmt::Result {
+        crate::fmt::LowerHex::fmt(&self.0, f)
     }
 }
 
+#[cfg(any(test, feature = "test-ops"))]
 impl Add<Self> for Wei {
     type Output = Wei;
 
@@ -256,6 +453,7 @@ impl Add<Self> for Wei {
     }
 }
 
+#[cfg(any(test, feature = "test-ops"))]
 impl Sub<Self> for Wei {
     type Output = Wei;
 
@@ -322,6 +520,17 @@ pub fn u256_to_arr(value: &U256) -> [u8; 32] {
     result
 }
 
+/// Decimal representation of `value`, which is also what its `Display` gives.
+pub fn u256_to_decimal_string(value: &U256) -> String {
+    format!("{}", value)
+}
+
+/// `0x`-prefixed hexadecimal representation of `value` without leading zeros (`0x0` for
+/// zero), the encoding of quantities in the Ethereum JSON-RPC API.
+pub fn u256_to_hex_string(value: &U256) -> String {
+    format!("{:#x}", value)
+}
+
 const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
 
 #[allow(dead_code)]
@@ -398,6 +607,58 @@ pub mod error {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_eth_address_checksum() {
+        // Examples of EIP-55
+        for checksummed in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = EthAddress::decode(&checksummed[2..].to_lowercase()).unwrap();
+            assert_eq!(address.to_checksum(), checksummed);
+            assert!(EthAddress::is_valid_checksum(checksummed));
+            assert!(EthAddress::is_valid_checksum(&checksummed.to_lowercase()));
+            assert_eq!(address.to_string(), checksummed.to_lowercase());
+        }
+        assert!(!EthAddress::is_valid_checksum(
+            "0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        ));
+        assert!(!EthAddress::is_valid_checksum("0x5aaeb6053f3e94c9b9"));
+    }
+
+    #[test]
+    fn test_eth_address_decode() {
+        let address = EthAddress::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(address.encode(), "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed");
+        assert_eq!(
+            Address::from(address),
+            Address::from_slice(address.as_bytes())
+        );
+        assert!(matches!(
+            EthAddress::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beazz"),
+            Err(AddressValidationError::FailedDecodeHex)
+        ));
+        assert!(matches!(
+            EthAddress::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed00"),
+            Err(AddressValidationError::IncorrectLength)
+        ));
+        assert!(EthAddress::try_from_bytes(&[0u8; 19]).is_none());
+        assert!(EthAddress::zero().is_zero());
+    }
+
+    #[test]
+    fn test_eth_address_borsh() {
+        let bytes = [7u8; 20];
+        let encoded = EthAddress::new(bytes).try_to_vec().unwrap();
+        assert_eq!(encoded, bytes.try_to_vec().unwrap());
+        assert_eq!(
+            <EthAddress as BorshDeserialize>::try_from_slice(&encoded).unwrap(),
+            EthAddress::new(bytes)
+        );
+    }
+
     #[test]
     fn test_hex() {
         assert_eq!(
@@ -490,6 +751,23 @@ mod tests {
         assert_eq!(Wei::new_u64(x).raw().as_u64(), x);
     }
 
+    #[test]
+    fn test_wei_gwei_conversions() {
+        let gwei_amount: u64 = rand::random();
+        let wei_amount = U256::from(gwei_amount) * U256::from(10).pow(9.into());
+        let wei = Wei::from_gwei(gwei_amount.into()).unwrap();
+        assert_eq!(wei, Wei::new(wei_amount));
+        assert_eq!(wei.to_gwei_lossy(), gwei_amount.into());
+
+        assert_eq!(
+            (wei + Wei::new_u64(999_999_999)).to_gwei_lossy(),
+            gwei_amount.into()
+        );
+        assert_eq!(Wei::from_gwei(U256::MAX), None);
+        assert_eq!(Wei::from_gwei(U256::one()), Some(Wei::ONE_GWEI));
+        assert_eq!(Wei::from_gwei(1_000_000_000.into()), Some(Wei::ONE_ETH));
+    }
+
     #[test]
     fn test_wei_from_eth() {
         let eth_amount: u64 = rand::random();
@@ -497,11 +775,59 @@ mod tests {
         assert_eq!(Wei::from_eth(eth_amount.into()), Some(Wei::new(wei_amount)));
     }
 
+    #[test]
+    fn test_l1_alias() {
+        let address = EthAddress::decode("7fa9385be102ac3eac297483dd6233d62b3e1496").unwrap();
+        let alias = address.to_l1_alias();
+        assert_eq!(alias.encode(), "90ba385be102ac3eac297483dd6233d62b3e25a7");
+        assert_eq!(alias.from_l1_alias(), address);
+
+        // The addition wraps around.
+        let max = EthAddress::new([0xff; 20]);
+        assert_eq!(
+            max.to_l1_alias().encode(),
+            "1111000000000000000000000000000000001110"
+        );
+        assert_eq!(max.to_l1_alias().from_l1_alias(), max);
+        assert_eq!(
+            EthAddress::zero().to_l1_alias(),
+            EthAddress::L1_ALIAS_OFFSET
+        );
+        assert_eq!(
+            EthAddress::L1_ALIAS_OFFSET.from_l1_alias(),
+            EthAddress::zero()
+        );
+    }
+
+    #[test]
+    fn test_wei_formatting() {
+        let cases = [
+            (Wei::zero(), "0", "0x0"),
+            (Wei::new_u64(255), "255", "0xff"),
+            (Wei::ONE_ETH, "1000000000000000000", "0xde0b6b3a7640000"),
+        ];
+        for (wei, decimal, hex) in cases.iter() {
+            assert_eq!(&wei.to_decimal_string(), decimal);
+            assert_eq!(&wei.to_string(), decimal);
+            assert_eq!(&wei.to_hex_string(), hex);
+            assert_eq!(&format!("{:#x}", wei), hex);
+            assert_eq!(format!("{:x}", wei), hex[2..]);
+        }
+        let max = Wei::new(U256::MAX);
+        assert_eq!(max.to_hex_string(), format!("0x{}", "f".repeat(64)));
+        assert_eq!(
+            max.to_decimal_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+    }
+
     #[test]
     fn test_fee_add() {
         let fee = Fee::new(100);
         assert_eq!(fee + fee, Fee::new(200));
         assert_eq!(fee.add(200.into()), Fee::new(300));
+        assert_eq!(fee.checked_add(200.into()), Some(Fee::new(300)));
+        assert_eq!(Fee::new(u128::MAX).checked_add(fee), None);
     }
 
     #[test]
@@ -0,0 +1,212 @@
+//! Dry-run of admin actions.
+//!
+//! `simulate` applies a proposed admin action exactly as the corresponding admin method
+//! would, but on top of an in-memory overlay of the engine state which is discarded
+//! afterwards. It reports the configuration the engine would end up with, or the error
+//! the admin method would fail with, so that governance can preview an action before
+//! queuing it.
+
+use crate::admin_controlled::PausedMask;
+use crate::connector::EthConnectorContract;
+use crate::engine;
+use crate::parameters::{CustodianChangeArgs, PauseEthConnectorCallArgs, PendingCustodianChange};
+use crate::prelude::{
+    BTreeMap, BorshDeserialize, BorshSerialize, EthAddress, String, ToOwned, Vec,
+};
+use crate::promise_budget::{self, PromiseBudget};
+use crate::protocol_fee::{self, ProtocolFeeConfig};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+use core::cell::RefCell;
+
+/// Admin actions which can be simulated. Each one has the arguments of the admin method
+/// of the same name.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum AdminAction {
+    SetPausedFlags(PauseEthConnectorCallArgs),
+    SetProtocolFee(ProtocolFeeConfig),
+    SetPromiseBudget(PromiseBudget),
+    SetEip3607Enabled(bool),
+    ProposeCustodianChange(CustodianChangeArgs),
+}
+
+/// Admin-controlled configuration of the engine.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AdminConfig {
+    pub paused_flags: PausedMask,
+    pub protocol_fee: ProtocolFeeConfig,
+    pub promise_budget: PromiseBudget,
+    pub eip3607_enabled: bool,
+    pub eth_custodian_address: EthAddress,
+    pub pending_custodian_change: Option<PendingCustodianChange>,
+}
+
+/// Result of the `simulate_admin_action` view.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AdminSimulation {
+    /// Configuration after the action; the current configuration if the action fails.
+    pub config: AdminConfig,
+    /// Error code the admin method would fail with, if any.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminSimulationError {
+    InvalidStoredConfig,
+}
+
+impl AsRef<[u8]> for AdminSimulationError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidStoredConfig => b"ERR_INVALID_ADMIN_CONFIG",
+        }
+    }
+}
+
+/// Applies `action` to an overlay of the state given by `io`, which is left untouched.
+pub fn simulate<I: IO + Copy>(
+    io: I,
+    action: AdminAction,
+    block_height: u64,
+) -> Result<AdminSimulation, AdminSimulationError> {
+    let overlay = RefCell::new(BTreeMap::new());
+    let mut io = OverlayIO::new(io, &overlay);
+    let error = apply(&mut io, action, block_height).err();
+    if error.is_some() {
+        // The admin method would panic, reverting its writes.
+        overlay.borrow_mut().clear();
+    }
+    Ok(AdminSimulation {
+        config: get_config(io)?,
+        error,
+    })
+}
+
+fn apply<I: IO + Copy>(io: &mut I, action: AdminAction, block_height: u64) -> Result<(), String> {
+    fn code<E: AsRef<[u8]>>(e: E) -> String {
+        String::from_utf8_lossy(e.as_ref()).into_owned()
+    }
+
+    match action {
+        AdminAction::SetPausedFlags(args) => {
+            EthConnectorContract::init_instance(*io).set_paused_flags(args);
+        }
+        AdminAction::SetProtocolFee(config) => {
+            protocol_fee::set_config(io, config).map_err(code)?;
+        }
+        AdminAction::SetPromiseBudget(budget) => promise_budget::set_budget(io, budget),
+        AdminAction::SetEip3607Enabled(enabled) => engine::set_eip3607_enabled(io, enabled),
+        AdminAction::ProposeCustodianChange(args) => {
+            EthConnectorContract::init_instance(*io)
+                .propose_custodian_change(args, block_height)
+                .map_err(code)?;
+        }
+    }
+    Ok(())
+}
+
+/// Current admin-controlled configuration.
+pub fn get_config<I: IO + Copy>(io: I) -> Result<AdminConfig, AdminSimulationError> {
+    let connector = EthConnectorContract::init_instance(io);
+    Ok(AdminConfig {
+        paused_flags: connector.get_paused_flags(),
+        protocol_fee: protocol_fee::get_config(&io)
+            .map_err(|_| AdminSimulationError::InvalidStoredConfig)?,
+        promise_budget: promise_budget::get_budget(&io)
+            .map_err(|_| AdminSimulationError::InvalidStoredConfig)?,
+        eip3607_enabled: engine::is_eip3607_enabled(&io),
+        eth_custodian_address: connector.get_eth_custodian_address(),
+        pending_custodian_change: connector.get_pending_custodian_change(),
+    })
+}
+
+/// Value read through an `OverlayIO`: either from the overlay or from the wrapped IO.
+pub enum OverlayValue<V> {
+    Inner(V),
+    Owned(Vec<u8>),
+}
+
+impl<V: StorageIntermediate> StorageIntermediate for OverlayValue<V> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Inner(value) => value.len(),
+            Self::Owned(value) => value.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Inner(value) => value.is_empty(),
+            Self::Owned(value) => value.is_empty(),
+        }
+    }
+
+    fn copy_to_slice(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Inner(value) => value.copy_to_slice(buffer),
+            Self::Owned(value) => buffer.copy_from_slice(value),
+        }
+    }
+}
+
+/// IO keeping all storage writes in memory. Reads see the writes made through it;
+/// the wrapped IO is never written to.
+#[derive(Clone, Copy)]
+pub struct OverlayIO<'o, I> {
+    inner: I,
+    /// Written values; `None` marks a removed key.
+    overlay: &'o RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl<'o, I: IO> OverlayIO<'o, I> {
+    pub fn new(inner: I, overlay: &'o RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>) -> Self {
+        Self { inner, overlay }
+    }
+
+    fn set(&mut self, key: &[u8], value: Option<Vec<u8>>) -> Option<OverlayValue<I::StorageValue>> {
+        let previous = self.read_storage(key);
+        self.overlay.borrow_mut().insert(key.to_owned(), value);
+        previous
+    }
+}
+
+impl<'o, I: IO> IO for OverlayIO<'o, I> {
+    type StorageValue = OverlayValue<I::StorageValue>;
+
+    fn read_input(&self) -> Self::StorageValue {
+        OverlayValue::Inner(self.inner.read_input())
+    }
+
+    fn return_output(&mut self, value: &[u8]) {
+        self.inner.return_output(value)
+    }
+
+    fn read_storage(&self, key: &[u8]) -> Option<Self::StorageValue> {
+        match self.overlay.borrow().get(key) {
+            Some(value) => value.clone().map(OverlayValue::Owned),
+            None => self.inner.read_storage(key).map(OverlayValue::Inner),
+        }
+    }
+
+    fn storage_has_key(&self, key: &[u8]) -> bool {
+        match self.overlay.borrow().get(key) {
+            Some(value) => value.is_some(),
+            None => self.inner.storage_has_key(key),
+        }
+    }
+
+    fn write_storage(&mut self, key: &[u8], value: &[u8]) -> Option<Self::StorageValue> {
+        self.set(key, Some(value.to_vec()))
+    }
+
+    fn write_storage_direct(
+        &mut self,
+        key: &[u8],
+        value: Self::StorageValue,
+    ) -> Option<Self::StorageValue> {
+        self.set(key, Some(value.to_vec()))
+    }
+
+    fn remove_storage(&mut self, key: &[u8]) -> Option<Self::StorageValue> {
+        self.set(key, None)
+    }
+}
@@ -0,0 +1,256 @@
+//! Journal of the ETH balance changes made while executing a transaction.
+//!
+//! `JournaledIO` wraps the IO given to the engine and records every write to a balance
+//! key, whether it comes from the EVM state (including the transfers made by precompiles),
+//! from gas payments or from the connector. The EVM executor only applies the state of the
+//! call frames which succeeded, so the changes of failed nested calls never reach the
+//! journal. Hosts executing several steps can drop the changes of a failed step with
+//! `checkpoint` and `revert_to`.
+
+use crate::prelude::{address_to_key, Address, BTreeMap, KeyPrefix, Vec, Wei, U256};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+use core::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub before: Wei,
+    pub after: Wei,
+}
+
+impl BalanceChange {
+    pub fn is_increase(&self) -> bool {
+        self.after > self.before
+    }
+
+    /// Absolute value of the change.
+    pub fn amount(&self) -> Wei {
+        let (larger, smaller) = if self.is_increase() {
+            (self.after, self.before)
+        } else {
+            (self.before, self.after)
+        };
+        larger.checked_sub(smaller).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BalanceJournal {
+    changes: BTreeMap<Address, BalanceChange>,
+    /// Entries as they were before each `record`, used to revert to a checkpoint.
+    undo_log: Vec<(Address, Option<BalanceChange>)>,
+}
+
+impl BalanceJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the balance of `address` changed from `before` to `after`. Only the
+    /// first `before` of an address is kept, so the entry spans the whole journal.
+    pub fn record(&mut self, address: Address, before: Wei, after: Wei) {
+        let previous = self.changes.get(&address).copied();
+        self.undo_log.push((address, previous));
+        let before = previous.map(|change| change.before).unwrap_or(before);
+        self.changes
+            .insert(address, BalanceChange { before, after });
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.undo_log.len())
+    }
+
+    /// Forgets all the changes recorded after the checkpoint was taken.
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        while self.undo_log.len() > checkpoint.0 {
+            if let Some((address, previous)) = self.undo_log.pop() {
+                match previous {
+                    Some(change) => self.changes.insert(address, change),
+                    None => self.changes.remove(&address),
+                };
+            }
+        }
+    }
+
+    pub fn get(&self, address: &Address) -> Option<BalanceChange> {
+        self.changes.get(address).copied()
+    }
+
+    /// Net change of every address, in ascending address order. Addresses whose balance
+    /// ended where it started are left out.
+    pub fn net_changes(&self) -> Vec<(Address, BalanceChange)> {
+        self.changes
+            .iter()
+            .filter(|(_, change)| change.before != change.after)
+            .map(|(address, change)| (*address, *change))
+            .collect()
+    }
+
+    pub fn total_before(&self) -> U256 {
+        self.changes.values().fold(U256::zero(), |total, change| {
+            total.saturating_add(change.before.raw())
+        })
+    }
+
+    pub fn total_after(&self) -> U256 {
+        self.changes.values().fold(U256::zero(), |total, change| {
+            total.saturating_add(change.after.raw())
+        })
+    }
+
+    /// True if ETH was only moved between addresses: nothing was minted nor burned.
+    pub fn is_conserved(&self) -> bool {
+        self.total_before() == self.total_after()
+    }
+}
+
+/// Address whose balance is stored under the given key, if the key is a balance key.
+pub fn balance_key_address(key: &[u8]) -> Option<Address> {
+    let prefix = address_to_key(KeyPrefix::Balance, &Address::zero());
+    if key.len() == prefix.len() && key[..2] == prefix[..2] {
+        Some(Address::from_slice(&key[2..]))
+    } else {
+        None
+    }
+}
+
+/// Balance stored as the given value. Balances are stored as 32 big-endian bytes.
+pub fn balance_from_bytes(bytes: &[u8]) -> Wei {
+    if bytes.len() == 32 {
+        Wei::new(U256::from_big_endian(bytes))
+    } else {
+        Wei::zero()
+    }
+}
+
+/// IO recording the changes of balances into a `BalanceJournal`.
+#[derive(Clone, Copy)]
+pub struct JournaledIO<'j, I> {
+    inner: I,
+    journal: &'j RefCell<BalanceJournal>,
+}
+
+impl<'j, I: IO> JournaledIO<'j, I> {
+    pub fn new(inner: I, journal: &'j RefCell<BalanceJournal>) -> Self {
+        Self { inner, journal }
+    }
+
+    fn balance(&self, key: &[u8]) -> Wei {
+        self.inner
+            .read_storage(key)
+            .map(|value| balance_from_bytes(&value.to_vec()))
+            .unwrap_or_else(Wei::zero)
+    }
+
+    fn record(&self, key: &[u8], after: Wei) {
+        if let Some(address) = balance_key_address(key) {
+            let before = self.balance(key);
+            self.journal.borrow_mut().record(address, before, after);
+        }
+    }
+}
+
+impl<'j, I: IO> IO for JournaledIO<'j, I> {
+    type StorageValue = I::StorageValue;
+
+    fn read_input(&self) -> Self::StorageValue {
+        self.inner.read_input()
+    }
+
+    fn return_output(&mut self, value: &[u8]) {
+        self.inner.return_output(value)
+    }
+
+    fn read_storage(&self, key: &[u8]) -> Option<Self::StorageValue> {
+        self.inner.read_storage(key)
+    }
+
+    fn storage_has_key(&self, key: &[u8]) -> bool {
+        self.inner.storage_has_key(key)
+    }
+
+    fn write_storage(&mut self, key: &[u8], value: &[u8]) -> Option<Self::StorageValue> {
+        self.record(key, balance_from_bytes(value));
+        self.inner.write_storage(key, value)
+    }
+
+    fn write_storage_direct(
+        &mut self,
+        key: &[u8],
+        value: Self::StorageValue,
+    ) -> Option<Self::StorageValue> {
+        if balance_key_address(key).is_some() {
+            self.record(key, balance_from_bytes(&value.to_vec()));
+        }
+        self.inner.write_storage_direct(key, value)
+    }
+
+    fn remove_storage(&mut self, key: &[u8]) -> Option<Self::StorageValue> {
+        self.record(key, Wei::zero());
+        self.inner.remove_storage(key)
+    }
+
+    fn read_storage_len(&self, key: &[u8]) -> Option<usize> {
+        self.inner.read_storage_len(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_net_changes() {
+        let alice = Address([1; 20]);
+        let bob = Address([2; 20]);
+        let mut journal = BalanceJournal::new();
+        journal.record(alice, Wei::new_u64(100), Wei::new_u64(70));
+        journal.record(bob, Wei::zero(), Wei::new_u64(30));
+        // A second change keeps the original `before`.
+        journal.record(alice, Wei::new_u64(70), Wei::new_u64(60));
+        journal.record(bob, Wei::new_u64(30), Wei::new_u64(40));
+
+        let changes = journal.net_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].0, alice);
+        assert!(!changes[0].1.is_increase());
+        assert_eq!(changes[0].1.amount(), Wei::new_u64(40));
+        assert_eq!(changes[1].1.amount(), Wei::new_u64(40));
+        assert!(journal.is_conserved());
+    }
+
+    #[test]
+    fn test_journal_revert_to_checkpoint() {
+        let alice = Address([1; 20]);
+        let bob = Address([2; 20]);
+        let mut journal = BalanceJournal::new();
+        journal.record(alice, Wei::new_u64(100), Wei::new_u64(90));
+        let checkpoint = journal.checkpoint();
+        journal.record(alice, Wei::new_u64(90), Wei::new_u64(50));
+        journal.record(bob, Wei::zero(), Wei::new_u64(40));
+        journal.revert_to(checkpoint);
+
+        assert_eq!(
+            journal.get(&alice),
+            Some(BalanceChange {
+                before: Wei::new_u64(100),
+                after: Wei::new_u64(90),
+            })
+        );
+        assert_eq!(journal.get(&bob), None);
+        // 10 wei left without going anywhere, e.g. paid for gas to an untracked account.
+        assert!(!journal.is_conserved());
+    }
+
+    #[test]
+    fn test_balance_key_address() {
+        let address = Address([3; 20]);
+        let key = address_to_key(KeyPrefix::Balance, &address);
+        assert_eq!(balance_key_address(&key), Some(address));
+        let nonce_key = address_to_key(KeyPrefix::Nonce, &address);
+        assert_eq!(balance_key_address(&nonce_key), None);
+        assert_eq!(balance_key_address(&key[..21]), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Statistics of the bridged tokens, for bridge dashboards.
+//!
+//! For ETH and for each bridged NEP-141 token, the engine counts what was bridged in and out
+//! and estimates how many distinct accounts received the token. A token is identified by its
+//! ERC-20 address, or by `ETH_TOKEN` (the zero address, as in the exit events) for ETH:
+//!
+//! - ETH is bridged in by `finish_deposit` and out by `withdraw`, whether from NEAR or
+//!   from Aurora through the exit to Ethereum precompile. Moving nETH between NEAR and
+//!   Aurora is not counted.
+//! - A NEP-141 token is bridged in by `ft_on_transfer` and out by the exit precompiles.
+//!
+//! Distinct recipients are counted with a HyperLogLog sketch of `REGISTERS` registers, whose
+//! standard error is about 13%. The sketch cannot forget a recipient, so `holders` counts
+//! every account which ever received the token, including the ones which no longer hold any.
+
+use crate::prelude::precompiles::native::events::ETH_ADDRESS;
+use crate::prelude::{
+    bytes_to_key, sdk, vec, Address, Balance, BorshDeserialize, BorshSerialize, KeyPrefix, Vec,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const BRIDGE_STATS_KEY: &[u8; 12] = b"BRIDGE_STATS";
+
+/// Token of the statistics of ETH.
+pub const ETH_TOKEN: Address = ETH_ADDRESS;
+
+const PRECISION: u32 = 6;
+/// Number of registers of the HyperLogLog sketches.
+pub const REGISTERS: usize = 1 << PRECISION;
+
+/// `REGISTERS * ln(REGISTERS / v)` for `v` empty registers, the linear counting estimate
+/// used while few recipients were seen.
+const LINEAR_COUNTING: [u64; REGISTERS] = [
+    266, 222, 196, 177, 163, 151, 142, 133, 126, 119, 113, 107, 102, 97, 93, 89, 85, 81, 78, 74,
+    71, 68, 65, 63, 60, 58, 55, 53, 51, 48, 46, 44, 42, 40, 39, 37, 35, 33, 32, 30, 28, 27, 25, 24,
+    23, 21, 20, 18, 17, 16, 15, 13, 12, 11, 10, 9, 7, 6, 5, 4, 3, 2, 1, 0,
+];
+
+/// Statistics of a token, returned by `get_bridge_stats`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeTokenStats {
+    /// Total amount ever bridged in, saturating at `Balance::MAX`.
+    pub deposited: Balance,
+    /// Total amount ever bridged out, saturating at `Balance::MAX`.
+    pub withdrawn: Balance,
+    /// Amount currently bridged: `deposited - withdrawn`.
+    pub supply: Balance,
+    /// Estimated number of distinct recipients of the token.
+    pub holders: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+struct StoredStats {
+    deposited: Balance,
+    withdrawn: Balance,
+    registers: Vec<u8>,
+}
+
+impl Default for StoredStats {
+    fn default() -> Self {
+        Self {
+            deposited: 0,
+            withdrawn: 0,
+            registers: vec![0; REGISTERS],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeStatsError {
+    InvalidStoredStats,
+}
+
+impl AsRef<[u8]> for BridgeStatsError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidStoredStats => b"ERR_INVALID_BRIDGE_STATS",
+        }
+    }
+}
+
+fn stats_key(token: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[BRIDGE_STATS_KEY.as_slice(), token.as_bytes()].concat(),
+    )
+}
+
+fn read<I: IO>(io: &I, token: &Address) -> Result<StoredStats, BridgeStatsError> {
+    match io.read_storage(&stats_key(token)) {
+        None => Ok(StoredStats::default()),
+        Some(bytes) => match StoredStats::try_from_slice(&bytes.to_vec()) {
+            Ok(stats) if stats.registers.len() == REGISTERS => Ok(stats),
+            _ => Err(BridgeStatsError::InvalidStoredStats),
+        },
+    }
+}
+
+pub fn get_stats<I: IO>(io: &I, token: &Address) -> Result<BridgeTokenStats, BridgeStatsError> {
+    let stats = read(io, token)?;
+    Ok(BridgeTokenStats {
+        deposited: stats.deposited,
+        withdrawn: stats.withdrawn,
+        supply: stats.deposited.saturating_sub(stats.withdrawn),
+        holders: estimate(&stats.registers),
+    })
+}
+
+/// Records that `amount` of the token was bridged in to `recipient`, given as the bytes of
+/// its NEAR account id or EVM address. Statistics which cannot be read are left alone:
+/// they must never make a deposit fail.
+pub fn record_deposit<I: IO>(io: &mut I, token: &Address, amount: Balance, recipient: &[u8]) {
+    if let Ok(mut stats) = read(io, token) {
+        stats.deposited = stats.deposited.saturating_add(amount);
+        add_to_sketch(&mut stats.registers, recipient);
+        io.write_borsh(&stats_key(token), &stats);
+    }
+}
+
+/// Records that `amount` of the token was bridged out.
+pub fn record_withdrawal<I: IO>(io: &mut I, token: &Address, amount: Balance) {
+    if let Ok(mut stats) = read(io, token) {
+        stats.withdrawn = stats.withdrawn.saturating_add(amount);
+        io.write_borsh(&stats_key(token), &stats);
+    }
+}
+
+fn add_to_sketch(registers: &mut [u8], item: &[u8]) {
+    let hash = sdk::keccak(item);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    let hash = u64::from_be_bytes(bytes);
+    let index = (hash >> (64 - PRECISION)) as usize;
+    // Position of the first set bit of the remaining bits, at most `64 - PRECISION + 1`.
+    let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+    if registers[index] < rank {
+        registers[index] = rank;
+    }
+}
+
+fn estimate(registers: &[u8]) -> u64 {
+    let empty = registers.iter().filter(|register| **register == 0).count();
+    if empty == REGISTERS {
+        return 0;
+    }
+    // Harmonic mean of `2^register`, in fixed point with 64 fractional bits.
+    let sum: u128 = registers
+        .iter()
+        .map(|register| 1u128 << (64 - u32::from(*register)))
+        .sum();
+    // alpha = 0.709 for 64 registers.
+    let raw = (709u128 * (REGISTERS * REGISTERS) as u128 * (1u128 << 64)) / (1000 * sum);
+    if raw <= (5 * REGISTERS / 2) as u128 && empty > 0 {
+        LINEAR_COUNTING[empty - 1]
+    } else {
+        raw as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::ToString;
+
+    #[test]
+    fn test_estimate_is_close() {
+        for &count in &[1u64, 10, 100, 1_000, 10_000] {
+            let mut registers = vec![0; REGISTERS];
+            for i in 0..count {
+                add_to_sketch(&mut registers, i.to_string().as_bytes());
+                // Receiving the token twice does not count twice.
+                add_to_sketch(&mut registers, i.to_string().as_bytes());
+            }
+            let estimate = estimate(&registers);
+            let error = (estimate as f64 - count as f64).abs() / count as f64;
+            assert!(error < 0.4, "{} estimated as {}", count, estimate);
+        }
+    }
+
+    #[test]
+    fn test_empty_sketch() {
+        assert_eq!(estimate(&[0; REGISTERS]), 0);
+    }
+
+    #[test]
+    fn test_stored_stats_layout() {
+        let stats = StoredStats {
+            deposited: 3,
+            withdrawn: 1,
+            registers: vec![0; REGISTERS],
+        };
+        let bytes = stats.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 16 + 16 + 4 + REGISTERS);
+        assert_eq!(StoredStats::try_from_slice(&bytes).unwrap(), stats);
+    }
+}
@@ -0,0 +1,80 @@
+//! Deduplicated contract code.
+//!
+//! Many contracts (e.g. the ERC-20s deployed for bridged NEP-141 tokens) have identical
+//! bytecode. Code is stored once per keccak hash, with a reference count, and every account
+//! only stores the hash of its code. Accounts deployed before the deduplication keep their
+//! code under their own key until `migrate` moves it; the addresses to migrate are listed
+//! off-chain (e.g. by the standalone storage).
+
+use crate::engine;
+use crate::prelude::{address_to_key, bytes_to_key, Address, KeyPrefix, RawAddress, Vec, H256};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+/// Hash of the code of the account, if its code is deduplicated.
+pub fn get_code_hash<I: IO>(io: &I, address: &Address) -> Option<H256> {
+    io.read_storage(&address_to_key(KeyPrefix::CodeHash, address))
+        .and_then(|value| {
+            if value.len() == 32 {
+                let mut buf = [0u8; 32];
+                value.copy_to_slice(&mut buf);
+                Some(H256(buf))
+            } else {
+                None
+            }
+        })
+}
+
+pub(crate) fn shared_code_key(code_hash: &H256) -> Vec<u8> {
+    bytes_to_key(KeyPrefix::SharedCode, code_hash.as_bytes())
+}
+
+/// Number of accounts using the code with the given hash.
+pub fn get_references<I: IO>(io: &I, code_hash: &H256) -> u64 {
+    io.read_u64(&bytes_to_key(
+        KeyPrefix::SharedCodeRefs,
+        code_hash.as_bytes(),
+    ))
+    .unwrap_or(0)
+}
+
+fn set_references<I: IO>(io: &mut I, code_hash: &H256, references: u64) {
+    let key = bytes_to_key(KeyPrefix::SharedCodeRefs, code_hash.as_bytes());
+    if references == 0 {
+        io.remove_storage(&key);
+        io.remove_storage(&shared_code_key(code_hash));
+    } else {
+        io.write_storage(&key, &references.to_le_bytes());
+    }
+}
+
+/// Stores `code` (unless already stored) and counts one more account using it.
+pub(crate) fn add_reference<I: IO>(io: &mut I, code_hash: &H256, code: &[u8]) {
+    let references = get_references(io, code_hash);
+    if references == 0 {
+        io.write_storage(&shared_code_key(code_hash), code);
+    }
+    set_references(io, code_hash, references.saturating_add(1));
+}
+
+/// Counts one less account using the code, removing the code once it is unused.
+pub(crate) fn remove_reference<I: IO>(io: &mut I, code_hash: &H256) {
+    let references = get_references(io, code_hash);
+    set_references(io, code_hash, references.saturating_sub(1));
+}
+
+/// Moves the code of the given accounts from their own key to the shared storage.
+/// Accounts without code stored under their own key are skipped. Returns the number of
+/// accounts migrated.
+pub fn migrate<I: IO>(io: &mut I, addresses: &[RawAddress]) -> u32 {
+    let mut migrated = 0;
+    for address in addresses {
+        let address = Address(*address);
+        let code = match io.remove_storage(&address_to_key(KeyPrefix::Code, &address)) {
+            Some(code) => code.to_vec(),
+            None => continue,
+        };
+        engine::set_code(io, &address, &code);
+        migrated += 1;
+    }
+    migrated
+}
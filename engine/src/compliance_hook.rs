@@ -0,0 +1,195 @@
+//! Compliance hooks of bridged tokens.
+//!
+//! Issuers of regulated tokens (e.g. stablecoins) must be able to block given accounts. The
+//! owner can set, for the ERC-20 of a bridged token, a compliance contract implementing
+//! `isBlacklisted(address) returns (bool)`. When set, the engine consults it before minting
+//! the token to a recipient (`ft_on_transfer`) and before letting a transaction withdraw the
+//! token (exits to NEAR or Ethereum), in which case the origin of the transaction is checked.
+//! The check fails closed: a hook which reverts or does not return a `bool` blocks the
+//! operation.
+
+use crate::prelude::precompiles::native::events::{
+    ETH_ADDRESS, EXIT_TO_ETH_SIGNATURE, EXIT_TO_NEAR_SIGNATURE,
+};
+use crate::prelude::precompiles::native::{ExitToEthereum, ExitToNear};
+use crate::prelude::{bytes_to_key, Address, KeyPrefix, Vec, U256};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+use evm::backend::Log;
+
+const COMPLIANCE_HOOK_KEY: &[u8; 15] = b"COMPLIANCE_HOOK";
+
+/// Selector of `isBlacklisted(address)`.
+pub const IS_BLACKLISTED_SELECTOR: [u8; 4] = [0xfe, 0x57, 0x5a, 0x87];
+
+/// EVM gas available to a compliance check.
+pub const COMPLIANCE_CHECK_GAS: u64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplianceError {
+    Blacklisted,
+    HookFailed,
+}
+
+impl AsRef<[u8]> for ComplianceError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Blacklisted => b"ERR_BLACKLISTED",
+            Self::HookFailed => b"ERR_COMPLIANCE_HOOK_FAILED",
+        }
+    }
+}
+
+fn hook_key(token: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[COMPLIANCE_HOOK_KEY.as_slice(), token.as_bytes()].concat(),
+    )
+}
+
+/// Compliance contract of the given ERC-20, if any.
+pub fn get_hook<I: IO>(io: &I, token: &Address) -> Option<Address> {
+    io.read_storage(&hook_key(token)).and_then(|value| {
+        if value.len() == 20 {
+            let mut bytes = [0u8; 20];
+            value.copy_to_slice(&mut bytes);
+            Some(Address(bytes))
+        } else {
+            None
+        }
+    })
+}
+
+/// Sets (or removes, with `None`) the compliance contract of the given ERC-20.
+pub fn set_hook<I: IO>(io: &mut I, token: &Address, hook: Option<Address>) {
+    match hook {
+        Some(hook) => io.write_storage(&hook_key(token), hook.as_bytes()),
+        None => io.remove_storage(&hook_key(token)),
+    };
+}
+
+/// Input of the `isBlacklisted` call for the given account.
+pub fn encode_is_blacklisted(account: &Address) -> Vec<u8> {
+    let mut input = Vec::with_capacity(36);
+    input.extend_from_slice(&IS_BLACKLISTED_SELECTOR);
+    input.extend_from_slice(&[0u8; 12]);
+    input.extend_from_slice(account.as_bytes());
+    input
+}
+
+/// Interprets the output of a successful `isBlacklisted` call.
+pub fn decode_is_blacklisted(output: &[u8]) -> Result<(), ComplianceError> {
+    if output.len() != 32 || output[..31].iter().any(|b| *b != 0) {
+        return Err(ComplianceError::HookFailed);
+    }
+    match output[31] {
+        0 => Ok(()),
+        1 => Err(ComplianceError::Blacklisted),
+        _ => Err(ComplianceError::HookFailed),
+    }
+}
+
+/// ERC-20 tokens withdrawn by the exit precompiles, according to the logs they emitted,
+/// with the amount withdrawn.
+pub(crate) fn token_exits(logs: &[Log]) -> impl Iterator<Item = (Address, U256)> + '_ {
+    logs.iter().filter_map(|log| {
+        let is_exit = (log.address == ExitToNear::ADDRESS
+            && log.topics.first() == Some(&EXIT_TO_NEAR_SIGNATURE))
+            || (log.address == ExitToEthereum::ADDRESS
+                && log.topics.first() == Some(&EXIT_TO_ETH_SIGNATURE));
+        if !is_exit || log.topics.len() < 3 {
+            return None;
+        }
+        let token = Address::from_slice(&log.topics[2][12..]);
+        if token == ETH_ADDRESS {
+            None
+        } else {
+            // The amount is the only (non-indexed) field of the data.
+            let amount = if log.data.len() == 32 {
+                U256::from_big_endian(&log.data)
+            } else {
+                U256::zero()
+            };
+            Some((token, amount))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{sdk, vec, H256};
+
+    #[test]
+    fn test_is_blacklisted_selector() {
+        assert_eq!(
+            &sdk::keccak(b"isBlacklisted(address)")[..4],
+            &IS_BLACKLISTED_SELECTOR
+        );
+    }
+
+    #[test]
+    fn test_decode_is_blacklisted() {
+        let mut output = [0u8; 32];
+        assert_eq!(decode_is_blacklisted(&output), Ok(()));
+        output[31] = 1;
+        assert_eq!(
+            decode_is_blacklisted(&output),
+            Err(ComplianceError::Blacklisted)
+        );
+        output[31] = 2;
+        assert_eq!(
+            decode_is_blacklisted(&output),
+            Err(ComplianceError::HookFailed)
+        );
+        assert_eq!(decode_is_blacklisted(&[]), Err(ComplianceError::HookFailed));
+    }
+
+    #[test]
+    fn test_token_exits() {
+        let token = Address([1u8; 20]);
+        let topic = |address: Address| {
+            let mut bytes = [0u8; 32];
+            bytes[12..].copy_from_slice(address.as_bytes());
+            H256(bytes)
+        };
+        let logs = vec![
+            Log {
+                address: ExitToNear::ADDRESS,
+                topics: vec![EXIT_TO_NEAR_SIGNATURE, topic(token), topic(token)],
+                data: Vec::new(),
+            },
+            // ETH exit
+            Log {
+                address: ExitToEthereum::ADDRESS,
+                topics: vec![EXIT_TO_ETH_SIGNATURE, topic(token), topic(ETH_ADDRESS)],
+                data: Vec::new(),
+            },
+            // Promise log of the exit precompile
+            Log {
+                address: ExitToNear::ADDRESS,
+                topics: Vec::new(),
+                data: Vec::new(),
+            },
+            // Same event emitted by another contract
+            Log {
+                address: token,
+                topics: vec![EXIT_TO_NEAR_SIGNATURE, topic(token), topic(token)],
+                data: Vec::new(),
+            },
+        ];
+        assert_eq!(
+            token_exits(&logs).collect::<Vec<_>>(),
+            vec![(token, U256::zero())]
+        );
+
+        let logs = vec![Log {
+            address: ExitToNear::ADDRESS,
+            topics: vec![EXIT_TO_NEAR_SIGNATURE, topic(token), topic(token)],
+            data: ethabi::encode(&[ethabi::Token::Int(U256::from(42))]),
+        }];
+        assert_eq!(
+            token_exits(&logs).collect::<Vec<_>>(),
+            vec![(token, U256::from(42))]
+        );
+    }
+}
@@ -1,17 +1,27 @@
 use crate::admin_controlled::{AdminControlled, PausedMask};
-use crate::deposit_event::{DepositedEvent, FtTransferMessageData, TokenMessageData};
+use crate::bridge_stats;
+use crate::connector_event;
+use crate::deposit_event::{
+    DepositedEvent, FtTransferMessageData, TokenMessageData, WithdrawnEvent,
+};
 use crate::engine::Engine;
+use crate::events;
+use crate::fee_schedule;
 use crate::fungible_token::{self, FungibleToken, FungibleTokenMetadata, FungibleTokenOps};
+use crate::json::JsonValue;
 use crate::parameters::{
-    BalanceOfCallArgs, BalanceOfEthCallArgs, FinishDepositCallArgs, InitCallArgs,
-    NEP141FtOnTransferArgs, PauseEthConnectorCallArgs, ResolveTransferCallArgs,
-    SetContractDataCallArgs, StorageBalanceOfCallArgs, StorageDepositCallArgs,
-    StorageWithdrawCallArgs, TransferCallArgs, TransferCallCallArgs, WithdrawResult,
+    BalanceOfCallArgs, BalanceOfEthCallArgs, CustodianChangeArgs, CustodianSupply,
+    FinishDepositCallArgs, FinishWithdrawCallArgs, FinishWithdrawWithProofArgs, InitCallArgs,
+    NEP141FtOnTransferArgs, PauseEthConnectorCallArgs, PendingCustodianChange, PendingWithdrawal,
+    PendingWithdrawalsPage, ResolveTransferCallArgs, SetContractDataCallArgs, SetCustodianCapArgs,
+    StorageBalanceOfCallArgs, StorageDepositCallArgs, StorageWithdrawCallArgs, TransferCallArgs,
+    TransferCallCallArgs, WithdrawResult,
 };
+use crate::prelude::precompiles::pausable;
 use crate::prelude::{
-    format, sdk, str, validate_eth_address, AccountId, Address, Balance, BorshDeserialize,
-    BorshSerialize, EthAddress, EthConnectorStorageId, KeyPrefix, NearGas, PromiseResult, ToString,
-    Vec, WithdrawCallArgs, ERR_FAILED_PARSE, H160,
+    format, sdk, str, validate_eth_address, AccountId, Address, BTreeMap, Balance,
+    BorshDeserialize, BorshSerialize, EthAddress, EthConnectorStorageId, KeyPrefix, NearGas,
+    PromiseResult, ToString, Vec, WithdrawCallArgs, ERR_FAILED_PARSE, H160,
 };
 use crate::prelude::{
     AddressValidationError, PromiseBatchAction, PromiseCreateArgs, PromiseWithCallbackArgs,
@@ -25,16 +35,35 @@ pub const ERR_NOT_ENOUGH_BALANCE_FOR_FEE: &str = "ERR_NOT_ENOUGH_BALANCE_FOR_FEE
 pub const ZERO_ATTACHED_BALANCE: Balance = 0;
 /// NEAR Gas for calling `fininsh_deposit` promise. Used in the `deposit` logic.
 pub const GAS_FOR_FINISH_DEPOSIT: NearGas = NearGas::new(50_000_000_000_000);
+/// NEAR Gas for calling `finish_withdraw` promise. Used in the `finish_withdraw_with_proof`
+/// logic.
+pub const GAS_FOR_FINISH_WITHDRAW: NearGas = NearGas::new(10_000_000_000_000);
 /// NEAR Gas for calling `verify_log_entry` promise. Used in the `deposit` logic.
 // Note: Is 40Tgas always enough?
 const GAS_FOR_VERIFY_LOG_ENTRY: NearGas = NearGas::new(40_000_000_000_000);
 
+/// Number of blocks the security council has to confirm a custodian change.
+pub const CUSTODIAN_CHANGE_WINDOW: u64 = 86_400;
+/// Largest number of pending withdrawals returned by one `get_pending_withdrawals` call.
+pub const MAX_PENDING_WITHDRAWALS_PAGE: u64 = 32;
+/// Sub-prefixes of `EthConnectorStorageId::PendingWithdrawals` for the withdrawals by nonce,
+/// the nonces by account and index, and the range of indices used by each account.
+const PENDING_WITHDRAWAL_SUB_PREFIX: u8 = 0;
+const PENDING_WITHDRAWAL_INDEX_SUB_PREFIX: u8 = 1;
+const PENDING_WITHDRAWAL_RANGE_SUB_PREFIX: u8 = 2;
+
 /// Admin control flow flag indicates that all control flow unpause (unblocked).
 pub const UNPAUSE_ALL: PausedMask = 0;
 /// Admin control flow flag indicates that the deposit is paused.
 pub const PAUSE_DEPOSIT: PausedMask = 1 << 0;
 /// Admin control flow flag indicates that withdrawal is paused.
 pub const PAUSE_WITHDRAW: PausedMask = 1 << 1;
+/// Admin control flow flag indicates that the exit to NEAR precompile is paused.
+pub const PAUSE_EXIT_TO_NEAR: PausedMask = pausable::PAUSE_EXIT_TO_NEAR;
+/// Admin control flow flag indicates that the exit to Ethereum precompile is paused.
+pub const PAUSE_EXIT_TO_ETHEREUM: PausedMask = pausable::PAUSE_EXIT_TO_ETHEREUM;
+/// Admin control flow flag indicates that the NFT exit to NEAR precompile is paused.
+pub const PAUSE_EXIT_NFT_TO_NEAR: PausedMask = pausable::PAUSE_EXIT_NFT_TO_NEAR;
 
 /// Eth-connector contract data. It's stored in the storage.
 /// Contains:
@@ -58,6 +87,19 @@ pub struct EthConnector {
     pub eth_custodian_address: EthAddress,
 }
 
+/// Promise scheduled by the connector. Outside of this module it can only be obtained from
+/// the connector methods, which save all their state changes before building it: the
+/// contracts called, and any call they make back into the connector before the promise
+/// resolves, never observe uncommitted state (checks-effects-interactions).
+#[must_use]
+pub struct Interaction<P>(P);
+
+impl<P> Interaction<P> {
+    pub fn into_promise(self) -> P {
+        self.0
+    }
+}
+
 impl<I: IO + Copy> EthConnectorContract<I> {
     /// Init Eth-connector contract instance.
     /// Load contract data from storage and init I/O handler.
@@ -126,7 +168,7 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         raw_proof: Vec<u8>,
         current_account_id: AccountId,
         predecessor_account_id: AccountId,
-    ) -> Result<PromiseWithCallbackArgs, error::DepositError> {
+    ) -> Result<Interaction<PromiseWithCallbackArgs>, error::DepositError> {
         // Check is current account owner
         let is_owner = current_account_id == predecessor_account_id;
         // Check is current flow paused. If it's owner account just skip it.
@@ -160,10 +202,16 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             return Err(error::DepositError::CustodianAddressMismatch);
         }
 
-        if event.fee.into_u128() >= event.amount {
+        let fee = fee_schedule::get_schedule_or_default(&self.io, &current_account_id)
+            .deposit_fee(event.amount, event.fee);
+        if fee.into_u128() >= event.amount {
             return Err(error::DepositError::InsufficientAmountForFee);
         }
 
+        // Fail early; the cap is enforced again when the tokens are minted.
+        self.custodian_minted_after_deposit(&event.eth_custodian_address, event.amount)
+            .ok_or(error::DepositError::CustodianCapExceeded)?;
+
         // Verify proof data with cross-contract call to prover account
         sdk::log!(&format!(
             "Deposit verify_log_entry for prover: {}",
@@ -191,7 +239,7 @@ impl<I: IO + Copy> EthConnectorContract<I> {
                 amount: event.amount,
                 proof_key: proof.get_key(),
                 relayer_id: predecessor_account_id,
-                fee: event.fee,
+                fee,
                 msg: None,
             }
             .try_to_vec()
@@ -200,8 +248,9 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             // fee is being minted in the `ft_on_transfer` callback method
             TokenMessageData::Eth {
                 receiver_id,
-                message,
+                mut message,
             } => {
+                message.fee = fee;
                 // Transfer to self and then transfer ETH in `ft_on_transfer`
                 // address - is NEAR account
                 let transfer_data = TransferCallCallArgs {
@@ -219,7 +268,7 @@ impl<I: IO + Copy> EthConnectorContract<I> {
                     amount: event.amount,
                     proof_key: proof.get_key(),
                     relayer_id: predecessor_account_id,
-                    fee: event.fee,
+                    fee,
                     msg: Some(transfer_data),
                 }
                 .try_to_vec()
@@ -234,10 +283,11 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             attached_balance: ZERO_ATTACHED_BALANCE,
             attached_gas: GAS_FOR_FINISH_DEPOSIT.into_u64(),
         };
-        Ok(PromiseWithCallbackArgs {
+        // Nothing is written before the proof is verified.
+        Ok(Interaction(PromiseWithCallbackArgs {
             base: verify_call,
             callback: finish_call,
-        })
+        }))
     }
 
     /// Finish deposit (private method)
@@ -251,18 +301,36 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         current_account_id: AccountId,
         data: FinishDepositCallArgs,
         prepaid_gas: NearGas,
-    ) -> Result<Option<PromiseWithCallbackArgs>, error::FinishDepositError> {
+    ) -> Result<Option<Interaction<PromiseWithCallbackArgs>>, error::FinishDepositError> {
         sdk::log!(&format!("Finish deposit with the amount: {}", data.amount));
 
+        let custodian = self.contract.eth_custodian_address;
+        let custodian_minted = self
+            .custodian_minted_after_deposit(&custodian, data.amount)
+            .ok_or(error::FinishDepositError::CustodianCapExceeded)?;
+
         // Mint tokens to recipient minus fee
         if let Some(msg) = data.msg {
             // Mint - calculate new balances
-            self.mint_eth_on_near(data.new_owner_id, data.amount)?;
+            self.mint_eth_on_near(data.new_owner_id.clone(), data.amount)?;
             // Store proof only after `mint` calculations
             self.record_proof(&data.proof_key)?;
+            self.set_custodian_minted(&custodian, custodian_minted);
             // Save new contract data
             self.save_ft_contract();
+            connector_event::emit_deposit(&data.new_owner_id, data.amount, 0, &data.relayer_id);
             let transfer_call_args = TransferCallCallArgs::try_from_slice(&msg).unwrap();
+            // The ETH ends up on Aurora, at the recipient of the message.
+            let recipient =
+                FtTransferMessageData::parse_on_transfer_message(&transfer_call_args.msg)
+                    .map(|message| message.recipient.as_bytes().to_vec())
+                    .unwrap_or_else(|_| transfer_call_args.receiver_id.as_bytes().to_vec());
+            bridge_stats::record_deposit(
+                &mut self.io,
+                &bridge_stats::ETH_TOKEN,
+                data.amount,
+                &recipient,
+            );
             let promise = self.ft_transfer_call(
                 predecessor_account_id,
                 current_account_id,
@@ -272,15 +340,29 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             Ok(Some(promise))
         } else {
             // Mint - calculate new balances
-            self.mint_eth_on_near(
-                data.new_owner_id.clone(),
-                data.amount - data.fee.into_u128(),
-            )?;
-            self.mint_eth_on_near(data.relayer_id, data.fee.into_u128())?;
+            let amount = data
+                .amount
+                .checked_sub(data.fee.into_u128())
+                .ok_or(error::FtTransferCallError::InsufficientAmountForFee)?;
+            self.mint_eth_on_near(data.new_owner_id.clone(), amount)?;
+            self.mint_eth_on_near(data.relayer_id.clone(), data.fee.into_u128())?;
             // Store proof only after `mint` calculations
             self.record_proof(&data.proof_key)?;
+            self.set_custodian_minted(&custodian, custodian_minted);
             // Save new contract data
             self.save_ft_contract();
+            connector_event::emit_deposit(
+                &data.new_owner_id,
+                amount,
+                data.fee.into_u128(),
+                &data.relayer_id,
+            );
+            bridge_stats::record_deposit(
+                &mut self.io,
+                &bridge_stats::ETH_TOKEN,
+                data.amount,
+                data.new_owner_id.as_bytes(),
+            );
             Ok(None)
         }
     }
@@ -291,7 +373,7 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         address: &Address,
         amount: Balance,
     ) -> Result<(), fungible_token::error::WithdrawError> {
-        self.burn_eth_on_aurora(address.0, amount)?;
+        self.burn_eth_on_aurora(EthAddress::from(*address), amount)?;
         self.save_ft_contract();
         Ok(())
     }
@@ -350,6 +432,24 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         self.ft.internal_withdraw_eth_from_aurora(address, amount)
     }
 
+    /// Credit ETH to the given addresses without a deposit proof. Only meant for local
+    /// development networks. The matching nETH is minted to the engine account on NEAR
+    /// so the total supplies on NEAR and Aurora stay consistent.
+    #[cfg(feature = "dev")]
+    pub fn dev_fund(
+        &mut self,
+        current_account_id: &AccountId,
+        addresses: &[EthAddress],
+        amount: Balance,
+    ) -> Result<(), fungible_token::error::DepositError> {
+        for address in addresses {
+            self.mint_eth_on_near(current_account_id.clone(), amount)?;
+            self.mint_eth_on_aurora(*address, amount)?;
+        }
+        self.save_ft_contract();
+        Ok(())
+    }
+
     /// Withdraw nETH from NEAR accounts
     /// NOTE: it should be without any log data
     pub fn withdraw_eth_from_near(
@@ -364,17 +464,160 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         self.assert_not_paused(PAUSE_WITHDRAW, is_owner)
             .map_err(|_| error::WithdrawError::Paused)?;
 
+        let fee_collector = fee_schedule::get_fee_collector(&self.io);
+        let fee = match &fee_collector {
+            Some(_) => fee_schedule::get_schedule_or_default(&self.io, current_account_id)
+                .withdraw_fee(args.amount)
+                .into_u128(),
+            None => 0,
+        };
+        if fee > 0 && fee >= args.amount {
+            return Err(error::WithdrawError::InsufficientAmountForFee);
+        }
+
         // Burn tokens to recipient
         self.ft
             .internal_withdraw_eth_from_near(predecessor_account_id, args.amount)?;
+        // The fee is not withdrawn; it stays on NEAR as nETH of the fee collector.
+        if let Some(fee_collector) = fee_collector.filter(|_| fee > 0) {
+            sdk::log!(&format!("Withdraw fee: {}", fee));
+            self.ft
+                .internal_deposit_eth_to_near(&fee_collector, fee)
+                .map_err(error::WithdrawError::FeeCollection)?;
+        }
+        let amount = args.amount - fee;
         // Save new contract data
         self.save_ft_contract();
+        let custodian = self.contract.eth_custodian_address;
+        let custodian_minted = self.get_custodian_supply(&custodian).minted;
+        self.set_custodian_minted(&custodian, custodian_minted.saturating_sub(amount));
+        bridge_stats::record_withdrawal(&mut self.io, &bridge_stats::ETH_TOKEN, amount);
+        let nonce = self.get_withdrawal_nonce();
+        self.io.write_storage(
+            &construct_contract_key(&EthConnectorStorageId::WithdrawalNonce),
+            &(nonce + 1).to_le_bytes(),
+        );
 
-        Ok(WithdrawResult {
+        let result = WithdrawResult {
             recipient_id: args.recipient_address,
-            amount: args.amount,
+            amount,
             eth_custodian_address: self.contract.eth_custodian_address,
-        })
+            nonce,
+        };
+        self.add_pending_withdrawal(PendingWithdrawal {
+            nonce,
+            sender_id: predecessor_account_id.clone(),
+            recipient_id: result.recipient_id,
+            amount,
+            eth_custodian_address: result.eth_custodian_address,
+        });
+        connector_event::emit_withdraw(predecessor_account_id, &result, fee);
+        Ok(result)
+    }
+
+    /// Verifies the proof of the `Withdrawn` event of the custodian, by which the ETH of the
+    /// pending withdrawal with the given nonce was released on Ethereum, and then marks the
+    /// withdrawal finalized with `finish_withdraw`.
+    pub fn finish_withdraw_with_proof(
+        &self,
+        args: FinishWithdrawWithProofArgs,
+        current_account_id: AccountId,
+    ) -> Result<Interaction<PromiseWithCallbackArgs>, error::FinishWithdrawError> {
+        let event = WithdrawnEvent::from_log_entry_data(&args.proof.log_entry_data)
+            .map_err(error::FinishWithdrawError::EventParseFailed)?;
+        let proof_key = args.proof.get_key();
+        // Fail early; both are checked again once the proof is verified.
+        if self.is_used_event(&proof_key) {
+            return Err(error::FinishWithdrawError::ProofUsed);
+        }
+        let withdrawal = self
+            .get_pending_withdrawal(args.nonce)
+            .ok_or(error::FinishWithdrawError::NoPendingWithdrawal)?;
+        if withdrawal.recipient_id != event.recipient
+            || withdrawal.amount != event.amount
+            || withdrawal.eth_custodian_address != event.eth_custodian_address
+        {
+            return Err(error::FinishWithdrawError::WithdrawalMismatch);
+        }
+
+        // Do not skip bridge call. This is only used for development and diagnostics.
+        let skip_bridge_call = false.try_to_vec().unwrap();
+        let mut proof_to_verify = args.proof.try_to_vec().unwrap();
+        proof_to_verify.extend(skip_bridge_call);
+
+        let verify_call = PromiseCreateArgs {
+            target_account_id: self.contract.prover_account.clone(),
+            method: "verify_log_entry".to_string(),
+            args: proof_to_verify,
+            attached_balance: ZERO_ATTACHED_BALANCE,
+            attached_gas: GAS_FOR_VERIFY_LOG_ENTRY.into_u64(),
+        };
+        let finish_call = PromiseCreateArgs {
+            target_account_id: current_account_id,
+            method: "finish_withdraw".to_string(),
+            args: FinishWithdrawCallArgs {
+                nonce: args.nonce,
+                proof_key,
+            }
+            .try_to_vec()
+            .unwrap(),
+            attached_balance: ZERO_ATTACHED_BALANCE,
+            attached_gas: GAS_FOR_FINISH_WITHDRAW.into_u64(),
+        };
+        // Nothing is written before the proof is verified.
+        Ok(Interaction(PromiseWithCallbackArgs {
+            base: verify_call,
+            callback: finish_call,
+        }))
+    }
+
+    /// Marks finalized the pending withdrawal whose `Withdrawn` event was verified.
+    pub fn finish_withdraw(
+        &mut self,
+        data: FinishWithdrawCallArgs,
+    ) -> Result<PendingWithdrawal, error::FinishWithdrawError> {
+        let withdrawal = self
+            .remove_pending_withdrawal(data.nonce)
+            .ok_or(error::FinishWithdrawError::NoPendingWithdrawal)?;
+        self.record_proof(&data.proof_key)?;
+        sdk::log!(&format!(
+            "Withdrawal {} to {} finalized",
+            withdrawal.nonce,
+            hex::encode(withdrawal.recipient_id),
+        ));
+        connector_event::emit_withdraw_finalized(&withdrawal);
+        Ok(withdrawal)
+    }
+
+    /// Forgets the pending withdrawal with the given nonce without finalizing it, freeing its
+    /// storage. Only the account which initiated the withdrawal, or the connector itself
+    /// for exits from Aurora, can forget it; it then cannot be finalized anymore.
+    pub fn forget_pending_withdrawal(
+        &mut self,
+        nonce: u64,
+        current_account_id: &AccountId,
+        predecessor_account_id: &AccountId,
+    ) -> Result<PendingWithdrawal, error::ForgetWithdrawalError> {
+        let withdrawal = self
+            .get_pending_withdrawal(nonce)
+            .ok_or(error::ForgetWithdrawalError::NoPendingWithdrawal)?;
+        if &withdrawal.sender_id != predecessor_account_id
+            && current_account_id != predecessor_account_id
+        {
+            return Err(error::ForgetWithdrawalError::NotAllowed);
+        }
+        self.remove_pending_withdrawal(nonce);
+        sdk::log!(&format!("Pending withdrawal {} forgotten", nonce));
+        Ok(withdrawal)
+    }
+
+    /// Nonce of the next withdrawal, i.e. the number of withdrawals so far.
+    pub fn get_withdrawal_nonce(&self) -> u64 {
+        self.io
+            .read_u64(&construct_contract_key(
+                &EthConnectorStorageId::WithdrawalNonce,
+            ))
+            .unwrap_or(0)
     }
 
     /// Returns total ETH supply on NEAR (nETH as NEP-141 token)
@@ -436,6 +679,12 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             &args.memo,
         )?;
         self.save_ft_contract();
+        connector_event::emit_ft_transfer(
+            predecessor_account_id,
+            &args.receiver_id,
+            args.amount,
+            args.memo.as_ref(),
+        );
         sdk::log!(&format!(
             "Transfer amount {} to {} success with memo: {:?}",
             args.amount, args.receiver_id, args.memo
@@ -475,7 +724,7 @@ impl<I: IO + Copy> EthConnectorContract<I> {
         current_account_id: AccountId,
         args: TransferCallCallArgs,
         prepaid_gas: NearGas,
-    ) -> Result<PromiseWithCallbackArgs, error::FtTransferCallError> {
+    ) -> Result<Interaction<PromiseWithCallbackArgs>, error::FtTransferCallError> {
         sdk::log!(&format!(
             "Transfer call to {} amount {}",
             args.receiver_id, args.amount,
@@ -515,17 +764,125 @@ impl<I: IO + Copy> EthConnectorContract<I> {
             }
         }
 
-        self.ft
-            .ft_transfer_call(
-                predecessor_account_id,
-                args.receiver_id,
+        let sender_id = predecessor_account_id.clone();
+        let receiver_id = args.receiver_id.clone();
+        let promise = self.ft.ft_transfer_call(
+            predecessor_account_id,
+            args.receiver_id,
+            args.amount,
+            &args.memo,
+            args.msg,
+            current_account_id,
+            prepaid_gas,
+        )?;
+        // The engine transferring to itself moves nothing.
+        if sender_id != receiver_id {
+            connector_event::emit_ft_transfer(
+                &sender_id,
+                &receiver_id,
                 args.amount,
-                &args.memo,
-                args.msg,
-                current_account_id,
-                prepaid_gas,
-            )
-            .map_err(Into::into)
+                args.memo.as_ref(),
+            );
+        }
+        Ok(self.commit(promise))
+    }
+
+    /// Saves the state of the connector, then allows `promise` to be scheduled.
+    fn commit<P>(&mut self, promise: P) -> Interaction<P> {
+        self.save_ft_contract();
+        Interaction(promise)
+    }
+
+    /// Withdrawals initiated by `account_id` which were not finalized yet, oldest first, from
+    /// the index `from_index` of the account and at most `limit` of them (capped to
+    /// `MAX_PENDING_WITHDRAWALS_PAGE`). Finalized withdrawals leave holes, so a page may hold
+    /// fewer withdrawals than asked for without being the last.
+    pub fn get_pending_withdrawals(
+        &self,
+        account_id: &AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> PendingWithdrawalsPage {
+        let (first, next) = self.get_pending_withdrawals_range(account_id);
+        let start = from_index.max(first);
+        let end = start
+            .saturating_add(limit.min(MAX_PENDING_WITHDRAWALS_PAGE))
+            .min(next);
+        let withdrawals = (start..end)
+            .filter_map(|index| {
+                self.io
+                    .read_u64(&pending_withdrawal_index_key(account_id, index))
+                    .ok()
+            })
+            .filter_map(|nonce| self.get_pending_withdrawal(nonce))
+            .collect();
+        PendingWithdrawalsPage {
+            withdrawals,
+            next_index: if end < next { Some(end) } else { None },
+        }
+    }
+
+    /// Pending withdrawal with the given nonce, if it was not finalized yet.
+    pub fn get_pending_withdrawal(&self, nonce: u64) -> Option<PendingWithdrawal> {
+        self.read_pending_withdrawal(nonce)
+            .map(|(_, withdrawal)| withdrawal)
+    }
+
+    /// The withdrawal with its index among the ones of its sender.
+    fn read_pending_withdrawal(&self, nonce: u64) -> Option<(u64, PendingWithdrawal)> {
+        self.io
+            .read_storage(&pending_withdrawal_key(nonce))
+            .and_then(|data| data.to_value().ok())
+    }
+
+    /// Indices of the first and the next pending withdrawal of `account_id`.
+    fn get_pending_withdrawals_range(&self, account_id: &AccountId) -> (u64, u64) {
+        self.io
+            .read_storage(&pending_withdrawals_range_key(account_id))
+            .and_then(|data| data.to_value().ok())
+            .unwrap_or_default()
+    }
+
+    fn set_pending_withdrawals_range(&mut self, account_id: &AccountId, range: (u64, u64)) {
+        let key = pending_withdrawals_range_key(account_id);
+        if range.0 == range.1 {
+            self.io.remove_storage(&key);
+        } else {
+            self.io.write_borsh(&key, &range);
+        }
+    }
+
+    fn add_pending_withdrawal(&mut self, withdrawal: PendingWithdrawal) {
+        let (first, next) = self.get_pending_withdrawals_range(&withdrawal.sender_id);
+        self.io.write_storage(
+            &pending_withdrawal_index_key(&withdrawal.sender_id, next),
+            &withdrawal.nonce.to_le_bytes(),
+        );
+        self.set_pending_withdrawals_range(&withdrawal.sender_id, (first, next + 1));
+        self.io.write_borsh(
+            &pending_withdrawal_key(withdrawal.nonce),
+            &(next, withdrawal),
+        );
+    }
+
+    fn remove_pending_withdrawal(&mut self, nonce: u64) -> Option<PendingWithdrawal> {
+        let (index, withdrawal) = self.read_pending_withdrawal(nonce)?;
+        let account_id = &withdrawal.sender_id;
+        self.io.remove_storage(&pending_withdrawal_key(nonce));
+        self.io
+            .remove_storage(&pending_withdrawal_index_key(account_id, index));
+        // Skip the holes at the start, so that the range stays as short as possible. Each
+        // index is skipped once.
+        let (mut first, next) = self.get_pending_withdrawals_range(account_id);
+        while first < next
+            && !self
+                .io
+                .storage_has_key(&pending_withdrawal_index_key(account_id, first))
+        {
+            first += 1;
+        }
+        self.set_pending_withdrawals_range(account_id, (first, next));
+        Some(withdrawal)
     }
 
     /// FT storage deposit logic
@@ -588,30 +945,72 @@ impl<I: IO + Copy> EthConnectorContract<I> {
     }
 
     /// ft_on_transfer callback function
+    ///
+    /// Returns (and writes as output) the amount of tokens to refund to the sender, instead
+    /// of panicking: all of them if the message cannot be parsed or nothing was minted, the
+    /// fee if only the relayer could not be paid.
     pub fn ft_on_transfer<'env, E: Env>(
         &mut self,
         engine: &Engine<'env, I, E>,
         args: &NEP141FtOnTransferArgs,
-    ) -> Result<(), error::FtTransferCallError> {
+    ) -> Balance {
         sdk::log!("Call ft_on_transfer");
+        let unused_amount = self.internal_ft_on_transfer(engine, args);
+        self.io
+            .return_output(format!("\"{}\"", unused_amount).as_bytes());
+        unused_amount
+    }
+
+    fn internal_ft_on_transfer<'env, E: Env>(
+        &mut self,
+        engine: &Engine<'env, I, E>,
+        args: &NEP141FtOnTransferArgs,
+    ) -> Balance {
         // Parse message with specific rules
-        let message_data = FtTransferMessageData::parse_on_transfer_message(&args.msg)
-            .map_err(error::FtTransferCallError::MessageParseFailed)?;
+        let message_data = match FtTransferMessageData::parse_on_transfer_message(&args.msg) {
+            Ok(message_data) => message_data,
+            Err(_e) => {
+                sdk::log!(str::from_utf8(_e.as_ref()).unwrap_or_default());
+                return args.amount;
+            }
+        };
 
         // Special case when predecessor_account_id is current_account_id
         let fee = message_data.fee.into_u128();
         // Mint fee to relayer
         let relayer = engine.get_relayer(message_data.relayer.as_bytes());
-        match (fee, relayer) {
-            (fee, Some(H160(evm_relayer_address))) if fee > 0 => {
-                self.mint_eth_on_aurora(message_data.recipient, args.amount - fee)?;
-                self.mint_eth_on_aurora(evm_relayer_address, fee)?;
-            }
-            _ => self.mint_eth_on_aurora(message_data.recipient, args.amount)?,
+        let (amount, fee_recipient) = match (fee, relayer) {
+            (fee, Some(H160(evm_relayer_address))) if fee > 0 => match args.amount.checked_sub(fee)
+            {
+                Some(amount) => (amount, Some(EthAddress::new(evm_relayer_address))),
+                None => {
+                    sdk::log!(ERR_NOT_ENOUGH_BALANCE_FOR_FEE);
+                    return args.amount;
+                }
+            },
+            _ => (args.amount, None),
+        };
+        // A failed mint leaves the state untouched, so what was not minted is refunded.
+        if self
+            .mint_eth_on_aurora(message_data.recipient, amount)
+            .is_err()
+        {
+            return args.amount;
         }
+        let (unused_amount, relayer_paid) = match fee_recipient {
+            Some(relayer) if self.mint_eth_on_aurora(relayer, fee).is_err() => (fee, None),
+            Some(relayer) => (0, Some(relayer)),
+            None => (0, None),
+        };
         self.save_ft_contract();
-        self.io.return_output("\"0\"".as_bytes());
-        Ok(())
+        connector_event::emit_ft_on_transfer(
+            &args.sender_id,
+            &message_data.recipient,
+            amount,
+            relayer_paid.map_or(0, |_| fee),
+            relayer_paid.as_ref(),
+        );
+        unused_amount
     }
 
     /// Get accounts counter for statistics.
@@ -658,7 +1057,214 @@ impl<I: IO + Copy> EthConnectorContract<I> {
 
     /// Set Eth connector paused flags
     pub fn set_paused_flags(&mut self, args: PauseEthConnectorCallArgs) {
+        let previous = self.get_paused();
         self.set_paused(args.paused_mask);
+        events::emit_config_changed(
+            "paused_flags",
+            JsonValue::U64(previous.into()),
+            JsonValue::U64(args.paused_mask.into()),
+        );
+    }
+
+    /// Account of the prover verifying the deposit proofs.
+    pub fn get_prover_account(&self) -> AccountId {
+        self.contract.prover_account.clone()
+    }
+
+    /// Eth address of the custodian contract deposits are accepted from.
+    pub fn get_eth_custodian_address(&self) -> EthAddress {
+        self.contract.eth_custodian_address
+    }
+
+    /// ETH currently minted through the given custodian, and its cap if any.
+    pub fn get_custodian_supply(&self, custodian: &EthAddress) -> CustodianSupply {
+        let minted = self
+            .io
+            .read_storage(&custodian_key(
+                EthConnectorStorageId::CustodianMinted,
+                custodian,
+            ))
+            .and_then(|data| data.to_value().ok())
+            .unwrap_or(0);
+        let cap = self
+            .io
+            .read_storage(&custodian_key(
+                EthConnectorStorageId::CustodianCap,
+                custodian,
+            ))
+            .and_then(|data| data.to_value().ok());
+        CustodianSupply { minted, cap }
+    }
+
+    /// Set or remove the cap on the ETH minted through a custodian. Lowering the cap below
+    /// the current supply only blocks further deposits.
+    pub fn set_custodian_cap(&mut self, args: SetCustodianCapArgs) {
+        let key = custodian_key(EthConnectorStorageId::CustodianCap, &args.custodian);
+        let previous: Option<Balance> = self
+            .io
+            .read_storage(&key)
+            .and_then(|data| data.to_value().ok());
+        events::emit_config_changed(
+            "custodian_cap",
+            custodian_cap_data(&args.custodian, previous),
+            custodian_cap_data(&args.custodian, args.cap),
+        );
+        match args.cap {
+            Some(cap) => {
+                self.io.write_borsh(&key, &cap);
+            }
+            None => {
+                self.io.remove_storage(&key);
+            }
+        }
+    }
+
+    /// Account which must confirm custodian changes. Defaults to the prover account.
+    pub fn get_custodian_council(&self) -> AccountId {
+        self.io
+            .read_storage(&construct_contract_key(
+                &EthConnectorStorageId::CustodianCouncil,
+            ))
+            .and_then(|data| data.to_value().ok())
+            .unwrap_or_else(|| self.contract.prover_account.clone())
+    }
+
+    pub fn set_custodian_council(&mut self, council: AccountId) {
+        events::emit_config_changed(
+            "custodian_council",
+            JsonValue::String(self.get_custodian_council().to_string()),
+            JsonValue::String(council.to_string()),
+        );
+        self.io.write_borsh(
+            &construct_contract_key(&EthConnectorStorageId::CustodianCouncil),
+            &council,
+        );
+    }
+
+    pub fn get_pending_custodian_change(&self) -> Option<PendingCustodianChange> {
+        self.io
+            .read_storage(&construct_contract_key(
+                &EthConnectorStorageId::PendingCustodianChange,
+            ))
+            .and_then(|data| data.to_value().ok())
+    }
+
+    /// First step of a custodian change, taken by the owner. The change only activates once
+    /// the security council confirms it, within `CUSTODIAN_CHANGE_WINDOW` blocks. An expired
+    /// proposal can be replaced by a new one.
+    pub fn propose_custodian_change(
+        &mut self,
+        args: CustodianChangeArgs,
+        block_height: u64,
+    ) -> Result<PendingCustodianChange, error::CustodianChangeError> {
+        if args.new_custodian == self.contract.eth_custodian_address {
+            return Err(error::CustodianChangeError::SameCustodian);
+        }
+        if let Some(pending) = self.get_pending_custodian_change() {
+            if !pending.is_expired(block_height) {
+                return Err(error::CustodianChangeError::ChangeAlreadyPending);
+            }
+        }
+
+        let change = PendingCustodianChange {
+            new_custodian: args.new_custodian,
+            proposed_at: block_height,
+            expires_at: block_height.saturating_add(CUSTODIAN_CHANGE_WINDOW),
+        };
+        self.io.write_borsh(
+            &construct_contract_key(&EthConnectorStorageId::PendingCustodianChange),
+            &change,
+        );
+        crate::events::emit_custodian_change(
+            crate::events::CUSTODIAN_CHANGE_PROPOSED,
+            &self.contract.eth_custodian_address,
+            &change,
+        );
+        Ok(change)
+    }
+
+    /// Second step of a custodian change: the security council confirms the address proposed
+    /// by the owner, which becomes the custodian immediately.
+    pub fn confirm_custodian_change(
+        &mut self,
+        args: CustodianChangeArgs,
+        predecessor_account_id: &AccountId,
+        block_height: u64,
+    ) -> Result<(), error::CustodianChangeError> {
+        if predecessor_account_id != &self.get_custodian_council() {
+            return Err(error::CustodianChangeError::NotCouncil);
+        }
+        let change = self
+            .get_pending_custodian_change()
+            .ok_or(error::CustodianChangeError::NoPendingChange)?;
+        if change.is_expired(block_height) {
+            return Err(error::CustodianChangeError::ChangeExpired);
+        }
+        if change.new_custodian != args.new_custodian {
+            return Err(error::CustodianChangeError::AddressMismatch);
+        }
+
+        let previous_custodian = self.contract.eth_custodian_address;
+        self.contract.eth_custodian_address = change.new_custodian;
+        self.io.write_borsh(
+            &construct_contract_key(&EthConnectorStorageId::Contract),
+            &self.contract,
+        );
+        self.io.remove_storage(&construct_contract_key(
+            &EthConnectorStorageId::PendingCustodianChange,
+        ));
+        crate::events::emit_custodian_change(
+            crate::events::CUSTODIAN_CHANGE_CONFIRMED,
+            &previous_custodian,
+            &change,
+        );
+        Ok(())
+    }
+
+    /// Drops the pending custodian change. Both the owner and the security council can cancel.
+    pub fn cancel_custodian_change(
+        &mut self,
+        predecessor_account_id: &AccountId,
+        current_account_id: &AccountId,
+    ) -> Result<(), error::CustodianChangeError> {
+        if predecessor_account_id != current_account_id
+            && predecessor_account_id != &self.get_custodian_council()
+        {
+            return Err(error::CustodianChangeError::NotCouncil);
+        }
+        let change = self
+            .get_pending_custodian_change()
+            .ok_or(error::CustodianChangeError::NoPendingChange)?;
+        self.io.remove_storage(&construct_contract_key(
+            &EthConnectorStorageId::PendingCustodianChange,
+        ));
+        crate::events::emit_custodian_change(
+            crate::events::CUSTODIAN_CHANGE_CANCELLED,
+            &self.contract.eth_custodian_address,
+            &change,
+        );
+        Ok(())
+    }
+
+    /// Supply of the custodian after minting `amount`, or `None` if it would exceed the cap.
+    fn custodian_minted_after_deposit(
+        &self,
+        custodian: &EthAddress,
+        amount: Balance,
+    ) -> Option<Balance> {
+        let supply = self.get_custodian_supply(custodian);
+        let minted = supply.minted.checked_add(amount)?;
+        match supply.cap {
+            Some(cap) if minted > cap => None,
+            _ => Some(minted),
+        }
+    }
+
+    fn set_custodian_minted(&mut self, custodian: &EthAddress, minted: Balance) {
+        self.io.write_borsh(
+            &custodian_key(EthConnectorStorageId::CustodianMinted, custodian),
+            &minted,
+        );
     }
 }
 
@@ -682,6 +1288,38 @@ fn construct_contract_key(suffix: &EthConnectorStorageId) -> Vec<u8> {
     crate::prelude::bytes_to_key(KeyPrefix::EthConnector, &[*suffix as u8])
 }
 
+fn custodian_key(id: EthConnectorStorageId, custodian: &EthAddress) -> Vec<u8> {
+    [construct_contract_key(&id).as_slice(), custodian.as_bytes()].concat()
+}
+
+fn pending_withdrawal_key(nonce: u64) -> Vec<u8> {
+    [
+        construct_contract_key(&EthConnectorStorageId::PendingWithdrawals).as_slice(),
+        &[PENDING_WITHDRAWAL_SUB_PREFIX],
+        &nonce.to_be_bytes(),
+    ]
+    .concat()
+}
+
+fn pending_withdrawal_index_key(account_id: &AccountId, index: u64) -> Vec<u8> {
+    [
+        construct_contract_key(&EthConnectorStorageId::PendingWithdrawals).as_slice(),
+        &[PENDING_WITHDRAWAL_INDEX_SUB_PREFIX],
+        account_id.as_bytes(),
+        &index.to_be_bytes(),
+    ]
+    .concat()
+}
+
+fn pending_withdrawals_range_key(account_id: &AccountId) -> Vec<u8> {
+    [
+        construct_contract_key(&EthConnectorStorageId::PendingWithdrawals).as_slice(),
+        &[PENDING_WITHDRAWAL_RANGE_SUB_PREFIX],
+        account_id.as_bytes(),
+    ]
+    .concat()
+}
+
 fn get_contract_data<T: BorshDeserialize, I: IO>(io: &I, suffix: &EthConnectorStorageId) -> T {
     io.read_storage(&construct_contract_key(suffix))
         .expect("Failed read storage")
@@ -713,6 +1351,56 @@ pub fn set_contract_data<I: IO>(
     Ok(contract_data)
 }
 
+/// Same as `set_contract_data`, except that once the connector is initialized the custodian
+/// address can only be changed through `propose_custodian_change` and
+/// `confirm_custodian_change`.
+pub fn update_contract_data<I: IO>(
+    io: &mut I,
+    args: SetContractDataCallArgs,
+) -> Result<EthConnector, error::CustodianChangeError> {
+    let new_custodian = validate_eth_address(args.eth_custodian_address.clone())
+        .map_err(error::CustodianChangeError::InvalidAddress)?;
+    let current = io
+        .read_storage(&construct_contract_key(&EthConnectorStorageId::Contract))
+        .and_then(|data| data.to_value::<EthConnector>().ok());
+    if let Some(current) = &current {
+        if current.eth_custodian_address != new_custodian {
+            return Err(error::CustodianChangeError::ConfirmationRequired);
+        }
+    }
+    let contract_data =
+        set_contract_data(io, args).map_err(error::CustodianChangeError::InvalidAddress)?;
+    events::emit_config_changed(
+        "eth_connector_prover",
+        current.as_ref().map_or(JsonValue::Null, |current| {
+            JsonValue::String(current.prover_account.to_string())
+        }),
+        JsonValue::String(contract_data.prover_account.to_string()),
+    );
+    events::emit_config_changed(
+        "eth_custodian",
+        current.as_ref().map_or(JsonValue::Null, |current| {
+            JsonValue::String(hex::encode(current.eth_custodian_address))
+        }),
+        JsonValue::String(hex::encode(contract_data.eth_custodian_address)),
+    );
+    Ok(contract_data)
+}
+
+fn custodian_cap_data(custodian: &EthAddress, cap: Option<Balance>) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "custodian".to_string(),
+        JsonValue::String(hex::encode(custodian)),
+    );
+    // As a string, like the other NEP-141 amounts, since it may not fit in a JSON number.
+    kvs.insert(
+        "cap".to_string(),
+        cap.map_or(JsonValue::Null, |cap| JsonValue::String(cap.to_string())),
+    );
+    JsonValue::Object(kvs)
+}
+
 /// Return metdata
 pub fn get_metadata<I: IO>(io: &I) -> Option<FungibleTokenMetadata> {
     io.read_storage(&construct_contract_key(
@@ -728,6 +1416,7 @@ pub mod error {
     use crate::{deposit_event, fungible_token};
 
     const PROOF_EXIST: &[u8; 15] = b"ERR_PROOF_EXIST";
+    const CUSTODIAN_CAP_EXCEEDED: &[u8; 26] = b"ERR_CUSTODIAN_CAP_EXCEEDED";
 
     #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
     pub enum DepositError {
@@ -737,6 +1426,7 @@ pub mod error {
         CustodianAddressMismatch,
         InsufficientAmountForFee,
         InvalidAddress(AddressValidationError),
+        CustodianCapExceeded,
     }
 
     impl AsRef<[u8]> for DepositError {
@@ -748,6 +1438,7 @@ pub mod error {
                 Self::CustodianAddressMismatch => b"ERR_WRONG_EVENT_ADDRESS",
                 Self::InsufficientAmountForFee => super::ERR_NOT_ENOUGH_BALANCE_FOR_FEE.as_bytes(),
                 Self::InvalidAddress(e) => e.as_ref(),
+                Self::CustodianCapExceeded => CUSTODIAN_CAP_EXCEEDED,
             }
         }
     }
@@ -756,6 +1447,7 @@ pub mod error {
     pub enum FinishDepositError {
         TransferCall(FtTransferCallError),
         ProofUsed,
+        CustodianCapExceeded,
     }
 
     impl From<ProofUsed> for FinishDepositError {
@@ -781,13 +1473,16 @@ pub mod error {
             match self {
                 Self::ProofUsed => PROOF_EXIST,
                 Self::TransferCall(e) => e.as_ref(),
+                Self::CustodianCapExceeded => CUSTODIAN_CAP_EXCEEDED,
             }
         }
     }
 
     pub enum WithdrawError {
         Paused,
+        InsufficientAmountForFee,
         FT(fungible_token::error::WithdrawError),
+        FeeCollection(fungible_token::error::DepositError),
     }
 
     impl From<fungible_token::error::WithdrawError> for WithdrawError {
@@ -800,7 +1495,9 @@ pub mod error {
         fn as_ref(&self) -> &[u8] {
             match self {
                 Self::Paused => crate::admin_controlled::ERR_PAUSED.as_bytes(),
+                Self::InsufficientAmountForFee => super::ERR_NOT_ENOUGH_BALANCE_FOR_FEE.as_bytes(),
                 Self::FT(e) => e.as_ref(),
+                Self::FeeCollection(e) => e.as_ref(),
             }
         }
     }
@@ -856,6 +1553,72 @@ pub mod error {
         }
     }
 
+    pub enum CustodianChangeError {
+        InvalidAddress(AddressValidationError),
+        SameCustodian,
+        ChangeAlreadyPending,
+        NoPendingChange,
+        ChangeExpired,
+        AddressMismatch,
+        NotCouncil,
+        ConfirmationRequired,
+    }
+
+    impl AsRef<[u8]> for CustodianChangeError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::InvalidAddress(e) => e.as_ref(),
+                Self::SameCustodian => b"ERR_SAME_CUSTODIAN",
+                Self::ChangeAlreadyPending => b"ERR_CUSTODIAN_CHANGE_ALREADY_PENDING",
+                Self::NoPendingChange => b"ERR_NO_PENDING_CUSTODIAN_CHANGE",
+                Self::ChangeExpired => b"ERR_CUSTODIAN_CHANGE_EXPIRED",
+                Self::AddressMismatch => b"ERR_CUSTODIAN_CHANGE_MISMATCH",
+                Self::NotCouncil => b"ERR_NOT_CUSTODIAN_COUNCIL",
+                Self::ConfirmationRequired => b"ERR_CUSTODIAN_CHANGE_REQUIRES_CONFIRMATION",
+            }
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum FinishWithdrawError {
+        EventParseFailed(deposit_event::error::ParseError),
+        ProofUsed,
+        NoPendingWithdrawal,
+        WithdrawalMismatch,
+    }
+
+    impl From<ProofUsed> for FinishWithdrawError {
+        fn from(_: ProofUsed) -> Self {
+            Self::ProofUsed
+        }
+    }
+
+    impl AsRef<[u8]> for FinishWithdrawError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::EventParseFailed(e) => e.as_ref(),
+                Self::ProofUsed => PROOF_EXIST,
+                Self::NoPendingWithdrawal => b"ERR_NO_PENDING_WITHDRAWAL",
+                Self::WithdrawalMismatch => b"ERR_WITHDRAWAL_MISMATCH",
+            }
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum ForgetWithdrawalError {
+        NoPendingWithdrawal,
+        NotAllowed,
+    }
+
+    impl AsRef<[u8]> for ForgetWithdrawalError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::NoPendingWithdrawal => b"ERR_NO_PENDING_WITHDRAWAL",
+                Self::NotAllowed => b"ERR_NOT_ALLOWED",
+            }
+        }
+    }
+
     pub struct ProofUsed;
 
     impl AsRef<[u8]> for ProofUsed {
@@ -0,0 +1,282 @@
+//! Structured events of the ETH connector.
+//!
+//! The connector logs its flows as free-form text, which indexers can only follow with
+//! regular expressions. Each flow moving nETH or ETH also emits an event in the format of
+//! `events` (NEP-297, `aurora` standard):
+//!
+//! - `eth_deposit` when `finish_deposit` mints nETH for a deposit from Ethereum;
+//! - `eth_withdraw` when nETH is burned to be withdrawn to Ethereum, whether by a NEAR
+//!   account or by the exit to Ethereum precompile;
+//! - `eth_withdraw_finalized` when a proof that the custodian released the ETH of a
+//!   withdrawal is accepted by `finish_withdraw_with_proof`;
+//! - `eth_ft_transfer` when nETH moves between NEAR accounts (`ft_transfer` and
+//!   `ft_transfer_call`);
+//! - `eth_ft_on_transfer` when nETH transferred to the engine is minted as ETH on Aurora.
+//!
+//! Amounts are strings, like the other NEP-141 amounts, and EVM addresses are hex encoded.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::parameters::{PendingWithdrawal, WithdrawResult};
+use crate::prelude::{AccountId, BTreeMap, Balance, EthAddress, String, ToString};
+
+pub const ETH_DEPOSIT: &str = "eth_deposit";
+pub const ETH_WITHDRAW: &str = "eth_withdraw";
+pub const ETH_WITHDRAW_FINALIZED: &str = "eth_withdraw_finalized";
+pub const ETH_FT_TRANSFER: &str = "eth_ft_transfer";
+pub const ETH_FT_ON_TRANSFER: &str = "eth_ft_on_transfer";
+
+/// `amount` was minted to `owner_id` and `fee` to `relayer_id`.
+pub fn emit_deposit(owner_id: &AccountId, amount: Balance, fee: Balance, relayer_id: &AccountId) {
+    events::emit(ETH_DEPOSIT, deposit_data(owner_id, amount, fee, relayer_id));
+}
+
+/// The amount of the `result` was burned from `sender_id` to be released on Ethereum, and
+/// the `fee` kept by the engine. The event carries the whole `result`, which is what the
+/// custodian on Ethereum verifies, so that the claim can be built from the event alone.
+pub fn emit_withdraw(sender_id: &AccountId, result: &WithdrawResult, fee: Balance) {
+    events::emit(ETH_WITHDRAW, withdraw_data(sender_id, result, fee));
+}
+
+/// The custodian released on Ethereum the ETH of the `withdrawal`.
+pub fn emit_withdraw_finalized(withdrawal: &PendingWithdrawal) {
+    events::emit(ETH_WITHDRAW_FINALIZED, withdraw_finalized_data(withdrawal));
+}
+
+pub fn emit_ft_transfer(
+    sender_id: &AccountId,
+    receiver_id: &AccountId,
+    amount: Balance,
+    memo: Option<&String>,
+) {
+    events::emit(
+        ETH_FT_TRANSFER,
+        ft_transfer_data(sender_id, receiver_id, amount, memo),
+    );
+}
+
+/// `amount` was minted on Aurora to `recipient` and `fee` to `relayer`, if any.
+pub fn emit_ft_on_transfer(
+    sender_id: &AccountId,
+    recipient: &EthAddress,
+    amount: Balance,
+    fee: Balance,
+    relayer: Option<&EthAddress>,
+) {
+    events::emit(
+        ETH_FT_ON_TRANSFER,
+        ft_on_transfer_data(sender_id, recipient, amount, fee, relayer),
+    );
+}
+
+fn amount(amount: Balance) -> JsonValue {
+    JsonValue::String(amount.to_string())
+}
+
+fn deposit_data(
+    owner_id: &AccountId,
+    amount_minted: Balance,
+    fee: Balance,
+    relayer_id: &AccountId,
+) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "owner_id".to_string(),
+        JsonValue::String(owner_id.to_string()),
+    );
+    kvs.insert("amount".to_string(), amount(amount_minted));
+    kvs.insert("fee".to_string(), amount(fee));
+    kvs.insert(
+        "relayer_id".to_string(),
+        JsonValue::String(relayer_id.to_string()),
+    );
+    JsonValue::Object(kvs)
+}
+
+fn withdraw_data(sender_id: &AccountId, result: &WithdrawResult, fee: Balance) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "sender_id".to_string(),
+        JsonValue::String(sender_id.to_string()),
+    );
+    kvs.insert(
+        "recipient".to_string(),
+        JsonValue::String(hex::encode(result.recipient_id)),
+    );
+    kvs.insert("amount".to_string(), amount(result.amount));
+    kvs.insert("fee".to_string(), amount(fee));
+    kvs.insert(
+        "eth_custodian_address".to_string(),
+        JsonValue::String(hex::encode(result.eth_custodian_address)),
+    );
+    kvs.insert("nonce".to_string(), JsonValue::U64(result.nonce));
+    JsonValue::Object(kvs)
+}
+
+fn withdraw_finalized_data(withdrawal: &PendingWithdrawal) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "sender_id".to_string(),
+        JsonValue::String(withdrawal.sender_id.to_string()),
+    );
+    kvs.insert(
+        "recipient".to_string(),
+        JsonValue::String(hex::encode(withdrawal.recipient_id)),
+    );
+    kvs.insert("amount".to_string(), amount(withdrawal.amount));
+    kvs.insert(
+        "eth_custodian_address".to_string(),
+        JsonValue::String(hex::encode(withdrawal.eth_custodian_address)),
+    );
+    kvs.insert("nonce".to_string(), JsonValue::U64(withdrawal.nonce));
+    JsonValue::Object(kvs)
+}
+
+fn ft_transfer_data(
+    sender_id: &AccountId,
+    receiver_id: &AccountId,
+    amount_transferred: Balance,
+    memo: Option<&String>,
+) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "sender_id".to_string(),
+        JsonValue::String(sender_id.to_string()),
+    );
+    kvs.insert(
+        "receiver_id".to_string(),
+        JsonValue::String(receiver_id.to_string()),
+    );
+    kvs.insert("amount".to_string(), amount(amount_transferred));
+    if let Some(memo) = memo {
+        kvs.insert("memo".to_string(), JsonValue::String(memo.clone()));
+    }
+    JsonValue::Object(kvs)
+}
+
+fn ft_on_transfer_data(
+    sender_id: &AccountId,
+    recipient: &EthAddress,
+    amount_minted: Balance,
+    fee: Balance,
+    relayer: Option<&EthAddress>,
+) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "sender_id".to_string(),
+        JsonValue::String(sender_id.to_string()),
+    );
+    kvs.insert(
+        "recipient".to_string(),
+        JsonValue::String(hex::encode(recipient)),
+    );
+    kvs.insert("amount".to_string(), amount(amount_minted));
+    kvs.insert("fee".to_string(), amount(fee));
+    kvs.insert(
+        "relayer".to_string(),
+        relayer.map_or(JsonValue::Null, |relayer| {
+            JsonValue::String(hex::encode(relayer))
+        }),
+    );
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{event_log, EVENT_JSON_PREFIX};
+
+    fn parse(log: &str) -> serde_json::Value {
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_events() {
+        let alice = AccountId::new("alice.near").unwrap();
+        let relayer = AccountId::new("relayer.near").unwrap();
+        let value = parse(&event_log(
+            ETH_DEPOSIT,
+            deposit_data(&alice, u128::MAX, 10, &relayer),
+        ));
+        assert_eq!(value["standard"], "aurora");
+        assert_eq!(value["event"], "eth_deposit");
+        let data = &value["data"][0];
+        assert_eq!(data["owner_id"], "alice.near");
+        assert_eq!(data["amount"], u128::MAX.to_string());
+        assert_eq!(data["fee"], "10");
+        assert_eq!(data["relayer_id"], "relayer.near");
+
+        let value = parse(&event_log(
+            ETH_WITHDRAW,
+            withdraw_data(
+                &alice,
+                &WithdrawResult {
+                    amount: 90,
+                    recipient_id: EthAddress::new([0x11; 20]),
+                    eth_custodian_address: EthAddress::new([0x22; 20]),
+                    nonce: 7,
+                },
+                10,
+            ),
+        ));
+        assert_eq!(value["event"], "eth_withdraw");
+        let data = &value["data"][0];
+        assert_eq!(data["sender_id"], "alice.near");
+        assert_eq!(data["recipient"], hex::encode([0x11; 20]));
+        assert_eq!(data["amount"], "90");
+        assert_eq!(data["fee"], "10");
+        assert_eq!(data["eth_custodian_address"], hex::encode([0x22; 20]));
+        assert_eq!(data["nonce"], 7);
+
+        let value = parse(&event_log(
+            ETH_WITHDRAW_FINALIZED,
+            withdraw_finalized_data(&PendingWithdrawal {
+                nonce: 7,
+                sender_id: alice,
+                recipient_id: EthAddress::new([0x11; 20]),
+                amount: 90,
+                eth_custodian_address: EthAddress::new([0x22; 20]),
+            }),
+        ));
+        assert_eq!(value["event"], "eth_withdraw_finalized");
+        let data = &value["data"][0];
+        assert_eq!(data["sender_id"], "alice.near");
+        assert_eq!(data["recipient"], hex::encode([0x11; 20]));
+        assert_eq!(data["amount"], "90");
+        assert_eq!(data["nonce"], 7);
+    }
+
+    #[test]
+    fn test_transfer_events() {
+        let alice = AccountId::new("alice.near").unwrap();
+        let bob = AccountId::new("bob.near").unwrap();
+        let memo = "thanks".to_string();
+        let value = parse(&event_log(
+            ETH_FT_TRANSFER,
+            ft_transfer_data(&alice, &bob, 5, Some(&memo)),
+        ));
+        assert_eq!(value["event"], "eth_ft_transfer");
+        let data = &value["data"][0];
+        assert_eq!(data["sender_id"], "alice.near");
+        assert_eq!(data["receiver_id"], "bob.near");
+        assert_eq!(data["amount"], "5");
+        assert_eq!(data["memo"], "thanks");
+        let value = parse(&event_log(
+            ETH_FT_TRANSFER,
+            ft_transfer_data(&alice, &bob, 5, None),
+        ));
+        assert!(value["data"][0].get("memo").is_none());
+
+        let value = parse(&event_log(
+            ETH_FT_ON_TRANSFER,
+            ft_on_transfer_data(&alice, &EthAddress::new([0x22; 20]), 7, 0, None),
+        ));
+        assert_eq!(value["event"], "eth_ft_on_transfer");
+        let data = &value["data"][0];
+        assert_eq!(data["recipient"], hex::encode([0x22; 20]));
+        assert_eq!(data["amount"], "7");
+        assert_eq!(data["fee"], "0");
+        assert!(data["relayer"].is_null());
+    }
+}
@@ -0,0 +1,119 @@
+//! Gate on the deployment of contracts, for staged launches and emergency freezes.
+//!
+//! The owner sets the mode of the gate: `Open` (the default) lets anybody deploy,
+//! `AllowlistOnly` only the addresses the owner allowed, and `Closed` nobody. The gate
+//! applies to the deployments made directly by a NEAR transaction: the `deploy_code` method
+//! (the deployer is the address of the predecessor account) and the `submit` of a contract
+//! creation transaction (the deployer is its signer). Contracts created by other contracts,
+//! and the ERC-20 contracts of bridged tokens, are not gated.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::{
+    bytes_to_key, Address, BTreeMap, BorshDeserialize, BorshSerialize, EthAddress, KeyPrefix,
+    ToString, Vec,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const DEPLOY_MODE_KEY: &[u8; 11] = b"DEPLOY_MODE";
+const DEPLOY_ALLOWED_KEY: &[u8; 14] = b"DEPLOY_ALLOWED";
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployMode {
+    Open,
+    AllowlistOnly,
+    Closed,
+}
+
+impl Default for DeployMode {
+    fn default() -> Self {
+        Self::Open
+    }
+}
+
+impl DeployMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::AllowlistOnly => "allowlist_only",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployGateError {
+    Closed,
+    NotAllowed,
+}
+
+impl AsRef<[u8]> for DeployGateError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Closed => b"ERR_DEPLOY_CLOSED",
+            Self::NotAllowed => b"ERR_DEPLOYER_NOT_ALLOWED",
+        }
+    }
+}
+
+fn allowed_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[DEPLOY_ALLOWED_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+pub fn get_mode<I: IO>(io: &I) -> DeployMode {
+    io.read_storage(&bytes_to_key(KeyPrefix::Config, DEPLOY_MODE_KEY))
+        .and_then(|bytes| DeployMode::try_from_slice(&bytes.to_vec()).ok())
+        .unwrap_or_default()
+}
+
+pub fn set_mode<I: IO>(io: &mut I, mode: DeployMode) {
+    let previous = get_mode(io);
+    io.write_borsh(&bytes_to_key(KeyPrefix::Config, DEPLOY_MODE_KEY), &mode);
+    events::emit_config_changed(
+        "deploy_mode",
+        JsonValue::String(previous.as_str().to_string()),
+        JsonValue::String(mode.as_str().to_string()),
+    );
+}
+
+/// Whether the address is on the allowlist, whatever the mode.
+pub fn is_allowed<I: IO>(io: &I, address: &Address) -> bool {
+    io.storage_has_key(&allowed_key(address))
+}
+
+pub fn set_allowed<I: IO>(io: &mut I, address: &Address, allowed: bool) {
+    let previous = is_allowed(io, address);
+    if allowed {
+        io.write_storage(&allowed_key(address), &[1]);
+    } else {
+        io.remove_storage(&allowed_key(address));
+    }
+    events::emit_config_changed(
+        "deploy_allowed",
+        allowed_data(address, previous),
+        allowed_data(address, allowed),
+    );
+}
+
+fn allowed_data(address: &Address, allowed: bool) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "address".to_string(),
+        JsonValue::String(EthAddress::from(*address).encode()),
+    );
+    kvs.insert("allowed".to_string(), JsonValue::Bool(allowed));
+    JsonValue::Object(kvs)
+}
+
+/// Checks that the gate lets `deployer` deploy a contract.
+pub fn check<I: IO>(io: &I, deployer: &Address) -> Result<(), DeployGateError> {
+    match get_mode(io) {
+        DeployMode::Open => Ok(()),
+        DeployMode::AllowlistOnly if is_allowed(io, deployer) => Ok(()),
+        DeployMode::AllowlistOnly => Err(DeployGateError::NotAllowed),
+        DeployMode::Closed => Err(DeployGateError::Closed),
+    }
+}
@@ -1,15 +1,35 @@
 use crate::deposit_event::error::ParseEventMessageError;
 use crate::log_entry::LogEntry;
-use crate::prelude::account_id::AccountId;
+use crate::prelude::account_id::{AccountId, MAX_ACCOUNT_ID_LEN};
 use crate::prelude::{
-    validate_eth_address, vec, AddressValidationError, Balance, BorshDeserialize, BorshSerialize,
-    EthAddress, Fee, String, ToString, TryFrom, TryInto, Vec, U256,
+    codec, validate_eth_address, vec, AddressValidationError, Balance, BorshDeserialize,
+    BorshSerialize, EthAddress, Fee, RawH256, String, ToString, TryFrom, TryInto, Vec, U256,
 };
 use byte_slice_cast::AsByteSlice;
 use ethabi::{Event, EventParam, Hash, Log, ParamType, RawLog};
 
 pub const DEPOSITED_EVENT: &str = "Deposited";
 
+/// Topic of the `Deposited` event: `keccak("Deposited(address,string,uint256,uint256)")`
+/// (see tests::test_deposited_event_topic).
+pub const DEPOSITED_EVENT_TOPIC: RawH256 = [
+    0xd1, 0x42, 0x43, 0x9c, 0x27, 0x8e, 0x25, 0xda, 0xd9, 0xa5, 0x07, 0x66, 0xf1, 0x53, 0xd0, 0xe3,
+    0xd2, 0xd7, 0xbf, 0x2b, 0xd1, 0x6f, 0xc2, 0x78, 0x1c, 0x4b, 0xd4, 0x94, 0xb2, 0xb1, 0x5a, 0x9d,
+];
+
+pub const WITHDRAWN_EVENT: &str = "Withdrawn";
+
+/// Topic of the `Withdrawn` event of the custodian: `keccak("Withdrawn(address,uint128)")`
+/// (see tests::test_withdrawn_event_topic).
+pub const WITHDRAWN_EVENT_TOPIC: RawH256 = [
+    0xab, 0x48, 0xb3, 0xd5, 0x9a, 0x24, 0x01, 0x96, 0xdc, 0x5b, 0xdd, 0x7f, 0x7a, 0x63, 0x8f, 0xca,
+    0x31, 0x0f, 0x81, 0x94, 0xc7, 0xd3, 0x50, 0xc3, 0xdd, 0x77, 0x65, 0x86, 0x13, 0x11, 0xdd, 0xf8,
+];
+
+/// Maximum length in bytes of the `recipient` field of the `Deposited` event:
+/// a NEAR account id, the `:` separator and a `0x`-prefixed Eth address.
+pub const MAX_EVENT_MESSAGE_LENGTH: usize = MAX_ACCOUNT_ID_LEN + 1 + 42;
+
 pub type EventParams = Vec<EventParam>;
 
 /// On-transfer message. Used for `ft_transfer_call` and  `ft_on_transfer` functions.
@@ -40,8 +60,10 @@ impl FtTransferMessageData {
             .map_err(|_| error::ParseOnTransferMessageError::InvalidAccount)?;
 
         // Decode message array from 2-th element of data array
-        let msg =
-            hex::decode(data[1]).map_err(|_| error::ParseOnTransferMessageError::InvalidHexData)?;
+        let msg = codec::hex_decode(data[1], 52).map_err(|e| match e {
+            codec::DecodeError::TooLarge => error::ParseOnTransferMessageError::WrongMessageFormat,
+            _ => error::ParseOnTransferMessageError::InvalidHexData,
+        })?;
         // Length = fee[32] + eth_address[20] bytes
         if msg.len() != 52 {
             return Err(error::ParseOnTransferMessageError::WrongMessageFormat);
@@ -58,8 +80,8 @@ impl FtTransferMessageData {
         let fee: Fee = fee_u128.into();
 
         // Get recipient Eth address from message slice
-        let mut recipient: EthAddress = Default::default();
-        recipient.copy_from_slice(&msg[32..52]);
+        let recipient = EthAddress::try_from_bytes(&msg[32..52])
+            .ok_or(error::ParseOnTransferMessageError::WrongMessageFormat)?;
 
         Ok(FtTransferMessageData {
             relayer: account_id,
@@ -75,7 +97,7 @@ impl FtTransferMessageData {
         // it means 32 bytes for fee data
         let mut data = U256::from(self.fee.into_u128()).as_byte_slice().to_vec();
         // Second data section should contain Eth address
-        data.extend(self.recipient);
+        data.extend_from_slice(self.recipient.as_bytes());
         // Add `:` separator between relayer_id and data message
         [self.relayer.as_ref(), &hex::encode(data)].join(":")
     }
@@ -105,7 +127,7 @@ impl FtTransferMessageData {
         let recipient_address = validate_eth_address(address)
             .map_err(ParseEventMessageError::EthAddressValidationError)?;
         // Second data section should contain Eth address
-        data.extend(recipient_address);
+        data.extend_from_slice(recipient_address.as_bytes());
         // Add `:` separator between relayer_id and data message
         //Ok([relayer_account_id.as_ref(), &hex::encode(data)].join(":"))
         Ok(Self {
@@ -142,6 +164,7 @@ impl TokenMessageData {
         message: &str,
         fee: Fee,
     ) -> Result<TokenMessageData, error::ParseEventMessageError> {
+        Self::validate_event_message(message)?;
         let data: Vec<_> = message.split(':').collect();
         // Data array can contain 1 or 2 elements
         if data.len() >= 3 {
@@ -168,6 +191,22 @@ impl TokenMessageData {
         }
     }
 
+    /// The message comes straight from an Ethereum event, so it is checked before being
+    /// split. ethabi decodes the event strings lossily, replacing invalid UTF-8 sequences
+    /// with `U+FFFD`, which is why that character is treated as invalid UTF-8.
+    fn validate_event_message(message: &str) -> Result<(), error::ParseEventMessageError> {
+        if message.len() > MAX_EVENT_MESSAGE_LENGTH {
+            return Err(error::ParseEventMessageError::MessageTooLong);
+        }
+        if message.contains(char::REPLACEMENT_CHARACTER) {
+            return Err(error::ParseEventMessageError::InvalidUtf8);
+        }
+        if message.chars().any(char::is_control) {
+            return Err(error::ParseEventMessageError::ControlCharacter);
+        }
+        Ok(())
+    }
+
     // Get recipient account id from Eth part of Token message data
     pub fn get_recipient(&self) -> AccountId {
         match self {
@@ -188,23 +227,44 @@ pub struct EthEvent {
 
 #[allow(dead_code)]
 impl EthEvent {
-    /// Get Ethereum event from `log_entry_data`
+    /// Get Ethereum event from `log_entry_data`. `topic` is the hash of the signature of
+    /// the event, which is given rather than computed on each call.
     pub fn fetch_log_entry_data(
         name: &str,
+        topic: RawH256,
         params: EventParams,
         data: &[u8],
     ) -> Result<Self, error::DecodeError> {
+        // Parsed as an anonymous event once its topic is checked, so that `ethabi` does not
+        // hash the signature again.
         let event = Event {
             name: name.to_string(),
             inputs: params,
-            anonymous: false,
+            anonymous: true,
         };
-        let log_entry: LogEntry = rlp::decode(data).map_err(|_| error::DecodeError::RlpFailed)?;
-        let eth_custodian_address = log_entry.address.0;
-        let topics = log_entry.topics.iter().map(|h| Hash::from(h.0)).collect();
+        let log_entry: LogEntry = codec::rlp_decode(data, codec::MAX_INPUT_SIZE)
+            .map_err(|_| error::DecodeError::RlpFailed)?;
+        let eth_custodian_address = EthAddress::new(log_entry.address.0);
+        let mut topics = log_entry.topics.iter().map(|h| Hash::from(h.0));
+        if topics.next() != Some(Hash::from(topic)) {
+            return Err(error::DecodeError::SchemaMismatch);
+        }
+
+        // The offsets and lengths of the dynamic values are checked first, see
+        // `codec::abi_check_dynamic`.
+        let dynamic_heads = event
+            .inputs
+            .iter()
+            .filter(|param| !param.indexed)
+            .enumerate()
+            .filter(|(_, param)| matches!(param.kind, ParamType::String | ParamType::Bytes));
+        for (index, _) in dynamic_heads {
+            codec::abi_check_dynamic(&log_entry.data, 32 * index)
+                .map_err(error::DecodeError::Abi)?;
+        }
 
         let raw_log = RawLog {
-            topics,
+            topics: topics.collect(),
             data: log_entry.data,
         };
         let log = event
@@ -256,14 +316,21 @@ impl DepositedEvent {
 
     /// Parses raw Ethereum logs proof's entry data
     pub fn from_log_entry_data(data: &[u8]) -> Result<Self, error::ParseError> {
-        let event = EthEvent::fetch_log_entry_data(DEPOSITED_EVENT, Self::event_params(), data)
-            .map_err(error::ParseError::LogParseFailed)?;
-        let sender = event.log.params[0]
-            .value
-            .clone()
-            .into_address()
-            .ok_or(error::ParseError::InvalidSender)?
-            .0;
+        let event = EthEvent::fetch_log_entry_data(
+            DEPOSITED_EVENT,
+            DEPOSITED_EVENT_TOPIC,
+            Self::event_params(),
+            data,
+        )
+        .map_err(error::ParseError::LogParseFailed)?;
+        let sender = EthAddress::new(
+            event.log.params[0]
+                .value
+                .clone()
+                .into_address()
+                .ok_or(error::ParseError::InvalidSender)?
+                .0,
+        );
 
         // parse_event_message
         let event_message_data: String = event.log.params[1].value.clone().to_string();
@@ -300,6 +367,63 @@ impl DepositedEvent {
     }
 }
 
+/// Data that was emitted by the `Withdrawn` event of the custodian, once it released the ETH
+/// of a withdrawal.
+pub struct WithdrawnEvent {
+    pub eth_custodian_address: EthAddress,
+    pub recipient: EthAddress,
+    pub amount: Balance,
+}
+
+impl WithdrawnEvent {
+    pub fn event_params() -> EventParams {
+        vec![
+            EventParam {
+                name: "recipient".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "amount".to_string(),
+                kind: ParamType::Uint(128),
+                indexed: false,
+            },
+        ]
+    }
+
+    /// Parses raw Ethereum logs proof's entry data
+    pub fn from_log_entry_data(data: &[u8]) -> Result<Self, error::ParseError> {
+        let event = EthEvent::fetch_log_entry_data(
+            WITHDRAWN_EVENT,
+            WITHDRAWN_EVENT_TOPIC,
+            Self::event_params(),
+            data,
+        )
+        .map_err(error::ParseError::LogParseFailed)?;
+        let recipient = EthAddress::new(
+            event.log.params[0]
+                .value
+                .clone()
+                .into_address()
+                .ok_or(error::ParseError::InvalidRecipient)?
+                .0,
+        );
+        let amount: u128 = event.log.params[1]
+            .value
+            .clone()
+            .into_uint()
+            .ok_or(error::ParseError::InvalidAmount)?
+            .try_into()
+            .map_err(|_| error::ParseError::OverflowNumber)?;
+
+        Ok(Self {
+            eth_custodian_address: event.eth_custodian_address,
+            recipient,
+            amount,
+        })
+    }
+}
+
 pub mod error {
     use super::*;
 
@@ -307,12 +431,14 @@ pub mod error {
     pub enum DecodeError {
         RlpFailed,
         SchemaMismatch,
+        Abi(codec::DecodeError),
     }
     impl AsRef<[u8]> for DecodeError {
         fn as_ref(&self) -> &[u8] {
             match self {
                 Self::RlpFailed => b"ERR_RLP_FAILED",
                 Self::SchemaMismatch => b"ERR_PARSE_DEPOSIT_EVENT",
+                Self::Abi(e) => e.as_ref(),
             }
         }
     }
@@ -320,6 +446,9 @@ pub mod error {
     #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
     pub enum ParseEventMessageError {
         TooManyParts,
+        MessageTooLong,
+        InvalidUtf8,
+        ControlCharacter,
         InvalidAccount,
         EthAddressValidationError(AddressValidationError),
         ParseMessageError(ParseOnTransferMessageError),
@@ -329,6 +458,9 @@ pub mod error {
         fn as_ref(&self) -> &[u8] {
             match self {
                 Self::TooManyParts => b"ERR_INVALID_EVENT_MESSAGE_FORMAT",
+                Self::MessageTooLong => b"ERR_EVENT_MESSAGE_TOO_LONG",
+                Self::InvalidUtf8 => b"ERR_EVENT_MESSAGE_INVALID_UTF8",
+                Self::ControlCharacter => b"ERR_EVENT_MESSAGE_CONTROL_CHARACTER",
                 Self::InvalidAccount => b"ERR_INVALID_ACCOUNT_ID",
                 Self::EthAddressValidationError(e) => e.as_ref(),
                 Self::ParseMessageError(e) => e.as_ref(),
@@ -346,6 +478,7 @@ pub mod error {
     pub enum ParseError {
         LogParseFailed(DecodeError),
         InvalidSender,
+        InvalidRecipient,
         InvalidAmount,
         InvalidFee,
         MessageParseFailed(ParseEventMessageError),
@@ -356,6 +489,7 @@ pub mod error {
             match self {
                 Self::LogParseFailed(e) => e.as_ref(),
                 Self::InvalidSender => b"ERR_INVALID_SENDER",
+                Self::InvalidRecipient => b"ERR_INVALID_RECIPIENT",
                 Self::InvalidAmount => b"ERR_INVALID_AMOUNT",
                 Self::InvalidFee => b"ERR_INVALID_FEE",
                 Self::MessageParseFailed(e) => e.as_ref(),
@@ -385,3 +519,198 @@ pub mod error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::format;
+
+    #[test]
+    fn test_deposited_event_topic() {
+        let event = Event {
+            name: DEPOSITED_EVENT.to_string(),
+            inputs: DepositedEvent::event_params(),
+            anonymous: false,
+        };
+        assert_eq!(event.signature(), Hash::from(DEPOSITED_EVENT_TOPIC));
+    }
+
+    #[test]
+    fn test_withdrawn_event_topic() {
+        let event = Event {
+            name: WITHDRAWN_EVENT.to_string(),
+            inputs: WithdrawnEvent::event_params(),
+            anonymous: false,
+        };
+        assert_eq!(event.signature(), Hash::from(WITHDRAWN_EVENT_TOPIC));
+    }
+
+    fn parse(message: &str) -> Result<TokenMessageData, error::ParseEventMessageError> {
+        TokenMessageData::parse_event_message_and_prepare_token_message_data(message, 0.into())
+    }
+
+    #[test]
+    fn test_event_message_validation() {
+        let address = hex::encode([0x0a; 20]);
+        let longest_account = "a".repeat(MAX_ACCOUNT_ID_LEN);
+        let longest_message = format!("{}:0x{}", longest_account, address);
+        assert_eq!(longest_message.len(), MAX_EVENT_MESSAGE_LENGTH);
+        assert!(parse(&longest_message).is_ok());
+        assert!(parse("aurora").is_ok());
+
+        let too_long = format!("{}a:0x{}", longest_account, address);
+        assert!(matches!(
+            parse(&too_long),
+            Err(error::ParseEventMessageError::MessageTooLong)
+        ));
+
+        let lossy = String::from_utf8_lossy(b"aurora:\xff\xfe").into_owned();
+        assert!(matches!(
+            parse(&lossy),
+            Err(error::ParseEventMessageError::InvalidUtf8)
+        ));
+
+        let control = format!("aurora\n:{}", address);
+        assert!(matches!(
+            parse(&control),
+            Err(error::ParseEventMessageError::ControlCharacter)
+        ));
+        assert!(matches!(
+            parse("aurora\0"),
+            Err(error::ParseEventMessageError::ControlCharacter)
+        ));
+    }
+
+    #[test]
+    fn test_event_message_parse_failures() {
+        let address = hex::encode([0x0a; 20]);
+        assert!(matches!(
+            parse(&format!("aurora:0x{}", address)),
+            Ok(TokenMessageData::Eth { .. })
+        ));
+        assert!(matches!(
+            parse(&format!("aurora:{}", address)),
+            Ok(TokenMessageData::Eth { .. })
+        ));
+
+        assert!(matches!(
+            parse(&format!("aurora:{}:extra", address)),
+            Err(error::ParseEventMessageError::TooManyParts)
+        ));
+        assert!(matches!(
+            parse("Aurora"),
+            Err(error::ParseEventMessageError::InvalidAccount)
+        ));
+        assert!(matches!(
+            parse(&format!(":{}", address)),
+            Err(error::ParseEventMessageError::InvalidAccount)
+        ));
+
+        let invalid_address = |message: &str| match parse(message) {
+            Err(error::ParseEventMessageError::EthAddressValidationError(e)) => Some(e),
+            _ => None,
+        };
+        // 42 characters, but no `0x` prefix.
+        assert!(matches!(
+            invalid_address(&format!("aurora:zz{}", address)),
+            Some(AddressValidationError::FailedDecodeHex)
+        ));
+        assert!(matches!(
+            invalid_address(&format!("aurora:0x{}", "zz".repeat(20))),
+            Some(AddressValidationError::FailedDecodeHex)
+        ));
+        assert!(matches!(
+            invalid_address(&format!("aurora:{}0", address)),
+            Some(AddressValidationError::FailedDecodeHex)
+        ));
+        assert!(matches!(
+            invalid_address(&format!("aurora:{}0000", address)),
+            Some(AddressValidationError::IncorrectLength)
+        ));
+        assert!(matches!(
+            invalid_address(&format!("aurora:{}", &address[2..])),
+            Some(AddressValidationError::IncorrectLength)
+        ));
+        assert!(matches!(
+            invalid_address("aurora:"),
+            Some(AddressValidationError::IncorrectLength)
+        ));
+    }
+
+    #[test]
+    fn test_on_transfer_message_parse_failures() {
+        let message = FtTransferMessageData {
+            relayer: "relayer.near".parse().unwrap(),
+            recipient: EthAddress::new([0x0a; 20]),
+            fee: 7.into(),
+        };
+        let encoded = message.encode();
+        let parsed = FtTransferMessageData::parse_on_transfer_message(&encoded).unwrap();
+        assert_eq!(parsed.relayer, message.relayer);
+        assert_eq!(parsed.recipient, message.recipient);
+        assert_eq!(parsed.fee, message.fee);
+
+        let parse = |message: &str| FtTransferMessageData::parse_on_transfer_message(message);
+        let data = &encoded["relayer.near:".len()..];
+        assert!(matches!(
+            parse(data),
+            Err(error::ParseOnTransferMessageError::TooManyParts)
+        ));
+        assert!(matches!(
+            parse(&format!("{}:", encoded)),
+            Err(error::ParseOnTransferMessageError::TooManyParts)
+        ));
+        assert!(matches!(
+            parse(&format!("Relayer:{}", data)),
+            Err(error::ParseOnTransferMessageError::InvalidAccount)
+        ));
+        assert!(matches!(
+            parse(&format!("relayer.near:zz{}", &data[2..])),
+            Err(error::ParseOnTransferMessageError::InvalidHexData)
+        ));
+        assert!(matches!(
+            parse(&format!("relayer.near:{}0", data)),
+            Err(error::ParseOnTransferMessageError::InvalidHexData)
+        ));
+        assert!(matches!(
+            parse(&format!("relayer.near:{}00", data)),
+            Err(error::ParseOnTransferMessageError::WrongMessageFormat)
+        ));
+        assert!(matches!(
+            parse(&format!("relayer.near:{}", &data[2..])),
+            Err(error::ParseOnTransferMessageError::WrongMessageFormat)
+        ));
+        // The fee is little endian: setting its last byte makes it exceed `u128`.
+        let overflowing_fee = format!("{}01{}", &data[..62], &data[64..]);
+        assert!(matches!(
+            parse(&format!("relayer.near:{}", overflowing_fee)),
+            Err(error::ParseOnTransferMessageError::OverflowNumber)
+        ));
+    }
+
+    #[test]
+    fn test_deposited_event_with_out_of_bounds_recipient() {
+        let word = |value: u64| {
+            let mut word = [0u8; 32];
+            word[24..].copy_from_slice(&value.to_be_bytes());
+            word.to_vec()
+        };
+        // The length of the recipient would overflow `usize` on wasm once added to its offset.
+        let data = [word(96), word(1), word(0), word(u32::MAX as u64)].concat();
+        let log_entry = crate::log_entry::LogEntry {
+            address: Default::default(),
+            topics: vec![
+                crate::prelude::H256(DEPOSITED_EVENT_TOPIC),
+                Default::default(),
+            ],
+            data,
+        };
+        let result = DepositedEvent::from_log_entry_data(&rlp::encode(&log_entry));
+        assert!(matches!(
+            result,
+            Err(error::ParseError::LogParseFailed(error::DecodeError::Abi(
+                codec::DecodeError::TooLarge
+            )))
+        ));
+    }
+}
@@ -1,8 +1,11 @@
-use crate::parameters::{CallArgs, NEP141FtOnTransferArgs, ResultLog, SubmitResult, ViewCallArgs};
+use crate::parameters::{
+    CallArgs, GasEstimate, NEP141FtOnTransferArgs, PromiseStats, ResultLog, SubmitResult,
+    ViewCallArgs, ViewTransactionArgs,
+};
 use core::mem;
 use evm::backend::{Apply, ApplyBackend, Backend, Basic, Log};
 use evm::executor;
-use evm::{Config, CreateScheme, ExitError, ExitFatal, ExitReason};
+use evm::{CreateScheme, ExitError, ExitFatal, ExitReason};
 
 use crate::connector::EthConnectorContract;
 use crate::map::BijectionMap;
@@ -10,16 +13,35 @@ use aurora_engine_sdk::env::Env;
 use aurora_engine_sdk::io::{StorageIntermediate, IO};
 use aurora_engine_sdk::promise::{PromiseHandler, PromiseId};
 
-use crate::parameters::{DeployErc20TokenArgs, NewCallArgs, TransactionStatus};
+use crate::code_dedup;
+use crate::compliance_hook::{self, ComplianceError};
+use crate::deploy_gate::{self, DeployGateError};
+use crate::era::{self, EngineEra};
+use crate::idempotency::{self, IdempotencyError};
+use crate::log_limits::{self, LogLimitError};
+use crate::min_gas_price::{self, MinGasPriceError};
+use crate::parameters::{
+    DeployErc20Result, DeployErc20TokenWithTemplateArgs, NewCallArgs, TransactionStatus,
+};
+use crate::prelude::precompiles::exit_nft::ExitNftToNear;
 use crate::prelude::precompiles::native::{ExitToEthereum, ExitToNear};
 use crate::prelude::precompiles::Precompiles;
 use crate::prelude::{
-    address_to_key, bytes_to_key, sdk, storage_to_key, u256_to_arr, vec, AccountId, Address,
-    BorshDeserialize, BorshSerialize, KeyPrefix, PromiseArgs, PromiseCreateArgs, ToString, TryFrom,
-    TryInto, Vec, Wei, ERC20_MINT_SELECTOR, H256, U256,
+    address_to_key, bytes_to_key, codec, is_sharded_generation, sdk, storage_to_key, u256_to_arr,
+    vec, AccountId, Address, Balance, BorshDeserialize, BorshSerialize, Fee, KeyPrefix,
+    PromiseArgs, PromiseCreateArgs, PromiseResult, Rc, ToString, TryFrom, TryInto, Vec, Wei, H256,
+    U256,
 };
+use crate::promise_budget::{self, PromiseBudget, PromiseBudgetError};
+use crate::promise_gas;
+use crate::storage_sharding;
+use crate::token_templates::{self, TokenTemplateError};
 use crate::transaction::{EthTransactionKind, NormalizedEthTransaction};
-use aurora_engine_precompiles::PrecompileConstructorContext;
+use crate::xcc::{self, XccError};
+use aurora_engine_precompiles::{
+    state::{PrecompileState, StorageState},
+    BlockContext, PrecompileConstructorContext,
+};
 
 /// Used as the first byte in the concatenation of data used to compute the blockhash.
 /// Could be useful in the future as a version byte, or to distinguish different types of blocks.
@@ -89,6 +111,21 @@ pub enum EngineErrorKind {
     MaxPriorityGasFeeTooLarge,
     GasPayment(GasPaymentError),
     GasOverflow,
+    PromiseBudget(PromiseBudgetError),
+    /// The sender of the transaction has deployed code (EIP-3607).
+    SenderHasCode,
+    /// A compliance hook blocked the withdrawal of a bridged token.
+    Compliance(ComplianceError),
+    /// The gas price is below the minimum and the free tier does not cover the transaction.
+    MinGasPrice(MinGasPriceError),
+    /// The logs of the transaction exceed the limits of `log_limits`.
+    LogLimit(LogLimitError),
+    /// The deployment gate does not let the sender deploy contracts.
+    DeployGate(DeployGateError),
+    /// A cross-contract call of the transaction cannot be scheduled, see `xcc`.
+    Xcc(XccError),
+    /// The recorded outcome of a call with an idempotency key cannot be read.
+    Idempotency(IdempotencyError),
 }
 
 impl EngineErrorKind {
@@ -127,6 +164,14 @@ impl EngineErrorKind {
             MaxPriorityGasFeeTooLarge => b"ERR_MAX_PRIORITY_FEE_GREATER",
             GasPayment(e) => e.as_ref(),
             GasOverflow => b"ERR_GAS_OVERFLOW",
+            PromiseBudget(e) => e.as_ref(),
+            SenderHasCode => b"ERR_SENDER_HAS_CODE",
+            Compliance(e) => e.as_ref(),
+            MinGasPrice(e) => e.as_ref(),
+            LogLimit(e) => e.as_ref(),
+            DeployGate(e) => e.as_ref(),
+            Xcc(e) => e.as_ref(),
+            Idempotency(e) => e.as_ref(),
         }
     }
 }
@@ -149,29 +194,68 @@ impl From<ExitFatal> for EngineErrorKind {
     }
 }
 
-/// An engine result.
-pub type EngineResult<T> = Result<T, EngineError>;
+pub use crate::error::EngineResult;
 
 trait ExitIntoResult {
     /// Checks if the EVM exit is ok or an error.
     fn into_result(self, data: Vec<u8>) -> Result<TransactionStatus, EngineErrorKind>;
 }
 
+/// Exceptional halts of the interpreter (stack and memory limits, invalid jumps, etc.)
+/// are part of the transaction outcome: they consume the gas of the failing frame and the
+/// transaction is committed (nonce and gas payment included), like on Ethereum. Only the
+/// errors the interpreter cannot attribute to the executed code abort the NEAR call.
 impl ExitIntoResult for ExitReason {
     fn into_result(self, data: Vec<u8>) -> Result<TransactionStatus, EngineErrorKind> {
         use ExitReason::*;
         match self {
             Succeed(_) => Ok(TransactionStatus::Succeed(data)),
             Revert(_) => Ok(TransactionStatus::Revert(data)),
-            Error(ExitError::OutOfOffset) => Ok(TransactionStatus::OutOfOffset),
-            Error(ExitError::OutOfFund) => Ok(TransactionStatus::OutOfFund),
-            Error(ExitError::OutOfGas) => Ok(TransactionStatus::OutOfGas),
-            Error(e) => Err(e.into()),
+            Error(e) => exit_error_status(&e).ok_or_else(|| e.into()),
             Fatal(e) => Err(e.into()),
         }
     }
 }
 
+fn exit_error_status(e: &ExitError) -> Option<TransactionStatus> {
+    let status = match e {
+        ExitError::OutOfOffset => TransactionStatus::OutOfOffset,
+        ExitError::OutOfFund => TransactionStatus::OutOfFund,
+        ExitError::OutOfGas => TransactionStatus::OutOfGas,
+        ExitError::CallTooDeep => TransactionStatus::CallTooDeep,
+        ExitError::StackUnderflow => TransactionStatus::StackUnderflow,
+        ExitError::StackOverflow => TransactionStatus::StackOverflow,
+        ExitError::InvalidJump => TransactionStatus::InvalidJump,
+        ExitError::InvalidRange => TransactionStatus::InvalidRange,
+        ExitError::DesignatedInvalid => TransactionStatus::DesignatedInvalid,
+        ExitError::CreateCollision => TransactionStatus::CreateCollision,
+        ExitError::CreateContractLimit => TransactionStatus::CreateContractLimit,
+        _ => return None,
+    };
+    Some(status)
+}
+
+/// Inverse of `exit_error_status`.
+fn status_exit_error(status: &TransactionStatus) -> Option<ExitError> {
+    let e = match status {
+        TransactionStatus::Succeed(_)
+        | TransactionStatus::Revert(_)
+        | TransactionStatus::ReturnDataTooLarge { .. } => return None,
+        TransactionStatus::OutOfOffset => ExitError::OutOfOffset,
+        TransactionStatus::OutOfFund => ExitError::OutOfFund,
+        TransactionStatus::OutOfGas => ExitError::OutOfGas,
+        TransactionStatus::CallTooDeep => ExitError::CallTooDeep,
+        TransactionStatus::StackUnderflow => ExitError::StackUnderflow,
+        TransactionStatus::StackOverflow => ExitError::StackOverflow,
+        TransactionStatus::InvalidJump => ExitError::InvalidJump,
+        TransactionStatus::InvalidRange => ExitError::InvalidRange,
+        TransactionStatus::DesignatedInvalid => ExitError::DesignatedInvalid,
+        TransactionStatus::CreateCollision => ExitError::CreateCollision,
+        TransactionStatus::CreateContractLimit => ExitError::CreateContractLimit,
+    };
+    Some(e)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct BalanceOverflow;
 
@@ -214,6 +298,7 @@ pub enum DeployErc20Error {
     Failed(TransactionStatus),
     Engine(EngineError),
     Register(RegisterTokenError),
+    Template(TokenTemplateError),
 }
 impl AsRef<[u8]> for DeployErc20Error {
     fn as_ref(&self) -> &[u8] {
@@ -222,6 +307,7 @@ impl AsRef<[u8]> for DeployErc20Error {
             Self::Failed(e) => e.as_ref(),
             Self::Engine(e) => e.as_ref(),
             Self::Register(e) => e.as_ref(),
+            Self::Template(e) => e.as_ref(),
         }
     }
 }
@@ -329,34 +415,79 @@ impl AsRef<[u8]> for EngineStateError {
     }
 }
 
-struct StackExecutorParams {
-    precompiles: Precompiles,
+struct StackExecutorParams<'a> {
+    era: EngineEra,
+    precompiles: Precompiles<'a>,
     gas_limit: u64,
 }
 
-impl StackExecutorParams {
-    fn new(gas_limit: u64, current_account_id: AccountId, random_seed: H256) -> Self {
+/// The precompiles of the EVM, extensions included.
+#[cfg_attr(
+    not(feature = "precompile-example"),
+    allow(unused_mut, clippy::redundant_clone)
+)]
+pub(crate) fn precompiles<'a, I: IO + Copy + 'a, E: Env>(
+    era: EngineEra,
+    io: I,
+    current_account_id: AccountId,
+    env: &E,
+    promise_results: Vec<PromiseResult>,
+) -> Precompiles<'a> {
+    let state: Rc<dyn PrecompileState + 'a> = Rc::new(StorageState(io));
+    let ctx = PrecompileConstructorContext {
+        current_account_id,
+        block: BlockContext {
+            height: env.block_height(),
+            timestamp: env.block_timestamp().nanos(),
+            random_seed: env.random_seed(),
+        },
+        predecessor_account_id: env.predecessor_account_id(),
+        promise_results,
+        state,
+    };
+    let mut precompiles = era.precompiles(ctx.clone());
+    // Extension precompiles, compiled in with feature flags.
+    #[cfg(feature = "precompile-example")]
+    precompiles.register_extension(aurora_engine_precompile_example::Echo, &ctx);
+    precompiles
+}
+
+/// Results of the promises the running method is the callback of.
+fn promise_results<P: PromiseHandler>(handler: &P) -> Vec<PromiseResult> {
+    (0..handler.promise_results_count())
+        .filter_map(|index| handler.promise_result(index))
+        .collect()
+}
+
+impl<'a> StackExecutorParams<'a> {
+    fn new<I: IO + Copy + 'a, E: Env>(
+        era: EngineEra,
+        io: I,
+        gas_limit: u64,
+        current_account_id: AccountId,
+        env: &E,
+        promise_results: Vec<PromiseResult>,
+    ) -> Self {
         Self {
-            precompiles: Precompiles::new_london(PrecompileConstructorContext {
-                current_account_id,
-                random_seed,
-            }),
+            era,
+            precompiles: precompiles(era, io, current_account_id, env, promise_results),
             gas_limit,
         }
     }
 
-    fn make_executor<'a, 'env, I: IO + Copy, E: Env>(
-        &'a self,
-        engine: &'a Engine<'env, I, E>,
+    fn make_executor<'b, 'env, I: IO + Copy, E: Env>(
+        &'b self,
+        engine: &'b Engine<'env, I, E>,
     ) -> executor::StackExecutor<
         'static,
-        'a,
+        'b,
         executor::MemoryStackState<Engine<'env, I, E>>,
-        Precompiles,
+        Precompiles<'a>,
     > {
-        let metadata = executor::StackSubstateMetadata::new(self.gas_limit, CONFIG);
+        let config = self.era.config();
+        let metadata = executor::StackSubstateMetadata::new(self.gas_limit, config);
         let state = executor::MemoryStackState::new(metadata, engine);
-        executor::StackExecutor::new_with_precompiles(state, CONFIG, &self.precompiles)
+        executor::StackExecutor::new_with_precompiles(state, config, &self.precompiles)
     }
 }
 
@@ -403,11 +534,13 @@ pub struct Engine<'env, I: IO, E: Env> {
     env: &'env E,
 }
 
-pub(crate) const CONFIG: &Config = &Config::london();
-
 /// Key for storing the state of the engine.
 const STATE_KEY: &[u8; 5] = b"STATE";
 
+/// Key of the flag switching off the EIP-3607 sender check. The check is on unless the
+/// key is present.
+const EIP3607_DISABLED_KEY: &[u8; 16] = b"EIP3607_DISABLED";
+
 impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
     pub fn new(
         origin: Address,
@@ -435,6 +568,11 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         }
     }
 
+    /// Era of the EVM semantics of the current block.
+    pub fn era(&self) -> EngineEra {
+        era::at(self.env.block_height())
+    }
+
     pub fn charge_gas(
         &mut self,
         sender: &Address,
@@ -489,9 +627,12 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         handler: &mut P,
     ) -> EngineResult<SubmitResult> {
         let executor_params = StackExecutorParams::new(
+            self.era(),
+            self.io,
             gas_limit,
             self.current_account_id.clone(),
-            self.env.random_seed(),
+            self.env,
+            promise_results(handler),
         );
         let mut executor = executor_params.make_executor(self);
         let address = executor.create_address(CreateScheme::Legacy { caller: origin });
@@ -510,11 +651,17 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         };
 
         let (values, logs) = executor.into_state().deconstruct();
-        let logs = filter_promises_from_logs(handler, logs);
+        let (logs, promises) = match self.schedule_promises(origin, handler, logs) {
+            Ok(result) => result,
+            Err(e) => {
+                increment_nonce(&mut self.io, &origin);
+                return Err(e.with_gas_used(used_gas));
+            }
+        };
 
         self.apply(values, Vec::<Log>::new(), true);
 
-        Ok(SubmitResult::new(status, used_gas, logs))
+        Ok(SubmitResult::new(status, used_gas, logs).with_promises(promises))
     }
 
     /// Call the EVM contract with arguments
@@ -526,7 +673,7 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         let origin = self.origin();
         match args {
             CallArgs::V2(call_args) => {
-                let contract = Address(call_args.contract);
+                let contract = Address::from(call_args.contract);
                 let value = call_args.value.into();
                 let input = call_args.input;
                 self.call(
@@ -540,7 +687,7 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
                 )
             }
             CallArgs::V1(call_args) => {
-                let contract = Address(call_args.contract);
+                let contract = Address::from(call_args.contract);
                 let value = Wei::zero();
                 let input = call_args.input;
                 self.call(
@@ -553,6 +700,43 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
                     handler,
                 )
             }
+            CallArgs::V3(call_args) => {
+                let key = call_args
+                    .idempotency_key
+                    .map(|key| idempotency::scoped_key(&origin, &key));
+                let block_height = self.env.block_height();
+                if let Some(key) = key.as_ref() {
+                    let recorded = idempotency::get_record(&self.io, key, block_height)
+                        .map_err(|e| EngineErrorKind::Idempotency(e).with_gas_used(0))?;
+                    if let Some(recorded) = recorded {
+                        sdk::log!(crate::prelude::format!(
+                            "Idempotency key already used, returning the original result {}",
+                            hex::encode(recorded.result_hash)
+                        )
+                        .as_str());
+                        return Ok(recorded.to_result());
+                    }
+                }
+                let contract = Address::from(call_args.contract);
+                let value = call_args.value.into();
+                let input = call_args.input;
+                let result = self.call(
+                    origin,
+                    contract,
+                    value,
+                    input,
+                    u64::MAX,
+                    Vec::new(),
+                    handler,
+                )?;
+                if let Some(key) = key {
+                    idempotency::record(&mut self.io, &origin, &key, &result, block_height)
+                        .map_err(|e| {
+                            EngineErrorKind::Idempotency(e).with_gas_used(result.gas_used)
+                        })?;
+                }
+                Ok(result)
+            }
         }
     }
 
@@ -568,9 +752,12 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         handler: &mut P,
     ) -> EngineResult<SubmitResult> {
         let executor_params = StackExecutorParams::new(
+            self.era(),
+            self.io,
             gas_limit,
             self.current_account_id.clone(),
-            self.env.random_seed(),
+            self.env,
+            promise_results(handler),
         );
         let mut executor = executor_params.make_executor(self);
         let (exit_reason, result) =
@@ -586,20 +773,81 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         };
 
         let (values, logs) = executor.into_state().deconstruct();
-        let logs = filter_promises_from_logs(handler, logs);
+        let (logs, promises) = match self.schedule_promises(origin, handler, logs) {
+            Ok(result) => result,
+            Err(e) => {
+                increment_nonce(&mut self.io, &origin);
+                return Err(e.with_gas_used(used_gas));
+            }
+        };
 
         // There is no way to return the logs to the NEAR log method as it only
         // allows a return of UTF-8 strings.
         self.apply(values, Vec::<Log>::new(), true);
 
-        Ok(SubmitResult::new(status, used_gas, logs))
+        Ok(SubmitResult::new(status, used_gas, logs).with_promises(promises))
     }
 
     pub fn view_with_args(&self, args: ViewCallArgs) -> Result<TransactionStatus, EngineErrorKind> {
-        let origin = Address::from_slice(&args.sender);
-        let contract = Address::from_slice(&args.address);
+        let origin = Address::from(args.sender);
+        let contract = Address::from(args.address);
         let value = U256::from_big_endian(&args.amount);
         self.view(origin, contract, Wei::new(value), args.input, u64::MAX)
+            .map(TransactionStatus::limit_return_data)
+    }
+
+    /// Runs an unsigned transaction from any address without storing anything, like
+    /// `eth_call`. The result is the one of `view_with_gas_used`, or of `view_create` if
+    /// the transaction has no recipient.
+    pub fn view_transaction(&self, args: ViewTransactionArgs) -> EngineResult<SubmitResult> {
+        let origin = Address::from(args.from);
+        let value = Wei::new(U256::from_big_endian(&args.value));
+        let gas_limit = args.gas_limit.unwrap_or(u64::MAX);
+        match args.to {
+            Some(contract) => self.view_with_gas_used(
+                origin,
+                Address::from(contract),
+                value,
+                args.data,
+                gas_limit,
+            ),
+            None => self.view_create(origin, value, args.data, gas_limit),
+        }
+    }
+
+    /// Same as `view_with_gas_used` for the deployment of `input` as init code. The output
+    /// is the address the contract would be deployed at.
+    pub fn view_create(
+        &self,
+        origin: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> EngineResult<SubmitResult> {
+        let executor_params = StackExecutorParams::new(
+            self.era(),
+            self.io,
+            gas_limit,
+            self.current_account_id.clone(),
+            self.env,
+            Vec::new(),
+        );
+        let mut executor = executor_params.make_executor(self);
+        let address = executor.create_address(CreateScheme::Legacy { caller: origin });
+        let exit_reason =
+            executor.transact_create(origin, value.raw(), input, gas_limit, Vec::new());
+
+        let used_gas = executor.used_gas();
+        let status = exit_reason
+            .into_result(address.0.to_vec())
+            .map_err(|e| e.with_gas_used(used_gas))?;
+
+        let (_, logs) = executor.into_state().deconstruct();
+        log_limits::check(&logs)
+            .map_err(|e| EngineErrorKind::LogLimit(e).with_gas_used(used_gas))?;
+        let (_, stats, logs) = self.view_promises(logs);
+
+        Ok(SubmitResult::new(status, used_gas, logs).with_promises(stats))
     }
 
     pub fn view(
@@ -610,15 +858,206 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
         input: Vec<u8>,
         gas_limit: u64,
     ) -> Result<TransactionStatus, EngineErrorKind> {
+        self.view_with_gas_used(origin, contract, value, input, gas_limit)
+            .map(|result| result.status)
+            .map_err(|e| e.kind)
+    }
+
+    /// Same as `view`, but also returns the gas used and the logs. The gas is computed
+    /// exactly as in `call` and `submit` (same config, so the EIP-3529 refund cap applies),
+    /// which makes the result usable for gas estimation.
+    pub fn view_with_gas_used(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> EngineResult<SubmitResult> {
+        self.view_with_promises(origin, contract, value, input, gas_limit)
+            .map(|(result, _)| result)
+    }
+
+    /// Same as `view_with_gas_used`, also estimating the NEAR gas needed by the promises
+    /// the call would schedule (see `promise_gas`), and the lowest gas limit with which the
+    /// call succeeds.
+    pub fn estimate_gas(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> EngineResult<GasEstimate> {
+        let (result, promises) =
+            self.view_with_promises(origin, contract, value, input.clone(), gas_limit)?;
+        let lowest_gas_limit = if result.status.is_ok() {
+            self.lowest_gas_limit(origin, contract, value, &input, result.gas_used, gas_limit)
+        } else {
+            result.gas_used
+        };
+        Ok(GasEstimate {
+            result,
+            promises_near_gas: promise_gas::total_gas(&promises),
+            gas_limit: lowest_gas_limit,
+        })
+    }
+
+    /// Lowest gas limit with which the call succeeds, given that it succeeds with `gas_limit`
+    /// and uses `gas_used`. The gas used is only a lower bound: a call only forwards 63/64 of
+    /// the gas left to a subcall (EIP-150), and the gas refunded at the end of the transaction
+    /// must be available during its execution.
+    fn lowest_gas_limit(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: &[u8],
+        gas_used: u64,
+        gas_limit: u64,
+    ) -> u64 {
+        let succeeds = |gas_limit: u64| {
+            self.view_with_promises(origin, contract, value, input.to_vec(), gas_limit)
+                .map_or(false, |(result, _)| result.status.is_ok())
+        };
+        // Most calls succeed with the gas they use. Otherwise, the bound is doubled until it
+        // is enough, rather than searching from `gas_limit` (usually `u64::MAX`).
+        if gas_used >= gas_limit || succeeds(gas_used) {
+            return gas_used.min(gas_limit);
+        }
+        // The call fails with `low` and succeeds with `high`.
+        let mut low = gas_used;
+        let mut high = gas_used.saturating_mul(2).min(gas_limit);
+        while high < gas_limit && !succeeds(high) {
+            low = high;
+            high = high.saturating_mul(2).min(gas_limit);
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if succeeds(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        high
+    }
+
+    fn view_with_promises(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> EngineResult<(SubmitResult, Vec<PromiseArgs>)> {
         let executor_params = StackExecutorParams::new(
+            self.era(),
+            self.io,
             gas_limit,
             self.current_account_id.clone(),
-            self.env.random_seed(),
+            self.env,
+            Vec::new(),
         );
         let mut executor = executor_params.make_executor(self);
-        let (status, result) =
+        let (exit_reason, result) =
             executor.transact_call(origin, contract, value.raw(), input, gas_limit, Vec::new());
-        status.into_result(result)
+
+        let used_gas = executor.used_gas();
+        let status = exit_reason
+            .into_result(result)
+            .map_err(|e| e.with_gas_used(used_gas))?;
+
+        let (_, logs) = executor.into_state().deconstruct();
+        log_limits::check(&logs)
+            .map_err(|e| EngineErrorKind::LogLimit(e).with_gas_used(used_gas))?;
+        let (promises, stats, logs) = self.view_promises(logs);
+
+        Ok((
+            SubmitResult::new(status, used_gas, logs).with_promises(stats),
+            promises,
+        ))
+    }
+
+    /// Promises a view would schedule and what they cost, and the logs left to return. The
+    /// cross-contract calls which cannot be paid for, and would fail the transaction, are
+    /// left out.
+    fn view_promises(&self, logs: Vec<Log>) -> (Vec<PromiseArgs>, PromiseStats, Vec<ResultLog>) {
+        let (xcc_calls, logs) = xcc::split_calls(logs);
+        let (mut promises, logs) = split_promises_from_logs(logs);
+        let mut stats = PromiseStats::default();
+        for promise in promises.iter() {
+            promise_budget::account(&mut stats, promise);
+        }
+        if let Ok(xcc_promises) = xcc::plan(&self.io, &self.current_account_id, xcc_calls) {
+            for promise in xcc_promises {
+                promise.account(&mut stats);
+                promises.push(promise.into_promise_args());
+            }
+        }
+        (promises, stats, logs)
+    }
+
+    /// Schedules the promises emitted by a transaction if they fit in the promise budget,
+    /// and returns the remaining logs together with what the promises cost. Nothing is
+    /// scheduled if `origin` may not withdraw one of the bridged tokens exited, or if one of
+    /// the cross-contract calls cannot be paid for. The cross-contract calls are scheduled
+    /// after the exits.
+    fn schedule_promises<P: PromiseHandler>(
+        &mut self,
+        origin: Address,
+        handler: &mut P,
+        logs: Vec<Log>,
+    ) -> Result<(Vec<ResultLog>, PromiseStats), EngineErrorKind> {
+        log_limits::check(&logs).map_err(EngineErrorKind::LogLimit)?;
+        let (xcc_calls, logs) = xcc::split_calls(logs);
+        let xcc_promises = xcc::plan(&self.io, &self.current_account_id, xcc_calls)
+            .map_err(EngineErrorKind::Xcc)?;
+        let exits: Vec<_> = compliance_hook::token_exits(&logs).collect();
+        for (token, _) in exits.iter() {
+            self.check_compliance(token, &origin)
+                .map_err(EngineErrorKind::Compliance)?;
+        }
+        let budget =
+            promise_budget::get_budget(&self.io).map_err(EngineErrorKind::PromiseBudget)?;
+        let mut xcc_stats = PromiseStats::default();
+        for promise in xcc_promises.iter() {
+            promise.account(&mut xcc_stats);
+        }
+        let result = filter_promises_from_logs(handler, logs, &budget, xcc_stats)
+            .map_err(EngineErrorKind::PromiseBudget)?;
+        xcc::schedule(&mut self.io, handler, xcc_promises);
+        for (token, amount) in exits {
+            // The amount of a bridged token fits in the `u128` of NEP-141.
+            let amount = Balance::try_from(amount).unwrap_or(Balance::MAX);
+            crate::bridge_stats::record_withdrawal(&mut self.io, &token, amount);
+        }
+        Ok(result)
+    }
+
+    /// Asks the compliance hook of the ERC-20 `token`, if any, whether `account` may
+    /// receive or withdraw it.
+    pub fn check_compliance(
+        &self,
+        token: &Address,
+        account: &Address,
+    ) -> Result<(), ComplianceError> {
+        let hook = match compliance_hook::get_hook(&self.io, token) {
+            Some(hook) => hook,
+            None => return Ok(()),
+        };
+        match self.view(
+            current_address(&self.current_account_id),
+            hook,
+            Wei::zero(),
+            compliance_hook::encode_is_blacklisted(account),
+            compliance_hook::COMPLIANCE_CHECK_GAS,
+        ) {
+            Ok(TransactionStatus::Succeed(output)) => {
+                compliance_hook::decode_is_blacklisted(&output)
+            }
+            _ => Err(ComplianceError::HookFailed),
+        }
     }
 
     fn relayer_key(account_id: &[u8]) -> Vec<u8> {
@@ -710,7 +1149,7 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
             assert_or_finish!(message.len() >= 40, output_on_fail, self.io);
 
             let recipient = Address(unwrap_res_or_finish!(
-                hex::decode(&message[..40]).unwrap().as_slice().try_into(),
+                codec::hex_decode_array::<20, _>(&message[..40]),
                 output_on_fail,
                 self.io
             ));
@@ -720,10 +1159,14 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
                 U256::from(0)
             } else {
                 assert_or_finish!(message.len() == 64, output_on_fail, self.io);
-                U256::from_big_endian(
-                    unwrap_res_or_finish!(hex::decode(message), output_on_fail, self.io).as_slice(),
-                )
+                U256::from_big_endian(&unwrap_res_or_finish!(
+                    codec::hex_decode_array::<32, _>(message),
+                    output_on_fail,
+                    self.io
+                ))
             };
+            // The fee is transferred as a `u64` amount of wei.
+            assert_or_finish!(fee <= U256::from(u64::MAX), output_on_fail, self.io);
 
             (recipient, fee)
         };
@@ -740,6 +1183,24 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
             self.io
         ));
 
+        unwrap_res_or_finish!(
+            self.check_compliance(&erc20_token, &recipient),
+            output_on_fail,
+            self.io
+        );
+
+        // Fee of the token's schedule, taken in tokens and minted to the relayer. It is not
+        // charged if the relayer has no address, like the fee of ETH deposits.
+        let token_fee = crate::fee_schedule::get_schedule_or_default(&self.io, token)
+            .deposit_fee(args.amount, Fee::new(0))
+            .into_u128();
+        let fee_relayer = if token_fee > 0 {
+            assert_or_finish!(token_fee < args.amount, output_on_fail, self.io);
+            self.get_relayer(relayer_account_id.as_bytes())
+        } else {
+            None
+        };
+
         if fee != U256::from(0) {
             let relayer_address = unwrap_res_or_finish!(
                 self.get_relayer(relayer_account_id.as_bytes()).ok_or(()),
@@ -760,63 +1221,101 @@ impl<'env, I: IO + Copy, E: Env> Engine<'env, I, E> {
             );
         }
 
-        let selector = ERC20_MINT_SELECTOR;
-        let tail = ethabi::encode(&[
-            ethabi::Token::Address(recipient),
-            ethabi::Token::Uint(args.amount.into()),
-        ]);
-
         let erc20_admin_address = current_address(current_account_id);
-        unwrap_res_or_finish!(
-            self.call(
-                erc20_admin_address,
-                erc20_token,
-                Wei::zero(),
-                [selector, tail.as_slice()].concat(),
-                u64::MAX,
-                Vec::new(), // TODO: are there values we should put here?
-                handler,
-            )
-            .and_then(|submit_result| {
-                match submit_result.status {
-                    TransactionStatus::Succeed(_) => Ok(()),
-                    TransactionStatus::Revert(bytes) => {
-                        let error_message = crate::prelude::format!(
-                            "Reverted with message: {}",
-                            crate::prelude::String::from_utf8_lossy(&bytes)
-                        );
-                        Err(EngineError {
-                            kind: EngineErrorKind::EvmError(ExitError::Other(
-                                crate::prelude::Cow::from(error_message),
-                            )),
-                            gas_used: submit_result.gas_used,
-                        })
-                    }
-                    TransactionStatus::OutOfFund => Err(EngineError {
-                        kind: EngineErrorKind::EvmError(ExitError::OutOfFund),
-                        gas_used: submit_result.gas_used,
-                    }),
-                    TransactionStatus::OutOfOffset => Err(EngineError {
-                        kind: EngineErrorKind::EvmError(ExitError::OutOfOffset),
-                        gas_used: submit_result.gas_used,
-                    }),
-                    TransactionStatus::OutOfGas => Err(EngineError {
-                        kind: EngineErrorKind::EvmError(ExitError::OutOfGas),
-                        gas_used: submit_result.gas_used,
-                    }),
-                    TransactionStatus::CallTooDeep => Err(EngineError {
-                        kind: EngineErrorKind::EvmError(ExitError::CallTooDeep),
-                        gas_used: submit_result.gas_used,
-                    }),
+        let unused_amount = match fee_relayer {
+            Some(relayer_address) => {
+                unwrap_res_or_finish!(
+                    self.mint_erc20(
+                        erc20_admin_address,
+                        erc20_token,
+                        recipient,
+                        args.amount - token_fee,
+                        handler
+                    ),
+                    output_on_fail,
+                    self.io
+                );
+                // The recipient was minted its tokens already, so only the fee is refunded.
+                match self.mint_erc20(
+                    erc20_admin_address,
+                    erc20_token,
+                    relayer_address,
+                    token_fee,
+                    handler,
+                ) {
+                    Ok(()) => 0,
+                    Err(_) => token_fee,
                 }
-            }),
-            output_on_fail,
-            self.io
+            }
+            None => {
+                unwrap_res_or_finish!(
+                    self.mint_erc20(
+                        erc20_admin_address,
+                        erc20_token,
+                        recipient,
+                        args.amount,
+                        handler
+                    ),
+                    output_on_fail,
+                    self.io
+                );
+                0
+            }
+        };
+
+        crate::bridge_stats::record_deposit(
+            &mut self.io,
+            &erc20_token,
+            args.amount - unused_amount,
+            recipient.as_bytes(),
         );
 
-        // TODO(marX)
-        // Everything succeed so return "0"
-        self.io.return_output(b"\"0\"");
+        self.io
+            .return_output(crate::prelude::format!("\"{}\"", unused_amount).as_bytes());
+    }
+
+    fn mint_erc20<P: PromiseHandler>(
+        &mut self,
+        erc20_admin_address: Address,
+        erc20_token: Address,
+        recipient: Address,
+        amount: Balance,
+        handler: &mut P,
+    ) -> Result<(), EngineError> {
+        self.call(
+            erc20_admin_address,
+            erc20_token,
+            Wei::zero(),
+            crate::erc20_abi::encode_mint(recipient, amount.into()),
+            u64::MAX,
+            Vec::new(), // TODO: are there values we should put here?
+            handler,
+        )
+        .and_then(|submit_result| {
+            match submit_result.status {
+                TransactionStatus::Succeed(_) => Ok(()),
+                TransactionStatus::Revert(bytes) => {
+                    let error_message = crate::prelude::format!(
+                        "Reverted with message: {}",
+                        crate::prelude::String::from_utf8_lossy(&bytes)
+                    );
+                    Err(EngineError {
+                        kind: EngineErrorKind::EvmError(ExitError::Other(
+                            crate::prelude::Cow::from(error_message),
+                        )),
+                        gas_used: submit_result.gas_used,
+                    })
+                }
+                status => Err(EngineError {
+                    // `Succeed` and `Revert` are matched above, the ERC-20 does not
+                    // return more than a word
+                    kind: EngineErrorKind::EvmError(status_exit_error(&status).unwrap_or_else(
+                        || ExitError::Other(crate::prelude::Cow::from("ERR_RETURN_DATA_TOO_LARGE")),
+                    )),
+                    gas_used: submit_result.gas_used,
+                }),
+            }
+        })
     }
 }
 
@@ -849,8 +1348,14 @@ pub fn submit<I: IO + Copy, E: Env, P: PromiseHandler>(
 
     check_nonce(&io, &sender, &transaction.nonce)?;
 
+    // EIP-3607: no transaction can originate from an address with code, since nobody
+    // is supposed to hold a key for it.
+    if is_eip3607_enabled(&io) && get_code_size(&io, &sender) != 0 {
+        return Err(EngineErrorKind::SenderHasCode.into());
+    }
+
     // Check intrinsic gas is covered by transaction gas limit
-    match transaction.intrinsic_gas(crate::engine::CONFIG) {
+    match transaction.intrinsic_gas(era::at(env.block_height()).config()) {
         None => {
             return Err(EngineErrorKind::GasOverflow.into());
         }
@@ -865,6 +1370,13 @@ pub fn submit<I: IO + Copy, E: Env, P: PromiseHandler>(
         return Err(EngineErrorKind::MaxPriorityGasFeeTooLarge.into());
     }
 
+    if transaction.to.is_none() {
+        deploy_gate::check(&io, &sender).map_err(EngineErrorKind::DeployGate)?;
+    }
+
+    min_gas_price::check(&mut io, &transaction, env.block_timestamp())
+        .map_err(EngineErrorKind::MinGasPrice)?;
+
     let mut engine = Engine::new_with_state(state, sender, current_account_id, io, env);
     let prepaid_amount = match engine.charge_gas(&sender, &transaction) {
         Ok(gas_result) => gas_result,
@@ -952,6 +1464,27 @@ pub fn compute_block_hash(chain_id: [u8; 32], block_height: u64, account_id: &[u
     sdk::sha256(&data)
 }
 
+/// The value of the `DIFFICULTY` opcode (`PREVRANDAO` since the Merge), derived from the
+/// random seed of the NEAR block according to
+/// ```text
+/// block_randomness = keccak256(concat(
+///     random_seed,
+///     origin,
+///     origin_nonce as u256,
+/// ))
+/// ```
+/// The seed is not known before the block is produced but it is the same for every receipt of
+/// the block, so the origin of the transaction and its nonce are mixed in, which gives each
+/// transaction of the block its own value.
+pub fn compute_block_randomness(random_seed: H256, origin: &Address, origin_nonce: U256) -> H256 {
+    let mut data = Vec::with_capacity(32 + 20 + 32);
+    data.extend_from_slice(random_seed.as_bytes());
+    data.extend_from_slice(origin.as_bytes());
+    data.extend_from_slice(&u256_to_arr(&origin_nonce));
+
+    sdk::keccak(&data)
+}
+
 pub fn get_state<I: IO>(io: &I) -> Result<EngineState, EngineStateError> {
     match io.read_storage(&bytes_to_key(KeyPrefix::Config, STATE_KEY)) {
         None => Err(EngineStateError::NotFound),
@@ -968,6 +1501,22 @@ pub fn set_state<I: IO>(io: &mut I, state: EngineState) {
     );
 }
 
+/// Whether transactions from addresses with code are rejected (EIP-3607).
+pub fn is_eip3607_enabled<I: IO>(io: &I) -> bool {
+    !io.storage_has_key(&bytes_to_key(KeyPrefix::Config, EIP3607_DISABLED_KEY))
+}
+
+/// Switches the EIP-3607 sender check on or off. It is on by default; switching it off is
+/// only meant to keep legacy senders working while they migrate.
+pub fn set_eip3607_enabled<I: IO>(io: &mut I, enabled: bool) {
+    let key = bytes_to_key(KeyPrefix::Config, EIP3607_DISABLED_KEY);
+    if enabled {
+        io.remove_storage(&key);
+    } else {
+        io.write_storage(&key, &[1]);
+    }
+}
+
 pub fn refund_unused_gas<I: IO>(
     io: &mut I,
     sender: &Address,
@@ -988,6 +1537,7 @@ pub fn refund_unused_gas<I: IO>(
 
     let spent_amount = gas_to_wei(gas_result.effective_gas_price)?;
     let reward_amount = gas_to_wei(gas_result.priority_fee_per_gas)?;
+    let (protocol_share, reward_amount) = crate::protocol_fee::collect(io, reward_amount)?;
 
     let refund = gas_result
         .prepaid_amount
@@ -996,17 +1546,29 @@ pub fn refund_unused_gas<I: IO>(
 
     add_balance(io, sender, refund)?;
     add_balance(io, relayer, reward_amount)?;
+    if let Some(share) = protocol_share {
+        add_balance(io, &share.treasury, share.amount)?;
+    }
 
     Ok(())
 }
 
 /// Used to bridge NEP-141 tokens from NEAR to Aurora. On Aurora the NEP-141 becomes an ERC-20.
+/// Version of the built-in ERC-20 contract templates deployed by `deploy_erc20_token`.
+#[cfg(feature = "error_refund")]
+pub const ERC20_TEMPLATE_VERSION: u8 = 2;
+#[cfg(not(feature = "error_refund"))]
+pub const ERC20_TEMPLATE_VERSION: u8 = 1;
+
+/// Decimals the ERC-20 contracts are deployed with.
+const ERC20_DEPLOY_DECIMALS: u8 = 0;
+
 pub fn deploy_erc20_token<I: IO + Copy, E: Env, P: PromiseHandler>(
-    args: DeployErc20TokenArgs,
+    args: DeployErc20TokenWithTemplateArgs,
     io: I,
     env: &E,
     handler: &mut P,
-) -> Result<Address, DeployErc20Error> {
+) -> Result<DeployErc20Result, DeployErc20Error> {
     let current_account_id = env.current_account_id();
     let erc20_admin_address = current_address(&current_account_id);
     let mut engine = Engine::new(
@@ -1019,21 +1581,18 @@ pub fn deploy_erc20_token<I: IO + Copy, E: Env, P: PromiseHandler>(
     )
     .map_err(DeployErc20Error::State)?;
 
-    #[cfg(feature = "error_refund")]
-    let erc20_contract = include_bytes!("../../etc/eth-contracts/res/EvmErc20V2.bin");
-    #[cfg(not(feature = "error_refund"))]
-    let erc20_contract = include_bytes!("../../etc/eth-contracts/res/EvmErc20.bin");
-
-    let deploy_args = ethabi::encode(&[
-        ethabi::Token::String("Empty".to_string()),
-        ethabi::Token::String("EMPTY".to_string()),
-        ethabi::Token::Uint(ethabi::Uint::from(0)),
-        ethabi::Token::Address(erc20_admin_address),
-    ]);
+    let (template, erc20_contract) =
+        token_templates::resolve(&io, &args.template).map_err(DeployErc20Error::Template)?;
+    let deploy_args = crate::erc20_abi::encode_constructor(
+        "Empty".to_string(),
+        "EMPTY".to_string(),
+        ERC20_DEPLOY_DECIMALS,
+        erc20_admin_address,
+    );
 
     let address = match Engine::deploy_code_with_input(
         &mut engine,
-        (&[erc20_contract, deploy_args.as_slice()].concat()).to_vec(),
+        (&[erc20_contract.as_slice(), deploy_args.as_slice()].concat()).to_vec(),
         handler,
     ) {
         Ok(result) => match result.status {
@@ -1045,29 +1604,61 @@ pub fn deploy_erc20_token<I: IO + Copy, E: Env, P: PromiseHandler>(
 
     sdk::log!(crate::prelude::format!("Deployed ERC-20 in Aurora at: {:#?}", address).as_str());
     engine
-        .register_token(address, args.nep141)
+        .register_token(address, args.nep141.clone())
         .map_err(DeployErc20Error::Register)?;
+    token_templates::record_deployment(&mut engine.io, &address, &template);
+
+    let result = DeployErc20Result::new(
+        args.nep141,
+        address.into(),
+        ERC20_DEPLOY_DECIMALS,
+        template.version,
+    );
+    crate::events::emit_erc20_deployed(&result);
 
-    Ok(address)
+    Ok(result)
 }
 
+/// Code is stored once per hash, see `code_dedup`.
 pub fn set_code<I: IO>(io: &mut I, address: &Address, code: &[u8]) {
-    io.write_storage(&address_to_key(KeyPrefix::Code, address), code);
+    remove_code(io, address);
+    if code.is_empty() {
+        return;
+    }
+    let code_hash = sdk::keccak(code);
+    code_dedup::add_reference(io, &code_hash, code);
+    io.write_storage(
+        &address_to_key(KeyPrefix::CodeHash, address),
+        code_hash.as_bytes(),
+    );
 }
 
 pub fn remove_code<I: IO>(io: &mut I, address: &Address) {
-    io.remove_storage(&address_to_key(KeyPrefix::Code, address));
+    if let Some(code_hash) = code_dedup::get_code_hash(io, address) {
+        io.remove_storage(&address_to_key(KeyPrefix::CodeHash, address));
+        code_dedup::remove_reference(io, &code_hash);
+    } else {
+        io.remove_storage(&address_to_key(KeyPrefix::Code, address));
+    }
+}
+
+/// Key under which the code of the account is stored: the shared key of its code hash,
+/// or its own key if it was deployed before the deduplication and not migrated yet.
+fn code_key<I: IO>(io: &I, address: &Address) -> Vec<u8> {
+    match code_dedup::get_code_hash(io, address) {
+        Some(code_hash) => code_dedup::shared_code_key(&code_hash),
+        None => address_to_key(KeyPrefix::Code, address).to_vec(),
+    }
 }
 
 pub fn get_code<I: IO>(io: &I, address: &Address) -> Vec<u8> {
-    io.read_storage(&address_to_key(KeyPrefix::Code, address))
+    io.read_storage(&code_key(io, address))
         .map(|s| s.to_vec())
         .unwrap_or_else(Vec::new)
 }
 
 pub fn get_code_size<I: IO>(io: &I, address: &Address) -> usize {
-    io.read_storage_len(&address_to_key(KeyPrefix::Code, address))
-        .unwrap_or(0)
+    io.read_storage_len(&code_key(io, address)).unwrap_or(0)
 }
 
 pub fn set_nonce<I: IO>(io: &mut I, address: &Address, nonce: &U256) {
@@ -1163,6 +1754,12 @@ pub fn get_balance<I: IO>(io: &I, address: &Address) -> Wei {
 
 pub fn remove_storage<I: IO>(io: &mut I, address: &Address, key: &H256, generation: u32) {
     io.remove_storage(storage_to_key(address, key, generation).as_ref());
+    if is_sharded_generation(generation) {
+        // Otherwise the value not migrated yet would be read again.
+        if let Some(legacy_generation) = storage_sharding::legacy_generation(io, address) {
+            io.remove_storage(storage_to_key(address, key, legacy_generation).as_ref());
+        }
+    }
 }
 
 pub fn set_storage<I: IO>(
@@ -1176,12 +1773,22 @@ pub fn set_storage<I: IO>(
 }
 
 pub fn get_storage<I: IO>(io: &I, address: &Address, key: &H256, generation: u32) -> H256 {
-    io.read_storage(storage_to_key(address, key, generation).as_ref())
-        .and_then(|value| {
-            if value.len() == 32 {
-                let mut buf = [0u8; 32];
-                value.copy_to_slice(&mut buf);
-                Some(H256(buf))
+    let read = |generation| {
+        io.read_storage(storage_to_key(address, key, generation).as_ref())
+            .and_then(|value| {
+                if value.len() == 32 {
+                    let mut buf = [0u8; 32];
+                    value.copy_to_slice(&mut buf);
+                    Some(H256(buf))
+                } else {
+                    None
+                }
+            })
+    };
+    read(generation)
+        .or_else(|| {
+            if is_sharded_generation(generation) {
+                storage_sharding::legacy_generation(io, address).and_then(read)
             } else {
                 None
             }
@@ -1225,6 +1832,9 @@ fn remove_all_storage<I: IO>(io: &mut I, address: &Address, generation: u32) {
     //     has to be deleted the storage nonce needs to be increased, and the old nonce keys
     //     can be deleted over time. That's how TurboGeth does storage.
     set_generation(io, address, generation + 1);
+    if is_sharded_generation(generation) {
+        storage_sharding::clear(io, address);
+    }
 }
 
 /// Removes an account.
@@ -1235,37 +1845,72 @@ fn remove_account<I: IO + Copy>(io: &mut I, address: &Address, generation: u32)
     remove_all_storage(io, address, generation);
 }
 
-fn filter_promises_from_logs<T, P>(handler: &mut P, logs: T) -> Vec<ResultLog>
+/// Logs used by the exit precompiles to schedule promises. They are never passed on to the caller.
+fn is_promise_log(log: &Log) -> bool {
+    (log.address == ExitToNear::ADDRESS
+        || log.address == ExitToEthereum::ADDRESS
+        || log.address == ExitNftToNear::ADDRESS)
+        && log.topics.is_empty()
+}
+
+/// Separates the promises scheduled by the exit precompiles from the other logs. Both keep
+/// the order in which the logs were emitted.
+fn split_promises_from_logs<T>(logs: T) -> (Vec<PromiseArgs>, Vec<ResultLog>)
 where
     T: IntoIterator<Item = Log>,
-    P: PromiseHandler,
 {
-    logs.into_iter()
+    let mut promises = Vec::new();
+    let logs = logs
+        .into_iter()
         .filter_map(|log| {
-            if log.address == ExitToNear::ADDRESS || log.address == ExitToEthereum::ADDRESS {
-                if log.topics.is_empty() {
-                    if let Ok(promise) = PromiseArgs::try_from_slice(&log.data) {
-                        match promise {
-                            PromiseArgs::Create(promise) => schedule_promise(handler, &promise),
-                            PromiseArgs::Callback(promise) => {
-                                let base_id = schedule_promise(handler, &promise.base);
-                                schedule_promise_callback(handler, base_id, &promise.callback)
-                            }
-                        };
-                    }
-                    // do not pass on these "internal logs" to caller
-                    None
-                } else {
-                    // The exit precompiles do produce externally consumable logs in
-                    // addition to the promises. The external logs have a non-empty
-                    // `topics` field.
-                    Some(log.into())
+            if is_promise_log(&log) {
+                if let Ok(promise) = PromiseArgs::try_from_slice(&log.data) {
+                    promises.push(promise);
                 }
+                // do not pass on these "internal logs" to caller
+                None
             } else {
+                // The exit precompiles do produce externally consumable logs in
+                // addition to the promises. The external logs have a non-empty
+                // `topics` field.
                 Some(log.into())
             }
         })
-        .collect()
+        .collect();
+    (promises, logs)
+}
+
+/// Schedules the promises found in `logs` and returns the remaining logs and what the
+/// promises cost, `planned` included. Both the promises and the returned logs keep the
+/// order in which the logs were emitted. Nothing is scheduled if the promises do not fit in
+/// `budget` together with the `planned` ones, which the transaction schedules otherwise.
+fn filter_promises_from_logs<T, P>(
+    handler: &mut P,
+    logs: T,
+    budget: &PromiseBudget,
+    planned: PromiseStats,
+) -> Result<(Vec<ResultLog>, PromiseStats), PromiseBudgetError>
+where
+    T: IntoIterator<Item = Log>,
+    P: PromiseHandler,
+{
+    let (promises, logs) = split_promises_from_logs(logs);
+    let mut stats = planned;
+    for promise in promises.iter() {
+        promise_budget::account(&mut stats, promise);
+    }
+    budget.check(&stats)?;
+
+    for promise in promises {
+        match promise {
+            PromiseArgs::Create(promise) => schedule_promise(handler, &promise),
+            PromiseArgs::Callback(promise) => {
+                let base_id = schedule_promise(handler, &promise.base);
+                schedule_promise_callback(handler, base_id, &promise.callback)
+            }
+        };
+    }
+    Ok((logs, stats))
 }
 
 fn schedule_promise<P: PromiseHandler>(handler: &mut P, promise: &PromiseCreateArgs) -> PromiseId {
@@ -1303,17 +1948,11 @@ impl<'env, I: IO + Copy, E: Env> evm::backend::Backend for Engine<'env, I, E> {
 
     /// Returns a block hash from a given index.
     ///
-    /// Currently, this returns
-    /// 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff if
-    /// only for the 256 most recent blocks, excluding of the current one.
-    /// Otherwise, it returns 0x0.
+    /// For the 256 most recent blocks, excluding the current one, this returns
+    /// the hash derived from the height by `compute_block_hash`, which needs no
+    /// storage. Otherwise, it returns 0x0.
     ///
-    /// In other words, if the requested block index is less than the current
-    /// block index, return
-    /// 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff.
-    /// Otherwise, return 0.
-    ///
-    /// This functionality may change in the future. Follow
+    /// The hashes of NEAR blocks are not available to contracts. Follow
     /// [nearcore#3456](https://github.com/near/nearcore/issues/3456) for more
     /// details.
     ///
@@ -1353,11 +1992,22 @@ impl<'env, I: IO + Copy, E: Env> evm::backend::Backend for Engine<'env, I, E> {
         U256::from(self.env.block_timestamp().secs())
     }
 
-    /// Returns the current block difficulty.
+    /// Returns the current block difficulty: zero before `EngineEra::Paris`, then the
+    /// randomness of the block for the transaction, see `compute_block_randomness`. The nonce
+    /// of the origin is the nonce of the transaction, since it is only incremented in storage
+    /// once the transaction is executed.
     ///
     /// See: https://doc.aurora.dev/develop/compat/evm#difficulty
     fn block_difficulty(&self) -> U256 {
-        U256::zero()
+        if !self.era().has_block_randomness() {
+            return U256::zero();
+        }
+        let randomness = compute_block_randomness(
+            self.env.random_seed(),
+            &self.origin,
+            get_nonce(&self.io, &self.origin),
+        );
+        U256::from_big_endian(randomness.as_bytes())
     }
 
     /// Returns the current block gas limit.
@@ -1429,6 +2079,13 @@ impl<'env, J: IO + Copy, E: Env> ApplyBackend for Engine<'env, J, E> {
     {
         let mut writes_counter: usize = 0;
         let mut code_bytes_written: usize = 0;
+        let activity_height = match crate::inactivity::get_policy(&self.io) {
+            Ok(policy) if policy.is_enabled() => Some(self.env.block_height()),
+            _ => None,
+        };
+        let mut diff = crate::state_commitment::DiffEncoder::default();
+        // The modified accounts (and their storage) come from `BTreeMap`s in the executor
+        // state, so they are written in ascending address (and slot) order.
         for apply in values {
             match apply {
                 Apply::Modify {
@@ -1439,9 +2096,26 @@ impl<'env, J: IO + Copy, E: Env> ApplyBackend for Engine<'env, J, E> {
                     reset_storage,
                 } => {
                     let generation = get_generation(&self.io, &address);
+                    if let Some(height) = activity_height {
+                        crate::inactivity::touch(&mut self.io, &address, height);
+                        writes_counter += 1;
+                    }
+                    let mut balance = Wei::new(basic.balance);
+                    if !balance.is_zero() && crate::stranded_eth::is_sink_address(&address) {
+                        crate::stranded_eth::strand(&mut self.io, &address, balance);
+                        balance = Wei::zero();
+                        writes_counter += 1;
+                    }
                     set_nonce(&mut self.io, &address, &basic.nonce);
-                    set_balance(&mut self.io, &address, &Wei::new(basic.balance));
+                    set_balance(&mut self.io, &address, &balance);
                     writes_counter += 2; // 1 for nonce, 1 for balance
+                    diff.modify(
+                        &address,
+                        basic.nonce,
+                        balance.raw(),
+                        code.as_deref(),
+                        reset_storage,
+                    );
 
                     if let Some(code) = code {
                         set_code(&mut self.io, &address, &code);
@@ -1462,6 +2136,7 @@ impl<'env, J: IO + Copy, E: Env> ApplyBackend for Engine<'env, J, E> {
                     };
 
                     for (index, value) in storage {
+                        diff.storage(&index, &value);
                         if value == H256::default() {
                             remove_storage(&mut self.io, &address, &index, next_generation)
                         } else {
@@ -1484,12 +2159,14 @@ impl<'env, J: IO + Copy, E: Env> ApplyBackend for Engine<'env, J, E> {
                     }
                 }
                 Apply::Delete { address } => {
+                    diff.delete(&address);
                     let generation = get_generation(&self.io, &address);
                     remove_account(&mut self.io, &address, generation);
                     writes_counter += 1;
                 }
             }
         }
+        crate::state_commitment::update(&mut self.io, &diff, self.env.block_height());
         // These variable are only used if logging feature is enabled.
         // In production logging is always enabled so we can ignore the warnings.
         #[allow(unused_variables)]
@@ -1504,4 +2181,156 @@ impl<'env, J: IO + Copy, E: Env> ApplyBackend for Engine<'env, J, E> {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::prelude::{PromiseBatchAction, PromiseResult, PromiseWithCallbackArgs, String};
+
+    #[derive(Default)]
+    struct PromiseRecorder {
+        scheduled: Vec<(u64, String)>,
+    }
+
+    impl PromiseRecorder {
+        fn schedule(&mut self, method: &str) -> PromiseId {
+            let id = self.scheduled.len() as u64;
+            self.scheduled.push((id, method.to_string()));
+            PromiseId::new(id)
+        }
+    }
+
+    impl PromiseHandler for PromiseRecorder {
+        fn promise_results_count(&self) -> u64 {
+            0
+        }
+
+        fn promise_result(&self, _index: u64) -> Option<PromiseResult> {
+            None
+        }
+
+        fn promise_create_call(&mut self, args: &PromiseCreateArgs) -> PromiseId {
+            self.schedule(&args.method)
+        }
+
+        fn promise_attach_callback(
+            &mut self,
+            _base: PromiseId,
+            callback: &PromiseCreateArgs,
+        ) -> PromiseId {
+            self.schedule(&callback.method)
+        }
+
+        fn promise_create_batch(&mut self, _args: &PromiseBatchAction) -> PromiseId {
+            self.schedule("batch")
+        }
+
+        fn promise_return(&mut self, _promise: PromiseId) {}
+    }
+
+    fn promise_args(method: &str) -> PromiseCreateArgs {
+        PromiseCreateArgs {
+            target_account_id: AccountId::new("target.near").unwrap(),
+            method: method.to_string(),
+            args: Vec::new(),
+            attached_balance: 0,
+            attached_gas: 0,
+        }
+    }
+
+    fn promise_log(address: Address, promise: PromiseArgs) -> Log {
+        Log {
+            address,
+            topics: Vec::new(),
+            data: promise.try_to_vec().unwrap(),
+        }
+    }
+
+    fn event_log(tag: u8) -> Log {
+        Log {
+            address: Address([tag; 20]),
+            topics: vec![H256([tag; 32])],
+            data: vec![tag],
+        }
+    }
+
+    #[test]
+    fn test_filter_promises_keeps_emission_order() {
+        let logs = vec![
+            event_log(1),
+            promise_log(ExitToNear::ADDRESS, PromiseArgs::Create(promise_args("a"))),
+            event_log(2),
+            promise_log(
+                ExitToEthereum::ADDRESS,
+                PromiseArgs::Callback(PromiseWithCallbackArgs {
+                    base: promise_args("b"),
+                    callback: promise_args("c"),
+                }),
+            ),
+            promise_log(ExitToNear::ADDRESS, PromiseArgs::Create(promise_args("d"))),
+            event_log(3),
+        ];
+        let mut handler = PromiseRecorder::default();
+
+        let (result, stats) = filter_promises_from_logs(
+            &mut handler,
+            logs,
+            &PromiseBudget::default(),
+            PromiseStats::default(),
+        )
+        .unwrap();
+
+        assert_eq!(stats.count, 4);
+        let tags: Vec<u8> = result.iter().map(|log| log.data[0]).collect();
+        assert_eq!(tags, vec![1, 2, 3]);
+        let methods: Vec<&str> = handler
+            .scheduled
+            .iter()
+            .map(|(_, method)| method.as_str())
+            .collect();
+        assert_eq!(methods, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_filter_promises_over_budget_schedules_nothing() {
+        let logs = vec![
+            promise_log(ExitToNear::ADDRESS, PromiseArgs::Create(promise_args("a"))),
+            promise_log(ExitToNear::ADDRESS, PromiseArgs::Create(promise_args("b"))),
+        ];
+        let budget = PromiseBudget {
+            max_promises: 1,
+            ..Default::default()
+        };
+        let mut handler = PromiseRecorder::default();
+
+        let result =
+            filter_promises_from_logs(&mut handler, logs, &budget, PromiseStats::default());
+
+        assert!(matches!(result, Err(PromiseBudgetError::TooManyPromises)));
+        assert!(handler.scheduled.is_empty());
+    }
+
+    #[test]
+    fn test_block_randomness_differs_per_transaction() {
+        let seed = H256::repeat_byte(7);
+        let alice = Address::repeat_byte(1);
+        let bob = Address::repeat_byte(2);
+        let randomness = compute_block_randomness(seed, &alice, U256::zero());
+
+        assert_eq!(
+            randomness,
+            compute_block_randomness(seed, &alice, U256::zero())
+        );
+        assert_ne!(randomness, seed);
+        assert_ne!(
+            randomness,
+            compute_block_randomness(seed, &alice, U256::one())
+        );
+        assert_ne!(
+            randomness,
+            compute_block_randomness(seed, &bob, U256::zero())
+        );
+        assert_ne!(
+            randomness,
+            compute_block_randomness(H256::repeat_byte(8), &alice, U256::zero())
+        );
+    }
+}
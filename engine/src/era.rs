@@ -0,0 +1,116 @@
+//! Eras of the EVM semantics over the history of the network.
+//!
+//! The gas schedule, the refund rules and the set of precompiles of the EVM change with the
+//! hard forks the engine follows. Each set of semantics is an `EngineEra`, and `SCHEDULE`
+//! gives the block height from which each era applies. The executor, the intrinsic gas
+//! check of `submit` and the precompiles all take the era of the current block from here,
+//! so that a replica running the latest engine replays an old block with the semantics it
+//! was executed with, rather than with the current ones.
+//!
+//! A change of semantics is a new variant, activated by a new entry of `SCHEDULE` at a
+//! height in the future of the networks the engine is deployed to.
+
+use crate::prelude::precompiles::{PrecompileConstructorContext, Precompiles};
+use evm::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EngineEra {
+    /// EIP-2929 gas costs and EIP-2930 access lists, refunds capped to half the gas used.
+    Berlin,
+    /// Berlin with the EIP-3529 refund rules (refunds capped to a fifth of the gas used, no
+    /// more `SELFDESTRUCT` refund) and EIP-3541 (no new code starting with `0xEF`).
+    London,
+    /// London with `DIFFICULTY` giving the randomness of the block for the transaction (like
+    /// `PREVRANDAO` of EIP-4399) instead of zero, see `engine::compute_block_randomness`.
+    Paris,
+}
+
+const BERLIN_CONFIG: &Config = &Config::berlin();
+const LONDON_CONFIG: &Config = &Config::london();
+
+/// Height from which `EngineEra::Paris` applies.
+pub const PARIS_HEIGHT: u64 = 250_000_000;
+
+/// Height from which each era applies, by increasing height. The first era applies from
+/// genesis.
+const SCHEDULE: &[(u64, EngineEra)] = &[(0, EngineEra::London), (PARIS_HEIGHT, EngineEra::Paris)];
+
+impl EngineEra {
+    /// Gas schedule, refund rules and enabled EIPs of the executor.
+    pub fn config(self) -> &'static Config {
+        match self {
+            Self::Berlin => BERLIN_CONFIG,
+            Self::London | Self::Paris => LONDON_CONFIG,
+        }
+    }
+
+    /// Standard and Aurora-specific precompiles. Extensions are registered on top of them.
+    pub fn precompiles<'a>(self, ctx: PrecompileConstructorContext<'a>) -> Precompiles<'a> {
+        match self {
+            Self::Berlin => Precompiles::new_berlin(ctx),
+            Self::London | Self::Paris => Precompiles::new_london(ctx),
+        }
+    }
+
+    /// Whether `DIFFICULTY` gives the randomness of the block rather than zero.
+    pub fn has_block_randomness(self) -> bool {
+        self >= Self::Paris
+    }
+}
+
+/// Era of the block at the given height.
+pub fn at(block_height: u64) -> EngineEra {
+    era_in(SCHEDULE, block_height)
+}
+
+fn era_in(schedule: &[(u64, EngineEra)], block_height: u64) -> EngineEra {
+    schedule
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= block_height)
+        .or_else(|| schedule.first())
+        .map(|(_, era)| *era)
+        .expect("ERR_EMPTY_ERA_SCHEDULE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_is_ordered() {
+        assert_eq!(SCHEDULE[0].0, 0);
+        for window in SCHEDULE.windows(2) {
+            assert!(window[0].0 < window[1].0);
+            assert!(window[0].1 < window[1].1);
+        }
+    }
+
+    #[test]
+    fn test_era_at_height() {
+        let schedule = [(0, EngineEra::Berlin), (100, EngineEra::London)];
+        assert_eq!(era_in(&schedule, 0), EngineEra::Berlin);
+        assert_eq!(era_in(&schedule, 99), EngineEra::Berlin);
+        assert_eq!(era_in(&schedule, 100), EngineEra::London);
+        assert_eq!(era_in(&schedule, u64::MAX), EngineEra::London);
+        assert_eq!(at(u64::MAX), SCHEDULE[SCHEDULE.len() - 1].1);
+        assert_eq!(at(PARIS_HEIGHT - 1), EngineEra::London);
+        assert_eq!(at(PARIS_HEIGHT), EngineEra::Paris);
+    }
+
+    #[test]
+    fn test_block_randomness() {
+        assert!(!EngineEra::Berlin.has_block_randomness());
+        assert!(!EngineEra::London.has_block_randomness());
+        assert!(EngineEra::Paris.has_block_randomness());
+    }
+
+    #[test]
+    fn test_refund_rules() {
+        // EIP-3529 lowered the refund cap from a half to a fifth of the gas used.
+        assert_eq!(EngineEra::Berlin.config().max_refund_quotient, 2);
+        assert_eq!(EngineEra::London.config().max_refund_quotient, 5);
+        assert_eq!(EngineEra::Berlin.config().refund_sstore_clears, 15_000);
+        assert_eq!(EngineEra::London.config().refund_sstore_clears, 4_800);
+    }
+}
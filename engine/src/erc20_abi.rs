@@ -0,0 +1,212 @@
+//! Typed ABI of the ERC-20 contracts deployed by the engine for bridged NEP-141 tokens
+//! (see `etc/eth-contracts/contracts/EvmErc20.sol`).
+//!
+//! Calls from the engine into these contracts must be encoded with the functions of this
+//! module. The selectors are constants checked against their signatures in the tests, so
+//! a mistake cannot silently make the bridged tokens unusable.
+
+use crate::prelude::{Address, String, Vec, U256};
+
+pub const MINT_SIGNATURE: &str = "mint(address,uint256)";
+pub const MINT_SELECTOR: [u8; 4] = [0x40, 0xc1, 0x0f, 0x19];
+
+pub const TRANSFER_SIGNATURE: &str = "transfer(address,uint256)";
+pub const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+pub const BALANCE_OF_SIGNATURE: &str = "balanceOf(address)";
+pub const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+pub const DECIMALS_SIGNATURE: &str = "decimals()";
+pub const DECIMALS_SELECTOR: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+
+pub const SET_METADATA_SIGNATURE: &str = "setMetadata(string,string,uint8)";
+pub const SET_METADATA_SELECTOR: [u8; 4] = [0x37, 0xd2, 0xc2, 0xf4];
+
+pub const WITHDRAW_TO_NEAR_SIGNATURE: &str = "withdrawToNear(bytes,uint256)";
+pub const WITHDRAW_TO_NEAR_SELECTOR: [u8; 4] = [0x6b, 0x35, 0x18, 0x48];
+
+pub const WITHDRAW_TO_ETHEREUM_SIGNATURE: &str = "withdrawToEthereum(address,uint256)";
+pub const WITHDRAW_TO_ETHEREUM_SELECTOR: [u8; 4] = [0x8d, 0x32, 0xca, 0xf4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Erc20AbiError {
+    InvalidOutput,
+}
+
+impl AsRef<[u8]> for Erc20AbiError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidOutput => b"ERR_INVALID_ERC20_OUTPUT",
+        }
+    }
+}
+
+fn encode_call(selector: [u8; 4], args: &[ethabi::Token]) -> Vec<u8> {
+    [&selector[..], &ethabi::encode(args)].concat()
+}
+
+/// Arguments of the constructor, to be appended to the code of the contract.
+pub fn encode_constructor(name: String, symbol: String, decimals: u8, admin: Address) -> Vec<u8> {
+    ethabi::encode(&[
+        ethabi::Token::String(name),
+        ethabi::Token::String(symbol),
+        ethabi::Token::Uint(U256::from(decimals)),
+        ethabi::Token::Address(admin),
+    ])
+}
+
+pub fn encode_mint(recipient: Address, amount: U256) -> Vec<u8> {
+    encode_call(
+        MINT_SELECTOR,
+        &[
+            ethabi::Token::Address(recipient),
+            ethabi::Token::Uint(amount),
+        ],
+    )
+}
+
+pub fn encode_transfer(recipient: Address, amount: U256) -> Vec<u8> {
+    encode_call(
+        TRANSFER_SELECTOR,
+        &[
+            ethabi::Token::Address(recipient),
+            ethabi::Token::Uint(amount),
+        ],
+    )
+}
+
+pub fn encode_balance_of(owner: Address) -> Vec<u8> {
+    encode_call(BALANCE_OF_SELECTOR, &[ethabi::Token::Address(owner)])
+}
+
+pub fn encode_decimals() -> Vec<u8> {
+    encode_call(DECIMALS_SELECTOR, &[])
+}
+
+pub fn encode_set_metadata(name: String, symbol: String, decimals: u8) -> Vec<u8> {
+    encode_call(
+        SET_METADATA_SELECTOR,
+        &[
+            ethabi::Token::String(name),
+            ethabi::Token::String(symbol),
+            ethabi::Token::Uint(U256::from(decimals)),
+        ],
+    )
+}
+
+pub fn encode_withdraw_to_near(recipient: Vec<u8>, amount: U256) -> Vec<u8> {
+    encode_call(
+        WITHDRAW_TO_NEAR_SELECTOR,
+        &[ethabi::Token::Bytes(recipient), ethabi::Token::Uint(amount)],
+    )
+}
+
+pub fn encode_withdraw_to_ethereum(recipient: Address, amount: U256) -> Vec<u8> {
+    encode_call(
+        WITHDRAW_TO_ETHEREUM_SELECTOR,
+        &[
+            ethabi::Token::Address(recipient),
+            ethabi::Token::Uint(amount),
+        ],
+    )
+}
+
+/// Decodes the output of `balanceOf`.
+pub fn decode_balance_of(output: &[u8]) -> Result<U256, Erc20AbiError> {
+    decode_uint(output, 256)
+}
+
+/// Decodes the output of `decimals`.
+pub fn decode_decimals(output: &[u8]) -> Result<u8, Erc20AbiError> {
+    decode_uint(output, 8).map(|value| value.low_u32() as u8)
+}
+
+fn decode_uint(output: &[u8], size: usize) -> Result<U256, Erc20AbiError> {
+    let tokens = ethabi::decode(&[ethabi::ParamType::Uint(size)], output)
+        .map_err(|_| Erc20AbiError::InvalidOutput)?;
+    match tokens.as_slice() {
+        [ethabi::Token::Uint(value)] if value.bits() <= size => Ok(*value),
+        _ => Err(Erc20AbiError::InvalidOutput),
+    }
+}
+
+/// Signatures and selectors of all the functions above, used to check each other.
+pub const FUNCTIONS: [(&str, [u8; 4]); 7] = [
+    (MINT_SIGNATURE, MINT_SELECTOR),
+    (TRANSFER_SIGNATURE, TRANSFER_SELECTOR),
+    (BALANCE_OF_SIGNATURE, BALANCE_OF_SELECTOR),
+    (DECIMALS_SIGNATURE, DECIMALS_SELECTOR),
+    (SET_METADATA_SIGNATURE, SET_METADATA_SELECTOR),
+    (WITHDRAW_TO_NEAR_SIGNATURE, WITHDRAW_TO_NEAR_SELECTOR),
+    (
+        WITHDRAW_TO_ETHEREUM_SIGNATURE,
+        WITHDRAW_TO_ETHEREUM_SELECTOR,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{sdk, ToString, ERC20_MINT_SELECTOR};
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        for (signature, selector) in FUNCTIONS.iter() {
+            assert_eq!(
+                &sdk::keccak(signature.as_bytes()).as_bytes()[..4],
+                &selector[..],
+                "{}",
+                signature
+            );
+        }
+        assert_eq!(ERC20_MINT_SELECTOR, MINT_SELECTOR);
+    }
+
+    #[test]
+    fn test_encode_mint() {
+        let recipient = Address([0x11; 20]);
+        let input = encode_mint(recipient, U256::from(7));
+        assert_eq!(input.len(), 4 + 2 * 32);
+        assert_eq!(input[..4], MINT_SELECTOR);
+        assert_eq!(&input[16..36], recipient.as_bytes());
+        assert_eq!(U256::from_big_endian(&input[36..68]), U256::from(7));
+    }
+
+    #[test]
+    fn test_encode_constructor() {
+        let args = encode_constructor(
+            "Empty".to_string(),
+            "EMPTY".to_string(),
+            0,
+            Address([1; 20]),
+        );
+        let tokens = ethabi::decode(
+            &[
+                ethabi::ParamType::String,
+                ethabi::ParamType::String,
+                ethabi::ParamType::Uint(8),
+                ethabi::ParamType::Address,
+            ],
+            &args,
+        )
+        .unwrap();
+        assert_eq!(tokens[0], ethabi::Token::String("Empty".to_string()));
+        assert_eq!(tokens[3], ethabi::Token::Address(Address([1; 20])));
+    }
+
+    #[test]
+    fn test_decode_outputs() {
+        let balance = ethabi::encode(&[ethabi::Token::Uint(U256::from(1_000))]);
+        assert_eq!(decode_balance_of(&balance), Ok(U256::from(1_000)));
+        let decimals = ethabi::encode(&[ethabi::Token::Uint(U256::from(18))]);
+        assert_eq!(decode_decimals(&decimals), Ok(18));
+
+        let too_large = ethabi::encode(&[ethabi::Token::Uint(U256::from(256))]);
+        assert_eq!(
+            decode_decimals(&too_large),
+            Err(Erc20AbiError::InvalidOutput)
+        );
+        assert_eq!(decode_balance_of(&[]), Err(Erc20AbiError::InvalidOutput));
+        assert_eq!(encode_decimals(), DECIMALS_SELECTOR.to_vec());
+    }
+}
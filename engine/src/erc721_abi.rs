@@ -0,0 +1,114 @@
+//! Typed ABI of the ERC-721 contracts deployed by the engine for bridged NEP-171 collections
+//! (see `etc/eth-contracts/contracts/EvmErc721.sol`).
+//!
+//! As for `erc20_abi`, the selectors are constants checked against their signatures in the
+//! tests.
+
+use crate::prelude::{sdk, Address, String, Vec, U256};
+
+pub const MINT_SIGNATURE: &str = "mint(address,string)";
+pub const MINT_SELECTOR: [u8; 4] = [0xd0, 0xde, 0xf5, 0x21];
+
+pub const OWNER_OF_SIGNATURE: &str = "ownerOf(uint256)";
+pub const OWNER_OF_SELECTOR: [u8; 4] = [0x63, 0x52, 0x21, 0x1e];
+
+pub const NEAR_TOKEN_ID_SIGNATURE: &str = "nearTokenId(uint256)";
+pub const NEAR_TOKEN_ID_SELECTOR: [u8; 4] = [0x49, 0xd8, 0x4f, 0x48];
+
+pub const WITHDRAW_TO_NEAR_SIGNATURE: &str = "withdrawToNear(bytes,uint256)";
+pub const WITHDRAW_TO_NEAR_SELECTOR: [u8; 4] = [0x6b, 0x35, 0x18, 0x48];
+
+fn encode_call(selector: [u8; 4], args: &[ethabi::Token]) -> Vec<u8> {
+    [&selector[..], &ethabi::encode(args)].concat()
+}
+
+/// Arguments of the constructor, to be appended to the code of the contract.
+pub fn encode_constructor(name: String, symbol: String, admin: Address) -> Vec<u8> {
+    ethabi::encode(&[
+        ethabi::Token::String(name),
+        ethabi::Token::String(symbol),
+        ethabi::Token::Address(admin),
+    ])
+}
+
+/// ERC-721 id of the NEAR token, as computed by `tokenIdOf`.
+pub fn token_id_of(near_token_id: &str) -> U256 {
+    U256::from_big_endian(sdk::keccak(near_token_id.as_bytes()).as_bytes())
+}
+
+pub fn encode_mint(recipient: Address, near_token_id: String) -> Vec<u8> {
+    encode_call(
+        MINT_SELECTOR,
+        &[
+            ethabi::Token::Address(recipient),
+            ethabi::Token::String(near_token_id),
+        ],
+    )
+}
+
+pub fn encode_owner_of(token_id: U256) -> Vec<u8> {
+    encode_call(OWNER_OF_SELECTOR, &[ethabi::Token::Uint(token_id)])
+}
+
+pub fn encode_near_token_id(token_id: U256) -> Vec<u8> {
+    encode_call(NEAR_TOKEN_ID_SELECTOR, &[ethabi::Token::Uint(token_id)])
+}
+
+pub fn encode_withdraw_to_near(recipient: Vec<u8>, token_id: U256) -> Vec<u8> {
+    encode_call(
+        WITHDRAW_TO_NEAR_SELECTOR,
+        &[
+            ethabi::Token::Bytes(recipient),
+            ethabi::Token::Uint(token_id),
+        ],
+    )
+}
+
+/// Signatures and selectors of all the functions above, used to check each other.
+pub const FUNCTIONS: [(&str, [u8; 4]); 4] = [
+    (MINT_SIGNATURE, MINT_SELECTOR),
+    (OWNER_OF_SIGNATURE, OWNER_OF_SELECTOR),
+    (NEAR_TOKEN_ID_SIGNATURE, NEAR_TOKEN_ID_SELECTOR),
+    (WITHDRAW_TO_NEAR_SIGNATURE, WITHDRAW_TO_NEAR_SELECTOR),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::ToString;
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        for (signature, selector) in FUNCTIONS.iter() {
+            assert_eq!(
+                &sdk::keccak(signature.as_bytes()).as_bytes()[..4],
+                &selector[..],
+                "{}",
+                signature
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_mint() {
+        let recipient = Address([0x11; 20]);
+        let input = encode_mint(recipient, "token-1".to_string());
+        assert_eq!(input[..4], MINT_SELECTOR);
+        let tokens = ethabi::decode(
+            &[ethabi::ParamType::Address, ethabi::ParamType::String],
+            &input[4..],
+        )
+        .unwrap();
+        assert_eq!(tokens[0], ethabi::Token::Address(recipient));
+        assert_eq!(tokens[1], ethabi::Token::String("token-1".to_string()));
+    }
+
+    #[test]
+    fn test_token_id_of() {
+        assert_eq!(
+            token_id_of("token-1"),
+            U256::from_big_endian(sdk::keccak(b"token-1").as_bytes())
+        );
+        assert_ne!(token_id_of("token-1"), token_id_of("token-2"));
+    }
+}
@@ -0,0 +1,23 @@
+//! Crate-wide error helpers.
+//!
+//! Errors of the engine are `ERR_*` byte strings (see their `AsRef<[u8]>` implementations).
+//! Those which can reach EVM callers also implement `RevertError`, so they can be returned
+//! as Solidity-compatible revert payloads instead.
+
+use crate::connector::error::{
+    DepositError, FinishDepositError, FtTransferCallError, WithdrawError,
+};
+pub use crate::engine::{EngineError, EngineErrorKind};
+use crate::operator::OperatorError;
+pub use aurora_engine_types::error::{aurora_error, revert_reason, RevertData, RevertError};
+
+/// An engine result.
+pub type EngineResult<T> = Result<T, EngineError>;
+
+impl RevertError for EngineError {}
+impl RevertError for EngineErrorKind {}
+impl RevertError for DepositError {}
+impl RevertError for FinishDepositError {}
+impl RevertError for WithdrawError {}
+impl RevertError for FtTransferCallError {}
+impl RevertError for OperatorError {}
@@ -0,0 +1,207 @@
+//! Structured events emitted by the engine.
+//!
+//! Events follow the NEP-297 format: a log line made of the `EVENT_JSON:` prefix followed
+//! by a JSON object with the `standard`, `version`, `event` and `data` fields, so that
+//! indexers can pick them up without decoding the input or the traces of transactions.
+//!
+//! Admin methods emit `config_changed` with the previous and new value of the setting they
+//! change, so that monitoring can spot a configuration change nobody announced.
+
+use crate::engine::EngineState;
+use crate::json::JsonValue;
+use crate::parameters::{DeployErc20Result, PendingCustodianChange};
+use crate::prelude::{format, sdk, vec, AccountId, BTreeMap, EthAddress, String, ToString, Vec};
+
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+pub const EVENT_STANDARD: &str = "aurora";
+pub const EVENT_VERSION: &str = "1.0.0";
+
+/// Name of the event emitted when an ERC-20 contract is deployed for a NEP-141 token.
+pub const ERC20_DEPLOYED: &str = "erc20_deployed";
+/// Names of the events emitted at each step of a custodian change.
+pub const CUSTODIAN_CHANGE_PROPOSED: &str = "custodian_change_proposed";
+pub const CUSTODIAN_CHANGE_CONFIRMED: &str = "custodian_change_confirmed";
+pub const CUSTODIAN_CHANGE_CANCELLED: &str = "custodian_change_cancelled";
+/// Name of the event emitted when an admin method changes a setting of the engine.
+pub const CONFIG_CHANGED: &str = "config_changed";
+
+/// Log line of the event with the given name and data.
+pub fn event_log(event: &str, data: JsonValue) -> String {
+    nep297_log(EVENT_STANDARD, EVENT_VERSION, event, vec![data])
+}
+
+/// Log line of an event of any NEP-297 standard, such as the NEP-141 events.
+pub fn nep297_log(standard: &str, version: &str, event: &str, data: Vec<JsonValue>) -> String {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "standard".to_string(),
+        JsonValue::String(standard.to_string()),
+    );
+    kvs.insert(
+        "version".to_string(),
+        JsonValue::String(version.to_string()),
+    );
+    kvs.insert("event".to_string(), JsonValue::String(event.to_string()));
+    kvs.insert("data".to_string(), JsonValue::Array(data));
+    format!("{}{}", EVENT_JSON_PREFIX, JsonValue::Object(kvs))
+}
+
+#[cfg_attr(not(feature = "log"), allow(unused_variables))]
+pub fn emit(event: &str, data: JsonValue) {
+    sdk::log!(event_log(event, data).as_str());
+}
+
+pub fn emit_erc20_deployed(result: &DeployErc20Result) {
+    emit(ERC20_DEPLOYED, JsonValue::from(result));
+}
+
+pub fn emit_custodian_change(
+    event: &str,
+    current_custodian: &EthAddress,
+    change: &PendingCustodianChange,
+) {
+    emit(event, custodian_change_data(current_custodian, change));
+}
+
+/// Emits `config_changed` with the previous and new values of `setting`, unless they are
+/// the same. `previous` is `JsonValue::Null` when the setting was not set before.
+pub fn emit_config_changed(setting: &str, previous: JsonValue, new: JsonValue) {
+    if previous != new {
+        emit(CONFIG_CHANGED, config_changed_data(setting, previous, new));
+    }
+}
+
+/// Emits `config_changed` for each field of the engine state changed by `new`.
+pub fn emit_state_changed(previous: Option<&EngineState>, new: &EngineState) {
+    let account = |account: &AccountId| JsonValue::String(account.to_string());
+    emit_config_changed(
+        "owner",
+        previous.map_or(JsonValue::Null, |state| account(&state.owner_id)),
+        account(&new.owner_id),
+    );
+    emit_config_changed(
+        "bridge_prover",
+        previous.map_or(JsonValue::Null, |state| account(&state.bridge_prover_id)),
+        account(&new.bridge_prover_id),
+    );
+    emit_config_changed(
+        "chain_id",
+        previous.map_or(JsonValue::Null, |state| {
+            JsonValue::String(hex::encode(state.chain_id))
+        }),
+        JsonValue::String(hex::encode(new.chain_id)),
+    );
+    emit_config_changed(
+        "upgrade_delay_blocks",
+        previous.map_or(JsonValue::Null, |state| {
+            JsonValue::U64(state.upgrade_delay_blocks)
+        }),
+        JsonValue::U64(new.upgrade_delay_blocks),
+    );
+}
+
+fn config_changed_data(setting: &str, previous: JsonValue, new: JsonValue) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "setting".to_string(),
+        JsonValue::String(setting.to_string()),
+    );
+    kvs.insert("previous".to_string(), previous);
+    kvs.insert("new".to_string(), new);
+    JsonValue::Object(kvs)
+}
+
+fn custodian_change_data(
+    current_custodian: &EthAddress,
+    change: &PendingCustodianChange,
+) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "current_custodian".to_string(),
+        JsonValue::String(hex::encode(current_custodian)),
+    );
+    kvs.insert(
+        "new_custodian".to_string(),
+        JsonValue::String(hex::encode(change.new_custodian)),
+    );
+    kvs.insert(
+        "proposed_at".to_string(),
+        JsonValue::U64(change.proposed_at),
+    );
+    kvs.insert("expires_at".to_string(), JsonValue::U64(change.expires_at));
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::AccountId;
+
+    #[test]
+    fn test_erc20_deployed_event() {
+        let result = DeployErc20Result::new(
+            AccountId::new("token.near").unwrap(),
+            EthAddress::new([0x11; 20]),
+            0,
+            1,
+        );
+        let log = event_log(ERC20_DEPLOYED, JsonValue::from(&result));
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        assert_eq!(value["standard"], "aurora");
+        assert_eq!(value["event"], "erc20_deployed");
+        let data = &value["data"][0];
+        assert_eq!(data["nep141"], "token.near");
+        assert_eq!(data["address"], hex::encode([0x11; 20]));
+        assert_eq!(data["decimals"], 0);
+        assert_eq!(data["template_version"], 1);
+    }
+
+    #[test]
+    fn test_custodian_change_event() {
+        let change = PendingCustodianChange {
+            new_custodian: EthAddress::new([0x22; 20]),
+            proposed_at: 10,
+            expires_at: 20,
+        };
+        let log = event_log(
+            CUSTODIAN_CHANGE_PROPOSED,
+            custodian_change_data(&EthAddress::new([0x11; 20]), &change),
+        );
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        assert_eq!(value["event"], "custodian_change_proposed");
+        let data = &value["data"][0];
+        assert_eq!(data["current_custodian"], hex::encode([0x11; 20]));
+        assert_eq!(data["new_custodian"], hex::encode([0x22; 20]));
+        assert_eq!(data["proposed_at"], 10);
+        assert_eq!(data["expires_at"], 20);
+    }
+
+    #[test]
+    fn test_config_changed_event() {
+        let log = event_log(
+            CONFIG_CHANGED,
+            config_changed_data(
+                "owner",
+                JsonValue::String("alice.near".to_string()),
+                JsonValue::String("bob.near".to_string()),
+            ),
+        );
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        assert_eq!(value["event"], "config_changed");
+        let data = &value["data"][0];
+        assert_eq!(data["setting"], "owner");
+        assert_eq!(data["previous"], "alice.near");
+        assert_eq!(data["new"], "bob.near");
+
+        let data = config_changed_data("paused_flags", JsonValue::Null, JsonValue::U64(1));
+        let value: serde_json::Value = serde_json::from_str(&data.to_string()).unwrap();
+        assert!(value["previous"].is_null());
+        assert_eq!(value["new"], 1);
+    }
+}
@@ -0,0 +1,76 @@
+//! Counting of the host functions called by a transaction.
+//!
+//! `ProfilingIO` wraps the IO given to the engine and counts the storage host functions
+//! it calls into an `ExecutionProfile`. Promises are not made through the IO; they are
+//! filled in by the caller from `SubmitResult::promises`. Profiling is only done by the
+//! standalone engine, so that the result returned on chain does not depend on it.
+
+use crate::parameters::ExecutionProfile;
+use aurora_engine_sdk::io::IO;
+use core::cell::Cell;
+
+/// IO counting the storage accesses made through it.
+#[derive(Clone, Copy)]
+pub struct ProfilingIO<'p, I> {
+    inner: I,
+    profile: &'p Cell<ExecutionProfile>,
+}
+
+impl<'p, I: IO> ProfilingIO<'p, I> {
+    pub fn new(inner: I, profile: &'p Cell<ExecutionProfile>) -> Self {
+        Self { inner, profile }
+    }
+
+    fn count<F: FnOnce(&mut ExecutionProfile) -> &mut u32>(&self, field: F) {
+        let mut profile = self.profile.get();
+        let counter = field(&mut profile);
+        *counter = counter.saturating_add(1);
+        self.profile.set(profile);
+    }
+}
+
+impl<'p, I: IO> IO for ProfilingIO<'p, I> {
+    type StorageValue = I::StorageValue;
+
+    fn read_input(&self) -> Self::StorageValue {
+        self.inner.read_input()
+    }
+
+    fn return_output(&mut self, value: &[u8]) {
+        self.inner.return_output(value)
+    }
+
+    fn read_storage(&self, key: &[u8]) -> Option<Self::StorageValue> {
+        self.count(|p| &mut p.storage_reads);
+        self.inner.read_storage(key)
+    }
+
+    fn storage_has_key(&self, key: &[u8]) -> bool {
+        self.count(|p| &mut p.storage_reads);
+        self.inner.storage_has_key(key)
+    }
+
+    fn write_storage(&mut self, key: &[u8], value: &[u8]) -> Option<Self::StorageValue> {
+        self.count(|p| &mut p.storage_writes);
+        self.inner.write_storage(key, value)
+    }
+
+    fn write_storage_direct(
+        &mut self,
+        key: &[u8],
+        value: Self::StorageValue,
+    ) -> Option<Self::StorageValue> {
+        self.count(|p| &mut p.storage_writes);
+        self.inner.write_storage_direct(key, value)
+    }
+
+    fn remove_storage(&mut self, key: &[u8]) -> Option<Self::StorageValue> {
+        self.count(|p| &mut p.storage_removes);
+        self.inner.remove_storage(key)
+    }
+
+    fn read_storage_len(&self, key: &[u8]) -> Option<usize> {
+        self.count(|p| &mut p.storage_reads);
+        self.inner.read_storage_len(key)
+    }
+}
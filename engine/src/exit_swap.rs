@@ -0,0 +1,35 @@
+//! DEXes allowed by the owner for the exits to NEAR which swap the withdrawn tokens (see
+//! `aurora_engine_precompiles::exit_swap`).
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::precompiles::exit_swap::dex_key;
+use crate::prelude::{AccountId, BTreeMap, ToString};
+use aurora_engine_sdk::io::IO;
+
+pub fn is_dex_allowed<I: IO>(io: &I, dex: &AccountId) -> bool {
+    io.storage_has_key(&dex_key(dex))
+}
+
+/// Allows (or disallows) exits to NEAR to swap the withdrawn tokens on the DEX. Swaps
+/// already scheduled are not affected.
+pub fn set_dex_allowed<I: IO>(io: &mut I, dex: &AccountId, allowed: bool) {
+    let previous = is_dex_allowed(io, dex);
+    if allowed {
+        io.write_storage(&dex_key(dex), &[1]);
+    } else {
+        io.remove_storage(&dex_key(dex));
+    }
+    events::emit_config_changed(
+        "exit_swap_dex",
+        dex_data(dex, previous),
+        dex_data(dex, allowed),
+    );
+}
+
+fn dex_data(dex: &AccountId, allowed: bool) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert("dex".to_string(), JsonValue::String(dex.to_string()));
+    kvs.insert("allowed".to_string(), JsonValue::Bool(allowed));
+    JsonValue::Object(kvs)
+}
@@ -0,0 +1,282 @@
+//! Bridge fee schedules.
+//!
+//! The owner can set, for each bridged token, the fee charged on deposits (transfers into
+//! the bridge) and on withdrawals (transfers out of it). Each direction has a flat part and
+//! a part proportional to the amount. The token is identified by its NEP-141 account; the
+//! schedule of ETH is the one of the engine account (nETH).
+//!
+//! A direction without fee keeps the legacy behaviour: the fee of a deposit is then the
+//! one chosen by the depositor in the event, and withdrawals are free. Otherwise:
+//! - the deposit fee of ETH replaces the fee of the event, and is paid to the relayer as
+//!   before;
+//! - the withdraw fee of ETH is kept from the withdrawn amount and credited as nETH to the
+//!   fee collector set by the owner. The nETH of the engine account backs the ETH on
+//!   Aurora, so it cannot take the fee. Withdrawals are free while no collector is set;
+//! - the deposit fee of other NEP-141 tokens is taken in tokens and minted to the relayer,
+//!   on top of the ETH fee the `ft_on_transfer` message may set.
+//!
+//! Withdrawals of other tokens go through the exit precompiles, which charge no fee: the
+//! withdraw rate of a token other than ETH must stay zero.
+
+use crate::json::JsonValue;
+use crate::prelude::{
+    bytes_to_key, sdk, AccountId, BTreeMap, Balance, BorshDeserialize, BorshSerialize, Fee,
+    KeyPrefix, ToString, Vec, U256,
+};
+use crate::protocol_fee::MAX_FEE_BASIS_POINTS;
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const FEE_SCHEDULE_KEY: &[u8; 12] = b"FEE_SCHEDULE";
+const FEE_COLLECTOR_KEY: &[u8; 13] = b"FEE_COLLECTOR";
+
+/// Name of the event emitted when the fee schedule of a token changes.
+pub const FEE_SCHEDULE_CHANGED: &str = "fee_schedule_changed";
+
+/// Fee charged in one direction.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeRate {
+    /// Part of the fee independent of the amount.
+    pub flat: Fee,
+    /// Part of the fee proportional to the amount, in basis points.
+    pub basis_points: u16,
+}
+
+impl FeeRate {
+    pub fn is_zero(&self) -> bool {
+        self.flat.into_u128() == 0 && self.basis_points == 0
+    }
+
+    /// Fee charged on a transfer of the given amount. It saturates instead of overflowing;
+    /// callers reject transfers whose amount does not cover the fee.
+    pub fn fee_for(&self, amount: Balance) -> Fee {
+        let proportional =
+            U256::from(amount) * U256::from(self.basis_points) / U256::from(MAX_FEE_BASIS_POINTS);
+        // Cannot overflow: `basis_points` is at most `MAX_FEE_BASIS_POINTS`.
+        let proportional = proportional.low_u128();
+        Fee::new(self.flat.into_u128().saturating_add(proportional))
+    }
+}
+
+/// Fees of a bridged token, per direction.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeSchedule {
+    pub deposit: FeeRate,
+    pub withdraw: FeeRate,
+}
+
+impl FeeSchedule {
+    /// Fee of a deposit, given the fee set by the depositor.
+    pub fn deposit_fee(&self, amount: Balance, depositor_fee: Fee) -> Fee {
+        if self.deposit.is_zero() {
+            depositor_fee
+        } else {
+            self.deposit.fee_for(amount)
+        }
+    }
+
+    /// Fee of a withdrawal.
+    pub fn withdraw_fee(&self, amount: Balance) -> Fee {
+        self.withdraw.fee_for(amount)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeScheduleError {
+    FeeTooHigh,
+    WithdrawFeeNotSupported,
+    InvalidStoredSchedule,
+}
+
+impl AsRef<[u8]> for FeeScheduleError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::FeeTooHigh => b"ERR_FEE_TOO_HIGH",
+            Self::WithdrawFeeNotSupported => b"ERR_WITHDRAW_FEE_NOT_SUPPORTED",
+            Self::InvalidStoredSchedule => b"ERR_INVALID_FEE_SCHEDULE",
+        }
+    }
+}
+
+fn schedule_key(token: &AccountId) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[FEE_SCHEDULE_KEY.as_slice(), token.as_bytes()].concat(),
+    )
+}
+
+pub fn get_schedule<I: IO>(io: &I, token: &AccountId) -> Result<FeeSchedule, FeeScheduleError> {
+    match io.read_storage(&schedule_key(token)) {
+        None => Ok(FeeSchedule::default()),
+        Some(bytes) => FeeSchedule::try_from_slice(&bytes.to_vec())
+            .map_err(|_| FeeScheduleError::InvalidStoredSchedule),
+    }
+}
+
+/// Sets the fee schedule of `token`. Only ETH, whose token is `current_account_id`, can
+/// have a withdraw fee.
+pub fn set_schedule<I: IO>(
+    io: &mut I,
+    current_account_id: &AccountId,
+    token: &AccountId,
+    schedule: FeeSchedule,
+) -> Result<(), FeeScheduleError> {
+    if schedule.deposit.basis_points > MAX_FEE_BASIS_POINTS
+        || schedule.withdraw.basis_points > MAX_FEE_BASIS_POINTS
+    {
+        return Err(FeeScheduleError::FeeTooHigh);
+    }
+    if token != current_account_id && !schedule.withdraw.is_zero() {
+        return Err(FeeScheduleError::WithdrawFeeNotSupported);
+    }
+    let previous = get_schedule(io, token).ok();
+    if schedule == FeeSchedule::default() {
+        io.remove_storage(&schedule_key(token));
+    } else {
+        io.write_borsh(&schedule_key(token), &schedule);
+    }
+    crate::events::emit(
+        FEE_SCHEDULE_CHANGED,
+        schedule_data(token, &schedule, previous.as_ref()),
+    );
+    Ok(())
+}
+
+/// Fee schedule of a token, falling back to no fee if the stored one is broken, so that a
+/// bad schedule never blocks the bridge.
+pub(crate) fn get_schedule_or_default<I: IO>(io: &I, token: &AccountId) -> FeeSchedule {
+    get_schedule(io, token).unwrap_or_else(|_| {
+        sdk::log!(
+            crate::prelude::format!("Invalid fee schedule of {}, no fee charged", token).as_str()
+        );
+        FeeSchedule::default()
+    })
+}
+
+fn fee_collector_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::Config, FEE_COLLECTOR_KEY)
+}
+
+/// NEAR account credited with the withdraw fees of ETH, if any.
+pub fn get_fee_collector<I: IO>(io: &I) -> Option<AccountId> {
+    io.read_storage(&fee_collector_key())
+        .and_then(|collector| collector.to_value().ok())
+}
+
+/// Sets the account credited with the withdraw fees of ETH. `None` stops charging them.
+pub fn set_fee_collector<I: IO>(io: &mut I, collector: Option<AccountId>) {
+    let account = |account: &AccountId| JsonValue::String(account.to_string());
+    let previous = get_fee_collector(io);
+    match &collector {
+        Some(collector) => {
+            io.write_borsh(&fee_collector_key(), collector);
+        }
+        None => {
+            io.remove_storage(&fee_collector_key());
+        }
+    }
+    crate::events::emit_config_changed(
+        "withdraw_fee_collector",
+        previous.as_ref().map_or(JsonValue::Null, account),
+        collector.as_ref().map_or(JsonValue::Null, account),
+    );
+}
+
+fn rate_data(rate: &FeeRate) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    // As a string, like the other NEP-141 amounts, since it may not fit in a JSON number.
+    kvs.insert("flat".to_string(), JsonValue::String(rate.flat.to_string()));
+    kvs.insert(
+        "basis_points".to_string(),
+        JsonValue::U64(rate.basis_points as u64),
+    );
+    JsonValue::Object(kvs)
+}
+
+/// Data of the `fee_schedule_changed` event. `previous` is the schedule replaced, `None`
+/// if the stored one could not be read.
+fn schedule_data(
+    token: &AccountId,
+    schedule: &FeeSchedule,
+    previous: Option<&FeeSchedule>,
+) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert("token".to_string(), JsonValue::String(token.to_string()));
+    kvs.insert("deposit".to_string(), rate_data(&schedule.deposit));
+    kvs.insert("withdraw".to_string(), rate_data(&schedule.withdraw));
+    let previous = previous.map_or(JsonValue::Null, |previous| {
+        let mut kvs = BTreeMap::new();
+        kvs.insert("deposit".to_string(), rate_data(&previous.deposit));
+        kvs.insert("withdraw".to_string(), rate_data(&previous.withdraw));
+        JsonValue::Object(kvs)
+    });
+    kvs.insert("previous".to_string(), previous);
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{event_log, EVENT_JSON_PREFIX};
+
+    fn schedule() -> FeeSchedule {
+        FeeSchedule {
+            deposit: FeeRate {
+                flat: Fee::new(10),
+                basis_points: 0,
+            },
+            withdraw: FeeRate {
+                flat: Fee::new(5),
+                basis_points: 30,
+            },
+        }
+    }
+
+    #[test]
+    fn test_fee_for() {
+        let schedule = schedule();
+        assert_eq!(schedule.withdraw_fee(10_000), Fee::new(35));
+        // rounding favours the user
+        assert_eq!(schedule.withdraw_fee(333), Fee::new(5));
+        assert_eq!(
+            FeeRate {
+                flat: Fee::new(u128::MAX),
+                basis_points: MAX_FEE_BASIS_POINTS,
+            }
+            .fee_for(u128::MAX),
+            Fee::new(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn test_deposit_fee() {
+        let schedule = schedule();
+        assert_eq!(schedule.deposit_fee(1_000, Fee::new(1)), Fee::new(10));
+        // Without deposit fee, the depositor chooses the fee.
+        assert_eq!(
+            FeeSchedule::default().deposit_fee(1_000, Fee::new(1)),
+            Fee::new(1)
+        );
+        assert_eq!(FeeSchedule::default().withdraw_fee(1_000), Fee::new(0));
+    }
+
+    #[test]
+    fn test_fee_schedule_changed_event() {
+        let token = AccountId::new("aurora").unwrap();
+        let log = event_log(
+            FEE_SCHEDULE_CHANGED,
+            schedule_data(&token, &schedule(), Some(&FeeSchedule::default())),
+        );
+        let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        assert_eq!(value["event"], "fee_schedule_changed");
+        let data = &value["data"][0];
+        assert_eq!(data["token"], "aurora");
+        assert_eq!(data["deposit"]["flat"], "10");
+        assert_eq!(data["deposit"]["basis_points"], 0);
+        assert_eq!(data["withdraw"]["flat"], "5");
+        assert_eq!(data["withdraw"]["basis_points"], 30);
+        assert_eq!(data["previous"]["deposit"]["flat"], "0");
+        assert_eq!(data["previous"]["withdraw"]["basis_points"], 0);
+    }
+}
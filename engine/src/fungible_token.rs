@@ -149,7 +149,7 @@ impl<I: IO + Copy> FungibleTokenOps<I> {
         &self,
         address: EthAddress,
     ) -> Result<Balance, crate::prelude::types::error::BalanceOverflowError> {
-        engine::get_balance(&self.io, &Address(address)).try_into_u128()
+        engine::get_balance(&self.io, &Address::from(address)).try_into_u128()
     }
 
     /// Internal ETH deposit to NEAR - nETH (NEP-141)
@@ -182,15 +182,16 @@ impl<I: IO + Copy> FungibleTokenOps<I> {
         let new_balance = balance
             .checked_add(amount)
             .ok_or(error::DepositError::BalanceOverflow)?;
-        engine::set_balance(
-            &mut self.io,
-            &Address(address),
-            &Wei::new(U256::from(new_balance)),
-        );
+        // Checked before writing the balance, so that a failed deposit changes nothing.
         self.total_eth_supply_on_aurora = self
             .total_eth_supply_on_aurora
             .checked_add(amount)
             .ok_or(error::DepositError::TotalSupplyOverflow)?;
+        engine::set_balance(
+            &mut self.io,
+            &Address::from(address),
+            &Wei::new(U256::from(new_balance)),
+        );
         Ok(())
     }
 
@@ -226,7 +227,7 @@ impl<I: IO + Copy> FungibleTokenOps<I> {
             .ok_or(error::WithdrawError::InsufficientFunds)?;
         engine::set_balance(
             &mut self.io,
-            &Address(address),
+            &Address::from(address),
             &Wei::new(U256::from(new_balance)),
         );
         self.total_eth_supply_on_aurora = self
@@ -380,25 +381,30 @@ impl<I: IO + Copy> FungibleTokenOps<I> {
                 } else {
                     receiver_balance
                 };
-                self.accounts_insert(receiver_id, receiver_balance - refund_amount);
+                // The callback must not fail, or the refund would be lost: the arithmetic
+                // saturates instead, and `refund_amount` is at most `receiver_balance` and
+                // `amount`.
+                let receiver_balance = receiver_balance.saturating_sub(refund_amount);
+                self.accounts_insert(receiver_id, receiver_balance);
                 sdk::log!(&crate::prelude::format!(
                     "Decrease receiver {} balance to: {}",
                     receiver_id,
-                    receiver_balance - refund_amount
+                    receiver_balance
                 ));
 
                 return if let Some(sender_balance) = self.get_account_eth_balance(sender_id) {
-                    self.accounts_insert(sender_id, sender_balance + refund_amount);
+                    self.accounts_insert(sender_id, sender_balance.saturating_add(refund_amount));
                     sdk::log!(&crate::prelude::format!(
                         "Refund amount {} from {} to {}",
                         refund_amount,
                         receiver_id,
                         sender_id
                     ));
-                    (amount - refund_amount, 0)
+                    (amount.saturating_sub(refund_amount), 0)
                 } else {
                     // Sender's account was deleted, so we need to burn tokens.
-                    self.total_eth_supply_on_near -= refund_amount;
+                    self.total_eth_supply_on_near =
+                        self.total_eth_supply_on_near.saturating_sub(refund_amount);
                     sdk::log!("The account of the sender was deleted");
                     (amount, refund_amount)
                 };
@@ -426,8 +432,11 @@ impl<I: IO + Copy> FungibleTokenOps<I> {
         let force = force.unwrap_or(false);
         if let Some(balance) = self.get_account_eth_balance(&account_id) {
             if balance == 0 || force {
+                self.total_eth_supply_on_near = self
+                    .total_eth_supply_on_near
+                    .checked_sub(balance)
+                    .ok_or(error::StorageFundingError::TotalSupplyUnderflow)?;
                 self.accounts_remove(&account_id);
-                self.total_eth_supply_on_near -= balance;
                 let storage_deposit = self.storage_balance_of(&account_id);
                 let action = PromiseAction::Transfer {
                     // The `+ 1` is to cover the 1 yoctoNEAR necessary to call this function in the first place.
@@ -686,11 +695,13 @@ pub mod error {
         NoAvailableBalance,
         InsufficientDeposit,
         UnRegisterPositiveBalance,
+        TotalSupplyUnderflow,
     }
 
     impl AsRef<[u8]> for StorageFundingError {
         fn as_ref(&self) -> &[u8] {
             match self {
+                Self::TotalSupplyUnderflow => TOTAL_SUPPLY_UNDERFLOW,
                 Self::NotRegistered => b"ERR_ACCOUNT_NOT_REGISTERED",
                 Self::NoAvailableBalance => b"ERR_NO_AVAILABLE_BALANCE",
                 Self::InsufficientDeposit => b"ERR_ATTACHED_DEPOSIT_NOT_ENOUGH",
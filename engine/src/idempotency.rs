@@ -0,0 +1,159 @@
+//! Idempotency keys of the calls made from NEAR.
+//!
+//! Unlike a signed transaction, a `call` from a NEAR account has no nonce: a client which
+//! resends a call it believes lost would execute it twice. A client can instead attach an
+//! idempotency key to the call (`CallArgs::V3`). The outcome of a call with a key is
+//! recorded, and a later call with the same key from the same account returns that outcome
+//! without executing anything.
+//!
+//! The storage used stays bounded:
+//! - each caller has its own ring of `WINDOW` slots, so the calls of one account never
+//!   evict the records of another; the key of an older call of the account can be used again;
+//! - a record is only honoured for `RETENTION_BLOCKS` blocks after the call;
+//! - only the status (with return data longer than `MAX_RECORDED_DATA_LEN` replaced by its
+//!   hash), the gas used and the hash of the full result are recorded, not the logs.
+//!
+//! Calls which fail with an engine error are not recorded, as nothing was executed.
+
+use crate::parameters::{SubmitResult, TransactionStatus};
+use crate::prelude::{
+    bytes_to_key, sdk, Address, BorshDeserialize, BorshSerialize, KeyPrefix, RawH256, Vec, H256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const CALL_RESULT_KEY: &[u8; 11] = b"CALL_RESULT";
+const CALL_RESULT_SLOT_KEY: &[u8; 16] = b"CALL_RESULT_SLOT";
+const CALL_RESULT_NEXT_KEY: &[u8; 16] = b"CALL_RESULT_NEXT";
+
+/// Number of recorded calls of an account after which its oldest one is forgotten.
+pub const WINDOW: u64 = 64;
+
+/// Number of blocks (about a day) during which a recorded call is returned on retry.
+pub const RETENTION_BLOCKS: u64 = 86_400;
+
+/// Longest return (or revert) data recorded as is.
+pub const MAX_RECORDED_DATA_LEN: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdempotencyError {
+    /// The record of the key cannot be decoded.
+    InvalidRecord,
+}
+
+impl AsRef<[u8]> for IdempotencyError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidRecord => b"ERR_INVALID_IDEMPOTENCY_RECORD",
+        }
+    }
+}
+
+/// Outcome of a call with an idempotency key.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct RecordedCall {
+    pub block_height: u64,
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    /// Hash of the Borsh-encoded `SubmitResult` of the call.
+    pub result_hash: RawH256,
+    /// Slot of the ring of the caller holding the record.
+    pub slot: u64,
+}
+
+impl RecordedCall {
+    /// Result returned when the call is retried. Its logs are not recorded.
+    pub fn to_result(&self) -> SubmitResult {
+        SubmitResult::new(self.status.clone(), self.gas_used, Vec::new())
+    }
+}
+
+/// Key of the calls of `caller` with the given idempotency key.
+pub fn scoped_key(caller: &Address, idempotency_key: &RawH256) -> H256 {
+    sdk::keccak(&[caller.as_bytes(), idempotency_key.as_slice()].concat())
+}
+
+fn result_key(key: &H256) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[CALL_RESULT_KEY.as_slice(), key.as_bytes()].concat(),
+    )
+}
+
+fn slot_key(caller: &Address, slot: u64) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[
+            CALL_RESULT_SLOT_KEY.as_slice(),
+            caller.as_bytes(),
+            &slot.to_le_bytes()[..],
+        ]
+        .concat(),
+    )
+}
+
+fn next_key(caller: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[CALL_RESULT_NEXT_KEY.as_slice(), caller.as_bytes()].concat(),
+    )
+}
+
+fn read_record<I: IO>(io: &I, key: &H256) -> Result<Option<RecordedCall>, IdempotencyError> {
+    match io.read_storage(&result_key(key)) {
+        None => Ok(None),
+        Some(bytes) => RecordedCall::try_from_slice(&bytes.to_vec())
+            .map(Some)
+            .map_err(|_| IdempotencyError::InvalidRecord),
+    }
+}
+
+/// Call recorded for the key, if it was made less than `RETENTION_BLOCKS` blocks before
+/// `block_height`.
+pub fn get_record<I: IO>(
+    io: &I,
+    key: &H256,
+    block_height: u64,
+) -> Result<Option<RecordedCall>, IdempotencyError> {
+    Ok(read_record(io, key)?
+        .filter(|record| block_height < record.block_height.saturating_add(RETENTION_BLOCKS)))
+}
+
+/// Records the outcome of the call of `caller` with the key. The call takes the slot of an
+/// earlier record of the key, or else the next slot of the ring of the caller, forgetting
+/// the call recorded in it.
+pub fn record<I: IO>(
+    io: &mut I,
+    caller: &Address,
+    key: &H256,
+    result: &SubmitResult,
+    block_height: u64,
+) -> Result<(), IdempotencyError> {
+    let slot = match read_record(io, key)? {
+        Some(previous) => previous.slot,
+        None => {
+            let next_key = next_key(caller);
+            let next = io.read_u64(&next_key).unwrap_or(0);
+            let slot = next % WINDOW;
+            // A slot holds the storage key of the record in it.
+            if let Some(evicted) = io.read_storage(&slot_key(caller, slot)) {
+                io.remove_storage(&evicted.to_vec());
+            }
+            io.write_storage(&slot_key(caller, slot), &result_key(key));
+            io.write_storage(&next_key, &(next + 1).to_le_bytes());
+            slot
+        }
+    };
+    let result_hash = sdk::keccak(&result.try_to_vec().unwrap()).0;
+    let record = RecordedCall {
+        block_height,
+        status: result
+            .status
+            .clone()
+            .limit_return_data_to(MAX_RECORDED_DATA_LEN),
+        gas_used: result.gas_used,
+        result_hash,
+        slot,
+    };
+    io.write_borsh(&result_key(key), &record);
+    Ok(())
+}
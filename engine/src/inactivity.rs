@@ -0,0 +1,273 @@
+//! Inactivity sweep policy.
+//!
+//! When enabled by the owner, the engine remembers the last block height at which every
+//! EVM account was modified. Accounts left untouched for at least `inactivity_blocks`
+//! blocks can be evicted by the owner: their nonce, balance, code and storage are removed
+//! from the NEAR state and replaced by a single 32-byte commitment. Anyone holding the
+//! evicted data (e.g. from an indexer) can later resurrect the account by presenting the
+//! preimage of the commitment.
+//!
+//! Storage slots cannot be enumerated on-chain, so the owner must supply the complete set
+//! of non-zero slots when evicting. Slots which are not supplied become unreachable, the
+//! same way they do when an account self-destructs.
+
+use crate::engine;
+use crate::prelude::{
+    address_to_key, bytes_to_key, sdk, u256_to_arr, Address, BorshDeserialize, BorshSerialize,
+    KeyPrefix, RawH256, RawU256, Vec, Wei, H256, U256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const INACTIVITY_POLICY_KEY: &[u8; 17] = b"INACTIVITY_POLICY";
+
+/// Sub-prefix of `KeyPrefix::AccountActivity` for the height of the last modification.
+const LAST_TOUCHED_SUB_PREFIX: u8 = 0;
+/// Sub-prefix of `KeyPrefix::AccountActivity` for the commitments of evicted accounts.
+const COMMITMENT_SUB_PREFIX: u8 = 1;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InactivityPolicy {
+    /// Number of blocks an account must be left untouched before it can be evicted.
+    /// A value of zero switches the policy off.
+    pub inactivity_blocks: u64,
+    /// Height at which the policy was switched on. Accounts which were not modified since
+    /// are considered last touched at this height.
+    pub enabled_at: u64,
+}
+
+impl InactivityPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.inactivity_blocks != 0
+    }
+}
+
+/// Everything the engine keeps about an account. The commitment of an evicted account
+/// is the keccak hash of its borsh serialization.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvictedAccount {
+    pub nonce: RawU256,
+    pub balance: RawU256,
+    pub code: Vec<u8>,
+    /// Non-zero storage slots, sorted by key.
+    pub storage: Vec<(RawH256, RawH256)>,
+}
+
+impl EvictedAccount {
+    pub fn commitment(&self) -> H256 {
+        sdk::keccak(&self.try_to_vec().expect("ERR_SERIALIZE"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InactivityError {
+    PolicyDisabled,
+    InvalidStoredPolicy,
+    AccountStillActive,
+    AccountAlreadyEvicted,
+    StorageSlotMismatch,
+    AccountNotEvicted,
+    InvalidResurrectionProof,
+    CodeAlreadyPresent,
+    BalanceOverflow,
+}
+
+impl AsRef<[u8]> for InactivityError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::PolicyDisabled => b"ERR_INACTIVITY_POLICY_DISABLED",
+            Self::InvalidStoredPolicy => b"ERR_INVALID_INACTIVITY_POLICY",
+            Self::AccountStillActive => b"ERR_ACCOUNT_STILL_ACTIVE",
+            Self::AccountAlreadyEvicted => b"ERR_ACCOUNT_ALREADY_EVICTED",
+            Self::StorageSlotMismatch => b"ERR_STORAGE_SLOT_MISMATCH",
+            Self::AccountNotEvicted => b"ERR_ACCOUNT_NOT_EVICTED",
+            Self::InvalidResurrectionProof => b"ERR_INVALID_RESURRECTION_PROOF",
+            Self::CodeAlreadyPresent => b"ERR_CODE_ALREADY_PRESENT",
+            Self::BalanceOverflow => b"ERR_BALANCE_OVERFLOW",
+        }
+    }
+}
+
+fn last_touched_key(address: &Address) -> Vec<u8> {
+    let key = [&[LAST_TOUCHED_SUB_PREFIX], address.as_bytes()].concat();
+    bytes_to_key(KeyPrefix::AccountActivity, &key)
+}
+
+fn commitment_key(address: &Address) -> Vec<u8> {
+    let key = [&[COMMITMENT_SUB_PREFIX], address.as_bytes()].concat();
+    bytes_to_key(KeyPrefix::AccountActivity, &key)
+}
+
+pub fn get_policy<I: IO>(io: &I) -> Result<InactivityPolicy, InactivityError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, INACTIVITY_POLICY_KEY)) {
+        None => Ok(InactivityPolicy::default()),
+        Some(bytes) => InactivityPolicy::try_from_slice(&bytes.to_vec())
+            .map_err(|_| InactivityError::InvalidStoredPolicy),
+    }
+}
+
+/// Sets the inactivity period. Switching the policy on starts the clock for all accounts
+/// at `current_height`; changing the period of an enabled policy does not reset it.
+pub fn set_policy<I: IO>(
+    io: &mut I,
+    inactivity_blocks: u64,
+    current_height: u64,
+) -> Result<(), InactivityError> {
+    let previous = get_policy(io)?;
+    let enabled_at = if previous.is_enabled() {
+        previous.enabled_at
+    } else {
+        current_height
+    };
+    let policy = InactivityPolicy {
+        inactivity_blocks,
+        enabled_at,
+    };
+    io.write_borsh(
+        &bytes_to_key(KeyPrefix::Config, INACTIVITY_POLICY_KEY),
+        &policy,
+    );
+    sdk::log!(
+        crate::prelude::format!("Inactivity policy set to {} blocks", inactivity_blocks).as_str()
+    );
+    Ok(())
+}
+
+/// Height at which the account was last modified, as seen by the policy.
+pub fn get_last_touched<I: IO>(io: &I, address: &Address) -> Option<u64> {
+    io.read_u64(&last_touched_key(address)).ok()
+}
+
+/// Records that the account was modified at the given height.
+pub fn touch<I: IO>(io: &mut I, address: &Address, height: u64) {
+    io.write_storage(&last_touched_key(address), &height.to_le_bytes());
+}
+
+/// Commitment to the data of an evicted account, if the account is evicted.
+pub fn get_commitment<I: IO>(io: &I, address: &Address) -> Option<H256> {
+    io.read_storage(&commitment_key(address)).and_then(|value| {
+        if value.len() == 32 {
+            let mut buf = [0u8; 32];
+            value.copy_to_slice(&mut buf);
+            Some(H256(buf))
+        } else {
+            None
+        }
+    })
+}
+
+/// Removes an inactive account from the state, keeping only a commitment to its data.
+/// `storage` must contain all non-zero storage slots of the account.
+pub fn evict<I: IO>(
+    io: &mut I,
+    address: &Address,
+    mut storage: Vec<(RawH256, RawH256)>,
+    current_height: u64,
+) -> Result<H256, InactivityError> {
+    let policy = get_policy(io)?;
+    if !policy.is_enabled() {
+        return Err(InactivityError::PolicyDisabled);
+    }
+    if get_commitment(io, address).is_some() {
+        return Err(InactivityError::AccountAlreadyEvicted);
+    }
+    let last_touched = get_last_touched(io, address)
+        .unwrap_or(policy.enabled_at)
+        .max(policy.enabled_at);
+    if current_height < last_touched.saturating_add(policy.inactivity_blocks) {
+        return Err(InactivityError::AccountStillActive);
+    }
+
+    storage.sort_unstable();
+    storage.dedup();
+    let generation = engine::get_generation(io, address);
+    for (index, value) in storage.iter() {
+        let stored = engine::get_storage(io, address, &H256(*index), generation);
+        if stored.0 != *value || stored.is_zero() {
+            return Err(InactivityError::StorageSlotMismatch);
+        }
+    }
+
+    let account = EvictedAccount {
+        nonce: u256_to_arr(&engine::get_nonce(io, address)),
+        balance: engine::get_balance(io, address).to_bytes(),
+        code: engine::get_code(io, address),
+        storage,
+    };
+    let commitment = account.commitment();
+
+    for (index, _) in account.storage.iter() {
+        engine::remove_storage(io, address, &H256(*index), generation);
+    }
+    engine::set_generation(io, address, generation + 1);
+    engine::remove_nonce(io, address);
+    engine::remove_code(io, address);
+    // The ETH stays in circulation (the account can be resurrected), so the balance is
+    // removed without going through the connector.
+    io.remove_storage(&address_to_key(KeyPrefix::Balance, address));
+    io.remove_storage(&last_touched_key(address));
+    io.write_storage(&commitment_key(address), commitment.as_bytes());
+
+    sdk::log!(crate::prelude::format!(
+        "Evicted inactive account {}",
+        hex::encode(address.as_bytes())
+    )
+    .as_str());
+    Ok(commitment)
+}
+
+/// Restores an evicted account from the preimage of its commitment. Anything the account
+/// received after the eviction is kept: balances are added up and the highest nonce wins.
+pub fn resurrect<I: IO>(
+    io: &mut I,
+    address: &Address,
+    account: EvictedAccount,
+    current_height: u64,
+) -> Result<(), InactivityError> {
+    let commitment = get_commitment(io, address).ok_or(InactivityError::AccountNotEvicted)?;
+    if account.commitment() != commitment {
+        return Err(InactivityError::InvalidResurrectionProof);
+    }
+    if !account.code.is_empty() && engine::get_code_size(io, address) != 0 {
+        return Err(InactivityError::CodeAlreadyPresent);
+    }
+
+    let balance = engine::get_balance(io, address)
+        .checked_add(Wei::new(U256::from_big_endian(&account.balance)))
+        .ok_or(InactivityError::BalanceOverflow)?;
+    let nonce = U256::from_big_endian(&account.nonce).max(engine::get_nonce(io, address));
+
+    engine::set_nonce(io, address, &nonce);
+    engine::set_balance(io, address, &balance);
+    if !account.code.is_empty() {
+        engine::set_code(io, address, &account.code);
+    }
+    let generation = engine::get_generation(io, address);
+    for (index, value) in account.storage.iter() {
+        engine::set_storage(io, address, &H256(*index), &H256(*value), generation);
+    }
+    io.remove_storage(&commitment_key(address));
+    touch(io, address, current_height);
+
+    sdk::log!(
+        crate::prelude::format!("Resurrected account {}", hex::encode(address.as_bytes())).as_str()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_depends_on_storage() {
+        let mut account = EvictedAccount {
+            nonce: [0u8; 32],
+            balance: [1u8; 32],
+            code: Vec::new(),
+            storage: Vec::new(),
+        };
+        let empty_storage = account.commitment();
+        account.storage.push(([2u8; 32], [3u8; 32]));
+        assert_ne!(empty_storage, account.commitment());
+    }
+}
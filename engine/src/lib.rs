@@ -18,14 +18,52 @@ pub mod proof;
 pub mod transaction;
 
 pub mod admin_controlled;
+pub mod admin_simulation;
+pub mod balance_journal;
+pub mod bridge_stats;
+pub mod code_dedup;
+pub mod compliance_hook;
 #[cfg_attr(feature = "contract", allow(dead_code))]
 pub mod connector;
+pub mod connector_event;
+pub mod deploy_gate;
 pub mod deposit_event;
 pub mod engine;
+pub mod era;
+pub mod erc20_abi;
+pub mod erc721_abi;
+pub mod error;
+pub mod events;
+pub mod execution_profile;
+pub mod exit_swap;
+pub mod fee_schedule;
 pub mod fungible_token;
+pub mod idempotency;
+pub mod inactivity;
 pub mod json;
 pub mod log_entry;
+pub mod log_limits;
+pub mod min_gas_price;
+pub mod near_state;
+pub mod nft_connector;
+pub mod operator;
+pub mod precompile_gas;
+pub mod preflight;
 mod prelude;
+pub mod promise_budget;
+pub mod promise_gas;
+pub mod protocol_fee;
+pub mod selftest;
+pub mod state_commitment;
+pub mod state_migration;
+pub mod storage_sharding;
+pub mod stranded_eth;
+pub mod system_contracts;
+pub mod token_amounts;
+pub mod token_templates;
+pub mod transfer_events;
+pub mod upgrade_history;
+pub mod xcc;
 
 #[cfg(target_arch = "wasm32")]
 #[global_allocator]
@@ -66,33 +104,77 @@ pub unsafe fn on_alloc_error(_: core::alloc::Layout) -> ! {
 mod contract {
     use borsh::{BorshDeserialize, BorshSerialize};
 
+    use crate::admin_simulation::{self, AdminAction};
+    use crate::balance_journal::{BalanceJournal, JournaledIO};
+    use crate::bridge_stats;
+    use crate::code_dedup;
+    use crate::compliance_hook;
     use crate::connector::{self, EthConnectorContract};
-    use crate::engine::{self, current_address, Engine, EngineState};
+    use crate::deploy_gate::{self, DeployMode};
+    use crate::engine::{self, current_address, Engine, EngineError, EngineErrorKind, EngineState};
+    use crate::era;
+    use crate::error::EngineResult;
+    use crate::events;
+    use crate::exit_swap;
+    use crate::fee_schedule;
     use crate::fungible_token::FungibleTokenMetadata;
+    use crate::inactivity;
+    use crate::min_gas_price;
+    use crate::near_state;
+    use crate::nft_connector;
+    use crate::operator;
     use crate::parameters::{
-        self, CallArgs, DeployErc20TokenArgs, GetErc20FromNep141CallArgs, GetStorageAtArgs,
-        InitCallArgs, IsUsedProofCallArgs, NEP141FtOnTransferArgs, NewCallArgs,
-        PauseEthConnectorCallArgs, ResolveTransferCallArgs, SetContractDataCallArgs,
-        StorageDepositCallArgs, StorageWithdrawCallArgs, TransferCallCallArgs, ViewCallArgs,
+        self, CallArgs, CallOnBehalfArgs, ConvertErc20AmountArgs, ConvertNep141AmountArgs,
+        DeployErc20TokenWithTemplateArgs, DeployErc721TokenArgs, EvictAccountArgs,
+        ForgetPendingWithdrawalArgs, GetErc20FromNep141CallArgs, GetFeeScheduleArgs,
+        GetMirroredNearStateArgs, GetOperatorAllowanceArgs, GetPendingWithdrawalsArgs,
+        GetStorageAtArgs, InitCallArgs, IsUsedProofCallArgs, MigrateStorageShardingArgs,
+        MirrorNearStateArgs, NEP141FtOnTransferArgs, NewCallArgs, NftOnTransferArgs,
+        OperatorApprovalArgs, OperatorRevocationArgs, PauseEthConnectorCallArgs,
+        RegisterErc20TemplateArgs, ResolveTransferCallArgs, ResurrectAccountArgs,
+        SetComplianceHookArgs, SetContractDataCallArgs, SetDeployAllowedArgs,
+        SetEip3607EnabledArgs, SetEthTransferEventsEnabledArgs, SetExitSwapDexArgs,
+        SetFeeCollectorArgs, SetFeeScheduleArgs, SetFreeTierArgs, SetInactivityPolicyArgs,
+        SetMinGasPriceArgs, SetNearStateSourceArgs, SetPrecompileGasOverrideArgs,
+        StorageDepositCallArgs, StorageWithdrawCallArgs, SubmitResult, TransferCallCallArgs,
+        ViewCallArgs, ViewTransactionArgs, XccCallbackArgs,
     };
     #[cfg(feature = "evm_bully")]
     use crate::parameters::{BeginBlockArgs, BeginChainArgs};
+    use crate::precompile_gas;
+    use crate::preflight;
+    use crate::promise_budget::{self, PromiseBudget};
+    use crate::protocol_fee::{self, ProtocolFeeConfig};
+    use crate::selftest;
+    use crate::state_commitment;
+    use crate::storage_sharding;
+    use crate::stranded_eth;
+    use crate::system_contracts;
+    use crate::token_amounts;
+    use crate::token_templates;
+    use crate::transaction;
+    use crate::transfer_events;
+    use crate::upgrade_history;
+    use crate::xcc;
     use aurora_engine_sdk::env::Env;
     use aurora_engine_sdk::io::{StorageIntermediate, IO};
     use aurora_engine_sdk::near_runtime::Runtime;
     use aurora_engine_sdk::promise::PromiseHandler;
     use aurora_engine_types::account_id::AccountId;
+    use core::cell::RefCell;
 
     use crate::json::parse_json;
     use crate::prelude::parameters::RefundCallArgs;
+    use crate::prelude::precompiles::exit_swap::unused_amount;
+    use crate::prelude::precompiles::xcc::CrossContractCall;
     use crate::prelude::sdk::types::{
         near_account_to_evm_address, SdkExpect, SdkProcess, SdkUnwrap,
     };
     use crate::prelude::storage::{bytes_to_key, KeyPrefix};
     use crate::prelude::types::{u256_to_arr, ERR_FAILED_PARSE};
     use crate::prelude::{
-        sdk, vec, Address, PromiseResult, ToString, TryFrom, TryInto, Vec, Wei,
-        ERC20_MINT_SELECTOR, H256, U256,
+        sdk, vec, Address, EthAddress, PromiseResult, RawAddress, ToString, TryFrom, TryInto, Vec,
+        Wei, H256, U256,
     };
 
     #[cfg(feature = "integration-test")]
@@ -111,12 +193,16 @@ mod contract {
     #[no_mangle]
     pub extern "C" fn new() {
         let mut io = Runtime;
-        if let Ok(state) = engine::get_state(&io) {
-            require_owner_only(&state, &io.predecessor_account_id());
+        let previous = engine::get_state(&io).ok();
+        if let Some(state) = &previous {
+            require_owner_only(state, &io.predecessor_account_id());
         }
 
         let args: NewCallArgs = io.read_input_borsh().sdk_unwrap();
-        engine::set_state(&mut io, args.into());
+        let state = EngineState::from(args);
+        events::emit_state_changed(previous.as_ref(), &state);
+        engine::set_state(&mut io, state);
+        system_contracts::install_genesis(&mut io).sdk_unwrap();
     }
 
     /// Get version of the contract.
@@ -153,6 +239,38 @@ mod contract {
         io.return_output(&engine::get_state(&io).sdk_unwrap().chain_id)
     }
 
+    /// Get the number of blocks between staging and deploying an upgrade, borsh-encoded.
+    #[no_mangle]
+    pub extern "C" fn get_upgrade_delay_blocks() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        io.return_output(
+            &state
+                .upgrade_delay_blocks
+                .try_to_vec()
+                .sdk_expect("ERR_SERIALIZE"),
+        );
+    }
+
+    /// Get the configuration of the engine and of its eth connector as a borsh-encoded
+    /// `EngineConfig`, for contracts which depend on it.
+    #[no_mangle]
+    pub extern "C" fn get_engine_config() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        let connector = EthConnectorContract::init_instance(io);
+        let config = parameters::EngineConfig {
+            chain_id: state.chain_id,
+            owner_id: state.owner_id,
+            bridge_prover_id: state.bridge_prover_id,
+            upgrade_delay_blocks: state.upgrade_delay_blocks,
+            prover_account: connector.get_prover_account(),
+            eth_custodian_address: connector.get_eth_custodian_address(),
+            custodian_council: connector.get_custodian_council(),
+        };
+        io.return_output(&config.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
     #[no_mangle]
     pub extern "C" fn get_upgrade_index() {
         let mut io = Runtime;
@@ -168,31 +286,363 @@ mod contract {
         let state = engine::get_state(&io).sdk_unwrap();
         let block_height = io.block_height();
         require_owner_only(&state, &io.predecessor_account_id());
-        io.read_input_and_store(&bytes_to_key(KeyPrefix::Config, CODE_KEY));
+        let code_key = bytes_to_key(KeyPrefix::Config, CODE_KEY);
+        let previous = io
+            .read_storage(&code_key)
+            .map(|code| sdk::keccak(&code.to_vec()));
+        let code = io.read_input().to_vec();
+        io.write_storage(&code_key, &code);
         io.write_storage(
             &bytes_to_key(KeyPrefix::Config, CODE_STAGE_KEY),
             &block_height.to_le_bytes(),
         );
+        upgrade_history::emit_staged(previous, sdk::keccak(&code));
     }
 
     /// Deploy staged upgrade.
     #[no_mangle]
     pub extern "C" fn deploy_upgrade() {
-        let io = Runtime;
+        let mut io = Runtime;
         let state = engine::get_state(&io).sdk_unwrap();
         let index = internal_get_upgrade_index();
         if io.block_height() <= index + state.upgrade_delay_blocks {
             sdk::panic_utf8(b"ERR_NOT_ALLOWED:TOO_EARLY");
         }
-        Runtime::self_deploy(&bytes_to_key(KeyPrefix::Config, CODE_KEY));
+        let code_key = bytes_to_key(KeyPrefix::Config, CODE_KEY);
+        if let Some(code) = io.read_storage(&code_key) {
+            upgrade_history::set_pending(&mut io, &code.to_vec());
+        }
+        Runtime::self_deploy(&code_key);
     }
 
     /// Called as part of the upgrade process (see `engine-sdk::self_deploy`). This function is meant
     /// to make any necessary changes to the state such that it aligns with the newly deployed
-    /// code.
+    /// code (see `state_migration`). A failure reverts the deployment of the code.
     #[no_mangle]
     pub extern "C" fn state_migration() {
-        // TODO: currently we don't have migrations
+        let mut io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+        let block_height = io.block_height();
+        crate::state_migration::run(&mut io, block_height).sdk_unwrap();
+        upgrade_history::activate_pending(&mut io, block_height).sdk_unwrap();
+        system_contracts::install_genesis(&mut io).sdk_unwrap();
+    }
+
+    /// Runs the self-test of the engine, to be called after an upgrade before unpausing.
+    /// Returns the borsh-encoded `SelfTestReport`, which lists the checks which failed.
+    #[no_mangle]
+    pub extern "C" fn post_upgrade_selftest() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let current_account_id = io.current_account_id();
+        let report = selftest::run(io, &io, current_account_id);
+        io.return_output(&report.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Version of the state layout, migrated by `state_migration` (borsh encoded).
+    #[no_mangle]
+    pub extern "C" fn get_state_version() {
+        let mut io = Runtime;
+        let version = crate::state_migration::get_version(&io).sdk_unwrap();
+        io.return_output(&version.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Code hashes of the deployed upgrades with their activation heights (borsh encoded).
+    #[no_mangle]
+    pub extern "C" fn get_upgrade_history() {
+        let mut io = Runtime;
+        let history = upgrade_history::get_history(&io).sdk_unwrap();
+        io.return_output(&history.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// System contracts installed from the genesis manifest (borsh encoded), if any.
+    #[no_mangle]
+    pub extern "C" fn get_system_contracts() {
+        let mut io = Runtime;
+        let installed = system_contracts::get_installed(&io).sdk_unwrap();
+        io.return_output(&installed.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Set the protocol fee configuration (treasury address and fee share).
+    /// Setting the fee to zero switches the protocol fee off.
+    #[no_mangle]
+    pub extern "C" fn set_protocol_fee() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let config: ProtocolFeeConfig = io.read_input_borsh().sdk_unwrap();
+        protocol_fee::set_config(&mut io, config).sdk_unwrap();
+    }
+
+    /// Set the maximum number of NEAR promises, and the maximum NEAR attached to them,
+    /// that a single EVM transaction can schedule.
+    #[no_mangle]
+    pub extern "C" fn set_promise_budget() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let budget: PromiseBudget = io.read_input_borsh().sdk_unwrap();
+        promise_budget::set_budget(&mut io, budget);
+    }
+
+    /// Set the code of the routers making the cross-contract calls of EVM addresses (see
+    /// `xcc`). Routers created before keep their code.
+    #[no_mangle]
+    pub extern "C" fn factory_update() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let code = io.read_input().to_vec();
+        if code.is_empty() {
+            sdk::panic_utf8(b"ERR_EMPTY_ROUTER_CODE");
+        }
+        xcc::set_router_code(&mut io, &code);
+    }
+
+    /// Switch the EIP-3607 check (rejecting transactions signed for addresses with code)
+    /// on or off. It is on by default.
+    #[no_mangle]
+    pub extern "C" fn set_eip3607_enabled() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetEip3607EnabledArgs = io.read_input_borsh().sdk_unwrap();
+        engine::set_eip3607_enabled(&mut io, args.enabled);
+    }
+
+    /// Switch on or off the NEP-141 `ft_transfer` events of the ETH moved by `submit` and
+    /// `call`. They are off by default.
+    #[no_mangle]
+    pub extern "C" fn set_eth_transfer_events_enabled() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetEthTransferEventsEnabledArgs = io.read_input_borsh().sdk_unwrap();
+        transfer_events::set_enabled(&mut io, args.enabled);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn is_eth_transfer_events_enabled() {
+        let mut io = Runtime;
+        let enabled = transfer_events::is_enabled(&io);
+        io.return_output(&enabled.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Set the minimum gas price (in Wei) of the transactions submitted to the engine.
+    /// Setting it to zero switches it off.
+    #[no_mangle]
+    pub extern "C" fn set_min_gas_price() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetMinGasPriceArgs = io.read_input_borsh().sdk_unwrap();
+        min_gas_price::set_min_gas_price(
+            &mut io,
+            Wei::new(U256::from_big_endian(&args.min_gas_price)),
+        )
+        .sdk_unwrap();
+    }
+
+    /// Set the mode of the deployment gate (borsh-encoded `DeployMode`).
+    #[no_mangle]
+    pub extern "C" fn set_deploy_mode() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let mode: DeployMode = io.read_input_borsh().sdk_unwrap();
+        deploy_gate::set_mode(&mut io, mode);
+    }
+
+    /// Add an address to the allowlist of the deployment gate, or remove it.
+    #[no_mangle]
+    pub extern "C" fn set_deploy_allowed() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetDeployAllowedArgs = io.read_input_borsh().sdk_unwrap();
+        deploy_gate::set_allowed(&mut io, &Address::from(args.address), args.allowed);
+    }
+
+    /// Set the monthly quota of transactions to a contract exempt from the minimum gas
+    /// price. A quota of zero removes the contract from the free tier.
+    #[no_mangle]
+    pub extern "C" fn set_free_tier() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetFreeTierArgs = io.read_input_borsh().sdk_unwrap();
+        min_gas_price::set_free_tier(&mut io, &Address::from(args.address), args.monthly_quota);
+    }
+
+    /// Scale the gas cost of a custom precompile, within the bounds of `precompile_gas`.
+    #[no_mangle]
+    pub extern "C" fn set_precompile_gas_override() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetPrecompileGasOverrideArgs = io.read_input_borsh().sdk_unwrap();
+        let precompiles = engine::precompiles(
+            era::at(io.block_height()),
+            io,
+            io.current_account_id(),
+            &io,
+            Vec::new(),
+        );
+        precompile_gas::set_percent(
+            &mut io,
+            &precompiles,
+            &Address::from(args.address),
+            args.percent,
+        )
+        .sdk_unwrap();
+    }
+
+    /// Set (or remove) the compliance contract consulted before a bridged ERC-20 is minted
+    /// or withdrawn.
+    #[no_mangle]
+    pub extern "C" fn set_compliance_hook() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetComplianceHookArgs = io.read_input_borsh().sdk_unwrap();
+        compliance_hook::set_hook(
+            &mut io,
+            &Address::from(args.token),
+            args.hook.map(Address::from),
+        );
+    }
+
+    /// Allow (or disallow) exits to NEAR to swap the withdrawn tokens on the given DEX.
+    #[no_mangle]
+    pub extern "C" fn set_exit_swap_dex() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetExitSwapDexArgs = io.read_input_borsh().sdk_unwrap();
+        exit_swap::set_dex_allowed(&mut io, &args.dex, args.allowed);
+    }
+
+    /// Allow (or disallow) a NEAR contract to publish values readable by the EVM through the
+    /// NEAR state read precompile.
+    #[no_mangle]
+    pub extern "C" fn set_near_state_source() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetNearStateSourceArgs = io.read_input_borsh().sdk_unwrap();
+        near_state::set_source(&mut io, &args.source, args.allowed);
+    }
+
+    /// Set the deposit and withdraw fees of a bridged token.
+    #[no_mangle]
+    pub extern "C" fn set_fee_schedule() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetFeeScheduleArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        fee_schedule::set_schedule(&mut io, &current_account_id, &args.token, args.schedule)
+            .sdk_unwrap();
+    }
+
+    /// Set the NEAR account credited with the withdraw fees of ETH.
+    #[no_mangle]
+    pub extern "C" fn set_fee_collector() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetFeeCollectorArgs = io.read_input_borsh().sdk_unwrap();
+        fee_schedule::set_fee_collector(&mut io, args.collector);
+    }
+
+    /// Set the number of blocks after which untouched EVM accounts can be evicted.
+    /// Setting it to zero switches the inactivity policy off.
+    #[no_mangle]
+    pub extern "C" fn set_inactivity_policy() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: SetInactivityPolicyArgs = io.read_input_borsh().sdk_unwrap();
+        let current_height = io.block_height();
+        inactivity::set_policy(&mut io, args.inactivity_blocks, current_height).sdk_unwrap();
+    }
+
+    /// Replace the state of an inactive EVM account by a commitment to it.
+    #[no_mangle]
+    pub extern "C" fn evict_inactive_account() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: EvictAccountArgs = io.read_input_borsh().sdk_unwrap();
+        let current_height = io.block_height();
+        let commitment = inactivity::evict(
+            &mut io,
+            &Address::from(args.address),
+            args.storage,
+            current_height,
+        )
+        .sdk_unwrap();
+        io.return_output(commitment.as_bytes());
+    }
+
+    /// Move the ETH sent to precompile addresses to the given address (20 bytes input).
+    /// Returns the amount moved, as 32 big-endian bytes.
+    #[no_mangle]
+    pub extern "C" fn reconcile_stranded_eth() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let recipient = io.read_input_arr20().sdk_unwrap();
+        let amount = stranded_eth::reconcile(&mut io, &Address(recipient)).sdk_unwrap();
+        io.return_output(&amount.to_bytes());
+    }
+
+    /// Move the code of the given accounts (borsh-encoded `Vec<[u8; 20]>`) to the
+    /// deduplicated code storage. Returns the number of accounts migrated (borsh `u32`).
+    #[no_mangle]
+    pub extern "C" fn migrate_code_dedup() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let addresses: Vec<RawAddress> = io.read_input_borsh().sdk_unwrap();
+        let migrated = code_dedup::migrate(&mut io, &addresses);
+        io.return_output(&migrated.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Switch the storage of a hot contract (20 bytes address input) to sharded keys.
+    /// Its existing slots must then be moved with `migrate_storage_sharding`.
+    #[no_mangle]
+    pub extern "C" fn enable_storage_sharding() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let address = io.read_input_arr20().sdk_unwrap();
+        storage_sharding::enable(&mut io, &Address(address)).sdk_unwrap();
+    }
+
+    /// Move a batch of storage slots of a contract to the sharded layout.
+    /// Returns the number of slots moved (borsh-encoded `u32`).
+    #[no_mangle]
+    pub extern "C" fn migrate_storage_sharding() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: MigrateStorageShardingArgs = io.read_input_borsh().sdk_unwrap();
+        let moved = storage_sharding::migrate(&mut io, &Address::from(args.address), &args.keys)
+            .sdk_unwrap();
+        io.return_output(&moved.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// End the storage migration of a contract (20 bytes address input). Slots which were
+    /// not migrated are lost.
+    #[no_mangle]
+    pub extern "C" fn finish_storage_sharding() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let address = io.read_input_arr20().sdk_unwrap();
+        storage_sharding::finish(&mut io, &Address(address)).sdk_unwrap();
     }
 
     ///
@@ -205,14 +655,11 @@ mod contract {
         let io = Runtime;
         let input = io.read_input().to_vec();
         let current_account_id = io.current_account_id();
-        let mut engine = Engine::new(
-            predecessor_address(&io.predecessor_account_id()),
-            current_account_id,
-            io,
-            &io,
-        )
-        .sdk_unwrap();
-        Engine::deploy_code_with_input(&mut engine, input, &mut Runtime)
+        let deployer = predecessor_address(&io.predecessor_account_id());
+        let mut engine = Engine::new(deployer, current_account_id, io, &io).sdk_unwrap();
+        deploy_gate::check(&io, &deployer)
+            .map_err(|e| EngineError::from(EngineErrorKind::DeployGate(e)))
+            .and_then(|()| Engine::deploy_code_with_input(&mut engine, input, &mut Runtime))
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
             .sdk_process();
         // TODO: charge for storage
@@ -224,15 +671,17 @@ mod contract {
         let io = Runtime;
         let bytes = io.read_input().to_vec();
         let args = CallArgs::deserialize(&bytes).sdk_expect("ERR_BORSH_DESERIALIZE");
-        let current_account_id = io.current_account_id();
-        let mut engine = Engine::new(
-            predecessor_address(&io.predecessor_account_id()),
-            current_account_id,
-            io,
-            &io,
-        )
-        .sdk_unwrap();
-        Engine::call_with_args(&mut engine, args, &mut Runtime)
+        let result = if transfer_events::is_enabled(&io) {
+            let journal = RefCell::new(BalanceJournal::new());
+            let result = execute_call(JournaledIO::new(io, &journal), args);
+            if result.is_ok() {
+                transfer_events::emit(&journal.borrow());
+            }
+            result
+        } else {
+            execute_call(io, args)
+        };
+        result
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
             .sdk_process();
         // TODO: charge for storage
@@ -247,15 +696,32 @@ mod contract {
         let current_account_id = io.current_account_id();
         let state = engine::get_state(&io).sdk_unwrap();
         let relayer_address = predecessor_address(&io.predecessor_account_id());
-        let result = engine::submit(
-            io,
-            &io,
-            &input,
-            state,
-            current_account_id,
-            relayer_address,
-            &mut Runtime,
-        );
+        let result = if transfer_events::is_enabled(&io) {
+            let journal = RefCell::new(BalanceJournal::new());
+            let result = engine::submit(
+                JournaledIO::new(io, &journal),
+                &io,
+                &input,
+                state,
+                current_account_id,
+                relayer_address,
+                &mut Runtime,
+            );
+            if result.is_ok() {
+                transfer_events::emit(&journal.borrow());
+            }
+            result
+        } else {
+            engine::submit(
+                io,
+                &io,
+                &input,
+                state,
+                current_account_id,
+                relayer_address,
+                &mut Runtime,
+            )
+        };
 
         result
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
@@ -295,6 +761,17 @@ mod contract {
             .sdk_process();
     }
 
+    /// Publish a value of the calling NEAR contract to the EVM, which must be allowed by the
+    /// owner (see `set_near_state_source`).
+    #[no_mangle]
+    pub extern "C" fn mirror_near_state() {
+        let mut io = Runtime;
+        let args: MirrorNearStateArgs = io.read_input_borsh().sdk_unwrap();
+        let source = io.predecessor_account_id();
+        let block_height = io.block_height();
+        near_state::publish(&mut io, &source, &args.key, args.value, block_height).sdk_unwrap();
+    }
+
     #[no_mangle]
     pub extern "C" fn register_relayer() {
         let io = Runtime;
@@ -312,11 +789,109 @@ mod contract {
         engine.register_relayer(predecessor_account_id.as_bytes(), Address(relayer_address));
     }
 
+    /// Approve a NEAR account as operator of an EVM address.
+    /// The approval is signed by the EVM address, so anyone can relay it.
+    #[no_mangle]
+    pub extern "C" fn approve_operator() {
+        let mut io = Runtime;
+        let args: OperatorApprovalArgs = io.read_input_borsh().sdk_unwrap();
+        let state = engine::get_state(&io).sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        operator::approve(&mut io, state.chain_id, &current_account_id, args).sdk_unwrap();
+    }
+
+    /// Revoke a NEAR account as operator of an EVM address.
+    /// The revocation is signed by the EVM address, so anyone can relay it.
+    #[no_mangle]
+    pub extern "C" fn revoke_operator() {
+        let mut io = Runtime;
+        let args: OperatorRevocationArgs = io.read_input_borsh().sdk_unwrap();
+        let state = engine::get_state(&io).sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        operator::revoke(&mut io, state.chain_id, &current_account_id, args).sdk_unwrap();
+    }
+
+    /// Execute an EVM call from an address which approved the predecessor as its operator.
+    #[no_mangle]
+    pub extern "C" fn call_on_behalf() {
+        let mut io = Runtime;
+        let args: CallOnBehalfArgs = io.read_input_borsh().sdk_unwrap();
+        let predecessor_account_id = io.predecessor_account_id();
+        let block_height = io.block_height();
+        operator::consume_allowance(&mut io, &predecessor_account_id, &args, block_height)
+            .sdk_unwrap();
+
+        let current_account_id = io.current_account_id();
+        let owner = Address::from(args.owner);
+        let mut engine = Engine::new(owner, current_account_id, io, &io).sdk_unwrap();
+        engine
+            .call(
+                owner,
+                Address::from(args.contract),
+                Wei::new(U256::from_big_endian(&args.value)),
+                args.input,
+                args.gas_limit,
+                Vec::new(),
+                &mut Runtime,
+            )
+            .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
+            .sdk_process();
+    }
+
+    /// Add the attached NEAR to the balance paying for the cross-contract calls of the
+    /// address given as input (20 bytes).
+    #[no_mangle]
+    pub extern "C" fn xcc_fund() {
+        let mut io = Runtime;
+        let address = io.read_input_arr20().sdk_unwrap();
+        let amount = io.attached_deposit();
+        xcc::fund(&mut io, &Address(address), amount);
+    }
+
+    /// Call back the EVM contract which made a cross-contract call, once the call is
+    /// resolved. The result of the call is the promise result of this method.
+    #[no_mangle]
+    pub extern "C" fn xcc_callback() {
+        let mut io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+        let args: XccCallbackArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let mut engine =
+            Engine::new(CrossContractCall::ADDRESS, current_account_id, io, &io).sdk_unwrap();
+        engine
+            .call(
+                CrossContractCall::ADDRESS,
+                Address::from(args.address),
+                Wei::zero(),
+                args.input,
+                u64::MAX,
+                Vec::new(),
+                &mut Runtime,
+            )
+            .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
+            .sdk_process();
+    }
+
+    /// Restore an evicted EVM account from the preimage of its commitment.
+    #[no_mangle]
+    pub extern "C" fn resurrect_account() {
+        let mut io = Runtime;
+        let args: ResurrectAccountArgs = io.read_input_borsh().sdk_unwrap();
+        let current_height = io.block_height();
+        inactivity::resurrect(
+            &mut io,
+            &Address::from(args.address),
+            args.account,
+            current_height,
+        )
+        .sdk_unwrap();
+    }
+
     /// Allow receiving NEP141 tokens to the EVM contract.
     ///
-    /// This function returns the amount of tokens to return to the sender.
-    /// Either all tokens are transferred tokens are returned in case of an
-    /// error, or no token is returned if tx was successful.
+    /// This function returns the amount of tokens to return to the sender: all of them if
+    /// the message is malformed or the tokens cannot be minted, none if the transfer was
+    /// successful. Failures are reported through this amount rather than by panicking.
     #[no_mangle]
     pub extern "C" fn ft_on_transfer() {
         let io = Runtime;
@@ -336,9 +911,7 @@ mod contract {
             .sdk_unwrap();
 
         if predecessor_account_id == current_account_id {
-            EthConnectorContract::init_instance(io)
-                .ft_on_transfer(&engine, &args)
-                .sdk_unwrap();
+            EthConnectorContract::init_instance(io).ft_on_transfer(&engine, &args);
         } else {
             let signer_account_id = io.signer_account_id();
             engine.receive_erc20_tokens(
@@ -351,125 +924,512 @@ mod contract {
         }
     }
 
-    /// Deploy ERC20 token mapped to a NEP141
+    /// Deploy ERC20 token mapped to a NEP141. The input is either `DeployErc20TokenArgs`, or
+    /// `DeployErc20TokenWithTemplateArgs` to choose the template of the ERC-20 contract.
+    #[no_mangle]
+    pub extern "C" fn deploy_erc20_token() {
+        let mut io = Runtime;
+        // Id of the NEP141 token in Near, and the template of the ERC-20
+        let args = DeployErc20TokenWithTemplateArgs::deserialize(&io.read_input().to_vec())
+            .sdk_expect("ERR_BORSH_DESERIALIZE");
+
+        let result = engine::deploy_erc20_token(args, io, &io, &mut Runtime).sdk_unwrap();
+
+        io.return_output(
+            &result
+                .address
+                .as_bytes()
+                .to_vec()
+                .try_to_vec()
+                .sdk_expect("ERR_SERIALIZE"),
+        );
+
+        // TODO: charge for storage
+    }
+
+    /// Same as `deploy_erc20_token`, but returns a versioned `DeployErc20Result` with the
+    /// metadata of the deployed token.
+    #[no_mangle]
+    pub extern "C" fn deploy_erc20_token_v2() {
+        let mut io = Runtime;
+        let args = DeployErc20TokenWithTemplateArgs::deserialize(&io.read_input().to_vec())
+            .sdk_expect("ERR_BORSH_DESERIALIZE");
+
+        let result = engine::deploy_erc20_token(args, io, &io, &mut Runtime).sdk_unwrap();
+
+        io.return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Register an ERC-20 template which `deploy_erc20_token` can deploy, see
+    /// `token_templates`.
+    #[no_mangle]
+    pub extern "C" fn register_erc20_template() {
+        let mut io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: RegisterErc20TemplateArgs = io.read_input_borsh().sdk_unwrap();
+        let info = token_templates::register(&mut io, args).sdk_unwrap();
+        io.return_output(&info.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// The built-in and registered ERC-20 templates, as a borsh-encoded
+    /// `Vec<Erc20TemplateInfo>`.
+    #[no_mangle]
+    pub extern "C" fn get_erc20_templates() {
+        let mut io = Runtime;
+        let templates = token_templates::get_templates(&io).sdk_unwrap();
+        io.return_output(&templates.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Template the ERC-20 token at the input address was deployed from, as a borsh-encoded
+    /// `Option<Erc20TemplateInfo>`.
+    #[no_mangle]
+    pub extern "C" fn get_erc20_template() {
+        let mut io = Runtime;
+        let address = io.read_input_arr20().sdk_unwrap();
+        let info = token_templates::get_deployed_template(&io, &Address(address));
+        io.return_output(&info.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Deploy the ERC-721 token mapped to a NEP-171 collection.
+    #[no_mangle]
+    pub extern "C" fn deploy_erc721_token() {
+        let mut io = Runtime;
+        let args: DeployErc721TokenArgs = io.read_input_borsh().sdk_unwrap();
+
+        let address = nft_connector::deploy_erc721_token(args, io, &io, &mut Runtime).sdk_unwrap();
+
+        io.return_output(
+            &address
+                .as_bytes()
+                .to_vec()
+                .try_to_vec()
+                .sdk_expect("ERR_SERIALIZE"),
+        );
+    }
+
+    /// NEP-171 `nft_on_transfer`: mints the token on the ERC-721 of the calling collection.
+    /// Returns `true`, so that the collection gives the token back, if it cannot be bridged.
+    #[no_mangle]
+    pub extern "C" fn nft_on_transfer() {
+        let mut io = Runtime;
+        let nep171 = io.predecessor_account_id();
+        let args: NftOnTransferArgs = parse_json(io.read_input().to_vec().as_slice())
+            .sdk_unwrap()
+            .try_into()
+            .sdk_unwrap();
+
+        let token_id = args.token_id.clone();
+        match nft_connector::nft_on_transfer(io, &io, &nep171, args, &mut Runtime) {
+            Ok(()) => io.return_output(b"false"),
+            Err(e) => {
+                sdk::log!(crate::prelude::format!(
+                    "Token {} of {} not bridged: {:?}",
+                    token_id,
+                    nep171,
+                    e
+                )
+                .as_str());
+                io.return_output(b"true")
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_erc721_from_nep171() {
+        let mut io = Runtime;
+        let nep171: AccountId = io.read_input().to_vec().try_into().sdk_unwrap();
+        io.return_output(
+            nft_connector::get_erc721(&io, &nep171)
+                .sdk_expect("ERR_NFT_COLLECTION_NOT_FOUND")
+                .as_bytes(),
+        );
+    }
+
+    /// Callback invoked by exit to NEAR precompile to handle potential
+    /// errors in the exit call, and the tokens given back after an exit swap.
+    #[no_mangle]
+    pub extern "C" fn refund_on_error() {
+        let io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+
+        // This function should only be called as the callback of
+        // exactly one promise.
+        if io.promise_results_count() != 1 {
+            sdk::panic_utf8(PROMISE_COUNT_ERR.as_bytes());
+        }
+
+        let args: RefundCallArgs = io.read_input_borsh().sdk_unwrap();
+        let amount = match io.promise_result(0) {
+            // An exit swap gives back the tokens which the DEX did not use
+            Some(PromiseResult::Successful(output)) => {
+                unused_amount(U256::from_big_endian(&args.amount), &output)
+            }
+            // Exit call failed; need to refund tokens
+            _ => U256::from_big_endian(&args.amount),
+        };
+        if !amount.is_zero() {
+            let current_account_id = io.current_account_id();
+            let refund_result = match args.erc20_address {
+                // ERC-20 exit; re-mint burned tokens
+                Some(erc20_address) => {
+                    let erc20_admin_address = current_address(&current_account_id);
+                    let mut engine =
+                        Engine::new(erc20_admin_address, current_account_id, io, &io).sdk_unwrap();
+                    let erc20_address = Address::from(erc20_address);
+                    let refund_address = Address::from(args.recipient_address);
+
+                    engine
+                        .call(
+                            erc20_admin_address,
+                            erc20_address,
+                            Wei::zero(),
+                            crate::erc20_abi::encode_mint(refund_address, amount),
+                            u64::MAX,
+                            Vec::new(),
+                            &mut Runtime,
+                        )
+                        .sdk_unwrap()
+                }
+                // ETH exit; transfer ETH back from precompile address
+                None => {
+                    let exit_address = aurora_engine_precompiles::native::ExitToNear::ADDRESS;
+                    let mut engine =
+                        Engine::new(exit_address, current_account_id, io, &io).sdk_unwrap();
+                    let refund_address = Address::from(args.recipient_address);
+                    let amount = Wei::new(amount);
+                    engine
+                        .call(
+                            exit_address,
+                            refund_address,
+                            amount,
+                            Vec::new(),
+                            u64::MAX,
+                            vec![(exit_address, Vec::new()), (refund_address, Vec::new())],
+                            &mut Runtime,
+                        )
+                        .sdk_unwrap()
+                }
+            };
+
+            if !refund_result.status.is_ok() {
+                sdk::panic_utf8(b"ERR_REFUND_FAILURE");
+            }
+        }
+    }
+
+    ///
+    /// NONMUTATIVE METHODS
+    ///
+    #[no_mangle]
+    pub extern "C" fn view() {
+        let mut io = Runtime;
+        let args: ViewCallArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let engine =
+            Engine::new(Address::from(args.sender), current_account_id, io, &io).sdk_unwrap();
+        let result = Engine::view_with_args(&engine, args).sdk_unwrap();
+        io.return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Runs the checks of `submit` on a transaction (same input) without executing it, and
+    /// returns a borsh-encoded `TransactionVerdict`.
+    #[no_mangle]
+    pub extern "C" fn validate_transaction() {
+        let mut io = Runtime;
+        let input = io.read_input().to_vec();
+        let state = engine::get_state(&io).sdk_unwrap();
+        let verdict = preflight::validate_transaction(
+            &io,
+            &state,
+            &input,
+            io.block_height(),
+            io.block_timestamp(),
+        );
+        io.return_output(&verdict.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Same as `view`, but returns a `SubmitResult`, including the gas used as `submit`
+    /// would compute it.
+    #[no_mangle]
+    pub extern "C" fn view_with_gas_used() {
+        let mut io = Runtime;
+        let args: ViewCallArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let origin = Address::from(args.sender);
+        let engine = Engine::new(origin, current_account_id, io, &io).sdk_unwrap();
+        let result = engine
+            .view_with_gas_used(
+                origin,
+                Address::from(args.address),
+                Wei::new(U256::from_big_endian(&args.amount)),
+                args.input,
+                u64::MAX,
+            )
+            .sdk_unwrap();
+        io.return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Runs an unsigned transaction (borsh-encoded `ViewTransactionArgs`) as if it was sent
+    /// by any address, like `eth_call`, without storing anything. Returns a `SubmitResult`.
+    #[no_mangle]
+    pub extern "C" fn view_transaction() {
+        let mut io = Runtime;
+        let args: ViewTransactionArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let engine =
+            Engine::new(Address::from(args.from), current_account_id, io, &io).sdk_unwrap();
+        let result = engine.view_transaction(args).sdk_unwrap();
+        io.return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Same as `view_with_gas_used`, also estimating the NEAR gas needed by the promises
+    /// the call would schedule (e.g. through the exit precompiles), which the relayer must
+    /// attach on top of the gas of the `submit` call itself, and searching the lowest gas
+    /// limit with which the call succeeds (`eth_estimateGas`).
+    #[no_mangle]
+    pub extern "C" fn estimate_gas() {
+        let mut io = Runtime;
+        let args: ViewCallArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let origin = Address::from(args.sender);
+        let engine = Engine::new(origin, current_account_id, io, &io).sdk_unwrap();
+        let estimate = engine
+            .estimate_gas(
+                origin,
+                Address::from(args.address),
+                Wei::new(U256::from_big_endian(&args.amount)),
+                args.input,
+                u64::MAX,
+            )
+            .sdk_unwrap();
+        io.return_output(&estimate.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Fields of a raw transaction (the input of `submit`) as a borsh-encoded
+    /// `DecodedTransaction`, without executing it.
+    #[no_mangle]
+    pub extern "C" fn decode_transaction() {
+        let mut io = Runtime;
+        let input = io.read_input().to_vec();
+        let decoded = transaction::decode(&input, era::at(io.block_height()).config()).sdk_unwrap();
+        io.return_output(&decoded.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Get the hash of the block at the given borsh-encoded height, the one returned by the
+    /// `BLOCKHASH` opcode for that height, see `engine::compute_block_hash`.
+    #[no_mangle]
+    pub extern "C" fn get_block_hash() {
+        let mut io = Runtime;
+        let block_height = io.read_input_borsh().sdk_unwrap();
+        let account_id = io.current_account_id();
+        let chain_id = engine::get_state(&io)
+            .map(|state| state.chain_id)
+            .sdk_unwrap();
+        let block_hash =
+            crate::engine::compute_block_hash(chain_id, block_height, account_id.as_bytes());
+        io.return_output(block_hash.as_bytes())
+    }
+
+    /// Get the height of the current block, i.e. the NEAR block height, borsh-encoded.
+    #[no_mangle]
+    pub extern "C" fn get_block_height() {
+        let mut io = Runtime;
+        let block_height = io.block_height();
+        io.return_output(&block_height.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Dry-run of an admin action: returns the configuration the engine would have after
+    /// the action, or the error the action would fail with. The state is not modified.
+    #[no_mangle]
+    pub extern "C" fn simulate_admin_action() {
+        let mut io = Runtime;
+        let action: AdminAction = io.read_input_borsh().sdk_unwrap();
+        let block_height = io.block_height();
+        let simulation = admin_simulation::simulate(io, action, block_height).sdk_unwrap();
+        io.return_output(&simulation.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    #[no_mangle]
+    pub extern "C" fn is_eip3607_enabled() {
+        let mut io = Runtime;
+        let enabled = engine::is_eip3607_enabled(&io);
+        io.return_output(&enabled.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Compliance contract of a bridged ERC-20 (20 bytes address input), as a
+    /// borsh-encoded `Option<EthAddress>`.
+    #[no_mangle]
+    pub extern "C" fn get_compliance_hook() {
+        let mut io = Runtime;
+        let token = io.read_input_arr20().sdk_unwrap();
+        let hook = compliance_hook::get_hook(&io, &Address(token)).map(EthAddress::from);
+        io.return_output(&hook.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Fee schedule of a bridged token (borsh-encoded `FeeSchedule`).
+    #[no_mangle]
+    pub extern "C" fn get_fee_schedule() {
+        let mut io = Runtime;
+        let args: GetFeeScheduleArgs = io.read_input_borsh().sdk_unwrap();
+        let schedule = fee_schedule::get_schedule(&io, &args.token).sdk_unwrap();
+        io.return_output(&schedule.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Account credited with the withdraw fees of ETH (borsh-encoded `Option<AccountId>`).
+    #[no_mangle]
+    pub extern "C" fn get_fee_collector() {
+        let mut io = Runtime;
+        let collector = fee_schedule::get_fee_collector(&io);
+        io.return_output(&collector.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Minimum gas price in Wei, as 32 big-endian bytes.
+    #[no_mangle]
+    pub extern "C" fn get_min_gas_price() {
+        let mut io = Runtime;
+        let min_gas_price = min_gas_price::get_min_gas_price(&io).sdk_unwrap();
+        io.return_output(&min_gas_price.to_bytes())
+    }
+
+    /// Mode of the deployment gate, as a borsh-encoded `DeployMode`.
+    #[no_mangle]
+    pub extern "C" fn get_deploy_mode() {
+        let mut io = Runtime;
+        let mode = deploy_gate::get_mode(&io);
+        io.return_output(&mode.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Whether the deployment gate lets an address (20 bytes input) deploy, as a borsh-encoded
+    /// `bool`.
+    #[no_mangle]
+    pub extern "C" fn is_deploy_allowed() {
+        let mut io = Runtime;
+        let address = io.read_input_arr20().sdk_unwrap();
+        let allowed = deploy_gate::check(&io, &Address(address)).is_ok();
+        io.return_output(&allowed.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Free tier of a contract (20 bytes address input), as a borsh-encoded
+    /// `Option<FreeTierStatus>`.
+    #[no_mangle]
+    pub extern "C" fn get_free_tier() {
+        let mut io = Runtime;
+        let address = io.read_input_arr20().sdk_unwrap();
+        let status =
+            min_gas_price::get_free_tier(&io, &Address(address), io.block_timestamp()).sdk_unwrap();
+        io.return_output(&status.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Percentage applied to the gas cost of a precompile, given its address (20 bytes
+    /// input), as a borsh-encoded `u16`; 100 if its cost is not overridden.
     #[no_mangle]
-    pub extern "C" fn deploy_erc20_token() {
+    pub extern "C" fn get_precompile_gas_override() {
         let mut io = Runtime;
-        // Id of the NEP141 token in Near
-        let args: DeployErc20TokenArgs = io.read_input_borsh().sdk_unwrap();
+        let address = io.read_input_arr20().sdk_unwrap();
+        let percent = precompile_gas::get_percent(&io, &Address(address));
+        io.return_output(&percent.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        let address = engine::deploy_erc20_token(args, io, &io, &mut Runtime).sdk_unwrap();
+    /// Last value published by a NEAR contract under a key, as a borsh-encoded
+    /// `Option<MirroredValue>`.
+    #[no_mangle]
+    pub extern "C" fn get_mirrored_near_state() {
+        let mut io = Runtime;
+        let args: GetMirroredNearStateArgs = io.read_input_borsh().sdk_unwrap();
+        let value = near_state::get_value(&io, &args.source, &args.key);
+        io.return_output(&value.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        io.return_output(&address.as_bytes().try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    /// Rolling commitment to the EVM state changes, as a borsh-encoded `StateCommitment`.
+    #[no_mangle]
+    pub extern "C" fn get_state_commitment() {
+        let mut io = Runtime;
+        let commitment = state_commitment::get(&io);
+        io.return_output(&commitment.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        // TODO: charge for storage
+    /// Statistics of a bridged token, given the address of its ERC-20 (20 bytes input; the
+    /// zero address for ETH), as a borsh-encoded `BridgeTokenStats`.
+    #[no_mangle]
+    pub extern "C" fn get_bridge_stats() {
+        let mut io = Runtime;
+        let token = io.read_input_arr20().sdk_unwrap();
+        let stats = bridge_stats::get_stats(&io, &Address(token)).sdk_unwrap();
+        io.return_output(&stats.try_to_vec().sdk_expect("ERR_SERIALIZE"));
     }
 
-    /// Callback invoked by exit to NEAR precompile to handle potential
-    /// errors in the exit call.
+    /// NEAR balance (u128, Borsh-encoded) paying for the cross-contract calls of the address
+    /// given as input (20 bytes).
     #[no_mangle]
-    pub extern "C" fn refund_on_error() {
-        let io = Runtime;
-        io.assert_private_call().sdk_unwrap();
+    pub extern "C" fn get_xcc_balance() {
+        let mut io = Runtime;
+        let address = io.read_input_arr20().sdk_unwrap();
+        let balance = xcc::get_balance(&io, &Address(address));
+        io.return_output(&balance.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        // This function should only be called as the callback of
-        // exactly one promise.
-        if io.promise_results_count() != 1 {
-            sdk::panic_utf8(PROMISE_COUNT_ERR.as_bytes());
-        }
+    #[no_mangle]
+    pub extern "C" fn get_promise_budget() {
+        let mut io = Runtime;
+        let budget = promise_budget::get_budget(&io).sdk_unwrap();
+        io.return_output(&budget.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        if let Some(PromiseResult::Successful(_)) = io.promise_result(0) {
-            // Promise succeeded -- nothing to do
-        } else {
-            // Exit call failed; need to refund tokens
+    #[no_mangle]
+    pub extern "C" fn get_protocol_fee() {
+        let mut io = Runtime;
+        let config = protocol_fee::get_config(&io).sdk_unwrap();
+        io.return_output(&config.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-            let current_account_id = io.current_account_id();
-            let args: RefundCallArgs = io.read_input_borsh().sdk_unwrap();
-            let refund_result = match args.erc20_address {
-                // ERC-20 exit; re-mint burned tokens
-                Some(erc20_address) => {
-                    let erc20_admin_address = current_address(&current_account_id);
-                    let mut engine =
-                        Engine::new(erc20_admin_address, current_account_id, io, &io).sdk_unwrap();
-                    let erc20_address = Address(erc20_address);
-                    let refund_address = Address(args.recipient_address);
-                    let amount = U256::from_big_endian(&args.amount);
+    /// Total amount of Wei collected by the protocol fee so far (big-endian encoded).
+    #[no_mangle]
+    pub extern "C" fn get_protocol_fee_accumulated() {
+        let mut io = Runtime;
+        let accumulated = protocol_fee::get_accumulated(&io);
+        io.return_output(&accumulated.to_bytes())
+    }
 
-                    let selector = ERC20_MINT_SELECTOR;
-                    let mint_args = ethabi::encode(&[
-                        ethabi::Token::Address(refund_address),
-                        ethabi::Token::Uint(amount),
-                    ]);
+    #[no_mangle]
+    pub extern "C" fn get_operator_allowance() {
+        let mut io = Runtime;
+        let args: GetOperatorAllowanceArgs = io.read_input_borsh().sdk_unwrap();
+        let allowance = operator::get_allowance(&io, &Address::from(args.owner), &args.operator);
+        io.return_output(&allowance.to_bytes())
+    }
 
-                    engine
-                        .call(
-                            erc20_admin_address,
-                            erc20_address,
-                            Wei::zero(),
-                            [selector, mint_args.as_slice()].concat(),
-                            u64::MAX,
-                            Vec::new(),
-                            &mut Runtime,
-                        )
-                        .sdk_unwrap()
-                }
-                // ETH exit; transfer ETH back from precompile address
-                None => {
-                    let exit_address = aurora_engine_precompiles::native::ExitToNear::ADDRESS;
-                    let mut engine =
-                        Engine::new(exit_address, current_account_id, io, &io).sdk_unwrap();
-                    let refund_address = Address(args.recipient_address);
-                    let amount = Wei::new(U256::from_big_endian(&args.amount));
-                    engine
-                        .call(
-                            exit_address,
-                            refund_address,
-                            amount,
-                            Vec::new(),
-                            u64::MAX,
-                            vec![(exit_address, Vec::new()), (refund_address, Vec::new())],
-                            &mut Runtime,
-                        )
-                        .sdk_unwrap()
-                }
-            };
+    /// Nonce to include in the next operator approval signed by the given address.
+    #[no_mangle]
+    pub extern "C" fn get_operator_approval_nonce() {
+        let mut io = Runtime;
+        let owner = io.read_input_arr20().sdk_unwrap();
+        let nonce = operator::get_approval_nonce(&io, &Address(owner));
+        io.return_output(&nonce.to_le_bytes())
+    }
 
-            if !refund_result.status.is_ok() {
-                sdk::panic_utf8(b"ERR_REFUND_FAILURE");
-            }
-        }
+    #[no_mangle]
+    pub extern "C" fn get_inactivity_policy() {
+        let mut io = Runtime;
+        let policy = inactivity::get_policy(&io).sdk_unwrap();
+        io.return_output(&policy.try_to_vec().sdk_expect("ERR_SERIALIZE"));
     }
 
-    ///
-    /// NONMUTATIVE METHODS
-    ///
+    /// Commitment of an evicted account; empty if the account is not evicted.
     #[no_mangle]
-    pub extern "C" fn view() {
+    pub extern "C" fn get_evicted_account_commitment() {
         let mut io = Runtime;
-        let args: ViewCallArgs = io.read_input_borsh().sdk_unwrap();
-        let current_account_id = io.current_account_id();
-        let engine = Engine::new(
-            Address::from_slice(&args.sender),
-            current_account_id,
-            io,
-            &io,
-        )
-        .sdk_unwrap();
-        let result = Engine::view_with_args(&engine, args).sdk_unwrap();
-        io.return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+        let address = io.read_input_arr20().sdk_unwrap();
+        let commitment = inactivity::get_commitment(&io, &Address(address));
+        io.return_output(commitment.as_ref().map(|c| c.as_bytes()).unwrap_or(&[]))
     }
 
+    /// ETH held by the stranded ETH sink, as 32 big-endian bytes.
     #[no_mangle]
-    pub extern "C" fn get_block_hash() {
+    pub extern "C" fn get_stranded_eth() {
         let mut io = Runtime;
-        let block_height = io.read_input_borsh().sdk_unwrap();
-        let account_id = io.current_account_id();
-        let chain_id = engine::get_state(&io)
-            .map(|state| state.chain_id)
-            .sdk_unwrap();
-        let block_hash =
-            crate::engine::compute_block_hash(chain_id, block_height, account_id.as_bytes());
-        io.return_output(block_hash.as_bytes())
+        let amount = stranded_eth::get_stranded_eth(&io);
+        io.return_output(&amount.to_bytes())
     }
 
     #[no_mangle]
@@ -500,9 +1460,14 @@ mod contract {
     pub extern "C" fn get_storage_at() {
         let mut io = Runtime;
         let args: GetStorageAtArgs = io.read_input_borsh().sdk_unwrap();
-        let address = Address(args.address);
+        let address = Address::from(args.address);
         let generation = engine::get_generation(&io, &address);
-        let value = engine::get_storage(&io, &Address(args.address), &H256(args.key), generation);
+        let value = engine::get_storage(
+            &io,
+            &Address::from(args.address),
+            &H256(args.key),
+            generation,
+        );
         io.return_output(&value.0)
     }
 
@@ -522,7 +1487,7 @@ mod contract {
         for account_balance in args.genesis_alloc {
             engine::set_balance(
                 &mut io,
-                &Address(account_balance.address),
+                &Address::from(account_balance.address),
                 &crate::prelude::types::Wei::new(U256::from(account_balance.balance)),
             )
         }
@@ -540,6 +1505,21 @@ mod contract {
         // TODO: https://github.com/aurora-is-near/aurora-engine/issues/2
     }
 
+    /// Credit ETH to a list of addresses. Only available in development builds,
+    /// so local tooling can get funded accounts without crafting deposit proofs.
+    #[cfg(feature = "dev")]
+    #[no_mangle]
+    pub extern "C" fn dev_fund() {
+        let io = Runtime;
+        let state = engine::get_state(&io).sdk_unwrap();
+        require_owner_only(&state, &io.predecessor_account_id());
+        let args: parameters::DevFundArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        EthConnectorContract::init_instance(io)
+            .dev_fund(&current_account_id, &args.addresses, args.amount)
+            .sdk_unwrap();
+    }
+
     #[no_mangle]
     pub extern "C" fn new_eth_connector() {
         let io = Runtime;
@@ -559,7 +1539,7 @@ mod contract {
         io.assert_private_call().sdk_unwrap();
 
         let args: SetContractDataCallArgs = io.read_input_borsh().sdk_unwrap();
-        connector::set_contract_data(&mut io, args).sdk_unwrap();
+        connector::update_contract_data(&mut io, args).sdk_unwrap();
     }
 
     #[no_mangle]
@@ -584,7 +1564,8 @@ mod contract {
         let predecessor_account_id = io.predecessor_account_id();
         let promise_args = EthConnectorContract::init_instance(io)
             .deposit(raw_proof, current_account_id, predecessor_account_id)
-            .sdk_unwrap();
+            .sdk_unwrap()
+            .into_promise();
         let promise_id = io.promise_crate_with_callback(&promise_args);
         io.promise_return(promise_id);
     }
@@ -593,20 +1574,7 @@ mod contract {
     pub extern "C" fn finish_deposit() {
         let mut io = Runtime;
         io.assert_private_call().sdk_unwrap();
-
-        // Check result from proof verification call
-        if io.promise_results_count() != 1 {
-            sdk::panic_utf8(PROMISE_COUNT_ERR.as_bytes());
-        }
-        let promise_result = match io.promise_result(0) {
-            Some(PromiseResult::Successful(bytes)) => {
-                bool::try_from_slice(&bytes).sdk_expect("ERR_PROMISE_ENCODING")
-            }
-            _ => sdk::panic_utf8(b"ERR_PROMISE_FAILED"),
-        };
-        if !promise_result {
-            sdk::panic_utf8(b"ERR_VERIFY_PROOF");
-        }
+        require_proof_verified(&io);
 
         let data = io.read_input_borsh().sdk_unwrap();
         let current_account_id = io.current_account_id();
@@ -620,12 +1588,56 @@ mod contract {
             )
             .sdk_unwrap();
 
-        if let Some(promise_args) = maybe_promise_args {
-            let promise_id = io.promise_crate_with_callback(&promise_args);
+        if let Some(interaction) = maybe_promise_args {
+            let promise_id = io.promise_crate_with_callback(&interaction.into_promise());
             io.promise_return(promise_id);
         }
     }
 
+    /// Finalizes a pending withdrawal given the proof of the `Withdrawn` event emitted by the
+    /// custodian when it released the ETH on Ethereum.
+    #[no_mangle]
+    pub extern "C" fn finish_withdraw_with_proof() {
+        let mut io = Runtime;
+        let args = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let promise_args = EthConnectorContract::init_instance(io)
+            .finish_withdraw_with_proof(args, current_account_id)
+            .sdk_unwrap()
+            .into_promise();
+        let promise_id = io.promise_crate_with_callback(&promise_args);
+        io.promise_return(promise_id);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn finish_withdraw() {
+        let mut io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+        require_proof_verified(&io);
+
+        let data = io.read_input_borsh().sdk_unwrap();
+        let withdrawal = EthConnectorContract::init_instance(io)
+            .finish_withdraw(data)
+            .sdk_unwrap();
+        io.return_output(&withdrawal.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Forgets a pending withdrawal which will never be finalized, freeing its storage. Only
+    /// the account which initiated the withdrawal can call it, or the engine itself for the
+    /// exits from Aurora.
+    #[no_mangle]
+    pub extern "C" fn forget_pending_withdrawal() {
+        let mut io = Runtime;
+        io.assert_one_yocto().sdk_unwrap();
+        let args: ForgetPendingWithdrawalArgs = io.read_input_borsh().sdk_unwrap();
+        let current_account_id = io.current_account_id();
+        let predecessor_account_id = io.predecessor_account_id();
+        let withdrawal = EthConnectorContract::init_instance(io)
+            .forget_pending_withdrawal(args.nonce, &current_account_id, &predecessor_account_id)
+            .sdk_unwrap();
+        io.return_output(&withdrawal.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
     #[no_mangle]
     pub extern "C" fn is_used_proof() {
         let mut io = Runtime;
@@ -722,7 +1734,8 @@ mod contract {
                 args,
                 io.prepaid_gas(),
             )
-            .sdk_unwrap();
+            .sdk_unwrap()
+            .into_promise();
         let promise_id = io.promise_crate_with_callback(&promise_args);
         io.promise_return(promise_id);
     }
@@ -794,6 +1807,110 @@ mod contract {
         EthConnectorContract::init_instance(io).set_paused_flags(args);
     }
 
+    /// Eth address of the custodian contract, borsh-encoded.
+    #[no_mangle]
+    pub extern "C" fn get_eth_custodian_address() {
+        let mut io = Runtime;
+        let address = EthConnectorContract::init_instance(io).get_eth_custodian_address();
+        io.return_output(&address.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Prover account of the eth connector, borsh-encoded.
+    #[no_mangle]
+    pub extern "C" fn get_connector_prover_account() {
+        let mut io = Runtime;
+        let prover_account = EthConnectorContract::init_instance(io).get_prover_account();
+        io.return_output(&prover_account.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_custodian_supply() {
+        let mut io = Runtime;
+        let custodian = io.read_input_arr20().sdk_unwrap();
+        let supply = EthConnectorContract::init_instance(io)
+            .get_custodian_supply(&EthAddress::new(custodian));
+        io.return_output(&supply.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Withdrawals initiated by a NEAR account which were not finalized yet, as a
+    /// borsh-encoded `PendingWithdrawalsPage`.
+    #[no_mangle]
+    pub extern "C" fn get_pending_withdrawals() {
+        let mut io = Runtime;
+        let args: GetPendingWithdrawalsArgs = io.read_input_borsh().sdk_unwrap();
+        let page = EthConnectorContract::init_instance(io).get_pending_withdrawals(
+            &args.account_id,
+            args.from_index,
+            args.limit,
+        );
+        io.return_output(&page.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    #[no_mangle]
+    pub extern "C" fn set_custodian_cap() {
+        let io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+
+        let args: parameters::SetCustodianCapArgs = io.read_input_borsh().sdk_unwrap();
+        EthConnectorContract::init_instance(io).set_custodian_cap(args);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_custodian_council() {
+        let mut io = Runtime;
+        let council = EthConnectorContract::init_instance(io).get_custodian_council();
+        io.return_output(council.as_bytes());
+    }
+
+    #[no_mangle]
+    pub extern "C" fn set_custodian_council() {
+        let io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+
+        let council: AccountId = io.read_input_borsh().sdk_unwrap();
+        EthConnectorContract::init_instance(io).set_custodian_council(council);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_pending_custodian_change() {
+        let mut io = Runtime;
+        let change = EthConnectorContract::init_instance(io).get_pending_custodian_change();
+        io.return_output(&change.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    #[no_mangle]
+    pub extern "C" fn propose_custodian_change() {
+        let io = Runtime;
+        io.assert_private_call().sdk_unwrap();
+
+        let args: parameters::CustodianChangeArgs = io.read_input_borsh().sdk_unwrap();
+        let block_height = io.block_height();
+        EthConnectorContract::init_instance(io)
+            .propose_custodian_change(args, block_height)
+            .sdk_unwrap();
+    }
+
+    #[no_mangle]
+    pub extern "C" fn confirm_custodian_change() {
+        let io = Runtime;
+        let args: parameters::CustodianChangeArgs = io.read_input_borsh().sdk_unwrap();
+        let predecessor_account_id = io.predecessor_account_id();
+        let block_height = io.block_height();
+        EthConnectorContract::init_instance(io)
+            .confirm_custodian_change(args, &predecessor_account_id, block_height)
+            .sdk_unwrap();
+    }
+
+    #[no_mangle]
+    pub extern "C" fn cancel_custodian_change() {
+        let io = Runtime;
+        let predecessor_account_id = io.predecessor_account_id();
+        let current_account_id = io.current_account_id();
+        EthConnectorContract::init_instance(io)
+            .cancel_custodian_change(&predecessor_account_id, &current_account_id)
+            .sdk_unwrap();
+    }
+
     #[no_mangle]
     pub extern "C" fn get_accounts_counter() {
         let io = Runtime;
@@ -825,6 +1942,35 @@ mod contract {
         );
     }
 
+    /// Amount of the ERC-20 of a bridged token (borsh-encoded `RawU256`) minted for an
+    /// amount of the NEP-141 token, see `token_amounts`.
+    #[no_mangle]
+    pub extern "C" fn convert_nep141_amount_to_erc20() {
+        let mut io = Runtime;
+        let args: ConvertNep141AmountArgs = io.read_input_borsh().sdk_unwrap();
+        let amount = token_amounts::nep141_to_erc20(&io, &args.nep141, args.amount).sdk_unwrap();
+        io.return_output(
+            &aurora_engine_types::types::u256_to_arr(&amount)
+                .try_to_vec()
+                .sdk_expect("ERR_SERIALIZE"),
+        );
+    }
+
+    /// Amount of a bridged NEP-141 token (borsh-encoded `u128`) transferred for an amount of
+    /// its ERC-20 exiting to NEAR, see `token_amounts`.
+    #[no_mangle]
+    pub extern "C" fn convert_erc20_amount_to_nep141() {
+        let mut io = Runtime;
+        let args: ConvertErc20AmountArgs = io.read_input_borsh().sdk_unwrap();
+        let amount = token_amounts::erc20_to_nep141(
+            &io,
+            &Address::from(args.erc20),
+            U256::from_big_endian(&args.amount),
+        )
+        .sdk_unwrap();
+        io.return_output(&amount.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
     #[no_mangle]
     pub extern "C" fn ft_metadata() {
         let mut io = Runtime;
@@ -852,7 +1998,7 @@ mod contract {
 
         let mut io = Runtime;
         let args: ([u8; 20], u64, u64) = io.read_input_borsh().sdk_expect("ERR_ARGS");
-        let address = Address(args.0);
+        let address = Address::from(args.0);
         let nonce = U256::from(args.1);
         let balance = U256::from(args.2);
         let current_account_id = io.current_account_id();
@@ -919,6 +2065,34 @@ mod contract {
     fn predecessor_address(predecessor_account_id: &AccountId) -> Address {
         near_account_to_evm_address(predecessor_account_id.as_bytes())
     }
+
+    /// Checks the result of the `verify_log_entry` call on the prover this is the callback of.
+    fn require_proof_verified(io: &Runtime) {
+        if io.promise_results_count() != 1 {
+            sdk::panic_utf8(PROMISE_COUNT_ERR.as_bytes());
+        }
+        let promise_result = match io.promise_result(0) {
+            Some(PromiseResult::Successful(bytes)) => {
+                bool::try_from_slice(&bytes).sdk_expect("ERR_PROMISE_ENCODING")
+            }
+            _ => sdk::panic_utf8(b"ERR_PROMISE_FAILED"),
+        };
+        if !promise_result {
+            sdk::panic_utf8(b"ERR_VERIFY_PROOF");
+        }
+    }
+
+    fn execute_call<I: IO + Copy>(io: I, args: CallArgs) -> EngineResult<SubmitResult> {
+        let env = Runtime;
+        let mut engine = Engine::new(
+            predecessor_address(&env.predecessor_account_id()),
+            env.current_account_id(),
+            io,
+            &env,
+        )
+        .sdk_unwrap();
+        Engine::call_with_args(&mut engine, args, &mut Runtime)
+    }
 }
 
 pub trait AuroraState {
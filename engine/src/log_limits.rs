@@ -0,0 +1,85 @@
+//! Limits on the logs of a transaction.
+//!
+//! The EVM already charges for logs as on Ethereum (375 gas per log, 375 per topic and 8 per
+//! byte of data) and the `LOG0`-`LOG4` opcodes cannot emit more than four topics, but the
+//! logs are returned to NEAR in the `SubmitResult`, whose size NEAR limits (see
+//! `MAX_RETURN_DATA_LEN`). A transaction whose logs exceed `MAX_LOGS_LEN` bytes, or with a
+//! log of more than `MAX_LOG_TOPICS` topics (which only a precompile could emit), is
+//! rejected, so that receipts stay storable downstream.
+
+use evm::backend::Log;
+
+/// Largest number of topics of a log, as on Ethereum.
+pub const MAX_LOG_TOPICS: usize = 4;
+
+/// Largest total size of the logs of a transaction: the half of the 4 MiB NEAR return value
+/// left by `MAX_RETURN_DATA_LEN`.
+pub const MAX_LOGS_LEN: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLimitError {
+    TooManyTopics,
+    LogsTooLarge,
+}
+
+impl AsRef<[u8]> for LogLimitError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::TooManyTopics => b"ERR_LOG_TOO_MANY_TOPICS",
+            Self::LogsTooLarge => b"ERR_LOGS_TOO_LARGE",
+        }
+    }
+}
+
+/// Size of a log in the `SubmitResult`: its address, topics and data.
+pub fn log_len(log: &Log) -> usize {
+    20 + 32 * log.topics.len() + log.data.len()
+}
+
+pub fn check(logs: &[Log]) -> Result<(), LogLimitError> {
+    let mut total_len: usize = 0;
+    for log in logs {
+        if log.topics.len() > MAX_LOG_TOPICS {
+            return Err(LogLimitError::TooManyTopics);
+        }
+        total_len = total_len.saturating_add(log_len(log));
+        if total_len > MAX_LOGS_LEN {
+            return Err(LogLimitError::LogsTooLarge);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{vec, Vec, H160, H256};
+
+    fn log(topics: usize, data_len: usize) -> Log {
+        Log {
+            address: H160::zero(),
+            topics: vec![H256::zero(); topics],
+            data: vec![0u8; data_len],
+        }
+    }
+
+    #[test]
+    fn test_topic_limit() {
+        assert_eq!(check(&[log(MAX_LOG_TOPICS, 0)]), Ok(()));
+        assert_eq!(
+            check(&[log(0, 0), log(MAX_LOG_TOPICS + 1, 0)]),
+            Err(LogLimitError::TooManyTopics)
+        );
+    }
+
+    #[test]
+    fn test_total_size_limit() {
+        let data_len = MAX_LOGS_LEN / 2 - log_len(&log(1, 0));
+        let logs: Vec<Log> = vec![log(1, data_len), log(1, data_len)];
+        assert_eq!(check(&logs), Ok(()));
+
+        let logs: Vec<Log> = vec![log(1, data_len), log(1, data_len + 1)];
+        assert_eq!(check(&logs), Err(LogLimitError::LogsTooLarge));
+        assert_eq!(check(&[]), Ok(()));
+    }
+}
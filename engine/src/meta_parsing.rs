@@ -1,12 +1,12 @@
 use crate::parameters::MetaCallArgs;
 use crate::prelude::precompiles::secp256k1::ecrecover;
 use crate::prelude::{
-    keccak, u256_to_arr, vec, Address, BorshDeserialize, Box, HashMap, InternalMetaCallArgs,
+    codec, keccak, u256_to_arr, vec, Address, BorshDeserialize, Box, HashMap, InternalMetaCallArgs,
     RawU256, String, ToOwned, ToString, Vec, Wei, H256, U256,
 };
 use ethabi::{encode, Token as ABIToken};
 use logos::Logos;
-use rlp::{Decodable, DecoderError, Rlp};
+use rlp::{DecoderError, Rlp};
 
 /// Internal errors to propagate up and format in the single place.
 pub enum ParsingError {
@@ -15,8 +15,17 @@ pub enum ParsingError {
     InvalidMetaTransactionFunctionArg,
     InvalidEcRecoverSignature,
     ArgsLengthMismatch,
+    ArgsTooLarge,
 }
 
+/// Maximum nesting of the RLP-encoded arguments of a meta transaction.
+pub const MAX_ARGS_DEPTH: usize = 16;
+
+/// Maximum number of values (lists included) in the RLP-encoded arguments of a meta
+/// transaction. Each value takes a byte of input but many more bytes of memory once decoded
+/// and ABI-encoded, so their number is capped rather than the input size alone.
+pub const MAX_ARGS_VALUES: usize = 16_384;
+
 pub type ParsingResult<T> = core::result::Result<T, ParsingError>;
 
 mod type_lexer {
@@ -134,19 +143,34 @@ pub fn parse_type(field_type: &str) -> ParsingResult<ArgType> {
     inner_type.ok_or(ParsingError::ArgumentParseError)
 }
 
+/// `keccak("EIP712Domain(string name,string version,uint256 chainId)")`
+/// (see tests::test_domain_hashes).
+const EIP712_DOMAIN_TYPE_HASH: RawU256 = [
+    0xc2, 0xf8, 0x78, 0x71, 0x76, 0xb8, 0xac, 0x6b, 0xf7, 0x21, 0x5b, 0x4a, 0xdc, 0xc1, 0xe0, 0x69,
+    0xbf, 0x4a, 0xb8, 0x2d, 0x9a, 0xb1, 0xdf, 0x05, 0xa5, 0x7a, 0x91, 0xd4, 0x25, 0x93, 0x5b, 0x6e,
+];
+
+/// `keccak("NEAR")`, the hashed name of the domain.
+const NEAR_DOMAIN_NAME_HASH: RawU256 = [
+    0xa4, 0x86, 0xe4, 0xb2, 0x7c, 0xce, 0x13, 0x1b, 0xfe, 0xac, 0xd0, 0x03, 0x01, 0x8c, 0x22, 0xa5,
+    0x57, 0x44, 0xbd, 0xb9, 0x48, 0x21, 0x82, 0x9f, 0x0f, 0xf1, 0xd4, 0x06, 0x1d, 0x8d, 0x05, 0x33,
+];
+
+/// `keccak("1")`, the hashed version of the domain.
+const NEAR_DOMAIN_VERSION_HASH: RawU256 = [
+    0xc8, 0x9e, 0xfd, 0xaa, 0x54, 0xc0, 0xf2, 0x0c, 0x7a, 0xdf, 0x61, 0x28, 0x82, 0xdf, 0x09, 0x50,
+    0xf5, 0xa9, 0x51, 0x63, 0x7e, 0x03, 0x07, 0xcd, 0xcb, 0x4c, 0x67, 0x2f, 0x29, 0x8b, 0x8b, 0xc6,
+];
+
 /// NEAR's domainSeparator
 /// See https://eips.ethereum.org/EIPS/eip-712#definition-of-domainseparator
 /// and https://eips.ethereum.org/EIPS/eip-712#rationale-for-domainseparator
 /// for definition and rationale for domainSeparator.
 pub fn near_erc712_domain(chain_id: U256) -> RawU256 {
-    let mut bytes = Vec::with_capacity(70);
-    bytes.extend_from_slice(
-        keccak("EIP712Domain(string name,string version,uint256 chainId)".as_bytes()).as_bytes(),
-    );
-    let near: RawU256 = keccak(b"NEAR").into();
-    bytes.extend_from_slice(&near);
-    let version: RawU256 = keccak(b"1").into();
-    bytes.extend_from_slice(&version);
+    let mut bytes = Vec::with_capacity(128);
+    bytes.extend_from_slice(&EIP712_DOMAIN_TYPE_HASH);
+    bytes.extend_from_slice(&NEAR_DOMAIN_NAME_HASH);
+    bytes.extend_from_slice(&NEAR_DOMAIN_VERSION_HASH);
     bytes.extend_from_slice(&u256_to_arr(&chain_id));
     keccak(&bytes).into()
 }
@@ -177,14 +201,33 @@ pub enum RlpValue {
     List(Vec<RlpValue>),
 }
 
-impl Decodable for RlpValue {
-    fn decode(rlp: &Rlp<'_>) -> core::result::Result<Self, DecoderError> {
+impl RlpValue {
+    /// Decodes a value, counting it and the values it contains in `budget` (the number of
+    /// values which can still be decoded).
+    fn decode_bounded(rlp: &Rlp<'_>, depth: usize, budget: &mut usize) -> ParsingResult<Self> {
+        if depth > MAX_ARGS_DEPTH || *budget == 0 {
+            return Err(ParsingError::ArgsTooLarge);
+        }
+        *budget -= 1;
+        let invalid = |_: DecoderError| ParsingError::InvalidMetaTransactionFunctionArg;
         if rlp.is_list() {
-            Ok(RlpValue::List(rlp.as_list()?))
+            let count = rlp.item_count().map_err(invalid)?;
+            // The count is bounded by the input size, and checked against the budget before
+            // allocating.
+            if count > *budget {
+                return Err(ParsingError::ArgsTooLarge);
+            }
+            let mut values = Vec::with_capacity(count);
+            for index in 0..count {
+                let item = rlp.at(index).map_err(invalid)?;
+                values.push(Self::decode_bounded(&item, depth + 1, budget)?);
+            }
+            Ok(RlpValue::List(values))
         } else {
-            Ok(RlpValue::Bytes(
-                rlp.decoder().decode_value(|bytes| Ok(bytes.to_vec()))?,
-            ))
+            rlp.decoder()
+                .decode_value(|bytes| Ok(bytes.to_vec()))
+                .map(RlpValue::Bytes)
+                .map_err(invalid)
         }
     }
 }
@@ -348,9 +391,13 @@ fn method_signature(method_and_type: &MethodAndTypes) -> String {
 
 /// Decode rlp-encoded args into vector of Values
 fn rlp_decode(args: &[u8]) -> ParsingResult<Vec<RlpValue>> {
-    let rlp = Rlp::new(args);
-    let res: core::result::Result<Vec<RlpValue>, DecoderError> = rlp.as_list();
-    res.map_err(|_| ParsingError::InvalidMetaTransactionFunctionArg)
+    let rlp = codec::rlp(args, codec::MAX_INPUT_SIZE)
+        .map_err(|_| ParsingError::InvalidMetaTransactionFunctionArg)?;
+    let mut budget = MAX_ARGS_VALUES;
+    match RlpValue::decode_bounded(&rlp, 0, &mut budget)? {
+        RlpValue::List(values) => Ok(values),
+        RlpValue::Bytes(_) => Err(ParsingError::InvalidMetaTransactionFunctionArg),
+    }
 }
 
 /// eip-712 hash a single argument, whose type is ty, and value is value.
@@ -366,12 +413,10 @@ fn eip_712_hash_argument(
         }
         ArgType::Byte(_) => eip_712_rlp_value(value, |b| Ok(b.clone())),
         // TODO: ensure rlp int is encoded as sign extended uint256, otherwise this is wrong
-        ArgType::Uint | ArgType::Int | ArgType::Bool => eip_712_rlp_value(value, |b| {
-            Ok(u256_to_arr(&U256::from_big_endian(b)).to_vec())
-        }),
-        ArgType::Address => {
-            eip_712_rlp_value(value, |b| Ok(encode_address(Address::from_slice(b))))
+        ArgType::Uint | ArgType::Int | ArgType::Bool => {
+            eip_712_rlp_value(value, |b| Ok(u256_to_arr(&decode_u256(b)?).to_vec()))
         }
+        ArgType::Address => eip_712_rlp_value(value, |b| Ok(encode_address(decode_address(b)?))),
         ArgType::Array { inner, .. } => eip_712_rlp_list(value, |l| {
             let mut r = vec![];
             for element in l {
@@ -386,18 +431,31 @@ fn eip_712_hash_argument(
             // struct_type.raw is with struct type with argument names (a "method_def"), so it follows
             // EIP-712 typeHash.
             let mut r = keccak(struct_type.raw.as_bytes()).as_bytes().to_vec();
-            for (i, element) in l.iter().enumerate() {
-                r.extend_from_slice(&eip_712_hash_argument(
-                    &struct_type.args[i].t,
-                    element,
-                    types,
-                )?);
+            if struct_type.args.len() != l.len() {
+                return Err(ParsingError::ArgsLengthMismatch);
+            }
+            for (arg, element) in struct_type.args.iter().zip(l) {
+                r.extend_from_slice(&eip_712_hash_argument(&arg.t, element, types)?);
             }
             Ok(keccak(&r).as_bytes().to_vec())
         }),
     }
 }
 
+fn decode_u256(bytes: &[u8]) -> ParsingResult<U256> {
+    if bytes.len() > 32 {
+        return Err(ParsingError::InvalidMetaTransactionFunctionArg);
+    }
+    Ok(U256::from_big_endian(bytes))
+}
+
+fn decode_address(bytes: &[u8]) -> ParsingResult<Address> {
+    if bytes.len() != 20 {
+        return Err(ParsingError::InvalidMetaTransactionFunctionArg);
+    }
+    Ok(Address::from_slice(bytes))
+}
+
 /// EIP-712 hash a RLP list. f must contain actual logic of EIP-712 encoding
 /// This function serves as a guard to assert value is a List instead of Value
 fn eip_712_rlp_list<F>(value: &RlpValue, f: F) -> ParsingResult<Vec<u8>>
@@ -427,8 +485,8 @@ fn eth_abi_encode_args(
     methods: &MethodAndTypes,
 ) -> ParsingResult<Vec<u8>> {
     let mut tokens = vec![];
-    for (i, arg) in args_decoded.iter().enumerate() {
-        tokens.push(arg_to_abi_token(&methods.method.args[i].t, arg, methods)?);
+    for (method_arg, arg) in methods.method.args.iter().zip(args_decoded) {
+        tokens.push(arg_to_abi_token(&method_arg.t, arg, methods)?);
     }
     Ok(encode(&tokens))
 }
@@ -444,11 +502,9 @@ fn arg_to_abi_token(
         }
         ArgType::Byte(_) => value_to_abi_token(arg, |b| Ok(ABIToken::FixedBytes(b.clone()))),
         ArgType::Uint | ArgType::Int | ArgType::Bool => {
-            value_to_abi_token(arg, |b| Ok(ABIToken::Uint(U256::from_big_endian(b))))
-        }
-        ArgType::Address => {
-            value_to_abi_token(arg, |b| Ok(ABIToken::Address(Address::from_slice(b))))
+            value_to_abi_token(arg, |b| Ok(ABIToken::Uint(decode_u256(b)?)))
         }
+        ArgType::Address => value_to_abi_token(arg, |b| Ok(ABIToken::Address(decode_address(b)?))),
         ArgType::Array {
             inner,
             length: None,
@@ -474,9 +530,12 @@ fn arg_to_abi_token(
                 .types
                 .get(type_name)
                 .ok_or(ParsingError::InvalidMetaTransactionFunctionArg)?;
+            if struct_type.args.len() != l.len() {
+                return Err(ParsingError::ArgsLengthMismatch);
+            }
             let mut tokens = vec![];
-            for (i, element) in l.iter().enumerate() {
-                tokens.push(arg_to_abi_token(&struct_type.args[i].t, element, methods)?);
+            for (arg, element) in struct_type.args.iter().zip(l) {
+                tokens.push(arg_to_abi_token(&arg.t, element, methods)?);
             }
             Ok(ABIToken::Tuple(tokens))
         }),
@@ -611,6 +670,18 @@ mod tests {
     use super::ArgType;
     use rand::Rng;
 
+    #[test]
+    fn test_domain_hashes() {
+        use crate::prelude::keccak;
+
+        assert_eq!(
+            keccak(b"EIP712Domain(string name,string version,uint256 chainId)").0,
+            super::EIP712_DOMAIN_TYPE_HASH
+        );
+        assert_eq!(keccak(b"NEAR").0, super::NEAR_DOMAIN_NAME_HASH);
+        assert_eq!(keccak(b"1").0, super::NEAR_DOMAIN_VERSION_HASH);
+    }
+
     #[test]
     fn test_parse_type() {
         // # atomic types
@@ -0,0 +1,212 @@
+//! Minimum gas price, with a free tier for subsidized contracts.
+//!
+//! The owner can set a minimum gas price, in Wei per gas: `submit` rejects the transactions
+//! paying less, like any other invalid transaction. Transactions calling a contract of the
+//! free tier are exempt, up to a quota of transactions per month and contract, so that a
+//! dApp can have its users transact for free without opening the engine to spam. A month is
+//! a period of 30 days since the Unix epoch, as seen by the timestamp of the NEAR block.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::{
+    bytes_to_key, sdk, Address, BTreeMap, BorshDeserialize, BorshSerialize, EthAddress, KeyPrefix,
+    ToString, Vec, Wei, U256,
+};
+use crate::transaction::NormalizedEthTransaction;
+use aurora_engine_sdk::env::Timestamp;
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const MIN_GAS_PRICE_KEY: &[u8; 13] = b"MIN_GAS_PRICE";
+const FREE_TIER_KEY: &[u8; 9] = b"FREE_TIER";
+const FREE_TIER_USAGE_KEY: &[u8; 15] = b"FREE_TIER_USAGE";
+
+/// Length of the period of a free tier quota, in seconds.
+pub const QUOTA_PERIOD_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Free tier of a contract, returned by `get_free_tier`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FreeTierStatus {
+    /// Number of transactions below the minimum gas price allowed per month.
+    pub monthly_quota: u64,
+    /// Number of them already used this month.
+    pub used: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct FreeTierUsage {
+    period: u64,
+    transactions: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinGasPriceError {
+    GasPriceTooLow,
+    FreeTierQuotaExceeded,
+    InvalidStoredValue,
+}
+
+impl AsRef<[u8]> for MinGasPriceError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::GasPriceTooLow => b"ERR_GAS_PRICE_TOO_LOW",
+            Self::FreeTierQuotaExceeded => b"ERR_FREE_TIER_QUOTA_EXCEEDED",
+            Self::InvalidStoredValue => b"ERR_INVALID_MIN_GAS_PRICE",
+        }
+    }
+}
+
+fn free_tier_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[FREE_TIER_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+fn free_tier_usage_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[FREE_TIER_USAGE_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+fn period(timestamp: Timestamp) -> u64 {
+    timestamp.secs() / QUOTA_PERIOD_SECS
+}
+
+/// Minimum gas price; zero (the default) if there is none.
+pub fn get_min_gas_price<I: IO>(io: &I) -> Result<Wei, MinGasPriceError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, MIN_GAS_PRICE_KEY)) {
+        None => Ok(Wei::zero()),
+        Some(bytes) if bytes.len() == 32 => Ok(Wei::new(U256::from_big_endian(&bytes.to_vec()))),
+        Some(_) => Err(MinGasPriceError::InvalidStoredValue),
+    }
+}
+
+pub fn set_min_gas_price<I: IO>(io: &mut I, min_gas_price: Wei) -> Result<(), MinGasPriceError> {
+    let previous = get_min_gas_price(io)?;
+    io.write_storage(
+        &bytes_to_key(KeyPrefix::Config, MIN_GAS_PRICE_KEY),
+        &min_gas_price.to_bytes(),
+    );
+    events::emit_config_changed(
+        "min_gas_price",
+        JsonValue::String(previous.to_decimal_string()),
+        JsonValue::String(min_gas_price.to_decimal_string()),
+    );
+    Ok(())
+}
+
+/// Free tier of the contract, `None` if it is not in the free tier.
+pub fn get_free_tier<I: IO>(
+    io: &I,
+    address: &Address,
+    now: Timestamp,
+) -> Result<Option<FreeTierStatus>, MinGasPriceError> {
+    let monthly_quota = match io.read_u64(&free_tier_key(address)) {
+        Ok(monthly_quota) => monthly_quota,
+        Err(_) => return Ok(None),
+    };
+    let usage = get_usage(io, address)?;
+    let used = if usage.period == period(now) {
+        usage.transactions
+    } else {
+        0
+    };
+    Ok(Some(FreeTierStatus {
+        monthly_quota,
+        used,
+    }))
+}
+
+fn get_usage<I: IO>(io: &I, address: &Address) -> Result<FreeTierUsage, MinGasPriceError> {
+    match io.read_storage(&free_tier_usage_key(address)) {
+        None => Ok(FreeTierUsage::default()),
+        Some(bytes) => FreeTierUsage::try_from_slice(&bytes.to_vec())
+            .map_err(|_| MinGasPriceError::InvalidStoredValue),
+    }
+}
+
+/// Adds the contract to the free tier with the given quota, or removes it with a quota of
+/// zero. The transactions already used this month still count against the new quota.
+pub fn set_free_tier<I: IO>(io: &mut I, address: &Address, monthly_quota: u64) {
+    let previous = io.read_u64(&free_tier_key(address)).unwrap_or(0);
+    if monthly_quota == 0 {
+        io.remove_storage(&free_tier_key(address));
+        io.remove_storage(&free_tier_usage_key(address));
+    } else {
+        io.write_storage(&free_tier_key(address), &monthly_quota.to_le_bytes());
+    }
+    events::emit_config_changed(
+        "free_tier",
+        free_tier_data(address, previous),
+        free_tier_data(address, monthly_quota),
+    );
+}
+
+fn free_tier_data(address: &Address, monthly_quota: u64) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "address".to_string(),
+        JsonValue::String(EthAddress::from(*address).encode()),
+    );
+    kvs.insert("monthly_quota".to_string(), JsonValue::U64(monthly_quota));
+    JsonValue::Object(kvs)
+}
+
+/// Checks that the transaction pays at least the minimum gas price. A transaction to a
+/// contract of the free tier which pays less uses one transaction of its quota.
+pub fn check<I: IO>(
+    io: &mut I,
+    transaction: &NormalizedEthTransaction,
+    now: Timestamp,
+) -> Result<(), MinGasPriceError> {
+    let (to, status) = match free_tier_needed(io, transaction, now)? {
+        Some(free_tier) => free_tier,
+        None => return Ok(()),
+    };
+    let usage = FreeTierUsage {
+        period: period(now),
+        transactions: status.used + 1,
+    };
+    io.write_borsh(&free_tier_usage_key(&to), &usage);
+    sdk::log!(crate::prelude::format!(
+        "Free tier transaction {} of {} to {:?}",
+        usage.transactions,
+        status.monthly_quota,
+        to
+    )
+    .as_str());
+    Ok(())
+}
+
+/// Same as `check`, without using a transaction of the free tier quota.
+pub fn validate<I: IO>(
+    io: &I,
+    transaction: &NormalizedEthTransaction,
+    now: Timestamp,
+) -> Result<(), MinGasPriceError> {
+    free_tier_needed(io, transaction, now).map(|_| ())
+}
+
+/// Recipient and free tier of a transaction paying less than the minimum gas price, which
+/// its quota covers. `None` if the transaction pays enough.
+fn free_tier_needed<I: IO>(
+    io: &I,
+    transaction: &NormalizedEthTransaction,
+    now: Timestamp,
+) -> Result<Option<(Address, FreeTierStatus)>, MinGasPriceError> {
+    let min_gas_price = get_min_gas_price(io)?;
+    // The base fee is zero, so the price paid is the priority fee.
+    let gas_price = transaction
+        .max_priority_fee_per_gas
+        .min(transaction.max_fee_per_gas);
+    if gas_price >= min_gas_price.raw() {
+        return Ok(None);
+    }
+    let to = transaction.to.ok_or(MinGasPriceError::GasPriceTooLow)?;
+    let status = get_free_tier(io, &to, now)?.ok_or(MinGasPriceError::GasPriceTooLow)?;
+    if status.used >= status.monthly_quota {
+        return Err(MinGasPriceError::FreeTierQuotaExceeded);
+    }
+    Ok(Some((to, status)))
+}
@@ -0,0 +1,96 @@
+//! NEAR contract state mirrored for the EVM.
+//!
+//! The owner allows NEAR contracts (e.g. price oracles) to publish values to the engine;
+//! an allowed contract then calls `mirror_near_state` whenever it wants the EVM to see a
+//! new value, and EVM contracts read the last published value synchronously through the
+//! `NearStateRead` precompile (see `aurora_engine_precompiles::near_state`).
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::precompiles::near_state::{
+    source_key, value_key, MirroredValue, MAX_KEY_LEN, MAX_VALUE_LEN,
+};
+use crate::prelude::{AccountId, BTreeMap, BorshDeserialize, ToString, Vec};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NearStateError {
+    SourceNotAllowed,
+    KeyTooLong,
+    ValueTooLong,
+}
+
+impl AsRef<[u8]> for NearStateError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::SourceNotAllowed => b"ERR_NEAR_STATE_SOURCE_NOT_ALLOWED",
+            Self::KeyTooLong => b"ERR_NEAR_STATE_KEY_TOO_LONG",
+            Self::ValueTooLong => b"ERR_NEAR_STATE_VALUE_TOO_LONG",
+        }
+    }
+}
+
+pub fn is_source_allowed<I: IO>(io: &I, source: &AccountId) -> bool {
+    io.storage_has_key(&source_key(source))
+}
+
+/// Allows (or disallows) the NEAR contract to publish values. The values it already
+/// published stay readable.
+pub fn set_source<I: IO>(io: &mut I, source: &AccountId, allowed: bool) {
+    let previous = is_source_allowed(io, source);
+    if allowed {
+        io.write_storage(&source_key(source), &[1]);
+    } else {
+        io.remove_storage(&source_key(source));
+    }
+    events::emit_config_changed(
+        "near_state_source",
+        source_data(source, previous),
+        source_data(source, allowed),
+    );
+}
+
+pub fn get_value<I: IO>(io: &I, source: &AccountId, key: &[u8]) -> Option<MirroredValue> {
+    io.read_storage(&value_key(source, key))
+        .and_then(|value| MirroredValue::try_from_slice(&value.to_vec()).ok())
+}
+
+/// Publishes the value of `source` under `key` at the given block height; `None` removes
+/// the key.
+pub fn publish<I: IO>(
+    io: &mut I,
+    source: &AccountId,
+    key: &[u8],
+    value: Option<Vec<u8>>,
+    block_height: u64,
+) -> Result<(), NearStateError> {
+    if !is_source_allowed(io, source) {
+        return Err(NearStateError::SourceNotAllowed);
+    }
+    if key.len() > MAX_KEY_LEN {
+        return Err(NearStateError::KeyTooLong);
+    }
+    match value {
+        Some(value) => {
+            if value.len() > MAX_VALUE_LEN {
+                return Err(NearStateError::ValueTooLong);
+            }
+            let value = MirroredValue {
+                value,
+                published_at: block_height,
+            };
+            io.write_borsh(&value_key(source, key), &value);
+        }
+        None => {
+            io.remove_storage(&value_key(source, key));
+        }
+    }
+    Ok(())
+}
+
+fn source_data(source: &AccountId, allowed: bool) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert("source".to_string(), JsonValue::String(source.to_string()));
+    kvs.insert("allowed".to_string(), JsonValue::Bool(allowed));
+    JsonValue::Object(kvs)
+}
@@ -0,0 +1,162 @@
+//! Bridge of NEP-171 collections to ERC-721 contracts.
+//!
+//! `deploy_erc721_token` deploys an ERC-721 for a NEP-171 collection and maps them to each
+//! other. A NEAR account then bridges a token with `nft_transfer_call` to the engine, with
+//! the hex address of the EVM recipient as message: `nft_on_transfer` mints the token to
+//! the recipient, or asks the collection to return it if it cannot. The engine holds the
+//! bridged tokens on NEAR. The way back is `withdrawToNear` on the ERC-721, which burns the
+//! token and calls the `ExitNftToNear` precompile to `nft_transfer` it to the NEAR recipient
+//! (see `aurora_engine_precompiles::exit_nft`).
+
+use crate::engine::{current_address, Engine, EngineError, EngineStateError};
+use crate::parameters::{DeployErc721TokenArgs, NftOnTransferArgs, TransactionStatus};
+use crate::prelude::precompiles::exit_nft::{erc721_key, is_valid_token_id, nep171_key};
+use crate::prelude::{codec, sdk, AccountId, Address, ToString, TryFrom, TryInto, Vec, Wei};
+use aurora_engine_sdk::env::Env;
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+use aurora_engine_sdk::promise::PromiseHandler;
+
+#[derive(Debug)]
+pub enum DeployErc721Error {
+    State(EngineStateError),
+    CollectionAlreadyRegistered,
+    Failed(TransactionStatus),
+    Engine(EngineError),
+}
+
+impl AsRef<[u8]> for DeployErc721Error {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::State(e) => e.as_ref(),
+            Self::CollectionAlreadyRegistered => b"ERR_NFT_COLLECTION_ALREADY_REGISTERED",
+            Self::Failed(e) => e.as_ref(),
+            Self::Engine(e) => e.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NftOnTransferError {
+    CollectionNotFound,
+    InvalidTokenId,
+    InvalidRecipient,
+    State(EngineStateError),
+    MintFailed,
+}
+
+impl AsRef<[u8]> for NftOnTransferError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::CollectionNotFound => b"ERR_NFT_COLLECTION_NOT_FOUND",
+            Self::InvalidTokenId => b"ERR_INVALID_TOKEN_ID",
+            Self::InvalidRecipient => b"ERR_INVALID_RECIPIENT",
+            Self::State(e) => e.as_ref(),
+            Self::MintFailed => b"ERR_NFT_MINT_FAILED",
+        }
+    }
+}
+
+/// ERC-721 bridged from the NEP-171 collection, if any.
+pub fn get_erc721<I: IO>(io: &I, nep171: &AccountId) -> Option<Address> {
+    io.read_storage(&erc721_key(nep171))
+        .filter(|value| value.len() == 20)
+        .map(|value| Address::from_slice(&value.to_vec()))
+}
+
+/// NEP-171 collection bridged to the ERC-721, if any.
+pub fn get_nep171<I: IO>(io: &I, erc721: &Address) -> Option<AccountId> {
+    io.read_storage(&nep171_key(erc721))
+        .and_then(|value| AccountId::try_from(value.to_vec()).ok())
+}
+
+fn register<I: IO>(io: &mut I, nep171: &AccountId, erc721: &Address) {
+    io.write_storage(&erc721_key(nep171), erc721.as_bytes());
+    io.write_storage(&nep171_key(erc721), nep171.as_bytes());
+}
+
+/// Deploys the ERC-721 of a NEP-171 collection, with the engine as admin.
+pub fn deploy_erc721_token<I: IO + Copy, E: Env, P: PromiseHandler>(
+    args: DeployErc721TokenArgs,
+    io: I,
+    env: &E,
+    handler: &mut P,
+) -> Result<Address, DeployErc721Error> {
+    if get_erc721(&io, &args.nep171).is_some() {
+        return Err(DeployErc721Error::CollectionAlreadyRegistered);
+    }
+    let current_account_id = env.current_account_id();
+    let admin_address = current_address(&current_account_id);
+    let mut engine = Engine::new(
+        aurora_engine_sdk::types::near_account_to_evm_address(
+            env.predecessor_account_id().as_bytes(),
+        ),
+        current_account_id,
+        io,
+        env,
+    )
+    .map_err(DeployErc721Error::State)?;
+
+    let erc721_contract = include_bytes!("../../etc/eth-contracts/res/EvmErc721.bin");
+    let deploy_args = crate::erc721_abi::encode_constructor(
+        "Empty".to_string(),
+        "EMPTY".to_string(),
+        admin_address,
+    );
+
+    let address = match Engine::deploy_code_with_input(
+        &mut engine,
+        [erc721_contract.as_slice(), deploy_args.as_slice()].concat(),
+        handler,
+    ) {
+        Ok(result) => match result.status {
+            TransactionStatus::Succeed(ret) => Address(ret.as_slice().try_into().unwrap()),
+            other => return Err(DeployErc721Error::Failed(other)),
+        },
+        Err(e) => return Err(DeployErc721Error::Engine(e)),
+    };
+
+    sdk::log!(crate::prelude::format!("Deployed ERC-721 in Aurora at: {:#?}", address).as_str());
+    let mut io = io;
+    register(&mut io, &args.nep171, &address);
+    Ok(address)
+}
+
+/// Mints the token transferred by the NEP-171 collection `nep171` to the EVM address of the
+/// message.
+pub fn nft_on_transfer<I: IO + Copy, E: Env, P: PromiseHandler>(
+    io: I,
+    env: &E,
+    nep171: &AccountId,
+    args: NftOnTransferArgs,
+    handler: &mut P,
+) -> Result<(), NftOnTransferError> {
+    let erc721 = get_erc721(&io, nep171).ok_or(NftOnTransferError::CollectionNotFound)?;
+    if !is_valid_token_id(&args.token_id) {
+        return Err(NftOnTransferError::InvalidTokenId);
+    }
+    // Message format: recipient of the token - 40 characters (Address in hex)
+    if args.msg.len() != 40 {
+        return Err(NftOnTransferError::InvalidRecipient);
+    }
+    let recipient = codec::hex_decode_array::<20, _>(args.msg.as_bytes())
+        .map(Address)
+        .map_err(|_| NftOnTransferError::InvalidRecipient)?;
+
+    let current_account_id = env.current_account_id();
+    let admin_address = current_address(&current_account_id);
+    let mut engine = Engine::new(admin_address, current_account_id, io, env)
+        .map_err(NftOnTransferError::State)?;
+    let result = engine.call(
+        admin_address,
+        erc721,
+        Wei::zero(),
+        crate::erc721_abi::encode_mint(recipient, args.token_id),
+        u64::MAX,
+        Vec::new(),
+        handler,
+    );
+    match result {
+        Ok(result) if result.status.is_ok() => Ok(()),
+        _ => Err(NftOnTransferError::MintFailed),
+    }
+}
@@ -0,0 +1,371 @@
+//! Allowance-based operators.
+//!
+//! An EVM address can approve a NEAR account as its operator by signing an approval
+//! message off-chain (`personal_sign` style). The operator can then execute EVM calls
+//! on behalf of that address via `call_on_behalf`, until the expiry height of the approval
+//! and only to the (contract, selector) pairs listed in it, attaching in total at most
+//! `spend_limit` Wei. An approval with a zero `spend_limit` only allows calls without value.
+//! The owner revokes an operator by signing a revocation message.
+
+use crate::parameters::{
+    CallOnBehalfArgs, OperatorAllowedCall, OperatorApprovalArgs, OperatorRevocationArgs,
+};
+use crate::prelude::precompiles::secp256k1::ecrecover;
+use crate::prelude::{
+    bytes_to_key, sdk, AccountId, Address, BorshDeserialize, BorshSerialize, EthAddress, KeyPrefix,
+    Vec, Wei, H256, U256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+/// Sub-prefix of `KeyPrefix::OperatorAllowance` for the approvals.
+const ALLOWANCE_SUB_PREFIX: u8 = 0;
+/// Sub-prefix of `KeyPrefix::OperatorAllowance` for the approval nonces.
+const NONCE_SUB_PREFIX: u8 = 1;
+
+const PERSONAL_SIGN_PREFIX: &[u8; 28] = b"\x19Ethereum Signed Message:\n32";
+
+/// The data signed by the owner of an EVM address to approve an operator.
+#[derive(BorshSerialize)]
+pub struct OperatorApprovalMessage<'a> {
+    pub chain_id: [u8; 32],
+    pub engine_account_id: &'a AccountId,
+    pub owner: EthAddress,
+    pub operator: &'a AccountId,
+    pub spend_limit: [u8; 32],
+    pub allowed_calls: &'a [OperatorAllowedCall],
+    pub expiry_height: u64,
+    pub nonce: u64,
+}
+
+impl<'a> OperatorApprovalMessage<'a> {
+    /// Hash which must be signed by the owner. Equal to the `personal_sign` hash of
+    /// `keccak256(borsh(message))`, so any Ethereum wallet can produce the signature.
+    pub fn signing_hash(&self) -> H256 {
+        let message_hash = sdk::keccak(&self.try_to_vec().expect("ERR_SERIALIZE"));
+        sdk::keccak(&[PERSONAL_SIGN_PREFIX.as_slice(), message_hash.as_bytes()].concat())
+    }
+}
+
+/// The data signed by the owner of an EVM address to revoke an operator. It shares the nonce
+/// of the approvals, so a revocation cannot be replayed after a new approval.
+#[derive(BorshSerialize)]
+pub struct OperatorRevocationMessage<'a> {
+    pub chain_id: [u8; 32],
+    pub engine_account_id: &'a AccountId,
+    pub owner: EthAddress,
+    pub operator: &'a AccountId,
+    pub nonce: u64,
+}
+
+impl<'a> OperatorRevocationMessage<'a> {
+    /// Hash which must be signed by the owner, like `OperatorApprovalMessage::signing_hash`.
+    pub fn signing_hash(&self) -> H256 {
+        let message_hash = sdk::keccak(&self.try_to_vec().expect("ERR_SERIALIZE"));
+        sdk::keccak(&[PERSONAL_SIGN_PREFIX.as_slice(), message_hash.as_bytes()].concat())
+    }
+}
+
+/// An approval as stored by the engine.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OperatorApproval {
+    /// Wei the operator may still attach to calls.
+    pub allowance: [u8; 32],
+    pub allowed_calls: Vec<OperatorAllowedCall>,
+    pub expiry_height: u64,
+}
+
+impl OperatorApproval {
+    pub fn allowance(&self) -> Wei {
+        Wei::new(U256::from_big_endian(&self.allowance))
+    }
+
+    /// Whether `input` sent to `contract` is one of the allowed calls.
+    pub fn allows(&self, contract: &EthAddress, input: &[u8]) -> bool {
+        let selector = input.get(..4).map(|selector| {
+            let mut result = [0u8; 4];
+            result.copy_from_slice(selector);
+            result
+        });
+        self.allowed_calls
+            .iter()
+            .any(|call| &call.contract == contract && call.selector == selector)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorError {
+    InvalidSignature,
+    NotApproved,
+    ApprovalExpired,
+    CallNotAllowed,
+    AllowanceExceeded,
+}
+
+impl AsRef<[u8]> for OperatorError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidSignature => b"ERR_INVALID_OPERATOR_APPROVAL_SIGNATURE",
+            Self::NotApproved => b"ERR_OPERATOR_NOT_APPROVED",
+            Self::ApprovalExpired => b"ERR_OPERATOR_APPROVAL_EXPIRED",
+            Self::CallNotAllowed => b"ERR_OPERATOR_CALL_NOT_ALLOWED",
+            Self::AllowanceExceeded => b"ERR_OPERATOR_ALLOWANCE_EXCEEDED",
+        }
+    }
+}
+
+fn allowance_key(owner: &Address, operator: &AccountId) -> Vec<u8> {
+    let key = [
+        &[ALLOWANCE_SUB_PREFIX],
+        owner.as_bytes(),
+        operator.as_bytes(),
+    ]
+    .concat();
+    bytes_to_key(KeyPrefix::OperatorAllowance, &key)
+}
+
+fn nonce_key(owner: &Address) -> Vec<u8> {
+    let key = [&[NONCE_SUB_PREFIX], owner.as_bytes()].concat();
+    bytes_to_key(KeyPrefix::OperatorAllowance, &key)
+}
+
+pub fn get_approval<I: IO>(
+    io: &I,
+    owner: &Address,
+    operator: &AccountId,
+) -> Option<OperatorApproval> {
+    io.read_storage(&allowance_key(owner, operator))
+        .and_then(|bytes| bytes.to_value().ok())
+}
+
+/// Remaining allowance of `operator`, zero if it is not approved by `owner`.
+pub fn get_allowance<I: IO>(io: &I, owner: &Address, operator: &AccountId) -> Wei {
+    get_approval(io, owner, operator)
+        .map(|approval| approval.allowance())
+        .unwrap_or_else(Wei::zero)
+}
+
+/// Stores the approval. It is kept once nothing is left to spend, as calls without value
+/// are still allowed.
+fn set_approval<I: IO>(
+    io: &mut I,
+    owner: &Address,
+    operator: &AccountId,
+    approval: &OperatorApproval,
+) {
+    io.write_borsh(&allowance_key(owner, operator), approval);
+}
+
+/// Checks the signature of `owner` over the hash, and consumes the nonce it was made with.
+fn verify_signature<I: IO>(
+    io: &mut I,
+    owner: &Address,
+    nonce: u64,
+    signing_hash: H256,
+    signature: &[u8; 65],
+) -> Result<(), OperatorError> {
+    let signer = ecrecover(signing_hash, signature).map_err(|_| OperatorError::InvalidSignature)?;
+    if &signer != owner {
+        return Err(OperatorError::InvalidSignature);
+    }
+    io.write_storage(&nonce_key(owner), &(nonce + 1).to_le_bytes());
+    Ok(())
+}
+
+/// Nonce which must be included in the next approval signed by `owner`.
+pub fn get_approval_nonce<I: IO>(io: &I, owner: &Address) -> u64 {
+    io.read_u64(&nonce_key(owner)).unwrap_or(0)
+}
+
+/// Verifies the owner's signature and sets the operator approval, replacing any previous one.
+/// A `spend_limit` of zero allows the calls without value.
+pub fn approve<I: IO>(
+    io: &mut I,
+    chain_id: [u8; 32],
+    engine_account_id: &AccountId,
+    args: OperatorApprovalArgs,
+) -> Result<(), OperatorError> {
+    let owner = Address::from(args.owner);
+    let nonce = get_approval_nonce(io, &owner);
+    let message = OperatorApprovalMessage {
+        chain_id,
+        engine_account_id,
+        owner: args.owner,
+        operator: &args.operator,
+        spend_limit: args.spend_limit,
+        allowed_calls: &args.allowed_calls,
+        expiry_height: args.expiry_height,
+        nonce,
+    };
+    verify_signature(io, &owner, nonce, message.signing_hash(), &args.signature)?;
+
+    let approval = OperatorApproval {
+        allowance: args.spend_limit,
+        allowed_calls: args.allowed_calls,
+        expiry_height: args.expiry_height,
+    };
+    set_approval(io, &owner, &args.operator, &approval);
+    sdk::log!(crate::prelude::format!(
+        "Operator {} approved for {} with spend limit {} until height {}",
+        args.operator,
+        hex::encode(args.owner),
+        approval.allowance(),
+        approval.expiry_height
+    )
+    .as_str());
+
+    Ok(())
+}
+
+/// Verifies the owner's signature and removes the approval of the operator.
+pub fn revoke<I: IO>(
+    io: &mut I,
+    chain_id: [u8; 32],
+    engine_account_id: &AccountId,
+    args: OperatorRevocationArgs,
+) -> Result<(), OperatorError> {
+    let owner = Address::from(args.owner);
+    let nonce = get_approval_nonce(io, &owner);
+    let message = OperatorRevocationMessage {
+        chain_id,
+        engine_account_id,
+        owner: args.owner,
+        operator: &args.operator,
+        nonce,
+    };
+    verify_signature(io, &owner, nonce, message.signing_hash(), &args.signature)?;
+
+    io.remove_storage(&allowance_key(&owner, &args.operator));
+    sdk::log!(crate::prelude::format!(
+        "Operator {} revoked for {}",
+        args.operator,
+        hex::encode(args.owner)
+    )
+    .as_str());
+
+    Ok(())
+}
+
+/// Checks that `owner` approved `operator` for the call and deducts the value attached to it
+/// from the allowance.
+pub fn consume_allowance<I: IO>(
+    io: &mut I,
+    operator: &AccountId,
+    args: &CallOnBehalfArgs,
+    block_height: u64,
+) -> Result<(), OperatorError> {
+    let owner = Address::from(args.owner);
+    let mut approval = get_approval(io, &owner, operator).ok_or(OperatorError::NotApproved)?;
+    check_call(&approval, args, block_height)?;
+    let value = Wei::new(U256::from_big_endian(&args.value));
+    let remaining = approval
+        .allowance()
+        .checked_sub(value)
+        .ok_or(OperatorError::AllowanceExceeded)?;
+    remaining.raw().to_big_endian(&mut approval.allowance);
+    set_approval(io, &owner, operator, &approval);
+    Ok(())
+}
+
+fn check_call(
+    approval: &OperatorApproval,
+    args: &CallOnBehalfArgs,
+    block_height: u64,
+) -> Result<(), OperatorError> {
+    if block_height >= approval.expiry_height {
+        return Err(OperatorError::ApprovalExpired);
+    }
+    if !approval.allows(&args.contract, &args.input) {
+        return Err(OperatorError::CallNotAllowed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::vec;
+
+    #[test]
+    fn test_signing_hash_depends_on_nonce() {
+        let engine_account_id: AccountId = "aurora".parse().unwrap();
+        let operator: AccountId = "operator.near".parse().unwrap();
+        let mut message = OperatorApprovalMessage {
+            chain_id: [0u8; 32],
+            engine_account_id: &engine_account_id,
+            owner: EthAddress::new([1u8; 20]),
+            operator: &operator,
+            spend_limit: [0u8; 32],
+            allowed_calls: &[],
+            expiry_height: 0,
+            nonce: 0,
+        };
+        let first = message.signing_hash();
+        message.nonce = 1;
+        assert_ne!(first, message.signing_hash());
+    }
+
+    fn approval() -> OperatorApproval {
+        OperatorApproval {
+            allowance: [0u8; 32],
+            allowed_calls: vec![
+                OperatorAllowedCall {
+                    contract: EthAddress::new([1u8; 20]),
+                    selector: Some([0xa9, 0x05, 0x9c, 0xbb]),
+                },
+                OperatorAllowedCall {
+                    contract: EthAddress::new([2u8; 20]),
+                    selector: None,
+                },
+            ],
+            expiry_height: 100,
+        }
+    }
+
+    fn call_args(contract: [u8; 20], input: Vec<u8>) -> CallOnBehalfArgs {
+        CallOnBehalfArgs {
+            owner: EthAddress::new([3u8; 20]),
+            contract: EthAddress::new(contract),
+            value: [0u8; 32],
+            input,
+            gas_limit: 100_000,
+        }
+    }
+
+    #[test]
+    fn test_check_call_allowlist() {
+        let approval = approval();
+        let transfer = vec![0xa9, 0x05, 0x9c, 0xbb, 0, 0];
+        let approve = vec![0x09, 0x5e, 0xa7, 0xb3, 0, 0];
+        assert_eq!(
+            check_call(&approval, &call_args([1u8; 20], transfer.clone()), 99),
+            Ok(())
+        );
+        assert_eq!(
+            check_call(&approval, &call_args([1u8; 20], approve), 99),
+            Err(OperatorError::CallNotAllowed)
+        );
+        assert_eq!(
+            check_call(&approval, &call_args([1u8; 20], Vec::new()), 99),
+            Err(OperatorError::CallNotAllowed)
+        );
+        assert_eq!(
+            check_call(&approval, &call_args([2u8; 20], Vec::new()), 99),
+            Ok(())
+        );
+        assert_eq!(
+            check_call(&approval, &call_args([2u8; 20], transfer), 99),
+            Err(OperatorError::CallNotAllowed)
+        );
+    }
+
+    #[test]
+    fn test_check_call_expiry() {
+        let approval = approval();
+        let args = call_args([2u8; 20], Vec::new());
+        assert_eq!(check_call(&approval, &args, 99), Ok(()));
+        assert_eq!(
+            check_call(&approval, &args, 100),
+            Err(OperatorError::ApprovalExpired)
+        );
+    }
+}
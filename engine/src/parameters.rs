@@ -1,10 +1,12 @@
 use crate::admin_controlled::PausedMask;
+use crate::fee_schedule::FeeSchedule;
 use crate::fungible_token::FungibleTokenMetadata;
+use crate::inactivity::EvictedAccount;
 use crate::json::{JsonError, JsonValue};
 use crate::prelude::account_id::AccountId;
 use crate::prelude::{
-    format, Balance, BorshDeserialize, BorshSerialize, EthAddress, RawAddress, RawH256, RawU256,
-    String, ToString, TryFrom, Vec, WeiU256,
+    format, BTreeMap, Balance, BorshDeserialize, BorshSerialize, EthAddress, PromiseCreateArgs,
+    RawH256, RawU256, String, ToString, TryFrom, Vec, WeiU256,
 };
 use crate::proof::Proof;
 use aurora_engine_types::types::Fee;
@@ -25,6 +27,20 @@ pub struct NewCallArgs {
     pub upgrade_delay_blocks: u64,
 }
 
+/// Borsh-encoded result of the `get_engine_config` view.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct EngineConfig {
+    pub chain_id: RawU256,
+    pub owner_id: AccountId,
+    pub bridge_prover_id: AccountId,
+    pub upgrade_delay_blocks: u64,
+    /// Prover of the deposits of the eth connector.
+    pub prover_account: AccountId,
+    pub eth_custodian_address: EthAddress,
+    /// Account which must confirm custodian changes.
+    pub custodian_council: AccountId,
+}
+
 /// Borsh-encoded parameters for the `meta_call` function.
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct MetaCallArgs {
@@ -32,8 +48,8 @@ pub struct MetaCallArgs {
     pub v: u8,
     pub nonce: RawU256,
     pub fee_amount: RawU256,
-    pub fee_address: RawAddress,
-    pub contract_address: RawAddress,
+    pub fee_address: EthAddress,
+    pub contract_address: EthAddress,
     pub value: RawU256,
     pub method_def: String,
     pub args: Vec<u8>,
@@ -42,7 +58,7 @@ pub struct MetaCallArgs {
 /// Borsh-encoded log for use in a `SubmitResult`.
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct ResultLog {
-    pub address: RawAddress,
+    pub address: EthAddress,
     pub topics: Vec<RawU256>,
     pub data: Vec<u8>,
 }
@@ -55,15 +71,20 @@ impl From<Log> for ResultLog {
             .map(|topic| topic.0)
             .collect::<Vec<_>>();
         ResultLog {
-            address: log.address.0,
+            address: log.address.into(),
             topics,
             data: log.data,
         }
     }
 }
 
+/// Largest data returned by a transaction which is reported in full. NEAR limits the value
+/// returned by a function call to 4 MiB, half of which is left for the logs and the rest of
+/// the `SubmitResult`.
+pub const MAX_RETURN_DATA_LEN: usize = 2 * 1024 * 1024;
+
 /// The status of a transaction.
-#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone)]
 pub enum TransactionStatus {
     Succeed(Vec<u8>),
     Revert(Vec<u8>),
@@ -71,22 +92,67 @@ pub enum TransactionStatus {
     OutOfFund,
     OutOfOffset,
     CallTooDeep,
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    /// Memory or return data access out of the addressable range.
+    InvalidRange,
+    DesignatedInvalid,
+    CreateCollision,
+    CreateContractLimit,
+    /// The transaction succeeded or reverted with data longer than `MAX_RETURN_DATA_LEN`
+    /// (or than the limit of `limit_return_data_to`), which is committed to by its hash
+    /// instead.
+    ReturnDataTooLarge {
+        reverted: bool,
+        len: u64,
+        /// Keccak hash of the data.
+        hash: RawH256,
+    },
 }
 
 impl TransactionStatus {
     pub fn is_ok(&self) -> bool {
-        matches!(*self, TransactionStatus::Succeed(_))
+        matches!(
+            *self,
+            TransactionStatus::Succeed(_)
+                | TransactionStatus::ReturnDataTooLarge {
+                    reverted: false,
+                    ..
+                }
+        )
     }
 
     pub fn is_revert(&self) -> bool {
-        matches!(*self, TransactionStatus::Revert(_))
+        matches!(
+            *self,
+            TransactionStatus::Revert(_)
+                | TransactionStatus::ReturnDataTooLarge { reverted: true, .. }
+        )
     }
 
     pub fn is_fail(&self) -> bool {
-        *self == TransactionStatus::OutOfGas
-            || *self == TransactionStatus::OutOfFund
-            || *self == TransactionStatus::OutOfOffset
-            || *self == TransactionStatus::CallTooDeep
+        !self.is_ok() && !self.is_revert()
+    }
+
+    /// Replaces data returned (or revert data) longer than `MAX_RETURN_DATA_LEN`, which
+    /// could not be returned to NEAR, by its length and hash.
+    pub fn limit_return_data(self) -> Self {
+        self.limit_return_data_to(MAX_RETURN_DATA_LEN)
+    }
+
+    /// Replaces data returned (or revert data) longer than `max_len` by its length and hash.
+    pub fn limit_return_data_to(self, max_len: usize) -> Self {
+        let (reverted, data) = match self {
+            Self::Succeed(data) if data.len() > max_len => (false, data),
+            Self::Revert(data) if data.len() > max_len => (true, data),
+            status => return status,
+        };
+        Self::ReturnDataTooLarge {
+            reverted,
+            len: data.len() as u64,
+            hash: crate::prelude::sdk::keccak(&data).0,
+        }
     }
 }
 
@@ -99,10 +165,52 @@ impl AsRef<[u8]> for TransactionStatus {
             Self::OutOfGas => b"ERR_OUT_OF_GAS",
             Self::OutOfOffset => b"ERR_OUT_OF_OFFSET",
             Self::CallTooDeep => b"ERR_CALL_TOO_DEEP",
+            Self::StackUnderflow => b"ERR_STACK_UNDERFLOW",
+            Self::StackOverflow => b"ERR_STACK_OVERFLOW",
+            Self::InvalidJump => b"ERR_INVALID_JUMP",
+            Self::InvalidRange => b"ERR_INVALID_RANGE",
+            Self::DesignatedInvalid => b"ERR_DESIGNATED_INVALID",
+            Self::CreateCollision => b"ERR_CREATE_COLLISION",
+            Self::CreateContractLimit => b"ERR_CREATE_CONTRACT_LIMIT",
+            Self::ReturnDataTooLarge { .. } => b"ERR_RETURN_DATA_TOO_LARGE",
         }
     }
 }
 
+/// NEAR promises scheduled by a transaction (see `promise_budget`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PromiseStats {
+    /// Number of promises, callbacks included.
+    pub count: u32,
+    /// Total NEAR (in yoctoNEAR) attached to the promises.
+    pub attached_balance: Balance,
+    /// Total NEAR gas attached to the promises.
+    pub attached_gas: u64,
+}
+
+impl PromiseStats {
+    pub fn add(&mut self, promise: &PromiseCreateArgs) {
+        self.count = self.count.saturating_add(1);
+        self.attached_balance = self
+            .attached_balance
+            .saturating_add(promise.attached_balance);
+        self.attached_gas = self.attached_gas.saturating_add(promise.attached_gas);
+    }
+}
+
+/// Host functions called by a transaction, reported by the standalone engine (see
+/// `execution_profile`) so that developers can optimize contracts for their NEAR gas cost
+/// and not only their EVM gas.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ExecutionProfile {
+    /// Storage reads, key existence checks included.
+    pub storage_reads: u32,
+    pub storage_writes: u32,
+    pub storage_removes: u32,
+    /// Number of promises created, callbacks included.
+    pub promises: u32,
+}
+
 /// Borsh-encoded parameters for the `call`, `call_with_args`, `deploy_code`,
 /// and `deploy_with_input` methods.
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
@@ -110,40 +218,85 @@ pub struct SubmitResult {
     version: u8,
     pub status: TransactionStatus,
     pub gas_used: u64,
+    /// Logs in the order they were emitted, without the internal logs of the exit
+    /// precompiles (which are turned into promises).
     pub logs: Vec<ResultLog>,
+    /// Promises scheduled by the transaction, so that relayers can account for the
+    /// NEAR they cost.
+    pub promises: PromiseStats,
 }
 
 impl SubmitResult {
     /// Must be incremented when making breaking changes to the SubmitResult ABI.
-    /// The current value of 7 is chosen because previously a `TransactionStatus` object
+    /// The value of 7 was chosen because previously a `TransactionStatus` object
     /// was first in the serialization, which is an enum with less than 7 variants.
     /// Therefore, no previous `SubmitResult` would have began with a leading 7 byte,
     /// and this can be used to distinguish the new ABI (with version byte) from the old.
-    const VERSION: u8 = 7;
+    /// Version 8 appended `promises`.
+    const VERSION: u8 = 8;
 
     pub fn new(status: TransactionStatus, gas_used: u64, logs: Vec<ResultLog>) -> Self {
         Self {
             version: Self::VERSION,
-            status,
+            status: status.limit_return_data(),
             gas_used,
             logs,
+            promises: PromiseStats::default(),
         }
     }
+
+    pub fn with_promises(mut self, promises: PromiseStats) -> Self {
+        self.promises = promises;
+        self
+    }
+}
+
+/// Borsh-encoded result of the `estimate_gas` function.
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct GasEstimate {
+    /// Result of the call, as returned by `view_with_gas_used`.
+    pub result: SubmitResult,
+    /// NEAR gas needed by the promises the call schedules: the gas attached to them and
+    /// the fees of their receipts.
+    pub promises_near_gas: u64,
+    /// Lowest gas limit with which the call succeeds, which can be more than the gas it uses
+    /// (e.g. because of EIP-150). The gas used if the call fails.
+    pub gas_limit: u64,
 }
 
 /// Borsh-encoded parameters for the engine `call` function.
 #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub struct FunctionCallArgsV2 {
-    pub contract: RawAddress,
+    pub contract: EthAddress,
+    /// Wei compatible Borsh-encoded value field to attach an ETH balance to the transaction
+    pub value: WeiU256,
+    pub input: Vec<u8>,
+}
+
+/// Borsh-encoded parameters for the engine `call` function, with an idempotency key.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FunctionCallArgsV3 {
+    pub contract: EthAddress,
     /// Wei compatible Borsh-encoded value field to attach an ETH balance to the transaction
     pub value: WeiU256,
     pub input: Vec<u8>,
+    /// Key making retries of the call return its original result instead of executing it
+    /// again, see `idempotency`. `None` behaves like `FunctionCallArgsV2`.
+    pub idempotency_key: Option<RawH256>,
+}
+
+/// Borsh-encoded parameters of the private `xcc_callback` method, see `xcc`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct XccCallbackArgs {
+    /// Address which made the cross-contract call.
+    pub address: EthAddress,
+    pub input: Vec<u8>,
 }
 
 /// Legacy Borsh-encoded parameters for the engine `call` function, to provide backward type compatibility
 #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub struct FunctionCallArgsV1 {
-    pub contract: RawAddress,
+    pub contract: EthAddress,
     pub input: Vec<u8>,
 }
 
@@ -153,6 +306,7 @@ pub struct FunctionCallArgsV1 {
 pub enum CallArgs {
     V2(FunctionCallArgsV2),
     V1(FunctionCallArgsV1),
+    V3(FunctionCallArgsV3),
 }
 
 impl CallArgs {
@@ -173,28 +327,426 @@ impl CallArgs {
     }
 }
 
+/// Borsh-encoded parameters for the `approve_operator` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OperatorApprovalArgs {
+    /// EVM address granting the approval.
+    pub owner: EthAddress,
+    /// NEAR account allowed to call on behalf of `owner`.
+    pub operator: AccountId,
+    /// Maximum total amount of Wei the operator may attach to calls.
+    pub spend_limit: RawU256,
+    /// Calls the operator may make; any other call is rejected.
+    pub allowed_calls: Vec<OperatorAllowedCall>,
+    /// NEAR block height from which the approval can no longer be used.
+    pub expiry_height: u64,
+    /// Signature of `owner` over the approval message, `(r, s, v)`.
+    pub signature: [u8; 65],
+}
+
+/// Borsh-encoded parameters for the `revoke_operator` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OperatorRevocationArgs {
+    /// EVM address which approved the operator.
+    pub owner: EthAddress,
+    pub operator: AccountId,
+    /// Signature of `owner` over the revocation message, `(r, s, v)`.
+    pub signature: [u8; 65],
+}
+
+/// A contract function an operator may call on behalf of the owner.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OperatorAllowedCall {
+    pub contract: EthAddress,
+    /// First 4 bytes of the input of the allowed calls. `None` allows the calls with an input
+    /// shorter than a selector, i.e. plain transfers of value.
+    pub selector: Option<[u8; 4]>,
+}
+
+/// Borsh-encoded parameters for the `call_on_behalf` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CallOnBehalfArgs {
+    /// EVM address the call is executed from.
+    pub owner: EthAddress,
+    pub contract: EthAddress,
+    pub value: WeiU256,
+    pub input: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+/// Borsh-encoded parameters for the `get_operator_allowance` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct GetOperatorAllowanceArgs {
+    pub owner: EthAddress,
+    pub operator: AccountId,
+}
+
+/// Borsh-encoded parameters for the `set_custodian_cap` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetCustodianCapArgs {
+    pub custodian: EthAddress,
+    /// Maximum amount of ETH minted through `custodian` which may be outstanding.
+    /// `None` removes the cap.
+    pub cap: Option<Balance>,
+}
+
+/// Borsh-encoded result of the `get_custodian_supply` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct CustodianSupply {
+    /// ETH minted from deposits into the custodian, minus withdrawals from it.
+    pub minted: Balance,
+    pub cap: Option<Balance>,
+}
+
+/// Borsh-encoded parameters for the `propose_custodian_change` and `confirm_custodian_change`
+/// functions.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CustodianChangeArgs {
+    pub new_custodian: EthAddress,
+}
+
+/// Borsh-encoded result of the `get_pending_custodian_change` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PendingCustodianChange {
+    pub new_custodian: EthAddress,
+    /// Height of the block in which the change was proposed.
+    pub proposed_at: u64,
+    /// Last height at which the change can be confirmed.
+    pub expires_at: u64,
+}
+
+impl PendingCustodianChange {
+    pub fn is_expired(&self, block_height: u64) -> bool {
+        block_height > self.expires_at
+    }
+}
+
+/// Borsh-encoded parameters for the `set_inactivity_policy` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetInactivityPolicyArgs {
+    /// Zero switches the policy off.
+    pub inactivity_blocks: u64,
+}
+
+/// Borsh-encoded parameters for the `set_min_gas_price` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetMinGasPriceArgs {
+    /// Minimum gas price in Wei (big-endian); zero switches it off.
+    pub min_gas_price: RawU256,
+}
+
+/// Borsh-encoded parameters for the `set_free_tier` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetFreeTierArgs {
+    /// Contract whose incoming transactions are exempt from the minimum gas price.
+    pub address: EthAddress,
+    /// Number of exempt transactions per month; zero removes the contract from the free tier.
+    pub monthly_quota: u64,
+}
+
+/// Borsh-encoded parameters for the `set_precompile_gas_override` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetPrecompileGasOverrideArgs {
+    /// Address of a custom (non-standard) precompile.
+    pub address: EthAddress,
+    /// Percentage of its own cost charged for the precompile; 100 removes the override.
+    pub percent: u16,
+}
+
+/// Borsh-encoded parameters for the `set_deploy_allowed` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetDeployAllowedArgs {
+    pub address: EthAddress,
+    pub allowed: bool,
+}
+
+/// Borsh-encoded parameters for the `set_eip3607_enabled` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetEip3607EnabledArgs {
+    pub enabled: bool,
+}
+
+/// Borsh-encoded parameters for the `set_eth_transfer_events_enabled` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetEthTransferEventsEnabledArgs {
+    pub enabled: bool,
+}
+
+/// Borsh-encoded parameters for the `set_compliance_hook` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetComplianceHookArgs {
+    /// Bridged ERC-20.
+    pub token: EthAddress,
+    /// Compliance contract, or `None` to remove the hook.
+    pub hook: Option<EthAddress>,
+}
+
+/// Borsh-encoded parameters for the `set_fee_schedule` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetFeeScheduleArgs {
+    /// NEP-141 account of the bridged token; the engine account for ETH.
+    pub token: AccountId,
+    pub schedule: FeeSchedule,
+}
+
+/// Borsh-encoded parameters for the `set_fee_collector` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetFeeCollectorArgs {
+    /// Account credited with the withdraw fees of ETH; `None` stops charging them.
+    pub collector: Option<AccountId>,
+}
+
+/// Borsh-encoded parameters for the `get_fee_schedule` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct GetFeeScheduleArgs {
+    pub token: AccountId,
+}
+
+/// Borsh-encoded parameters for the `set_exit_swap_dex` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetExitSwapDexArgs {
+    pub dex: AccountId,
+    /// Whether exits to NEAR may swap on the DEX.
+    pub allowed: bool,
+}
+
+/// Borsh-encoded parameters for the `set_near_state_source` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetNearStateSourceArgs {
+    pub source: AccountId,
+    /// Whether the NEAR contract may publish values to the EVM.
+    pub allowed: bool,
+}
+
+/// Borsh-encoded parameters for the `mirror_near_state` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct MirrorNearStateArgs {
+    pub key: Vec<u8>,
+    /// New value of the key; `None` removes it.
+    pub value: Option<Vec<u8>>,
+}
+
+/// Borsh-encoded parameters for the `get_mirrored_near_state` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct GetMirroredNearStateArgs {
+    pub source: AccountId,
+    pub key: Vec<u8>,
+}
+
+/// Borsh-encoded parameters for the `evict_inactive_account` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct EvictAccountArgs {
+    pub address: EthAddress,
+    /// All non-zero storage slots of the account.
+    pub storage: Vec<(RawH256, RawH256)>,
+}
+
+/// Borsh-encoded parameters for the `migrate_storage_sharding` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct MigrateStorageShardingArgs {
+    pub address: EthAddress,
+    /// Storage slots to move to the sharded layout.
+    pub keys: Vec<RawH256>,
+}
+
+/// Borsh-encoded parameters for the `resurrect_account` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ResurrectAccountArgs {
+    pub address: EthAddress,
+    /// Preimage of the commitment stored when the account was evicted.
+    pub account: EvictedAccount,
+}
+
+/// Borsh-encoded parameters for the `dev_fund` function.
+#[cfg(feature = "dev")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DevFundArgs {
+    pub addresses: Vec<EthAddress>,
+    /// Amount of Wei credited to each address.
+    pub amount: Balance,
+}
+
 /// Borsh-encoded parameters for the `view` function.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
 pub struct ViewCallArgs {
-    pub sender: RawAddress,
-    pub address: RawAddress,
+    pub sender: EthAddress,
+    pub address: EthAddress,
     pub amount: RawU256,
     pub input: Vec<u8>,
 }
 
+/// Borsh-encoded parameters for the `view_transaction` function, with the fields of an
+/// `eth_call` request. Nothing is signed, so `from` can be any address.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct ViewTransactionArgs {
+    pub from: EthAddress,
+    /// Contract called; `None` simulates the deployment of `data` as init code.
+    pub to: Option<EthAddress>,
+    pub value: RawU256,
+    /// Gas available to the transaction; `None` does not limit it.
+    pub gas_limit: Option<u64>,
+    pub data: Vec<u8>,
+}
+
 /// Borsh-encoded parameters for `deploy_erc20_token` function.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq, Clone)]
 pub struct DeployErc20TokenArgs {
     pub nep141: AccountId,
 }
 
+/// Template of the ERC-20 contract deployed for a bridged NEP-141 token.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Erc20Template {
+    /// `EvmErc20`: ERC-20 with EIP-2612 permits.
+    Standard,
+    /// `EvmErc20Stablecoin`: `Standard` with the EIP-3009 transfers with authorization.
+    Stablecoin,
+    /// Template registered by the owner (see `token_templates`). The deployment fails unless
+    /// the code of the template has the given hash.
+    Registered {
+        name: String,
+        version: u8,
+        code_hash: RawH256,
+    },
+}
+
+impl Default for Erc20Template {
+    fn default() -> Self {
+        Self::Standard
+    }
+}
+
+/// Borsh-encoded parameters for `deploy_erc20_token` function choosing the template of the
+/// ERC-20 contract. The function also accepts `DeployErc20TokenArgs`, which deploys the
+/// `Standard` template.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq, Clone)]
+pub struct DeployErc20TokenWithTemplateArgs {
+    pub nep141: AccountId,
+    pub template: Erc20Template,
+}
+
+impl DeployErc20TokenWithTemplateArgs {
+    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if let Ok(value) = Self::try_from_slice(bytes) {
+            Some(value)
+        } else if let Ok(value) = DeployErc20TokenArgs::try_from_slice(bytes) {
+            Some(value.into())
+        } else {
+            None
+        }
+    }
+}
+
+impl From<DeployErc20TokenArgs> for DeployErc20TokenWithTemplateArgs {
+    fn from(args: DeployErc20TokenArgs) -> Self {
+        Self {
+            nep141: args.nep141,
+            template: Erc20Template::default(),
+        }
+    }
+}
+
+/// Borsh-encoded parameters for the `register_erc20_template` function. The code is the
+/// creation code of the ERC-20 contract, which must take the constructor arguments of the
+/// built-in templates: `(string name, string symbol, uint8 decimals, address admin)`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq, Clone)]
+pub struct RegisterErc20TemplateArgs {
+    pub name: String,
+    pub version: u8,
+    pub code: Vec<u8>,
+}
+
+/// Borsh-encoded description of an ERC-20 template, returned by the `get_erc20_templates`
+/// and `get_erc20_template` functions.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq, Clone)]
+pub struct Erc20TemplateInfo {
+    pub name: String,
+    pub version: u8,
+    /// Keccak hash of the creation code of the template.
+    pub code_hash: RawH256,
+}
+
+/// Borsh-encoded parameters for `deploy_erc721_token` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq, Clone)]
+pub struct DeployErc721TokenArgs {
+    pub nep171: AccountId,
+}
+
+/// Borsh-encoded result of the `deploy_erc20_token_v2` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeployErc20Result {
+    version: u8,
+    pub nep141: AccountId,
+    pub address: EthAddress,
+    /// Decimals the ERC-20 contract was deployed with.
+    pub decimals: u8,
+    /// Version of the ERC-20 contract template which was deployed.
+    pub template_version: u8,
+}
+
+impl DeployErc20Result {
+    /// Must be incremented when making breaking changes to the DeployErc20Result ABI.
+    const VERSION: u8 = 1;
+
+    pub fn new(nep141: AccountId, address: EthAddress, decimals: u8, template_version: u8) -> Self {
+        Self {
+            version: Self::VERSION,
+            nep141,
+            address,
+            decimals,
+            template_version,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+}
+
+impl From<&DeployErc20Result> for JsonValue {
+    fn from(result: &DeployErc20Result) -> Self {
+        let mut kvs = BTreeMap::new();
+        kvs.insert(
+            "nep141".to_string(),
+            JsonValue::String(result.nep141.to_string()),
+        );
+        kvs.insert(
+            "address".to_string(),
+            JsonValue::String(hex::encode(result.address)),
+        );
+        kvs.insert(
+            "decimals".to_string(),
+            JsonValue::U64(result.decimals as u64),
+        );
+        kvs.insert(
+            "template_version".to_string(),
+            JsonValue::U64(result.template_version as u64),
+        );
+        JsonValue::Object(kvs)
+    }
+}
+
 /// Borsh-encoded parameters for `get_erc20_from_nep141` function.
 pub type GetErc20FromNep141CallArgs = DeployErc20TokenArgs;
 
+/// Borsh-encoded parameters for the `convert_nep141_amount_to_erc20` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ConvertNep141AmountArgs {
+    pub nep141: AccountId,
+    pub amount: Balance,
+}
+
+/// Borsh-encoded parameters for the `convert_erc20_amount_to_nep141` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ConvertErc20AmountArgs {
+    pub erc20: EthAddress,
+    pub amount: RawU256,
+}
+
 /// Borsh-encoded parameters for the `get_storage_at` function.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct GetStorageAtArgs {
-    pub address: RawAddress,
+    pub address: EthAddress,
     pub key: RawH256,
 }
 
@@ -202,7 +754,7 @@ pub struct GetStorageAtArgs {
 #[cfg(feature = "evm_bully")]
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct AccountBalance {
-    pub address: RawAddress,
+    pub address: EthAddress,
     pub balance: RawU256,
 }
 
@@ -221,7 +773,7 @@ pub struct BeginBlockArgs {
     /// The current block's hash (for replayer use).
     pub hash: RawU256,
     /// The current block's beneficiary address.
-    pub coinbase: RawAddress,
+    pub coinbase: EthAddress,
     /// The current block's timestamp (in seconds since the Unix epoch).
     pub timestamp: RawU256,
     /// The current block's number (the genesis block is number zero).
@@ -266,6 +818,30 @@ impl From<NEP141FtOnTransferArgs> for String {
     }
 }
 
+/// JSON arguments of `nft_on_transfer`, called by a NEP-171 collection on `nft_transfer_call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftOnTransferArgs {
+    pub sender_id: AccountId,
+    pub previous_owner_id: AccountId,
+    pub token_id: String,
+    pub msg: String,
+}
+
+impl TryFrom<JsonValue> for NftOnTransferArgs {
+    type Error = JsonError;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender_id: AccountId::try_from(value.string("sender_id")?)
+                .map_err(|_| JsonError::InvalidString)?,
+            previous_owner_id: AccountId::try_from(value.string("previous_owner_id")?)
+                .map_err(|_| JsonError::InvalidString)?,
+            token_id: value.string("token_id")?,
+            msg: value.string("msg")?,
+        })
+    }
+}
+
 /// Eth-connector deposit arguments
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct DepositCallArgs {
@@ -283,12 +859,48 @@ pub struct IsUsedProofCallArgs {
 }
 
 /// withdraw result for eth-connector
+///
+/// The custodian on Ethereum reads `amount`, `recipient_id` and `eth_custodian_address`, in
+/// this order, from the proof of the withdrawal; fields are only appended after them.
 #[derive(BorshSerialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(BorshDeserialize))]
 pub struct WithdrawResult {
     pub amount: Balance,
-    pub recipient_id: RawAddress,
-    pub eth_custodian_address: RawAddress,
+    pub recipient_id: EthAddress,
+    pub eth_custodian_address: EthAddress,
+    /// Number of withdrawals before this one, which identifies it.
+    pub nonce: u64,
+}
+
+/// Withdrawal to an Ethereum address which was not finalized yet, returned by
+/// `get_pending_withdrawals`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PendingWithdrawal {
+    /// Nonce of the withdrawal, see `WithdrawResult`.
+    pub nonce: u64,
+    /// NEAR account the nETH was burned from; the engine for exits from Aurora.
+    pub sender_id: AccountId,
+    pub recipient_id: EthAddress,
+    pub amount: Balance,
+    pub eth_custodian_address: EthAddress,
+}
+
+/// Borsh-encoded parameters for the `get_pending_withdrawals` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct GetPendingWithdrawalsArgs {
+    pub account_id: AccountId,
+    /// Index of the first withdrawal of the account to return; the `next_index` of the
+    /// previous page, or zero.
+    pub from_index: u64,
+    pub limit: u64,
+}
+
+/// Page of the pending withdrawals of an account, oldest first.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct PendingWithdrawalsPage {
+    pub withdrawals: Vec<PendingWithdrawal>,
+    /// Index to request the next page from, `None` on the last page.
+    pub next_index: Option<u64>,
 }
 
 /// Fungible token storage balance
@@ -329,6 +941,29 @@ pub struct FinishDepositCallArgs {
     pub msg: Option<Vec<u8>>,
 }
 
+/// Borsh-encoded parameters for the `finish_withdraw_with_proof` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FinishWithdrawWithProofArgs {
+    /// Nonce of the pending withdrawal the proof finalizes, see `WithdrawResult`.
+    pub nonce: u64,
+    /// Proof of the `Withdrawn` event of the custodian releasing the ETH of the withdrawal.
+    pub proof: Proof,
+}
+
+/// Finish withdraw NEAR eth-connector call args: the pending withdrawal whose `Withdrawn`
+/// event was proved by `finish_withdraw_with_proof`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FinishWithdrawCallArgs {
+    pub nonce: u64,
+    pub proof_key: String,
+}
+
+/// Borsh-encoded parameters for the `forget_pending_withdrawal` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ForgetPendingWithdrawalArgs {
+    pub nonce: u64,
+}
+
 /// Deposit ETH args
 #[derive(Default, BorshDeserialize, BorshSerialize, Clone)]
 pub struct DepositEthCallArgs {
@@ -537,8 +1172,8 @@ mod tests {
     #[test]
     fn test_roundtrip_view_call() {
         let x = ViewCallArgs {
-            sender: [1; 20],
-            address: [2; 20],
+            sender: EthAddress::new([1; 20]),
+            address: EthAddress::new([2; 20]),
             amount: [3; 32],
             input: vec![1, 2, 3],
         };
@@ -547,15 +1182,59 @@ mod tests {
         assert_eq!(x, res);
     }
 
+    #[test]
+    fn test_parse_nft_on_transfer_args() {
+        let json = crate::json::parse_json(
+            br#"{"sender_id": "alice.near", "previous_owner_id": "bob.near", "token_id": "1", "msg": "00"}"#,
+        )
+        .unwrap();
+        let args = NftOnTransferArgs::try_from(json).unwrap();
+        assert_eq!(args.sender_id.as_ref(), "alice.near");
+        assert_eq!(args.previous_owner_id.as_ref(), "bob.near");
+        assert_eq!(args.token_id, "1");
+        assert_eq!(args.msg, "00");
+
+        let json =
+            crate::json::parse_json(br#"{"sender_id": "alice.near", "token_id": "1"}"#).unwrap();
+        assert!(NftOnTransferArgs::try_from(json).is_err());
+    }
+
+    #[test]
+    fn test_limit_return_data() {
+        let status = TransactionStatus::Succeed(vec![1; MAX_RETURN_DATA_LEN]);
+        assert_eq!(
+            status.limit_return_data(),
+            TransactionStatus::Succeed(vec![1; MAX_RETURN_DATA_LEN])
+        );
+
+        let data = vec![1; MAX_RETURN_DATA_LEN + 1];
+        let len = data.len() as u64;
+        let hash = crate::prelude::sdk::keccak(&data).0;
+        let expected = |reverted| TransactionStatus::ReturnDataTooLarge {
+            reverted,
+            len,
+            hash,
+        };
+        let status = TransactionStatus::Succeed(data.clone()).limit_return_data();
+        assert_eq!(status, expected(false));
+        assert!(status.is_ok());
+        let status = TransactionStatus::Revert(data.clone()).limit_return_data();
+        assert_eq!(status, expected(true));
+        assert!(status.is_revert());
+
+        let result = SubmitResult::new(TransactionStatus::Succeed(data), 0, Vec::new());
+        assert_eq!(result.status, expected(false));
+    }
+
     #[test]
     fn test_call_args_deserialize() {
         let new_input = FunctionCallArgsV2 {
-            contract: [0u8; 20],
+            contract: EthAddress::zero(),
             value: WeiU256::default(),
             input: Vec::new(),
         };
         let legacy_input = FunctionCallArgsV1 {
-            contract: [0u8; 20],
+            contract: EthAddress::zero(),
             input: Vec::new(),
         };
 
@@ -588,5 +1267,59 @@ mod tests {
         let input_bytes = new_input.try_to_vec().unwrap();
         let parsed_data = CallArgs::deserialize(&input_bytes);
         assert_eq!(parsed_data, None);
+
+        // Using new input format with an idempotency key.
+        let idempotent_input = FunctionCallArgsV3 {
+            contract: EthAddress::zero(),
+            value: WeiU256::default(),
+            input: Vec::new(),
+            idempotency_key: Some([7; 32]),
+        };
+        let input_bytes = CallArgs::V3(idempotent_input.clone()).try_to_vec().unwrap();
+        let parsed_data = CallArgs::deserialize(&input_bytes);
+        assert_eq!(parsed_data, Some(CallArgs::V3(idempotent_input)));
+    }
+
+    #[test]
+    fn test_deploy_erc20_token_args_deserialize() {
+        let nep141 = AccountId::new("token.near").unwrap();
+
+        let legacy_input = DeployErc20TokenArgs {
+            nep141: nep141.clone(),
+        };
+        let parsed_data =
+            DeployErc20TokenWithTemplateArgs::deserialize(&legacy_input.try_to_vec().unwrap());
+        assert_eq!(
+            parsed_data,
+            Some(DeployErc20TokenWithTemplateArgs {
+                nep141: nep141.clone(),
+                template: Erc20Template::Standard,
+            })
+        );
+
+        let input = DeployErc20TokenWithTemplateArgs {
+            nep141,
+            template: Erc20Template::Stablecoin,
+        };
+        let parsed_data =
+            DeployErc20TokenWithTemplateArgs::deserialize(&input.try_to_vec().unwrap());
+        assert_eq!(parsed_data, Some(input));
+
+        let input = DeployErc20TokenWithTemplateArgs {
+            nep141: AccountId::new("token.near").unwrap(),
+            template: Erc20Template::Registered {
+                name: "audited".to_string(),
+                version: 3,
+                code_hash: [7u8; 32],
+            },
+        };
+        let parsed_data =
+            DeployErc20TokenWithTemplateArgs::deserialize(&input.try_to_vec().unwrap());
+        assert_eq!(parsed_data, Some(input));
+
+        assert_eq!(
+            DeployErc20TokenWithTemplateArgs::deserialize(&[1, 2, 3]),
+            None
+        );
     }
 }
@@ -0,0 +1,73 @@
+//! Owner overrides of the gas costs of the custom precompiles.
+//!
+//! The owner can scale the cost of any precompile of the engine which is not a standard
+//! Ethereum one, by a percentage between `MIN_PERCENT` and `MAX_PERCENT`, to fix a mispriced
+//! precompile without a code upgrade. The precompiles apply the stored percentage
+//! themselves, see `aurora_engine_precompiles::gas_override`.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::precompiles::gas_override::{
+    self, gas_override_key, DEFAULT_PERCENT, MAX_PERCENT, MIN_PERCENT,
+};
+use crate::prelude::precompiles::Precompiles;
+use crate::prelude::{Address, BTreeMap, EthAddress, ToString};
+use aurora_engine_sdk::io::IO;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecompileGasError {
+    NotCustomPrecompile,
+    PercentOutOfRange,
+}
+
+impl AsRef<[u8]> for PrecompileGasError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::NotCustomPrecompile => b"ERR_NOT_CUSTOM_PRECOMPILE",
+            Self::PercentOutOfRange => b"ERR_GAS_PERCENT_OUT_OF_RANGE",
+        }
+    }
+}
+
+/// Percentage applied to the cost of the precompile; `DEFAULT_PERCENT` without override.
+pub fn get_percent<I: IO>(io: &I, address: &Address) -> u16 {
+    gas_override::read_percent(io, address).unwrap_or(DEFAULT_PERCENT)
+}
+
+/// Sets the percentage applied to the cost of the custom precompile at `address`, one of
+/// `precompiles`. Setting `DEFAULT_PERCENT` removes the override.
+pub fn set_percent<I: IO>(
+    io: &mut I,
+    precompiles: &Precompiles,
+    address: &Address,
+    percent: u16,
+) -> Result<(), PrecompileGasError> {
+    if gas_override::is_standard(address) || !precompiles.all_precompiles.contains_key(address) {
+        return Err(PrecompileGasError::NotCustomPrecompile);
+    }
+    if !(MIN_PERCENT..=MAX_PERCENT).contains(&percent) {
+        return Err(PrecompileGasError::PercentOutOfRange);
+    }
+    let previous = get_percent(io, address);
+    if percent == DEFAULT_PERCENT {
+        io.remove_storage(&gas_override_key(address));
+    } else {
+        io.write_storage(&gas_override_key(address), &percent.to_le_bytes());
+    }
+    events::emit_config_changed(
+        "precompile_gas",
+        override_data(address, previous),
+        override_data(address, percent),
+    );
+    Ok(())
+}
+
+fn override_data(address: &Address, percent: u16) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "address".to_string(),
+        JsonValue::String(EthAddress::from(*address).encode()),
+    );
+    kvs.insert("percent".to_string(), JsonValue::U64(u64::from(percent)));
+    JsonValue::Object(kvs)
+}
@@ -0,0 +1,164 @@
+//! Preflight validation of transactions, for relayers.
+//!
+//! A relayer pays the NEAR gas of a `submit` even when the transaction is rejected before
+//! execution, or fails for lack of funds. `validate_transaction` runs the checks of
+//! `submit` which come before the execution (signature, chain id, nonce, EIP-3607, intrinsic
+//! gas, fees, deployment gate and minimum gas price) without changing the state, and also
+//! checks that the balance of the sender covers the gas limit at the gas price plus the
+//! value. The verdict tells why a transaction is doomed, so that a relayer can, for
+//! instance, hold a transaction whose nonce is ahead of the sender's rather than drop it.
+
+use crate::deploy_gate;
+use crate::engine::{self, EngineErrorKind, EngineState, GasPaymentError};
+use crate::era;
+use crate::min_gas_price;
+use crate::prelude::{
+    u256_to_arr, Address, BorshDeserialize, BorshSerialize, EthAddress, RawU256, String, TryFrom,
+    TryInto, U256,
+};
+use crate::transaction::{EthTransactionKind, NormalizedEthTransaction};
+use aurora_engine_sdk::env::Timestamp;
+use aurora_engine_sdk::io::IO;
+
+/// Borsh-encoded result of `validate_transaction`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionVerdict {
+    /// `submit` would execute the transaction.
+    Valid {
+        sender: EthAddress,
+        intrinsic_gas: u64,
+        /// Gas limit times gas price, plus value: the most the transaction can cost the sender.
+        max_cost: RawU256,
+    },
+    /// The nonce is not the next one of the sender: `actual` is lower if the nonce was
+    /// already used, higher if transactions are missing before it.
+    IncorrectNonce {
+        sender: EthAddress,
+        expected: RawU256,
+        actual: RawU256,
+    },
+    /// The balance of the sender does not cover `max_cost`.
+    InsufficientBalance {
+        sender: EthAddress,
+        balance: RawU256,
+        max_cost: RawU256,
+    },
+    /// `submit` would reject the transaction with the `error` code. The sender is unknown if
+    /// the transaction cannot be parsed, or is signed for another chain.
+    Invalid {
+        sender: Option<EthAddress>,
+        error: String,
+    },
+}
+
+impl TransactionVerdict {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid { .. })
+    }
+
+    fn invalid(sender: Option<Address>, error: &[u8]) -> Self {
+        Self::Invalid {
+            sender: sender.map(EthAddress::from),
+            error: String::from_utf8_lossy(error).into(),
+        }
+    }
+}
+
+/// Verdict of `submit` on the transaction, as of the current state.
+pub fn validate_transaction<I: IO>(
+    io: &I,
+    state: &EngineState,
+    transaction_bytes: &[u8],
+    block_height: u64,
+    now: Timestamp,
+) -> TransactionVerdict {
+    let transaction: NormalizedEthTransaction =
+        match EthTransactionKind::try_from(transaction_bytes) {
+            Ok(transaction) => transaction.into(),
+            Err(e) => return TransactionVerdict::invalid(None, e.as_ref()),
+        };
+
+    if let Some(chain_id) = transaction.chain_id {
+        if U256::from(chain_id) != U256::from(state.chain_id) {
+            return TransactionVerdict::invalid(None, EngineErrorKind::InvalidChainId.as_bytes());
+        }
+    }
+
+    let sender = match transaction.address {
+        Some(sender) => sender,
+        None => {
+            return TransactionVerdict::invalid(None, EngineErrorKind::InvalidSignature.as_bytes())
+        }
+    };
+    let invalid =
+        |kind: EngineErrorKind| TransactionVerdict::invalid(Some(sender), kind.as_bytes());
+
+    let expected_nonce = engine::get_nonce(io, &sender);
+    if transaction.nonce != expected_nonce {
+        return TransactionVerdict::IncorrectNonce {
+            sender: sender.into(),
+            expected: u256_to_arr(&expected_nonce),
+            actual: u256_to_arr(&transaction.nonce),
+        };
+    }
+
+    if engine::is_eip3607_enabled(io) && engine::get_code_size(io, &sender) != 0 {
+        return invalid(EngineErrorKind::SenderHasCode);
+    }
+
+    let intrinsic_gas = match transaction.intrinsic_gas(era::at(block_height).config()) {
+        Some(intrinsic_gas) => intrinsic_gas,
+        None => return invalid(EngineErrorKind::GasOverflow),
+    };
+    if transaction.gas_limit < intrinsic_gas.into() {
+        return invalid(EngineErrorKind::IntrinsicGasNotMet);
+    }
+    if TryInto::<u64>::try_into(transaction.gas_limit).is_err() {
+        return invalid(EngineErrorKind::GasOverflow);
+    }
+
+    if transaction.max_priority_fee_per_gas > transaction.max_fee_per_gas {
+        return invalid(EngineErrorKind::MaxPriorityGasFeeTooLarge);
+    }
+
+    if transaction.to.is_none() {
+        if let Err(e) = deploy_gate::check(io, &sender) {
+            return invalid(EngineErrorKind::DeployGate(e));
+        }
+    }
+
+    if let Err(e) = min_gas_price::validate(io, &transaction, now) {
+        return invalid(EngineErrorKind::MinGasPrice(e));
+    }
+
+    // As in `Engine::charge_gas`, with a base fee of zero.
+    let gas_price = transaction
+        .max_priority_fee_per_gas
+        .min(transaction.max_fee_per_gas);
+    let max_cost = match transaction
+        .gas_limit
+        .checked_mul(gas_price)
+        .and_then(|gas_cost| gas_cost.checked_add(transaction.value.raw()))
+    {
+        Some(max_cost) => max_cost,
+        None => {
+            return invalid(EngineErrorKind::GasPayment(
+                GasPaymentError::EthAmountOverflow,
+            ))
+        }
+    };
+    let balance = engine::get_balance(io, &sender).raw();
+    if balance < max_cost {
+        return TransactionVerdict::InsufficientBalance {
+            sender: sender.into(),
+            balance: u256_to_arr(&balance),
+            max_cost: u256_to_arr(&max_cost),
+        };
+    }
+
+    TransactionVerdict::Valid {
+        sender: sender.into(),
+        intrinsic_gas,
+        max_cost: u256_to_arr(&max_cost),
+    }
+}
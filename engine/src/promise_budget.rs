@@ -0,0 +1,148 @@
+//! Per-transaction budget of NEAR promises.
+//!
+//! The exit precompiles (and any other code path turning EVM logs into promises) let a
+//! single EVM transaction schedule an arbitrary number of cross-contract calls, each with
+//! NEAR attached. The engine reports what every transaction scheduled in its
+//! `SubmitResult`, and the owner can cap both the number of promises and the total NEAR
+//! attached to them. A transaction exceeding the budget is rejected before any of its
+//! promises is scheduled.
+
+use crate::parameters::PromiseStats;
+use crate::prelude::{bytes_to_key, sdk, BorshDeserialize, BorshSerialize, KeyPrefix, PromiseArgs};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const PROMISE_BUDGET_KEY: &[u8; 14] = b"PROMISE_BUDGET";
+
+/// Owner-set limits on the promises a single transaction can schedule.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromiseBudget {
+    /// Maximum number of promises, callbacks included.
+    pub max_promises: u32,
+    /// Maximum total NEAR (in yoctoNEAR) attached to the promises.
+    pub max_attached_balance: u128,
+}
+
+impl Default for PromiseBudget {
+    /// No limits.
+    fn default() -> Self {
+        Self {
+            max_promises: u32::MAX,
+            max_attached_balance: u128::MAX,
+        }
+    }
+}
+
+impl PromiseBudget {
+    pub fn check(&self, stats: &PromiseStats) -> Result<(), PromiseBudgetError> {
+        if stats.count > self.max_promises {
+            return Err(PromiseBudgetError::TooManyPromises);
+        }
+        if stats.attached_balance > self.max_attached_balance {
+            return Err(PromiseBudgetError::AttachedBalanceTooHigh);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromiseBudgetError {
+    TooManyPromises,
+    AttachedBalanceTooHigh,
+    InvalidStoredBudget,
+}
+
+impl AsRef<[u8]> for PromiseBudgetError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::TooManyPromises => b"ERR_PROMISE_BUDGET_TOO_MANY_PROMISES",
+            Self::AttachedBalanceTooHigh => b"ERR_PROMISE_BUDGET_ATTACHED_BALANCE",
+            Self::InvalidStoredBudget => b"ERR_INVALID_PROMISE_BUDGET",
+        }
+    }
+}
+
+pub fn get_budget<I: IO>(io: &I) -> Result<PromiseBudget, PromiseBudgetError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, PROMISE_BUDGET_KEY)) {
+        None => Ok(PromiseBudget::default()),
+        Some(bytes) => PromiseBudget::try_from_slice(&bytes.to_vec())
+            .map_err(|_| PromiseBudgetError::InvalidStoredBudget),
+    }
+}
+
+pub fn set_budget<I: IO>(io: &mut I, budget: PromiseBudget) {
+    io.write_borsh(
+        &bytes_to_key(KeyPrefix::Config, PROMISE_BUDGET_KEY),
+        &budget,
+    );
+    sdk::log!(crate::prelude::format!(
+        "Promise budget set to {} promises, {} yoctoNEAR",
+        budget.max_promises,
+        budget.max_attached_balance
+    )
+    .as_str());
+}
+
+/// Adds the promises scheduled by `promise` to `stats`.
+pub fn account(stats: &mut PromiseStats, promise: &PromiseArgs) {
+    match promise {
+        PromiseArgs::Create(promise) => stats.add(promise),
+        PromiseArgs::Callback(promise) => {
+            stats.add(&promise.base);
+            stats.add(&promise.callback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{PromiseCreateArgs, PromiseWithCallbackArgs, Vec};
+
+    fn promise(attached_balance: u128) -> PromiseCreateArgs {
+        PromiseCreateArgs {
+            target_account_id: "target.near".parse().unwrap(),
+            method: "method".into(),
+            args: Vec::new(),
+            attached_balance,
+            attached_gas: 10,
+        }
+    }
+
+    #[test]
+    fn test_budget_counts_callbacks() {
+        let mut stats = PromiseStats::default();
+        account(&mut stats, &PromiseArgs::Create(promise(1)));
+        account(
+            &mut stats,
+            &PromiseArgs::Callback(PromiseWithCallbackArgs {
+                base: promise(2),
+                callback: promise(3),
+            }),
+        );
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.attached_balance, 6);
+        assert_eq!(stats.attached_gas, 30);
+
+        let budget = PromiseBudget {
+            max_promises: 3,
+            max_attached_balance: 6,
+        };
+        assert_eq!(budget.check(&stats), Ok(()));
+        let budget = PromiseBudget {
+            max_promises: 2,
+            ..budget
+        };
+        assert_eq!(
+            budget.check(&stats),
+            Err(PromiseBudgetError::TooManyPromises)
+        );
+        let budget = PromiseBudget {
+            max_promises: 3,
+            max_attached_balance: 5,
+        };
+        assert_eq!(
+            budget.check(&stats),
+            Err(PromiseBudgetError::AttachedBalanceTooHigh)
+        );
+    }
+}
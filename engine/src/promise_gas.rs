@@ -0,0 +1,79 @@
+//! NEAR gas needed by the promises a transaction schedules.
+//!
+//! The EVM gas of a transaction says nothing about the promises scheduled by the exit
+//! (and other cross-contract) precompiles: each of them needs the NEAR gas attached to
+//! it, and the engine pays the fees of creating its receipt. Relayers add this to the gas
+//! of the `submit` call so that the whole chain of receipts can complete.
+//!
+//! The fees are those of the NEAR runtime config for a receipt sent to another account
+//! (send and execution fees both count).
+
+use crate::prelude::{PromiseArgs, PromiseCreateArgs};
+
+/// Fee of an action receipt.
+const ACTION_RECEIPT_GAS: u64 = 2 * 108_059_500_000;
+/// Base fee of a function call action.
+const FUNCTION_CALL_GAS: u64 = 2 * 2_319_861_500_000;
+/// Fee per byte of method name and arguments of a function call action.
+const FUNCTION_CALL_BYTE_GAS: u64 = 2 * 2_235_934;
+/// Fee of the data receipt passing the result of a promise to its callback.
+const DATA_RECEIPT_GAS: u64 = 2 * 4_697_339_419_375;
+
+fn create_gas(promise: &PromiseCreateArgs) -> u64 {
+    let bytes = (promise.method.len() + promise.args.len()) as u64;
+    ACTION_RECEIPT_GAS
+        .saturating_add(FUNCTION_CALL_GAS)
+        .saturating_add(FUNCTION_CALL_BYTE_GAS.saturating_mul(bytes))
+        .saturating_add(promise.attached_gas)
+}
+
+/// NEAR gas needed by `promise`, callback included: the gas attached to it and the fees
+/// of its receipts.
+pub fn promise_gas(promise: &PromiseArgs) -> u64 {
+    match promise {
+        PromiseArgs::Create(promise) => create_gas(promise),
+        PromiseArgs::Callback(promise) => create_gas(&promise.base)
+            .saturating_add(create_gas(&promise.callback))
+            .saturating_add(DATA_RECEIPT_GAS),
+    }
+}
+
+/// NEAR gas needed by all the given promises.
+pub fn total_gas(promises: &[PromiseArgs]) -> u64 {
+    promises.iter().fold(0, |total, promise| {
+        total.saturating_add(promise_gas(promise))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{PromiseWithCallbackArgs, Vec};
+
+    fn promise(attached_gas: u64) -> PromiseCreateArgs {
+        PromiseCreateArgs {
+            target_account_id: "target.near".parse().unwrap(),
+            method: "method".into(),
+            args: Vec::new(),
+            attached_balance: 0,
+            attached_gas,
+        }
+    }
+
+    #[test]
+    fn test_promise_gas() {
+        let fees = ACTION_RECEIPT_GAS + FUNCTION_CALL_GAS + 6 * FUNCTION_CALL_BYTE_GAS;
+        let create = PromiseArgs::Create(promise(10));
+        assert_eq!(promise_gas(&create), fees + 10);
+
+        let callback = PromiseArgs::Callback(PromiseWithCallbackArgs {
+            base: promise(10),
+            callback: promise(20),
+        });
+        assert_eq!(promise_gas(&callback), 2 * fees + DATA_RECEIPT_GAS + 30);
+        assert_eq!(
+            total_gas(&[create, callback]),
+            3 * fees + DATA_RECEIPT_GAS + 40
+        );
+    }
+}
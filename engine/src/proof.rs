@@ -23,3 +23,17 @@ impl Proof {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_with_crafted_length_is_rejected() {
+        let mut bytes = Proof::default().try_to_vec().unwrap();
+        // The last field is `proof`; claim it has `u32::MAX` nodes.
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Proof::try_from_slice(&bytes).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! Protocol fee switch.
+//!
+//! When enabled by the owner, a share of the gas fee paid by every `submit` transaction
+//! is diverted from the relayer to a treasury address inside the EVM. The total amount
+//! ever credited to the treasury is tracked so it can be queried without replaying history.
+//! Each collection emits a `protocol_fee_collected` event.
+//!
+//! Only the gas fee is shared: the bridge fees of `fee_schedule` go to the relayer and to
+//! the fee collector as before. The treasury is credited by `refund_unused_gas`, through the
+//! IO of the transaction like the relayer, so the credit is in the balance journal and in
+//! the `ft_transfer` events of the transaction. Like the other gas payments, it is not part
+//! of the state commitment.
+
+use crate::engine::BalanceOverflow;
+use crate::json::JsonValue;
+use crate::prelude::{
+    bytes_to_key, u256_to_arr, Address, BTreeMap, BorshDeserialize, BorshSerialize, EthAddress,
+    KeyPrefix, ToString, Wei, U256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+/// Fees are expressed in basis points, i.e. 1/100th of a percent.
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+
+/// Name of the event emitted when a protocol fee is credited to the treasury.
+pub const PROTOCOL_FEE_COLLECTED: &str = "protocol_fee_collected";
+
+const PROTOCOL_FEE_KEY: &[u8; 12] = b"PROTOCOL_FEE";
+const PROTOCOL_FEE_ACCUMULATED_KEY: &[u8; 24] = b"PROTOCOL_FEE_ACCUMULATED";
+
+/// Owner-set configuration of the protocol fee.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolFeeConfig {
+    /// EVM address which receives the protocol share of the fees.
+    pub treasury: EthAddress,
+    /// Share of the gas fee (in basis points) sent to the treasury.
+    /// A value of zero switches the protocol fee off.
+    pub fee_basis_points: u16,
+}
+
+impl ProtocolFeeConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.fee_basis_points != 0
+    }
+
+    /// Splits `amount` into the protocol share and the remainder.
+    pub fn split(&self, amount: Wei) -> Result<(Wei, Wei), ProtocolFeeError> {
+        let protocol_share = amount
+            .raw()
+            .checked_mul(U256::from(self.fee_basis_points))
+            .ok_or(ProtocolFeeError::FeeOverflow)?
+            / U256::from(MAX_FEE_BASIS_POINTS);
+        // Only underflows if `fee_basis_points` is above `MAX_FEE_BASIS_POINTS`.
+        let remainder = amount
+            .raw()
+            .checked_sub(protocol_share)
+            .ok_or(ProtocolFeeError::FeeTooHigh)?;
+        Ok((Wei::new(protocol_share), Wei::new(remainder)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolFeeError {
+    FeeTooHigh,
+    FeeOverflow,
+    InvalidStoredConfig,
+}
+
+impl AsRef<[u8]> for ProtocolFeeError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::FeeTooHigh => b"ERR_PROTOCOL_FEE_TOO_HIGH",
+            Self::FeeOverflow => b"ERR_PROTOCOL_FEE_OVERFLOW",
+            Self::InvalidStoredConfig => b"ERR_INVALID_PROTOCOL_FEE_CONFIG",
+        }
+    }
+}
+
+pub fn get_config<I: IO>(io: &I) -> Result<ProtocolFeeConfig, ProtocolFeeError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, PROTOCOL_FEE_KEY)) {
+        None => Ok(ProtocolFeeConfig::default()),
+        Some(bytes) => ProtocolFeeConfig::try_from_slice(&bytes.to_vec())
+            .map_err(|_| ProtocolFeeError::InvalidStoredConfig),
+    }
+}
+
+pub fn set_config<I: IO>(io: &mut I, config: ProtocolFeeConfig) -> Result<(), ProtocolFeeError> {
+    if config.fee_basis_points > MAX_FEE_BASIS_POINTS {
+        return Err(ProtocolFeeError::FeeTooHigh);
+    }
+    let previous = get_config(io)
+        .ok()
+        .map_or(JsonValue::Null, |previous| config_data(&previous));
+    io.write_borsh(&bytes_to_key(KeyPrefix::Config, PROTOCOL_FEE_KEY), &config);
+    crate::events::emit_config_changed("protocol_fee", previous, config_data(&config));
+    Ok(())
+}
+
+fn config_data(config: &ProtocolFeeConfig) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "treasury".to_string(),
+        JsonValue::String(hex::encode(config.treasury)),
+    );
+    kvs.insert(
+        "fee_basis_points".to_string(),
+        JsonValue::U64(config.fee_basis_points as u64),
+    );
+    JsonValue::Object(kvs)
+}
+
+/// Total amount of Wei ever credited to the treasury (across all treasury addresses).
+pub fn get_accumulated<I: IO>(io: &I) -> Wei {
+    io.read_u256(&bytes_to_key(
+        KeyPrefix::Config,
+        PROTOCOL_FEE_ACCUMULATED_KEY,
+    ))
+    .map(Wei::new)
+    .unwrap_or_else(|_| Wei::zero())
+}
+
+fn collected_data(treasury: &EthAddress, amount: Wei, accumulated: Wei) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "treasury".to_string(),
+        JsonValue::String(hex::encode(treasury)),
+    );
+    // As strings, since Wei amounts may not fit in a JSON number.
+    kvs.insert("amount".to_string(), JsonValue::String(amount.to_string()));
+    kvs.insert(
+        "accumulated".to_string(),
+        JsonValue::String(accumulated.to_string()),
+    );
+    JsonValue::Object(kvs)
+}
+
+/// Part of a gas fee owed to the treasury.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolShare {
+    pub treasury: Address,
+    pub amount: Wei,
+}
+
+/// Takes the protocol share out of `fee` and accounts for it. Returns the share, which the
+/// caller credits to the treasury, and the part of the fee which is left for the relayer.
+pub fn collect<I: IO>(
+    io: &mut I,
+    fee: Wei,
+) -> Result<(Option<ProtocolShare>, Wei), BalanceOverflow> {
+    let config = match get_config(io) {
+        Ok(config) if config.is_enabled() => config,
+        // A broken config must never make user transactions fail; fall back to no fee.
+        _ => return Ok((None, fee)),
+    };
+    let (protocol_share, remainder) = match config.split(fee) {
+        Ok(split) => split,
+        Err(_) => return Ok((None, fee)),
+    };
+    if protocol_share.is_zero() {
+        return Ok((None, remainder));
+    }
+
+    let accumulated = get_accumulated(io)
+        .checked_add(protocol_share)
+        .ok_or(BalanceOverflow)?;
+    io.write_storage(
+        &bytes_to_key(KeyPrefix::Config, PROTOCOL_FEE_ACCUMULATED_KEY),
+        &u256_to_arr(&accumulated.raw()),
+    );
+    crate::events::emit(
+        PROTOCOL_FEE_COLLECTED,
+        collected_data(&config.treasury, protocol_share, accumulated),
+    );
+
+    let share = ProtocolShare {
+        treasury: Address::from(config.treasury),
+        amount: protocol_share,
+    };
+    Ok((Some(share), remainder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_protocol_fee() {
+        let config = ProtocolFeeConfig {
+            treasury: EthAddress::new([1u8; 20]),
+            fee_basis_points: 250,
+        };
+        let (protocol_share, remainder) = config.split(Wei::new_u64(10_000)).unwrap();
+        assert_eq!(protocol_share, Wei::new_u64(250));
+        assert_eq!(remainder, Wei::new_u64(9_750));
+
+        // rounding always favours the relayer
+        let (protocol_share, remainder) = config.split(Wei::new_u64(39)).unwrap();
+        assert_eq!(protocol_share, Wei::zero());
+        assert_eq!(remainder, Wei::new_u64(39));
+    }
+
+    #[test]
+    fn test_split_protocol_fee_overflow() {
+        let config = ProtocolFeeConfig {
+            treasury: EthAddress::new([1u8; 20]),
+            fee_basis_points: 250,
+        };
+        assert_eq!(
+            config.split(Wei::new(U256::max_value())),
+            Err(ProtocolFeeError::FeeOverflow)
+        );
+        let config = ProtocolFeeConfig {
+            fee_basis_points: MAX_FEE_BASIS_POINTS + 1,
+            ..config
+        };
+        assert_eq!(
+            config.split(Wei::new_u64(10_000)),
+            Err(ProtocolFeeError::FeeTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_disabled_protocol_fee() {
+        let config = ProtocolFeeConfig::default();
+        assert!(!config.is_enabled());
+        let (protocol_share, remainder) = config.split(Wei::new_u64(1_000)).unwrap();
+        assert_eq!(protocol_share, Wei::zero());
+        assert_eq!(remainder, Wei::new_u64(1_000));
+    }
+}
@@ -0,0 +1,193 @@
+//! Self-test of the engine, run by the owner right after an upgrade.
+//!
+//! `run` checks that the stored configuration can still be read by the deployed code, that
+//! the upgrade was completed by its `state_migration`, that every built-in precompile is
+//! registered (and not shadowed by EVM code), and that a plain transfer executes with the
+//! expected gas. Every check runs even if an earlier one failed; the report says which
+//! ones failed and why, so that the owner can decide whether to unpause the engine.
+
+use crate::engine::{self, Engine};
+use crate::era;
+use crate::fee_schedule;
+use crate::inactivity;
+use crate::min_gas_price;
+use crate::parameters::TransactionStatus;
+use crate::prelude::precompiles::account_to_address::NearAccountToAddress;
+use crate::prelude::precompiles::blake2::Blake2F;
+use crate::prelude::precompiles::bn128::{Bn128Add, Bn128Mul, Bn128Pair};
+use crate::prelude::precompiles::hash::{RIPEMD160, SHA256};
+use crate::prelude::precompiles::identity::Identity;
+use crate::prelude::precompiles::modexp::ModExp;
+use crate::prelude::precompiles::native::{ExitToEthereum, ExitToNear};
+use crate::prelude::precompiles::random::RandomSeed;
+use crate::prelude::precompiles::secp256k1::ECRecover;
+use crate::prelude::precompiles::{Berlin, Istanbul};
+use crate::prelude::{
+    AccountId, Address, BorshDeserialize, BorshSerialize, String, ToString, Vec, Wei,
+};
+use crate::promise_budget;
+use crate::protocol_fee;
+use crate::system_contracts;
+use crate::upgrade_history;
+use aurora_engine_sdk::env::Env;
+use aurora_engine_sdk::io::IO;
+
+/// Gas used by a plain transfer to an account without code.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Outcome of one check.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestCheck {
+    pub name: String,
+    /// Error code of the check, `None` if it passed.
+    pub error: Option<String>,
+}
+
+/// Result of `post_upgrade_selftest`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+
+    fn check<T, E: AsRef<[u8]>>(&mut self, name: &str, result: Result<T, E>) {
+        self.checks.push(SelfTestCheck {
+            name: name.to_string(),
+            error: result
+                .err()
+                .map(|e| String::from_utf8_lossy(e.as_ref()).into_owned()),
+        });
+    }
+}
+
+pub fn run<I: IO + Copy, E: Env>(io: I, env: &E, current_account_id: AccountId) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    match engine::get_state(&io) {
+        Ok(state) => report.check(
+            "engine_state",
+            if state.chain_id == [0; 32] {
+                Err("ERR_ZERO_CHAIN_ID")
+            } else {
+                Ok(())
+            },
+        ),
+        Err(e) => report.check::<(), _>("engine_state", Err(e)),
+    }
+    report.check(
+        "upgrade_completed",
+        if upgrade_history::has_pending(&io) {
+            Err("ERR_UPGRADE_NOT_MIGRATED")
+        } else {
+            Ok(())
+        },
+    );
+    report.check("upgrade_history", upgrade_history::get_history(&io));
+    report.check("protocol_fee", protocol_fee::get_config(&io));
+    report.check("promise_budget", promise_budget::get_budget(&io));
+    report.check("min_gas_price", min_gas_price::get_min_gas_price(&io));
+    report.check("inactivity_policy", inactivity::get_policy(&io));
+    report.check(
+        "fee_schedule",
+        fee_schedule::get_schedule(&io, &current_account_id),
+    );
+    report.check("system_contracts", system_contracts::get_installed(&io));
+    report.check(
+        "precompiles",
+        check_precompiles(io, current_account_id.clone(), env),
+    );
+    report.check(
+        "sample_execution",
+        check_sample_execution(io, env, current_account_id),
+    );
+
+    report
+}
+
+fn check_precompiles<I: IO + Copy, E: Env>(
+    io: I,
+    current_account_id: AccountId,
+    env: &E,
+) -> Result<(), &'static str> {
+    let precompiles = engine::precompiles(
+        era::at(env.block_height()),
+        io,
+        current_account_id,
+        env,
+        Vec::new(),
+    );
+    let expected = [
+        ECRecover::ADDRESS,
+        SHA256::ADDRESS,
+        RIPEMD160::ADDRESS,
+        Identity::ADDRESS,
+        ModExp::<Berlin>::ADDRESS,
+        Bn128Add::<Istanbul>::ADDRESS,
+        Bn128Mul::<Istanbul>::ADDRESS,
+        Bn128Pair::<Istanbul>::ADDRESS,
+        Blake2F::ADDRESS,
+        ExitToNear::ADDRESS,
+        ExitToEthereum::ADDRESS,
+        RandomSeed::ADDRESS,
+        NearAccountToAddress::ADDRESS,
+    ];
+    if expected
+        .iter()
+        .any(|address| !precompiles.all_precompiles.contains_key(address))
+    {
+        return Err("ERR_PRECOMPILE_MISSING");
+    }
+    // Code at a precompile address is never executed.
+    if precompiles
+        .all_precompiles
+        .keys()
+        .any(|address| !engine::get_code(&io, address).is_empty())
+    {
+        return Err("ERR_PRECOMPILE_HAS_CODE");
+    }
+    Ok(())
+}
+
+/// Transfers nothing from the zero address to itself, without storing anything.
+fn check_sample_execution<I: IO + Copy, E: Env>(
+    io: I,
+    env: &E,
+    current_account_id: AccountId,
+) -> Result<(), &'static str> {
+    let origin = Address::zero();
+    let engine = Engine::new(origin, current_account_id, io, env)
+        .map_err(|_| "ERR_SAMPLE_EXECUTION_STATE")?;
+    let result = engine
+        .view_with_gas_used(origin, origin, Wei::zero(), Vec::new(), TRANSFER_GAS)
+        .map_err(|_| "ERR_SAMPLE_EXECUTION_FAILED")?;
+    match result.status {
+        TransactionStatus::Succeed(_) if result.gas_used == TRANSFER_GAS => Ok(()),
+        TransactionStatus::Succeed(_) => Err("ERR_SAMPLE_EXECUTION_GAS"),
+        _ => Err("ERR_SAMPLE_EXECUTION_FAILED"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut report = SelfTestReport::default();
+        report.check::<(), &str>("a", Ok(()));
+        assert!(report.passed());
+        report.check::<(), &str>("b", Err("ERR_B"));
+        assert!(!report.passed());
+        assert_eq!(
+            report.checks[1],
+            SelfTestCheck {
+                name: "b".to_string(),
+                error: Some("ERR_B".to_string()),
+            }
+        );
+    }
+}
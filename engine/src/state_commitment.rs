@@ -0,0 +1,158 @@
+//! Rolling commitment to the EVM state changes, for light verification of replicas.
+//!
+//! Every time EVM execution changes the state, the engine hashes the changes (see
+//! `DiffEncoder`) and folds the hash into a running commitment:
+//! `commitment = keccak(previous_commitment || keccak(changes))`. The commitment is stored
+//! together with the height of the block of the last change, and returned by the
+//! `get_state_commitment` view. A replica replaying the same transactions (e.g. the
+//! standalone engine) computes the same commitment, so comparing it with the view at a given
+//! block tells whether the replica is in sync, without any state root machinery.
+//!
+//! Only the changes applied by the EVM executor are covered: the direct writes of the engine
+//! (e.g. gas prepayment, bridge deposits, configuration) are not.
+
+use crate::prelude::{
+    bytes_to_key, sdk, Address, BorshDeserialize, BorshSerialize, KeyPrefix, RawH256, Vec, H256,
+    U256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const STATE_COMMITMENT_KEY: &[u8; 16] = b"STATE_COMMITMENT";
+
+const MODIFY_TAG: u8 = 0;
+const DELETE_TAG: u8 = 1;
+
+/// Commitment returned by `get_state_commitment`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateCommitment {
+    /// Height of the block of the last change folded into the commitment.
+    pub block_height: u64,
+    pub commitment: RawH256,
+}
+
+/// Serialization of the changes applied by one EVM execution, in the order they are applied.
+#[derive(Default)]
+pub struct DiffEncoder {
+    bytes: Vec<u8>,
+}
+
+impl DiffEncoder {
+    pub fn modify(
+        &mut self,
+        address: &Address,
+        nonce: U256,
+        balance: U256,
+        code: Option<&[u8]>,
+        reset_storage: bool,
+    ) {
+        self.bytes.push(MODIFY_TAG);
+        self.bytes.extend_from_slice(address.as_bytes());
+        self.bytes.extend_from_slice(&u256_to_bytes(nonce));
+        self.bytes.extend_from_slice(&u256_to_bytes(balance));
+        match code {
+            Some(code) => {
+                self.bytes.push(1);
+                self.bytes.extend_from_slice(sdk::keccak(code).as_bytes());
+            }
+            None => self.bytes.push(0),
+        }
+        self.bytes.push(reset_storage as u8);
+    }
+
+    /// Storage slot written by the last modified account; a zero value removes the slot.
+    pub fn storage(&mut self, index: &H256, value: &H256) {
+        self.bytes.extend_from_slice(index.as_bytes());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    pub fn delete(&mut self, address: &Address) {
+        self.bytes.push(DELETE_TAG);
+        self.bytes.extend_from_slice(address.as_bytes());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn hash(&self) -> H256 {
+        sdk::keccak(&self.bytes)
+    }
+}
+
+fn u256_to_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn commitment_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::Config, STATE_COMMITMENT_KEY)
+}
+
+/// Commitment to all the changes so far; the default (zero) commitment before any change.
+pub fn get<I: IO>(io: &I) -> StateCommitment {
+    io.read_storage(&commitment_key())
+        .and_then(|value| StateCommitment::try_from_slice(&value.to_vec()).ok())
+        .unwrap_or_default()
+}
+
+/// Commitment following `previous` once the changes with the given hash are applied.
+pub fn fold(previous: &RawH256, diff_hash: &H256) -> RawH256 {
+    sdk::keccak(&[previous.as_slice(), diff_hash.as_bytes()].concat()).0
+}
+
+/// Folds the changes of an EVM execution into the stored commitment.
+pub fn update<I: IO>(io: &mut I, diff: &DiffEncoder, block_height: u64) {
+    if diff.is_empty() {
+        return;
+    }
+    let previous = get(io);
+    let next = StateCommitment {
+        block_height,
+        commitment: fold(&previous.commitment, &diff.hash()),
+    };
+    io.write_borsh(&commitment_key(), &next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(balance: u64, slot: Option<(H256, H256)>) -> DiffEncoder {
+        let mut diff = DiffEncoder::default();
+        diff.modify(
+            &Address([1u8; 20]),
+            U256::one(),
+            U256::from(balance),
+            None,
+            false,
+        );
+        if let Some((index, value)) = slot {
+            diff.storage(&index, &value);
+        }
+        diff
+    }
+
+    #[test]
+    fn test_commitment_depends_on_changes_and_order() {
+        let a = diff(10, None).hash();
+        let b = diff(10, Some((H256::zero(), H256::repeat_byte(1)))).hash();
+        assert_ne!(a, b);
+        assert_ne!(a, diff(11, None).hash());
+
+        let zero = [0u8; 32];
+        let ab = fold(&fold(&zero, &a), &b);
+        let ba = fold(&fold(&zero, &b), &a);
+        assert_ne!(ab, ba);
+        assert_eq!(ab, fold(&fold(&zero, &a), &b));
+    }
+
+    #[test]
+    fn test_deleted_account_differs_from_modified() {
+        let mut deleted = DiffEncoder::default();
+        deleted.delete(&Address([1u8; 20]));
+        assert!(!deleted.is_empty());
+        assert_ne!(deleted.hash(), diff(0, None).hash());
+        assert!(DiffEncoder::default().is_empty());
+    }
+}
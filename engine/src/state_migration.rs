@@ -0,0 +1,106 @@
+//! Migrations of the state layout, applied together with an upgrade.
+//!
+//! `deploy_upgrade` deploys the staged code and calls the `state_migration` of the new code
+//! in the same batch of actions, so a failing migration reverts the deployment with it: the
+//! deployed code never runs on a state of a layout it does not expect.
+//!
+//! Each change of layout is a `StateMigration` bringing the state to a new version. The
+//! migrations of the code are listed by `migrations` in increasing order of version, and
+//! `state_migration` applies, in order, those above the version stored in the state. A
+//! migration therefore runs once, on the layout of the version before it.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::{bytes_to_key, sdk, Box, KeyPrefix, Vec};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const STATE_VERSION_KEY: &[u8; 13] = b"STATE_VERSION";
+
+pub trait StateMigration<I: IO> {
+    /// Version of the state after the migration.
+    fn version(&self) -> u32;
+
+    /// Changes the state from the layout of the previous version to the layout of this one.
+    fn migrate(&self, io: &mut I, block_height: u64) -> Result<(), StateMigrationError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateMigrationError {
+    InvalidStoredVersion,
+    /// The migrations are not in strictly increasing order of version.
+    UnorderedMigrations,
+    /// The state does not have the layout the migration expects.
+    UnexpectedLayout,
+}
+
+impl AsRef<[u8]> for StateMigrationError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidStoredVersion => b"ERR_INVALID_STATE_VERSION",
+            Self::UnorderedMigrations => b"ERR_UNORDERED_STATE_MIGRATIONS",
+            Self::UnexpectedLayout => b"ERR_UNEXPECTED_STATE_LAYOUT",
+        }
+    }
+}
+
+/// Migrations of the current code, in increasing order of version.
+pub fn migrations<I: IO>() -> Vec<Box<dyn StateMigration<I>>> {
+    Vec::new()
+}
+
+/// Version of the state, 0 if no migration was ever applied.
+pub fn get_version<I: IO>(io: &I) -> Result<u32, StateMigrationError> {
+    match io.read_storage(&version_key()) {
+        None => Ok(0),
+        Some(value) if value.len() == 4 => {
+            let mut buf = [0u8; 4];
+            value.copy_to_slice(&mut buf);
+            Ok(u32::from_le_bytes(buf))
+        }
+        Some(_) => Err(StateMigrationError::InvalidStoredVersion),
+    }
+}
+
+/// Applies the migrations of the current code, see `apply`.
+pub fn run<I: IO>(io: &mut I, block_height: u64) -> Result<u32, StateMigrationError> {
+    apply(io, &migrations(), block_height)
+}
+
+/// Applies the migrations above the stored version, in order, and returns the new version.
+pub fn apply<I: IO>(
+    io: &mut I,
+    migrations: &[Box<dyn StateMigration<I>>],
+    block_height: u64,
+) -> Result<u32, StateMigrationError> {
+    if migrations
+        .windows(2)
+        .any(|pair| pair[0].version() >= pair[1].version())
+    {
+        return Err(StateMigrationError::UnorderedMigrations);
+    }
+
+    let previous = get_version(io)?;
+    let mut version = previous;
+    for migration in migrations {
+        if migration.version() <= version {
+            continue;
+        }
+        migration.migrate(io, block_height)?;
+        version = migration.version();
+        sdk::log!(crate::prelude::format!("Migrated state to version {}", version).as_str());
+    }
+
+    if version != previous {
+        io.write_storage(&version_key(), &version.to_le_bytes());
+        events::emit_config_changed(
+            "state_version",
+            JsonValue::U64(previous.into()),
+            JsonValue::U64(version.into()),
+        );
+    }
+    Ok(version)
+}
+
+fn version_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::Config, STATE_VERSION_KEY)
+}
@@ -0,0 +1,111 @@
+//! Sharded storage keys for hot contracts.
+//!
+//! All the storage slots of a contract normally share the `[version, Storage, address]`
+//! prefix, so every access to a very busy contract (e.g. a major DEX pair) walks the same
+//! path of the NEAR trie. The owner can switch such contracts to sharded keys, which put a
+//! shard byte derived from the slot before the address (see
+//! `aurora_engine_types::storage::storage_to_key`).
+//!
+//! A contract uses sharded keys when its storage generation has the
+//! `SHARDED_GENERATION_FLAG` bit set, so storage accesses cost no extra read. Switching an
+//! existing contract starts a migration: until the owner finishes it, slots missing from the
+//! sharded layout are read from the previous one, and `migrate` moves batches of slots
+//! (listed off-chain, e.g. by the standalone storage) to the sharded layout.
+
+use crate::engine;
+use crate::prelude::{
+    bytes_to_key, is_sharded_generation, sdk, storage_to_key, Address, KeyPrefix, RawH256, H256,
+    SHARDED_GENERATION_FLAG,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const SHARDING_MIGRATION_KEY: &[u8; 18] = b"SHARDING_MIGRATION";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageShardingError {
+    AlreadySharded,
+    NoMigration,
+}
+
+impl AsRef<[u8]> for StorageShardingError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::AlreadySharded => b"ERR_STORAGE_ALREADY_SHARDED",
+            Self::NoMigration => b"ERR_NO_STORAGE_SHARDING_MIGRATION",
+        }
+    }
+}
+
+fn migration_key(address: &Address) -> crate::prelude::Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[SHARDING_MIGRATION_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+/// Switches the contract to sharded storage keys and starts the migration of its slots.
+pub fn enable<I: IO>(io: &mut I, address: &Address) -> Result<(), StorageShardingError> {
+    let generation = engine::get_generation(io, address);
+    if is_sharded_generation(generation) {
+        return Err(StorageShardingError::AlreadySharded);
+    }
+    engine::set_generation(io, address, generation | SHARDED_GENERATION_FLAG);
+    io.write_storage(&migration_key(address), &generation.to_be_bytes());
+    sdk::log!(crate::prelude::format!(
+        "Storage of {} switched to sharded keys",
+        hex::encode(address)
+    )
+    .as_str());
+    Ok(())
+}
+
+/// Generation of the slots which still have to be migrated, if the contract is migrating.
+pub fn legacy_generation<I: IO>(io: &I, address: &Address) -> Option<u32> {
+    io.read_storage(&migration_key(address)).and_then(|value| {
+        if value.len() == 4 {
+            let mut bytes = [0u8; 4];
+            value.copy_to_slice(&mut bytes);
+            Some(u32::from_be_bytes(bytes))
+        } else {
+            None
+        }
+    })
+}
+
+/// Moves the given slots from the previous layout to the sharded one. Slots which are not
+/// stored in the previous layout are skipped. Returns the number of slots moved.
+pub fn migrate<I: IO>(
+    io: &mut I,
+    address: &Address,
+    keys: &[RawH256],
+) -> Result<u32, StorageShardingError> {
+    let legacy_generation =
+        legacy_generation(io, address).ok_or(StorageShardingError::NoMigration)?;
+    let generation = engine::get_generation(io, address);
+    let mut moved = 0;
+    for key in keys {
+        let key = H256(*key);
+        let legacy_key = storage_to_key(address, &key, legacy_generation);
+        if let Some(value) = io.remove_storage(legacy_key.as_ref()) {
+            let sharded_key = storage_to_key(address, &key, generation);
+            // A slot written since the switch is already up to date.
+            if !io.storage_has_key(sharded_key.as_ref()) {
+                io.write_storage_direct(sharded_key.as_ref(), value);
+            }
+            moved += 1;
+        }
+    }
+    Ok(moved)
+}
+
+/// Ends the migration: slots left in the previous layout are no longer visible.
+pub fn finish<I: IO>(io: &mut I, address: &Address) -> Result<(), StorageShardingError> {
+    io.remove_storage(&migration_key(address))
+        .ok_or(StorageShardingError::NoMigration)?;
+    Ok(())
+}
+
+/// Drops the migration of the contract, if any. Used when all its storage is cleared.
+pub(crate) fn clear<I: IO>(io: &mut I, address: &Address) {
+    io.remove_storage(&migration_key(address));
+}
@@ -0,0 +1,120 @@
+//! Sink for ETH sent to precompile addresses.
+//!
+//! Nothing can spend the balance of a precompile address, so ETH sent to one (by a call with
+//! value, or as the beneficiary of a `SELFDESTRUCT`) would leave circulation without the
+//! connector knowing. Instead, the engine moves such balances to a sink when applying the
+//! state of a transaction. The sum of the EVM balances and of the sink always matches the
+//! ETH bridged to Aurora, and the owner can move the content of the sink back to an account.
+//!
+//! The exit precompiles are not sinks: their balances track the ETH which left Aurora and
+//! are used to refund failed exits.
+
+use crate::engine;
+use crate::prelude::precompiles::account_to_address::NearAccountToAddress;
+use crate::prelude::precompiles::near_state::NearStateRead;
+use crate::prelude::precompiles::promise_result::PromiseResults;
+use crate::prelude::precompiles::random::RandomSeed;
+use crate::prelude::{bytes_to_key, sdk, Address, KeyPrefix, Wei};
+use aurora_engine_sdk::io::IO;
+
+const STRANDED_ETH_KEY: &[u8; 12] = b"STRANDED_ETH";
+
+/// Highest address of the standard Ethereum precompiles (`blake2f`).
+const LAST_STANDARD_PRECOMPILE: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrandedEthError {
+    NothingToReconcile,
+    BalanceOverflow,
+}
+
+impl AsRef<[u8]> for StrandedEthError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::NothingToReconcile => b"ERR_NO_STRANDED_ETH",
+            Self::BalanceOverflow => b"ERR_BALANCE_OVERFLOW",
+        }
+    }
+}
+
+/// True if ETH sent to the address cannot be spent anymore and must go to the sink.
+pub fn is_sink_address(address: &Address) -> bool {
+    let bytes = address.as_bytes();
+    let is_standard_precompile =
+        bytes[..19].iter().all(|b| *b == 0) && (1..=LAST_STANDARD_PRECOMPILE).contains(&bytes[19]);
+    is_standard_precompile
+        || *address == RandomSeed::ADDRESS
+        || *address == NearAccountToAddress::ADDRESS
+        || *address == NearStateRead::ADDRESS
+        || *address == PromiseResults::ADDRESS
+}
+
+/// Total amount of ETH held by the sink.
+pub fn get_stranded_eth<I: IO>(io: &I) -> Wei {
+    io.read_u256(&bytes_to_key(KeyPrefix::Config, STRANDED_ETH_KEY))
+        .map(Wei::new)
+        .unwrap_or_else(|_| Wei::zero())
+}
+
+fn set_stranded_eth<I: IO>(io: &mut I, amount: Wei) {
+    let key = bytes_to_key(KeyPrefix::Config, STRANDED_ETH_KEY);
+    if amount.is_zero() {
+        io.remove_storage(&key);
+    } else {
+        io.write_storage(&key, &amount.to_bytes());
+    }
+}
+
+/// Adds `amount`, taken from the balance of `address`, to the sink.
+#[cfg_attr(not(feature = "log"), allow(unused_variables))]
+pub fn strand<I: IO>(io: &mut I, address: &Address, amount: Wei) {
+    // The sink never holds more than the total supply of ETH, so this cannot saturate.
+    let total = get_stranded_eth(io).raw().saturating_add(amount.raw());
+    set_stranded_eth(io, Wei::new(total));
+    sdk::log!(crate::prelude::format!(
+        "Moved {} wei sent to {} to the stranded ETH sink",
+        amount.to_decimal_string(),
+        hex::encode(address.as_bytes())
+    )
+    .as_str());
+}
+
+/// Empties the sink into the balance of `recipient`, returning the amount moved.
+pub fn reconcile<I: IO>(io: &mut I, recipient: &Address) -> Result<Wei, StrandedEthError> {
+    let amount = get_stranded_eth(io);
+    if amount.is_zero() {
+        return Err(StrandedEthError::NothingToReconcile);
+    }
+    let balance = engine::get_balance(io, recipient)
+        .checked_add(amount)
+        .ok_or(StrandedEthError::BalanceOverflow)?;
+    engine::set_balance(io, recipient, &balance);
+    set_stranded_eth(io, Wei::zero());
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::precompiles::native::{ExitToEthereum, ExitToNear};
+
+    #[test]
+    fn test_sink_addresses() {
+        for i in 1..=9u8 {
+            let mut address = [0u8; 20];
+            address[19] = i;
+            assert!(is_sink_address(&Address(address)));
+        }
+        assert!(is_sink_address(&RandomSeed::ADDRESS));
+        assert!(is_sink_address(&NearAccountToAddress::ADDRESS));
+        assert!(is_sink_address(&NearStateRead::ADDRESS));
+        assert!(is_sink_address(&PromiseResults::ADDRESS));
+        assert!(!is_sink_address(&Address([0u8; 20])));
+        assert!(!is_sink_address(&ExitToNear::ADDRESS));
+        assert!(!is_sink_address(&ExitToEthereum::ADDRESS));
+        let mut address = [0u8; 20];
+        address[18] = 1;
+        address[19] = 1;
+        assert!(!is_sink_address(&Address(address)));
+    }
+}
@@ -0,0 +1,278 @@
+//! System contracts installed by the engine itself.
+//!
+//! The genesis manifest (`etc/system-contracts/genesis.json`, embedded in the contract) lists
+//! the EVM contracts every Aurora network has at the same addresses, such as the CREATE2
+//! deployer. They are installed by `new` and by the `state_migration` of an upgrade, when
+//! the embedded manifest has a higher version than the one installed, so that a test
+//! network deployed with a given engine has the same system contracts as mainnet.
+//!
+//! The installed manifest is kept in state, with the hash of each contract instead of its
+//! code. A contract is only written at an address which is empty, already has the same
+//! code, or had a system contract of an older manifest; any other address is left alone and
+//! the contract is not recorded as installed. Contracts dropped from the manifest are not
+//! removed, since they may hold funds.
+
+use crate::engine;
+use crate::json::{parse_json, JsonValue};
+use crate::prelude::{
+    bytes_to_key, codec, sdk, vec, Address, BTreeMap, BorshDeserialize, BorshSerialize, EthAddress,
+    KeyPrefix, RawH256, String, ToString, TryFrom, Vec, U256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const SYSTEM_CONTRACTS_KEY: &[u8; 16] = b"SYSTEM_CONTRACTS";
+
+/// Name of the event emitted when a version of the manifest is installed.
+pub const SYSTEM_CONTRACTS_INSTALLED: &str = "system_contracts_installed";
+
+/// Manifest embedded in the contract.
+pub const GENESIS_MANIFEST: &[u8] = include_bytes!("../../etc/system-contracts/genesis.json");
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SystemContract {
+    pub name: String,
+    pub address: EthAddress,
+    /// Runtime (deployed) code.
+    pub code: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GenesisManifest {
+    pub version: u32,
+    pub contracts: Vec<SystemContract>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledSystemContract {
+    pub name: String,
+    pub address: EthAddress,
+    /// Keccak hash of the code.
+    pub code_hash: RawH256,
+}
+
+/// The manifest as installed, returned by `get_system_contracts`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledManifest {
+    pub version: u32,
+    pub contracts: Vec<InstalledSystemContract>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemContractsError {
+    InvalidManifest,
+    DuplicateAddress,
+    InvalidStoredManifest,
+}
+
+impl AsRef<[u8]> for SystemContractsError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidManifest => b"ERR_INVALID_SYSTEM_CONTRACTS_MANIFEST",
+            Self::DuplicateAddress => b"ERR_DUPLICATE_SYSTEM_CONTRACT_ADDRESS",
+            Self::InvalidStoredManifest => b"ERR_INVALID_STORED_SYSTEM_CONTRACTS",
+        }
+    }
+}
+
+impl GenesisManifest {
+    /// Parses a manifest of the form
+    /// `{"version": 1, "contracts": [{"name": "...", "address": "0x...", "code": "0x..."}]}`.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, SystemContractsError> {
+        let json = parse_json(bytes).ok_or(SystemContractsError::InvalidManifest)?;
+        let version = json.u64("version").map_err(invalid_manifest)?;
+        let contracts = match &json {
+            JsonValue::Object(kvs) => match kvs.get("contracts") {
+                Some(JsonValue::Array(contracts)) => contracts,
+                _ => return Err(SystemContractsError::InvalidManifest),
+            },
+            _ => return Err(SystemContractsError::InvalidManifest),
+        };
+        let contracts = contracts
+            .iter()
+            .map(|contract| {
+                let address = contract.string("address").map_err(invalid_manifest)?;
+                let code = contract.string("code").map_err(invalid_manifest)?;
+                Ok(SystemContract {
+                    name: contract.string("name").map_err(invalid_manifest)?,
+                    address: EthAddress::decode(strip_hex_prefix(&address))
+                        .map_err(invalid_manifest)?,
+                    code: codec::hex_decode(strip_hex_prefix(&code), codec::MAX_INPUT_SIZE)
+                        .map_err(invalid_manifest)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            version: u32::try_from(version).map_err(invalid_manifest)?,
+            contracts,
+        })
+    }
+}
+
+fn strip_hex_prefix(hex: &str) -> &str {
+    hex.strip_prefix("0x").unwrap_or(hex)
+}
+
+fn invalid_manifest<E>(_: E) -> SystemContractsError {
+    SystemContractsError::InvalidManifest
+}
+
+pub fn get_installed<I: IO>(io: &I) -> Result<Option<InstalledManifest>, SystemContractsError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, SYSTEM_CONTRACTS_KEY)) {
+        None => Ok(None),
+        Some(bytes) => InstalledManifest::try_from_slice(&bytes.to_vec())
+            .map(Some)
+            .map_err(|_| SystemContractsError::InvalidStoredManifest),
+    }
+}
+
+/// Installs the manifest unless the same or a later version is already installed.
+/// Returns the installed manifest if anything changed.
+pub fn install<I: IO>(
+    io: &mut I,
+    manifest: &GenesisManifest,
+) -> Result<Option<InstalledManifest>, SystemContractsError> {
+    let previous = get_installed(io)?;
+    if let Some(previous) = &previous {
+        if previous.version >= manifest.version {
+            return Ok(None);
+        }
+    }
+    for (index, contract) in manifest.contracts.iter().enumerate() {
+        if manifest.contracts[..index]
+            .iter()
+            .any(|other| other.address == contract.address)
+        {
+            return Err(SystemContractsError::DuplicateAddress);
+        }
+    }
+
+    let mut installed = Vec::with_capacity(manifest.contracts.len());
+    for contract in &manifest.contracts {
+        let address = Address::from(contract.address);
+        let was_system_contract = previous.as_ref().map_or(false, |previous| {
+            previous
+                .contracts
+                .iter()
+                .any(|other| other.address == contract.address)
+        });
+        let existing = engine::get_code(io, &address);
+        if !existing.is_empty() && existing != contract.code && !was_system_contract {
+            sdk::log!(crate::prelude::format!(
+                "System contract {} not installed: {} has other code",
+                contract.name,
+                contract.address
+            )
+            .as_str());
+            continue;
+        }
+        engine::set_code(io, &address, &contract.code);
+        // Like any contract, a system contract starts with nonce 1 (EIP-161).
+        if engine::get_nonce(io, &address).is_zero() {
+            engine::set_nonce(io, &address, &U256::one());
+        }
+        installed.push(InstalledSystemContract {
+            name: contract.name.clone(),
+            address: contract.address,
+            code_hash: sdk::keccak(&contract.code).0,
+        });
+    }
+
+    let installed = InstalledManifest {
+        version: manifest.version,
+        contracts: installed,
+    };
+    io.write_borsh(
+        &bytes_to_key(KeyPrefix::Config, SYSTEM_CONTRACTS_KEY),
+        &installed,
+    );
+    crate::events::emit(SYSTEM_CONTRACTS_INSTALLED, installed_data(&installed));
+    Ok(Some(installed))
+}
+
+/// Installs the manifest embedded in the contract, see `install`.
+pub fn install_genesis<I: IO>(io: &mut I) -> Result<(), SystemContractsError> {
+    let manifest = GenesisManifest::from_json(GENESIS_MANIFEST)?;
+    install(io, &manifest).map(|_| ())
+}
+
+fn installed_data(installed: &InstalledManifest) -> JsonValue {
+    let contracts = installed
+        .contracts
+        .iter()
+        .map(|contract| {
+            let mut kvs = BTreeMap::new();
+            kvs.insert("name".to_string(), JsonValue::String(contract.name.clone()));
+            kvs.insert(
+                "address".to_string(),
+                JsonValue::String(contract.address.encode()),
+            );
+            kvs.insert(
+                "code_hash".to_string(),
+                JsonValue::String(hex::encode(contract.code_hash)),
+            );
+            JsonValue::Object(kvs)
+        })
+        .collect();
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "version".to_string(),
+        JsonValue::U64(installed.version as u64),
+    );
+    kvs.insert("contracts".to_string(), JsonValue::Array(contracts));
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_manifest_is_valid() {
+        let manifest = GenesisManifest::from_json(GENESIS_MANIFEST).unwrap();
+        assert!(manifest.version > 0);
+        for contract in &manifest.contracts {
+            assert!(!contract.code.is_empty(), "{}", contract.name);
+        }
+    }
+
+    #[test]
+    fn test_manifest_from_json() {
+        let json = br#"{"version": 2, "contracts": [
+            {"name": "a", "address": "0x0000000000000000000000000000000000000001", "code": "0x6001"}
+        ]}"#;
+        assert_eq!(
+            GenesisManifest::from_json(json),
+            Ok(GenesisManifest {
+                version: 2,
+                contracts: vec![SystemContract {
+                    name: "a".to_string(),
+                    address: EthAddress::new([
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1
+                    ]),
+                    code: vec![0x60, 0x01],
+                }],
+            })
+        );
+        assert_eq!(
+            GenesisManifest::from_json(br#"{"version": 2, "contracts": [{"name": "a"}]}"#),
+            Err(SystemContractsError::InvalidManifest)
+        );
+    }
+
+    #[test]
+    fn test_installed_event() {
+        let installed = InstalledManifest {
+            version: 1,
+            contracts: vec![InstalledSystemContract {
+                name: "create2_deployer".to_string(),
+                address: EthAddress::new([1; 20]),
+                code_hash: [2; 32],
+            }],
+        };
+        let data = installed_data(&installed);
+        let value: serde_json::Value = serde_json::from_str(&data.to_string()).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["contracts"][0]["name"], "create2_deployer");
+        assert_eq!(value["contracts"][0]["code_hash"], hex::encode([2; 32]));
+    }
+}
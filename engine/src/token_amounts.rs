@@ -0,0 +1,54 @@
+//! Conversion of amounts between a NEP-141 token and its ERC-20.
+//!
+//! The ERC-20 of a bridged token counts in the units of the NEP-141: `ft_on_transfer` mints
+//! the NEP-141 amount as is, and the exits to NEAR transfer the burned amount as is. There
+//! is no rescaling, hence no rounding, but an ERC-20 amount above `u128::MAX` cannot be
+//! represented on the NEP-141 side. The views of this module apply these rules, so that
+//! front ends quoting bridge amounts do not have to. The fees of the token's schedule (see
+//! `fee_schedule`) are not deducted.
+
+use crate::engine;
+use crate::prelude::{bytes_to_key, AccountId, Address, Balance, KeyPrefix, U256};
+use aurora_engine_sdk::io::IO;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAmountError {
+    /// The token is not bridged.
+    TokenNotFound,
+    /// The ERC-20 amount does not fit in a NEP-141 amount.
+    AmountOverflow,
+}
+
+impl AsRef<[u8]> for TokenAmountError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::TokenNotFound => b"ERR_TOKEN_NOT_FOUND",
+            Self::AmountOverflow => b"ERR_AMOUNT_OVERFLOW",
+        }
+    }
+}
+
+/// ERC-20 amount minted for `amount` of the NEP-141 token.
+pub fn nep141_to_erc20<I: IO>(
+    io: &I,
+    nep141: &AccountId,
+    amount: Balance,
+) -> Result<U256, TokenAmountError> {
+    engine::get_erc20_from_nep141(io, nep141).map_err(|_| TokenAmountError::TokenNotFound)?;
+    Ok(U256::from(amount))
+}
+
+/// NEP-141 amount transferred when `amount` of the ERC-20 token exits to NEAR.
+pub fn erc20_to_nep141<I: IO>(
+    io: &I,
+    erc20: &Address,
+    amount: U256,
+) -> Result<Balance, TokenAmountError> {
+    if !io.storage_has_key(&bytes_to_key(KeyPrefix::Erc20Nep141Map, erc20.as_bytes())) {
+        return Err(TokenAmountError::TokenNotFound);
+    }
+    if amount > U256::from(Balance::MAX) {
+        return Err(TokenAmountError::AmountOverflow);
+    }
+    Ok(amount.as_u128())
+}
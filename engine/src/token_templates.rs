@@ -0,0 +1,213 @@
+//! Registry of the ERC-20 templates bridged tokens are deployed from.
+//!
+//! Besides the built-in templates compiled into the engine, the owner can register the
+//! creation code of audited ERC-20 variants under a name and a version. A registered
+//! template is immutable: a new variant of the code is registered under a new version, and
+//! a deployment pins the hash of the code it expects (see `Erc20Template::Registered`).
+//!
+//! The template each token was deployed from is recorded, so that integrators can check
+//! which code backs a bridged asset with `get_erc20_template`.
+
+use crate::engine::ERC20_TEMPLATE_VERSION;
+use crate::events;
+use crate::json::JsonValue;
+use crate::parameters::{Erc20Template, Erc20TemplateInfo, RegisterErc20TemplateArgs};
+use crate::prelude::{
+    bytes_to_key, sdk, Address, BTreeMap, BorshDeserialize, KeyPrefix, ToString, Vec,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const TEMPLATE_CODE_KEY: &[u8; 19] = b"ERC20_TEMPLATE_CODE";
+const TEMPLATES_KEY: &[u8; 15] = b"ERC20_TEMPLATES";
+const DEPLOYED_TEMPLATE_KEY: &[u8; 17] = b"ERC20_TEMPLATE_OF";
+
+/// Name of the `Standard` built-in template.
+pub const STANDARD: &str = "standard";
+/// Name of the `Stablecoin` built-in template.
+pub const STABLECOIN: &str = "stablecoin";
+/// Longest name of a registered template, in bytes.
+pub const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenTemplateError {
+    InvalidName,
+    EmptyCode,
+    AlreadyRegistered,
+    NotFound,
+    CodeHashMismatch,
+    InvalidStoredTemplates,
+}
+
+impl AsRef<[u8]> for TokenTemplateError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidName => b"ERR_INVALID_TEMPLATE_NAME",
+            Self::EmptyCode => b"ERR_EMPTY_TEMPLATE_CODE",
+            Self::AlreadyRegistered => b"ERR_TEMPLATE_ALREADY_REGISTERED",
+            Self::NotFound => b"ERR_TEMPLATE_NOT_FOUND",
+            Self::CodeHashMismatch => b"ERR_TEMPLATE_CODE_HASH_MISMATCH",
+            Self::InvalidStoredTemplates => b"ERR_INVALID_STORED_TEMPLATES",
+        }
+    }
+}
+
+/// Registers a new template, which must not reuse the name of a built-in template nor the
+/// name and version of a registered one.
+pub fn register<I: IO>(
+    io: &mut I,
+    args: RegisterErc20TemplateArgs,
+) -> Result<Erc20TemplateInfo, TokenTemplateError> {
+    if args.name.is_empty()
+        || args.name.len() > MAX_NAME_LEN
+        || args.name == STANDARD
+        || args.name == STABLECOIN
+    {
+        return Err(TokenTemplateError::InvalidName);
+    }
+    if args.code.is_empty() {
+        return Err(TokenTemplateError::EmptyCode);
+    }
+    let code_key = template_code_key(&args.name, args.version);
+    if io.storage_has_key(&code_key) {
+        return Err(TokenTemplateError::AlreadyRegistered);
+    }
+
+    let info = Erc20TemplateInfo {
+        name: args.name,
+        version: args.version,
+        code_hash: sdk::keccak(&args.code).0,
+    };
+    let mut registered = get_registered(io)?;
+    registered.push(info.clone());
+    io.write_storage(&code_key, &args.code);
+    io.write_borsh(&bytes_to_key(KeyPrefix::Config, TEMPLATES_KEY), &registered);
+    events::emit_config_changed("erc20_template", JsonValue::Null, template_data(&info));
+    Ok(info)
+}
+
+/// The built-in templates followed by the registered ones, in registration order.
+pub fn get_templates<I: IO>(io: &I) -> Result<Vec<Erc20TemplateInfo>, TokenTemplateError> {
+    let mut templates = Vec::new();
+    for template in &[Erc20Template::Standard, Erc20Template::Stablecoin] {
+        templates.push(resolve(io, template)?.0);
+    }
+    templates.extend(get_registered(io)?);
+    Ok(templates)
+}
+
+/// Description and creation code of the template.
+pub fn resolve<I: IO>(
+    io: &I,
+    template: &Erc20Template,
+) -> Result<(Erc20TemplateInfo, Vec<u8>), TokenTemplateError> {
+    let (name, version, code) = match template {
+        Erc20Template::Standard => (STANDARD, ERC20_TEMPLATE_VERSION, builtin_code(template)),
+        Erc20Template::Stablecoin => (STABLECOIN, ERC20_TEMPLATE_VERSION, builtin_code(template)),
+        Erc20Template::Registered {
+            name,
+            version,
+            code_hash,
+        } => {
+            let code = io
+                .read_storage(&template_code_key(name, *version))
+                .ok_or(TokenTemplateError::NotFound)?
+                .to_vec();
+            if sdk::keccak(&code).0 != *code_hash {
+                return Err(TokenTemplateError::CodeHashMismatch);
+            }
+            (name.as_str(), *version, code)
+        }
+    };
+    let info = Erc20TemplateInfo {
+        name: name.to_string(),
+        version,
+        code_hash: sdk::keccak(&code).0,
+    };
+    Ok((info, code))
+}
+
+/// Records the template the ERC-20 token at `address` was deployed from.
+pub fn record_deployment<I: IO>(io: &mut I, address: &Address, info: &Erc20TemplateInfo) {
+    io.write_borsh(&deployed_template_key(address), info);
+}
+
+/// Template the ERC-20 token at `address` was deployed from, `None` for a token deployed
+/// before the templates were recorded.
+pub fn get_deployed_template<I: IO>(io: &I, address: &Address) -> Option<Erc20TemplateInfo> {
+    io.read_storage(&deployed_template_key(address))
+        .and_then(|bytes| Erc20TemplateInfo::try_from_slice(&bytes.to_vec()).ok())
+}
+
+fn get_registered<I: IO>(io: &I) -> Result<Vec<Erc20TemplateInfo>, TokenTemplateError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, TEMPLATES_KEY)) {
+        None => Ok(Vec::new()),
+        Some(bytes) => Vec::<Erc20TemplateInfo>::try_from_slice(&bytes.to_vec())
+            .map_err(|_| TokenTemplateError::InvalidStoredTemplates),
+    }
+}
+
+/// Code of a built-in template, to which the constructor arguments are appended.
+fn builtin_code(template: &Erc20Template) -> Vec<u8> {
+    #[cfg(feature = "error_refund")]
+    let code: &[u8] = match template {
+        Erc20Template::Stablecoin => {
+            include_bytes!("../../etc/eth-contracts/res/EvmErc20StablecoinV2.bin")
+        }
+        _ => include_bytes!("../../etc/eth-contracts/res/EvmErc20V2.bin"),
+    };
+    #[cfg(not(feature = "error_refund"))]
+    let code: &[u8] = match template {
+        Erc20Template::Stablecoin => {
+            include_bytes!("../../etc/eth-contracts/res/EvmErc20Stablecoin.bin")
+        }
+        _ => include_bytes!("../../etc/eth-contracts/res/EvmErc20.bin"),
+    };
+    code.to_vec()
+}
+
+fn template_code_key(name: &str, version: u8) -> Vec<u8> {
+    // The version comes first, having a fixed length.
+    let suffix = [TEMPLATE_CODE_KEY.as_slice(), &[version], name.as_bytes()].concat();
+    bytes_to_key(KeyPrefix::Config, &suffix)
+}
+
+fn deployed_template_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[DEPLOYED_TEMPLATE_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+fn template_data(info: &Erc20TemplateInfo) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert("name".to_string(), JsonValue::String(info.name.clone()));
+    kvs.insert("version".to_string(), JsonValue::U64(info.version.into()));
+    kvs.insert(
+        "code_hash".to_string(),
+        JsonValue::String(hex::encode(info.code_hash)),
+    );
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_keys_are_distinct() {
+        // Names which are prefixes of one another, with versions equal to their next byte.
+        assert_ne!(template_code_key("a", b'b'), template_code_key("ab", b'b'));
+        assert_ne!(template_code_key("ab", 1), template_code_key("ab", 2));
+
+        let keys = [
+            template_code_key("a", 1),
+            bytes_to_key(KeyPrefix::Config, TEMPLATES_KEY),
+            deployed_template_key(&Address::zero()),
+        ];
+        for (i, key) in keys.iter().enumerate() {
+            for other in &keys[i + 1..] {
+                assert!(!key.starts_with(other) && !other.starts_with(key));
+            }
+        }
+    }
+}
@@ -23,6 +23,18 @@ impl Decodable for AccessTuple {
     }
 }
 
+pub(crate) fn rlp_append_access_list(s: &mut RlpStream, access_list: &[AccessTuple]) {
+    s.begin_list(access_list.len());
+    for tuple in access_list.iter() {
+        s.begin_list(2);
+        s.append(&tuple.address);
+        s.begin_list(tuple.storage_keys.len());
+        for key in tuple.storage_keys.iter() {
+            s.append(key);
+        }
+    }
+}
+
 /// See https://eips.ethereum.org/EIPS/eip-2930
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Transaction2930 {
@@ -47,8 +59,25 @@ impl Transaction2930 {
         self.rlp_append(s, 11);
     }
 
+    /// Same bytes as `rlp_append_unsigned` preceded by the type byte, without copying
+    /// `data` more than once.
+    fn unsigned_message(&self) -> Vec<u8> {
+        let mut head = RlpStream::new();
+        self.rlp_append_head(&mut head);
+        let mut tail = RlpStream::new();
+        rlp_append_access_list(&mut tail, &self.access_list);
+        super::unsigned_message(Some(TYPE_BYTE), head.as_raw(), &self.data, tail.as_raw())
+    }
+
     fn rlp_append(&self, s: &mut RlpStream, list_len: usize) {
         s.begin_list(list_len);
+        self.rlp_append_head(s);
+        s.append(&self.data);
+        rlp_append_access_list(s, &self.access_list);
+    }
+
+    /// Fields before `data`.
+    fn rlp_append_head(&self, s: &mut RlpStream) {
         s.append(&self.chain_id);
         s.append(&self.nonce);
         s.append(&self.gas_price);
@@ -58,16 +87,6 @@ impl Transaction2930 {
             Some(address) => s.append(address),
         };
         s.append(&self.value.raw());
-        s.append(&self.data);
-        s.begin_list(self.access_list.len());
-        for tuple in self.access_list.iter() {
-            s.begin_list(2);
-            s.append(&tuple.address);
-            s.begin_list(tuple.storage_keys.len());
-            for key in tuple.storage_keys.iter() {
-                s.append(key);
-            }
-        }
     }
 }
 
@@ -82,10 +101,7 @@ pub struct SignedTransaction2930 {
 
 impl SignedTransaction2930 {
     pub fn sender(&self) -> Option<Address> {
-        let mut rlp_stream = RlpStream::new();
-        rlp_stream.append(&TYPE_BYTE);
-        self.transaction.rlp_append_unsigned(&mut rlp_stream);
-        let message_hash = sdk::keccak(rlp_stream.as_raw());
+        let message_hash = sdk::keccak(&self.transaction.unsigned_message());
         ecrecover(
             message_hash,
             &super::vrs_to_arr(self.parity, self.r, self.s),
@@ -21,6 +21,22 @@ pub struct TransactionLegacy {
 impl TransactionLegacy {
     pub fn rlp_append_unsigned(&self, s: &mut RlpStream, chain_id: Option<u64>) {
         s.begin_list(if chain_id.is_none() { 6 } else { 9 });
+        self.rlp_append_head(s);
+        s.append(&self.data);
+        Self::rlp_append_tail(s, chain_id);
+    }
+
+    /// Same bytes as `rlp_append_unsigned`, without copying `data` more than once.
+    fn unsigned_message(&self, chain_id: Option<u64>) -> Vec<u8> {
+        let mut head = RlpStream::new();
+        self.rlp_append_head(&mut head);
+        let mut tail = RlpStream::new();
+        Self::rlp_append_tail(&mut tail, chain_id);
+        super::unsigned_message(None, head.as_raw(), &self.data, tail.as_raw())
+    }
+
+    /// Fields before `data`.
+    fn rlp_append_head(&self, s: &mut RlpStream) {
         s.append(&self.nonce);
         s.append(&self.gas_price);
         s.append(&self.gas_limit);
@@ -29,7 +45,10 @@ impl TransactionLegacy {
             Some(address) => s.append(address),
         };
         s.append(&self.value.raw());
-        s.append(&self.data);
+    }
+
+    /// Fields after `data`: the EIP-155 replay protection, if any.
+    fn rlp_append_tail(s: &mut RlpStream, chain_id: Option<u64>) {
         if let Some(chain_id) = chain_id {
             s.append(&chain_id);
             s.append(&0u8);
@@ -75,7 +94,6 @@ pub struct LegacyEthSignedTransaction {
 impl LegacyEthSignedTransaction {
     /// Returns sender of given signed transaction by doing ecrecover on the signature.
     pub fn sender(&self) -> Option<Address> {
-        let mut rlp_stream = RlpStream::new();
         // See details of CHAIN_ID computation here - https://github.com/ethereum/EIPs/blob/master/EIPS/eip-155.md#specification
         let (chain_id, rec_id) = match self.v {
             0..=26 => return None,
@@ -83,9 +101,7 @@ impl LegacyEthSignedTransaction {
             29..=34 => return None,
             _ => (Some((self.v - 35) / 2), ((self.v - 35) % 2) as u8),
         };
-        self.transaction
-            .rlp_append_unsigned(&mut rlp_stream, chain_id);
-        let message_hash = sdk::keccak(rlp_stream.as_raw());
+        let message_hash = sdk::keccak(&self.transaction.unsigned_message(chain_id));
         ecrecover(message_hash, &super::vrs_to_arr(rec_id, self.r, self.s)).ok()
     }
 
@@ -177,7 +193,7 @@ mod tests {
                 to: Some(address_from_arr(
                     &hex::decode("F0109fC8DF283027b6285cc889F5aA624EaC1F55").unwrap()
                 )),
-                value: Wei::new_u64(1000000000),
+                value: Wei::ONE_GWEI,
                 data: vec![],
             }
         );
@@ -197,6 +213,26 @@ mod tests {
         assert_eq!(tx_1.transaction.to, tx_2.transaction.to);
     }
 
+    #[test]
+    fn test_unsigned_message() {
+        let mut transaction = TransactionLegacy {
+            nonce: U256::from(7),
+            gas_price: U256::from(234567897654321u128),
+            gas_limit: U256::from(2000000u128),
+            to: None,
+            value: Wei::ONE_GWEI,
+            data: vec![],
+        };
+        for data in [vec![], vec![0x01], vec![0xab; 100_000]] {
+            transaction.data = data;
+            for chain_id in [None, Some(1313161554)] {
+                let mut s = RlpStream::new();
+                transaction.rlp_append_unsigned(&mut s, chain_id);
+                assert_eq!(transaction.unsigned_message(chain_id), s.as_raw());
+            }
+        }
+    }
+
     fn address_from_arr(arr: &[u8]) -> Address {
         assert_eq!(arr.len(), 20);
         let mut address = [0u8; 20];
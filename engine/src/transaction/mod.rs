@@ -1,4 +1,7 @@
-use crate::prelude::{vec, Address, TryFrom, Vec, U256};
+use crate::prelude::{
+    codec, sdk, u256_to_arr, vec, Address, BorshDeserialize, BorshSerialize, EthAddress, RawH256,
+    RawU256, TryFrom, Vec, U256,
+};
 use rlp::{Decodable, DecoderError, Rlp};
 
 pub mod eip_1559;
@@ -20,20 +23,27 @@ impl TryFrom<&[u8]> for EthTransactionKind {
     type Error = ParseTransactionError;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        if bytes[0] == eip_2930::TYPE_BYTE {
+        if bytes.is_empty() {
+            Err(ParseTransactionError::EmptyInput)
+        } else if bytes.len() > codec::MAX_INPUT_SIZE {
+            Err(ParseTransactionError::TooLarge)
+        } else if bytes[0] == eip_2930::TYPE_BYTE {
             Ok(Self::Eip2930(eip_2930::SignedTransaction2930::decode(
-                &Rlp::new(&bytes[1..]),
+                &codec::rlp(&bytes[1..], codec::MAX_INPUT_SIZE)?,
             )?))
         } else if bytes[0] == eip_1559::TYPE_BYTE {
             Ok(Self::Eip1559(eip_1559::SignedTransaction1559::decode(
-                &Rlp::new(&bytes[1..]),
+                &codec::rlp(&bytes[1..], codec::MAX_INPUT_SIZE)?,
             )?))
         } else if bytes[0] <= 0x7f {
             Err(ParseTransactionError::UnknownTransactionType)
         } else if bytes[0] == 0xff {
             Err(ParseTransactionError::ReservedSentinel)
         } else {
-            let legacy = legacy::LegacyEthSignedTransaction::decode(&Rlp::new(bytes))?;
+            let legacy = legacy::LegacyEthSignedTransaction::decode(&codec::rlp(
+                bytes,
+                codec::MAX_INPUT_SIZE,
+            )?)?;
             Ok(Self::Legacy(legacy))
         }
     }
@@ -59,6 +69,132 @@ impl From<EthTransactionKind> for Vec<u8> {
     }
 }
 
+impl EthTransactionKind {
+    pub fn tx_type(&self) -> TransactionType {
+        match self {
+            Self::Legacy(_) => TransactionType::Legacy,
+            Self::Eip2930(_) => TransactionType::Eip2930,
+            Self::Eip1559(_) => TransactionType::Eip1559,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TransactionType {
+    Legacy,
+    Eip2930,
+    Eip1559,
+}
+
+impl TransactionType {
+    /// EIP-2718 type of the transaction; 0 for legacy transactions.
+    pub fn type_byte(&self) -> u8 {
+        match self {
+            Self::Legacy => 0,
+            Self::Eip2930 => eip_2930::TYPE_BYTE,
+            Self::Eip1559 => eip_1559::TYPE_BYTE,
+        }
+    }
+}
+
+/// What a transaction does, as far as can be told without executing it.
+/// The signature is not checked and the sender is not recovered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TransactionClassification {
+    pub tx_type: TransactionType,
+    /// `None` for contract creations.
+    pub to: Option<Address>,
+    pub value: Wei,
+    /// Size of the calldata, or of the init code for contract creations.
+    pub data_len: usize,
+}
+
+impl TransactionClassification {
+    pub fn is_contract_creation(&self) -> bool {
+        self.to.is_none()
+    }
+}
+
+/// Classifies raw transaction bytes (as given to `submit`) without executing them.
+pub fn classify(bytes: &[u8]) -> Result<TransactionClassification, ParseTransactionError> {
+    let kind = EthTransactionKind::try_from(bytes)?;
+    let tx_type = kind.tx_type();
+    let (to, value, data_len) = match kind {
+        EthTransactionKind::Legacy(tx) => (
+            tx.transaction.to,
+            tx.transaction.value,
+            tx.transaction.data.len(),
+        ),
+        EthTransactionKind::Eip2930(tx) => (
+            tx.transaction.to,
+            tx.transaction.value,
+            tx.transaction.data.len(),
+        ),
+        EthTransactionKind::Eip1559(tx) => (
+            tx.transaction.to,
+            tx.transaction.value,
+            tx.transaction.data.len(),
+        ),
+    };
+    Ok(TransactionClassification {
+        tx_type,
+        to,
+        value,
+        data_len,
+    })
+}
+
+/// Fields of a raw transaction, returned by the `decode_transaction` view so that wallets
+/// can show what a transaction does before relaying it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransaction {
+    /// EIP-2718 type of the transaction, see `TransactionType::type_byte`.
+    pub tx_type: u8,
+    /// `None` for legacy transactions signed without replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: RawU256,
+    pub gas_limit: RawU256,
+    /// The gas price for legacy and EIP-2930 transactions.
+    pub max_priority_fee_per_gas: RawU256,
+    /// The gas price for legacy and EIP-2930 transactions.
+    pub max_fee_per_gas: RawU256,
+    /// `None` for contract creations.
+    pub to: Option<EthAddress>,
+    pub value: RawU256,
+    /// Keccak-256 hash of the calldata, or of the init code for contract creations.
+    pub data_hash: RawH256,
+    pub data_len: u64,
+    /// `None` if the signature is invalid.
+    pub sender: Option<EthAddress>,
+    /// Gas charged before execution; `None` if it overflows, which `submit` rejects.
+    pub intrinsic_gas: Option<u64>,
+}
+
+/// Decodes raw transaction bytes (as given to `submit`) with the parser of `submit`, without
+/// executing them.
+pub fn decode(
+    bytes: &[u8],
+    config: &evm::Config,
+) -> Result<DecodedTransaction, ParseTransactionError> {
+    let kind = EthTransactionKind::try_from(bytes)?;
+    let tx_type = kind.tx_type();
+    let tx = NormalizedEthTransaction::from(kind);
+    Ok(DecodedTransaction {
+        tx_type: tx_type.type_byte(),
+        chain_id: tx.chain_id,
+        nonce: u256_to_arr(&tx.nonce),
+        gas_limit: u256_to_arr(&tx.gas_limit),
+        max_priority_fee_per_gas: u256_to_arr(&tx.max_priority_fee_per_gas),
+        max_fee_per_gas: u256_to_arr(&tx.max_fee_per_gas),
+        to: tx.to.map(EthAddress::from),
+        value: tx.value.to_bytes(),
+        data_hash: sdk::keccak(&tx.data).0,
+        data_len: tx.data.len() as u64,
+        sender: tx.address.map(EthAddress::from),
+        intrinsic_gas: tx.intrinsic_gas(config),
+    })
+}
+
 /// A normalized Ethereum transaction which can be created from older
 /// transactions.
 pub struct NormalizedEthTransaction {
@@ -158,9 +294,11 @@ impl NormalizedEthTransaction {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParseTransactionError {
+    EmptyInput,
     UnknownTransactionType,
     // Per the EIP-2718 spec 0xff is a reserved value
     ReservedSentinel,
+    TooLarge,
     RlpDecodeError(DecoderError),
 }
 
@@ -170,11 +308,20 @@ impl From<DecoderError> for ParseTransactionError {
     }
 }
 
+impl From<codec::DecodeError> for ParseTransactionError {
+    fn from(_: codec::DecodeError) -> Self {
+        // `codec::rlp` only fails on the size check.
+        Self::TooLarge
+    }
+}
+
 impl AsRef<[u8]> for ParseTransactionError {
     fn as_ref(&self) -> &[u8] {
         match self {
+            Self::EmptyInput => b"ERR_EMPTY_TX",
             Self::UnknownTransactionType => b"ERR_UNKNOWN_TX_TYPE",
             Self::ReservedSentinel => b"ERR_RESERVED_LEADING_TX_BYTE",
+            Self::TooLarge => b"ERR_TX_TOO_LARGE",
             Self::RlpDecodeError(_) => b"ERR_TX_RLP_DECODE",
         }
     }
@@ -205,3 +352,159 @@ fn vrs_to_arr(v: u8, r: U256, s: U256) -> [u8; 65] {
     result[64] = v;
     result
 }
+
+/// RLP encoding of the message signed by a transaction: `prefix` (the type byte of typed
+/// transactions), then a list made of the already encoded items `head`, the byte string
+/// `data` and the already encoded items `tail`.
+///
+/// Encoding the whole list with an `RlpStream` copies `data` several times, as its buffer
+/// grows and again when the list header is inserted in front of the items. Here the buffer
+/// is allocated once with its final size and `data` is copied into it once, which matters
+/// for transactions with large calldata.
+fn unsigned_message(prefix: Option<u8>, head: &[u8], data: &[u8], tail: &[u8]) -> Vec<u8> {
+    let (data_header, data_header_len) = if data.len() == 1 && data[0] < 0x80 {
+        // A single byte below 0x80 is its own encoding.
+        ([0u8; 9], 0)
+    } else {
+        rlp_header(0x80, data.len())
+    };
+    let payload_len = head.len() + data_header_len + data.len() + tail.len();
+    let (list_header, list_header_len) = rlp_header(0xc0, payload_len);
+
+    let mut message =
+        Vec::with_capacity(usize::from(prefix.is_some()) + list_header_len + payload_len);
+    message.extend(prefix);
+    message.extend_from_slice(&list_header[..list_header_len]);
+    message.extend_from_slice(head);
+    message.extend_from_slice(&data_header[..data_header_len]);
+    message.extend_from_slice(data);
+    message.extend_from_slice(tail);
+    message
+}
+
+/// Header of an RLP string (`offset = 0x80`) or list (`offset = 0xc0`) with a payload of
+/// `len` bytes, and the length of the header.
+fn rlp_header(offset: u8, len: usize) -> ([u8; 9], usize) {
+    let mut header = [0u8; 9];
+    if len <= 55 {
+        header[0] = offset + len as u8;
+        (header, 1)
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let leading_zeros = len_bytes.iter().take_while(|b| **b == 0).count();
+        let len_of_len = len_bytes.len() - leading_zeros;
+        header[0] = offset + 55 + len_of_len as u8;
+        header[1..=len_of_len].copy_from_slice(&len_bytes[leading_zeros..]);
+        (header, 1 + len_of_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_call() {
+        let encoded_tx = hex::decode("f86a8086d55698372431831e848094f0109fc8df283027b6285cc889f5aa624eac1f55843b9aca008025a009ebb6ca057a0535d6186462bc0b465b561c94a295bdb0621fc19208ab149a9ca0440ffd775ce91a833ab410777204d5341a6f9fa91216a6f3ee2c051fea6a0428").unwrap();
+        let classification = classify(&encoded_tx).unwrap();
+        assert_eq!(classification.tx_type, TransactionType::Legacy);
+        assert!(!classification.is_contract_creation());
+        assert_eq!(
+            classification.to,
+            Some(Address::from_slice(
+                &hex::decode("F0109fC8DF283027b6285cc889F5aA624EaC1F55").unwrap()
+            ))
+        );
+        assert_eq!(classification.value, Wei::ONE_GWEI);
+        assert_eq!(classification.data_len, 0);
+    }
+
+    #[test]
+    fn test_classify_contract_creation() {
+        let tx = eip_1559::SignedTransaction1559 {
+            transaction: eip_1559::Transaction1559 {
+                chain_id: 1,
+                nonce: U256::zero(),
+                max_priority_fee_per_gas: U256::zero(),
+                max_fee_per_gas: U256::zero(),
+                gas_limit: U256::from(100_000),
+                to: None,
+                value: Wei::zero(),
+                data: vec![0x60, 0x00, 0x60, 0x00, 0xf3],
+                access_list: vec![],
+            },
+            parity: 0,
+            r: U256::one(),
+            s: U256::one(),
+        };
+        let bytes: Vec<u8> = EthTransactionKind::Eip1559(tx).into();
+        let classification = classify(&bytes).unwrap();
+        assert_eq!(classification.tx_type, TransactionType::Eip1559);
+        assert!(classification.is_contract_creation());
+        assert_eq!(classification.data_len, 5);
+    }
+
+    #[test]
+    fn test_classify_invalid_input() {
+        assert_eq!(classify(&[]), Err(ParseTransactionError::EmptyInput));
+        assert_eq!(
+            classify(&[0x05, 0xc0]),
+            Err(ParseTransactionError::UnknownTransactionType)
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let encoded_tx = hex::decode("f86a8086d55698372431831e848094f0109fc8df283027b6285cc889f5aa624eac1f55843b9aca008025a009ebb6ca057a0535d6186462bc0b465b561c94a295bdb0621fc19208ab149a9ca0440ffd775ce91a833ab410777204d5341a6f9fa91216a6f3ee2c051fea6a0428").unwrap();
+        let decoded = decode(&encoded_tx, &evm::Config::london()).unwrap();
+        assert_eq!(decoded.tx_type, 0);
+        assert_eq!(decoded.chain_id, Some(1));
+        assert_eq!(U256::from_big_endian(&decoded.nonce), U256::zero());
+        assert_eq!(
+            U256::from_big_endian(&decoded.gas_limit),
+            U256::from(2_000_000)
+        );
+        assert_eq!(
+            decoded.to,
+            Some(EthAddress::decode("F0109fC8DF283027b6285cc889F5aA624EaC1F55").unwrap())
+        );
+        assert_eq!(decoded.value, Wei::ONE_GWEI.to_bytes());
+        assert_eq!(decoded.data_hash, sdk::keccak(&[]).0);
+        assert_eq!(decoded.data_len, 0);
+        assert!(decoded.sender.is_some());
+        assert_eq!(decoded.intrinsic_gas, Some(21_000));
+    }
+
+    #[test]
+    fn test_unsigned_message_matches_rlp_stream() {
+        for len in [0, 1, 55, 56, 255, 256, 65_535, 65_536] {
+            for first_byte in [0x00u8, 0x7f, 0x80] {
+                let data = vec![first_byte; len];
+                let mut s = rlp::RlpStream::new();
+                s.append(&U256::from(1));
+                s.append(&0x2au8);
+                s.append(&U256::MAX);
+                let head = s.out().to_vec();
+                let mut s = rlp::RlpStream::new();
+                s.append(&1u64);
+                s.append(&0u8);
+                let tail = s.out().to_vec();
+
+                let mut expected = rlp::RlpStream::new();
+                expected.append(&eip_1559::TYPE_BYTE);
+                expected.begin_list(6);
+                expected.append(&U256::from(1));
+                expected.append(&0x2au8);
+                expected.append(&U256::MAX);
+                expected.append(&data);
+                expected.append(&1u64);
+                expected.append(&0u8);
+
+                assert_eq!(
+                    unsigned_message(Some(eip_1559::TYPE_BYTE), &head, &data, &tail),
+                    expected.as_raw()
+                );
+            }
+        }
+    }
+}
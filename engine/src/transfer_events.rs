@@ -0,0 +1,190 @@
+//! NEP-141 `ft_transfer` events for the ETH moved between EVM addresses.
+//!
+//! The connector emits its own events for deposits and withdrawals, but ETH moving between
+//! EVM addresses (value transfers, gas payments to the relayer) is invisible on the NEAR
+//! side. When the owner switches these events on, `submit` and `call` journal the balance
+//! changes of the transaction and emit a single `ft_transfer` event for all of them, in
+//! the NEP-141 format, so that NEAR indexers can follow the bridged ETH. They are off by
+//! default: journaling reads every balance before it is written, which costs gas.
+//!
+//! The journal only has the net change of each address, so the transfers of the event are
+//! rebuilt by matching the addresses which lost ETH with the ones which gained some, in
+//! ascending address order. ETH which was not moved to another address (e.g. burned by a
+//! withdrawal) is left out.
+
+use crate::balance_journal::BalanceJournal;
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::{bytes_to_key, sdk, Address, BTreeMap, KeyPrefix, String, ToString, Vec, Wei};
+use aurora_engine_sdk::io::IO;
+
+const ETH_TRANSFER_EVENTS_KEY: &[u8; 19] = b"ETH_TRANSFER_EVENTS";
+
+pub const NEP141_STANDARD: &str = "nep141";
+pub const NEP141_VERSION: &str = "1.0.0";
+/// Name of the NEP-141 event emitted for the ETH moved by a transaction.
+pub const FT_TRANSFER: &str = "ft_transfer";
+
+/// ETH moved from one address to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub amount: Wei,
+}
+
+pub fn is_enabled<I: IO>(io: &I) -> bool {
+    io.storage_has_key(&bytes_to_key(KeyPrefix::Config, ETH_TRANSFER_EVENTS_KEY))
+}
+
+pub fn set_enabled<I: IO>(io: &mut I, enabled: bool) {
+    let previous = is_enabled(io);
+    let key = bytes_to_key(KeyPrefix::Config, ETH_TRANSFER_EVENTS_KEY);
+    if enabled {
+        io.write_storage(&key, &[1]);
+    } else {
+        io.remove_storage(&key);
+    }
+    events::emit_config_changed(
+        "eth_transfer_events",
+        JsonValue::Bool(previous),
+        JsonValue::Bool(enabled),
+    );
+}
+
+/// Transfers accounting for the net balance changes of the journal.
+pub fn transfers(journal: &BalanceJournal) -> Vec<EthTransfer> {
+    let mut senders = Vec::new();
+    let mut receivers = Vec::new();
+    for (address, change) in journal.net_changes() {
+        if change.is_increase() {
+            receivers.push((address, change.amount().raw()));
+        } else {
+            senders.push((address, change.amount().raw()));
+        }
+    }
+
+    let mut transfers = Vec::new();
+    let mut receivers = receivers.into_iter().peekable();
+    for (from, mut left) in senders {
+        while !left.is_zero() {
+            let (to, wanted) = match receivers.peek_mut() {
+                Some(receiver) => receiver,
+                None => return transfers,
+            };
+            let amount = left.min(*wanted);
+            transfers.push(EthTransfer {
+                from,
+                to: *to,
+                amount: Wei::new(amount),
+            });
+            left -= amount;
+            *wanted -= amount;
+            if wanted.is_zero() {
+                receivers.next();
+            }
+        }
+    }
+    transfers
+}
+
+/// Emits the `ft_transfer` event of the journal, unless no ETH was moved.
+pub fn emit(journal: &BalanceJournal) {
+    let transfers = transfers(journal);
+    if !transfers.is_empty() {
+        sdk::log!(event_log(&transfers).as_str());
+    }
+}
+
+/// Log line of the `ft_transfer` event of the given transfers.
+pub fn event_log(transfers: &[EthTransfer]) -> String {
+    let data = transfers.iter().map(transfer_data).collect();
+    events::nep297_log(NEP141_STANDARD, NEP141_VERSION, FT_TRANSFER, data)
+}
+
+fn transfer_data(transfer: &EthTransfer) -> JsonValue {
+    let mut kvs = BTreeMap::new();
+    kvs.insert(
+        "old_owner_id".to_string(),
+        JsonValue::String(hex::encode(transfer.from)),
+    );
+    kvs.insert(
+        "new_owner_id".to_string(),
+        JsonValue::String(hex::encode(transfer.to)),
+    );
+    kvs.insert(
+        "amount".to_string(),
+        JsonValue::String(transfer.amount.raw().to_string()),
+    );
+    JsonValue::Object(kvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::vec;
+
+    #[test]
+    fn test_transfers_match_senders_with_receivers() {
+        let alice = Address([1; 20]);
+        let bob = Address([2; 20]);
+        let carol = Address([3; 20]);
+        let relayer = Address([4; 20]);
+        let mut journal = BalanceJournal::new();
+        journal.record(alice, Wei::new_u64(100), Wei::new_u64(40));
+        journal.record(bob, Wei::new_u64(10), Wei::new_u64(5));
+        journal.record(carol, Wei::zero(), Wei::new_u64(50));
+        journal.record(relayer, Wei::new_u64(1), Wei::new_u64(16));
+
+        let transfer = |from, to, amount| EthTransfer {
+            from,
+            to,
+            amount: Wei::new_u64(amount),
+        };
+        assert_eq!(
+            transfers(&journal),
+            vec![
+                transfer(alice, carol, 50),
+                transfer(alice, relayer, 10),
+                transfer(bob, relayer, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transfers_leave_out_burned_eth() {
+        let alice = Address([1; 20]);
+        let bob = Address([2; 20]);
+        let mut journal = BalanceJournal::new();
+        journal.record(alice, Wei::new_u64(100), Wei::zero());
+        journal.record(bob, Wei::zero(), Wei::new_u64(30));
+
+        assert_eq!(
+            transfers(&journal),
+            vec![EthTransfer {
+                from: alice,
+                to: bob,
+                amount: Wei::new_u64(30),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ft_transfer_event() {
+        let log = event_log(&[EthTransfer {
+            from: Address([1; 20]),
+            to: Address([2; 20]),
+            amount: Wei::new_u64(30),
+        }]);
+        let json = log.strip_prefix(events::EVENT_JSON_PREFIX).unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        assert_eq!(value["standard"], "nep141");
+        assert_eq!(value["version"], "1.0.0");
+        assert_eq!(value["event"], "ft_transfer");
+        let data = &value["data"][0];
+        assert_eq!(data["old_owner_id"], hex::encode([1; 20]));
+        assert_eq!(data["new_owner_id"], hex::encode([2; 20]));
+        assert_eq!(data["amount"], "30");
+    }
+}
@@ -0,0 +1,142 @@
+//! Registry of the engine code deployed over time.
+//!
+//! `deploy_upgrade` records the hash of the staged code before deploying it, and the
+//! `state_migration` of the new code moves it into the history together with the height
+//! at which it became active. Replicas replaying historical transactions use the registry
+//! to know which code (and therefore which semantics) applied at a given height.
+//!
+//! The contract cannot read its own code, so the history starts with the first upgrade
+//! deployed by a version of the engine which keeps the registry.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::prelude::{
+    bytes_to_key, sdk, BorshDeserialize, BorshSerialize, KeyPrefix, RawH256, Vec, H256,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+
+const UPGRADE_HISTORY_KEY: &[u8; 15] = b"UPGRADE_HISTORY";
+const PENDING_UPGRADE_KEY: &[u8; 15] = b"UPGRADE_PENDING";
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpgradeRecord {
+    /// Keccak hash of the deployed WASM code.
+    pub code_hash: RawH256,
+    /// Height of the first block executed with this code.
+    pub activated_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeHistoryError {
+    InvalidStoredHistory,
+}
+
+impl AsRef<[u8]> for UpgradeHistoryError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::InvalidStoredHistory => b"ERR_INVALID_UPGRADE_HISTORY",
+        }
+    }
+}
+
+/// All recorded upgrades, in activation order.
+pub fn get_history<I: IO>(io: &I) -> Result<Vec<UpgradeRecord>, UpgradeHistoryError> {
+    match io.read_storage(&bytes_to_key(KeyPrefix::Config, UPGRADE_HISTORY_KEY)) {
+        None => Ok(Vec::new()),
+        Some(bytes) => Vec::<UpgradeRecord>::try_from_slice(&bytes.to_vec())
+            .map_err(|_| UpgradeHistoryError::InvalidStoredHistory),
+    }
+}
+
+/// The upgrade in effect at the given height, if any was recorded before it.
+pub fn active_at(history: &[UpgradeRecord], height: u64) -> Option<&UpgradeRecord> {
+    history
+        .iter()
+        .rev()
+        .find(|record| record.activated_at <= height)
+}
+
+/// Emits `config_changed` for code staged by `stage_upgrade`, given the hash of the code
+/// it replaces, if any.
+pub fn emit_staged(previous: Option<H256>, staged: H256) {
+    events::emit_config_changed(
+        "staged_upgrade",
+        previous.map_or(JsonValue::Null, |hash| hash_data(&hash.0)),
+        hash_data(&staged.0),
+    );
+}
+
+fn hash_data(code_hash: &RawH256) -> JsonValue {
+    JsonValue::String(hex::encode(code_hash))
+}
+
+/// Remembers the hash of the code about to be deployed, until it becomes active.
+pub fn set_pending<I: IO>(io: &mut I, code: &[u8]) {
+    let code_hash = sdk::keccak(code);
+    io.write_storage(
+        &bytes_to_key(KeyPrefix::Config, PENDING_UPGRADE_KEY),
+        code_hash.as_bytes(),
+    );
+}
+
+/// Whether code was deployed by `deploy_upgrade` without its `state_migration` having run.
+pub fn has_pending<I: IO>(io: &I) -> bool {
+    io.storage_has_key(&bytes_to_key(KeyPrefix::Config, PENDING_UPGRADE_KEY))
+}
+
+/// Moves the pending upgrade (if any) into the history, as activated at `height`.
+pub fn activate_pending<I: IO>(io: &mut I, height: u64) -> Result<(), UpgradeHistoryError> {
+    let code_hash = match io.read_storage(&bytes_to_key(KeyPrefix::Config, PENDING_UPGRADE_KEY)) {
+        Some(value) if value.len() == 32 => {
+            let mut buf = [0u8; 32];
+            value.copy_to_slice(&mut buf);
+            buf
+        }
+        _ => return Ok(()),
+    };
+    let mut history = get_history(io)?;
+    let previous = history
+        .last()
+        .map_or(JsonValue::Null, |record| hash_data(&record.code_hash));
+    history.push(UpgradeRecord {
+        code_hash,
+        activated_at: height,
+    });
+    io.write_borsh(
+        &bytes_to_key(KeyPrefix::Config, UPGRADE_HISTORY_KEY),
+        &history,
+    );
+    io.remove_storage(&bytes_to_key(KeyPrefix::Config, PENDING_UPGRADE_KEY));
+    sdk::log!(crate::prelude::format!(
+        "Engine code {} active from height {}",
+        hex::encode(code_hash),
+        height
+    )
+    .as_str());
+    events::emit_config_changed("engine_code", previous, hash_data(&code_hash));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::vec;
+
+    #[test]
+    fn test_active_at() {
+        let history = vec![
+            UpgradeRecord {
+                code_hash: [1u8; 32],
+                activated_at: 10,
+            },
+            UpgradeRecord {
+                code_hash: [2u8; 32],
+                activated_at: 20,
+            },
+        ];
+        assert_eq!(active_at(&history, 9), None);
+        assert_eq!(active_at(&history, 10).unwrap().code_hash, [1u8; 32]);
+        assert_eq!(active_at(&history, 19).unwrap().code_hash, [1u8; 32]);
+        assert_eq!(active_at(&history, 20).unwrap().code_hash, [2u8; 32]);
+    }
+}
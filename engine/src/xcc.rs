@@ -0,0 +1,306 @@
+//! Scheduling of the cross-contract calls made through the `CrossContractCall` precompile.
+//!
+//! Every EVM address making cross-contract calls has a router: the sub-account of the engine
+//! named after the address (see `router_account_id`), running the router code the owner sets
+//! with `factory_update`. The router makes the NEAR call the engine passes to its `execute`
+//! method, with the NEAR attached to it, and returns the result of the call.
+//!
+//! The NEAR attached to the calls, and the storage deposit of a router when the engine creates
+//! it, are paid from a NEAR balance kept for each address and funded with `xcc_fund`, so that
+//! an EVM address cannot spend the NEAR of the engine. A transaction whose calls are not
+//! covered by the balance of their address fails.
+//!
+//! When the precompile was given a callback, the private `xcc_callback` method of the engine
+//! calls the caller back once the NEAR call is resolved. The EVM call is made from the address
+//! of the precompile, and the caller reads the result of the NEAR call with the
+//! `PromiseResults` precompile.
+
+use crate::events;
+use crate::json::JsonValue;
+use crate::parameters::{PromiseStats, XccCallbackArgs};
+use crate::prelude::precompiles::xcc::{router_account_id, CrossContractCall, ScheduledCall};
+use crate::prelude::{
+    bytes_to_key, sdk, vec, AccountId, Address, BTreeMap, BorshDeserialize, BorshSerialize,
+    KeyPrefix, NearGas, PromiseAction, PromiseArgs, PromiseBatchAction, PromiseCreateArgs,
+    PromiseWithCallbackArgs, ToString, Vec,
+};
+use aurora_engine_sdk::io::{StorageIntermediate, IO};
+use aurora_engine_sdk::promise::PromiseHandler;
+use evm::backend::Log;
+
+const ROUTER_CODE_KEY: &[u8; 8] = b"XCC_CODE";
+const ROUTER_KEY: &[u8; 10] = b"XCC_ROUTER";
+const BALANCE_KEY: &[u8; 11] = b"XCC_BALANCE";
+
+/// NEAR transferred to a router when it is created, covering the storage of its code.
+pub const ROUTER_DEPOSIT: u128 = 2_000_000_000_000_000_000_000_000;
+/// Gas of the router itself, added to the gas of the call it makes.
+pub const EXECUTE_GAS: NearGas = NearGas::new(10_000_000_000_000);
+/// Gas of `xcc_callback`.
+pub const CALLBACK_GAS: NearGas = NearGas::new(60_000_000_000_000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XccError {
+    RouterCodeNotSet,
+    InsufficientBalance,
+}
+
+impl AsRef<[u8]> for XccError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::RouterCodeNotSet => b"ERR_XCC_ROUTER_CODE_NOT_SET",
+            Self::InsufficientBalance => b"ERR_XCC_INSUFFICIENT_BALANCE",
+        }
+    }
+}
+
+/// Promises scheduling a cross-contract call. They are planned before any of them is
+/// scheduled, so that they count towards the promise budget of the transaction.
+pub struct XccPromises {
+    sender: Address,
+    /// Creation of the router of the sender, on its first call.
+    create_router: Option<PromiseBatchAction>,
+    execute: PromiseCreateArgs,
+    callback: Option<PromiseCreateArgs>,
+    /// NEAR paid from the balance of the sender.
+    cost: u128,
+}
+
+impl XccPromises {
+    /// Adds the promises to `stats`.
+    pub fn account(&self, stats: &mut PromiseStats) {
+        if self.create_router.is_some() {
+            stats.count = stats.count.saturating_add(1);
+            stats.attached_balance = stats.attached_balance.saturating_add(ROUTER_DEPOSIT);
+        }
+        stats.add(&self.execute);
+        if let Some(callback) = &self.callback {
+            stats.add(callback);
+        }
+    }
+
+    /// The call and its callback, leaving out the creation of the router.
+    pub fn into_promise_args(self) -> PromiseArgs {
+        match self.callback {
+            Some(callback) => PromiseArgs::Callback(PromiseWithCallbackArgs {
+                base: self.execute,
+                callback,
+            }),
+            None => PromiseArgs::Create(self.execute),
+        }
+    }
+}
+
+/// Separates the calls logged by the precompile from the other logs.
+pub fn split_calls(logs: Vec<Log>) -> (Vec<ScheduledCall>, Vec<Log>) {
+    let mut calls = Vec::new();
+    let logs = logs
+        .into_iter()
+        .filter(|log| {
+            if log.address != CrossContractCall::ADDRESS || !log.topics.is_empty() {
+                return true;
+            }
+            if let Ok(call) = ScheduledCall::try_from_slice(&log.data) {
+                calls.push(call);
+            }
+            false
+        })
+        .collect();
+    (calls, logs)
+}
+
+/// Plans the promises of `calls`, failing if a router must be created but no router code is
+/// set, or if the balance of an address does not cover its calls.
+pub fn plan<I: IO>(
+    io: &I,
+    engine_account_id: &AccountId,
+    calls: Vec<ScheduledCall>,
+) -> Result<Vec<XccPromises>, XccError> {
+    let mut balances: BTreeMap<Address, u128> = BTreeMap::new();
+    let mut created_routers: Vec<Address> = Vec::new();
+    let mut promises = Vec::with_capacity(calls.len());
+    for call in calls {
+        let sender = Address(call.sender);
+        let router = router_account_id(&sender, engine_account_id);
+
+        let create_router = if has_router(io, &sender) || created_routers.contains(&sender) {
+            None
+        } else {
+            let code = get_router_code(io).ok_or(XccError::RouterCodeNotSet)?;
+            created_routers.push(sender);
+            Some(PromiseBatchAction {
+                target_account_id: router.clone(),
+                actions: vec![
+                    PromiseAction::CreateAccount,
+                    PromiseAction::Transfer {
+                        amount: ROUTER_DEPOSIT,
+                    },
+                    PromiseAction::DeployConotract { code },
+                ],
+            })
+        };
+
+        let promise = call.args.promise;
+        let deposit = if create_router.is_some() {
+            ROUTER_DEPOSIT
+        } else {
+            0
+        };
+        let cost = promise
+            .attached_balance
+            .checked_add(deposit)
+            .ok_or(XccError::InsufficientBalance)?;
+        let balance = balances
+            .entry(sender)
+            .or_insert_with(|| get_balance(io, &sender));
+        *balance = balance
+            .checked_sub(cost)
+            .ok_or(XccError::InsufficientBalance)?;
+
+        let execute = PromiseCreateArgs {
+            target_account_id: router,
+            method: "execute".to_string(),
+            attached_balance: promise.attached_balance,
+            attached_gas: promise.attached_gas.saturating_add(EXECUTE_GAS.into_u64()),
+            args: promise.try_to_vec().unwrap(),
+        };
+        let callback = call.args.callback.map(|input| PromiseCreateArgs {
+            target_account_id: engine_account_id.clone(),
+            method: "xcc_callback".to_string(),
+            args: XccCallbackArgs {
+                address: call.sender,
+                input,
+            }
+            .try_to_vec()
+            .unwrap(),
+            attached_balance: 0,
+            attached_gas: CALLBACK_GAS.into_u64(),
+        });
+
+        promises.push(XccPromises {
+            sender,
+            create_router,
+            execute,
+            callback,
+            cost,
+        });
+    }
+    Ok(promises)
+}
+
+/// Schedules the planned promises, paying for them from the balances of their senders.
+pub fn schedule<I: IO, P: PromiseHandler>(io: &mut I, handler: &mut P, promises: Vec<XccPromises>) {
+    for promise in promises {
+        let balance = get_balance(io, &promise.sender);
+        // `plan` checked the balance.
+        set_balance(io, &promise.sender, balance - promise.cost);
+
+        let execute_id = match promise.create_router {
+            Some(create_router) => {
+                io.write_storage(&router_key(&promise.sender), &[1]);
+                let create_id = handler.promise_create_batch(&create_router);
+                handler.promise_attach_callback(create_id, &promise.execute)
+            }
+            None => handler.promise_create_call(&promise.execute),
+        };
+        sdk::log!(&crate::prelude::format!(
+            "call_contract {}.{}",
+            promise.execute.target_account_id,
+            promise.execute.method
+        ));
+        if let Some(callback) = promise.callback {
+            handler.promise_attach_callback(execute_id, &callback);
+        }
+    }
+}
+
+/// Sets the code the routers created from now on run.
+pub fn set_router_code<I: IO>(io: &mut I, code: &[u8]) {
+    let key = bytes_to_key(KeyPrefix::Config, ROUTER_CODE_KEY);
+    let previous = io
+        .read_storage(&key)
+        .map(|code| JsonValue::String(hex::encode(sdk::keccak(&code.to_vec()))));
+    io.write_storage(&key, code);
+    events::emit_config_changed(
+        "xcc_router_code_hash",
+        previous.unwrap_or(JsonValue::Null),
+        JsonValue::String(hex::encode(sdk::keccak(code))),
+    );
+}
+
+pub fn get_router_code<I: IO>(io: &I) -> Option<Vec<u8>> {
+    io.read_storage(&bytes_to_key(KeyPrefix::Config, ROUTER_CODE_KEY))
+        .map(|code| code.to_vec())
+}
+
+/// Whether the engine created the router of `address`.
+pub fn has_router<I: IO>(io: &I, address: &Address) -> bool {
+    io.storage_has_key(&router_key(address))
+}
+
+/// NEAR balance (in yoctoNEAR) paying for the cross-contract calls of `address`.
+pub fn get_balance<I: IO>(io: &I, address: &Address) -> u128 {
+    io.read_storage(&balance_key(address))
+        .and_then(|balance| balance.to_value().ok())
+        .unwrap_or(0)
+}
+
+/// Adds `amount` yoctoNEAR to the balance of `address`.
+pub fn fund<I: IO>(io: &mut I, address: &Address, amount: u128) {
+    let balance = get_balance(io, address).saturating_add(amount);
+    set_balance(io, address, balance);
+}
+
+fn set_balance<I: IO>(io: &mut I, address: &Address, balance: u128) {
+    io.write_borsh(&balance_key(address), &balance);
+}
+
+fn router_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[ROUTER_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+fn balance_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::Config,
+        &[BALANCE_KEY.as_slice(), address.as_bytes()].concat(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_calls() {
+        let call = ScheduledCall {
+            sender: [1; 20],
+            args: crate::prelude::parameters::CrossContractCallArgs {
+                promise: PromiseCreateArgs {
+                    target_account_id: "target.near".parse().unwrap(),
+                    method: "method".to_string(),
+                    args: Vec::new(),
+                    attached_balance: 0,
+                    attached_gas: 0,
+                },
+                callback: None,
+            },
+        };
+        let xcc_log = Log {
+            address: CrossContractCall::ADDRESS,
+            topics: Vec::new(),
+            data: call.try_to_vec().unwrap(),
+        };
+        let other_log = Log {
+            address: Address::from_low_u64_be(1),
+            topics: Vec::new(),
+            data: vec![1],
+        };
+        let (calls, logs) = split_calls(vec![other_log.clone(), xcc_log]);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].sender, [1; 20]);
+        assert_eq!(logs, vec![other_log]);
+    }
+}
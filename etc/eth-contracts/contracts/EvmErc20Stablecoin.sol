@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: CC0-1.0
+pragma solidity ^0.8.0;
+
+import "./EvmErc20.sol";
+import "./TransferAuthorization.sol";
+
+
+/**
+ * @title Bridged stablecoin
+ * @dev `EvmErc20` with the transfers with authorization of EIP-3009, which stablecoin
+ * integrations (exchanges, payment processors) rely on. Authorizations are signed in the
+ * EIP-712 domain of the permits.
+ */
+contract EvmErc20Stablecoin is EvmErc20, TransferAuthorization {
+    constructor (string memory metadata_name, string memory metadata_symbol, uint8 metadata_decimals, address admin)
+        EvmErc20(metadata_name, metadata_symbol, metadata_decimals, admin)
+    {
+    }
+
+    function _domainSeparator() internal view override returns (bytes32) {
+        return DOMAIN_SEPARATOR();
+    }
+
+    function _authorizedTransfer(address from, address to, uint256 value) internal override {
+        _transfer(from, to, value);
+    }
+}
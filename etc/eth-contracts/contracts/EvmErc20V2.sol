@@ -3,6 +3,7 @@ pragma solidity ^0.8.0;
 
 import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
 import "./AdminControlled.sol";
+import "./BridgedPermit.sol";
 import "./IExit.sol";
 
 
@@ -12,7 +13,7 @@ import "./IExit.sol";
  * Note they can later distribute these tokens as they wish using `transfer` and other
  * `ERC20` functions.
  */
-contract EvmErc20V2 is ERC20, AdminControlled, IExit {
+contract EvmErc20V2 is ERC20, AdminControlled, BridgedPermit, IExit {
     string private _name;
     string private _symbol;
     uint8 private _decimals;
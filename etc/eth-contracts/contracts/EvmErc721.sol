@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: CC0-1.0
+pragma solidity ^0.8.0;
+
+import "@openzeppelin/contracts/token/ERC721/ERC721.sol";
+import "./AdminControlled.sol";
+
+
+/**
+ * @title EvmErc721
+ * @dev ERC-721 contract of a NEP-171 collection bridged to Aurora. The engine (the admin)
+ * mints the NEAR token with id `t` as the token `tokenIdOf(t)` and keeps `t`, so that
+ * `withdrawToNear` can send the token back to NEAR through the exit NFT precompile.
+ */
+contract EvmErc721 is ERC721, AdminControlled {
+    mapping(uint256 => string) private _nearTokenIds;
+
+    constructor (string memory metadata_name, string memory metadata_symbol, address admin)
+        ERC721(metadata_name, metadata_symbol)
+        AdminControlled(admin, 0)
+    {
+    }
+
+    function tokenIdOf(string memory near_token_id) public pure returns (uint256) {
+        return uint256(keccak256(bytes(near_token_id)));
+    }
+
+    function nearTokenId(uint256 tokenId) external view returns (string memory) {
+        require(_exists(tokenId), "ERC721: nonexistent token");
+        return _nearTokenIds[tokenId];
+    }
+
+    function mint(address to, string memory near_token_id) public onlyAdmin {
+        uint256 tokenId = tokenIdOf(near_token_id);
+        _nearTokenIds[tokenId] = near_token_id;
+        _mint(to, tokenId);
+    }
+
+    function withdrawToNear(bytes memory recipient, uint256 tokenId) external {
+        require(_isApprovedOrOwner(_msgSender(), tokenId), "ERC721: caller is not owner nor approved");
+        bytes memory near_token_id = bytes(_nearTokenIds[tokenId]);
+        _burn(tokenId);
+        delete _nearTokenIds[tokenId];
+
+        bytes memory input = abi.encodePacked(uint32(near_token_id.length), near_token_id, recipient);
+        uint input_size = 4 + near_token_id.length + recipient.length;
+
+        assembly {
+            let res := call(gas(), 0x4ef4bcbe1bc5c00c19ec753e362ac0756c01f43b, 0, add(input, 32), input_size, 0, 32)
+            // The token is burned, it must not be lost if the exit fails.
+            if iszero(res) {
+                revert(0, 0)
+            }
+        }
+    }
+}
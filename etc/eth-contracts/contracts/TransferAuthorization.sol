@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: CC0-1.0
+pragma solidity ^0.8.0;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+
+
+/**
+ * @dev Transfers with authorization (EIP-3009).
+ *
+ * Unlike permits, authorizations have random nonces rather than sequential ones, so that a
+ * holder can sign several transfers which are submitted in any order. The token provides the
+ * EIP-712 domain of the signatures and makes the transfers.
+ */
+abstract contract TransferAuthorization {
+    bytes32 public constant TRANSFER_WITH_AUTHORIZATION_TYPEHASH =
+        keccak256("TransferWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 validBefore,bytes32 nonce)");
+    bytes32 public constant RECEIVE_WITH_AUTHORIZATION_TYPEHASH =
+        keccak256("ReceiveWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 validBefore,bytes32 nonce)");
+    bytes32 public constant CANCEL_AUTHORIZATION_TYPEHASH =
+        keccak256("CancelAuthorization(address authorizer,bytes32 nonce)");
+
+    mapping(address => mapping(bytes32 => bool)) private _authorizationStates;
+
+    event AuthorizationUsed(address indexed authorizer, bytes32 indexed nonce);
+    event AuthorizationCanceled(address indexed authorizer, bytes32 indexed nonce);
+
+    /**
+     * @dev Whether the authorization with the nonce was used or canceled.
+     */
+    function authorizationState(address authorizer, bytes32 nonce) external view returns (bool) {
+        return _authorizationStates[authorizer][nonce];
+    }
+
+    function transferWithAuthorization(
+        address from,
+        address to,
+        uint256 value,
+        uint256 validAfter,
+        uint256 validBefore,
+        bytes32 nonce,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external {
+        _requireValidAuthorization(from, nonce, validAfter, validBefore);
+        _requireSignedBy(
+            from,
+            keccak256(abi.encode(TRANSFER_WITH_AUTHORIZATION_TYPEHASH, from, to, value, validAfter, validBefore, nonce)),
+            v,
+            r,
+            s
+        );
+        _markAuthorizationAsUsed(from, nonce);
+
+        _authorizedTransfer(from, to, value);
+    }
+
+    /**
+     * @dev Same as `transferWithAuthorization`, but only the payee can submit the authorization,
+     * so that it cannot be front-run when the payee is a contract acting on the transfer.
+     */
+    function receiveWithAuthorization(
+        address from,
+        address to,
+        uint256 value,
+        uint256 validAfter,
+        uint256 validBefore,
+        bytes32 nonce,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external {
+        require(to == msg.sender, "ERC3009: caller must be the payee");
+        _requireValidAuthorization(from, nonce, validAfter, validBefore);
+        _requireSignedBy(
+            from,
+            keccak256(abi.encode(RECEIVE_WITH_AUTHORIZATION_TYPEHASH, from, to, value, validAfter, validBefore, nonce)),
+            v,
+            r,
+            s
+        );
+        _markAuthorizationAsUsed(from, nonce);
+
+        _authorizedTransfer(from, to, value);
+    }
+
+    function cancelAuthorization(address authorizer, bytes32 nonce, uint8 v, bytes32 r, bytes32 s) external {
+        require(!_authorizationStates[authorizer][nonce], "ERC3009: authorization is used or canceled");
+        bytes32 structHash = keccak256(abi.encode(CANCEL_AUTHORIZATION_TYPEHASH, authorizer, nonce));
+        _requireSignedBy(authorizer, structHash, v, r, s);
+
+        _authorizationStates[authorizer][nonce] = true;
+        emit AuthorizationCanceled(authorizer, nonce);
+    }
+
+    function _domainSeparator() internal view virtual returns (bytes32);
+
+    function _authorizedTransfer(address from, address to, uint256 value) internal virtual;
+
+    function _requireValidAuthorization(address authorizer, bytes32 nonce, uint256 validAfter, uint256 validBefore) private view {
+        require(block.timestamp > validAfter, "ERC3009: authorization is not yet valid");
+        require(block.timestamp < validBefore, "ERC3009: authorization is expired");
+        require(!_authorizationStates[authorizer][nonce], "ERC3009: authorization is used or canceled");
+    }
+
+    function _markAuthorizationAsUsed(address authorizer, bytes32 nonce) private {
+        _authorizationStates[authorizer][nonce] = true;
+        emit AuthorizationUsed(authorizer, nonce);
+    }
+
+    function _requireSignedBy(address authorizer, bytes32 structHash, uint8 v, bytes32 r, bytes32 s) private view {
+        bytes32 hash = ECDSA.toTypedDataHash(_domainSeparator(), structHash);
+        require(ECDSA.recover(hash, v, r, s) == authorizer, "ERC3009: invalid signature");
+    }
+}
@@ -92,4 +92,72 @@ describe('EthCustodian contract', () => {
             expect(await evmErc20Contract.decimals()).to.equal(newMetadataDecimals);
         });
     });
+
+    describe('Permit', () => {
+        const signPermit = async (owner, spender, value, nonce, deadline) => {
+            const domain = {
+                name: await evmErc20Contract.name(),
+                version: '1',
+                chainId: (await ethers.provider.getNetwork()).chainId,
+                verifyingContract: evmErc20Contract.address,
+            };
+            const types = {
+                Permit: [
+                    { name: 'owner', type: 'address' },
+                    { name: 'spender', type: 'address' },
+                    { name: 'value', type: 'uint256' },
+                    { name: 'nonce', type: 'uint256' },
+                    { name: 'deadline', type: 'uint256' },
+                ],
+            };
+            const message = {
+                owner: owner.address,
+                spender: spender.address,
+                value,
+                nonce,
+                deadline,
+            };
+            return ethers.utils.splitSignature(await owner._signTypedData(domain, types, message));
+        };
+
+        it('Should approve with a signature', async () => {
+            const deadline = ethers.constants.MaxUint256;
+            const { v, r, s } = await signPermit(adminAccount, user1, 100, 0, deadline);
+
+            await evmErc20Contract
+                .connect(user1)
+                .permit(adminAccount.address, user1.address, 100, deadline, v, r, s);
+
+            expect(await evmErc20Contract.allowance(adminAccount.address, user1.address)).to.equal(100);
+            expect(await evmErc20Contract.nonces(adminAccount.address)).to.equal(1);
+
+            // The nonce was used
+            await expect(
+                evmErc20Contract
+                    .connect(user1)
+                    .permit(adminAccount.address, user1.address, 100, deadline, v, r, s),
+            )
+                .to
+                .be
+                .reverted;
+        });
+
+        it('Should use the current name in the domain', async () => {
+            const deadline = ethers.constants.MaxUint256;
+            const { v, r, s } = await signPermit(adminAccount, user1, 100, 0, deadline);
+
+            await evmErc20Contract
+                .connect(adminAccount)
+                .setMetadata('NEW_CUSTOM_TOKEN', metadataSymbol, metadataDecimals);
+
+            await expect(
+                evmErc20Contract
+                    .connect(user1)
+                    .permit(adminAccount.address, user1.address, 100, deadline, v, r, s),
+            )
+                .to
+                .be
+                .reverted;
+        });
+    });
 });
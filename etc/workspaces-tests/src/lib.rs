@@ -0,0 +1,142 @@
+//! End-to-end tests of the engine in a NEAR sandbox.
+//!
+//! `aurora-engine-tests` runs the contract in-process, where a promise is executed right
+//! after the call which created it. Here the wasm built by `make mainnet-test-build` is
+//! deployed to a sandbox node with `workspaces`, so that promises become real receipts,
+//! executed in later blocks with their own gas and failures. Run with `make test-workspaces`.
+
+use aurora_engine::fungible_token::FungibleTokenMetadata;
+use aurora_engine::parameters::{InitCallArgs, NewCallArgs};
+use aurora_engine::proof::Proof;
+use aurora_engine::transaction::legacy::{LegacyEthSignedTransaction, TransactionLegacy};
+use aurora_engine_types::account_id::AccountId;
+use aurora_engine_types::types::Wei;
+use aurora_engine_types::{Address, U256};
+use borsh::BorshSerialize;
+use rlp::RlpStream;
+use secp256k1::{Message, PublicKey, SecretKey};
+use sha3::{Digest, Keccak256};
+use workspaces::network::Sandbox;
+use workspaces::result::CallExecutionDetails;
+use workspaces::{Contract, Worker};
+
+/// Engine built with the `integration-test` feature, by `make mainnet-test-build`.
+pub const WASM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../mainnet-test.wasm");
+
+pub const CHAIN_ID: u64 = 1313161556;
+
+/// Custodian of the deposit proofs of `engine-tests/src/tests/res/vectors`.
+pub const CUSTODIAN_ADDRESS: &str = "096DE9C2B8A5B8c22cEe3289B101f6960d68E51E";
+
+const VECTORS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../engine-tests/src/tests/res/vectors"
+);
+
+/// Deploys the engine and initializes it with its eth connector. The engine is its own
+/// prover: with the `integration-test` feature, `verify_log_entry` accepts every proof.
+pub async fn deploy_engine(worker: &Worker<Sandbox>) -> anyhow::Result<Contract> {
+    let wasm = std::fs::read(WASM_PATH)
+        .map_err(|e| anyhow::anyhow!("{}: {} (run `make mainnet-test-build`)", WASM_PATH, e))?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let engine_id = engine_account_id(&contract);
+
+    let mut chain_id = [0u8; 32];
+    U256::from(CHAIN_ID).to_big_endian(&mut chain_id);
+    let new_args = NewCallArgs {
+        chain_id,
+        owner_id: engine_id.clone(),
+        bridge_prover_id: engine_id.clone(),
+        upgrade_delay_blocks: 1,
+    };
+    check(
+        contract
+            .call(worker, "new")
+            .args(new_args.try_to_vec()?)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+
+    let connector_args = InitCallArgs {
+        prover_account: engine_id,
+        eth_custodian_address: CUSTODIAN_ADDRESS.to_string(),
+        metadata: FungibleTokenMetadata::default(),
+    };
+    check(
+        contract
+            .call(worker, "new_eth_connector")
+            .args(connector_args.try_to_vec()?)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+
+    Ok(contract)
+}
+
+pub fn engine_account_id(contract: &Contract) -> AccountId {
+    AccountId::new(contract.id().as_str()).unwrap()
+}
+
+/// Fails unless the transaction and all the receipts it led to succeeded.
+pub fn check(outcome: CallExecutionDetails) -> anyhow::Result<CallExecutionDetails> {
+    anyhow::ensure!(outcome.is_success(), "Transaction failed: {:#?}", outcome);
+    Ok(outcome)
+}
+
+/// Borsh-encoded `Proof` of the deposit vector with the given name.
+pub fn deposit_proof(name: &str) -> anyhow::Result<Vec<u8>> {
+    let file = std::fs::File::open(format!("{}/deposit_proofs.json", VECTORS_DIR))?;
+    let vectors: serde_json::Value = serde_json::from_reader(std::io::BufReader::new(file))?;
+    let proof = vectors["vectors"]
+        .as_array()
+        .and_then(|vectors| vectors.iter().find(|vector| vector["name"] == name))
+        .map(|vector| &vector["proof"])
+        .ok_or_else(|| anyhow::anyhow!("No deposit proof named {}", name))?;
+    let proof = Proof {
+        log_index: serde_json::from_value(proof["log_index"].clone())?,
+        log_entry_data: serde_json::from_value(proof["log_entry_data"].clone())?,
+        receipt_index: serde_json::from_value(proof["receipt_index"].clone())?,
+        receipt_data: serde_json::from_value(proof["receipt_data"].clone())?,
+        header_data: serde_json::from_value(proof["header_data"].clone())?,
+        proof: serde_json::from_value(proof["proof"].clone())?,
+    };
+    Ok(proof.try_to_vec()?)
+}
+
+pub fn keccak(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+pub fn address_from_secret_key(secret_key: &SecretKey) -> Address {
+    let public_key = PublicKey::from_secret_key(secret_key);
+    let hash = keccak(&public_key.serialize()[1..]);
+    Address::from_slice(&hash[12..])
+}
+
+pub fn transfer(to: Address, amount: Wei, nonce: U256) -> TransactionLegacy {
+    TransactionLegacy {
+        nonce,
+        gas_price: U256::zero(),
+        gas_limit: u64::MAX.into(),
+        to: Some(to),
+        value: amount,
+        data: Vec::new(),
+    }
+}
+
+/// RLP encoding of the transaction signed for `CHAIN_ID`, as expected by `submit`.
+pub fn sign_transaction(tx: TransactionLegacy, secret_key: &SecretKey) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+    tx.rlp_append_unsigned(&mut stream, Some(CHAIN_ID));
+    let message = Message::parse(&keccak(stream.as_raw()));
+    let (signature, recovery_id) = secp256k1::sign(&message, secret_key);
+    let signed = LegacyEthSignedTransaction {
+        transaction: tx,
+        v: recovery_id.serialize() as u64 + 2 * CHAIN_ID + 35,
+        r: U256::from_big_endian(&signature.r.b32()),
+        s: U256::from_big_endian(&signature.s.b32()),
+    };
+    rlp::encode(&signed).to_vec()
+}
@@ -0,0 +1,199 @@
+use aurora_engine::parameters::{SubmitResult, TransactionStatus, WithdrawResult};
+use aurora_engine_types::parameters::WithdrawCallArgs;
+use aurora_engine_types::types::{EthAddress, Wei};
+use aurora_engine_types::{Address, U256};
+use aurora_engine_workspaces_tests::{
+    address_from_secret_key, check, deploy_engine, deposit_proof, sign_transaction, transfer,
+    CUSTODIAN_ADDRESS,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use secp256k1::SecretKey;
+use serde_json::json;
+use workspaces::network::Sandbox;
+use workspaces::{Contract, Worker};
+
+const DEPOSITED_RECIPIENT: &str = "eth_recipient.root";
+const DEPOSITED_AMOUNT: u128 = 800400;
+const DEPOSITED_FEE: u128 = 400;
+
+async fn ft_balance_of(
+    worker: &Worker<Sandbox>,
+    engine: &Contract,
+    account_id: &str,
+) -> anyhow::Result<u128> {
+    let args = json!({ "account_id": account_id }).to_string().into_bytes();
+    let balance: String = engine.view(worker, "ft_balance_of", args).await?.json()?;
+    Ok(balance.parse()?)
+}
+
+async fn get_balance(
+    worker: &Worker<Sandbox>,
+    engine: &Contract,
+    address: Address,
+) -> anyhow::Result<Wei> {
+    let result = engine
+        .view(worker, "get_balance", address.as_bytes().to_vec())
+        .await?;
+    Ok(Wei::new(U256::from_big_endian(&result.result)))
+}
+
+#[tokio::test]
+async fn test_deposit_transfer_withdraw() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let engine = deploy_engine(&worker).await?;
+    let relayer = worker.dev_create_account().await?;
+    let user = worker.dev_create_account().await?;
+
+    // `deposit` calls the prover, then `finish_deposit` in a callback.
+    check(
+        relayer
+            .call(&worker, engine.id(), "deposit")
+            .args(deposit_proof("deposit_to_near")?)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+    assert_eq!(
+        ft_balance_of(&worker, &engine, DEPOSITED_RECIPIENT).await?,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE
+    );
+    assert_eq!(
+        ft_balance_of(&worker, &engine, relayer.id().as_str()).await?,
+        DEPOSITED_FEE
+    );
+
+    // A proof can only be used once, even once the first deposit is over.
+    let outcome = relayer
+        .call(&worker, engine.id(), "deposit")
+        .args(deposit_proof("deposit_to_near")?)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(!outcome.is_success());
+
+    let transfer_amount = 300;
+    check(
+        relayer
+            .call(&worker, engine.id(), "ft_transfer")
+            .args_json(json!({
+                "receiver_id": user.id(),
+                "amount": transfer_amount.to_string(),
+                "memo": null,
+            }))?
+            .deposit(1)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+    assert_eq!(
+        ft_balance_of(&worker, &engine, user.id().as_str()).await?,
+        transfer_amount
+    );
+
+    let withdraw_amount = 100;
+    let recipient_address = EthAddress::new([0x89; 20]);
+    let outcome = check(
+        user.call(&worker, engine.id(), "withdraw")
+            .args(
+                WithdrawCallArgs {
+                    recipient_address,
+                    amount: withdraw_amount,
+                }
+                .try_to_vec()?,
+            )
+            .deposit(1)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+    let result = WithdrawResult::try_from_slice(&outcome.raw_bytes()?)?;
+    assert_eq!(result.amount, withdraw_amount);
+    assert_eq!(result.recipient_id, recipient_address);
+    assert_eq!(
+        result.eth_custodian_address,
+        EthAddress::decode(CUSTODIAN_ADDRESS).unwrap()
+    );
+    assert_eq!(
+        ft_balance_of(&worker, &engine, user.id().as_str()).await?,
+        transfer_amount - withdraw_amount
+    );
+
+    // Withdrawing more than the balance fails without burning anything.
+    let outcome = user
+        .call(&worker, engine.id(), "withdraw")
+        .args(
+            WithdrawCallArgs {
+                recipient_address,
+                amount: transfer_amount,
+            }
+            .try_to_vec()?,
+        )
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(!outcome.is_success());
+    assert_eq!(
+        ft_balance_of(&worker, &engine, user.id().as_str()).await?,
+        transfer_amount - withdraw_amount
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submit_transfer() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let engine = deploy_engine(&worker).await?;
+    let relayer = worker.dev_create_account().await?;
+
+    let secret_key = SecretKey::parse(&[7; 32]).unwrap();
+    let sender = address_from_secret_key(&secret_key);
+    let receiver = Address::from([1; 20]);
+    let initial_balance = 1_000_000u64;
+
+    // `mint_account` mints the matching nETH through the prover and `finish_deposit`.
+    check(
+        engine
+            .call(&worker, "mint_account")
+            .args((sender.0, 0u64, initial_balance).try_to_vec()?)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+    assert_eq!(
+        get_balance(&worker, &engine, sender).await?,
+        Wei::new_u64(initial_balance)
+    );
+
+    let amount = Wei::new_u64(123);
+    let tx = sign_transaction(transfer(receiver, amount, U256::zero()), &secret_key);
+    let outcome = check(
+        relayer
+            .call(&worker, engine.id(), "submit")
+            .args(tx)
+            .max_gas()
+            .transact()
+            .await?,
+    )?;
+    let result = SubmitResult::try_from_slice(&outcome.raw_bytes()?)?;
+    assert!(matches!(result.status, TransactionStatus::Succeed(_)));
+
+    assert_eq!(get_balance(&worker, &engine, receiver).await?, amount);
+    assert_eq!(
+        get_balance(&worker, &engine, sender).await?,
+        Wei::new_u64(initial_balance - 123)
+    );
+
+    // Replaying the transaction fails on its nonce.
+    let tx = sign_transaction(transfer(receiver, amount, U256::zero()), &secret_key);
+    let outcome = relayer
+        .call(&worker, engine.id(), "submit")
+        .args(tx)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(!outcome.is_success());
+
+    Ok(())
+}